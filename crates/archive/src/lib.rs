@@ -5,6 +5,7 @@ mod tarball;
 mod zip;
 
 use failure::Fail;
+use lazy_static::lazy_static;
 
 #[derive(Fail, Debug)]
 #[fail(display = "HTTP failure ({})", code)]
@@ -12,12 +13,26 @@ pub struct HttpError {
     pub code: ::reqwest::StatusCode,
 }
 
-pub use crate::tarball::Tarball;
+pub use crate::tarball::{decompress_gzip, decompress_zstd, recompress_zstd, Tarball};
 pub use crate::zip::Zip;
 
 use std::fs::File;
 use std::path::Path;
 
+lazy_static! {
+    static ref CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+/// Returns the HTTP client shared by every request Volta makes.
+///
+/// Sharing a single client means sharing its connection pool, so the requests made while
+/// fetching a tool (the index, the archive size and the archive itself) and while fetching
+/// several tools in one command reuse their connections to the same servers, instead of each
+/// one paying for a new TCP connection and TLS handshake.
+pub fn http_client() -> &'static reqwest::Client {
+    &CLIENT
+}
+
 /// Metadata describing whether an archive comes from a local or remote origin.
 #[derive(Copy, Clone)]
 pub enum Origin {
@@ -57,6 +72,16 @@ cfg_if::cfg_if! {
         pub fn fetch_native(url: &str, cache_file: &Path) -> Result<Box<dyn Archive>, failure::Error> {
             Tarball::fetch(url, cache_file)
         }
+
+        /// Fetch a remote archive in the native OS-preferred format from the specified
+        /// URL, without keeping a copy of the archive once it has been unpacked.
+        ///
+        /// On Unixes, the tarball is unpacked directly from the download and the scratch
+        /// file is not used. On Windows, zip files can only be read once they have been
+        /// downloaded completely, so the archive is downloaded to the scratch file first.
+        pub fn stream_native(url: &str, _scratch_file: &Path) -> Result<Box<dyn Archive>, failure::Error> {
+            Tarball::stream(url)
+        }
     } else if #[cfg(windows)] {
         /// Load an archive in the native OS-preferred format from the specified file.
         ///
@@ -74,6 +99,16 @@ cfg_if::cfg_if! {
         pub fn fetch_native(url: &str, cache_file: &Path) -> Result<Box<dyn Archive>, failure::Error> {
             Zip::fetch(url, cache_file)
         }
+
+        /// Fetch a remote archive in the native OS-preferred format from the specified
+        /// URL, without keeping a copy of the archive once it has been unpacked.
+        ///
+        /// On Unixes, the tarball is unpacked directly from the download and the scratch
+        /// file is not used. On Windows, zip files can only be read once they have been
+        /// downloaded completely, so the archive is downloaded to the scratch file first.
+        pub fn stream_native(url: &str, scratch_file: &Path) -> Result<Box<dyn Archive>, failure::Error> {
+            Zip::fetch(url, scratch_file)
+        }
     } else {
         compile_error!("Unsupported OS (expected 'unix' or 'windows').");
     }
@@ -6,14 +6,12 @@ use std::io::copy;
 use std::path::Path;
 
 use progress_read::ProgressRead;
-use reqwest;
 use verbatim::PathExt;
 use zip_rs::ZipArchive;
 
 use failure;
 
-use super::Archive;
-use super::Origin;
+use super::{http_client, Archive, Origin};
 
 pub struct Zip {
     compressed_size: u64,
@@ -36,7 +34,7 @@ impl Zip {
     /// Initiate fetching of a Node zip archive from the given URL, returning
     /// a `Remote` data source.
     pub fn fetch(url: &str, cache_file: &Path) -> Result<Box<dyn Archive>, failure::Error> {
-        let mut response = reqwest::get(url)?;
+        let mut response = http_client().get(url).send()?;
 
         if !response.status().is_success() {
             return Err(super::HttpError {
@@ -0,0 +1,355 @@
+//! Provides the security audit behind `volta audit-toolchain`, which checks the pinned and
+//! installed Node versions against the security metadata published by the Node.js project.
+//!
+//! A version is reported when its release line has reached its end of life, according to the
+//! release schedule, or when it is affected by a vulnerability from the advisories of the Node.js
+//! security working group. For the latter, the newest release of the same line that isn't
+//! affected is suggested, from the Node index.
+
+use std::collections::BTreeMap;
+
+use crate::doctor::Status;
+use crate::error::ErrorDetails;
+use crate::session::Session;
+use crate::settings::check_online;
+use crate::style::{progress_spinner, tool_version};
+use crate::tool::{node_index_versions, node_release_line, node_release_schedule, NodeSchedule};
+use crate::version::parse_requirements;
+use archive::http_client;
+use cfg_if::cfg_if;
+use chrono::{Local, NaiveDate};
+use log::warn;
+use semver::{Version, VersionReq};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use volta_fail::{Fallible, ResultExt};
+
+cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        fn vulnerabilities_url() -> String {
+            format!("{}/node-vulnerabilities.json", mockito::SERVER_URL)
+        }
+    } else {
+        /// Returns the URL of the advisories of the Node.js security working group
+        fn vulnerabilities_url() -> String {
+            "https://raw.githubusercontent.com/nodejs/security-wg/main/vuln/core/index.json"
+                .to_string()
+        }
+    }
+}
+
+/// An advisory, with the ranges of the versions it affects and the versions that fix it
+#[derive(Deserialize)]
+struct RawVulnerability {
+    #[serde(default)]
+    cve: Vec<String>,
+    vulnerable: String,
+    #[serde(default)]
+    patched: Option<String>,
+}
+
+struct Vulnerability {
+    /// The CVE identifiers of the advisory, or its own identifier if it has none
+    ids: Vec<String>,
+    vulnerable: VersionReq,
+    patched: Option<VersionReq>,
+}
+
+impl Vulnerability {
+    fn affects(&self, version: &Version) -> bool {
+        self.vulnerable.matches(version)
+            && !self
+                .patched
+                .as_ref()
+                .map_or(false, |patched| patched.matches(version))
+    }
+}
+
+/// The result of auditing one Node version
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Item {
+    pub name: String,
+    /// Where the version is used: `installed`, `default` or `project`
+    pub sources: Vec<&'static str>,
+    pub status: Status,
+    pub end_of_life: Option<String>,
+    pub cves: Vec<String>,
+    pub fixed_in: Option<String>,
+    pub problems: Vec<String>,
+}
+
+impl Item {
+    fn new(version: &Version, sources: Vec<&'static str>) -> Self {
+        Item {
+            name: tool_version("node", version),
+            sources,
+            status: Status::Pass,
+            end_of_life: None,
+            cves: Vec::new(),
+            fixed_in: None,
+            problems: Vec::new(),
+        }
+    }
+}
+
+/// Audits the Node versions in the inventory, along with the default and project versions
+pub fn audit(session: &Session) -> Fallible<Vec<Item>> {
+    let mut versions: BTreeMap<Version, Vec<&'static str>> = BTreeMap::new();
+    for version in &session.inventory()?.node.versions {
+        versions
+            .entry(version.clone())
+            .or_default()
+            .push("installed");
+    }
+    if let Some(platform) = session.default_platform()? {
+        versions
+            .entry(platform.node_runtime.clone())
+            .or_default()
+            .push("default");
+    }
+    if let Some(platform) = session.project_platform()? {
+        versions
+            .entry(platform.node_runtime.clone())
+            .or_default()
+            .push("project");
+    }
+    if versions.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let schedule = node_release_schedule()?;
+    let raw_vulnerabilities: BTreeMap<String, RawVulnerability> =
+        fetch_json(&vulnerabilities_url())?;
+    let (vulnerabilities, warnings) = parse_vulnerabilities(raw_vulnerabilities);
+    for warning in warnings {
+        warn!("{}", warning);
+    }
+    let releases = node_index_versions(session)?;
+    let today = Local::today().naive_local();
+
+    Ok(versions
+        .into_iter()
+        .map(|(version, sources)| {
+            let mut item = Item::new(&version, sources);
+            audit_version(
+                &mut item,
+                &version,
+                &schedule,
+                &vulnerabilities,
+                &releases,
+                today,
+            );
+            item
+        })
+        .collect())
+}
+
+fn fetch_json<T: DeserializeOwned>(url: &str) -> Fallible<T> {
+    check_online(url)?;
+    let spinner = progress_spinner(&format!("Fetching Node security metadata: {}", url));
+    let contents = http_client()
+        .get(url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|mut response| response.json())
+        .with_context(|_| ErrorDetails::AuditFetchError {
+            from_url: url.to_string(),
+        })?;
+    spinner.finish_and_clear();
+    Ok(contents)
+}
+
+/// Parses the ranges of the advisories, returning them along with a warning for each range that
+/// can't be parsed
+///
+/// An advisory whose vulnerable range can't be parsed is skipped, since it can't be matched. One
+/// whose patched range can't be parsed is kept without it, so that it errs on reporting a version.
+fn parse_vulnerabilities(
+    raw: BTreeMap<String, RawVulnerability>,
+) -> (Vec<Vulnerability>, Vec<String>) {
+    let mut warnings = Vec::new();
+    let vulnerabilities = raw
+        .into_iter()
+        .filter_map(|(id, vulnerability)| {
+            let vulnerable = match parse_requirements(&vulnerability.vulnerable) {
+                Ok(vulnerable) => vulnerable,
+                Err(_) => {
+                    warnings.push(format!(
+                        "Skipping advisory {}, whose vulnerable range '{}' could not be parsed",
+                        id, vulnerability.vulnerable
+                    ));
+                    return None;
+                }
+            };
+            let patched = match vulnerability.patched {
+                Some(ref patched) if !patched.trim().is_empty() => {
+                    match parse_requirements(patched) {
+                        Ok(patched) => Some(patched),
+                        Err(_) => {
+                            warnings.push(format!(
+                                "Ignoring the patched range '{}' of advisory {}, which could not be parsed",
+                                patched, id
+                            ));
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+            let ids = if vulnerability.cve.is_empty() {
+                vec![format!("advisory {}", id)]
+            } else {
+                vulnerability.cve
+            };
+
+            Some(Vulnerability {
+                ids,
+                vulnerable,
+                patched,
+            })
+        })
+        .collect();
+    (vulnerabilities, warnings)
+}
+
+fn audit_version(
+    item: &mut Item,
+    version: &Version,
+    schedule: &NodeSchedule,
+    vulnerabilities: &[Vulnerability],
+    releases: &[Version],
+    today: NaiveDate,
+) {
+    let (line_key, line) = node_release_line(version);
+    if let Some(end) = schedule.end_of_life(version, today) {
+        item.status = Status::Warn;
+        item.end_of_life = Some(end.to_string());
+        item.problems.push(format!(
+            "the Node {} line reached its end of life on {}",
+            line, end
+        ));
+    }
+
+    let affecting: Vec<&Vulnerability> = vulnerabilities
+        .iter()
+        .filter(|vulnerability| vulnerability.affects(version))
+        .collect();
+    if affecting.is_empty() {
+        return;
+    }
+
+    let mut cves: Vec<String> = affecting
+        .iter()
+        .flat_map(|vulnerability| vulnerability.ids.iter().cloned())
+        .collect();
+    cves.sort();
+    cves.dedup();
+
+    let fixed_in = releases
+        .iter()
+        .filter(|release| node_release_line(release).0 == line_key && !release.is_prerelease())
+        .filter(|release| *release > version)
+        .filter(|release| {
+            !affecting
+                .iter()
+                .any(|vulnerability| vulnerability.affects(release))
+        })
+        .max();
+
+    item.status = Status::Fail;
+    item.problems.push(match fixed_in {
+        Some(fixed_in) => format!("affected by {}, fixed in {}", cves.join(", "), fixed_in),
+        None => format!(
+            "affected by {}, with no fix in the Node {} line",
+            cves.join(", "),
+            line
+        ),
+    });
+    item.cves = cves;
+    item.fixed_in = fixed_in.map(ToString::to_string);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    fn audited(version_str: &str) -> Item {
+        let schedule = NodeSchedule::from_ends(vec![
+            ("v10", NaiveDate::from_ymd(2021, 4, 30)),
+            ("v14", NaiveDate::from_ymd(2023, 4, 30)),
+        ]);
+
+        let mut raw = BTreeMap::new();
+        raw.insert(
+            "100".to_string(),
+            RawVulnerability {
+                cve: vec!["CVE-2021-22883".to_string()],
+                vulnerable: "<14.15.5".to_string(),
+                patched: Some("^10.24.0".to_string()),
+            },
+        );
+        let (vulnerabilities, _) = parse_vulnerabilities(raw);
+        let releases = vec![version("14.15.6"), version("14.15.5"), version("14.15.4")];
+        let today = NaiveDate::from_ymd(2022, 1, 1);
+
+        let version = version(version_str);
+        let mut item = Item::new(&version, vec!["installed"]);
+        audit_version(
+            &mut item,
+            &version,
+            &schedule,
+            &vulnerabilities,
+            &releases,
+            today,
+        );
+        item
+    }
+
+    #[test]
+    fn test_parse_vulnerabilities_warns_about_unparseable_ranges() {
+        let mut raw = BTreeMap::new();
+        raw.insert(
+            "1".to_string(),
+            RawVulnerability {
+                cve: Vec::new(),
+                vulnerable: "not a range".to_string(),
+                patched: None,
+            },
+        );
+        raw.insert(
+            "2".to_string(),
+            RawVulnerability {
+                cve: Vec::new(),
+                vulnerable: "<14.15.5".to_string(),
+                patched: Some("not a range either".to_string()),
+            },
+        );
+        let (vulnerabilities, warnings) = parse_vulnerabilities(raw);
+
+        assert_eq!(vulnerabilities.len(), 1);
+        assert!(vulnerabilities[0].patched.is_none());
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("advisory 1"));
+        assert!(warnings[1].contains("advisory 2"));
+    }
+
+    #[test]
+    fn test_audit_version() {
+        let intact = audited("14.15.5");
+        assert_eq!(intact.status, Status::Pass);
+
+        let vulnerable = audited("14.15.4");
+        assert_eq!(vulnerable.status, Status::Fail);
+        assert_eq!(vulnerable.cves, vec!["CVE-2021-22883"]);
+        assert_eq!(vulnerable.fixed_in, Some("14.15.6".to_string()));
+
+        let end_of_life = audited("10.24.0");
+        assert_eq!(end_of_life.status, Status::Warn);
+        assert_eq!(end_of_life.end_of_life, Some("2021-04-30".to_string()));
+    }
+}
@@ -0,0 +1,170 @@
+//! Provides the checks of a project's pinned toolchain behind `volta check`.
+
+use std::path::Path;
+
+use crate::doctor::{Check, Report};
+use crate::error::ErrorDetails;
+use crate::project::{satisfies_engines, Project};
+use crate::session::Session;
+use crate::tool::{node_end_of_life_message, node_release_schedule, Spec};
+use crate::version::VersionSpec;
+use log::debug;
+use semver::{Version, VersionReq};
+use volta_fail::{throw, Fallible};
+
+/// The tools that Volta provides shims for. A local executable with the same name takes
+/// precedence over the shim in package scripts, since those run with `node_modules/.bin` first
+/// on the PATH.
+const SHIMMED_TOOLS: [&str; 7] = ["node", "npm", "npx", "yarn", "yarnpkg", "pnpm", "pnpx"];
+
+/// Checks that the toolchain of the current project can be used, collecting the results into a
+/// report.
+pub fn check_project(session: &mut Session) -> Fallible<Report> {
+    let (pins, engines, local_bins) = match session.project()? {
+        Some(project) => (
+            pinned_tools(project),
+            check_engines(project),
+            check_local_bins(&project.local_bin_dir()),
+        ),
+        None => throw!(ErrorDetails::NotInPackage),
+    };
+    let pins = check_pins(&pins, session);
+
+    Ok(Report::from_checks(vec![pins, engines, local_bins]))
+}
+
+/// Returns the versions of Node and the package managers that the project uses
+fn pinned_tools(project: &Project) -> Vec<(&'static str, Version)> {
+    let platform = match project.platform() {
+        Some(platform) => platform,
+        None => return Vec::new(),
+    };
+
+    let mut pins = vec![("node", platform.node_runtime.clone())];
+    pins.extend(platform.npm.clone().map(|version| ("npm", version)));
+    pins.extend(platform.yarn.clone().map(|version| ("yarn", version)));
+    pins.extend(platform.pnpm.clone().map(|version| ("pnpm", version)));
+    pins
+}
+
+/// Verifies that every pinned version has been fetched or can be found upstream, and that the
+/// pinned Node line hasn't reached its end of life.
+fn check_pins(pins: &[(&'static str, Version)], session: &mut Session) -> Check {
+    let mut check = Check::new("pinned versions");
+    if pins.is_empty() {
+        check.warn("the project doesn't pin Node; run `volta pin node` to pin it");
+        return check;
+    }
+
+    for (tool, version) in pins {
+        if *tool == "node" {
+            check_end_of_life(version, &mut check);
+        }
+        if is_fetched(tool, version, session) {
+            continue;
+        }
+
+        // Exact versions are trusted when they are resolved, so an exact requirement is used to
+        // look the version up in the index
+        let matching = VersionSpec::Semver(VersionReq::exact(version));
+        let spec = match *tool {
+            "node" => Spec::Node(matching),
+            "npm" => Spec::Npm(matching),
+            "yarn" => Spec::Yarn(matching),
+            _ => Spec::Pnpm(matching),
+        };
+        if spec.resolve(session).is_err() {
+            check.fail(format!(
+                "{}@{} has not been fetched and could not be found upstream",
+                tool, version
+            ));
+        }
+    }
+
+    check
+}
+
+fn check_end_of_life(version: &Version, check: &mut Check) {
+    match node_release_schedule() {
+        Ok(schedule) => {
+            if let Some(end) = schedule.end_of_life_now(version) {
+                check.warn(node_end_of_life_message(version, end));
+            }
+        }
+        Err(error) => debug!("Could not check the Node release schedule: {}", error),
+    }
+}
+
+fn is_fetched(tool: &str, version: &Version, session: &Session) -> bool {
+    let inventory = match session.inventory() {
+        Ok(inventory) => inventory,
+        Err(_) => return false,
+    };
+
+    match tool {
+        "node" => inventory.node.versions.contains(version),
+        "npm" => inventory.npm.versions.contains(version),
+        "yarn" => inventory.yarn.versions.contains(version),
+        _ => inventory.pnpm.versions.contains(version),
+    }
+}
+
+/// Verifies that the Node used by the project satisfies its `engines.node` requirement.
+fn check_engines(project: &Project) -> Check {
+    let mut check = Check::new("engines");
+
+    if let (Some(platform), Some(engines)) = (project.platform(), project.engines()) {
+        match satisfies_engines(&platform.node_runtime, engines) {
+            Some(true) => {}
+            Some(false) => check.fail(format!(
+                "node@{} does not satisfy `engines.node` ({})",
+                platform.node_runtime, engines
+            )),
+            None => check.warn(format!(
+                "`engines.node` ({}) is not a valid version requirement",
+                engines
+            )),
+        }
+    }
+
+    check
+}
+
+/// Verifies that no local executable shadows the shims of the pinned tools.
+fn check_local_bins(bin_dir: &Path) -> Check {
+    let mut check = Check::new("local executables");
+
+    for tool in SHIMMED_TOOLS.iter() {
+        let executables = [bin_dir.join(tool), bin_dir.join(format!("{}.cmd", tool))];
+        if let Some(executable) = executables.iter().find(|path| path.exists()) {
+            check.fail(format!(
+                "{} takes precedence over the pinned {} in package scripts",
+                executable.display(),
+                tool
+            ));
+        }
+    }
+
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doctor::Status;
+    use std::fs::{create_dir_all, write};
+
+    #[test]
+    fn test_check_local_bins() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin_dir = dir.path().join("node_modules").join(".bin");
+        create_dir_all(&bin_dir).unwrap();
+        write(bin_dir.join("eslint"), "").unwrap();
+        assert_eq!(check_local_bins(&bin_dir).status, Status::Pass);
+
+        write(bin_dir.join("node"), "").unwrap();
+        let check = check_local_bins(&bin_dir);
+        assert_eq!(check.status, Status::Fail);
+        assert_eq!(check.messages.len(), 1);
+    }
+}
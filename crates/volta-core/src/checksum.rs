@@ -0,0 +1,218 @@
+//! Provides the checksums that upstream servers publish for tool archives: the `SHASUMS256.txt`
+//! file of each Node release, and the `dist.integrity` of each version in the npm registry.
+//!
+//! These are what archives from outside of a download, like the ones in the shared cache, are
+//! verified against when the project doesn't lock them.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::fs::read_file_error;
+use crate::settings::check_online;
+use crate::tool::registry_fetch_error;
+use archive::http_client;
+use cfg_if::cfg_if;
+use log::debug;
+use semver::Version;
+use serde::Deserialize;
+use sha2::{Digest, Sha256, Sha512};
+use volta_fail::{Fallible, ResultExt};
+
+cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        pub(crate) fn public_registry_root() -> String {
+            format!("{}/registry", mockito::SERVER_URL)
+        }
+    } else {
+        /// URL of the npm registry that package managers are downloaded from
+        pub(crate) fn public_registry_root() -> String {
+            "https://registry.npmjs.org".to_string()
+        }
+    }
+}
+
+/// A checksum published for an archive
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Checksum {
+    /// A SHA-256 checksum, as a hex string
+    Sha256(String),
+    /// A SHA-512 checksum, as the bytes of the hash
+    Sha512(Vec<u8>),
+}
+
+impl Checksum {
+    /// Reads the SHA-512 checksum from a Subresource Integrity string, like the `dist.integrity`
+    /// of the npm registry, which may list hashes for several algorithms
+    pub(crate) fn from_integrity(integrity: &str) -> Option<Self> {
+        integrity
+            .split_whitespace()
+            .filter_map(|hash| {
+                let mut parts = hash.splitn(2, '-');
+                match (parts.next(), parts.next()) {
+                    (Some("sha512"), Some(digest)) => base64::decode(digest).ok(),
+                    _ => None,
+                }
+            })
+            .next()
+            .map(Checksum::Sha512)
+    }
+
+    /// The checksum as a hex string, the way `sha256sum` and `sha512sum` print it
+    pub(crate) fn to_hex(&self) -> String {
+        match self {
+            Checksum::Sha256(hex) => hex.to_ascii_lowercase(),
+            Checksum::Sha512(bytes) => hex::encode(bytes),
+        }
+    }
+
+    /// Whether the contents of `file` have this checksum
+    pub(crate) fn matches(&self, file: &Path) -> Fallible<bool> {
+        let mut buffer = Vec::new();
+        File::open(file)
+            .and_then(|mut contents| contents.read_to_end(&mut buffer))
+            .with_context(read_file_error(&file))?;
+
+        let found = match self {
+            Checksum::Sha256(_) => {
+                let mut hasher = Sha256::new();
+                hasher.input(buffer);
+                hex::encode(&hasher.result())
+            }
+            Checksum::Sha512(_) => {
+                let mut hasher = Sha512::new();
+                hasher.input(buffer);
+                hex::encode(&hasher.result())
+            }
+        };
+        Ok(found == self.to_hex())
+    }
+}
+
+/// Fetches the checksum of a Node archive from the `SHASUMS256.txt` file in the same directory
+/// as the archive, returning `None` if the file doesn't list the archive
+pub(crate) fn fetch_node_checksum(archive_url: &str) -> Fallible<Option<Checksum>> {
+    let (base_url, file_name) = match archive_url.rfind('/') {
+        Some(index) => (&archive_url[..index], &archive_url[index + 1..]),
+        None => return Ok(None),
+    };
+    let url = format!("{}/SHASUMS256.txt", base_url);
+
+    check_online(&url)?;
+    debug!("Fetching the checksum of {} from {}", file_name, url);
+    let shasums = http_client()
+        .get(&url)
+        .send()
+        .and_then(|mut response| response.error_for_status()?.text())
+        .with_context(registry_fetch_error("Node", &url))?;
+
+    Ok(find_checksum(&shasums, file_name).map(Checksum::Sha256))
+}
+
+/// The part of a version document of the npm registry that holds its checksums
+#[derive(Deserialize)]
+struct RawVersionDocument {
+    dist: RawDist,
+}
+
+#[derive(Deserialize)]
+struct RawDist {
+    integrity: Option<String>,
+}
+
+/// Fetches the checksum that the public npm registry publishes for a version of a package,
+/// returning `None` if the registry doesn't publish a SHA-512 checksum for it
+pub(crate) fn fetch_registry_checksum(
+    tool: &str,
+    package: &str,
+    version: &Version,
+) -> Fallible<Option<Checksum>> {
+    let url = format!("{}/{}/{}", public_registry_root(), package, version);
+
+    check_online(&url)?;
+    debug!(
+        "Fetching the checksum of {}@{} from {}",
+        package, version, url
+    );
+    let document = http_client()
+        .get(&url)
+        .send()
+        .and_then(|mut response| response.error_for_status()?.json::<RawVersionDocument>())
+        .with_context(registry_fetch_error(tool, &url))?;
+
+    Ok(document
+        .dist
+        .integrity
+        .and_then(|integrity| Checksum::from_integrity(&integrity)))
+}
+
+/// Finds the checksum of a file in the contents of a `SHASUMS256.txt` file, which lists one
+/// `<checksum>  <file name>` pair per line
+pub(crate) fn find_checksum(shasums: &str, file_name: &str) -> Option<String> {
+    shasums.lines().find_map(|line| {
+        let mut words = line.split_whitespace();
+        let checksum = words.next()?;
+        if words.next()? == file_name {
+            Some(checksum.to_string())
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::write;
+
+    #[test]
+    fn test_find_checksum() {
+        let shasums = "\
+1b6a4f8e1b41eaa3e413c2e954e7feb8f96a3d0efc037c0dc9e90d46d058ab2c  node-v18.19.0-darwin-x64.tar.gz
+61632bb78ee828d6e8f42adc0bc2238a6b8ee8ccf30385115d4d5f7d259c8e89  node-v18.19.0-linux-x64.tar.gz
+";
+        assert_eq!(
+            find_checksum(shasums, "node-v18.19.0-linux-x64.tar.gz"),
+            Some("61632bb78ee828d6e8f42adc0bc2238a6b8ee8ccf30385115d4d5f7d259c8e89".to_string())
+        );
+        assert_eq!(
+            find_checksum(shasums, "node-v18.19.0-linux-arm64.tar.gz"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_checksum_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("archive.tgz");
+        write(&file, "archive").unwrap();
+
+        let sha256 = Checksum::Sha256(
+            "EF0D3A5A545E9D6D3F0E2B8B5BA5F6A15C7D0B5E3E35BC4D8D1F0F8C0A5A0E0E".to_string(),
+        );
+        assert!(!sha256.matches(&file).unwrap());
+
+        let mut hasher = Sha256::new();
+        hasher.input(b"archive");
+        let sha256 = Checksum::Sha256(hex::encode(&hasher.result()).to_ascii_uppercase());
+        assert!(sha256.matches(&file).unwrap());
+
+        let mut hasher = Sha512::new();
+        hasher.input(b"archive");
+        let integrity = format!("sha512-{}", base64::encode(&hasher.result()));
+        let sha512 = Checksum::from_integrity(&integrity).unwrap();
+        assert!(sha512.matches(&file).unwrap());
+        write(&file, "tampered").unwrap();
+        assert!(!sha512.matches(&file).unwrap());
+    }
+
+    #[test]
+    fn test_from_integrity() {
+        assert_eq!(
+            Checksum::from_integrity("sha1-AAAA sha512-AAEC"),
+            Some(Checksum::Sha512(vec![0, 1, 2]))
+        );
+        assert_eq!(Checksum::from_integrity("sha1-AAAA"), None);
+        assert_eq!(Checksum::from_integrity("sha512-%%%%"), None);
+    }
+}
@@ -0,0 +1,185 @@
+//! Provides the downloads that make up a pinned toolchain on Linux, along with their published
+//! checksums, so that container images can install exactly the toolchain a project pins.
+
+use crate::checksum::{find_checksum, public_registry_root, Checksum};
+use crate::error::ErrorDetails;
+use crate::platform::PlatformSpec;
+use crate::settings::{check_online, settings};
+use crate::style::progress_spinner;
+use crate::tool::{public_node_server_root, registry_fetch_error};
+use archive::http_client;
+use log::debug;
+use semver::Version;
+use serde::Deserialize;
+use volta_fail::{Fallible, ResultExt};
+
+/// The architectures that Linux containers are commonly built for, as pairs of the Docker
+/// `TARGETARCH` name and the name used in Node distros
+const ARCHITECTURES: &[(&str, &str)] = &[("amd64", "x64"), ("arm64", "arm64")];
+
+/// The downloads of a pinned toolchain on Linux
+pub struct ContainerToolchain {
+    pub node: NodeDownload,
+    pub package_managers: Vec<PackageDownload>,
+}
+
+/// The Node distros of a version, one per architecture
+pub struct NodeDownload {
+    pub version: Version,
+    /// The URL of the directory holding the distros of this version
+    pub base_url: String,
+    pub distros: Vec<NodeDistro>,
+}
+
+/// The Node distro for one architecture
+pub struct NodeDistro {
+    /// The Docker name of the architecture, e.g. `amd64`
+    pub target_arch: &'static str,
+    pub file_name: String,
+    /// The SHA-256 checksum published in `SHASUMS256.txt`
+    pub sha256: String,
+}
+
+/// A package manager, installed from its package tarball in the npm registry
+pub struct PackageDownload {
+    pub tool: &'static str,
+    pub version: Version,
+    pub tarball: String,
+    /// The SHA-512 checksum published in the registry as the `dist.integrity`, as a hex string
+    pub sha512: String,
+}
+
+#[derive(Deserialize)]
+struct RawPackageDocument {
+    dist: RawDist,
+}
+
+#[derive(Deserialize)]
+struct RawDist {
+    tarball: String,
+    integrity: Option<String>,
+}
+
+impl ContainerToolchain {
+    /// Looks up the downloads and checksums of the tools in a pinned platform
+    ///
+    /// A pinned npm is installed over the npm bundled with Node, so it is only included when the
+    /// platform pins one.
+    pub fn resolve(platform: &PlatformSpec) -> Fallible<Self> {
+        let node = NodeDownload::resolve(&platform.node_runtime)?;
+
+        let mut package_managers = Vec::new();
+        if let Some(npm) = &platform.npm {
+            package_managers.push(PackageDownload::resolve("npm", "npm", npm)?);
+        }
+        if let Some(yarn) = &platform.yarn {
+            // Yarn 2 and later are published as a separate package
+            let package = if yarn.major >= 2 {
+                "@yarnpkg/cli-dist"
+            } else {
+                "yarn"
+            };
+            package_managers.push(PackageDownload::resolve("yarn", package, yarn)?);
+        }
+        if let Some(pnpm) = &platform.pnpm {
+            package_managers.push(PackageDownload::resolve("pnpm", "pnpm", pnpm)?);
+        }
+
+        Ok(ContainerToolchain {
+            node,
+            package_managers,
+        })
+    }
+}
+
+impl NodeDownload {
+    fn resolve(version: &Version) -> Fallible<Self> {
+        let root = match settings()?.node_mirror {
+            Some(ref mirror) => mirror.clone(),
+            None => public_node_server_root(),
+        };
+        let base_url = format!("{}/v{}", root, version);
+        let url = format!("{}/SHASUMS256.txt", base_url);
+
+        check_online(&url)?;
+        let spinner = progress_spinner(&format!("Fetching checksums: {}", url));
+        let shasums = http_client()
+            .get(&url)
+            .send()
+            .and_then(|mut response| response.error_for_status()?.text())
+            .with_context(registry_fetch_error("Node", &url))?;
+        spinner.finish_and_clear();
+
+        let distros: Vec<NodeDistro> = ARCHITECTURES
+            .iter()
+            .filter_map(|&(target_arch, node_arch)| {
+                let file_name = format!("node-v{}-linux-{}.tar.gz", version, node_arch);
+                let sha256 = find_checksum(&shasums, &file_name)?;
+                Some(NodeDistro {
+                    target_arch,
+                    file_name,
+                    sha256,
+                })
+            })
+            .collect();
+
+        // Releases too old to have arm64 builds are still usable on amd64
+        if distros.is_empty() {
+            return Err(ErrorDetails::ChecksumNotFound {
+                file: format!("node-v{}-linux-x64.tar.gz", version),
+                from_url: url,
+            }
+            .into());
+        }
+
+        debug!(
+            "Found checksums of node@{} for {} architectures",
+            version,
+            distros.len()
+        );
+        Ok(NodeDownload {
+            version: version.clone(),
+            base_url,
+            distros,
+        })
+    }
+}
+
+impl PackageDownload {
+    fn resolve(tool: &'static str, package: &str, version: &Version) -> Fallible<Self> {
+        let url = format!("{}/{}/{}", public_registry_root(), package, version);
+
+        check_online(&url)?;
+        let spinner = progress_spinner(&format!("Fetching public registry: {}", url));
+        let document = http_client()
+            .get(&url)
+            .send()
+            .and_then(|mut response| response.error_for_status()?.json::<RawPackageDocument>())
+            .with_context(registry_fetch_error(tool, &url))?;
+        spinner.finish_and_clear();
+
+        // The SHA-1 `shasum` is published for every version, but isn't enough to trust a download
+        let sha512 = match document
+            .dist
+            .integrity
+            .as_ref()
+            .and_then(|integrity| Checksum::from_integrity(integrity))
+        {
+            Some(checksum) => checksum.to_hex(),
+            None => {
+                return Err(ErrorDetails::ChecksumNotFound {
+                    file: document.dist.tarball,
+                    from_url: url,
+                }
+                .into())
+            }
+        };
+
+        Ok(PackageDownload {
+            tool,
+            version: version.clone(),
+            tarball: document.dist.tarball,
+            sha512,
+        })
+    }
+}
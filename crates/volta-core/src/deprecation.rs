@@ -0,0 +1,83 @@
+//! Provides the deprecation warnings, which tell users about legacy formats and superseded
+//! flags that still work but will be removed in a future version.
+//!
+//! Unlike errors, deprecation warnings never change the outcome of a command. They are only
+//! shown once per message, and can be turned off with the `deprecationWarnings` setting or the
+//! `--no-warnings` flag, for scripts that can't be updated right away.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::settings::settings;
+use lazy_static::lazy_static;
+use log::{debug, warn};
+
+static SUPPRESSED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    /// The warnings shown so far, so that a legacy format read several times is reported once
+    static ref SHOWN: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// Shows a deprecation warning built from a format string, like `warn!`
+///
+/// ```
+/// use volta_core::warn_deprecated;
+///
+/// warn_deprecated!("the `{}` flag is deprecated; use `{}` instead", "--silent", "--quiet");
+/// ```
+#[macro_export]
+macro_rules! warn_deprecated {
+    ($($arg:tt)+) => {
+        $crate::deprecation::warn(format_args!($($arg)+))
+    };
+}
+
+/// Turns off the deprecation warnings for the rest of the process, as `--no-warnings` does
+pub fn suppress() {
+    SUPPRESSED.store(true, Ordering::SeqCst);
+}
+
+/// Shows a deprecation warning, unless it was already shown or the warnings are turned off
+///
+/// Prefer the `warn_deprecated!` macro, which builds the message from a format string.
+pub fn warn(message: fmt::Arguments) {
+    let message = message.to_string();
+    if !first_time(&message) {
+        return;
+    }
+
+    if enabled() {
+        warn!("{}", message);
+    } else {
+        debug!("Deprecation warning turned off: {}", message);
+    }
+}
+
+fn enabled() -> bool {
+    if SUPPRESSED.load(Ordering::SeqCst) {
+        return false;
+    }
+    // Settings that can't be read are reported by the command itself, so the warning is shown
+    settings().map_or(true, |settings| settings.deprecation_warnings)
+}
+
+/// Records the message as shown, returning whether it wasn't shown before
+fn first_time(message: &str) -> bool {
+    let mut shown = SHOWN.lock().unwrap_or_else(|error| error.into_inner());
+    shown.insert(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_time() {
+        assert!(first_time("the `legacy` key is deprecated"));
+        assert!(!first_time("the `legacy` key is deprecated"));
+        assert!(first_time("the `older` key is deprecated"));
+    }
+}
@@ -0,0 +1,609 @@
+//! Provides the diagnostic checks behind `volta doctor`.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::fs::{create_staging_file, read_dir_eager};
+use crate::layout::{layered_path, volta_home, volta_install};
+use crate::platform::wsl;
+use crate::session::Session;
+use crate::settings::{settings, ShimStrategy};
+use crate::shim;
+use crate::tool::{load_default_npm_version, node_index_url};
+use cfg_if::cfg_if;
+use dirs::home_dir;
+use semver::Version;
+use serde::Serialize;
+
+/// How long the network check waits for the Node index, so that `volta doctor` doesn't hang
+/// when the machine is offline
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(5);
+
+cfg_if! {
+    if #[cfg(windows)] {
+        const NODE_EXECUTABLE: &str = "node.exe";
+        /// The Node executable of the other side of WSL
+        const CROSS_OS_NODE_EXECUTABLE: &str = "node";
+    } else {
+        const NODE_EXECUTABLE: &str = "node";
+        /// The Node executable of the other side of WSL
+        const CROSS_OS_NODE_EXECUTABLE: &str = "node.exe";
+    }
+}
+
+/// The outcome of a single diagnostic check, ordered from best to worst.
+///
+/// A skipped check comes first, so that it never makes a report worse than its other checks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Skip,
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Status::Skip => "skip",
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        };
+        f.write_str(s)
+    }
+}
+
+/// The result of one diagnostic check, along with any messages explaining it.
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub messages: Vec<String>,
+}
+
+impl Check {
+    pub(crate) fn new(name: &'static str) -> Self {
+        Check {
+            name,
+            status: Status::Pass,
+            messages: Vec::new(),
+        }
+    }
+
+    /// Records a message explaining the result, without changing the status
+    pub(crate) fn note(&mut self, message: impl Into<String>) {
+        self.messages.push(message.into());
+    }
+
+    /// Records why the check could not run, when nothing else made it worse than a pass
+    pub(crate) fn skip(&mut self, message: impl Into<String>) {
+        if self.status == Status::Pass {
+            self.status = Status::Skip;
+        }
+        self.messages.push(message.into());
+    }
+
+    pub(crate) fn warn(&mut self, message: impl Into<String>) {
+        self.status = self.status.max(Status::Warn);
+        self.messages.push(message.into());
+    }
+
+    pub(crate) fn fail(&mut self, message: impl Into<String>) {
+        self.status = Status::Fail;
+        self.messages.push(message.into());
+    }
+}
+
+/// The full set of diagnostic results.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub status: Status,
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    /// Collects checks into a report, whose status is the worst status of any check, and a pass
+    /// if every check was skipped.
+    pub(crate) fn from_checks(checks: Vec<Check>) -> Self {
+        let status = checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(Status::Pass)
+            .max(Status::Pass);
+
+        Report { status, checks }
+    }
+}
+
+/// Runs every diagnostic check and collects the results into a report.
+///
+/// Individual checks never abort the run: an error inside a check is recorded as
+/// a failure of that check so the remaining checks still get a chance to report.
+pub fn diagnose(session: &Session) -> Report {
+    let checks = vec![
+        check_path(),
+        check_shims(),
+        check_shim_strategy(),
+        check_inventory(session),
+        check_permissions(),
+        check_conflicts(),
+        check_cross_os(),
+        check_network(session),
+    ];
+
+    Report::from_checks(checks)
+}
+
+/// Verifies that the shim directory is on the PATH ahead of any other Node installation.
+fn check_path() -> Check {
+    match volta_home() {
+        Ok(home) => path_check(&env::var_os("PATH").unwrap_or_default(), home.shim_dir()),
+        Err(error) => {
+            let mut check = Check::new("PATH ordering");
+            check.fail(error.to_string());
+            check
+        }
+    }
+}
+
+fn path_check(path: &OsStr, shim_dir: &Path) -> Check {
+    let mut check = Check::new("PATH ordering");
+    let mut shim_seen = false;
+    for dir in env::split_paths(path) {
+        if same_path(&dir, shim_dir) {
+            shim_seen = true;
+            break;
+        }
+
+        if dir.join(NODE_EXECUTABLE).is_file() {
+            check.fail(format!(
+                "{} contains Node and comes before {} on the PATH",
+                dir.display(),
+                shim_dir.display()
+            ));
+        }
+    }
+
+    if !shim_seen {
+        check.fail(format!("{} is not on the PATH", shim_dir.display()));
+    }
+
+    check
+}
+
+/// Verifies that every shim points at the installed Volta shim executable.
+fn check_shims() -> Check {
+    let paths = volta_home().and_then(|home| {
+        volta_install().map(|install| (home.shim_dir(), install.shim_executable()))
+    });
+
+    match paths {
+        // Copied shims can't be followed to the executable, so their contents are compared instead
+        Ok((shim_dir, shim_executable)) => shims_check(
+            shim_dir,
+            shim_executable,
+            shim::strategy().ok() == Some(ShimStrategy::Copy),
+        ),
+        Err(error) => {
+            let mut check = Check::new("shim integrity");
+            check.fail(error.to_string());
+            check
+        }
+    }
+}
+
+fn shims_check(shim_dir: &Path, shim_executable: &Path, copies: bool) -> Check {
+    let mut check = Check::new("shim integrity");
+    if !shim_executable.is_file() {
+        check.fail(format!(
+            "shim executable {} is missing",
+            shim_executable.display()
+        ));
+        return check;
+    }
+
+    let entries = match read_dir_eager(shim_dir) {
+        Ok(entries) => entries,
+        Err(error) => {
+            check.fail(format!("could not read {}: {}", shim_dir.display(), error));
+            return check;
+        }
+    };
+
+    let executable = fs::read(shim_executable).ok();
+
+    for (entry, metadata) in entries {
+        let shim = entry.path();
+        // The Git Bash helper scripts on Windows are plain files without an extension, and the
+        // `.cmd` and `.ps1` scripts call the shim next to them
+        if cfg!(windows)
+            && shim
+                .extension()
+                .map_or(true, |extension| extension != "exe")
+        {
+            continue;
+        }
+
+        if copies && !metadata.file_type().is_symlink() {
+            if !is_copy(&shim, metadata.len(), executable.as_ref()) {
+                check.warn(format!(
+                    "{} is not a copy of {}",
+                    shim.display(),
+                    shim_executable.display()
+                ));
+            }
+            continue;
+        }
+
+        match fs::canonicalize(&shim) {
+            Ok(target) => {
+                if !same_path(&target, shim_executable) {
+                    check.warn(format!(
+                        "{} points to {} instead of {}",
+                        shim.display(),
+                        target.display(),
+                        shim_executable.display()
+                    ));
+                }
+            }
+            Err(_) => check.fail(format!("{} is a broken link", shim.display())),
+        }
+    }
+
+    check
+}
+
+/// Whether a shim of `len` bytes has the same contents as the shim executable, comparing the
+/// sizes first so that most shims that aren't copies don't have to be read
+fn is_copy(shim: &Path, len: u64, executable: Option<&Vec<u8>>) -> bool {
+    match executable {
+        Some(executable) if executable.len() as u64 == len => {
+            fs::read(shim).map_or(false, |contents| contents == *executable)
+        }
+        _ => false,
+    }
+}
+
+/// Explains how shims are created, since on Windows that depends on whether symlinks are allowed.
+fn check_shim_strategy() -> Check {
+    let mut check = Check::new("shim strategy");
+    let configured = match settings() {
+        Ok(settings) => settings.shim_strategy,
+        Err(error) => {
+            check.fail(error.to_string());
+            return check;
+        }
+    };
+
+    if configured == ShimStrategy::Copy {
+        check.note("shims are copies of the shim executable, as set by `shimStrategy`");
+        return check;
+    }
+
+    cfg_if! {
+        if #[cfg(windows)] {
+            match shim::symlink_support() {
+                shim::SymlinkSupport::DeveloperMode => {
+                    check.note("shims are symlinks, since Developer Mode is enabled")
+                }
+                shim::SymlinkSupport::Privileged => check.warn(
+                    "shims are symlinks, since Volta is running elevated; shims created from a \
+                     prompt that isn't elevated will be copies of the shim executable",
+                ),
+                shim::SymlinkSupport::Unavailable => check.note(
+                    "shims are copies of the shim executable, since symlinks require Developer \
+                     Mode or an elevated prompt",
+                ),
+            }
+        } else {
+            check.note("shims are symlinks");
+        }
+    }
+
+    check
+}
+
+/// Verifies that every fetched tool in the inventory has a matching unpacked image.
+fn check_inventory(session: &Session) -> Check {
+    let mut check = Check::new("inventory consistency");
+    let inventory = match session.inventory() {
+        Ok(inventory) => inventory,
+        Err(error) => {
+            check.fail(error.to_string());
+            return check;
+        }
+    };
+
+    check_images(&mut check, "node", &inventory.node.versions, |version| {
+        let npm = load_default_npm_version(version).ok()?;
+        layered_path(|home| home.node_image_dir(&version.to_string(), &npm.to_string())).ok()
+    });
+    check_images(&mut check, "npm", &inventory.npm.versions, |version| {
+        layered_path(|home| home.npm_image_dir(&version.to_string())).ok()
+    });
+    check_images(&mut check, "yarn", &inventory.yarn.versions, |version| {
+        layered_path(|home| home.yarn_image_dir(&version.to_string())).ok()
+    });
+    check_images(&mut check, "pnpm", &inventory.pnpm.versions, |version| {
+        layered_path(|home| home.pnpm_image_dir(&version.to_string())).ok()
+    });
+
+    check
+}
+
+/// Warns about every version of a tool whose image, as found by `image_dir`, doesn't exist
+fn check_images<'a, V, F>(check: &mut Check, tool: &str, versions: V, image_dir: F)
+where
+    V: IntoIterator<Item = &'a Version>,
+    F: Fn(&Version) -> Option<PathBuf>,
+{
+    for version in versions {
+        let installed = image_dir(version).map_or(false, |dir| dir.is_dir());
+        if !installed {
+            check.warn(format!(
+                "{}@{} is in the inventory but has no image; run `volta fetch {}@{}`",
+                tool, version, tool, version
+            ));
+        }
+    }
+}
+
+/// Verifies that Volta can write into its own temporary directory.
+fn check_permissions() -> Check {
+    let mut check = Check::new("write permissions");
+    if let Err(error) = create_staging_file() {
+        check.fail(error.to_string());
+    }
+    check
+}
+
+/// Looks for other Node version managers that may override Volta's shims.
+fn check_conflicts() -> Check {
+    let mut check = Check::new("conflicting version managers");
+    let home = home_dir();
+
+    for &(name, var, dir) in &[("nvm", "NVM_DIR", ".nvm"), ("asdf", "ASDF_DIR", ".asdf")] {
+        let found = env::var_os(var)
+            .map(PathBuf::from)
+            .or_else(|| home.as_ref().map(|home| home.join(dir)))
+            .filter(|path| path.is_dir());
+
+        if let Some(path) = found {
+            check.warn(format!("found {} installed at {}", name, path.display()));
+        }
+    }
+
+    check
+}
+
+/// Verifies that tools can't run Node for the other side of WSL from the PATH.
+fn check_cross_os() -> Check {
+    let mut check = Check::new("WSL interop");
+    let foreign = env::var_os("PATH")
+        .map(|path| wsl::foreign_paths(&path))
+        .unwrap_or_default();
+    if foreign.is_empty() {
+        return check;
+    }
+
+    let passthrough = settings().map_or(false, |settings| settings.cross_os_passthrough);
+    if !passthrough {
+        check.note(format!(
+            "{} PATH directories with Node for the other operating system are left out for tools",
+            foreign.len()
+        ));
+        return check;
+    }
+
+    for dir in foreign
+        .iter()
+        .filter(|dir| dir.join(CROSS_OS_NODE_EXECUTABLE).is_file())
+    {
+        check.warn(format!(
+            "{} has Node for the other operating system, which `crossOsPassthrough` lets tools run",
+            dir.display()
+        ));
+    }
+
+    check
+}
+
+/// Verifies that the configured Node index can be reached, unless offline mode is enabled.
+fn check_network(session: &Session) -> Check {
+    let mut check = Check::new("network");
+    match settings() {
+        Ok(settings) if settings.offline => {
+            check.skip("offline mode is enabled, so the Node index is not requested");
+            return check;
+        }
+        Ok(_) => {}
+        Err(error) => {
+            check.fail(error.to_string());
+            return check;
+        }
+    }
+
+    let url = match node_index_url(session) {
+        Ok(url) => url,
+        Err(error) => {
+            check.fail(error.to_string());
+            return check;
+        }
+    };
+
+    let client = match reqwest::Client::builder().timeout(NETWORK_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(error) => {
+            check.fail(error.to_string());
+            return check;
+        }
+    };
+
+    match client.head(&url).send() {
+        Ok(ref response) if response.status().is_success() => {}
+        Ok(response) => check.fail(format!("{} responded with {}", url, response.status())),
+        Err(error) => check.fail(format!("could not reach {}: {}", url, error)),
+    }
+
+    check
+}
+
+fn same_path(left: &Path, right: &Path) -> bool {
+    match (fs::canonicalize(left), fs::canonicalize(right)) {
+        (Ok(left), Ok(right)) => left == right,
+        _ => left == right,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_skipped_checks_pass_the_report() {
+        let mut skipped = Check::new("network");
+        skipped.skip("offline mode is enabled, so the Node index is not requested");
+        assert_eq!(skipped.status, Status::Skip);
+        assert_eq!(Report::from_checks(vec![skipped]).status, Status::Pass);
+
+        let mut skipped = Check::new("network");
+        skipped.skip("offline");
+        let mut warning = Check::new("conflicting version managers");
+        warning.warn("found nvm");
+        assert_eq!(
+            Report::from_checks(vec![skipped, warning]).status,
+            Status::Warn
+        );
+    }
+
+    #[test]
+    fn test_path_check() {
+        let dir = tempdir().unwrap();
+        let shim_dir = dir.path().join("shims");
+        let other = dir.path().join("other");
+        create_dir_all(&shim_dir).unwrap();
+        create_dir_all(&other).unwrap();
+        write(other.join(NODE_EXECUTABLE), "").unwrap();
+
+        let shims_first = env::join_paths(vec![&shim_dir, &other]).unwrap();
+        assert_eq!(path_check(&shims_first, &shim_dir).status, Status::Pass);
+
+        let node_first = env::join_paths(vec![&other, &shim_dir]).unwrap();
+        let check = path_check(&node_first, &shim_dir);
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.messages[0].contains("comes before"));
+
+        let without_shims = env::join_paths(vec![&other]).unwrap();
+        let check = path_check(&without_shims, &shim_dir);
+        assert_eq!(check.status, Status::Fail);
+        assert!(check
+            .messages
+            .iter()
+            .any(|m| m.contains("is not on the PATH")));
+    }
+
+    #[test]
+    fn test_shims_check_missing_executable() {
+        let dir = tempdir().unwrap();
+        let check = shims_check(dir.path(), &dir.path().join("volta-shim"), false);
+
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.messages[0].contains("is missing"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_shims_check_links() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempdir().unwrap();
+        let shim_dir = dir.path().join("bin");
+        let shim_executable = dir.path().join("volta-shim");
+        let other = dir.path().join("other");
+        create_dir_all(&shim_dir).unwrap();
+        write(&shim_executable, "shim").unwrap();
+        write(&other, "other").unwrap();
+
+        symlink(&shim_executable, shim_dir.join("node")).unwrap();
+        assert_eq!(
+            shims_check(&shim_dir, &shim_executable, false).status,
+            Status::Pass
+        );
+
+        symlink(&other, shim_dir.join("npm")).unwrap();
+        let check = shims_check(&shim_dir, &shim_executable, false);
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.messages[0].contains("points to"));
+
+        symlink(dir.path().join("missing"), shim_dir.join("yarn")).unwrap();
+        let check = shims_check(&shim_dir, &shim_executable, false);
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.messages.iter().any(|m| m.contains("broken link")));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_shims_check_copies() {
+        let dir = tempdir().unwrap();
+        let shim_dir = dir.path().join("bin");
+        let shim_executable = dir.path().join("volta-shim");
+        create_dir_all(&shim_dir).unwrap();
+        write(&shim_executable, "shim").unwrap();
+
+        write(shim_dir.join("node"), "shim").unwrap();
+        assert_eq!(
+            shims_check(&shim_dir, &shim_executable, true).status,
+            Status::Pass
+        );
+
+        write(shim_dir.join("npm"), "an older shim").unwrap();
+        let check = shims_check(&shim_dir, &shim_executable, true);
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.messages[0].contains("is not a copy"));
+    }
+
+    #[test]
+    fn test_shims_check_copies_compares_contents() {
+        let dir = tempdir().unwrap();
+        let shim_dir = dir.path().join("bin");
+        let shim_executable = dir.path().join("volta-shim");
+        create_dir_all(&shim_dir).unwrap();
+        write(&shim_executable, "shim").unwrap();
+
+        // A file of the same size is still not a copy
+        write(shim_dir.join("node"), "mihs").unwrap();
+        let check = shims_check(&shim_dir, &shim_executable, true);
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.messages[0].contains("is not a copy"));
+    }
+
+    #[test]
+    fn test_check_images() {
+        let dir = tempdir().unwrap();
+        create_dir_all(dir.path().join("1.22.4")).unwrap();
+        let versions = vec![
+            Version::parse("1.22.4").unwrap(),
+            Version::parse("1.22.5").unwrap(),
+        ];
+
+        let mut check = Check::new("inventory consistency");
+        check_images(&mut check, "yarn", &versions, |version| {
+            Some(dir.path().join(version.to_string()))
+        });
+
+        assert_eq!(check.status, Status::Warn);
+        assert_eq!(
+            check.messages,
+            vec!["yarn@1.22.5 is in the inventory but has no image; run `volta fetch yarn@1.22.5`"]
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Provides the `VOLTA_*` environment variables that Volta reads, along with the standard
+//! `NO_COLOR`.
+//!
+//! Every setting and hook can be overridden from the environment, which takes precedence over
+//! both the user and the project configuration. All variables are declared here, so that the
+//! documentation printed by `volta env --list-variables` is generated from the same list that
+//! the rest of Volta reads from.
+
+use std::env;
+use std::ffi::OsString;
+use std::fmt::Write;
+
+pub const VOLTA_HOME: &str = "VOLTA_HOME";
+pub const VOLTA_INSTALL_DIR: &str = "VOLTA_INSTALL_DIR";
+pub const VOLTA_SYSTEM_HOME: &str = "VOLTA_SYSTEM_HOME";
+pub const VOLTA_LOG: &str = "VOLTA_LOG";
+pub const VOLTA_LOGLEVEL: &str = "VOLTA_LOGLEVEL";
+pub const VOLTA_LOG_FILE: &str = "VOLTA_LOG_FILE";
+pub const VOLTA_BYPASS: &str = "VOLTA_BYPASS";
+pub const VOLTA_UNSAFE_GLOBAL: &str = "VOLTA_UNSAFE_GLOBAL";
+pub const VOLTA_OFFLINE: &str = "VOLTA_OFFLINE";
+pub const VOLTA_NODE_MIRROR: &str = "VOLTA_NODE_MIRROR";
+pub const VOLTA_PROGRESS: &str = "VOLTA_PROGRESS";
+pub const VOLTA_BACKTRACE: &str = "VOLTA_BACKTRACE";
+pub const VOLTA_SHIM_STRATEGY: &str = "VOLTA_SHIM_STRATEGY";
+pub const VOLTA_NODE_VERSION_FILES: &str = "VOLTA_NODE_VERSION_FILES";
+pub const VOLTA_USE_ENGINES: &str = "VOLTA_USE_ENGINES";
+pub const VOLTA_ISOLATE_PATH: &str = "VOLTA_ISOLATE_PATH";
+pub const VOLTA_LOCAL_TOOLCHAIN: &str = "VOLTA_LOCAL_TOOLCHAIN";
+pub const VOLTA_LOCK_TIMEOUT: &str = "VOLTA_LOCK_TIMEOUT";
+pub const VOLTA_CACHE_ARCHIVES: &str = "VOLTA_CACHE_ARCHIVES";
+pub const VOLTA_NODE_INDEX_MAX_AGE: &str = "VOLTA_NODE_INDEX_MAX_AGE";
+pub const VOLTA_PACKAGE_MANAGER_PRECEDENCE: &str = "VOLTA_PACKAGE_MANAGER_PRECEDENCE";
+pub const VOLTA_AUTOINSTALL: &str = "VOLTA_AUTOINSTALL";
+pub const VOLTA_STRICT: &str = "VOLTA_STRICT";
+pub const VOLTA_PASSTHROUGH: &str = "VOLTA_PASSTHROUGH";
+pub const VOLTA_REMOVE_ENV: &str = "VOLTA_REMOVE_ENV";
+pub const VOLTA_SET_ENV: &str = "VOLTA_SET_ENV";
+pub const VOLTA_CROSS_OS_PASSTHROUGH: &str = "VOLTA_CROSS_OS_PASSTHROUGH";
+pub const VOLTA_NODE_BUILDS: &str = "VOLTA_NODE_BUILDS";
+pub const VOLTA_BUILD_FROM_SOURCE: &str = "VOLTA_BUILD_FROM_SOURCE";
+pub const VOLTA_READ_ONLY: &str = "VOLTA_READ_ONLY";
+pub const VOLTA_METRICS: &str = "VOLTA_METRICS";
+pub const VOLTA_METRICS_ENDPOINT: &str = "VOLTA_METRICS_ENDPOINT";
+pub const VOLTA_UNKNOWN_ERROR_EXIT_CODE: &str = "VOLTA_UNKNOWN_ERROR_EXIT_CODE";
+pub const VOLTA_SIGNAL_EXIT: &str = "VOLTA_SIGNAL_EXIT";
+pub const VOLTA_DEPRECATION_WARNINGS: &str = "VOLTA_DEPRECATION_WARNINGS";
+pub const VOLTA_CACHE_SHARED_DIR: &str = "VOLTA_CACHE_SHARED_DIR";
+pub const VOLTA_DEDUP_IMAGES: &str = "VOLTA_DEDUP_IMAGES";
+pub const VOLTA_ARCHIVE_COMPRESSION: &str = "VOLTA_ARCHIVE_COMPRESSION";
+pub const VOLTA_NODE_UPDATE_NOTICES: &str = "VOLTA_NODE_UPDATE_NOTICES";
+pub const VOLTA_INSTALL_SCRIPTS: &str = "VOLTA_INSTALL_SCRIPTS";
+pub const VOLTA_TRACE: &str = "VOLTA_TRACE";
+pub const VOLTA_TRACE_FILE: &str = "VOLTA_TRACE_FILE";
+pub const VOLTA_HOOK_EVENTS_PUBLISH: &str = "VOLTA_HOOK_EVENTS_PUBLISH";
+pub const NO_COLOR: &str = "NO_COLOR";
+
+/// An environment variable read by Volta
+pub struct EnvVar {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// All of the environment variables read by Volta, in the order they are documented
+pub const VARIABLES: &[EnvVar] = &[
+    EnvVar {
+        name: VOLTA_HOME,
+        description: "The directory Volta stores tools and configuration in",
+    },
+    EnvVar {
+        name: VOLTA_INSTALL_DIR,
+        description: "The directory the Volta executables are installed in",
+    },
+    EnvVar {
+        name: VOLTA_SYSTEM_HOME,
+        description: "A read-only directory of Node and Yarn versions shared by all users",
+    },
+    EnvVar {
+        name: VOLTA_LOG,
+        description: "The level of log messages to show: off, error, warn, info, debug or trace",
+    },
+    EnvVar {
+        name: VOLTA_LOGLEVEL,
+        description: "The same as VOLTA_LOG, which takes precedence",
+    },
+    EnvVar {
+        name: VOLTA_LOG_FILE,
+        description: "A file to also write timestamped debug messages to",
+    },
+    EnvVar {
+        name: VOLTA_BYPASS,
+        description: "When set, shims run the tool from the PATH without Volta",
+    },
+    EnvVar {
+        name: VOLTA_UNSAFE_GLOBAL,
+        description: "When set, global package installs go to npm or Yarn instead of Volta",
+    },
+    EnvVar {
+        name: VOLTA_OFFLINE,
+        description: "Overrides the `offline` setting",
+    },
+    EnvVar {
+        name: VOLTA_NODE_MIRROR,
+        description: "Overrides the `nodeMirror` setting",
+    },
+    EnvVar {
+        name: VOLTA_PROGRESS,
+        description: "Overrides the `progress` setting: bar or none",
+    },
+    EnvVar {
+        name: VOLTA_BACKTRACE,
+        description: "Overrides the `backtrace` setting",
+    },
+    EnvVar {
+        name: VOLTA_SHIM_STRATEGY,
+        description: "Overrides the `shimStrategy` setting: symlink or copy",
+    },
+    EnvVar {
+        name: VOLTA_NODE_VERSION_FILES,
+        description: "Overrides the `nodeVersionFiles` setting",
+    },
+    EnvVar {
+        name: VOLTA_USE_ENGINES,
+        description: "Overrides the `useEngines` setting",
+    },
+    EnvVar {
+        name: VOLTA_ISOLATE_PATH,
+        description: "Overrides the `isolatePath` setting",
+    },
+    EnvVar {
+        name: VOLTA_LOCAL_TOOLCHAIN,
+        description: "Overrides the `localToolchain` setting",
+    },
+    EnvVar {
+        name: VOLTA_LOCK_TIMEOUT,
+        description: "Overrides the `lockTimeout` setting, in seconds",
+    },
+    EnvVar {
+        name: VOLTA_CACHE_ARCHIVES,
+        description: "Overrides the `cacheArchives` setting",
+    },
+    EnvVar {
+        name: VOLTA_NODE_INDEX_MAX_AGE,
+        description: "Overrides the `nodeIndexMaxAge` setting, in seconds",
+    },
+    EnvVar {
+        name: VOLTA_PACKAGE_MANAGER_PRECEDENCE,
+        description: "Overrides the `packageManagerPrecedence` setting: volta or packageManager",
+    },
+    EnvVar {
+        name: VOLTA_AUTOINSTALL,
+        description: "Overrides the `autoinstall` setting",
+    },
+    EnvVar {
+        name: VOLTA_STRICT,
+        description: "Overrides the `strict` setting",
+    },
+    EnvVar {
+        name: VOLTA_PASSTHROUGH,
+        description: "Overrides the `passthrough` setting, for Yarn and pnpm",
+    },
+    EnvVar {
+        name: VOLTA_REMOVE_ENV,
+        description:
+            "Overrides the `removeEnv` setting with a comma-separated list of variable names",
+    },
+    EnvVar {
+        name: VOLTA_SET_ENV,
+        description:
+            "Overrides the `setEnv` setting with a comma-separated list of `NAME=value` pairs",
+    },
+    EnvVar {
+        name: VOLTA_CROSS_OS_PASSTHROUGH,
+        description: "Overrides the `crossOsPassthrough` setting",
+    },
+    EnvVar {
+        name: VOLTA_NODE_BUILDS,
+        description: "Overrides the `nodeBuilds` setting",
+    },
+    EnvVar {
+        name: VOLTA_BUILD_FROM_SOURCE,
+        description: "Overrides the `buildFromSource` setting",
+    },
+    EnvVar {
+        name: VOLTA_READ_ONLY,
+        description: "Overrides the `readOnly` setting",
+    },
+    EnvVar {
+        name: VOLTA_METRICS,
+        description: "Overrides the `metrics` setting",
+    },
+    EnvVar {
+        name: VOLTA_METRICS_ENDPOINT,
+        description: "Overrides the `metricsEndpoint` setting",
+    },
+    EnvVar {
+        name: VOLTA_UNKNOWN_ERROR_EXIT_CODE,
+        description: "Overrides the `unknownErrorExitCode` setting",
+    },
+    EnvVar {
+        name: VOLTA_SIGNAL_EXIT,
+        description: "Overrides the `signalExit` setting",
+    },
+    EnvVar {
+        name: VOLTA_DEPRECATION_WARNINGS,
+        description: "Overrides the `deprecationWarnings` setting",
+    },
+    EnvVar {
+        name: VOLTA_CACHE_SHARED_DIR,
+        description: "Overrides the `cacheSharedDir` setting",
+    },
+    EnvVar {
+        name: VOLTA_DEDUP_IMAGES,
+        description: "Overrides the `dedupImages` setting",
+    },
+    EnvVar {
+        name: VOLTA_ARCHIVE_COMPRESSION,
+        description: "Overrides the `archiveCompression` setting",
+    },
+    EnvVar {
+        name: VOLTA_NODE_UPDATE_NOTICES,
+        description: "Overrides the `nodeUpdateNotices` setting",
+    },
+    EnvVar {
+        name: VOLTA_INSTALL_SCRIPTS,
+        description: "Overrides the `installScripts` setting",
+    },
+    EnvVar {
+        name: VOLTA_TRACE,
+        description: "When enabled, shims print how long each phase of running a tool took",
+    },
+    EnvVar {
+        name: VOLTA_TRACE_FILE,
+        description: "A file to append VOLTA_TRACE timings to, instead of printing them",
+    },
+    EnvVar {
+        name: "VOLTA_HOOK_<TOOL>_<HOOK>",
+        description: "Overrides a tool hook with its JSON configuration, for example \
+                      VOLTA_HOOK_NODE_DISTRO='{\"prefix\": \"https://example.com/\"}'. \
+                      <TOOL> is NODE, NPM, YARN or PACKAGES and <HOOK> is DISTRO, LATEST or INDEX",
+    },
+    EnvVar {
+        name: VOLTA_HOOK_EVENTS_PUBLISH,
+        description: "Overrides the events publish hook with its JSON configuration",
+    },
+    EnvVar {
+        name: NO_COLOR,
+        description: "Turns off colored output, unless `--color always` is used",
+    },
+];
+
+/// The name of the variable that overrides the given hook of the given tool
+pub(crate) fn hook_var(tool: &str, hook: &str) -> String {
+    format!("VOLTA_HOOK_{}_{}", tool, hook)
+}
+
+/// Reads a boolean flag: any value other than `0` or `false` enables it.
+pub(crate) fn flag(name: &str) -> Option<bool> {
+    env::var_os(name).map(|value| value != OsString::from("0") && value != OsString::from("false"))
+}
+
+/// Reads a string value, treating an empty value the same as an unset variable.
+pub(crate) fn string(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Reads a comma-separated list of values, ignoring empty entries.
+pub(crate) fn list(name: &str) -> Option<Vec<String>> {
+    string(name).map(|value| {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+/// Reads a value that may not be valid Unicode, such as a path.
+pub(crate) fn os_string(name: &str) -> Option<OsString> {
+    env::var_os(name)
+}
+
+/// Returns whether the variable is set, regardless of its value.
+pub(crate) fn is_set(name: &str) -> bool {
+    env::var_os(name).is_some()
+}
+
+/// Generates the documentation for all of the environment variables read by Volta
+pub fn documentation() -> String {
+    let width = VARIABLES
+        .iter()
+        .map(|var| var.name.len())
+        .max()
+        .unwrap_or_default();
+
+    let mut docs = String::new();
+    for var in VARIABLES {
+        // Writing to a String can't fail
+        let _ = writeln!(
+            docs,
+            "    {:width$}  {}",
+            var.name,
+            var.description,
+            width = width
+        );
+    }
+    docs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flag() {
+        env::set_var("VOLTA_TEST_FLAG_ON", "1");
+        env::set_var("VOLTA_TEST_FLAG_OFF", "false");
+
+        assert_eq!(flag("VOLTA_TEST_FLAG_ON"), Some(true));
+        assert_eq!(flag("VOLTA_TEST_FLAG_OFF"), Some(false));
+        assert_eq!(flag("VOLTA_TEST_FLAG_UNSET"), None);
+    }
+
+    #[test]
+    fn test_list() {
+        env::set_var("VOLTA_TEST_LIST", "NODE_PATH, NODE_OPTIONS,,");
+
+        assert_eq!(
+            list("VOLTA_TEST_LIST"),
+            Some(vec!["NODE_PATH".to_string(), "NODE_OPTIONS".to_string()])
+        );
+        assert_eq!(list("VOLTA_TEST_LIST_UNSET"), None);
+    }
+
+    #[test]
+    fn test_documentation_lists_every_variable() {
+        let docs = documentation();
+
+        for var in VARIABLES {
+            assert!(docs.contains(var.name));
+        }
+    }
+}
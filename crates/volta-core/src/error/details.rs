@@ -35,6 +35,11 @@ impl fmt::Display for CreatePostscriptErrorPath {
 #[derive(Debug, Fail)]
 #[cfg_attr(test, derive(PartialEq))]
 pub enum ErrorDetails {
+    /// Thrown when the Node security metadata for `volta audit-toolchain` can't be downloaded
+    AuditFetchError {
+        from_url: String,
+    },
+
     /// Thrown when package tries to install a binary that is already installed.
     BinaryAlreadyInstalled {
         bin_name: String,
@@ -58,16 +63,32 @@ pub enum ErrorDetails {
         command: String,
     },
 
-    /// Thrown when a user tries to `volta pin` something other than node/yarn/npm.
+    /// Thrown when a user tries to `volta fetch` a package from a local directory.
+    CannotFetchLinkedPackage {
+        package: String,
+    },
+
+    /// Thrown when a user tries to `volta pin` something other than node/npm/yarn/pnpm.
     CannotPinPackage {
         package: String,
     },
 
+    /// Thrown when the checksum of a download is missing from the list published with it
+    ChecksumNotFound {
+        file: String,
+        from_url: String,
+    },
+
     /// Thrown when the Completions out-dir is not a directory
     CompletionsOutFileError {
         path: PathBuf,
     },
 
+    /// Thrown when a destructive command needs confirmation in a non-interactive run
+    ConfirmationRequired {
+        action: String,
+    },
+
     /// Thrown when the containing directory could not be determined
     ContainingDirError {
         path: PathBuf,
@@ -104,6 +125,48 @@ pub enum ErrorDetails {
 
     CurrentDirError,
 
+    /// Thrown when a custom tool provides an executable that another tool already provides
+    CustomToolBinConflict {
+        tool: String,
+        bin: String,
+        owner: String,
+    },
+
+    /// Thrown when a custom tool is defined with the name of a tool that Volta manages itself
+    CustomToolBuiltinName {
+        tool: String,
+    },
+
+    /// Thrown when a custom tool's `binDir` is not a relative path inside of its archive
+    CustomToolInvalidBinDir {
+        tool: String,
+        bin_dir: String,
+    },
+
+    /// Thrown when a custom tool's name, or the name of one of its executables, is not a
+    /// plain file name
+    CustomToolInvalidName {
+        tool: String,
+        name: String,
+    },
+
+    /// Thrown when a custom tool is defined without a `distro` hook
+    CustomToolNoDistro {
+        tool: String,
+    },
+
+    /// Thrown when resolving a version of a custom tool requires an index, but none is defined
+    CustomToolNoIndex {
+        tool: String,
+        matching: String,
+    },
+
+    /// Thrown when there is no version of a custom tool matching a requested specifier
+    CustomToolVersionNotFound {
+        tool: String,
+        matching: String,
+    },
+
     /// Thrown when deleting a directory fails
     DeleteDirectoryError {
         directory: PathBuf,
@@ -144,11 +207,27 @@ pub enum ErrorDetails {
         command: String,
     },
 
+    /// Thrown when the `extends` links between projects form a cycle.
+    ExtendsCycleError {
+        file: PathBuf,
+    },
+
+    /// Thrown when a project's `extends` chain is longer than the supported maximum.
+    ExtendsDepthError {
+        file: PathBuf,
+        max: usize,
+    },
+
     /// Thrown when a hook command returns a non-zero exit code
     HookCommandFailed {
         command: String,
     },
 
+    /// Thrown when a hook declares a format that it can't be read in
+    HookFormatNotSupported {
+        hook: String,
+    },
+
     /// Thrown when a hook contains multiple fields (prefix, template, or bin)
     HookMultipleFieldsSpecified,
 
@@ -160,6 +239,34 @@ pub enum ErrorDetails {
         command: String,
     },
 
+    /// Thrown when an installation of Node made by another version manager can't be copied
+    ImportNodeError {
+        version: String,
+        dir: PathBuf,
+    },
+
+    /// Thrown when the lifecycle scripts of a package fail in the cleared environment
+    InstallScriptsFailed {
+        package: String,
+    },
+
+    /// Thrown when there isn't enough disk space to download or unpack a tool
+    InsufficientSpaceError {
+        dir: PathBuf,
+        required: u64,
+        available: u64,
+    },
+
+    /// Thrown when a signed file doesn't match its signature
+    IntegrityError {
+        file: PathBuf,
+    },
+
+    /// Thrown when signing is turned on but the machine secret can't be read
+    IntegrityKeyError {
+        file: PathBuf,
+    },
+
     InvalidHookCommand {
         command: String,
     },
@@ -177,17 +284,85 @@ pub enum ErrorDetails {
         version: String,
     },
 
+    /// Thrown when a setting has a value that isn't supported
+    InvalidSetting {
+        setting: String,
+        value: String,
+        expected: String,
+    },
+
     /// Thrown when a tool name is invalid per npm's rules.
     InvalidToolName {
         name: String,
         errors: Vec<String>,
     },
 
+    /// Thrown when a package in a local directory doesn't have a name.
+    LinkedPackageNameError {
+        dir: PathBuf,
+    },
+
+    /// Thrown when an archive doesn't match the checksum recorded in the project's `volta.lock`
+    LockedChecksumMismatch {
+        tool: String,
+        version: String,
+        origin: String,
+    },
+
+    /// Thrown when a project pins a different version than its `volta.lock` records
+    LockedVersionMismatch {
+        tool: String,
+        pinned: String,
+        locked: String,
+    },
+
+    /// Thrown when unable to create or remove the Volta lock file
+    LockFileError {
+        file: PathBuf,
+    },
+
+    /// Thrown when another Volta process holds the lock for longer than the lock timeout
+    LockTimeoutError {
+        file: PathBuf,
+        timeout: u64,
+    },
+
+    /// Thrown when usage metrics are used in a build without the `metrics` feature
+    MetricsNotAvailable,
+
+    /// Thrown when the usage metrics could not be sent to the configured endpoint
+    MetricsSendError {
+        endpoint: String,
+    },
+
     /// Thrown when BinConfig (read from file) does not contain Platform info.
     NoBinPlatform {
         binary: String,
     },
 
+    /// Thrown when a custom tool has neither a project version nor a default version
+    NoCustomToolVersion {
+        tool: String,
+    },
+
+    /// Thrown when building Node from source fails
+    NodeBuildError {
+        version: String,
+        step: String,
+        log: PathBuf,
+    },
+
+    /// Thrown when the release schedule of the Node lines can't be downloaded
+    NodeScheduleFetchError {
+        from_url: String,
+    },
+
+    /// Thrown when an unpacked Node distro fails to report the expected version.
+    NodeVerificationError {
+        version: String,
+        found: Option<String>,
+    },
+
     /// Thrown when there is no Node version matching a requested semver specifier.
     NodeVersionNotFound {
         matching: String,
@@ -204,30 +379,65 @@ pub enum ErrorDetails {
 
     NoLocalDataDir,
 
+    /// Thrown when sending the usage metrics without a configured endpoint
+    NoMetricsEndpoint,
+
     /// Thrown when a user tries to install or fetch a package with no executables.
     NoPackageExecutables,
 
-    /// Thrown when a user tries to pin a Yarn version before pinning a Node version.
-    NoPinnedNodeVersion,
+    /// Thrown when none of the platform-specific dependencies of a package supports the system
+    NoPackagePlatformBuild {
+        package: String,
+        platform: String,
+    },
+
+    /// Thrown when a user tries to pin a package manager version before pinning a Node version.
+    NoPinnedNodeVersion {
+        tool: String,
+    },
 
     /// Thrown when the platform (Node version) could not be determined
     NoPlatform,
 
+    /// Thrown when a project doesn't pin Node and strict mode prevents using the default
+    NoProjectNode,
+
     /// Thrown when Yarn is not set in a project
     NoProjectYarn,
 
+    /// Thrown when pnpm is not set in a project
+    NoProjectPnpm,
+
     /// Thrown when no shell profiles could be found
     NoShellProfile {
         env_profile: String,
         bin_dir: PathBuf,
     },
 
+    /// Thrown when `volta export-env` is run in a project that doesn't pin Node
+    NothingToExport,
+
+    /// Thrown when `volta generate` is run in a project that doesn't pin Node
+    NothingToGenerate,
+
+    /// Thrown when `volta lock` is run in a project that doesn't pin Node
+    NothingToLock,
+
+    /// Thrown when `volta ci-setup` is run in a project that doesn't pin Node
+    NothingToSetUp,
+
     /// Thrown when the user tries to pin Node or Yarn versions outside of a package.
     NotInPackage,
 
+    /// Thrown when pinning the tools of a workspace outside of any workspace
+    NotInWorkspace,
+
     /// Thrown when default Yarn is not set
     NoDefaultYarn,
 
+    /// Thrown when default pnpm is not set
+    NoDefaultPnpm,
+
     NoVersionsFound,
 
     /// Thrown when there is an error running `npm view`
@@ -250,10 +460,20 @@ pub enum ErrorDetails {
         package: String,
     },
 
+    /// Thrown when there is no npm version matching a requested semver specifier.
+    NpmVersionNotFound {
+        matching: String,
+    },
+
     NpxNotAvailable {
         version: String,
     },
 
+    /// Thrown when a network request is needed while offline mode is enabled
+    OfflineModeError {
+        from_url: String,
+    },
+
     /// Thrown when package install command is not successful.
     PackageInstallFailed,
 
@@ -267,6 +487,11 @@ pub enum ErrorDetails {
         package: String,
     },
 
+    /// Thrown when trying to upgrade a package that isn't installed
+    PackageNotInstalled {
+        package: String,
+    },
+
     /// Thrown when parsing a package manifest fails
     PackageParseError {
         file: PathBuf,
@@ -299,6 +524,16 @@ pub enum ErrorDetails {
         file: PathBuf,
     },
 
+    /// Thrown when unable to parse a hook configured with an environment variable
+    ParseHookVariableError {
+        variable: String,
+    },
+
+    /// Thrown when unable to parse a project's `volta.lock`
+    ParseLockfileError {
+        file: PathBuf,
+    },
+
     /// Thrown when unable to parse the node index cache
     ParseNodeIndexCacheError,
 
@@ -310,6 +545,11 @@ pub enum ErrorDetails {
     /// Thrown when unable to parse the node index cache expiration
     ParseNodeIndexExpiryError,
 
+    /// Thrown when the release schedule of the Node lines can't be parsed
+    ParseNodeScheduleError {
+        from_url: String,
+    },
+
     /// Thrown when unable to parse the npm manifest file from a node install
     ParseNpmManifestError,
 
@@ -324,16 +564,36 @@ pub enum ErrorDetails {
     /// Thrown when unable to parse the platform.json file
     ParsePlatformError,
 
+    /// Thrown when unable to parse a settings file
+    ParseSettingsError {
+        file: PathBuf,
+    },
+
+    /// Thrown when a toolchain state file passed to `volta import` could not be parsed
+    ParseStateError {
+        file: PathBuf,
+    },
+
     /// Thrown when unable to parse a tool spec (`<tool>[@<version>]`)
     ParseToolSpecError {
         tool_spec: String,
     },
 
+    /// Thrown when Volta doesn't have permission to write to a file or directory it needs to modify
+    PermissionDenied {
+        path: PathBuf,
+    },
+
     /// Thrown when persisting an archive to the inventory fails
     PersistInventoryError {
         tool: String,
     },
 
+    /// Thrown when there is no pnpm version matching a requested semver specifier.
+    PnpmVersionNotFound {
+        matching: String,
+    },
+
     /// Thrown when executing a project-local binary fails
     ProjectLocalBinaryExecError {
         command: String,
@@ -344,6 +604,9 @@ pub enum ErrorDetails {
         command: String,
     },
 
+    /// Thrown when the answer to an interactive prompt could not be read
+    PromptError,
+
     /// Thrown when a publish hook contains both the url and bin fields
     PublishHookBothUrlAndBin,
 
@@ -370,6 +633,11 @@ pub enum ErrorDetails {
         dir: PathBuf,
     },
 
+    /// Thrown when unable to read a file
+    ReadFileError {
+        file: PathBuf,
+    },
+
     /// Thrown when there was an error opening a hooks.json file
     ReadHooksError {
         file: PathBuf,
@@ -388,6 +656,16 @@ pub enum ErrorDetails {
     /// Thrown when there was an error reading the npm manifest file
     ReadNpmManifestError,
 
+    /// Thrown when there was an error reading an npm configuration file
+    ReadNpmrcError {
+        file: PathBuf,
+    },
+
+    /// Thrown when a change to the toolchain is attempted while read-only mode is enabled
+    ReadOnlyModeError {
+        operation: String,
+    },
+
     /// Thrown when there was an error reading a package configuration file
     ReadPackageConfigError {
         file: PathBuf,
@@ -398,19 +676,98 @@ pub enum ErrorDetails {
         file: PathBuf,
     },
 
+    /// Thrown when there was an error reading a settings file
+    ReadSettingsError {
+        file: PathBuf,
+    },
+
+    /// Thrown when a toolchain state file passed to `volta import` could not be read
+    ReadStateError {
+        file: PathBuf,
+    },
+
     /// Thrown when unable to read the user Path environment variable from the registry
     #[cfg(windows)]
     ReadUserPathError,
 
+    /// Thrown when the executable that a shim resolves to is itself a Volta shim
+    RecursiveShim {
+        command: String,
+    },
+
     /// Thrown when the public registry for Node or Yarn could not be downloaded.
     RegistryFetchError {
         tool: String,
         from_url: String,
     },
 
+    /// Thrown when the destination of `volta relocate` already has contents
+    RelocateDestinationError {
+        path: PathBuf,
+    },
+
+    /// Thrown when the Volta home could not be moved or copied to its new location
+    RelocateError {
+        from: PathBuf,
+        to: PathBuf,
+    },
+
+    /// Thrown when the destination of `volta relocate` is inside the current Volta home
+    RelocateIntoHomeError {
+        path: PathBuf,
+    },
+
+    /// Thrown when `volta verify --repair` finds a problem with a linked package
+    RepairLinkedPackageError {
+        package: String,
+    },
+
+    /// Thrown when `volta verify --repair` can't move the files of a damaged version aside
+    RepairMoveAsideError {
+        path: PathBuf,
+    },
+
+    /// Thrown when the command passed to `volta run` could not be executed
+    RunCommandError {
+        command: String,
+    },
+
     /// Thrown when the shim binary is called directly, not through a symlink
     RunShimDirectly,
 
+    /// Thrown when the latest Volta release doesn't include a build for this platform
+    SelfUpdateAssetError {
+        version: String,
+        asset: String,
+    },
+
+    /// Thrown when a downloaded Volta release doesn't match its published checksum
+    SelfUpdateChecksumError {
+        version: String,
+    },
+
+    /// Thrown when a Volta release could not be downloaded
+    SelfUpdateDownloadError {
+        version: String,
+        from_url: String,
+    },
+
+    /// Thrown when a downloaded Volta release doesn't contain one of the Volta executables
+    SelfUpdateMissingFileError {
+        version: String,
+        file: String,
+    },
+
+    /// Thrown when the list of Volta releases could not be fetched or parsed
+    SelfUpdateReleaseError {
+        from_url: String,
+    },
+
+    /// Thrown when an installed Volta executable could not be replaced
+    SelfUpdateReplaceError {
+        file: PathBuf,
+    },
+
     /// Thrown when there was an error copying an unpacked tool to the image directory
     SetupToolImageError {
         tool: String,
@@ -421,11 +778,13 @@ pub enum ErrorDetails {
     /// Thrown when Volta is unable to create a shim
     ShimCreateError {
         name: String,
+        file: PathBuf,
     },
 
     /// Thrown when Volta is unable to remove a shim
     ShimRemoveError {
         name: String,
+        file: PathBuf,
     },
 
     /// Thrown when serializnig a bin config to JSON fails
@@ -440,6 +799,18 @@ pub enum ErrorDetails {
     /// Thrown when serializing the toolchain to JSON fails
     StringifyToolchainError,
 
+    /// Thrown when unable to create a symlink
+    SymlinkError {
+        link: PathBuf,
+        target: PathBuf,
+    },
+
+    /// Thrown when a tool needed to run a command isn't installed and auto-install is disabled
+    ToolchainNotInstalled {
+        tool: String,
+        version: String,
+    },
+
     /// Thrown when a given feature has not yet been implemented
     Unimplemented {
         feature: String,
@@ -462,6 +833,12 @@ pub enum ErrorDetails {
     /// Thrown when the shell name was not specified in the Volta environment.
     UnspecifiedShell,
 
+    /// Thrown when the directory of the version manager to import tools from doesn't exist
+    VersionManagerNotFound {
+        manager: String,
+        dir: PathBuf,
+    },
+
     VersionParseError {
         version: String,
     },
@@ -476,6 +853,16 @@ pub enum ErrorDetails {
         file: PathBuf,
     },
 
+    /// Thrown when unable to write a file
+    WriteFileError {
+        file: PathBuf,
+    },
+
+    /// Thrown when unable to write a file in a project-local toolchain directory
+    WriteLocalToolchainError {
+        file: PathBuf,
+    },
+
     /// Thrown when there was an error writing the node index cache
     WriteNodeIndexCacheError {
         file: PathBuf,
@@ -486,6 +873,11 @@ pub enum ErrorDetails {
         file: PathBuf,
     },
 
+    /// Thrown when there was an error writing the combined npm configuration for a package install
+    WriteNpmrcError {
+        file: PathBuf,
+    },
+
     /// Thrown when there was an error writing a package config
     WritePackageConfigError {
         file: PathBuf,
@@ -521,6 +913,14 @@ pub enum ErrorDetails {
 impl fmt::Display for ErrorDetails {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            ErrorDetails::AuditFetchError { from_url } => write!(
+                f,
+                "Could not download the Node security metadata
+from {}
+
+Please verify your internet connection.",
+                from_url
+            ),
             ErrorDetails::BinaryAlreadyInstalled {
                 bin_name,
                 existing_package,
@@ -558,13 +958,28 @@ Please ensure your PATH is valid."
 VOLTA_BYPASS is enabled, please ensure that the command exists on your system or unset VOLTA_BYPASS",
                 command,
             ),
+            ErrorDetails::CannotFetchLinkedPackage { package } => write!(
+                f,
+                "Cannot fetch a package from a local directory
+
+Use `volta install {}` to make its executables available, using the package in place.",
+                package
+            ),
             ErrorDetails::CannotPinPackage { package } => write!(
                 f,
-                "Only node and yarn can be pinned in a project
+                "Only node, npm, yarn and pnpm can be pinned in a project
 
 Use `npm install` or `yarn add` to select a version of {} for this project.",
                 package
             ),
+            ErrorDetails::ChecksumNotFound { file, from_url } => write!(
+                f,
+                "Could not find the checksum of {}
+in {}
+
+Please ensure that the Node mirror or the npm registry publishes the checksums of each release.",
+                file, from_url
+            ),
             ErrorDetails::CompletionsOutFileError { path } => write!(
                 f,
                 "Completions file `{}` already exists.
@@ -572,6 +987,13 @@ Use `npm install` or `yarn add` to select a version of {} for this project.",
 Please remove the file or pass `-f` or `--force` to override.",
                 path.display()
             ),
+            ErrorDetails::ConfirmationRequired { action } => write!(
+                f,
+                "Could not {} without confirmation, since there is no terminal to ask.
+
+Please use the `--yes` flag to confirm it.",
+                action
+            ),
             ErrorDetails::ContainingDirError { path } => write!(
                 f,
                 "Could not create the containing directory for {}
@@ -639,6 +1061,55 @@ in {}
 
 Please ensure that you have the correct permissions."
             ),
+            ErrorDetails::CustomToolBinConflict { tool, bin, owner } => write!(
+                f,
+                "Cannot use '{}' as an executable of the custom tool '{}', since it is already provided by {}.
+
+Please remove it from the `bins` of '{}' in your hooks.json.",
+                bin, tool, owner, tool
+            ),
+            ErrorDetails::CustomToolBuiltinName { tool } => write!(
+                f,
+                "Cannot define a custom tool named '{}', since Volta manages that tool itself.
+
+Please remove it from the `tools` section of your hooks.json.",
+                tool
+            ),
+            ErrorDetails::CustomToolInvalidBinDir { tool, bin_dir } => write!(
+                f,
+                "Invalid `binDir` '{}' for the custom tool '{}'.
+
+The directory must be a relative path inside of the tool's archive.",
+                bin_dir, tool
+            ),
+            ErrorDetails::CustomToolInvalidName { tool, name } => write!(
+                f,
+                "Invalid name '{}' in the definition of the custom tool '{}'.
+
+Tool and executable names must be plain file names, without any path separators.",
+                name, tool
+            ),
+            ErrorDetails::CustomToolNoDistro { tool } => write!(
+                f,
+                "The custom tool '{}' has no `distro` hook.
+
+Please add a 'prefix', 'template', or 'bin' hook for the download URL to its definition in hooks.json.",
+                tool
+            ),
+            ErrorDetails::CustomToolNoIndex { tool, matching } => write!(
+                f,
+                r#"Could not resolve {} version matching "{}" because the tool has no `index` hook.
+
+Please use an exact version, or add an `index` hook to its definition in hooks.json."#,
+                tool, matching
+            ),
+            ErrorDetails::CustomToolVersionNotFound { tool, matching } => write!(
+                f,
+                r#"Could not find {} version matching "{}" in the version index.
+
+Please verify that the version is correct."#,
+                tool, matching
+            ),
             ErrorDetails::DeleteDirectoryError { directory } => write!(
                 f,
                 "Could not remove directory
@@ -696,14 +1167,36 @@ Please verify your internet connection and ensure the correct version is specifi
 Please ensure that the correct command is specified.",
                 command
             ),
-            ErrorDetails::HookCommandFailed { command } => write!(
+            ErrorDetails::ExtendsCycleError { file } => write!(
                 f,
-                "Hook command '{}' indicated a failure.
+                "Circular `extends` configuration found at '{}'
 
-Please verify the requested tool and version.",
-                command
+Please ensure that the `volta.extends` settings in your projects don't refer back to each other.",
+                file.display()
             ),
-            ErrorDetails::HookMultipleFieldsSpecified => write!(
+            ErrorDetails::ExtendsDepthError { file, max } => write!(
+                f,
+                "Too many levels of `extends` configuration while loading '{}'
+
+Volta follows at most {} `volta.extends` references; please shorten the chain.",
+                file.display(),
+                max
+            ),
+            ErrorDetails::HookCommandFailed { command } => write!(
+                f,
+                "Hook command '{}' indicated a failure.
+
+Please verify the requested tool and version.",
+                command
+            ),
+            ErrorDetails::HookFormatNotSupported { hook } => write!(
+                f,
+                "The {} hook doesn't support a 'format'.
+
+Only the index hooks of npm, Yarn, and custom tools can declare the format of their index.",
+                hook
+            ),
+            ErrorDetails::HookMultipleFieldsSpecified => write!(
                 f,
                 "Hook configuration includes multiple hook types.
 
@@ -722,6 +1215,56 @@ Please include one of 'bin', 'prefix', or 'template'"
 Please ensure that the correct command is specified.",
                 command
             ),
+            ErrorDetails::ImportNodeError { version, dir } => write!(
+                f,
+                "Could not import Node {} from {}
+
+{}",
+                version,
+                dir.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::InstallScriptsFailed { package } => write!(
+                f,
+                "The install scripts for {} failed in the cleared environment.
+
+They may need environment variables, a proxy, or files in the home directory. To install it
+without running its scripts, use `volta install --ignore-scripts {}`",
+                package, package
+            ),
+            ErrorDetails::InsufficientSpaceError {
+                dir,
+                required,
+                available,
+            } => write!(
+                f,
+                "Not enough disk space to download and unpack the tool
+into {}
+
+{} bytes are required, but only {} bytes are available.
+Please free up some space and try again.",
+                dir.display(),
+                required,
+                available
+            ),
+            ErrorDetails::IntegrityError { file } => write!(
+                f,
+                "The signature of {}
+does not match its contents.
+
+The file may have been changed outside of Volta, so Volta will not run with it.
+If you made the change yourself, run `volta sign` to sign the file again.",
+                file.display()
+            ),
+            ErrorDetails::IntegrityKeyError { file } => write!(
+                f,
+                "The signing key at {}
+is missing or corrupt, but signing is turned on.
+
+The key may have been changed outside of Volta, so Volta will not run without it.
+If you removed it yourself, run `volta sign` to create a new key and sign your files again.",
+                file.display()
+            ),
             ErrorDetails::InvalidHookCommand { command } => write!(
                 f,
                 "Invalid hook command: '{}'
@@ -766,6 +1309,17 @@ To {action} the packages '{name}' and '{version}', please {action} them in separ
                 write!(f, "{}\n\n{}", error, wrapped_cta)
             }
 
+            ErrorDetails::InvalidSetting {
+                setting,
+                value,
+                expected,
+            } => write!(
+                f,
+                "Invalid value '{}' for setting '{}'.
+
+Please use {}.",
+                value, setting, expected
+            ),
             ErrorDetails::InvalidToolName { name, errors } => {
                 let indentation = "    ";
                 let wrapped = match text_width() {
@@ -787,6 +1341,68 @@ To {action} the packages '{name}' and '{version}', please {action} them in separ
                 )
             }
 
+            ErrorDetails::LinkedPackageNameError { dir } => write!(
+                f,
+                "Could not determine the name of the package
+in {}
+
+Please ensure that its package.json has a `name` field.",
+                dir.display()
+            ),
+            ErrorDetails::LockedChecksumMismatch {
+                tool,
+                version,
+                origin,
+            } => write!(
+                f,
+                "The archive of {} v{} does not match the checksum recorded in volta.lock
+from {}
+
+If the lockfile is correct, the archive has been tampered with or corrupted.",
+                tool, version, origin
+            ),
+            ErrorDetails::LockedVersionMismatch {
+                tool,
+                pinned,
+                locked,
+            } => write!(
+                f,
+                "This project pins {} v{}, but its volta.lock records v{}.
+
+Use `volta lock` to update the lockfile.",
+                tool, pinned, locked
+            ),
+            ErrorDetails::LockFileError { file } => write!(
+                f,
+                "Could not access the Volta lock file
+at {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::LockTimeoutError { file, timeout } => write!(
+                f,
+                "Timed out after {} seconds waiting for another Volta process to finish.
+
+If no other Volta process is running, the lock may have been left behind by a crash.
+Please remove the lock file at {} and try again.",
+                timeout,
+                file.display()
+            ),
+            ErrorDetails::MetricsNotAvailable => write!(
+                f,
+                "Usage metrics are not available in this build of Volta.
+
+Volta needs to be built with the `metrics` feature to record usage metrics."
+            ),
+            ErrorDetails::MetricsSendError { endpoint } => write!(
+                f,
+                "Could not send the usage metrics to {}
+
+Please verify your internet connection and the 'metricsEndpoint' setting.",
+                endpoint
+            ),
             ErrorDetails::NoBinPlatform { binary } => write!(
                 f,
                 "Platform info for executable `{}` is missing
@@ -794,6 +1410,44 @@ To {action} the packages '{name}' and '{version}', please {action} them in separ
 Please uninstall and re-install the package that provides that executable.",
                 binary
             ),
+            ErrorDetails::NoCustomToolVersion { tool } => write!(
+                f,
+                "{} is not available.
+
+Use `volta install {}` to select a default version (see `volta help install` for more info).",
+                tool, tool
+            ),
+            ErrorDetails::NodeBuildError { version, step, log } => write!(
+                f,
+                "Could not build Node v{} from source (`{}` failed)
+
+See the build output in {}
+Please make sure that the tools Node needs to build (Python, a C++ compiler and make)
+are installed and try again.",
+                version,
+                step,
+                log.display()
+            ),
+            ErrorDetails::NodeScheduleFetchError { from_url } => write!(
+                f,
+                "Could not download the Node release schedule
+from {}
+
+Please verify your internet connection.",
+                from_url
+            ),
+            ErrorDetails::NodeVerificationError { version, found } => write!(
+                f,
+                "Could not verify the installation of Node v{}{}
+
+The downloaded archive may be truncated or built for a different architecture.
+Please remove any cached archive for this version and try again.",
+                version,
+                match found {
+                    Some(found) => format!(" (node reported '{}')", found),
+                    None => String::new(),
+                }
+            ),
             ErrorDetails::NodeVersionNotFound { matching } => write!(
                 f,
                 r#"Could not find Node version matching "{}" in the version registry.
@@ -828,6 +1482,12 @@ Please ensure Volta was installed correctly"
                 "Could not determine LocalAppData directory.
 
 Please ensure the directory is available."
+            ),
+            ErrorDetails::NoMetricsEndpoint => write!(
+                f,
+                "No endpoint is configured for sending usage metrics.
+
+Use the 'metricsEndpoint' setting to choose where the usage metrics are sent."
             ),
             ErrorDetails::NoPackageExecutables => write!(
                 f,
@@ -835,43 +1495,101 @@ Please ensure the directory is available."
 
 Please verify the requested package name."
             ),
-            ErrorDetails::NoPinnedNodeVersion => write!(
+            ErrorDetails::NoPackagePlatformBuild { package, platform } => write!(
                 f,
-                "Cannot pin Yarn because the Node version is not pinned in this project.
+                "Could not find a build of {} for this platform ({}).
 
-Use `volta pin node` to pin Node first, then pin a Yarn version."
+The package provides its executables through platform-specific dependencies, but none of them supports this system.",
+                package, platform
+            ),
+            ErrorDetails::NoPinnedNodeVersion { tool } => write!(
+                f,
+                "Cannot pin {} because the Node version is not pinned in this project.
+
+Use `volta pin node` to pin Node first, then pin a {} version.",
+                tool, tool
             ),
             ErrorDetails::NoPlatform => write!(
                 f,
                 "Node is not available.
 
 To run any Node command, first set a default version using `volta install node`"
+            ),
+            ErrorDetails::NoProjectNode => write!(
+                f,
+                "No Node version found in this project.
+
+Strict mode requires every project to pin its toolchain instead of using the default.
+Use `volta pin node` to select a version (see `volta help pin` for more info)."
             ),
             ErrorDetails::NoProjectYarn => write!(
                 f,
                 "No Yarn version found in this project.
 
 Use `volta pin yarn` to select a version (see `volta help pin` for more info)."
+            ),
+            ErrorDetails::NoProjectPnpm => write!(
+                f,
+                "No pnpm version found in this project.
+
+Use `volta pin pnpm` to select a version (see `volta help pin` for more info)."
             ),
             ErrorDetails::NoShellProfile { env_profile, bin_dir } => write!(
                 f,
                 "Could not locate user profile.
-Tried $PROFILE ({}), ~/.bashrc, ~/.bash_profile, ~/.zshrc, ~/.profile, and ~/.config/fish/config.fish
+Tried $PROFILE ({}), ~/.bashrc, ~/.bash_profile, ~/.zshrc, ~/.profile, ~/.config/fish/config.fish,
+and ~/.config/powershell/Microsoft.PowerShell_profile.ps1
 
 Please create one of these and try again; or you can edit your profile manually to add '{}' to your PATH",
                 env_profile, bin_dir.display()
             ),
+            ErrorDetails::NothingToExport => write!(
+                f,
+                "No Node version found in this project.
+
+Use `volta pin node` to select a version before running `volta export-env`."
+            ),
+            ErrorDetails::NothingToGenerate => write!(
+                f,
+                "No Node version found in this project.
+
+Use `volta pin node` to select a version before running `volta generate`."
+            ),
+            ErrorDetails::NothingToLock => write!(
+                f,
+                "No Node version found in this project.
+
+Use `volta pin node` to select a version before running `volta lock`."
+            ),
+            ErrorDetails::NothingToSetUp => write!(
+                f,
+                "No Node version found in this project.
+
+Use `volta pin node` to select a version before running `volta ci-setup`."
+            ),
             ErrorDetails::NotInPackage => write!(
                 f,
                 "Not in a node package.
 
 Use `volta install` to select a default version of a tool."
+            ),
+            ErrorDetails::NotInWorkspace => write!(
+                f,
+                "Not in a workspace.
+
+Workspace packages are listed in the `workspaces` field of the root package.json, or in pnpm-workspace.yaml."
             ),
             ErrorDetails::NoDefaultYarn => write!(
                 f,
                 "Yarn is not available.
 
 Use `volta install yarn` to select a default version (see `volta help install for more info)."
+            ),
+            ErrorDetails::NoDefaultPnpm => write!(
+                f,
+                "pnpm is not available.
+
+Use `volta install pnpm` to select a default version (see `volta help install` for more info)."
             ),
             // No CTA as this error is purely informational
             ErrorDetails::NoVersionsFound => write!(f, "No tool versions found"),
@@ -889,6 +1607,13 @@ Please verify your internet connection and ensure the correct version is specifi
 {}",
                 package, PERMISSIONS_CTA
             ),
+            ErrorDetails::NpmVersionNotFound { matching } => write!(
+                f,
+                r#"Could not find npm version matching "{}" in the version registry.
+
+Please verify that the version is correct."#,
+                matching
+            ),
             ErrorDetails::NpmViewMetadataFetchError { package } => write!(
                 f,
                 "Could not download package metadata for '{}'
@@ -910,6 +1635,13 @@ Please ensure the requested package name is correct.",
 This project is configured to use version {} of npm.",
                 version
             ),
+            ErrorDetails::OfflineModeError { from_url } => write!(
+                f,
+                "Could not download from {} because offline mode is enabled.
+
+Please disable the 'offline' setting, or use a version that has already been fetched.",
+                from_url
+            ),
             // Confirming permissions is a Weak CTA in this case, but it seems the most likely error vector
             ErrorDetails::PackageInstallFailed => write!(
                 f,
@@ -933,6 +1665,13 @@ Please verify your internet connection.",
 Please verify the requested package name.",
                 package
             ),
+            ErrorDetails::PackageNotInstalled { package } => write!(
+                f,
+                "Package '{}' is not installed.
+
+Use `volta install {}` to install it.",
+                package, package
+            ),
             ErrorDetails::PackageParseError { file } => write!(
                 f,
                 "Could not parse project manifest
@@ -985,6 +1724,21 @@ from {}
 Please ensure the file is correctly formatted.",
                 file.display()
             ),
+            ErrorDetails::ParseHookVariableError { variable } => write!(
+                f,
+                "Could not parse hook configuration from the {} environment variable.
+
+Please ensure the variable contains valid JSON hook configuration.",
+                variable
+            ),
+            ErrorDetails::ParseLockfileError { file } => write!(
+                f,
+                "Could not parse lockfile
+from {}
+
+Please ensure the file was created with `volta lock`.",
+                file.display()
+            ),
             ErrorDetails::ParseNodeIndexCacheError => write!(
                 f,
                 "Could not parse Node index cache file.
@@ -1007,6 +1761,14 @@ Please verify your internet connection.",
 {}",
                 REPORT_BUG_CTA
             ),
+            ErrorDetails::ParseNodeScheduleError { from_url } => write!(
+                f,
+                "Could not parse the Node release schedule
+from {}
+
+Please verify your internet connection.",
+                from_url
+            ),
             ErrorDetails::ParseNpmManifestError => write!(
                 f,
                 "Could not parse package.json file for bundled npm.
@@ -1035,6 +1797,22 @@ Please verify the requested package and version.",
 {}",
                 REPORT_BUG_CTA
             ),
+            ErrorDetails::ParseSettingsError { file } => write!(
+                f,
+                "Could not parse settings file
+from {}
+
+Please ensure the file is correctly formatted.",
+                file.display()
+            ),
+            ErrorDetails::ParseStateError { file } => write!(
+                f,
+                "Could not parse toolchain state file.
+from {}
+
+Please ensure the file was created with `volta export`.",
+                file.display()
+            ),
             ErrorDetails::ParseToolSpecError { tool_spec } => write!(
                 f,
                 "Could not parse tool spec `{}`
@@ -1042,6 +1820,24 @@ Please verify the requested package and version.",
 Please supply a spec in the format `<tool name>[@<version>]`.",
                 tool_spec
             ),
+            ErrorDetails::PermissionDenied { path } => {
+                let fix = if cfg!(windows) {
+                    "Please ensure that your user account has permission to modify it."
+                } else {
+                    "If it is owned by another user, for example after running Volta with `sudo`, you can
+take ownership of it with `sudo chown -R $(whoami) <path>`."
+                };
+                write!(
+                    f,
+                    "Volta does not have permission to modify
+{}
+
+{}
+Alternatively, set VOLTA_HOME to a directory in your own home directory.",
+                    path.display(),
+                    fix
+                )
+            }
             ErrorDetails::PersistInventoryError { tool } => write!(
                 f,
                 "Could not store {} archive in inventory cache
@@ -1049,6 +1845,13 @@ Please supply a spec in the format `<tool name>[@<version>]`.",
 {}",
                 tool, PERMISSIONS_CTA
             ),
+            ErrorDetails::PnpmVersionNotFound { matching } => write!(
+                f,
+                r#"Could not find pnpm version matching "{}" in the version registry.
+
+Please verify that the version is correct."#,
+                matching
+            ),
             ErrorDetails::ProjectLocalBinaryExecError { command } => write!(
                 f,
                 "Could not execute `{}`
@@ -1063,6 +1866,12 @@ Please ensure you have correct permissions to access the file.",
 Please ensure that all project dependencies are installed with `npm install` or `yarn install`",
                 command
             ),
+            ErrorDetails::PromptError => write!(
+                f,
+                "Could not read an answer from the terminal.
+
+Please provide the choice as an argument instead."
+            ),
             ErrorDetails::PublishHookBothUrlAndBin => write!(
                 f,
                 "Publish hook configuration includes both hook types.
@@ -1109,6 +1918,15 @@ from {}
 {}",
                 dir.display(), PERMISSIONS_CTA
             ),
+            ErrorDetails::ReadFileError { file } => write!(
+                f,
+                "Could not read file
+at {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
             ErrorDetails::ReadHooksError { file } => write!(
                 f,
                 "Could not read hooks file
@@ -1142,6 +1960,22 @@ from {}
 
 Please ensure the version of Node is correct."
             ),
+            ErrorDetails::ReadNpmrcError { file } => write!(
+                f,
+                "Could not read npm configuration file
+from {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::ReadOnlyModeError { operation } => write!(
+                f,
+                "Could not {} because read-only mode is enabled.
+
+Please ask the administrator of this machine to make the change, or disable the 'readOnly' setting.",
+                operation
+            ),
             ErrorDetails::ReadPackageConfigError { file } => write!(
                 f,
                 "Could not read package configuration file
@@ -1161,12 +1995,37 @@ from {}
                 PERMISSIONS_CTA
             ),
             #[cfg(windows)]
+            ErrorDetails::ReadSettingsError { file } => write!(
+                f,
+                "Could not read settings file
+from {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::ReadStateError { file } => write!(
+                f,
+                "Could not read toolchain state file
+from {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
             ErrorDetails::ReadUserPathError => write!(
                 f,
                 "Could not read user Path environment variable.
 
 Please ensure you have access to the your environment variables."
             ),
+            ErrorDetails::RecursiveShim { command } => write!(
+                f,
+                "Could not run '{}', because it resolves back to a Volta shim.
+
+Please ensure that your hooks and PATH point to the real executable, not to a shim.",
+                command
+            ),
             ErrorDetails::RegistryFetchError { tool, from_url } => write!(
                 f,
                 "Could not download {} version registry
@@ -1175,12 +2034,105 @@ from {}
 Please verify your internet connection.",
                 tool, from_url
             ),
+            ErrorDetails::RelocateDestinationError { path } => write!(
+                f,
+                "Could not move the Volta home to {}, since it already exists and isn't empty.
+
+Please choose a new or empty directory.",
+                path.display()
+            ),
+            ErrorDetails::RelocateError { from, to } => write!(
+                f,
+                "Could not move the Volta home
+from {}
+to {}
+
+{}",
+                from.display(),
+                to.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::RelocateIntoHomeError { path } => write!(
+                f,
+                "Could not move the Volta home to {}, since it is inside the Volta home.
+
+Please choose a directory outside of the current Volta home.",
+                path.display()
+            ),
+            ErrorDetails::RepairLinkedPackageError { package } => write!(
+                f,
+                "Could not repair {}, since it is linked from a local directory.
+
+Please install it again from its working copy.",
+                package
+            ),
+            ErrorDetails::RepairMoveAsideError { path } => write!(
+                f,
+                "Could not move the damaged version aside
+at {}
+
+{}",
+                path.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::RunCommandError { command } => write!(
+                f,
+                "Could not execute command '{}'
+
+Please ensure that the command exists and is executable with the requested platform.",
+                command
+            ),
             ErrorDetails::RunShimDirectly => write!(
                 f,
                 "'volta-shim' should not be called directly.
 
 Please use the existing shims provided by Volta (node, yarn, etc.) to run tools."
             ),
+            ErrorDetails::SelfUpdateAssetError { version, asset } => write!(
+                f,
+                "Volta {} does not include a build for this platform (expected {}).
+
+Please download and install Volta manually.",
+                version, asset
+            ),
+            ErrorDetails::SelfUpdateChecksumError { version } => write!(
+                f,
+                "The download of Volta {} does not match its published checksum.
+
+Please try again.",
+                version
+            ),
+            ErrorDetails::SelfUpdateDownloadError { version, from_url } => write!(
+                f,
+                "Could not download Volta {}
+from {}
+
+Please verify your internet connection.",
+                version, from_url
+            ),
+            ErrorDetails::SelfUpdateMissingFileError { version, file } => write!(
+                f,
+                "The download of Volta {} does not contain {}.
+
+Please download and install Volta manually.",
+                version, file
+            ),
+            ErrorDetails::SelfUpdateReleaseError { from_url } => write!(
+                f,
+                "Could not determine the latest Volta release
+from {}
+
+Please verify your internet connection.",
+                from_url
+            ),
+            ErrorDetails::SelfUpdateReplaceError { file } => write!(
+                f,
+                "Could not replace {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
             ErrorDetails::SetupToolImageError { tool, version, dir } => write!(
                 f,
                 "Could not create environment for {} v{}
@@ -1192,19 +2144,25 @@ at {}
                 dir.display(),
                 PERMISSIONS_CTA
             ),
-            ErrorDetails::ShimCreateError { name } => write!(
+            ErrorDetails::ShimCreateError { name, file } => write!(
                 f,
                 r#"Could not create shim for "{}"
+at {}
 
 {}"#,
-                name, PERMISSIONS_CTA
+                name,
+                file.display(),
+                PERMISSIONS_CTA
             ),
-            ErrorDetails::ShimRemoveError { name } => write!(
+            ErrorDetails::ShimRemoveError { name, file } => write!(
                 f,
                 r#"Could not remove shim for "{}"
+at {}
 
 {}"#,
-                name, PERMISSIONS_CTA
+                name,
+                file.display(),
+                PERMISSIONS_CTA
             ),
             ErrorDetails::StringifyBinConfigError => write!(
                 f,
@@ -1234,6 +2192,24 @@ at {}
 {}",
                 REPORT_BUG_CTA
             ),
+            ErrorDetails::SymlinkError { link, target } => write!(
+                f,
+                "Could not create a link to {}
+at {}
+
+{}",
+                target.display(),
+                link.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::ToolchainNotInstalled { tool, version } => write!(
+                f,
+                "{} is not installed.
+
+Use `volta fetch --from-project` to install the toolchain pinned by this project,
+or enable the `autoinstall` setting to install missing tools automatically.",
+                tool_version(tool, version)
+            ),
             ErrorDetails::Unimplemented { feature } => {
                 write!(f, "{} is not supported yet.", feature)
             }
@@ -1258,6 +2234,15 @@ Please ensure you are using a supported shell.",
 Please ensure Volta was installed correctly."
             ),
             ErrorDetails::UnspecifiedShell => write!(f, "Volta shell not specified"),
+            ErrorDetails::VersionManagerNotFound { manager, dir } => write!(
+                f,
+                "Could not find the {} directory at {}
+
+Please ensure that {} is installed, or set the variable it uses for its directory.",
+                manager,
+                dir.display(),
+                manager
+            ),
             ErrorDetails::VersionParseError { version } => write!(
                 f,
                 r#"Could not parse version "{}"
@@ -1279,6 +2264,24 @@ to {}
                 "Could not write bundled npm version
 to {}
 
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::WriteFileError { file } => write!(
+                f,
+                "Could not write file
+at {}
+
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::WriteLocalToolchainError { file } => write!(
+                f,
+                "Could not write to the project toolchain directory
+at {}
+
 {}",
                 file.display(),
                 PERMISSIONS_CTA
@@ -1297,6 +2300,15 @@ to {}
                 "Could not write Node index cache expiration
 to {}
 
+{}",
+                file.display(),
+                PERMISSIONS_CTA
+            ),
+            ErrorDetails::WriteNpmrcError { file } => write!(
+                f,
+                "Could not write npm configuration file
+to {}
+
 {}",
                 file.display(),
                 PERMISSIONS_CTA
@@ -1363,13 +2375,17 @@ Please verify that the version is correct."#,
 impl VoltaFail for ErrorDetails {
     fn exit_code(&self) -> ExitCode {
         match self {
+            ErrorDetails::AuditFetchError { .. } => ExitCode::NetworkError,
             ErrorDetails::BinaryAlreadyInstalled { .. } => ExitCode::FileSystemError,
             ErrorDetails::BinaryExecError => ExitCode::ExecutionFailure,
             ErrorDetails::BinaryNotFound { .. } => ExitCode::ExecutableNotFound,
             ErrorDetails::BuildPathError => ExitCode::EnvironmentError,
             ErrorDetails::BypassError { .. } => ExitCode::ExecutionFailure,
+            ErrorDetails::CannotFetchLinkedPackage { .. } => ExitCode::InvalidArguments,
             ErrorDetails::CannotPinPackage { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::ChecksumNotFound { .. } => ExitCode::NetworkError,
             ErrorDetails::CompletionsOutFileError { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::ConfirmationRequired { .. } => ExitCode::InvalidArguments,
             ErrorDetails::ContainingDirError { .. } => ExitCode::FileSystemError,
             ErrorDetails::CouldNotDetermineTool => ExitCode::UnknownError,
             ErrorDetails::CouldNotStartMigration => ExitCode::EnvironmentError,
@@ -1379,6 +2395,13 @@ impl VoltaFail for ErrorDetails {
             ErrorDetails::CreateTempDirError { .. } => ExitCode::FileSystemError,
             ErrorDetails::CreateTempFileError { .. } => ExitCode::FileSystemError,
             ErrorDetails::CurrentDirError => ExitCode::EnvironmentError,
+            ErrorDetails::CustomToolBinConflict { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::CustomToolBuiltinName { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::CustomToolInvalidBinDir { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::CustomToolInvalidName { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::CustomToolNoDistro { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::CustomToolNoIndex { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::CustomToolVersionNotFound { .. } => ExitCode::NoVersionMatch,
             ErrorDetails::DeleteDirectoryError { .. } => ExitCode::FileSystemError,
             ErrorDetails::DeleteFileError { .. } => ExitCode::FileSystemError,
             ErrorDetails::DeprecatedCommandError { .. } => ExitCode::InvalidArguments,
@@ -1387,36 +2410,69 @@ impl VoltaFail for ErrorDetails {
             ErrorDetails::ExecutablePathError { .. } => ExitCode::UnknownError,
             ErrorDetails::ExecutablePermissionsError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ExecuteHookError { .. } => ExitCode::ExecutionFailure,
+            ErrorDetails::ExtendsCycleError { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::ExtendsDepthError { .. } => ExitCode::ConfigurationError,
             ErrorDetails::HookCommandFailed { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::HookFormatNotSupported { .. } => ExitCode::ConfigurationError,
             ErrorDetails::HookMultipleFieldsSpecified => ExitCode::ConfigurationError,
             ErrorDetails::HookNoFieldsSpecified => ExitCode::ConfigurationError,
             ErrorDetails::HookPathError { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::ImportNodeError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::InstallScriptsFailed { .. } => ExitCode::FileSystemError,
+            ErrorDetails::InsufficientSpaceError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::IntegrityError { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::IntegrityKeyError { .. } => ExitCode::ConfigurationError,
             ErrorDetails::InvalidHookCommand { .. } => ExitCode::ExecutableNotFound,
             ErrorDetails::InvalidHookOutput { .. } => ExitCode::ExecutionFailure,
             ErrorDetails::InvalidInvocation { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::InvalidSetting { .. } => ExitCode::ConfigurationError,
             ErrorDetails::InvalidToolName { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::LinkedPackageNameError { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::LockedChecksumMismatch { .. } => ExitCode::FileSystemError,
+            ErrorDetails::LockedVersionMismatch { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::LockFileError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::LockTimeoutError { .. } => ExitCode::EnvironmentError,
+            ErrorDetails::MetricsNotAvailable => ExitCode::NotYetImplemented,
+            ErrorDetails::MetricsSendError { .. } => ExitCode::NetworkError,
             ErrorDetails::NoBinPlatform { .. } => ExitCode::ExecutionFailure,
+            ErrorDetails::NoCustomToolVersion { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::NodeBuildError { .. } => ExitCode::BuildError,
+            ErrorDetails::NodeScheduleFetchError { .. } => ExitCode::NetworkError,
+            ErrorDetails::NodeVerificationError { .. } => ExitCode::FileSystemError,
             ErrorDetails::NodeVersionNotFound { .. } => ExitCode::NoVersionMatch,
             ErrorDetails::NoGlobalInstalls { .. } => ExitCode::InvalidArguments,
             ErrorDetails::NoHomeEnvironmentVar => ExitCode::EnvironmentError,
             ErrorDetails::NoInstallDir => ExitCode::EnvironmentError,
             ErrorDetails::NoLocalDataDir => ExitCode::EnvironmentError,
+            ErrorDetails::NoMetricsEndpoint => ExitCode::ConfigurationError,
             ErrorDetails::NoPackageExecutables { .. } => ExitCode::InvalidArguments,
-            ErrorDetails::NoPinnedNodeVersion => ExitCode::ConfigurationError,
+            ErrorDetails::NoPackagePlatformBuild { .. } => ExitCode::EnvironmentError,
+            ErrorDetails::NoPinnedNodeVersion { .. } => ExitCode::ConfigurationError,
             ErrorDetails::NoPlatform => ExitCode::ConfigurationError,
+            ErrorDetails::NoProjectNode => ExitCode::ConfigurationError,
             ErrorDetails::NoProjectYarn => ExitCode::ConfigurationError,
+            ErrorDetails::NoProjectPnpm => ExitCode::ConfigurationError,
             ErrorDetails::NoShellProfile { .. } => ExitCode::EnvironmentError,
+            ErrorDetails::NothingToExport => ExitCode::ConfigurationError,
+            ErrorDetails::NothingToGenerate => ExitCode::ConfigurationError,
+            ErrorDetails::NothingToLock => ExitCode::ConfigurationError,
+            ErrorDetails::NothingToSetUp => ExitCode::ConfigurationError,
             ErrorDetails::NotInPackage => ExitCode::ConfigurationError,
+            ErrorDetails::NotInWorkspace => ExitCode::ConfigurationError,
             ErrorDetails::NoDefaultYarn => ExitCode::ConfigurationError,
+            ErrorDetails::NoDefaultPnpm => ExitCode::ConfigurationError,
             ErrorDetails::NoVersionsFound => ExitCode::NoVersionMatch,
             ErrorDetails::NpmPackFetchError { .. } => ExitCode::NetworkError,
             ErrorDetails::NpmPackUnpackError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::NpmVersionNotFound { .. } => ExitCode::NoVersionMatch,
             ErrorDetails::NpmViewMetadataFetchError { .. } => ExitCode::NetworkError,
             ErrorDetails::NpmViewMetadataParseError { .. } => ExitCode::UnknownError,
             ErrorDetails::NpxNotAvailable { .. } => ExitCode::ExecutableNotFound,
+            ErrorDetails::OfflineModeError { .. } => ExitCode::NetworkError,
             ErrorDetails::PackageInstallFailed => ExitCode::FileSystemError,
             ErrorDetails::PackageMetadataFetchError { .. } => ExitCode::NetworkError,
             ErrorDetails::PackageNotFound { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::PackageNotInstalled { .. } => ExitCode::InvalidArguments,
             ErrorDetails::PackageParseError { .. } => ExitCode::ConfigurationError,
             ErrorDetails::PackageReadError { .. } => ExitCode::FileSystemError,
             ErrorDetails::PackageUnpackError => ExitCode::ConfigurationError,
@@ -1424,33 +2480,59 @@ impl VoltaFail for ErrorDetails {
             ErrorDetails::PackageWriteError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ParseBinConfigError => ExitCode::UnknownError,
             ErrorDetails::ParseHooksError { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::ParseHookVariableError { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::ParseLockfileError { .. } => ExitCode::ConfigurationError,
             ErrorDetails::ParseToolSpecError { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::PermissionDenied { .. } => ExitCode::FileSystemError,
             ErrorDetails::ParseNodeIndexCacheError => ExitCode::UnknownError,
             ErrorDetails::ParseNodeIndexError { .. } => ExitCode::NetworkError,
             ErrorDetails::ParseNodeIndexExpiryError => ExitCode::UnknownError,
+            ErrorDetails::ParseNodeScheduleError { .. } => ExitCode::NetworkError,
             ErrorDetails::ParseNpmManifestError => ExitCode::UnknownError,
             ErrorDetails::ParsePackageConfigError => ExitCode::UnknownError,
             ErrorDetails::ParsePackageMetadataError { .. } => ExitCode::UnknownError,
             ErrorDetails::ParsePlatformError => ExitCode::ConfigurationError,
+            ErrorDetails::ParseSettingsError { .. } => ExitCode::ConfigurationError,
+            ErrorDetails::ParseStateError { .. } => ExitCode::ConfigurationError,
             ErrorDetails::PersistInventoryError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::PnpmVersionNotFound { .. } => ExitCode::NoVersionMatch,
             ErrorDetails::ProjectLocalBinaryExecError { .. } => ExitCode::ExecutionFailure,
             ErrorDetails::ProjectLocalBinaryNotFound { .. } => ExitCode::FileSystemError,
+            ErrorDetails::PromptError => ExitCode::EnvironmentError,
             ErrorDetails::PublishHookBothUrlAndBin => ExitCode::ConfigurationError,
             ErrorDetails::PublishHookNeitherUrlNorBin => ExitCode::ConfigurationError,
             ErrorDetails::ReadBinConfigDirError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadBinConfigError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadDefaultNpmError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadDirError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadFileError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadHooksError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadNodeIndexCacheError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadNodeIndexExpiryError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadNpmManifestError => ExitCode::UnknownError,
+            ErrorDetails::ReadNpmrcError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadOnlyModeError { .. } => ExitCode::ReadOnlyError,
             ErrorDetails::ReadPackageConfigError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ReadPlatformError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadSettingsError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ReadStateError { .. } => ExitCode::FileSystemError,
             #[cfg(windows)]
             ErrorDetails::ReadUserPathError => ExitCode::EnvironmentError,
+            ErrorDetails::RecursiveShim { .. } => ExitCode::EnvironmentError,
             ErrorDetails::RegistryFetchError { .. } => ExitCode::NetworkError,
+            ErrorDetails::RelocateDestinationError { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::RelocateError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::RelocateIntoHomeError { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::RepairLinkedPackageError { .. } => ExitCode::InvalidArguments,
+            ErrorDetails::RepairMoveAsideError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::RunCommandError { .. } => ExitCode::ExecutionFailure,
             ErrorDetails::RunShimDirectly => ExitCode::InvalidArguments,
+            ErrorDetails::SelfUpdateAssetError { .. } => ExitCode::NoVersionMatch,
+            ErrorDetails::SelfUpdateChecksumError { .. } => ExitCode::NetworkError,
+            ErrorDetails::SelfUpdateDownloadError { .. } => ExitCode::NetworkError,
+            ErrorDetails::SelfUpdateMissingFileError { .. } => ExitCode::UnknownError,
+            ErrorDetails::SelfUpdateReleaseError { .. } => ExitCode::NetworkError,
+            ErrorDetails::SelfUpdateReplaceError { .. } => ExitCode::FileSystemError,
             ErrorDetails::SetupToolImageError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ShimCreateError { .. } => ExitCode::FileSystemError,
             ErrorDetails::ShimRemoveError { .. } => ExitCode::FileSystemError,
@@ -1458,16 +2540,22 @@ impl VoltaFail for ErrorDetails {
             ErrorDetails::StringifyPackageConfigError => ExitCode::UnknownError,
             ErrorDetails::StringifyPlatformError => ExitCode::UnknownError,
             ErrorDetails::StringifyToolchainError => ExitCode::UnknownError,
+            ErrorDetails::SymlinkError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::ToolchainNotInstalled { .. } => ExitCode::ConfigurationError,
             ErrorDetails::Unimplemented { .. } => ExitCode::UnknownError,
             ErrorDetails::UnpackArchiveError { .. } => ExitCode::UnknownError,
             ErrorDetails::UnrecognizedShell { .. } => ExitCode::EnvironmentError,
             ErrorDetails::UnspecifiedPostscript => ExitCode::EnvironmentError,
             ErrorDetails::UnspecifiedShell => ExitCode::EnvironmentError,
+            ErrorDetails::VersionManagerNotFound { .. } => ExitCode::ConfigurationError,
             ErrorDetails::VersionParseError { .. } => ExitCode::NoVersionMatch,
             ErrorDetails::WriteBinConfigError { .. } => ExitCode::FileSystemError,
             ErrorDetails::WriteDefaultNpmError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WriteFileError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WriteLocalToolchainError { .. } => ExitCode::FileSystemError,
             ErrorDetails::WriteNodeIndexCacheError { .. } => ExitCode::FileSystemError,
             ErrorDetails::WriteNodeIndexExpiryError { .. } => ExitCode::FileSystemError,
+            ErrorDetails::WriteNpmrcError { .. } => ExitCode::FileSystemError,
             ErrorDetails::WritePackageConfigError { .. } => ExitCode::FileSystemError,
             ErrorDetails::WritePackageShasumError { .. } => ExitCode::FileSystemError,
             ErrorDetails::WritePlatformError { .. } => ExitCode::FileSystemError,
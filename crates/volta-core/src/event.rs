@@ -3,15 +3,30 @@
 use os_info;
 
 use std::env;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use serde::Serialize;
 
 use crate::hook::Publish;
+use crate::metrics::{self, Metrics};
 use crate::monitor::Monitor;
 use crate::session::ActivityKind;
+use lazy_static::lazy_static;
+use log::debug;
 use volta_fail::{ExitCode, VoltaError};
 
+/// How long publishing the events may take, since it happens when every command and shim exits
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    /// The client for publishing events, which gives up after `PUBLISH_TIMEOUT`, rather than
+    /// the shared client, which waits as long as a download takes
+    static ref PUBLISH_CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(PUBLISH_TIMEOUT)
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new());
+}
+
 // the Event data that is serialized to JSON and sent the plugin
 #[derive(Serialize)]
 pub struct Event {
@@ -44,6 +59,19 @@ enum EventKind {
     ToolEnd {
         exit_code: i32,
     },
+    InstallStart {
+        tool: String,
+    },
+    InstallEnd {
+        tool: String,
+        exit_code: ExitCode,
+    },
+    ShimCreated {
+        shim: String,
+    },
+    ToolUse {
+        version: String,
+    },
 }
 
 impl EventKind {
@@ -109,6 +137,23 @@ impl EventLog {
     pub fn add_event_tool_end(&mut self, activity_kind: ActivityKind, exit_code: i32) {
         self.add_event(EventKind::ToolEnd { exit_code }, activity_kind)
     }
+    pub fn add_event_install_start(&mut self, activity_kind: ActivityKind, tool: String) {
+        self.add_event(EventKind::InstallStart { tool }, activity_kind)
+    }
+    pub fn add_event_install_end(
+        &mut self,
+        activity_kind: ActivityKind,
+        tool: String,
+        exit_code: ExitCode,
+    ) {
+        self.add_event(EventKind::InstallEnd { tool, exit_code }, activity_kind)
+    }
+    pub fn add_event_shim_created(&mut self, activity_kind: ActivityKind, shim: String) {
+        self.add_event(EventKind::ShimCreated { shim }, activity_kind)
+    }
+    pub fn add_event_tool_use(&mut self, activity_kind: ActivityKind, version: String) {
+        self.add_event(EventKind::ToolUse { version }, activity_kind)
+    }
     pub fn add_event_error(&mut self, activity_kind: ActivityKind, error: &VoltaError) {
         let exit_code = error.exit_code();
         self.add_event(
@@ -126,10 +171,23 @@ impl EventLog {
         self.events.push(event);
     }
 
+    /// Adds the commands started and the tool versions used in this session to the local usage
+    /// metrics
+    pub fn record_metrics(&self) {
+        let mut usage = Metrics::default();
+        for event in &self.events {
+            match event.event {
+                EventKind::Start => usage.count_command(&event.name),
+                EventKind::ToolUse { ref version } => usage.count_tool(&event.name, version),
+                _ => {}
+            }
+        }
+        metrics::record(usage);
+    }
+
     pub fn publish(&mut self, plugin: Option<&Publish>) {
         match plugin {
-            // Note: This call to unimplemented is left in, as it's not a Fallible operation that can use ErrorDetails::Unimplemented
-            Some(&Publish::Url(_)) => unimplemented!(),
+            Some(&Publish::Url(ref url)) => send_events(url, &self.events),
             Some(&Publish::Bin(ref command)) => {
                 let mut monitor = Monitor::new(command);
                 monitor.send_events(&self.events);
@@ -139,12 +197,32 @@ impl EventLog {
     }
 }
 
+/// Sends the events to the given URL as a JSON array in the body of a POST request
+///
+/// Publishing is best-effort, so a failure or a server that is slow to respond is logged rather
+/// than interrupting the user.
+fn send_events(url: &str, events: &[Event]) {
+    let result = PUBLISH_CLIENT
+        .post(url)
+        .json(events)
+        .send()
+        .and_then(|response| response.error_for_status());
+
+    if let Err(error) = result {
+        debug!("Unable to publish events to {}: {}", url, error);
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
 
-    use super::EventLog;
+    use super::{send_events, EventLog, PUBLISH_TIMEOUT};
     use crate::error::ErrorDetails;
     use crate::session::ActivityKind;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::{Duration, Instant};
     use volta_fail::ExitCode;
 
     #[test]
@@ -168,5 +246,60 @@ pub mod tests {
         event_log.add_event_error(ActivityKind::Install, &error);
         assert_eq!(event_log.events.len(), 4);
         assert_eq!(event_log.events[3].name, "install");
+
+        event_log.add_event_install_start(ActivityKind::Install, "node@12.4.0".to_string());
+        event_log.add_event_shim_created(ActivityKind::Install, "cowsay".to_string());
+        event_log.add_event_install_end(
+            ActivityKind::Install,
+            "node@12.4.0".to_string(),
+            ExitCode::Success,
+        );
+        event_log.add_event_tool_use(ActivityKind::Node, "12.4.0".to_string());
+        assert_eq!(event_log.events.len(), 8);
+        assert_eq!(event_log.events[7].name, "node");
+    }
+
+    #[test]
+    fn test_send_events() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !String::from_utf8_lossy(&request).contains("\"name\":\"pin\"") {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+
+        let mut event_log = EventLog::init();
+        event_log.add_event_start(ActivityKind::Pin);
+        send_events(&url, &event_log.events);
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /events"));
+    }
+
+    #[test]
+    fn test_send_events_gives_up_on_slow_server() {
+        // The connection is accepted by the OS, but nothing ever responds
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+
+        let mut event_log = EventLog::init();
+        event_log.add_event_start(ActivityKind::Pin);
+        let start = Instant::now();
+        send_events(&url, &event_log.events);
+
+        assert!(start.elapsed() < PUBLISH_TIMEOUT + Duration::from_secs(5));
+        drop(listener);
     }
 }
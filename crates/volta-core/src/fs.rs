@@ -1,12 +1,15 @@
 //! Provides utilities for operating on the filesystem.
 
-use std::fs::{self, create_dir_all, read_dir, DirEntry, File, Metadata};
+use std::fs::{self, create_dir_all, read_dir, DirEntry, File, Metadata, OpenOptions};
 use std::io::{self, ErrorKind};
 use std::path::Path;
 
 use crate::error::ErrorDetails;
-use crate::layout::volta_home;
-use tempfile::{tempdir_in, NamedTempFile, TempDir};
+use crate::layout::{long_path, volta_home};
+use crate::signal::remove_on_interrupt;
+use cfg_if::cfg_if;
+use log::debug;
+use tempfile::{tempdir_in, tempfile_in, NamedTempFile, TempDir};
 use volta_fail::{Fallible, ResultExt};
 
 /// Opens a file, creating it if it doesn't exist
@@ -24,11 +27,16 @@ pub fn touch(path: &Path) -> io::Result<File> {
 pub fn ensure_dir_does_not_exist<P: AsRef<Path>>(path: &P) -> Fallible<()> {
     if path.as_ref().exists() {
         // remove the directory and all of its contents
-        fs::remove_dir_all(path).with_context(delete_dir_error(path))?;
+        fs::remove_dir_all(long_path(path)).with_context(delete_dir_error(path))?;
     }
     Ok(())
 }
 
+pub fn create_dir_error<P: AsRef<Path>>(dir: &P) -> impl FnOnce(&io::Error) -> ErrorDetails {
+    let dir = dir.as_ref().to_path_buf();
+    |_| ErrorDetails::CreateDirError { dir }
+}
+
 pub fn delete_dir_error<P: AsRef<Path>>(directory: &P) -> impl FnOnce(&io::Error) -> ErrorDetails {
     let directory = directory.as_ref().to_path_buf();
     |_| ErrorDetails::DeleteDirectoryError { directory }
@@ -39,6 +47,68 @@ pub fn delete_file_error<P: AsRef<Path>>(file: &P) -> impl FnOnce(&io::Error) ->
     |_| ErrorDetails::DeleteFileError { file }
 }
 
+pub fn read_file_error<P: AsRef<Path>>(file: &P) -> impl FnOnce(&io::Error) -> ErrorDetails {
+    let file = file.as_ref().to_path_buf();
+    |_| ErrorDetails::ReadFileError { file }
+}
+
+pub fn write_file_error<P: AsRef<Path>>(file: &P) -> impl FnOnce(&io::Error) -> ErrorDetails {
+    let file = file.as_ref().to_path_buf();
+    |_| ErrorDetails::WriteFileError { file }
+}
+
+pub fn symlink_error<L, T>(link: &L, target: &T) -> impl FnOnce(&io::Error) -> ErrorDetails
+where
+    L: AsRef<Path>,
+    T: AsRef<Path>,
+{
+    let link = link.as_ref().to_path_buf();
+    let target = target.as_ref().to_path_buf();
+    |_| ErrorDetails::SymlinkError { link, target }
+}
+
+/// Checks that a file or directory can be modified, before making any changes to it.
+///
+/// A path that doesn't exist yet is checked by trying its closest existing parent directory,
+/// since that is where it will be created. Only a lack of permissions is reported, any other
+/// problem is left to be reported by the operation itself.
+pub fn check_writable(path: &Path) -> Fallible<()> {
+    let result = if path.is_file() {
+        OpenOptions::new().write(true).open(path).map(|_| ())
+    } else {
+        match path.ancestors().find(|ancestor| ancestor.is_dir()) {
+            Some(dir) => tempfile_in(dir).map(|_| ()),
+            None => Ok(()),
+        }
+    };
+
+    match result {
+        Err(error) if error.kind() == ErrorKind::PermissionDenied => {
+            Err(ErrorDetails::PermissionDenied {
+                path: path.to_path_buf(),
+            }
+            .into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks that the parts of the Volta home that commands modify can be written to: the home
+/// itself, and the inventory, image and shim directories, since those can belong to another user
+/// on their own, like when only a single tool was installed with `sudo`.
+pub fn check_home_writable() -> Fallible<()> {
+    let home = volta_home()?;
+    for dir in &[
+        home.root(),
+        home.inventory_dir(),
+        home.image_dir(),
+        home.shim_dir(),
+    ] {
+        check_writable(dir)?;
+    }
+    Ok(())
+}
+
 /// Reads a file, if it exists.
 pub fn read_file<P: AsRef<Path>>(path: P) -> io::Result<Option<String>> {
     let result: io::Result<String> = fs::read_to_string(path);
@@ -88,20 +158,174 @@ where
         .collect::<Vec<T>>())
 }
 
+/// Recursively copies the contents of the `src` directory into `dest`, creating it if necessary
+pub fn copy_dir_all(src: &Path, dest: &Path) -> io::Result<()> {
+    let (src, dest) = (long_path(src), long_path(dest));
+    create_dir_all(&dest)?;
+    for (entry, metadata) in read_dir_eager(&src)? {
+        let target = dest.join(entry.file_name());
+        if metadata.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves the `src` directory to `dest`, which must not exist yet
+///
+/// A directory can only be renamed within a volume, so when `dest` is on another one (for example
+/// a project-local toolchain), `src` is copied into a staging directory next to `dest` and that
+/// is renamed instead, so that `dest` never holds a partial copy. Any other failure to rename is
+/// returned as is.
+pub fn move_dir(src: &Path, dest: &Path) -> io::Result<()> {
+    match fs::rename(long_path(src), long_path(dest)) {
+        Err(ref error) if is_cross_device(error) => {
+            let parent = dest.parent().unwrap_or(dest);
+            let staging = tempdir_in(parent)?;
+            remove_on_interrupt(staging.path().to_owned());
+            let staged = staging.path().join("image");
+            copy_dir_all(src, &staged)?;
+            fs::rename(long_path(&staged), long_path(dest))
+        }
+        result => result,
+    }
+}
+
+cfg_if! {
+    if #[cfg(windows)] {
+        /// Whether a rename failed because the source and the destination are on different volumes
+        fn is_cross_device(error: &io::Error) -> bool {
+            // ERROR_NOT_SAME_DEVICE
+            error.raw_os_error() == Some(17)
+        }
+    } else {
+        /// Whether a rename failed because the source and the destination are on different volumes
+        fn is_cross_device(error: &io::Error) -> bool {
+            error.raw_os_error() == Some(libc::EXDEV)
+        }
+    }
+}
+
 /// Creates a NamedTempFile in the Volta tmp directory
 pub fn create_staging_file() -> Fallible<NamedTempFile> {
     let tmp_dir = volta_home()?.tmp_dir();
-    NamedTempFile::new_in(&tmp_dir).with_context(|_| ErrorDetails::CreateTempFileError {
-        in_dir: tmp_dir.to_owned(),
-    })
+    let file =
+        NamedTempFile::new_in(&tmp_dir).with_context(|_| ErrorDetails::CreateTempFileError {
+            in_dir: tmp_dir.to_owned(),
+        })?;
+    remove_on_interrupt(file.path().to_owned());
+    Ok(file)
 }
 
 /// Creates a staging directory in the Volta tmp directory
 pub fn create_staging_dir() -> Fallible<TempDir> {
     let tmp_root = volta_home()?.tmp_dir();
-    tempdir_in(&tmp_root).with_context(|_| ErrorDetails::CreateTempDirError {
+    let dir = tempdir_in(&tmp_root).with_context(|_| ErrorDetails::CreateTempDirError {
         in_dir: tmp_root.to_owned(),
-    })
+    })?;
+    remove_on_interrupt(dir.path().to_owned());
+    Ok(dir)
+}
+
+/// Checks that there are at least `required` bytes of space available for unpacking into `dir`,
+/// so that running out of space is reported before unpacking starts rather than partway through.
+///
+/// If the available space can't be determined, the check is skipped.
+pub fn check_available_space(dir: &Path, required: u64) -> Fallible<()> {
+    match available_space(dir) {
+        Some(available) if available < required => Err(ErrorDetails::InsufficientSpaceError {
+            dir: dir.to_path_buf(),
+            required,
+            available,
+        }
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+cfg_if! {
+    if #[cfg(windows)] {
+        /// Returns the number of bytes available to the current user on the volume containing `dir`
+        fn available_space(dir: &Path) -> Option<u64> {
+            use std::os::windows::ffi::OsStrExt;
+            use std::ptr;
+            use winapi::shared::ntdef::ULARGE_INTEGER;
+            use winapi::um::fileapi::GetDiskFreeSpaceExW;
+
+            let wide: Vec<u16> = dir.as_os_str().encode_wide().chain(Some(0)).collect();
+            let mut available: ULARGE_INTEGER = unsafe { std::mem::zeroed() };
+            let result = unsafe {
+                GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, ptr::null_mut(), ptr::null_mut())
+            };
+
+            if result == 0 {
+                None
+            } else {
+                Some(unsafe { *available.QuadPart() })
+            }
+        }
+    } else if #[cfg(unix)] {
+        /// Returns the number of bytes available to the current user on the volume containing `dir`
+        fn available_space(dir: &Path) -> Option<u64> {
+            use std::ffi::CString;
+            use std::os::unix::ffi::OsStrExt;
+
+            let path = CString::new(dir.as_os_str().as_bytes()).ok()?;
+            let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+            if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+                return None;
+            }
+
+            #[allow(clippy::useless_conversion)]
+            Some(u64::from(stat.f_bavail) * u64::from(stat.f_frsize))
+        }
+    }
+}
+
+cfg_if! {
+    if #[cfg(target_os = "macos")] {
+        /// The extended attribute that Gatekeeper uses to mark files downloaded from the internet
+        const QUARANTINE_ATTRIBUTE: &[u8] = b"com.apple.quarantine\0";
+
+        /// Removes the quarantine attribute from every file in `dir`, so that Gatekeeper doesn't
+        /// block freshly unpacked executables the first time they run.
+        ///
+        /// Volta verifies the archives it downloads itself, and files without the attribute are
+        /// simply skipped. Failures are only logged, since the image is usable either way.
+        pub fn clear_quarantine(dir: &Path) {
+            use std::ffi::CString;
+            use std::os::unix::ffi::OsStrExt;
+
+            for entry in walkdir::WalkDir::new(dir).into_iter().filter_map(|entry| entry.ok()) {
+                let path = match CString::new(entry.path().as_os_str().as_bytes()) {
+                    Ok(path) => path,
+                    Err(_) => continue,
+                };
+                let result = unsafe {
+                    libc::removexattr(
+                        path.as_ptr(),
+                        QUARANTINE_ATTRIBUTE.as_ptr() as *const libc::c_char,
+                        libc::XATTR_NOFOLLOW,
+                    )
+                };
+                if result != 0 {
+                    let error = io::Error::last_os_error();
+                    if error.raw_os_error() != Some(libc::ENOATTR) {
+                        debug!(
+                            "Could not clear the quarantine attribute of '{}': {}",
+                            entry.path().display(),
+                            error
+                        );
+                    }
+                }
+            }
+        }
+    } else {
+        /// Only macOS quarantines downloaded files, so there is nothing to clear
+        pub fn clear_quarantine(_dir: &Path) {}
+    }
 }
 
 /// Create a symlink. The `dst` path will be a symbolic link pointing to the `src` path.
@@ -116,3 +340,41 @@ where
     #[cfg(unix)]
     return std::os::unix::fs::symlink(src, dest);
 }
+
+/// Create a symlink to a directory. The `dst` path will be a symbolic link pointing to the `src`
+/// path.
+pub fn symlink_dir<S, D>(src: S, dest: D) -> io::Result<()>
+where
+    S: AsRef<Path>,
+    D: AsRef<Path>,
+{
+    #[cfg(windows)]
+    return std::os::windows::fs::symlink_dir(src, dest);
+
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(src, dest);
+}
+
+cfg_if! {
+    if #[cfg(windows)] {
+        /// On Windows, this isn't a concern as there is no concept of 'executable' permissions
+        pub fn set_executable_permissions(_bin: &Path) -> io::Result<()> {
+            Ok(())
+        }
+    } else if #[cfg(unix)] {
+        use std::os::unix::fs::PermissionsExt;
+
+        /// Ensure that a given binary has 'executable' permissions on Unix, otherwise we won't be able to call it
+        pub fn set_executable_permissions(bin: &Path) -> io::Result<()> {
+            let mut permissions = fs::metadata(bin)?.permissions();
+            let mode = permissions.mode();
+
+            if mode & 0o111 != 0o111 {
+                permissions.set_mode(mode | 0o111);
+                fs::set_permissions(bin, permissions)
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Provides the history of changes to the toolchain, an append-only log in the Volta home that
+//! records every install, uninstall, pin and change of a default version.
+//!
+//! The history is only informational, so failing to write it never fails the change that it
+//! records.
+
+use std::env;
+use std::fmt;
+use std::fs::{read_to_string, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::ErrorDetails;
+use crate::fs::{read_file_error, write_file_error};
+use crate::layout::volta_home;
+use chrono::Local;
+use fs_utils::ensure_containing_dir_exists;
+use log::debug;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use volta_fail::{Fallible, ResultExt};
+
+/// The kind of change that a history entry records
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Install,
+    Uninstall,
+    Pin,
+    Default,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Action::Install => "install",
+            Action::Uninstall => "uninstall",
+            Action::Pin => "pin",
+            Action::Default => "default",
+        };
+        f.write_str(s)
+    }
+}
+
+/// A change to the toolchain
+#[derive(Serialize, Deserialize)]
+pub struct Entry {
+    /// The local time of the change, in RFC 3339 format
+    pub timestamp: String,
+    pub user: String,
+    pub action: Action,
+    pub tool: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// The project that a version was pinned in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<PathBuf>,
+}
+
+impl Entry {
+    pub(crate) fn new(
+        action: Action,
+        tool: &str,
+        previous: Option<&Version>,
+        version: Option<&Version>,
+    ) -> Self {
+        Entry {
+            timestamp: Local::now().to_rfc3339(),
+            user: current_user(),
+            action,
+            tool: tool.to_string(),
+            previous: previous.map(|previous| previous.to_string()),
+            version: version.map(|version| version.to_string()),
+            project: None,
+        }
+    }
+
+    pub(crate) fn in_project(mut self, project_root: &Path) -> Self {
+        self.project = Some(project_root.to_path_buf());
+        self
+    }
+
+    /// Appends the entry to the history file, logging any failure instead of returning it
+    pub(crate) fn record(self) {
+        if let Err(error) = self.append() {
+            debug!("Could not record the change in the history: {}", error);
+        }
+    }
+
+    fn append(&self) -> Fallible<()> {
+        let file = volta_home()?.history_file();
+        ensure_containing_dir_exists(&file)
+            .with_context(|_| ErrorDetails::ContainingDirError { path: file.clone() })?;
+
+        let mut line = serde_json::to_string(self).expect("an entry can always be serialized");
+        line.push('\n');
+        // Each entry is written at once with a single append, so that entries written by
+        // concurrent processes don't interleave
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&file)
+            .and_then(|mut history| history.write_all(line.as_bytes()))
+            .with_context(write_file_error(&file))
+    }
+}
+
+/// Reads the history, oldest entry first
+pub fn read() -> Fallible<Vec<Entry>> {
+    let file = volta_home()?.history_file();
+    if !file.is_file() {
+        return Ok(Vec::new());
+    }
+
+    let contents = read_to_string(&file).with_context(read_file_error(&file))?;
+    Ok(parse(&contents))
+}
+
+/// Parses the entries of the history, skipping any line that can't be read, such as a line
+/// truncated by a crash
+fn parse(contents: &str) -> Vec<Entry> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// The user making the change, which is the user that invoked `sudo` when run through it
+fn current_user() -> String {
+    ["SUDO_USER", "USER", "USERNAME", "LOGNAME"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|user| !user.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let contents = r#"{"timestamp":"2020-01-02T03:04:05+00:00","user":"alice","action":"default","tool":"node","previous":"10.16.3","version":"12.13.0"}
+{"timestamp":"2020-01-02T03:04:06+00:00","user":"alice","action":"pin","tool":"yarn","version":"1.19.1","project":"/home/alice/app"}
+{"timestamp":"2020-01-02T03:04:07+00:00","user":"alice","act
+"#;
+        let entries = parse(contents);
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].action == Action::Default);
+        assert_eq!(entries[0].previous.as_deref(), Some("10.16.3"));
+        assert_eq!(
+            entries[1].project.as_deref(),
+            Some(Path::new("/home/alice/app"))
+        );
+    }
+}
@@ -1,14 +1,16 @@
 //! Provides types for working with Volta hooks.
 
+use std::collections::HashMap;
 use std::env;
 use std::fs::File;
 use std::marker::PhantomData;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use crate::env_vars;
 use crate::error::ErrorDetails;
 use crate::layout::volta_home;
-use crate::project::Project;
-use crate::tool::{Node, Package, Tool, Yarn};
+use crate::project::{current_project_root, Project};
+use crate::tool::{Node, Npm, Package, Tool, Yarn};
 use lazycell::LazyCell;
 use log::debug;
 use volta_fail::{Fallible, ResultExt};
@@ -48,9 +50,11 @@ impl LazyHookConfig {
 /// Volta hook configuration
 pub struct HookConfig {
     node: Option<ToolHooks<Node>>,
+    npm: Option<ToolHooks<Npm>>,
     yarn: Option<ToolHooks<Yarn>>,
     package: Option<ToolHooks<Package>>,
     events: Option<EventHooks>,
+    tools: HashMap<String, CustomToolHooks>,
 }
 
 /// Volta hooks for an individual tool
@@ -61,6 +65,8 @@ pub struct ToolHooks<T: Tool> {
     pub latest: Option<tool::MetadataHook>,
     /// The hook for resolving the Tool Index URL
     pub index: Option<tool::MetadataHook>,
+    /// The format of the index returned by the `index` hook, if it isn't the tool's usual format
+    pub index_format: Option<tool::IndexFormat>,
 
     phantom: PhantomData<T>,
 }
@@ -68,15 +74,40 @@ pub struct ToolHooks<T: Tool> {
 impl<T: Tool> ToolHooks<T> {
     /// Creates a merged struct, with "right" having precedence over "left".
     fn merge(left: Self, right: Self) -> Self {
+        // The format belongs to the index hook it was declared with
+        let (index, index_format) = match right.index {
+            Some(index) => (Some(index), right.index_format),
+            None => (left.index, left.index_format),
+        };
+
         Self {
             distro: right.distro.or(left.distro),
             latest: right.latest.or(left.latest),
-            index: right.index.or(left.index),
+            index,
+            index_format,
             phantom: PhantomData,
         }
     }
 }
 
+/// The definition of a tool that isn't built into Volta, such as `deno` or `bun`, which is
+/// fetched, installed and pinned like the built-in tools
+#[derive(Clone, Debug)]
+pub struct CustomToolHooks {
+    /// The hook for resolving the URL of the archive for a version
+    pub distro: tool::DistroHook,
+    /// The hook for resolving the URL for the latest version, if any
+    pub latest: Option<tool::MetadataHook>,
+    /// The hook for resolving the URL of the version index, if any
+    pub index: Option<tool::MetadataHook>,
+    /// The format of the index returned by the `index` hook
+    pub index_format: tool::IndexFormat,
+    /// The executables provided by the tool, which each get a shim
+    pub bins: Vec<String>,
+    /// The directory in the archive that contains the executables
+    pub bin_dir: PathBuf,
+}
+
 macro_rules! merge_hook_config_field {
     ($left:ident, $right:ident, $field:ident, $type:ident) => {
         match ($left.$field, $right.$field) {
@@ -93,6 +124,10 @@ impl HookConfig {
         self.node.as_ref()
     }
 
+    pub fn npm(&self) -> Option<&ToolHooks<Npm>> {
+        self.npm.as_ref()
+    }
+
     pub fn yarn(&self) -> Option<&ToolHooks<Yarn>> {
         self.yarn.as_ref()
     }
@@ -105,34 +140,71 @@ impl HookConfig {
         self.events.as_ref()
     }
 
-    /// Returns the current hooks, which are a merge between the user hooks and
-    /// the project hooks (if any).
+    /// Returns the definition of the custom tool with the given name, if any.
+    pub fn custom_tool(&self, name: &str) -> Option<&CustomToolHooks> {
+        self.tools.get(name)
+    }
+
+    /// Returns the name of the custom tool that provides the given executable, if any.
+    pub fn custom_tool_for_bin(&self, bin: &str) -> Option<&str> {
+        self.tools
+            .iter()
+            .find(|(_, hooks)| hooks.bins.iter().any(|b| b == bin))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the current hooks, which are a merge between the user hooks, the project hooks
+    /// (if any) and the hooks overridden by environment variables (if any).
     fn current() -> Fallible<Self> {
-        let maybe_project_config = Self::for_current_dir()?;
         let maybe_default_config = Self::for_default()?;
-
-        Ok(match (maybe_project_config, maybe_default_config) {
-            (Some(project_config), Some(default_config)) => {
-                debug!("Merging default and project hooks");
-                Self::merge(default_config, project_config)
+        let maybe_project_config = Self::for_current_dir()?;
+        let maybe_env_config = Self::for_env(env_vars::string)?;
+
+        let merged = maybe_default_config
+            .into_iter()
+            .chain(maybe_project_config)
+            .chain(maybe_env_config)
+            .fold(None, |merged, config| match merged {
+                Some(merged) => Some(Self::merge(merged, config)),
+                None => Some(config),
+            });
+
+        Ok(merged.unwrap_or_else(|| {
+            debug!("No custom hooks found");
+            Self {
+                node: None,
+                npm: None,
+                yarn: None,
+                package: None,
+                events: None,
+                tools: HashMap::new(),
             }
-            (Some(project_config), None) => project_config,
-            (None, Some(default_config)) => default_config,
-            (None, None) => {
-                debug!("No custom hooks found");
-                Self {
-                    node: None,
-                    yarn: None,
-                    package: None,
-                    events: None,
-                }
+        }))
+    }
+
+    /// Returns the hooks overridden by `VOLTA_HOOK_*` variables, which are looked up with
+    /// `lookup`.  Relative paths to `bin` hooks are resolved from the current directory.
+    fn for_env<F>(lookup: F) -> Fallible<Option<Self>>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        match serial::RawHookConfig::from_env_with(lookup)? {
+            Some(raw) => {
+                debug!("Found hooks in environment variables");
+                let current_dir =
+                    env::current_dir().with_context(|_| ErrorDetails::CurrentDirError)?;
+                raw.into_hook_config(&current_dir).map(Some)
             }
-        })
+            None => Ok(None),
+        }
     }
 
     /// Returns the per-project hooks for the current directory.
     fn for_current_dir() -> Fallible<Option<Self>> {
-        Self::for_dir(&env::current_dir().with_context(|_| ErrorDetails::CurrentDirError)?)
+        match current_project_root()? {
+            Some(root) => Self::for_dir(&root),
+            None => Ok(None),
+        }
     }
 
     /// Returns the per-project hooks for the specified directory.  If the
@@ -187,11 +259,17 @@ impl HookConfig {
 
     /// Creates a merged struct, with "right" having precedence over "left".
     fn merge(left: Self, right: Self) -> Self {
+        // Custom tools are defined as a whole, so a definition replaces any earlier one
+        let mut tools = left.tools;
+        tools.extend(right.tools);
+
         Self {
             node: merge_hook_config_field!(left, right, node, ToolHooks),
+            npm: merge_hook_config_field!(left, right, npm, ToolHooks),
             yarn: merge_hook_config_field!(left, right, yarn, ToolHooks),
             package: merge_hook_config_field!(left, right, package, ToolHooks),
             events: merge_hook_config_field!(left, right, events, EventHooks),
+            tools,
         }
     }
 }
@@ -214,7 +292,7 @@ impl EventHooks {
 #[cfg(test)]
 pub mod tests {
 
-    use super::{tool, HookConfig, Publish};
+    use super::{serial, tool, HookConfig, Publish};
     use std::path::PathBuf;
 
     fn fixture_path(fixture_dir: &str) -> PathBuf {
@@ -286,6 +364,8 @@ pub mod tests {
                 base_path: fixture_dir.clone(),
             })
         );
+        assert_eq!(node.index_format, None);
+        assert_eq!(yarn.index_format, Some(tool::IndexFormat::Npm));
         assert_eq!(
             hooks.events.unwrap().publish,
             Some(Publish::Bin("/events/bin".to_string()))
@@ -298,6 +378,7 @@ pub mod tests {
         let prefix_file = fixture_dir.join("prefixes.json");
         let hooks = HookConfig::from_file(&prefix_file).unwrap().unwrap();
         let node = hooks.node.unwrap();
+        let npm = hooks.npm.unwrap();
         let yarn = hooks.yarn.unwrap();
 
         assert_eq!(
@@ -318,6 +399,24 @@ pub mod tests {
                 "http://localhost/node/index/".to_string()
             ))
         );
+        assert_eq!(
+            npm.distro,
+            Some(tool::DistroHook::Prefix(
+                "http://localhost/npm/distro/".to_string()
+            ))
+        );
+        assert_eq!(
+            npm.latest,
+            Some(tool::MetadataHook::Prefix(
+                "http://localhost/npm/latest/".to_string()
+            ))
+        );
+        assert_eq!(
+            npm.index,
+            Some(tool::MetadataHook::Prefix(
+                "http://localhost/npm/index/".to_string()
+            ))
+        );
         assert_eq!(
             yarn.distro,
             Some(tool::DistroHook::Prefix(
@@ -383,6 +482,89 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_from_str_custom_tools() {
+        let fixture_dir = fixture_path("hooks");
+        let tools_file = fixture_dir.join("tools.json");
+        let hooks = HookConfig::from_file(&tools_file).unwrap().unwrap();
+        let deno = hooks.custom_tool("deno").unwrap();
+        let bun = hooks.custom_tool("bun").unwrap();
+
+        assert_eq!(
+            deno.distro,
+            tool::DistroHook::Template(
+                "http://localhost/deno/v{{version}}/deno-{{os}}-{{arch}}.zip".to_string()
+            )
+        );
+        assert_eq!(deno.index_format, tool::IndexFormat::Github);
+        assert_eq!(deno.bins, vec!["deno".to_string()]);
+        assert_eq!(deno.bin_dir, PathBuf::new());
+
+        assert_eq!(
+            bun.distro,
+            tool::DistroHook::Prefix("http://localhost/bun/distro/".to_string())
+        );
+        assert_eq!(bun.latest, None);
+        assert_eq!(bun.index_format, tool::IndexFormat::Npm);
+        assert_eq!(bun.bin_dir, PathBuf::from("bun-dist"));
+
+        assert_eq!(hooks.custom_tool_for_bin("bunx"), Some("bun"));
+        assert_eq!(hooks.custom_tool_for_bin("node"), None);
+    }
+
+    #[test]
+    fn test_custom_tools_reject_unsafe_names() {
+        let invalid = [
+            r#"{ "tools": { "../deno": { "distro": { "prefix": "http://localhost/" } } } }"#,
+            r#"{ "tools": { "deno": { "distro": { "prefix": "http://localhost/" }, "bins": ["../deno"] } } }"#,
+            r#"{ "tools": { "deno": { "distro": { "prefix": "http://localhost/" }, "bins": ["bin\\deno"] } } }"#,
+            r#"{ "tools": { "deno": { "distro": { "prefix": "http://localhost/" }, "bins": ["node"] } } }"#,
+            r#"{ "tools": { "deno": { "distro": { "prefix": "http://localhost/" }, "binDir": "../.." } } }"#,
+            r#"{ "tools": { "deno": { "distro": { "prefix": "http://localhost/" }, "binDir": "/usr/bin" } } }"#,
+        ];
+
+        for hooks in invalid.iter() {
+            let raw: serial::RawHookConfig = serde_json::from_str(hooks).unwrap();
+            assert!(
+                raw.into_hook_config(&fixture_path("hooks")).is_err(),
+                "accepted {}",
+                hooks
+            );
+        }
+
+        let valid = r#"{ "tools": { "deno": { "distro": { "prefix": "http://localhost/" }, "bins": ["deno"], "binDir": "./bin" } } }"#;
+        let raw: serial::RawHookConfig = serde_json::from_str(valid).unwrap();
+        assert!(raw.into_hook_config(&fixture_path("hooks")).is_ok());
+    }
+
+    #[test]
+    fn test_format_only_allowed_on_supported_index_hooks() {
+        let invalid = [
+            r#"{ "node": { "index": { "prefix": "http://localhost/", "format": "npm" } } }"#,
+            r#"{ "packages": { "index": { "prefix": "http://localhost/", "format": "npm" } } }"#,
+            r#"{ "yarn": { "distro": { "prefix": "http://localhost/", "format": "npm" } } }"#,
+            r#"{ "npm": { "latest": { "prefix": "http://localhost/", "format": "github" } } }"#,
+        ];
+
+        for hooks in invalid.iter() {
+            let raw: serial::RawHookConfig = serde_json::from_str(hooks).unwrap();
+            assert!(
+                raw.into_hook_config(&fixture_path("hooks")).is_err(),
+                "accepted {}",
+                hooks
+            );
+        }
+
+        let valid =
+            r#"{ "npm": { "index": { "prefix": "http://localhost/", "format": "github" } } }"#;
+        let raw: serial::RawHookConfig = serde_json::from_str(valid).unwrap();
+        let hooks = raw.into_hook_config(&fixture_path("hooks")).unwrap();
+        assert_eq!(
+            hooks.npm.unwrap().index_format,
+            Some(tool::IndexFormat::Github)
+        );
+    }
+
     #[test]
     fn test_for_dir() {
         let project_dir = fixture_path("hooks/project");
@@ -478,4 +660,27 @@ pub mod tests {
             Some(Publish::Bin("/events/bin".to_string()))
         );
     }
+
+    #[test]
+    fn test_for_env() {
+        let env_hooks = HookConfig::for_env(|name| match name {
+            "VOLTA_HOOK_YARN_INDEX" => {
+                Some(r#"{ "prefix": "http://localhost/yarn/index/", "format": "npm" }"#.to_string())
+            }
+            _ => None,
+        })
+        .expect("Could not parse hook variables")
+        .expect("Could not find hook variables");
+        let yarn = env_hooks.yarn.expect("No yarn config found");
+
+        assert_eq!(
+            yarn.index,
+            Some(tool::MetadataHook::Prefix(
+                "http://localhost/yarn/index/".to_string()
+            ))
+        );
+        assert_eq!(yarn.index_format, Some(tool::IndexFormat::Npm));
+        assert_eq!(yarn.distro, None);
+        assert!(env_hooks.node.is_none());
+    }
 }
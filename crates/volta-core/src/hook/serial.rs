@@ -1,18 +1,42 @@
+use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 use std::marker::PhantomData;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 
 use super::tool;
+use crate::env_vars::{self, hook_var};
 use crate::error::ErrorDetails;
-use crate::tool::{Node, Package, Tool, Yarn};
+use crate::tool::{Node, Npm, Package, Tool, Yarn};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use volta_fail::{Fallible, VoltaError};
+use volta_fail::{throw, Fallible, ResultExt, VoltaError};
+
+/// The tools that Volta manages itself, which can't be redefined as custom tools
+const BUILTIN_TOOLS: [&str; 6] = ["node", "npm", "npx", "yarn", "pnpm", "volta"];
 
 #[derive(Serialize, Deserialize)]
 pub struct RawResolveHook {
     prefix: Option<String>,
     template: Option<String>,
     bin: Option<String>,
+    /// The format of the index, only meaningful for `index` hooks
+    format: Option<RawIndexFormat>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RawIndexFormat {
+    Github,
+    Npm,
+}
+
+impl From<RawIndexFormat> for tool::IndexFormat {
+    fn from(raw: RawIndexFormat) -> Self {
+        match raw {
+            RawIndexFormat::Github => tool::IndexFormat::Github,
+            RawIndexFormat::Npm => tool::IndexFormat::Npm,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -33,27 +57,42 @@ impl RawResolveHook {
                 prefix: Some(prefix),
                 template: None,
                 bin: None,
+                ..
             } => Ok(to_prefix(prefix)),
             RawResolveHook {
                 prefix: None,
                 template: Some(template),
                 bin: None,
+                ..
             } => Ok(to_template(template)),
             RawResolveHook {
                 prefix: None,
                 template: None,
                 bin: Some(bin),
+                ..
             } => Ok(to_bin(bin)),
             RawResolveHook {
                 prefix: None,
                 template: None,
                 bin: None,
+                ..
             } => Err(ErrorDetails::HookNoFieldsSpecified.into()),
             _ => Err(ErrorDetails::HookMultipleFieldsSpecified.into()),
         }
     }
 
+    /// Fails if the hook declares a format, which only index hooks can
+    fn reject_format(&self, hook: &str) -> Fallible<()> {
+        if self.format.is_some() {
+            throw!(ErrorDetails::HookFormatNotSupported {
+                hook: hook.to_string()
+            });
+        }
+        Ok(())
+    }
+
     pub fn into_distro_hook(self, base_dir: &Path) -> Fallible<tool::DistroHook> {
+        self.reject_format("distro")?;
         self.into_hook(
             tool::DistroHook::Prefix,
             tool::DistroHook::Template,
@@ -65,6 +104,7 @@ impl RawResolveHook {
     }
 
     pub fn into_metadata_hook(self, base_dir: &Path) -> Fallible<tool::MetadataHook> {
+        self.reject_format("latest")?;
         self.into_hook(
             tool::MetadataHook::Prefix,
             tool::MetadataHook::Template,
@@ -74,6 +114,15 @@ impl RawResolveHook {
             },
         )
     }
+
+    /// Converts an index hook, along with the format of the index that it declares
+    pub fn into_index_hook(
+        mut self,
+        base_dir: &Path,
+    ) -> Fallible<(tool::MetadataHook, Option<tool::IndexFormat>)> {
+        let format = self.format.take().map(tool::IndexFormat::from);
+        Ok((self.into_metadata_hook(base_dir)?, format))
+    }
 }
 
 impl TryFrom<RawPublishHook> for super::Publish {
@@ -101,9 +150,11 @@ impl TryFrom<RawPublishHook> for super::Publish {
 #[derive(Serialize, Deserialize)]
 pub struct RawHookConfig {
     pub node: Option<RawToolHooks<Node>>,
+    pub npm: Option<RawToolHooks<Npm>>,
     pub yarn: Option<RawToolHooks<Yarn>>,
     pub packages: Option<RawToolHooks<Package>>,
     pub events: Option<RawEventHooks>,
+    pub tools: Option<HashMap<String, RawCustomToolHooks>>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -133,25 +184,189 @@ pub struct RawToolHooks<T: Tool> {
     phantom: PhantomData<T>,
 }
 
+/// The definition of a custom tool, keyed by the tool's name in the `tools` section
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawCustomToolHooks {
+    pub distro: Option<RawResolveHook>,
+    pub latest: Option<RawResolveHook>,
+    pub index: Option<RawResolveHook>,
+    /// The executables provided by the tool, defaulting to one named after the tool
+    pub bins: Option<Vec<String>>,
+    /// The directory in the archive containing the executables, defaulting to its root
+    pub bin_dir: Option<String>,
+}
+
+impl RawCustomToolHooks {
+    fn into_custom_tool_hooks(
+        self,
+        name: &str,
+        base_dir: &Path,
+    ) -> Fallible<super::CustomToolHooks> {
+        if !is_file_name(name) {
+            throw!(ErrorDetails::CustomToolInvalidName {
+                tool: name.to_string(),
+                name: name.to_string(),
+            });
+        }
+        if BUILTIN_TOOLS.contains(&name) {
+            throw!(ErrorDetails::CustomToolBuiltinName {
+                tool: name.to_string()
+            });
+        }
+
+        // The executables become shims, and are looked up in the image, so they must not be
+        // able to point anywhere else
+        let bins = self.bins.unwrap_or_else(|| vec![name.to_string()]);
+        for bin in &bins {
+            if !is_file_name(bin) {
+                throw!(ErrorDetails::CustomToolInvalidName {
+                    tool: name.to_string(),
+                    name: bin.clone(),
+                });
+            }
+            if BUILTIN_TOOLS.contains(&bin.as_str()) {
+                throw!(ErrorDetails::CustomToolBinConflict {
+                    tool: name.to_string(),
+                    bin: bin.clone(),
+                    owner: "Volta".to_string(),
+                });
+            }
+        }
+
+        let bin_dir = self.bin_dir.map(PathBuf::from).unwrap_or_default();
+        let inside_archive = bin_dir.components().all(|component| match component {
+            Component::Normal(_) | Component::CurDir => true,
+            _ => false,
+        });
+        if !inside_archive {
+            throw!(ErrorDetails::CustomToolInvalidBinDir {
+                tool: name.to_string(),
+                bin_dir: bin_dir.to_string_lossy().to_string(),
+            });
+        }
+
+        let distro = match self.distro {
+            Some(distro) => distro.into_distro_hook(base_dir)?,
+            None => throw!(ErrorDetails::CustomToolNoDistro {
+                tool: name.to_string()
+            }),
+        };
+        let latest = self
+            .latest
+            .map(|l| l.into_metadata_hook(base_dir))
+            .transpose()?;
+        let (index, index_format) = self
+            .index
+            .map(|i| i.into_index_hook(base_dir))
+            .transpose()?
+            .map_or((None, None), |(index, format)| (Some(index), format));
+
+        Ok(super::CustomToolHooks {
+            distro,
+            latest,
+            index,
+            index_format: index_format.unwrap_or(tool::IndexFormat::Github),
+            bins,
+            bin_dir,
+        })
+    }
+}
+
+/// Whether a name can only refer to a file in the directory it is looked up in
+fn is_file_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(|c| c == '/' || c == '\\')
+}
+
 impl RawHookConfig {
+    /// Reads the hooks overridden by `VOLTA_HOOK_*` variables, if any, looking up each variable
+    /// with `lookup`.
+    pub fn from_env_with<F>(lookup: F) -> Fallible<Option<Self>>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let node = RawToolHooks::from_env_with("NODE", &lookup)?;
+        let npm = RawToolHooks::from_env_with("NPM", &lookup)?;
+        let yarn = RawToolHooks::from_env_with("YARN", &lookup)?;
+        let packages = RawToolHooks::from_env_with("PACKAGES", &lookup)?;
+        let events = parse_hook_var(env_vars::VOLTA_HOOK_EVENTS_PUBLISH, &lookup)?.map(|publish| {
+            RawEventHooks {
+                publish: Some(publish),
+            }
+        });
+
+        if node.is_none()
+            && npm.is_none()
+            && yarn.is_none()
+            && packages.is_none()
+            && events.is_none()
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(RawHookConfig {
+            node,
+            npm,
+            yarn,
+            packages,
+            events,
+            tools: None,
+        }))
+    }
+
     pub fn into_hook_config(self, base_dir: &Path) -> Fallible<super::HookConfig> {
         let node = self.node.map(|n| n.into_tool_hooks(base_dir)).transpose()?;
+        let npm = self.npm.map(|n| n.into_tool_hooks(base_dir)).transpose()?;
         let yarn = self.yarn.map(|y| y.into_tool_hooks(base_dir)).transpose()?;
         let package = self
             .packages
             .map(|p| p.into_tool_hooks(base_dir))
             .transpose()?;
+        // The Node index and package metadata are only ever read in their own formats
+        reject_index_format(node.as_ref(), "node")?;
+        reject_index_format(package.as_ref(), "packages")?;
         let events = self.events.map(|e| e.try_into()).transpose()?;
+        let tools = self
+            .tools
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, raw)| {
+                let hooks = raw.into_custom_tool_hooks(&name, base_dir)?;
+                Ok((name, hooks))
+            })
+            .collect::<Fallible<_>>()?;
         Ok(super::HookConfig {
             node,
+            npm,
             yarn,
             package,
             events,
+            tools,
         })
     }
 }
 
 impl<T: Tool> RawToolHooks<T> {
+    fn from_env_with<F>(tool_name: &str, lookup: &F) -> Fallible<Option<Self>>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let distro = parse_hook_var(&hook_var(tool_name, "DISTRO"), lookup)?;
+        let latest = parse_hook_var(&hook_var(tool_name, "LATEST"), lookup)?;
+        let index = parse_hook_var(&hook_var(tool_name, "INDEX"), lookup)?;
+
+        if distro.is_none() && latest.is_none() && index.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(RawToolHooks {
+            distro,
+            latest,
+            index,
+            phantom: PhantomData,
+        }))
+    }
+
     pub fn into_tool_hooks(self, base_dir: &Path) -> Fallible<super::ToolHooks<T>> {
         let distro = self
             .distro
@@ -161,16 +376,43 @@ impl<T: Tool> RawToolHooks<T> {
             .latest
             .map(|d| d.into_metadata_hook(base_dir))
             .transpose()?;
-        let index = self
+        let (index, index_format) = self
             .index
-            .map(|d| d.into_metadata_hook(base_dir))
-            .transpose()?;
+            .map(|d| d.into_index_hook(base_dir))
+            .transpose()?
+            .map_or((None, None), |(index, format)| (Some(index), format));
 
         Ok(super::ToolHooks {
             distro,
             latest,
             index,
+            index_format,
             phantom: PhantomData,
         })
     }
 }
+
+/// Fails if the index hook of a tool declares a format, for tools whose index has a fixed format
+fn reject_index_format<T: Tool>(hooks: Option<&super::ToolHooks<T>>, tool: &str) -> Fallible<()> {
+    if hooks.map_or(false, |hooks| hooks.index_format.is_some()) {
+        throw!(ErrorDetails::HookFormatNotSupported {
+            hook: format!("{} index", tool)
+        });
+    }
+    Ok(())
+}
+
+/// Parses the JSON configuration of a hook from a variable, if `lookup` finds it.
+fn parse_hook_var<H, F>(name: &str, lookup: &F) -> Fallible<Option<H>>
+where
+    H: DeserializeOwned,
+    F: Fn(&str) -> Option<String>,
+{
+    lookup(name)
+        .map(|value| {
+            serde_json::from_str(&value).with_context(|_| ErrorDetails::ParseHookVariableError {
+                variable: name.to_string(),
+            })
+        })
+        .transpose()
+}
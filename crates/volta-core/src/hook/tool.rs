@@ -24,7 +24,7 @@ lazy_static! {
 }
 
 /// A hook for resolving the distro URL for a given tool version
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum DistroHook {
     Prefix(String),
     Template(String),
@@ -49,7 +49,7 @@ impl DistroHook {
 }
 
 /// A hook for resolving the URL for metadata about a tool
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum MetadataHook {
     Prefix(String),
     Template(String),
@@ -69,6 +69,15 @@ impl MetadataHook {
     }
 }
 
+/// The format of the version index that an index hook points to
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum IndexFormat {
+    /// A list of releases, as returned by the GitHub releases API
+    Github,
+    /// A package document, as returned by the npm registry
+    Npm,
+}
+
 /// Execute a shell command and return the trimmed stdout from that command
 fn execute_binary(bin: &str, base_path: &Path, extra_arg: Option<String>) -> Fallible<String> {
     let mut trimmed = bin.trim().to_string();
@@ -0,0 +1,192 @@
+//! Provides the layout of the image directory of a tool version, so that the paths inside of an
+//! image are only worked out in one place.
+//!
+//! A Node image is an unpacked Node distro, which is laid out like a global npm prefix: on Unix
+//! the executables are in `bin` and the bundled packages in `lib/node_modules`, while on Windows
+//! the executables are at the root and the packages in `node_modules` next to them. The images
+//! of npm, Yarn and pnpm are unpacked packages, which have the same layout on every platform.
+
+use std::path::{Path, PathBuf};
+
+use crate::layout::{volta_home, volta_system_home, VoltaHome};
+use semver::Version;
+use volta_fail::Fallible;
+
+/// How the files of an image are laid out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Layout {
+    /// A Node distro, which follows the layout of a global npm prefix on each platform
+    Distro,
+    /// An unpacked package, which has the same layout on every platform
+    Package,
+}
+
+/// The image directory of a tool version
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ToolImage {
+    root: PathBuf,
+    layout: Layout,
+}
+
+impl ToolImage {
+    /// The image of a Node version, along with the version of npm bundled with it
+    pub fn node(home: &VoltaHome, node: &Version, npm: &Version) -> Self {
+        ToolImage::distro(home.node_image_dir(&node.to_string(), &npm.to_string()))
+    }
+
+    /// The image of a Node version in a project-local toolchain
+    pub fn local_node(toolchain_dir: &Path, version: &Version) -> Self {
+        ToolImage::distro(toolchain_dir.join("node").join(version.to_string()))
+    }
+
+    /// The image of a version of npm other than the ones bundled with Node
+    pub fn npm(home: &VoltaHome, version: &Version) -> Self {
+        ToolImage::package(home.npm_image_dir(&version.to_string()))
+    }
+
+    /// The image of a Yarn version
+    pub fn yarn(home: &VoltaHome, version: &Version) -> Self {
+        ToolImage::package(home.yarn_image_dir(&version.to_string()))
+    }
+
+    /// The image of a pnpm version
+    pub fn pnpm(home: &VoltaHome, version: &Version) -> Self {
+        ToolImage::package(home.pnpm_image_dir(&version.to_string()))
+    }
+
+    /// A Node distro in any directory, such as one that was just unpacked
+    pub fn distro(root: PathBuf) -> Self {
+        ToolImage {
+            root,
+            layout: Layout::Distro,
+        }
+    }
+
+    fn package(root: PathBuf) -> Self {
+        ToolImage {
+            root,
+            layout: Layout::Package,
+        }
+    }
+
+    /// Returns the image from the user's Volta home if it exists there, or from the shared system
+    /// home if it only exists there, in the same way as `layered_path`
+    pub fn layered<F>(to_image: F) -> Fallible<Self>
+    where
+        F: Fn(&VoltaHome) -> Self,
+    {
+        let user_image = to_image(volta_home()?);
+        if !user_image.exists() {
+            if let Some(system_home) = volta_system_home()? {
+                let system_image = to_image(system_home);
+                if system_image.exists() {
+                    return Ok(system_image);
+                }
+            }
+        }
+
+        Ok(user_image)
+    }
+
+    /// The root directory of the image
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether the image has been unpacked
+    pub fn exists(&self) -> bool {
+        self.root.is_dir()
+    }
+
+    /// The directory holding the executables of the image
+    pub fn bin_dir(&self) -> PathBuf {
+        match self.layout {
+            Layout::Distro if cfg!(windows) => self.root.clone(),
+            _ => self.root.join("bin"),
+        }
+    }
+
+    /// The directory holding the libraries of the image
+    pub fn lib_dir(&self) -> PathBuf {
+        match self.layout {
+            Layout::Distro if cfg!(windows) => self.root.clone(),
+            _ => self.root.join("lib"),
+        }
+    }
+
+    /// The directory holding the packages of the image, which for a Node distro are the packages
+    /// bundled with it, such as npm
+    pub fn modules_dir(&self) -> PathBuf {
+        match self.layout {
+            Layout::Distro => self.lib_dir().join("node_modules"),
+            Layout::Package => self.root.join("node_modules"),
+        }
+    }
+
+    /// The directory that npm uses as its prefix when it is run from the image
+    ///
+    /// npm finds the `bin` and `lib` directories of a prefix on its own, so this is the root of
+    /// the image on every platform.
+    pub fn npm_prefix(&self) -> &Path {
+        &self.root
+    }
+
+    /// The path of the `node` executable of a Node image
+    pub fn node_executable(&self) -> PathBuf {
+        if cfg!(windows) {
+            self.bin_dir().join("node.exe")
+        } else {
+            self.bin_dir().join("node")
+        }
+    }
+
+    /// The path of the manifest of the npm bundled with a Node image
+    pub fn npm_manifest(&self) -> PathBuf {
+        self.modules_dir().join("npm").join("package.json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distro_layout() {
+        let image = ToolImage::distro(PathBuf::from("image"));
+
+        if cfg!(windows) {
+            assert_eq!(image.bin_dir(), PathBuf::from("image"));
+            assert_eq!(image.node_executable(), Path::new("image").join("node.exe"));
+            assert_eq!(
+                image.npm_manifest(),
+                Path::new("image")
+                    .join("node_modules")
+                    .join("npm")
+                    .join("package.json")
+            );
+        } else {
+            assert_eq!(image.bin_dir(), Path::new("image").join("bin"));
+            assert_eq!(
+                image.node_executable(),
+                Path::new("image").join("bin").join("node")
+            );
+            assert_eq!(
+                image.npm_manifest(),
+                Path::new("image")
+                    .join("lib")
+                    .join("node_modules")
+                    .join("npm")
+                    .join("package.json")
+            );
+        }
+        assert_eq!(image.npm_prefix(), Path::new("image"));
+    }
+
+    #[test]
+    fn test_package_layout() {
+        let image = ToolImage::package(PathBuf::from("yarn"));
+
+        assert_eq!(image.bin_dir(), Path::new("yarn").join("bin"));
+        assert_eq!(image.lib_dir(), Path::new("yarn").join("lib"));
+    }
+}
@@ -0,0 +1,303 @@
+//! Provides the signing of the default platform and settings files, which lets Volta detect
+//! changes made to them outside of Volta, such as by another user of a shared machine.
+//!
+//! Signing is off until `volta sign` creates the machine secret, a random key stored in the
+//! Volta home. On Unix, the key file is only readable by its owner. On Windows, it gets no
+//! permissions of its own and is only as private as the Volta home itself: the default one, in
+//! the local application data of the user, is kept from other users by the permissions of the
+//! user profile, but one moved elsewhere with `VOLTA_HOME` may not be.
+//!
+//! From then on, each signed file has a signature file next to it, holding an HMAC of its name and
+//! contents keyed by the secret. Volta signs the platform file again whenever it saves it, and
+//! refuses to read a signed file whose signature is missing or doesn't match, so a file edited by
+//! hand needs to be signed again with `volta sign`.
+//!
+//! Signing stays on for as long as the secret or any signature exists. A secret that can't be
+//! read or that was removed while files are still signed is an error, instead of turning the
+//! checks off, and so is a signed file that was emptied or removed.
+
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::ErrorDetails;
+use crate::fs::{delete_file_error, read_file, read_file_error, write_file_error};
+use crate::layout::{volta_home, VoltaHome};
+use fs_utils::ensure_containing_dir_exists;
+use hmac::{Hmac, Mac};
+use log::debug;
+use rand::RngCore;
+use sha2::Sha256;
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// The length in bytes of the machine secret
+const SECRET_LENGTH: usize = 32;
+
+/// Returns the files that are signed once signing is turned on
+fn signed_files(home: &VoltaHome) -> [&Path; 2] {
+    [home.default_platform_file(), home.default_settings_file()]
+}
+
+/// Returns the path of the signature of a file
+fn signature_file(file: &Path) -> PathBuf {
+    let mut name = OsString::from(file.as_os_str());
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+/// Turns on signing, creating the machine secret if needed, and signs the existing files
+///
+/// This is also how a file that was changed by hand is signed again.
+pub fn enable_signing() -> Fallible<()> {
+    let home = volta_home()?;
+    let key_file = home.integrity_key_file();
+    let contents = read_file(key_file).with_context(read_file_error(&key_file))?;
+    if contents
+        .as_ref()
+        .and_then(|key| decode_secret(key))
+        .is_none()
+    {
+        let mut secret = [0u8; SECRET_LENGTH];
+        rand::thread_rng().fill_bytes(&mut secret);
+        write_secret(key_file, &secret)?;
+    }
+
+    for file in signed_files(home).iter() {
+        if file.is_file() {
+            sign(home, file)?;
+        } else {
+            remove_if_exists(&signature_file(file))?;
+        }
+    }
+    Ok(())
+}
+
+/// Turns off signing, removing the machine secret and the signatures
+pub fn disable_signing() -> Fallible<()> {
+    let home = volta_home()?;
+    for file in signed_files(home).iter() {
+        remove_if_exists(&signature_file(file))?;
+    }
+    remove_if_exists(home.integrity_key_file())
+}
+
+fn remove_if_exists(file: &Path) -> Fallible<()> {
+    if file.exists() {
+        fs::remove_file(file).with_context(delete_file_error(&file))?;
+    }
+    Ok(())
+}
+
+/// Whether the file is one of the files that are signed
+pub(crate) fn is_signed(home: &VoltaHome, file: &Path) -> bool {
+    signed_files(home).iter().any(|signed| *signed == file)
+}
+
+/// Writes the signature of the file, if signing is turned on
+pub(crate) fn sign(home: &VoltaHome, file: &Path) -> Fallible<()> {
+    let secret = match read_secret(home)? {
+        Some(secret) => secret,
+        None => return Ok(()),
+    };
+
+    let contents = fs::read(file).with_context(read_file_error(&file))?;
+    let signature_file = signature_file(file);
+    fs::write(&signature_file, signature(&secret, file, &contents))
+        .with_context(write_file_error(&signature_file))
+}
+
+/// Checks the signature of the file, if signing is turned on
+///
+/// A missing file is only accepted if it has no signature either, since it was never signed.
+pub(crate) fn verify(home: &VoltaHome, file: &Path) -> Fallible<()> {
+    let secret = match read_secret(home)? {
+        Some(secret) => secret,
+        None => return Ok(()),
+    };
+    let signature_file = signature_file(file);
+    if !file.exists() {
+        if signature_file.exists() {
+            throw!(ErrorDetails::IntegrityError {
+                file: file.to_path_buf(),
+            });
+        }
+        return Ok(());
+    }
+
+    let contents = fs::read(file).with_context(read_file_error(&file))?;
+    let recorded = read_file(&signature_file).with_context(read_file_error(&signature_file))?;
+    if !matches(
+        &secret,
+        file,
+        &contents,
+        recorded.as_ref().map(String::as_str),
+    ) {
+        throw!(ErrorDetails::IntegrityError {
+            file: file.to_path_buf(),
+        });
+    }
+    debug!("Verified the signature of {}", file.display());
+    Ok(())
+}
+
+/// Returns the machine secret, or `None` if signing is turned off
+fn read_secret(home: &VoltaHome) -> Fallible<Option<Vec<u8>>> {
+    let key_file = home.integrity_key_file();
+    let contents = read_file(key_file).with_context(read_file_error(&key_file))?;
+    let is_enabled = contents.is_some()
+        || signed_files(home)
+            .iter()
+            .any(|file| signature_file(file).exists());
+    if !is_enabled {
+        return Ok(None);
+    }
+
+    match contents
+        .as_ref()
+        .and_then(|contents| decode_secret(contents))
+    {
+        Some(secret) => Ok(Some(secret)),
+        None => throw!(ErrorDetails::IntegrityKeyError {
+            file: key_file.to_path_buf(),
+        }),
+    }
+}
+
+fn decode_secret(contents: &str) -> Option<Vec<u8>> {
+    hex::decode(contents.trim())
+        .ok()
+        .filter(|secret| secret.len() == SECRET_LENGTH)
+}
+
+fn write_secret(key_file: &Path, secret: &[u8]) -> Fallible<()> {
+    ensure_containing_dir_exists(&key_file).with_context(|_| ErrorDetails::ContainingDirError {
+        path: key_file.to_path_buf(),
+    })?;
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(key_file)
+        .and_then(|mut file| file.write_all(hex::encode(secret).as_bytes()))
+        .with_context(write_file_error(&key_file))
+}
+
+/// Returns the signature of the file, which covers its name so that signatures can't be swapped
+fn signature(secret: &[u8], file: &Path, contents: &[u8]) -> String {
+    hex::encode(mac(secret, file, contents).result().code())
+}
+
+fn matches(secret: &[u8], file: &Path, contents: &[u8], recorded: Option<&str>) -> bool {
+    match recorded.and_then(|recorded| hex::decode(recorded.trim()).ok()) {
+        // Comparing through the MAC takes the same time however much of the signature matches
+        Some(recorded) => mac(secret, file, contents).verify(&recorded).is_ok(),
+        None => false,
+    }
+}
+
+fn mac(secret: &[u8], file: &Path, contents: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_varkey(secret).expect("HMAC accepts keys of any length");
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    mac.input(name.as_bytes());
+    mac.input(&[0]);
+    mac.input(contents);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let secret = [7u8; SECRET_LENGTH];
+        let file = Path::new("settings.json");
+        let contents = br#"{"offline": true}"#;
+        let recorded = signature(&secret, file, contents);
+
+        assert!(matches(&secret, file, contents, Some(&recorded)));
+        assert!(!matches(
+            &secret,
+            file,
+            br#"{"offline": false}"#,
+            Some(&recorded)
+        ));
+        assert!(!matches(
+            &secret,
+            Path::new("platform.json"),
+            contents,
+            Some(&recorded)
+        ));
+        assert!(!matches(
+            &[8u8; SECRET_LENGTH],
+            file,
+            contents,
+            Some(&recorded)
+        ));
+        assert!(!matches(&secret, file, contents, None));
+        assert!(!matches(&secret, file, contents, Some("not a signature")));
+    }
+
+    fn signed_home() -> (tempfile::TempDir, VoltaHome) {
+        let dir = tempfile::tempdir().unwrap();
+        let home = VoltaHome::new(dir.path().to_owned());
+        fs::create_dir_all(home.default_toolchain_dir()).unwrap();
+        fs::write(home.default_platform_file(), r#"{"node":null}"#).unwrap();
+        write_secret(home.integrity_key_file(), &[7u8; SECRET_LENGTH]).unwrap();
+        sign(&home, home.default_platform_file()).unwrap();
+        (dir, home)
+    }
+
+    #[test]
+    fn test_verify_signed_file() {
+        let (_dir, home) = signed_home();
+        let platform = home.default_platform_file();
+        assert!(verify(&home, platform).is_ok());
+
+        fs::write(platform, r#"{"node":"12.0.0"}"#).unwrap();
+        assert!(verify(&home, platform).is_err());
+
+        // Emptying or removing a signed file is a change too
+        fs::write(platform, "").unwrap();
+        assert!(verify(&home, platform).is_err());
+        fs::remove_file(platform).unwrap();
+        assert!(verify(&home, platform).is_err());
+
+        // A file that was never signed may be missing
+        assert!(verify(&home, home.default_settings_file()).is_ok());
+
+        // An empty file can be signed like any other
+        fs::write(platform, "").unwrap();
+        sign(&home, platform).unwrap();
+        assert!(verify(&home, platform).is_ok());
+    }
+
+    #[test]
+    fn test_verify_missing_or_corrupt_key() {
+        let (_dir, home) = signed_home();
+        let platform = home.default_platform_file();
+
+        fs::write(home.integrity_key_file(), "not a key").unwrap();
+        assert!(verify(&home, platform).is_err());
+        assert!(sign(&home, platform).is_err());
+
+        fs::write(home.integrity_key_file(), hex::encode([7u8; 4])).unwrap();
+        assert!(verify(&home, platform).is_err());
+
+        // Signing stays on while a signature exists
+        fs::remove_file(home.integrity_key_file()).unwrap();
+        assert!(verify(&home, platform).is_err());
+
+        fs::remove_file(signature_file(platform)).unwrap();
+        assert!(verify(&home, platform).is_ok());
+    }
+}
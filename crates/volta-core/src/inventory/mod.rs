@@ -2,7 +2,9 @@
 //! of available tool versions.
 
 mod node;
+mod npm;
 mod package;
+mod pnpm;
 mod yarn;
 
 use std::collections::BTreeSet;
@@ -10,12 +12,15 @@ use std::path::Path;
 
 use crate::error::ErrorDetails;
 use crate::fs::read_dir_eager;
+use crate::layout::{volta_home, volta_system_home};
+use crate::trace;
 use crate::version::parse_version;
 use failure::ResultExt;
 use lazycell::LazyCell;
 use regex::Regex;
 use semver::Version;
 use volta_fail::Fallible;
+use volta_layout::v2::VoltaHome;
 
 /// Lazily loaded inventory.
 pub struct LazyInventory {
@@ -44,21 +49,50 @@ impl LazyInventory {
 /// The inventory of locally available tool versions.
 pub struct Inventory {
     pub node: node::Collection,
+    pub npm: npm::Collection,
     pub yarn: yarn::Collection,
+    pub pnpm: pnpm::Collection,
     pub packages: package::Collection,
 }
 
 impl Inventory {
     /// Returns the current inventory.
     fn current() -> Fallible<Inventory> {
+        let _phase = trace::phase("inventory read");
         Ok(Inventory {
             node: node::Collection::load()?,
+            npm: npm::Collection::load()?,
             yarn: yarn::Collection::load()?,
+            pnpm: pnpm::Collection::load()?,
             packages: package::Collection::load()?,
         })
     }
 }
 
+/// Returns the set of all versions found in the inventory directory given by `to_dir`, both in the
+/// user home and in the system-wide home, if there is one.
+fn versions_in_homes<F>(to_dir: F, re: &Regex) -> Fallible<BTreeSet<Version>>
+where
+    F: Fn(&VoltaHome) -> &Path,
+{
+    // Directories for tools added after the layout was created may not exist yet
+    let user_dir = to_dir(volta_home()?);
+    let mut versions = if user_dir.is_dir() {
+        versions_matching(user_dir, re)?
+    } else {
+        BTreeSet::new()
+    };
+
+    if let Some(system_home) = volta_system_home()? {
+        let system_dir = to_dir(system_home);
+        if system_dir.is_dir() {
+            versions.extend(versions_matching(system_dir, re)?);
+        }
+    }
+
+    Ok(versions)
+}
+
 /// Reads the contents of a directory and returns the set of all versions found
 /// in the directory's listing by matching filenames against the specified regex
 /// and parsing the `version` named capture as a semantic version.
@@ -5,8 +5,8 @@ use semver::Version;
 
 use volta_fail::Fallible;
 
-use super::versions_matching;
-use crate::layout::volta_home;
+use super::versions_in_homes;
+use volta_layout::v2::VoltaHome;
 
 // Convenience for access as `node::Collection`
 pub use NodeCollection as Collection;
@@ -17,21 +17,30 @@ pub struct NodeCollection {
 
 impl NodeCollection {
     pub(crate) fn load() -> Fallible<Self> {
+        // Archives aren't kept when the `cacheArchives` setting is disabled, but the file
+        // recording the bundled npm version is always written when Node is unpacked
         let re = Regex::new(
             r"(?x)
             node
             -
-            v(?P<version>\d+\.\d+\.\d+) # Node version
+            v(?P<version>
+                \d+\.\d+\.\d+           # Node version
+                (-[0-9A-Za-z.-]+?)?     # prerelease, for builds from the prerelease channels
+            )
             -
-            (?P<os>[a-z]+)              # operating system
-            -
-            (?P<arch>[a-z0-9]+)         # architecture
-            \.(zip|tar\.gz)
+            (
+                (?P<os>[a-z]+)          # operating system
+                -
+                (?P<arch>[a-z0-9]+)     # architecture
+                \.(zip|tar\.gz)
+            |
+                npm                     # bundled npm version file
+            )
             ",
         )
         .unwrap();
 
-        let versions = versions_matching(volta_home()?.node_inventory_dir(), &re)?;
+        let versions = versions_in_homes(VoltaHome::node_inventory_dir, &re)?;
 
         Ok(NodeCollection { versions })
     }
@@ -0,0 +1,40 @@
+use std::collections::BTreeSet;
+
+use regex::Regex;
+use semver::Version;
+
+use volta_fail::Fallible;
+
+use super::versions_in_homes;
+use volta_layout::v2::VoltaHome;
+
+// Convenience for access as `npm::Collection`
+pub use NpmCollection as Collection;
+
+/// The versions of npm that were fetched from the registry, separately from the copies
+/// bundled with Node
+pub struct NpmCollection {
+    pub versions: BTreeSet<Version>,
+}
+
+impl NpmCollection {
+    pub(crate) fn load() -> Fallible<Self> {
+        let re = Regex::new(
+            r"(?x)
+            npm
+            -
+            (?P<version>
+                \d+\.\d+\.\d+           # npm version
+                (-[0-9A-Za-z.-]+?)?     # prerelease
+            )
+            \.tgz
+            $
+            ",
+        )
+        .unwrap();
+
+        let versions = versions_in_homes(VoltaHome::npm_inventory_dir, &re)?;
+
+        Ok(Collection { versions })
+    }
+}
@@ -0,0 +1,42 @@
+use std::collections::BTreeSet;
+
+use regex::Regex;
+use semver::Version;
+
+use volta_fail::Fallible;
+
+use super::versions_in_homes;
+use volta_layout::v2::VoltaHome;
+
+// Convenience for access as `pnpm::Collection`
+pub use PnpmCollection as Collection;
+
+pub struct PnpmCollection {
+    pub versions: BTreeSet<Version>,
+}
+
+impl PnpmCollection {
+    pub(crate) fn load() -> Fallible<Self> {
+        let re = Regex::new(
+            r"(?x)
+            pnpm
+            -
+            v(?P<version>
+                \d+\.\d+\.\d+           # pnpm version
+                (-[0-9A-Za-z.-]+?)?     # prerelease
+            )
+            -
+            (?P<os>[a-z]+)              # operating system
+            -
+            (?P<arch>[a-z0-9]+)         # architecture
+            (\.exe)?
+            $
+            ",
+        )
+        .unwrap();
+
+        let versions = versions_in_homes(VoltaHome::pnpm_inventory_dir, &re)?;
+
+        Ok(Collection { versions })
+    }
+}
@@ -5,8 +5,8 @@ use semver::Version;
 
 use volta_fail::Fallible;
 
-use super::versions_matching;
-use crate::layout::volta_home;
+use super::versions_in_homes;
+use volta_layout::v2::VoltaHome;
 
 // Convenience for access as `yarn::Collection`
 pub use YarnCollection as Collection;
@@ -27,7 +27,7 @@ impl YarnCollection {
         )
         .unwrap();
 
-        let versions = versions_matching(volta_home()?.yarn_inventory_dir(), &re)?;
+        let versions = versions_in_homes(VoltaHome::yarn_inventory_dir, &re)?;
 
         Ok(Collection { versions })
     }
@@ -1,13 +1,14 @@
 use std::env;
 use std::path::PathBuf;
 
+use crate::env_vars;
 use crate::error::ErrorDetails;
 use cfg_if::cfg_if;
 use double_checked_cell::DoubleCheckedCell;
 use dunce::canonicalize;
 use lazy_static::lazy_static;
 use volta_fail::{Fallible, ResultExt};
-use volta_layout::v1::{VoltaHome, VoltaInstall};
+pub use volta_layout::v2::{VoltaHome, VoltaInstall};
 
 cfg_if! {
     if #[cfg(unix)] {
@@ -22,11 +23,12 @@ cfg_if! {
 lazy_static! {
     static ref VOLTA_HOME: DoubleCheckedCell<VoltaHome> = DoubleCheckedCell::new();
     static ref VOLTA_INSTALL: DoubleCheckedCell<VoltaInstall> = DoubleCheckedCell::new();
+    static ref VOLTA_SYSTEM_HOME: DoubleCheckedCell<Option<VoltaHome>> = DoubleCheckedCell::new();
 }
 
 pub fn volta_home<'a>() -> Fallible<&'a VoltaHome> {
     VOLTA_HOME.get_or_try_init(|| {
-        let home_dir = match env::var_os("VOLTA_HOME") {
+        let home_dir = match env_vars::os_string(env_vars::VOLTA_HOME) {
             Some(home) => PathBuf::from(home),
             None => default_home_dir()?,
         };
@@ -35,9 +37,50 @@ pub fn volta_home<'a>() -> Fallible<&'a VoltaHome> {
     })
 }
 
+/// Returns the system-wide Volta home, if there is one
+///
+/// The system home has the same layout as the user home. It is shared by all users of the
+/// machine and is only ever read from, so that administrators can provision Node and Yarn once
+/// for everyone. Tools are always fetched into the user home, which takes precedence.
+pub fn volta_system_home<'a>() -> Fallible<Option<&'a VoltaHome>> {
+    VOLTA_SYSTEM_HOME
+        .get_or_try_init(|| {
+            let user_home = volta_home()?.root();
+            let system_dir = env_vars::os_string(env_vars::VOLTA_SYSTEM_HOME)
+                .map(PathBuf::from)
+                .or_else(default_system_home_dir)
+                .filter(|dir| dir.is_dir() && dir != user_home);
+
+            Ok(system_dir.map(VoltaHome::new))
+        })
+        .map(Option::as_ref)
+}
+
+/// Resolves a path in the Volta home, falling back to the system-wide home
+///
+/// If the path given by `to_path` doesn't exist in the user home but does exist in the system
+/// home, the system path is returned. Otherwise the user path is returned, whether or not it
+/// exists, since that is where it would be created.
+pub fn layered_path<F>(to_path: F) -> Fallible<PathBuf>
+where
+    F: Fn(&VoltaHome) -> PathBuf,
+{
+    let user_path = to_path(volta_home()?);
+    if !user_path.exists() {
+        if let Some(system_home) = volta_system_home()? {
+            let system_path = to_path(system_home);
+            if system_path.exists() {
+                return Ok(system_path);
+            }
+        }
+    }
+
+    Ok(user_path)
+}
+
 pub fn volta_install<'a>() -> Fallible<&'a VoltaInstall> {
     VOLTA_INSTALL.get_or_try_init(|| {
-        let install_dir = match env::var_os("VOLTA_INSTALL_DIR") {
+        let install_dir = match env_vars::os_string(env_vars::VOLTA_INSTALL_DIR) {
             Some(install) => PathBuf::from(install),
             None => default_install_dir()?,
         };
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use super::volta_home;
 use crate::error::ErrorDetails;
@@ -10,7 +10,18 @@ pub(super) fn default_home_dir() -> Fallible<PathBuf> {
     Ok(home)
 }
 
+pub(super) fn default_system_home_dir() -> Option<PathBuf> {
+    Some(PathBuf::from("/opt/volta"))
+}
+
 pub fn env_paths() -> Fallible<Vec<PathBuf>> {
     let home = volta_home()?;
     Ok(vec![home.shim_dir().to_owned()])
 }
+
+/// Returns the path to use for filesystem operations on deep directory trees
+///
+/// Unix has no limit on the length of a path, so the path is used as-is.
+pub fn long_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    path.as_ref().to_path_buf()
+}
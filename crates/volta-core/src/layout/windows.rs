@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf, Prefix};
 
 use super::{volta_home, volta_install};
 use crate::error::ErrorDetails;
@@ -10,9 +12,89 @@ pub(super) fn default_home_dir() -> Fallible<PathBuf> {
     Ok(home)
 }
 
+pub(super) fn default_system_home_dir() -> Option<PathBuf> {
+    env::var_os("ProgramFiles").map(|dir| PathBuf::from(dir).join("Volta"))
+}
+
 pub fn env_paths() -> Fallible<Vec<PathBuf>> {
     let home = volta_home()?;
     let install = volta_install()?;
 
     Ok(vec![home.shim_dir().to_owned(), install.root().to_owned()])
 }
+
+/// Returns the path to use for filesystem operations on deep directory trees
+///
+/// Windows limits paths to 260 characters unless they use the `\\?\` verbatim prefix, which
+/// the `node_modules` directories of packages regularly exceed. Absolute paths are converted to
+/// their verbatim form, including UNC paths (`\\server\share` becomes `\\?\UNC\server\share`),
+/// which is needed when the home directory is on a network share. Relative paths and paths that
+/// are already verbatim are returned unchanged.
+///
+/// Verbatim paths are only understood by the Windows file APIs, so they shouldn't be shown to
+/// users or passed to other programs, for example in `PATH`.
+pub fn long_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    let mut components = path.components();
+    let prefix = match components.next() {
+        Some(Component::Prefix(prefix)) => prefix,
+        _ => return path.to_path_buf(),
+    };
+
+    let mut verbatim = match prefix.kind() {
+        Prefix::Disk(_) => {
+            let mut verbatim = OsString::from(r"\\?\");
+            verbatim.push(prefix.as_os_str());
+            PathBuf::from(verbatim)
+        }
+        Prefix::UNC(server, share) => {
+            let mut verbatim = OsString::from(r"\\?\UNC\");
+            verbatim.push(server);
+            verbatim.push(r"\");
+            verbatim.push(share);
+            PathBuf::from(verbatim)
+        }
+        _ => return path.to_path_buf(),
+    };
+
+    // Verbatim paths aren't normalized by Windows, so the remaining components are added one at
+    // a time, which replaces any `/` separators with `\`
+    for component in components {
+        match component {
+            Component::RootDir => verbatim.push(r"\"),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                verbatim.pop();
+            }
+            other => verbatim.push(other.as_os_str()),
+        }
+    }
+    verbatim
+}
+
+#[cfg(test)]
+mod tests {
+    use super::long_path;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_long_path() {
+        assert_eq!(
+            long_path(r"C:\Users\user\AppData\Local\Volta"),
+            PathBuf::from(r"\\?\C:\Users\user\AppData\Local\Volta")
+        );
+        assert_eq!(
+            long_path("C:/Users/user/./Volta/tools/../tmp"),
+            PathBuf::from(r"\\?\C:\Users\user\Volta\tmp")
+        );
+        assert_eq!(
+            long_path(r"\\server\share\user\Volta"),
+            PathBuf::from(r"\\?\UNC\server\share\user\Volta")
+        );
+        assert_eq!(
+            long_path(r"\\?\C:\Users\user\Volta"),
+            PathBuf::from(r"\\?\C:\Users\user\Volta")
+        );
+        assert_eq!(long_path(r"Volta\tmp"), PathBuf::from(r"Volta\tmp"));
+    }
+}
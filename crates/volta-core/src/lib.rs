@@ -4,23 +4,46 @@
 // https://doc.rust-lang.org/nightly/unstable-book/language-features/doc-cfg.html
 #![cfg_attr(feature = "cross-platform-docs", feature(doc_cfg))]
 
+pub mod audit;
+pub mod check;
+mod checksum;
 mod command;
+pub mod container;
+pub mod deprecation;
+pub mod doctor;
+pub mod env_vars;
 pub mod error;
 mod event;
 pub mod fs;
+pub mod history;
 mod hook;
+pub mod image;
+pub mod integrity;
 pub mod inventory;
 pub mod layout;
+pub mod lockfile;
 pub mod log;
 pub mod manifest;
+pub mod metrics;
 pub mod monitor;
+mod notice;
 pub mod platform;
 pub mod project;
+pub mod prompt;
+pub mod relocate;
 pub mod run;
+pub mod self_update;
 pub mod session;
+pub mod settings;
 pub mod shim;
 pub mod signal;
+pub mod stats;
+mod store;
 pub mod style;
+pub mod sync;
 pub mod tool;
 pub mod toolchain;
+pub mod trace;
+pub mod verify;
 pub mod version;
+pub mod workspace;
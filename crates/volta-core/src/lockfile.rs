@@ -0,0 +1,480 @@
+//! Provides the `volta.lock` file, which a project can commit to record the exact versions of
+//! its pinned tools along with the checksums of their archives.
+//!
+//! When a project has a lockfile, the versions it pins are checked against it before running a
+//! tool, and each archive is verified against the recorded checksum before it is unpacked.
+//! Checksums are SHA-256.
+//!
+//! Node tarballs can be recompressed with zstd once they are in the inventory, which replaces the
+//! archive that was downloaded. So the lockfile also records the checksum of the uncompressed
+//! contents of each tarball, which is the same for both, and a recompressed tarball is verified by
+//! decompressing it. Nothing that is only recorded in the Volta home is trusted.
+
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::error::ErrorDetails;
+use crate::fs::{read_file_error, write_file_error};
+use crate::platform::PlatformSpec;
+use crate::project::current_project_root;
+use crate::session::Session;
+use crate::settings::check_mutable;
+use crate::tool::{zstd_archive_file, Node, Npm, Pnpm, Yarn};
+use crate::version::version_serde;
+use log::{debug, warn};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// The name of the lockfile in the root of a project
+pub const LOCKFILE_NAME: &str = "volta.lock";
+
+/// The contents of a `volta.lock` file, by tool name
+#[derive(Default, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(flatten)]
+    pub tools: BTreeMap<String, LockedTool>,
+}
+
+/// The locked version of a tool
+#[derive(Serialize, Deserialize)]
+pub struct LockedTool {
+    #[serde(with = "version_serde")]
+    pub version: Version,
+
+    /// The SHA-256 checksums of the archives of this version, by the name of the archive file,
+    /// since Node and pnpm publish a separate archive for each platform
+    #[serde(default)]
+    pub sha256: BTreeMap<String, String>,
+
+    /// The SHA-256 checksums of the uncompressed contents of the tarballs of this version, by
+    /// the name of the archive file, which still verify a tarball once it is recompressed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub content_sha256: BTreeMap<String, String>,
+}
+
+/// The checksums that a lockfile records for an archive
+#[derive(Debug, Default)]
+pub(crate) struct LockedChecksum {
+    /// The checksum of the archive as it was downloaded
+    pub archive: Option<String>,
+    /// The checksum of the uncompressed contents of a tarball
+    pub content: Option<String>,
+}
+
+impl Lockfile {
+    /// Reads the lockfile of the project rooted at `project_root`, if it has one
+    pub fn for_dir(project_root: &Path) -> Fallible<Option<Self>> {
+        let file = project_root.join(LOCKFILE_NAME);
+        if !file.is_file() {
+            return Ok(None);
+        }
+
+        let contents = read_to_string(&file).with_context(read_file_error(&file))?;
+        let lockfile = serde_json::from_str(&contents)
+            .with_context(|_| ErrorDetails::ParseLockfileError { file: file.clone() })?;
+        debug!("Found lockfile at '{}'", file.display());
+        Ok(Some(lockfile))
+    }
+
+    /// Reads the lockfile of the project containing the current directory, if any
+    pub(crate) fn current() -> Fallible<Option<Self>> {
+        match current_project_root()? {
+            Some(root) => Lockfile::for_dir(&root),
+            None => Ok(None),
+        }
+    }
+
+    /// Records the checksums of an archive for a version of a tool, replacing the entry for the
+    /// tool if it was locked to a different version
+    ///
+    /// If only the recompressed tarball is left in the inventory, just the checksum of its
+    /// contents can be recorded.
+    pub fn insert(&mut self, tool: &str, version: &Version, archive: &Path) -> Fallible<()> {
+        let name = archive_name(archive);
+        let checksum = if archive.is_file() || !zstd_archive_file(archive).is_file() {
+            Some(archive_checksum(archive)?)
+        } else {
+            None
+        };
+        let content = if is_tarball(&name) {
+            Some(content_checksum(archive)?)
+        } else {
+            None
+        };
+
+        let entry = self
+            .tools
+            .entry(tool.to_string())
+            .or_insert_with(|| LockedTool {
+                version: version.clone(),
+                sha256: BTreeMap::new(),
+                content_sha256: BTreeMap::new(),
+            });
+        if entry.version != *version {
+            entry.version = version.clone();
+            entry.sha256.clear();
+            entry.content_sha256.clear();
+        }
+        if let Some(checksum) = checksum {
+            entry.sha256.insert(name.clone(), checksum);
+        }
+        if let Some(content) = content {
+            entry.content_sha256.insert(name, content);
+        }
+        Ok(())
+    }
+
+    /// Writes the lockfile into the root of a project, returning the path of the file
+    pub fn write(&self, project_root: &Path) -> Fallible<PathBuf> {
+        let file = project_root.join(LOCKFILE_NAME);
+        check_mutable(&format!("write {}", file.display()))?;
+
+        let mut contents =
+            serde_json::to_string_pretty(self).expect("the lockfile can always be serialized");
+        contents.push('\n');
+        std::fs::write(&file, contents).with_context(write_file_error(&file))?;
+        Ok(file)
+    }
+
+    /// Verifies that the versions pinned in `platform` are the versions that are locked
+    ///
+    /// Only node and npm are checked when the package managers aren't pinned by the project.
+    pub(crate) fn check_platform(&self, platform: &PlatformSpec, all_tools: bool) -> Fallible<()> {
+        let mut pins = vec![
+            ("node", Some(&platform.node_runtime)),
+            ("npm", platform.npm.as_ref()),
+        ];
+        if all_tools {
+            pins.push(("yarn", platform.yarn.as_ref()));
+            pins.push(("pnpm", platform.pnpm.as_ref()));
+        }
+
+        for (tool, pinned) in pins {
+            if let (Some(pinned), Some(locked)) = (pinned, self.tools.get(tool)) {
+                if *pinned != locked.version {
+                    throw!(ErrorDetails::LockedVersionMismatch {
+                        tool: tool.to_string(),
+                        pinned: pinned.to_string(),
+                        locked: locked.version.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the lockfile of the current project, recording the pinned versions along with the
+/// checksums of their archives for the current platform, and returns the path of the file
+///
+/// The checksums that were recorded on other platforms are kept, so that the lockfile can be
+/// completed by running `volta lock` on each platform the project is developed on.
+pub fn lock_project(session: &Session) -> Fallible<PathBuf> {
+    let (project_root, platform) = match session.project()? {
+        Some(project) => (project.project_root().to_path_buf(), project.platform()),
+        None => throw!(ErrorDetails::NotInPackage),
+    };
+    let platform = match platform {
+        Some(platform) => platform,
+        None => throw!(ErrorDetails::NothingToLock),
+    };
+
+    let mut lockfile = Lockfile::for_dir(&project_root)?.unwrap_or_default();
+    let node = &platform.node_runtime;
+    lockfile.insert(
+        "node",
+        node,
+        &Node::new(node.clone()).inventory_archive(session)?,
+    )?;
+    if let Some(npm) = &platform.npm {
+        lockfile.insert(
+            "npm",
+            npm,
+            &Npm::new(npm.clone()).inventory_archive(session)?,
+        )?;
+    }
+    if let Some(yarn) = &platform.yarn {
+        lockfile.insert(
+            "yarn",
+            yarn,
+            &Yarn::new(yarn.clone()).inventory_archive(session)?,
+        )?;
+    }
+    if let Some(pnpm) = &platform.pnpm {
+        lockfile.insert(
+            "pnpm",
+            pnpm,
+            &Pnpm::new(pnpm.clone()).inventory_archive(session)?,
+        )?;
+    }
+
+    // Tools that are no longer pinned are no longer locked either. Entries for any other tool
+    // can only have been written by hand or by a newer version of Volta, which knows what they
+    // mean, so they are kept as they are.
+    lockfile.tools.retain(|tool, _| match tool.as_str() {
+        "node" => true,
+        "npm" => platform.npm.is_some(),
+        "yarn" => platform.yarn.is_some(),
+        "pnpm" => platform.pnpm.is_some(),
+        _ => true,
+    });
+
+    lockfile.write(&project_root)
+}
+
+/// Returns the checksums that the current project's lockfile records for an archive, if the
+/// lockfile locks the tool to this version
+pub(crate) fn locked_checksum(
+    tool: &str,
+    version: &Version,
+    archive: &Path,
+) -> Fallible<Option<LockedChecksum>> {
+    let lockfile = match Lockfile::current()? {
+        Some(lockfile) => lockfile,
+        None => return Ok(None),
+    };
+
+    match lockfile.tools.get(tool) {
+        Some(locked) if locked.version == *version => {
+            let name = archive_name(archive);
+            let checksum = LockedChecksum {
+                archive: locked.sha256.get(&name).cloned(),
+                content: locked.content_sha256.get(&name).cloned(),
+            };
+            if checksum.archive.is_none() && checksum.content.is_none() {
+                warn!(
+                    "{} does not record a checksum of {} for this platform; run `volta lock` to add it",
+                    LOCKFILE_NAME, name
+                );
+                return Ok(None);
+            }
+            Ok(Some(checksum))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Returns whether an archive can be verified against the checksums recorded in a lockfile
+///
+/// That is the case when the archive as it was downloaded is in the inventory, or when only its
+/// recompressed tarball is and the lockfile records the checksum of its contents.
+pub(crate) fn can_verify(archive: &Path, expected: &LockedChecksum) -> bool {
+    archive.is_file() || (expected.content.is_some() && zstd_archive_file(archive).is_file())
+}
+
+/// Verifies an archive against the checksums recorded in a lockfile, where `origin` is the file
+/// or URL that the archive was loaded from
+///
+/// The archive is checked as it was downloaded if the lockfile records its checksum, and by its
+/// uncompressed contents otherwise, reading the recompressed tarball if the archive was replaced.
+pub(crate) fn verify_archive(
+    tool: &str,
+    version: &Version,
+    archive: &Path,
+    expected: &LockedChecksum,
+    origin: &str,
+) -> Fallible<()> {
+    let matches = |found: String, locked: &str| {
+        debug!(
+            "Verifying '{}' against locked checksum {}",
+            archive.display(),
+            locked
+        );
+        found.eq_ignore_ascii_case(locked)
+    };
+    let verified = match (&expected.archive, &expected.content) {
+        (Some(checksum), _) if archive.is_file() => {
+            matches(archive_checksum(archive)?, checksum.as_str())
+        }
+        (_, Some(content)) if can_verify(archive, expected) => {
+            matches(content_checksum(archive)?, content.as_str())
+        }
+        _ => false,
+    };
+
+    if !verified {
+        throw!(ErrorDetails::LockedChecksumMismatch {
+            tool: tool.to_string(),
+            version: version.to_string(),
+            origin: origin.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+fn archive_name(archive: &Path) -> String {
+    archive
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Whether an archive is a gzip tarball, which may be recompressed in the inventory
+fn is_tarball(name: &str) -> bool {
+    name.ends_with(".tar.gz")
+}
+
+/// Calculate the SHA-1 checksum of a file, as a hex string
+///
+/// This is only used to compare with the SHA-1 shasums that the npm registry publishes, which
+/// are recorded next to package archives. Lockfiles use `archive_checksum`.
+pub(crate) fn file_checksum(file: &Path) -> Fallible<String> {
+    let mut buffer = Vec::new();
+    File::open(file)
+        .and_then(|mut contents| contents.read_to_end(&mut buffer))
+        .with_context(read_file_error(&file))?;
+
+    let mut hasher = Sha1::new();
+    hasher.input(buffer);
+    Ok(hex::encode(&hasher.result()))
+}
+
+/// Calculate the SHA-256 checksum of an archive, as a hex string
+fn archive_checksum(file: &Path) -> Fallible<String> {
+    let mut buffer = Vec::new();
+    File::open(file)
+        .and_then(|mut contents| contents.read_to_end(&mut buffer))
+        .with_context(read_file_error(&file))?;
+
+    let mut hasher = Sha256::new();
+    hasher.input(buffer);
+    Ok(hex::encode(&hasher.result()))
+}
+
+/// Calculate the SHA-256 checksum of the uncompressed contents of a gzip tarball, as a hex
+/// string, decompressing its recompressed tarball instead if only that one is in the inventory
+fn content_checksum(archive: &Path) -> Fallible<String> {
+    let (file, contents) = if archive.is_file() {
+        (archive.to_path_buf(), archive::decompress_gzip(archive))
+    } else {
+        let file = zstd_archive_file(archive);
+        let contents = archive::decompress_zstd(&file);
+        (file, contents)
+    };
+
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 64 * 1024];
+    contents
+        .and_then(|mut contents| loop {
+            let read = contents.read(&mut buffer)?;
+            if read == 0 {
+                break Ok(());
+            }
+            hasher.input(&buffer[..read]);
+        })
+        .with_context(read_file_error(&file))?;
+    Ok(hex::encode(&hasher.result()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::write;
+
+    #[test]
+    fn test_insert_and_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("npm-6.14.8.tgz");
+        write(&archive, "npm").unwrap();
+        let version = Version::parse("6.14.8").unwrap();
+
+        let mut lockfile = Lockfile::default();
+        lockfile.insert("npm", &version, &archive).unwrap();
+        let checksum = LockedChecksum {
+            archive: Some(lockfile.tools["npm"].sha256["npm-6.14.8.tgz"].clone()),
+            content: None,
+        };
+        assert_eq!(checksum.archive.as_ref().unwrap().len(), 64);
+        assert!(lockfile.tools["npm"].content_sha256.is_empty());
+        assert!(verify_archive("npm", &version, &archive, &checksum, "npm").is_ok());
+
+        write(&archive, "tampered").unwrap();
+        assert!(verify_archive("npm", &version, &archive, &checksum, "npm").is_err());
+
+        // Locking a different version replaces the checksums of the previous one
+        let newer = Version::parse("6.14.9").unwrap();
+        let newer_archive = dir.path().join("npm-6.14.9.tgz");
+        write(&newer_archive, "npm").unwrap();
+        lockfile.insert("npm", &newer, &newer_archive).unwrap();
+        assert_eq!(lockfile.tools["npm"].version, newer);
+        assert_eq!(lockfile.tools["npm"].sha256.len(), 1);
+    }
+
+    #[test]
+    fn test_verify_recompressed() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("node-v14.15.0-linux-x64.tar.gz");
+        let mut fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        fixture.push("fixtures");
+        fixture.push("tarballs");
+        fixture.push("test-file.tar.gz");
+        std::fs::copy(&fixture, &archive).unwrap();
+        let version = Version::parse("14.15.0").unwrap();
+
+        let mut lockfile = Lockfile::default();
+        lockfile.insert("node", &version, &archive).unwrap();
+        let locked = &lockfile.tools["node"];
+        let checksum = LockedChecksum {
+            archive: locked.sha256.get("node-v14.15.0-linux-x64.tar.gz").cloned(),
+            content: locked
+                .content_sha256
+                .get("node-v14.15.0-linux-x64.tar.gz")
+                .cloned(),
+        };
+        assert!(checksum.archive.is_some());
+        assert!(checksum.content.is_some());
+
+        // Once only the recompressed tarball is left, it is verified by its contents
+        let zstd_file = zstd_archive_file(&archive);
+        archive::recompress_zstd(&archive, &zstd_file).unwrap();
+        std::fs::remove_file(&archive).unwrap();
+        assert!(can_verify(&archive, &checksum));
+        assert!(verify_archive("node", &version, &archive, &checksum, "node").is_ok());
+
+        // Locking the recompressed tarball records the same contents, but no archive checksum
+        let mut relocked = Lockfile::default();
+        relocked.insert("node", &version, &archive).unwrap();
+        assert!(relocked.tools["node"].sha256.is_empty());
+        assert_eq!(relocked.tools["node"].content_sha256, locked.content_sha256);
+
+        // The recompressed tarball can't be verified without the checksum of its contents
+        let archive_only = LockedChecksum {
+            archive: checksum.archive.clone(),
+            content: None,
+        };
+        assert!(!can_verify(&archive, &archive_only));
+
+        // A tampered recompressed tarball doesn't pass
+        archive::recompress_zstd(&fixture, &zstd_file).unwrap();
+        let tampered = LockedChecksum {
+            archive: None,
+            content: Some("0".repeat(64)),
+        };
+        assert!(verify_archive("node", &version, &archive, &tampered, "node").is_err());
+        write(&zstd_file, "tampered").unwrap();
+        assert!(verify_archive("node", &version, &archive, &checksum, "node").is_err());
+    }
+
+    #[test]
+    fn test_serialized_format() {
+        let lockfile: Lockfile = serde_json::from_str(
+            r#"{ "node": { "version": "14.15.0", "sha256": { "node-v14.15.0-linux-x64.tar.gz": "abc" } } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            lockfile.tools["node"].version,
+            Version::parse("14.15.0").unwrap()
+        );
+        assert_eq!(
+            lockfile.tools["node"].sha256["node-v14.15.0-linux-x64.tar.gz"],
+            "abc"
+        );
+    }
+}
@@ -1,12 +1,16 @@
 //! This module provides a custom Logger implementation for use with the `log` crate
 use atty::Stream;
-use console::style;
+use chrono::Local;
 use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
-use std::env;
+use std::cmp::max;
 use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
 use textwrap::{NoHyphenation, Wrapper};
 
-use crate::style::text_width;
+use crate::env_vars;
+use crate::style::{error_prefix, text_width, warning_prefix};
 
 const ERROR_PREFIX: &str = "error:";
 const WARNING_PREFIX: &str = "warning:";
@@ -14,7 +18,6 @@ const SHIM_ERROR_PREFIX: &str = "Volta error:";
 const SHIM_WARNING_PREFIX: &str = "Volta warning:";
 const MIGRATION_ERROR_PREFIX: &str = "Volta update error:";
 const MIGRATION_WARNING_PREFIX: &str = "Volta update warning:";
-const VOLTA_LOGLEVEL: &str = "VOLTA_LOGLEVEL";
 const ALLOWED_PREFIX: &str = "volta";
 const WRAP_INDENT: &str = "    ";
 
@@ -40,26 +43,85 @@ pub enum LogVerbosity {
 pub struct Logger {
     context: LogContext,
     level: LevelFilter,
+    file: Option<LogFile>,
+}
+
+/// A file that log messages are also written to, with timestamps, when `VOLTA_LOG_FILE` is set
+///
+/// The file always records debug messages (or trace messages if requested), regardless of what
+/// is shown in the terminal, since it is meant for investigating slow or failing operations
+/// after the fact.
+struct LogFile {
+    file: Mutex<File>,
+    level: LevelFilter,
 }
 
 impl Log for Logger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= self.level
+        metadata.level() <= self.max_level()
     }
 
     fn log(&self, record: &Record) {
-        if self.enabled(record.metadata()) && record.target().starts_with(ALLOWED_PREFIX) {
+        if !self.enabled(record.metadata()) || !record.target().starts_with(ALLOWED_PREFIX) {
+            return;
+        }
+
+        if let Some(log_file) = &self.file {
+            log_file.write(record);
+        }
+
+        if record.level() <= self.level {
             match record.level() {
                 Level::Error => self.log_error(record.args()),
                 Level::Warn => self.log_warning(record.args()),
+                Level::Info => println!("{}", record.args()),
                 Level::Debug => eprintln!("[verbose] {}", record.args()),
-                // all info-level messages go to stdout
-                _ => println!("{}", record.args()),
+                Level::Trace => eprintln!("[trace] {}", record.args()),
             }
         }
     }
 
-    fn flush(&self) {}
+    fn flush(&self) {
+        if let Some(log_file) = &self.file {
+            if let Ok(mut file) = log_file.file.lock() {
+                let _ = file.flush();
+            }
+        }
+    }
+}
+
+impl LogFile {
+    fn from_env(level: LevelFilter) -> Option<Self> {
+        let path = env_vars::os_string(env_vars::VOLTA_LOG_FILE)?;
+        // If the file can't be opened, Volta still works normally, just without the log file
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .ok()?;
+
+        Some(LogFile {
+            file: Mutex::new(file),
+            level: max(level, LevelFilter::Debug),
+        })
+    }
+
+    fn write(&self, record: &Record) {
+        if record.level() > self.level {
+            return;
+        }
+
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(
+                file,
+                "{} {:<5} [{}] {}",
+                Local::now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+                record.level(),
+                std::process::id(),
+                record.args()
+            );
+        }
+    }
 }
 
 impl Logger {
@@ -68,19 +130,36 @@ impl Logger {
     /// If set to Default, will use the environment to determine the level of verbosity
     pub fn init(context: LogContext, verbosity: LogVerbosity) -> Result<(), SetLoggerError> {
         let logger = Logger::new(context, verbosity);
-        log::set_max_level(logger.level);
+        log::set_max_level(logger.max_level());
         log::set_boxed_logger(Box::new(logger))?;
         Ok(())
     }
 
     fn new(context: LogContext, verbosity: LogVerbosity) -> Self {
+        let env_level = level_from_env();
         let level = match verbosity {
             LogVerbosity::Quiet => LevelFilter::Error,
-            LogVerbosity::Default => level_from_env(),
-            LogVerbosity::Verbose => LevelFilter::Debug,
+            LogVerbosity::Default => env_level.unwrap_or_else(default_level),
+            // Verbose shows at least debug messages, but `VOLTA_LOG=trace` can also show tracing
+            LogVerbosity::Verbose => {
+                max(env_level.unwrap_or(LevelFilter::Debug), LevelFilter::Debug)
+            }
         };
+        let file = LogFile::from_env(level);
 
-        Logger { context, level }
+        Logger {
+            context,
+            level,
+            file,
+        }
+    }
+
+    /// The most verbose level that is written anywhere, either to the terminal or the log file
+    fn max_level(&self) -> LevelFilter {
+        match &self.file {
+            Some(file) => max(self.level, file.level),
+            None => self.level,
+        }
     }
 
     fn log_error<D>(&self, message: &D)
@@ -93,7 +172,7 @@ impl Logger {
             LogContext::Migration => MIGRATION_ERROR_PREFIX,
         };
 
-        eprintln!("{} {}", style(prefix).red().bold(), message);
+        eprintln!("{} {}", error_prefix(prefix), message);
     }
 
     fn log_warning<D>(&self, message: &D)
@@ -108,7 +187,7 @@ impl Logger {
 
         eprintln!(
             "{} {}",
-            style(prefix).yellow().bold(),
+            warning_prefix(prefix),
             wrap_content(prefix, message)
         );
     }
@@ -133,23 +212,41 @@ where
     }
 }
 
-/// Determines the correct logging level based on the environment
-/// If VOLTA_LOGLEVEL is set to a valid level, we use that
-/// If not, we check the current stdout to determine whether it is a TTY or not
+/// Determines the logging level requested in the environment
+/// If VOLTA_LOG (or the older VOLTA_LOGLEVEL) is set to a valid level, we use that
+fn level_from_env() -> Option<LevelFilter> {
+    env_vars::string(env_vars::VOLTA_LOG)
+        .or_else(|| env_vars::string(env_vars::VOLTA_LOGLEVEL))
+        .and_then(|level| parse_level(&level))
+}
+
+/// Parses a level name, which is one of `off`, `error`, `warn`, `info`, `debug` or `trace`
+fn parse_level(level: &str) -> Option<LevelFilter> {
+    level.to_uppercase().parse().ok()
+}
+
+/// Determines the logging level when none is requested
+/// We check the current stdout to determine whether it is a TTY or not
 ///     If it is a TTY, we use Info
 ///     If it is NOT a TTY, we use Error as we don't want to show warnings when running as a script
-fn level_from_env() -> LevelFilter {
-    env::var(VOLTA_LOGLEVEL)
-        .ok()
-        .and_then(|level| level.to_uppercase().parse().ok())
-        .unwrap_or_else(|| {
-            if atty::is(Stream::Stdout) {
-                LevelFilter::Info
-            } else {
-                LevelFilter::Error
-            }
-        })
+fn default_level() -> LevelFilter {
+    if atty::is(Stream::Stdout) {
+        LevelFilter::Info
+    } else {
+        LevelFilter::Error
+    }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_level() {
+        assert_eq!(parse_level("trace"), Some(LevelFilter::Trace));
+        assert_eq!(parse_level("Debug"), Some(LevelFilter::Debug));
+        assert_eq!(parse_level("WARN"), Some(LevelFilter::Warn));
+        assert_eq!(parse_level("off"), Some(LevelFilter::Off));
+        assert_eq!(parse_level("loud"), None);
+    }
+}
@@ -1,6 +1,6 @@
 //! Provides the `Manifest` type, which represents a Node manifest file (`package.json`).
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{read_to_string, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -8,28 +8,114 @@ use std::rc::Rc;
 
 use crate::error::ErrorDetails;
 use crate::platform::PlatformSpec;
+use crate::settings::check_mutable;
 use detect_indent;
+use dunce::canonicalize;
 use semver::Version;
 use serde::Serialize;
 use serde_json;
-use volta_fail::{Fallible, ResultExt};
+use volta_fail::{throw, Fallible, ResultExt};
 
 pub(crate) mod serial;
 
+/// The maximum number of `extends` links followed when resolving a project's toolchain.
+const MAX_EXTENDS_DEPTH: usize = 10;
+
 /// A Node manifest file.
 pub struct Manifest {
-    /// The platform image specified by the `volta` section.
+    /// The platform image specified by the `volta` section, including any inherited versions.
     pub platform: Option<Rc<PlatformSpec>>,
+    /// The `extends` entry in the `volta` section, if any.
+    pub extends: Option<String>,
+    /// The platform image inherited through `extends`, if any.
+    pub inherited: Option<Rc<PlatformSpec>>,
+    /// The manifests that `extends` links to, nearest first.
+    pub extended_files: Vec<PathBuf>,
+    /// The `engines.node` requirement, if any.
+    pub engines: Option<String>,
     /// The `dependencies` section.
     pub dependencies: HashMap<String, String>,
     /// The `devDependencies` section.
     pub dev_dependencies: HashMap<String, String>,
+    /// The package manager named by the `packageManager` field, if any.
+    pub package_manager: Option<PackageManager>,
+    /// The versions of custom tools pinned in the `volta` section.
+    pub tools: BTreeMap<String, Version>,
+}
+
+/// A package manager pinned by the `packageManager` field used by corepack, for example
+/// `"packageManager": "pnpm@8.6.0"`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PackageManager {
+    Npm(Version),
+    Yarn(Version),
+    Pnpm(Version),
+}
+
+impl PackageManager {
+    /// Parses the value of a `packageManager` field, which has the form `name@version`,
+    /// optionally followed by a `+` and the hash of the package manager's archive.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.splitn(2, '@');
+        let name = parts.next()?;
+        let version = parts.next()?;
+        let version = Version::parse(version.splitn(2, '+').next()?).ok()?;
+
+        match name {
+            "npm" => Some(PackageManager::Npm(version)),
+            "yarn" => Some(PackageManager::Yarn(version)),
+            "pnpm" => Some(PackageManager::Pnpm(version)),
+            _ => None,
+        }
+    }
+
+    /// Returns `platform` with the package manager applied. A version already pinned in the
+    /// platform is only replaced if `overrides` is true.
+    pub fn apply_to(&self, platform: &PlatformSpec, overrides: bool) -> PlatformSpec {
+        let choose = |pinned: &Option<Version>, declared: &Version| match pinned {
+            Some(pinned) if !overrides => Some(pinned.clone()),
+            _ => Some(declared.clone()),
+        };
+
+        match self {
+            PackageManager::Yarn(version) => PlatformSpec {
+                yarn: choose(&platform.yarn, version),
+                ..platform.clone()
+            },
+            PackageManager::Pnpm(version) => PlatformSpec {
+                pnpm: choose(&platform.pnpm, version),
+                ..platform.clone()
+            },
+            PackageManager::Npm(version) => PlatformSpec {
+                npm: choose(&platform.npm, version),
+                ..platform.clone()
+            },
+        }
+    }
 }
 
 impl Manifest {
     /// Loads and parses a Node manifest for the project rooted at the specified path.
     pub fn for_dir(project_root: &Path) -> Fallible<Manifest> {
+        Manifest::for_file(&project_root.join("package.json"), &mut Vec::new())
+    }
+
+    /// Whether the manifest for the project rooted at the specified path has a `volta` (or
+    /// `toolchain`) key. Only the keys are checked, so this is cheaper than loading the manifest.
+    pub fn has_toolchain(project_root: &Path) -> Fallible<bool> {
         let package_file = project_root.join("package.json");
+        let file = File::open(&package_file).with_context(|_| ErrorDetails::PackageReadError {
+            file: package_file.clone(),
+        })?;
+
+        let keys: serial::ToolchainKeys = serde_json::de::from_reader(file)
+            .with_context(|_| ErrorDetails::PackageParseError { file: package_file })?;
+        Ok(keys.volta.is_some() || keys.toolchain.is_some())
+    }
+
+    /// Loads and parses a Node manifest, following any `extends` links. `chain` holds the
+    /// manifests that have already been visited, to detect cycles.
+    fn for_file(package_file: &Path, chain: &mut Vec<PathBuf>) -> Fallible<Manifest> {
         let file = File::open(&package_file).with_context(|_| ErrorDetails::PackageReadError {
             file: package_file.to_path_buf(),
         })?;
@@ -39,7 +125,57 @@ impl Manifest {
                 file: package_file.to_path_buf(),
             }
         })?;
-        serial.into_manifest(&package_file)
+
+        let (inherited, extended_files) = match serial.extends() {
+            Some(extends) => Manifest::resolve_extends(package_file, extends, chain)?,
+            None => (None, Vec::new()),
+        };
+
+        let mut manifest = serial.into_manifest(&package_file, inherited)?;
+        manifest.extended_files = extended_files;
+        Ok(manifest)
+    }
+
+    /// Loads the platform of the manifest that `package_file` extends, along with the files of
+    /// every manifest it was inherited from.
+    fn resolve_extends(
+        package_file: &Path,
+        extends: &str,
+        chain: &mut Vec<PathBuf>,
+    ) -> Fallible<(Option<Rc<PlatformSpec>>, Vec<PathBuf>)> {
+        let current =
+            canonicalize(package_file).with_context(|_| ErrorDetails::PackageReadError {
+                file: package_file.to_path_buf(),
+            })?;
+        chain.push(current);
+
+        if chain.len() > MAX_EXTENDS_DEPTH {
+            throw!(ErrorDetails::ExtendsDepthError {
+                file: package_file.to_path_buf(),
+                max: MAX_EXTENDS_DEPTH,
+            });
+        }
+
+        // `extends` may point at either a package.json or the directory containing one
+        let mut target = package_file
+            .parent()
+            .map(|dir| dir.join(extends))
+            .unwrap_or_else(|| PathBuf::from(extends));
+        if target.is_dir() {
+            target.push("package.json");
+        }
+
+        let target = canonicalize(&target).with_context(|_| ErrorDetails::PackageReadError {
+            file: target.clone(),
+        })?;
+        if chain.contains(&target) {
+            throw!(ErrorDetails::ExtendsCycleError { file: target });
+        }
+
+        let parent = Manifest::for_file(&target, chain)?;
+        let mut extended_files = vec![target];
+        extended_files.extend(parent.extended_files.iter().cloned());
+        Ok((parent.platform(), extended_files))
     }
 
     /// Returns a reference to the platform image specified by manifest, if any.
@@ -66,13 +202,33 @@ impl Manifest {
         self.platform().map(|t| t.yarn.clone()).unwrap_or(None)
     }
 
+    /// Returns the pinned version of pnpm as a Version, if any.
+    pub fn pnpm(&self) -> Option<Version> {
+        self.platform().map(|t| t.pnpm.clone()).unwrap_or(None)
+    }
+
+    /// Returns the pinned version of a custom tool, if any.
+    pub fn custom_tool(&self, name: &str) -> Option<&Version> {
+        self.tools.get(name)
+    }
+
     /// Updates the pinned platform information
     pub fn update_platform(&mut self, platform: PlatformSpec) {
         self.platform = Some(Rc::new(platform));
     }
 
+    /// Updates the pinned version of a custom tool
+    pub fn update_custom_tool(&mut self, name: &str, version: Version) {
+        self.tools.insert(name.to_string(), version);
+    }
+
     /// Updates the `volta` key in the specified `package.json` to match the current Manifest
     pub fn write(&self, package_file: PathBuf) -> Fallible<()> {
+        check_mutable(&format!(
+            "update the pinned versions in {}",
+            package_file.display()
+        ))?;
+
         // Helper for lazily creating the file name string without moving `package_file` into
         // one of the individual `with_context` closures below.
         let get_file = || package_file.to_owned();
@@ -82,6 +238,7 @@ impl Manifest {
             .with_context(|_| ErrorDetails::PackageReadError { file: get_file() })?;
 
         let is_end_with_newline = contents.ends_with('\n');
+        let is_crlf = contents.contains("\r\n");
 
         let mut v: serde_json::Value = serde_json::from_str(&contents)
             .with_context(|_| ErrorDetails::PackageParseError { file: get_file() })?;
@@ -91,33 +248,90 @@ impl Manifest {
             let indent = detect_indent::detect_indent(&contents);
 
             // update the "volta" key
-            if let Some(platform) = self.platform() {
-                let volta_value = serde_json::to_value(serial::ToolchainSpec::from(platform))
+            let spec = match self.platform() {
+                Some(platform) => Some(self.local_toolchain_spec(platform)),
+                None if self.extends.is_some() || !self.tools.is_empty() => {
+                    Some(serial::ToolchainSpec {
+                        extends: self.extends.clone(),
+                        ..serial::ToolchainSpec::default()
+                    })
+                }
+                None => None,
+            };
+            if let Some(mut spec) = spec {
+                spec.tools = self
+                    .tools
+                    .iter()
+                    .map(|(name, version)| (name.clone(), version.to_string()))
+                    .collect();
+                let volta_value = serde_json::to_value(spec)
                     .with_context(|_| ErrorDetails::StringifyToolchainError)?;
                 map.insert("volta".to_string(), volta_value);
             } else {
                 map.remove("volta");
             }
 
-            // serialize the updated contents back to package.json
-            let mut file = File::create(&package_file)
-                .with_context(|_| ErrorDetails::PackageWriteError { file: get_file() })?;
+            // serialize the updated contents into memory first, so that a failure can't leave
+            // a truncated package.json behind
+            let mut buffer = Vec::new();
             let formatter =
                 serde_json::ser::PrettyFormatter::with_indent(indent.indent().as_bytes());
-            let mut ser = serde_json::Serializer::with_formatter(&file, formatter);
+            let mut ser = serde_json::Serializer::with_formatter(&mut buffer, formatter);
             map.serialize(&mut ser)
                 .with_context(|_| ErrorDetails::PackageWriteError { file: get_file() })?;
+            let mut updated = String::from_utf8(buffer)
+                .with_context(|_| ErrorDetails::PackageWriteError { file: get_file() })?;
+
             // append the empty line if the original package.json has one
             if is_end_with_newline {
-                writeln!(file)
-                    .with_context(|_| ErrorDetails::PackageWriteError { file: get_file() })?;
+                updated.push('\n');
+            }
+
+            // keep Windows-style line endings if the original package.json used them
+            if is_crlf {
+                updated = updated.replace('\n', "\r\n");
             }
+
+            let mut file = File::create(&package_file)
+                .with_context(|_| ErrorDetails::PackageWriteError { file: get_file() })?;
+            file.write_all(updated.as_bytes())
+                .with_context(|_| ErrorDetails::PackageWriteError { file: get_file() })?;
         }
         Ok(())
     }
 }
 
+impl Manifest {
+    /// Builds the toolchain to write to the `volta` key, leaving out any versions that are
+    /// inherited unchanged through `extends`.
+    fn local_toolchain_spec(&self, platform: Rc<PlatformSpec>) -> serial::ToolchainSpec {
+        let mut spec = serial::ToolchainSpec::from(platform.clone());
+        spec.extends = self.extends.clone();
+
+        if let Some(inherited) = &self.inherited {
+            if inherited.node_runtime == platform.node_runtime {
+                spec.node = None;
+                if inherited.npm == platform.npm {
+                    spec.npm = None;
+                }
+            }
+            if inherited.yarn == platform.yarn {
+                spec.yarn = None;
+            }
+            if inherited.pnpm == platform.pnpm {
+                spec.pnpm = None;
+            }
+        }
+
+        spec
+    }
+}
+
 pub struct BinManifest {
+    /// The `name` field.
+    pub name: Option<String>,
+    /// The `version` field.
+    pub version: Option<String>,
     /// The `bin` section, containing a map of binary names to locations.
     pub bin: HashMap<String, String>,
     /// The `engines` section, containing a spec of the Node versions that the package works on.
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::hash::Hash;
 use std::marker::PhantomData;
@@ -8,9 +8,11 @@ use std::rc::Rc;
 
 use super::super::{manifest, platform};
 use crate::version::parse_version;
+use crate::warn_deprecated;
 use log::warn;
+use semver::Version;
 use serde;
-use serde::de::{Deserialize, Deserializer, Error, MapAccess, Visitor};
+use serde::de::{Deserialize, Deserializer, Error, IgnoredAny, MapAccess, Visitor};
 use serde_json::value::Value;
 use volta_fail::Fallible;
 
@@ -53,15 +55,33 @@ pub struct Manifest {
     #[serde(rename = "devDependencies")]
     pub dev_dependencies: HashMap<String, String>,
 
+    #[serde(default, deserialize_with = "Engines::deserialize")]
+    pub engines: Option<Engines>,
+
+    #[serde(default, deserialize_with = "ToolchainSpec::deserialize_checked")]
     pub toolchain: Option<ToolchainSpec>,
 
+    #[serde(default, deserialize_with = "ToolchainSpec::deserialize_checked")]
     pub volta: Option<ToolchainSpec>,
+
+    #[serde(default)]
+    #[serde(rename = "packageManager")]
+    pub package_manager: Option<String>,
+}
+
+/// The top-level keys of a manifest that a toolchain can be pinned with, without their contents.
+#[derive(serde::Deserialize)]
+pub struct ToolchainKeys {
+    pub toolchain: Option<IgnoredAny>,
+    pub volta: Option<IgnoredAny>,
 }
 
 #[derive(serde::Deserialize)]
 pub struct RawBinManifest {
     pub name: Option<String>,
 
+    pub version: Option<String>,
+
     // the "bin" field can be a map or a string
     // (see https://docs.npmjs.com/files/package.json#bin)
     #[serde(default)] // handles Option
@@ -74,13 +94,44 @@ pub struct RawBinManifest {
     pub engines: Option<Engines>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Default, serde::Serialize, serde::Deserialize)]
 pub struct ToolchainSpec {
-    pub node: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub npm: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub yarn: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pnpm: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// The versions of custom tools defined in the hooks
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tools: BTreeMap<String, String>,
+}
+
+impl ToolchainSpec {
+    /// Deserialize a toolchain, requiring that the `node` key is present unless the
+    /// toolchain inherits its versions from another project using `extends`, or only pins
+    /// custom tools, which don't need Node.
+    pub fn deserialize_checked<'de, D>(d: D) -> Result<Option<ToolchainSpec>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let spec: Option<ToolchainSpec> = Deserialize::deserialize(d)?;
+        match spec {
+            Some(ToolchainSpec {
+                node: None,
+                extends: None,
+                ref tools,
+                ..
+            }) if tools.is_empty() => Err(D::Error::custom(
+                "the `node` key is required unless `extends` or `tools` is specified",
+            )),
+            spec => Ok(spec),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -114,15 +165,68 @@ impl Engines {
 }
 
 impl Manifest {
-    pub fn into_manifest(self, package_path: &Path) -> Fallible<manifest::Manifest> {
+    pub fn into_manifest(
+        self,
+        package_path: &Path,
+        inherited: Option<Rc<platform::PlatformSpec>>,
+    ) -> Fallible<manifest::Manifest> {
+        let package_manager = self.parse_package_manager(package_path);
+        let tools = self.custom_tools()?;
         Ok(manifest::Manifest {
-            platform: self.to_platform(package_path)?.map(Rc::new),
+            platform: self
+                .to_platform(package_path, inherited.as_ref().map(|p| &**p))?
+                .map(Rc::new),
+            extends: self.extends().map(str::to_string),
+            inherited,
+            extended_files: Vec::new(),
+            engines: self.engines.as_ref().map(|engines| engines.node.clone()),
             dependencies: self.dependencies,
             dev_dependencies: self.dev_dependencies,
+            package_manager,
+            tools,
         })
     }
 
-    pub fn to_platform(&self, package_path: &Path) -> Fallible<Option<platform::PlatformSpec>> {
+    /// Parses the versions of custom tools pinned in the toolchain, if any.
+    fn custom_tools(&self) -> Fallible<BTreeMap<String, Version>> {
+        match self.volta.as_ref().or_else(|| self.toolchain.as_ref()) {
+            Some(toolchain) => toolchain
+                .tools
+                .iter()
+                .map(|(name, version)| Ok((name.clone(), parse_version(version)?)))
+                .collect(),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    /// Parses the `packageManager` field, warning about values that aren't understood.
+    fn parse_package_manager(&self, package_path: &Path) -> Option<manifest::PackageManager> {
+        let value = self.package_manager.as_ref()?;
+        let package_manager = manifest::PackageManager::parse(value);
+        if package_manager.is_none() {
+            warn!(
+                "this project (`{}`) has an unsupported `packageManager` field (`{}`); it will be ignored.",
+                package_path.display(),
+                value
+            );
+        }
+        package_manager
+    }
+
+    /// Returns the path of the project this manifest inherits its toolchain from, if any.
+    pub fn extends(&self) -> Option<&str> {
+        self.volta
+            .as_ref()
+            .or_else(|| self.toolchain.as_ref())
+            .and_then(|toolchain| toolchain.extends.as_ref())
+            .map(String::as_str)
+    }
+
+    pub fn to_platform(
+        &self,
+        package_path: &Path,
+        inherited: Option<&platform::PlatformSpec>,
+    ) -> Fallible<Option<platform::PlatformSpec>> {
         // Backwards compatibility to allow users to upgrade to using the
         // `volta` key simultaneously with the `toolchain` key, but with
         // deprecation warnings about the use of `toolchain`. Prefer the `volta`
@@ -131,14 +235,14 @@ impl Manifest {
         let toolchain = match (&self.volta, &self.toolchain) {
             (Some(volta), None) => Some(volta),
             (Some(volta), Some(_toolchain)) => {
-                warn!(
+                warn_deprecated!(
                     "this project (`{}`) is configured with both the deprecated `toolchain` key and the `volta` key; using the versions specified in `volta`.",
                     package_path.display()
                 );
                 Some(volta)
             }
             (None, Some(toolchain)) => {
-                warn!(
+                warn_deprecated!(
                     "this project (`{}`) is configured with the `toolchain` key, which is deprecated and will be removed in a future version. Please switch to `volta` instead.",
                     package_path.display()
                 );
@@ -148,19 +252,30 @@ impl Manifest {
         };
 
         if let Some(toolchain) = &toolchain {
-            return Ok(Some(platform::PlatformSpec {
-                node_runtime: parse_version(&toolchain.node)?,
-                npm: if let Some(npm) = &toolchain.npm {
-                    Some(parse_version(&npm)?)
-                } else {
-                    None
-                },
-                yarn: if let Some(yarn) = &toolchain.yarn {
-                    Some(parse_version(&yarn)?)
-                } else {
-                    None
-                },
-            }));
+            let node = toolchain.node.as_ref().map(parse_version).transpose()?;
+            let npm = toolchain.npm.as_ref().map(parse_version).transpose()?;
+            let yarn = toolchain.yarn.as_ref().map(parse_version).transpose()?;
+            let inherited_yarn = || inherited.and_then(|parent| parent.yarn.clone());
+            let pnpm = toolchain.pnpm.as_ref().map(parse_version).transpose()?;
+            let inherited_pnpm = || inherited.and_then(|parent| parent.pnpm.clone());
+
+            // Versions set locally take precedence over inherited ones. The bundled npm is tied
+            // to the Node version, so it is only inherited along with Node itself.
+            return Ok(match (node, inherited) {
+                (Some(node), _) => Some(platform::PlatformSpec {
+                    node_runtime: node,
+                    npm,
+                    yarn: yarn.or_else(inherited_yarn),
+                    pnpm: pnpm.or_else(inherited_pnpm),
+                }),
+                (None, Some(parent)) => Some(platform::PlatformSpec {
+                    node_runtime: parent.node_runtime.clone(),
+                    npm: npm.or_else(|| parent.npm.clone()),
+                    yarn: yarn.or_else(inherited_yarn),
+                    pnpm: pnpm.or_else(inherited_pnpm),
+                }),
+                (None, None) => None,
+            });
         }
         Ok(None)
     }
@@ -182,6 +297,8 @@ impl From<RawBinManifest> for super::BinManifest {
         }
 
         super::BinManifest {
+            name: raw.name,
+            version: raw.version,
             bin: map,
             engine: raw.engines.map(|e| e.node),
         }
@@ -191,9 +308,12 @@ impl From<RawBinManifest> for super::BinManifest {
 impl From<Rc<platform::PlatformSpec>> for ToolchainSpec {
     fn from(source: Rc<platform::PlatformSpec>) -> Self {
         ToolchainSpec {
-            node: source.node_runtime.to_string(),
+            node: Some(source.node_runtime.to_string()),
             npm: source.npm.as_ref().map(|v| v.to_string()),
             yarn: source.yarn.as_ref().map(|v| v.to_string()),
+            pnpm: source.pnpm.as_ref().map(|v| v.to_string()),
+            extends: None,
+            tools: BTreeMap::new(),
         }
     }
 }
@@ -270,8 +390,12 @@ where
 pub mod tests {
 
     use super::{BinMap, Engines, Manifest, RawBinManifest};
+    use crate::manifest::PackageManager;
+    use crate::platform::PlatformSpec;
+    use semver::Version;
     use serde_json;
     use std::collections::HashMap;
+    use std::path::Path;
 
     #[test]
     fn test_empty_package() {
@@ -370,7 +494,7 @@ pub mod tests {
         }"#;
         let manifest_node_only: Manifest =
             serde_json::de::from_str(package_node_only).expect("Could not deserialize string");
-        assert_eq!(manifest_node_only.volta.unwrap().node, "0.11.4");
+        assert_eq!(manifest_node_only.volta.unwrap().node.unwrap(), "0.11.4");
 
         let package_node_npm = r#"{
             "volta": {
@@ -383,7 +507,7 @@ pub mod tests {
         let toolchain_node_npm = manifest_node_npm
             .volta
             .expect("Did not parse toolchain correctly");
-        assert_eq!(toolchain_node_npm.node, "0.10.5");
+        assert_eq!(toolchain_node_npm.node.unwrap(), "0.10.5");
         assert_eq!(toolchain_node_npm.npm.unwrap(), "1.2.18");
 
         let package_yarn_only = r#"{
@@ -409,10 +533,133 @@ pub mod tests {
         let toolchain_node_and_yarn = manifest_node_and_yarn
             .volta
             .expect("Did not parse toolchain correctly");
-        assert_eq!(toolchain_node_and_yarn.node, "0.10.5");
+        assert_eq!(toolchain_node_and_yarn.node.unwrap(), "0.10.5");
         assert_eq!(toolchain_node_and_yarn.yarn.unwrap(), "1.2.1");
     }
 
+    #[test]
+    fn test_package_engines() {
+        let package_engines = r#"{
+            "engines": {
+                "node": ">= 8.9"
+            }
+        }"#;
+        let manifest_engines: Manifest =
+            serde_json::de::from_str(package_engines).expect("Could not deserialize string");
+        assert_eq!(
+            manifest_engines.engines,
+            Some(Engines {
+                node: ">= 8.9".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_package_toolchain_with_extends() {
+        let package_extends_only = r#"{
+            "volta": {
+                "extends": "../../package.json"
+            }
+        }"#;
+        let manifest_extends_only: Manifest =
+            serde_json::de::from_str(package_extends_only).expect("Could not deserialize string");
+        assert_eq!(manifest_extends_only.extends(), Some("../../package.json"));
+        assert_eq!(manifest_extends_only.volta.unwrap().node, None);
+
+        let package_extends_yarn = r#"{
+            "volta": {
+                "extends": "../../package.json",
+                "yarn": "1.2.1"
+            }
+        }"#;
+        let manifest_extends_yarn: Manifest =
+            serde_json::de::from_str(package_extends_yarn).expect("Could not deserialize string");
+        let toolchain_extends_yarn = manifest_extends_yarn
+            .volta
+            .expect("Did not parse toolchain correctly");
+        assert_eq!(toolchain_extends_yarn.yarn.unwrap(), "1.2.1");
+    }
+
+    #[test]
+    fn test_package_toolchain_with_tools() {
+        let package_tools_only = r#"{
+            "volta": {
+                "tools": { "deno": "1.40.0" }
+            }
+        }"#;
+        let manifest_tools_only: Manifest =
+            serde_json::de::from_str(package_tools_only).expect("Could not deserialize string");
+        assert_eq!(
+            manifest_tools_only.custom_tools().unwrap().get("deno"),
+            Some(&Version::parse("1.40.0").unwrap())
+        );
+
+        let package_invalid_tool = r#"{
+            "volta": {
+                "node": "12.16.0",
+                "tools": { "deno": "latest" }
+            }
+        }"#;
+        let manifest_invalid_tool: Manifest =
+            serde_json::de::from_str(package_invalid_tool).expect("Could not deserialize string");
+        assert!(manifest_invalid_tool.custom_tools().is_err());
+    }
+
+    #[test]
+    fn test_package_manager() {
+        let package_pnpm = r#"{
+            "packageManager": "pnpm@8.6.0+sha256.0123456789abcdef"
+        }"#;
+        let manifest_pnpm: Manifest =
+            serde_json::de::from_str(package_pnpm).expect("Could not deserialize string");
+        assert_eq!(
+            manifest_pnpm.parse_package_manager(Path::new("package.json")),
+            Some(PackageManager::Pnpm(Version::parse("8.6.0").unwrap()))
+        );
+
+        let package_unknown = r#"{
+            "packageManager": "bun@1.0.0"
+        }"#;
+        let manifest_unknown: Manifest =
+            serde_json::de::from_str(package_unknown).expect("Could not deserialize string");
+        assert_eq!(
+            manifest_unknown.parse_package_manager(Path::new("package.json")),
+            None
+        );
+
+        assert_eq!(PackageManager::parse("yarn"), None);
+        assert_eq!(PackageManager::parse("yarn@latest"), None);
+    }
+
+    #[test]
+    fn test_package_manager_precedence() {
+        let platform = PlatformSpec {
+            node_runtime: Version::parse("12.16.0").unwrap(),
+            npm: None,
+            yarn: Some(Version::parse("1.22.0").unwrap()),
+            pnpm: None,
+        };
+        let declared = PackageManager::Yarn(Version::parse("3.2.0").unwrap());
+
+        assert_eq!(declared.apply_to(&platform, false).yarn, platform.yarn);
+        assert_eq!(
+            declared.apply_to(&platform, true).yarn,
+            Some(Version::parse("3.2.0").unwrap())
+        );
+
+        let declared = PackageManager::Pnpm(Version::parse("8.6.0").unwrap());
+        assert_eq!(
+            declared.apply_to(&platform, false).pnpm,
+            Some(Version::parse("8.6.0").unwrap())
+        );
+
+        let declared = PackageManager::Npm(Version::parse("10.2.0").unwrap());
+        assert_eq!(
+            declared.apply_to(&platform, false).npm,
+            Some(Version::parse("10.2.0").unwrap())
+        );
+    }
+
     #[test]
     fn test_package_toolchain_with_toolchain_key() {
         let package_empty_toolchain = r#"{
@@ -433,7 +680,10 @@ pub mod tests {
         }"#;
         let manifest_node_only: Manifest =
             serde_json::de::from_str(package_node_only).expect("Could not deserialize string");
-        assert_eq!(manifest_node_only.toolchain.unwrap().node, "0.11.4");
+        assert_eq!(
+            manifest_node_only.toolchain.unwrap().node.unwrap(),
+            "0.11.4"
+        );
 
         let package_node_npm = r#"{
             "toolchain": {
@@ -446,7 +696,7 @@ pub mod tests {
         let toolchain_node_npm = manifest_node_npm
             .toolchain
             .expect("Did not parse toolchain correctly");
-        assert_eq!(toolchain_node_npm.node, "0.10.5");
+        assert_eq!(toolchain_node_npm.node.unwrap(), "0.10.5");
         assert_eq!(toolchain_node_npm.npm.unwrap(), "1.2.18");
 
         let package_yarn_only = r#"{
@@ -472,7 +722,7 @@ pub mod tests {
         let toolchain_node_and_yarn = manifest_node_and_yarn
             .toolchain
             .expect("Did not parse toolchain correctly");
-        assert_eq!(toolchain_node_and_yarn.node, "0.10.5");
+        assert_eq!(toolchain_node_and_yarn.node.unwrap(), "0.10.5");
         assert_eq!(toolchain_node_and_yarn.yarn.unwrap(), "1.2.1");
     }
 
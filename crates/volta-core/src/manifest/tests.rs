@@ -1,3 +1,4 @@
+use crate::error::ErrorDetails;
 use crate::manifest::Manifest;
 use semver::Version;
 use std::collections::{HashMap, HashSet};
@@ -127,3 +128,44 @@ fn handles_dep_with_no_bin() {
     let expected_bin = HashMap::new();
     assert_eq!(bin, expected_bin);
 }
+
+#[test]
+fn inherits_platform_from_extends() {
+    let project_path = fixture_path("extends/packages/child");
+    let manifest = Manifest::for_dir(&project_path).expect("Could not get manifest");
+    let platform = manifest.platform().expect("Did not inherit a platform");
+
+    assert_eq!(platform.node_runtime, Version::parse("10.16.0").unwrap());
+    assert_eq!(platform.npm, Some(Version::parse("6.9.0").unwrap()));
+    assert_eq!(platform.yarn, Some(Version::parse("1.12.3").unwrap()));
+    assert_eq!(manifest.extends, Some("../../package.json".to_string()));
+    assert_eq!(
+        manifest.extended_files,
+        vec![dunce::canonicalize(fixture_path("extends/package.json")).unwrap()]
+    );
+}
+
+#[test]
+fn detects_extends_cycle() {
+    let project_path = fixture_path("extends/cycle/a");
+    let error = match Manifest::for_dir(&project_path) {
+        Ok(_) => panic!("Expected the extends cycle to be detected"),
+        Err(error) => error,
+    };
+
+    // The cycle is reported at the first file that is visited twice
+    match error.downcast_ref::<ErrorDetails>() {
+        Some(ErrorDetails::ExtendsCycleError { file }) => assert_eq!(
+            *file,
+            dunce::canonicalize(project_path.join("package.json")).unwrap()
+        ),
+        _ => panic!("Expected an extends cycle error, got: {}", error),
+    }
+}
+
+#[test]
+fn detects_toolchain_key() {
+    assert!(Manifest::has_toolchain(&fixture_path("basic")).unwrap());
+    assert!(!Manifest::has_toolchain(&fixture_path("basic/nested")).unwrap());
+    assert!(Manifest::has_toolchain(&fixture_path("basic/nested/broken")).is_err());
+}
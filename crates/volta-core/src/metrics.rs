@@ -0,0 +1,177 @@
+//! Provides opt-in usage metrics: anonymized counts of the commands that were run and of the
+//! tool versions that were used, aggregated locally in the Volta home.
+//!
+//! Nothing is recorded unless Volta was built with the `metrics` feature and the user enabled
+//! the `metrics` setting, which projects can't enable on the user's behalf. The counts never
+//! include paths, package names or arguments, and they only leave the machine when they are
+//! sent explicitly with `volta metrics --send`.
+
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, remove_file, File};
+use std::io::Write;
+
+use crate::error::ErrorDetails;
+use crate::fs::{create_staging_file, read_file_error, write_file_error};
+use crate::layout::volta_home;
+use crate::settings::{check_online, settings};
+use archive::http_client;
+use fs_utils::ensure_containing_dir_exists;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// Whether this build of Volta can record usage metrics
+pub const AVAILABLE: bool = cfg!(feature = "metrics");
+
+/// The aggregated usage counts
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    /// How many times each command was run, by command name
+    #[serde(default)]
+    pub commands: BTreeMap<String, u64>,
+    /// How many times each tool version was used, by `<tool>@<version>`
+    #[serde(default)]
+    pub tools: BTreeMap<String, u64>,
+}
+
+impl Metrics {
+    pub(crate) fn count_command(&mut self, command: &str) {
+        *self.commands.entry(command.to_string()).or_insert(0) += 1;
+    }
+
+    pub(crate) fn count_tool(&mut self, tool: &str, version: &str) {
+        *self
+            .tools
+            .entry(format!("{}@{}", tool, version))
+            .or_insert(0) += 1;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty() && self.tools.is_empty()
+    }
+
+    fn add(&mut self, other: Metrics) {
+        for (command, count) in other.commands {
+            *self.commands.entry(command).or_insert(0) += count;
+        }
+        for (tool, count) in other.tools {
+            *self.tools.entry(tool).or_insert(0) += count;
+        }
+    }
+
+    /// Reads the counts recorded so far
+    pub fn read() -> Fallible<Self> {
+        let file = volta_home()?.metrics_file();
+        if !file.is_file() {
+            return Ok(Metrics::default());
+        }
+
+        let contents = read_to_string(&file).with_context(read_file_error(&file))?;
+        // Counts that can't be read, such as from a file truncated by a crash, start over
+        Ok(serde_json::from_str(&contents).unwrap_or_default())
+    }
+
+    /// Writes the counts through a staging file, so that the file is never partially written
+    fn write(&self) -> Fallible<()> {
+        let file = volta_home()?.metrics_file();
+        let contents =
+            serde_json::to_string_pretty(self).expect("metrics can always be serialized");
+
+        let staged = create_staging_file()?;
+        let mut staged_file: &File = staged.as_file();
+        staged_file
+            .write_all(contents.as_bytes())
+            .with_context(write_file_error(&staged.path()))?;
+
+        ensure_containing_dir_exists(&file)
+            .with_context(|_| ErrorDetails::ContainingDirError { path: file.clone() })?;
+        staged
+            .persist(&file)
+            .with_context(|_| ErrorDetails::WriteFileError { file: file.clone() })?;
+        Ok(())
+    }
+
+    /// Removes the counts recorded so far
+    pub fn clear() -> Fallible<()> {
+        let file = volta_home()?.metrics_file();
+        if file.is_file() {
+            remove_file(&file).with_context(|_| ErrorDetails::DeleteFileError { file })?;
+        }
+        Ok(())
+    }
+
+    /// Posts the counts as JSON to the `metricsEndpoint` setting, removing them once they have
+    /// been received
+    pub fn send() -> Fallible<()> {
+        if !AVAILABLE {
+            throw!(ErrorDetails::MetricsNotAvailable);
+        }
+        let endpoint = match settings()?.metrics_endpoint {
+            Some(ref endpoint) => endpoint.clone(),
+            None => throw!(ErrorDetails::NoMetricsEndpoint),
+        };
+
+        let metrics = Metrics::read()?;
+        if metrics.is_empty() {
+            debug!(
+                "No usage metrics have been recorded, not sending to {}",
+                endpoint
+            );
+            return Ok(());
+        }
+
+        check_online(&endpoint)?;
+        http_client()
+            .post(&endpoint)
+            .json(&metrics)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .with_context(|_| ErrorDetails::MetricsSendError {
+                endpoint: endpoint.clone(),
+            })?;
+        Metrics::clear()
+    }
+}
+
+/// Adds the usage of a session to the recorded counts, if the user consented to usage metrics
+///
+/// Metrics are only informational, so failing to record them never fails the session.
+pub(crate) fn record(usage: Metrics) {
+    if !AVAILABLE || usage.is_empty() {
+        return;
+    }
+    match settings() {
+        Ok(settings) if settings.metrics => {}
+        _ => return,
+    }
+
+    let result = Metrics::read().and_then(|mut metrics| {
+        metrics.add(usage);
+        metrics.write()
+    });
+    if let Err(error) = result {
+        debug!("Could not record the usage metrics: {}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add() {
+        let mut metrics = Metrics::default();
+        metrics.count_command("install");
+        metrics.count_tool("node", "12.13.0");
+
+        let mut usage = Metrics::default();
+        usage.count_command("install");
+        usage.count_command("pin");
+        usage.count_tool("node", "12.13.0");
+        metrics.add(usage);
+
+        assert_eq!(metrics.commands.get("install"), Some(&2));
+        assert_eq!(metrics.commands.get("pin"), Some(&1));
+        assert_eq!(metrics.tools.get("node@12.13.0"), Some(&2));
+    }
+}
@@ -0,0 +1,159 @@
+//! Provides the notices about newer security releases of the pinned Node line, which tell the
+//! users of a project that its pinned version has known vulnerabilities fixed in a later patch.
+//!
+//! The notices are off unless the `nodeUpdateNotices` setting turns them on. They never use the
+//! network: they are based on the cached public index, which is kept up to date by the commands
+//! that resolve Node versions. They are also rate-limited, since they are checked by every shim
+//! run in a project: the time of the last check of each pinned version is recorded in the Node
+//! cache, and the index is only read again for that version once a day. So projects that pin
+//! different versions each get their own notice.
+
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::ErrorDetails;
+use crate::fs::write_file_error;
+use crate::layout::volta_home;
+use crate::settings::settings;
+use crate::style::tool_version;
+use crate::tool::cached_node_security_releases;
+use fs_utils::ensure_containing_dir_exists;
+use log::{debug, warn};
+use semver::Version;
+use volta_fail::{Fallible, ResultExt};
+
+/// How long to wait after a check before the index is checked again
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Shows a notice if the line of the pinned version of Node has a newer security release
+///
+/// Failures are only logged, since the notice must never get in the way of running the tool.
+pub(crate) fn notify_pinned_node(pinned: &Version) {
+    if let Err(error) = check_pinned_node(pinned) {
+        debug!(
+            "Could not check for newer Node security releases: {}",
+            error
+        );
+    }
+}
+
+fn check_pinned_node(pinned: &Version) -> Fallible<()> {
+    if !settings()?.node_update_notices {
+        return Ok(());
+    }
+
+    let now = unix_time(SystemTime::now());
+    let last_check_file = volta_home()?.node_notice_check_file();
+    let mut last_checks = read_last_checks(&last_check_file);
+    let key = pinned.to_string();
+    if !is_due(last_checks.get(&key).copied(), now) {
+        return Ok(());
+    }
+
+    // The versions that are due again don't need to be remembered, which keeps the file small
+    last_checks.retain(|_, last_check| !is_due(Some(*last_check), now));
+    last_checks.insert(key, now);
+    ensure_containing_dir_exists(&last_check_file).with_context(|_| {
+        ErrorDetails::ContainingDirError {
+            path: last_check_file.clone(),
+        }
+    })?;
+    let contents =
+        serde_json::to_string(&last_checks).expect("a map of versions to times is valid JSON");
+    write(&last_check_file, contents).with_context(write_file_error(&last_check_file))?;
+
+    let releases = cached_node_security_releases()?;
+    if let Some(release) = newer_security_release(pinned, &releases) {
+        warn!(
+            "{} is pinned, but {} is a newer security release of Node {}. Run `volta pin node@{}` to update.",
+            tool_version("node", pinned),
+            release,
+            pinned.major,
+            pinned.major
+        );
+    }
+    Ok(())
+}
+
+/// Reads the time of the last check of each pinned version, as seconds since the Unix epoch
+///
+/// A file that can't be read or parsed, like one written by an earlier version that recorded a
+/// single time for every version, is the same as no checks at all.
+fn read_last_checks(file: &Path) -> BTreeMap<String, u64> {
+    read_to_string(file)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Whether the index should be checked again, given the time of the last check
+fn is_due(last_check: Option<u64>, now: u64) -> bool {
+    match last_check {
+        // A last check in the future means that the clock was changed, so it isn't trusted
+        Some(last_check) if last_check <= now => now - last_check >= CHECK_INTERVAL.as_secs(),
+        _ => true,
+    }
+}
+
+/// Returns the latest security release in the same major line as `pinned`, if it is newer
+fn newer_security_release<'a>(pinned: &Version, releases: &'a [Version]) -> Option<&'a Version> {
+    releases
+        .iter()
+        .filter(|release| release.major == pinned.major && !release.is_prerelease())
+        .filter(|release| *release > pinned)
+        .max()
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_due() {
+        let day = CHECK_INTERVAL.as_secs();
+        assert!(is_due(None, 1_000_000));
+        assert!(!is_due(Some(1_000_000), 1_000_000 + day - 1));
+        assert!(is_due(Some(1_000_000), 1_000_000 + day));
+        assert!(is_due(Some(1_000_000 + day), 1_000_000));
+    }
+
+    #[test]
+    fn test_read_last_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notices.checked");
+        assert!(read_last_checks(&file).is_empty());
+
+        write(&file, "1000000").unwrap();
+        assert!(read_last_checks(&file).is_empty());
+
+        write(&file, r#"{"14.15.0":1000000,"12.20.0":2000000}"#).unwrap();
+        let last_checks = read_last_checks(&file);
+        assert_eq!(last_checks.get("14.15.0"), Some(&1_000_000));
+        assert_eq!(last_checks.get("12.20.0"), Some(&2_000_000));
+        assert_eq!(last_checks.get("15.5.0"), None);
+    }
+
+    #[test]
+    fn test_newer_security_release() {
+        let releases: Vec<Version> = ["12.20.1", "14.15.4", "14.15.5", "15.5.1"]
+            .iter()
+            .map(|version| Version::parse(version).unwrap())
+            .collect();
+        let pinned = |version| Version::parse(version).unwrap();
+
+        assert_eq!(
+            newer_security_release(&pinned("14.15.0"), &releases),
+            Some(&releases[2])
+        );
+        assert_eq!(newer_security_release(&pinned("14.15.5"), &releases), None);
+        assert_eq!(newer_security_release(&pinned("13.14.0"), &releases), None);
+    }
+}
@@ -0,0 +1,329 @@
+//! Provides a cache of the platforms pinned by projects, so that running a shim in a project
+//! that was seen before doesn't need to parse its `package.json`.
+//!
+//! Each project has a small binary file in the Volta cache directory, named after a hash of the
+//! project root, which records the modification time and hash of `package.json` along with the
+//! pinned versions. A project that inherits its platform through `extends` also records every
+//! manifest in the chain, since changing any of them changes the platform. An entry is used as
+//! long as the modification times are unchanged, or the contents still hash to the same values,
+//! and is replaced otherwise.
+//!
+//! Reading and writing the cache is best-effort: any problem is treated as a cache miss.
+
+use std::convert::TryInto;
+use std::fs::{create_dir_all, read, write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::PlatformSpec;
+use crate::layout::volta_home;
+use log::debug;
+use semver::Version;
+use sha1::{Digest, Sha1};
+
+/// Identifies the file format, so that entries written in another format are ignored
+const MAGIC: &[u8; 4] = b"VPC3";
+const HASH_LEN: usize = 20;
+
+/// The state of a manifest when the platform was read from it
+#[derive(Debug, PartialEq)]
+struct ManifestState {
+    file: PathBuf,
+    modified: Duration,
+    hash: [u8; HASH_LEN],
+}
+
+impl ManifestState {
+    fn read(file: &Path) -> Option<Self> {
+        Some(ManifestState {
+            file: file.to_path_buf(),
+            modified: modified(file)?,
+            hash: hash(&read(file).ok()?),
+        })
+    }
+
+    /// Whether the manifest is unchanged, and whether its modification time is too
+    fn check(&self) -> Option<bool> {
+        if modified(&self.file)? == self.modified {
+            return Some(true);
+        }
+        // The file may have been touched without changing, in which case the entry is still valid
+        if hash(&read(&self.file).ok()?) == self.hash {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+/// A cached platform, with the state of `package.json`, and of any manifests it extends, when
+/// it was read
+#[derive(Debug, PartialEq)]
+struct Entry {
+    manifests: Vec<ManifestState>,
+    platform: PlatformSpec,
+}
+
+/// Returns the cached platform for the project at `project_root`, if neither `package.json` nor
+/// any manifest it extends has changed since it was cached.
+pub(crate) fn read_platform(project_root: &Path) -> Option<PlatformSpec> {
+    let cache_file = cache_file(project_root)?;
+    let entry = decode(&read(&cache_file).ok()?)?;
+
+    let mut touched = false;
+    for manifest in &entry.manifests {
+        match manifest.check() {
+            Some(untouched) => touched |= !untouched,
+            None => {
+                debug!(
+                    "Cached platform for '{}' is stale, since '{}' changed",
+                    project_root.display(),
+                    manifest.file.display()
+                );
+                return None;
+            }
+        }
+    }
+    if touched {
+        let extended_files = entry
+            .manifests
+            .iter()
+            .skip(1)
+            .map(|manifest| manifest.file.clone())
+            .collect::<Vec<_>>();
+        write_platform(project_root, &extended_files, &entry.platform);
+    }
+
+    debug!("Using cached platform for '{}'", project_root.display());
+    Some(entry.platform)
+}
+
+/// Caches the platform pinned by the project at `project_root`, where `extended_files` are the
+/// manifests that it inherits its platform from.
+pub(crate) fn write_platform(
+    project_root: &Path,
+    extended_files: &[PathBuf],
+    platform: &PlatformSpec,
+) {
+    let package_file = project_root.join("package.json");
+    let manifests = std::iter::once(package_file.as_path())
+        .chain(extended_files.iter().map(PathBuf::as_path))
+        .map(ManifestState::read)
+        .collect::<Option<Vec<_>>>();
+    let entry = match manifests {
+        Some(manifests) => Entry {
+            manifests,
+            platform: platform.clone(),
+        },
+        None => return,
+    };
+
+    let written = cache_file(project_root).map(|file| {
+        file.parent()
+            .map_or(Ok(()), create_dir_all)
+            .and_then(|_| write(&file, encode(&entry)))
+    });
+
+    if let Some(Err(error)) = written {
+        debug!("Unable to cache platform: {}", error);
+    }
+}
+
+fn cache_file(project_root: &Path) -> Option<PathBuf> {
+    let key = hex::encode(hash(project_root.to_string_lossy().as_bytes()));
+    volta_home().ok().map(|home| home.platform_cache_file(&key))
+}
+
+fn modified(file: &Path) -> Option<Duration> {
+    file.metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+}
+
+fn hash(contents: &[u8]) -> [u8; HASH_LEN] {
+    let mut hasher = Sha1::new();
+    hasher.input(contents);
+
+    let mut hash = [0; HASH_LEN];
+    hash.copy_from_slice(&hasher.result());
+    hash
+}
+
+/// Encodes an entry as the magic bytes and the number of manifests, then for each manifest its
+/// path as a length-prefixed string, its modification time as seconds and nanoseconds and its
+/// hash, and then the Node, npm, Yarn and pnpm versions as length-prefixed strings, with an empty
+/// string for a missing version.
+fn encode(entry: &Entry) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(128);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&(entry.manifests.len() as u16).to_le_bytes());
+    for manifest in &entry.manifests {
+        encode_str(&mut bytes, &manifest.file.to_string_lossy());
+        bytes.extend_from_slice(&manifest.modified.as_secs().to_le_bytes());
+        bytes.extend_from_slice(&manifest.modified.subsec_nanos().to_le_bytes());
+        bytes.extend_from_slice(&manifest.hash);
+    }
+
+    let versions = [
+        Some(&entry.platform.node_runtime),
+        entry.platform.npm.as_ref(),
+        entry.platform.yarn.as_ref(),
+        entry.platform.pnpm.as_ref(),
+    ];
+    for version in versions.iter() {
+        encode_str(
+            &mut bytes,
+            &version.map(Version::to_string).unwrap_or_default(),
+        );
+    }
+    bytes
+}
+
+fn encode_str(bytes: &mut Vec<u8>, value: &str) {
+    bytes.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+fn decode(bytes: &[u8]) -> Option<Entry> {
+    let mut reader = Reader { bytes };
+
+    if reader.take(MAGIC.len())? != &MAGIC[..] {
+        return None;
+    }
+    let count = u16::from_le_bytes(reader.take(2)?.try_into().ok()?);
+    let mut manifests = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let file = PathBuf::from(reader.string()?);
+        let secs = u64::from_le_bytes(reader.take(8)?.try_into().ok()?);
+        let nanos = u32::from_le_bytes(reader.take(4)?.try_into().ok()?);
+        let hash = reader.take(HASH_LEN)?.try_into().ok()?;
+        manifests.push(ManifestState {
+            file,
+            modified: Duration::new(secs, nanos),
+            hash,
+        });
+    }
+
+    let node_runtime = reader.version()??;
+    let npm = reader.version()?;
+    let yarn = reader.version()?;
+    let pnpm = reader.version()?;
+    if !reader.bytes.is_empty() {
+        return None;
+    }
+
+    Some(Entry {
+        manifests,
+        platform: PlatformSpec {
+            node_runtime,
+            npm,
+            yarn,
+            pnpm,
+        },
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < len {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn string(&mut self) -> Option<&'a str> {
+        let len = u16::from_le_bytes(self.take(2)?.try_into().ok()?) as usize;
+        std::str::from_utf8(self.take(len)?).ok()
+    }
+
+    /// Reads a length-prefixed version, returning `Some(None)` for an empty one
+    fn version(&mut self) -> Option<Option<Version>> {
+        let version = self.string()?;
+        if version.is_empty() {
+            Some(None)
+        } else {
+            Version::from_str(version).ok().map(Some)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_encode_decode() {
+        let entry = Entry {
+            manifests: vec![
+                ManifestState {
+                    file: PathBuf::from("/projects/app/package.json"),
+                    modified: SystemTime::now().duration_since(UNIX_EPOCH).unwrap(),
+                    hash: hash(b"{\"volta\": {\"extends\": \"..\"}}"),
+                },
+                ManifestState {
+                    file: PathBuf::from("/projects/package.json"),
+                    modified: SystemTime::now().duration_since(UNIX_EPOCH).unwrap(),
+                    hash: hash(b"{\"volta\": {\"node\": \"10.16.0\", \"yarn\": \"1.17.3\"}}"),
+                },
+            ],
+            platform: PlatformSpec {
+                node_runtime: Version::parse("10.16.0").unwrap(),
+                npm: None,
+                yarn: Some(Version::parse("1.17.3").unwrap()),
+                pnpm: None,
+            },
+        };
+        let bytes = encode(&entry);
+
+        assert_eq!(decode(&bytes), Some(entry));
+        assert_eq!(decode(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(decode(b"VPC2"), None);
+    }
+
+    #[test]
+    fn test_stale_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let parent = dir.path().join("package.json");
+        let child = dir.path().join("child").join("package.json");
+        create_dir_all(child.parent().unwrap()).unwrap();
+        write(&parent, r#"{"volta": {"node": "10.16.0"}}"#).unwrap();
+        write(&child, r#"{"volta": {"extends": ".."}}"#).unwrap();
+
+        let entry = Entry {
+            manifests: vec![
+                ManifestState::read(&child).unwrap(),
+                ManifestState::read(&parent).unwrap(),
+            ],
+            platform: PlatformSpec {
+                node_runtime: Version::parse("10.16.0").unwrap(),
+                npm: None,
+                yarn: None,
+                pnpm: None,
+            },
+        };
+        assert!(entry
+            .manifests
+            .iter()
+            .all(|manifest| manifest.check() == Some(true)));
+
+        // Recording an old modification time stands in for touching the parent, since the
+        // filesystem may not tick between writes
+        let mut parent_state = ManifestState::read(&parent).unwrap();
+        parent_state.modified = Duration::new(0, 0);
+        assert_eq!(parent_state.check(), Some(false));
+
+        // Changing the pin in the parent makes the entry stale, though the child is unchanged
+        write(&parent, r#"{"volta": {"node": "12.0.0"}}"#).unwrap();
+        assert_eq!(parent_state.check(), None);
+        assert_eq!(entry.manifests[0].check(), Some(true));
+    }
+}
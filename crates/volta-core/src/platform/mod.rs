@@ -1,48 +1,94 @@
-use std::env::JoinPathsError;
+use std::env::{self, JoinPathsError};
 use std::ffi::OsString;
 use std::path::PathBuf;
 
 use envoy;
 use semver::Version;
+use serde::{Deserialize, Serialize};
 
 use crate::error::ErrorDetails;
-use crate::layout::{env_paths, volta_home};
+use crate::image::ToolImage;
+use crate::layout::env_paths;
+use crate::project::local_toolchain_dir;
 use crate::session::Session;
+use crate::settings::settings;
 use crate::tool::load_default_npm_version;
 use crate::tool::NodeVersion;
+use crate::trace;
+use crate::version::{option_version_serde, version_serde};
+use cfg_if::cfg_if;
+use log::debug;
 use volta_fail::{Fallible, ResultExt};
 
+pub(crate) mod cache;
 pub mod sourced;
+pub(crate) mod wsl;
 pub use self::sourced::{Source, SourcedImage, SourcedPlatformSpec};
 
 /// A specification of tool versions needed for a platform
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// This is the shape that project pins, the user default and `volta run` all resolve to. It
+/// serializes to the same `{ node, npm, yarn, pnpm }` object as the `volta` key of a manifest,
+/// which is how `volta export` writes the default platform.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct PlatformSpec {
     /// The pinned version of Node.
+    #[serde(rename = "node", with = "version_serde")]
     pub node_runtime: Version,
     /// The pinned version of npm, if any.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_version_serde"
+    )]
     pub npm: Option<Version>,
     /// The pinned version of Yarn, if any.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_version_serde"
+    )]
     pub yarn: Option<Version>,
+    /// The pinned version of pnpm, if any.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_version_serde"
+    )]
+    pub pnpm: Option<Version>,
 }
 
 impl PlatformSpec {
     pub fn checkout(&self, session: &mut Session) -> Fallible<Image> {
+        let _phase = trace::phase("platform checkout");
         session.ensure_node(&self.node_runtime)?;
 
+        // A version of npm other than the one bundled with Node is installed into its own image
+        let bundled_npm = load_default_npm_version(&self.node_runtime)?;
+        let npm = match self.npm {
+            Some(ref npm_version) if *npm_version != bundled_npm => {
+                session.ensure_npm(npm_version)?;
+                Some(npm_version.clone())
+            }
+            _ => None,
+        };
+
         if let Some(ref yarn_version) = self.yarn {
             session.ensure_yarn(yarn_version)?;
         }
 
+        if let Some(ref pnpm_version) = self.pnpm {
+            session.ensure_pnpm(pnpm_version)?;
+        }
+
         Ok(Image {
             node: NodeVersion {
                 runtime: self.node_runtime.clone(),
-                npm: match self.npm {
-                    Some(ref version) => version.clone(),
-                    None => load_default_npm_version(&self.node_runtime)?,
-                },
+                npm: bundled_npm,
             },
+            npm,
             yarn: self.yarn.clone(),
+            pnpm: self.pnpm.clone(),
         })
     }
 }
@@ -50,39 +96,74 @@ impl PlatformSpec {
 /// A platform image.
 #[derive(Clone, Debug)]
 pub struct Image {
-    /// The pinned version of Node.
+    /// The pinned version of Node, along with the version of npm bundled with it.
     pub node: NodeVersion,
+    /// The pinned version of npm, if it differs from the version bundled with Node.
+    pub npm: Option<Version>,
     /// The pinned version of Yarn, if any.
     pub yarn: Option<Version>,
+    /// The pinned version of pnpm, if any.
+    pub pnpm: Option<Version>,
 }
 
 impl Image {
-    fn bins(&self) -> Fallible<Vec<PathBuf>> {
-        let home = volta_home()?;
-        let node_str = self.node.runtime.to_string();
-        let npm_str = self.node.npm.to_string();
-        // ISSUE(#292): Install npm, and handle using that
-        let mut bins = vec![home.node_image_bin_dir(&node_str, &npm_str)];
+    /// The version of npm in use: the pinned version if there is one, or the bundled version
+    pub fn npm(&self) -> &Version {
+        self.npm.as_ref().unwrap_or(&self.node.npm)
+    }
+
+    /// The directories of the image that hold the executables of its tools, in the order they
+    /// are put on the `PATH`
+    fn bin_dirs(&self) -> Fallible<Vec<PathBuf>> {
+        let node = self.node_image()?;
+        let mut bins = Vec::new();
+        // The pinned npm comes before Node, so that it is found instead of the bundled copy
+        if let Some(ref npm) = self.npm {
+            bins.push(ToolImage::layered(|home| ToolImage::npm(home, npm))?.bin_dir());
+        }
+        bins.push(node.bin_dir());
         if let Some(ref yarn) = self.yarn {
-            let yarn_str = yarn.to_string();
-            bins.push(home.yarn_image_bin_dir(&yarn_str));
+            bins.push(ToolImage::layered(|home| ToolImage::yarn(home, yarn))?.bin_dir());
+        }
+        if let Some(ref pnpm) = self.pnpm {
+            bins.push(ToolImage::layered(|home| ToolImage::pnpm(home, pnpm))?.bin_dir());
         }
         Ok(bins)
     }
 
+    /// The image of the Node version, from the project-local toolchain if there is one
+    fn node_image(&self) -> Fallible<ToolImage> {
+        match local_toolchain_dir()? {
+            Some(toolchain_dir) => Ok(ToolImage::local_node(&toolchain_dir, &self.node.runtime)),
+            None => {
+                ToolImage::layered(|home| ToolImage::node(home, &self.node.runtime, &self.node.npm))
+            }
+        }
+    }
+
     /// Produces a modified version of the current `PATH` environment variable that
-    /// will find toolchain executables (Node, Yarn) in the installation directories
+    /// will find toolchain executables (Node, Yarn, pnpm) in the installation directories
     /// for the given versions instead of in the Volta shim directory.
+    ///
+    /// If the `isolatePath` setting is enabled, the current `PATH` is ignored and only the image
+    /// directories and the essential system directories are included, so that executables
+    /// installed outside of Volta can't be picked up by mistake.
     pub fn path(&self) -> Fallible<OsString> {
+        let _phase = trace::phase("PATH construction");
+        if settings()?.isolate_path {
+            debug!("Using an isolated PATH with only the image and system directories");
+            return isolated_path(self.bin_dirs()?);
+        }
+
         let old_path = envoy::path().unwrap_or_else(|| envoy::Var::from(""));
         let mut new_path = old_path.split();
 
-        for remove_path in env_paths()? {
+        for remove_path in env_paths()?.into_iter().chain(cross_os_paths()?) {
             new_path = new_path.remove(remove_path);
         }
 
         new_path
-            .prefix(self.bins()?)
+            .prefix(self.bin_dirs()?)
             .join()
             .with_context(build_path_error)
     }
@@ -100,7 +181,7 @@ impl System {
         let old_path = envoy::path().unwrap_or_else(|| envoy::Var::from(""));
         let mut new_path = old_path.split();
 
-        for remove_path in env_paths()? {
+        for remove_path in env_paths()?.into_iter().chain(cross_os_paths()?) {
             new_path = new_path.remove(remove_path);
         }
 
@@ -108,6 +189,56 @@ impl System {
     }
 }
 
+/// The directories on the `PATH` that belong to the other side of WSL and have Node or a package
+/// manager in them, which are removed so that tools never resolve to Node for the other operating
+/// system, unless the `crossOsPassthrough` setting allows it
+fn cross_os_paths() -> Fallible<Vec<PathBuf>> {
+    if settings()?.cross_os_passthrough {
+        return Ok(Vec::new());
+    }
+
+    let foreign = env::var_os("PATH")
+        .map(|path| wsl::foreign_paths(&path))
+        .unwrap_or_default();
+    for dir in &foreign {
+        debug!(
+            "Removing '{}' from PATH for the other operating system",
+            dir.display()
+        );
+    }
+    Ok(foreign)
+}
+
+/// Builds a `PATH` from the given directories followed by the essential system directories.
+fn isolated_path(bins: Vec<PathBuf>) -> Fallible<OsString> {
+    env::join_paths(bins.into_iter().chain(essential_system_paths())).with_context(build_path_error)
+}
+
+cfg_if! {
+    if #[cfg(unix)] {
+        /// The system directories that the basic commands scripts rely on are installed in
+        fn essential_system_paths() -> Vec<PathBuf> {
+            ["/usr/bin", "/bin", "/usr/sbin", "/sbin"]
+                .iter()
+                .map(PathBuf::from)
+                .collect()
+        }
+    } else if #[cfg(windows)] {
+        /// The system directories that the basic commands scripts rely on are installed in
+        fn essential_system_paths() -> Vec<PathBuf> {
+            let system_root = env::var_os("SystemRoot")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(r"C:\Windows"));
+            vec![
+                system_root.join("System32"),
+                system_root.clone(),
+                system_root.join("System32").join("Wbem"),
+                system_root.join("System32").join("WindowsPowerShell").join("v1.0"),
+            ]
+        }
+    }
+}
+
 fn build_path_error(_err: &JoinPathsError) -> ErrorDetails {
     ErrorDetails::BuildPathError
 }
@@ -170,7 +301,9 @@ mod test {
                 runtime: v123.clone(),
                 npm: v643.clone(),
             },
+            npm: None,
             yarn: None,
+            pnpm: None,
         };
 
         assert_eq!(
@@ -183,7 +316,9 @@ mod test {
                 runtime: v123.clone(),
                 npm: v643.clone(),
             },
+            npm: None,
             yarn: Some(v457.clone()),
+            pnpm: None,
         };
 
         assert_eq!(
@@ -193,6 +328,34 @@ mod test {
                 expected_node_bin, expected_yarn_bin
             ),
         );
+
+        let npm_bin = volta_home()
+            .unwrap()
+            .root()
+            .join("tools")
+            .join("image")
+            .join("npm")
+            .join("10.2.0")
+            .join("bin");
+        let expected_npm_bin = npm_bin.as_path().to_str().unwrap();
+
+        let with_npm_image = Image {
+            node: NodeVersion {
+                runtime: v123.clone(),
+                npm: v643.clone(),
+            },
+            npm: Some(Version::parse("10.2.0").unwrap()),
+            yarn: None,
+            pnpm: None,
+        };
+
+        assert_eq!(
+            with_npm_image.path().unwrap().into_string().unwrap(),
+            format!(
+                "{}:{}:/usr/bin:/blah:/doesnt/matter/bin",
+                expected_npm_bin, expected_node_bin
+            ),
+        );
     }
 
     #[cfg(windows)]
@@ -239,7 +402,9 @@ mod test {
                 runtime: v123.clone(),
                 npm: v643.clone(),
             },
+            npm: None,
             yarn: None,
+            pnpm: None,
         };
 
         assert_eq!(
@@ -252,7 +417,9 @@ mod test {
                 runtime: v123.clone(),
                 npm: v643.clone(),
             },
+            npm: None,
             yarn: Some(v457.clone()),
+            pnpm: None,
         };
 
         assert_eq!(
@@ -304,4 +471,34 @@ mod test {
             expected_path
         );
     }
+
+    #[test]
+    fn test_platform_spec_serde() {
+        let spec: PlatformSpec =
+            serde_json::from_str(r#"{ "node": "12.16.1", "yarn": "1.22.4" }"#).unwrap();
+        assert_eq!(
+            spec,
+            PlatformSpec {
+                node_runtime: Version::parse("12.16.1").unwrap(),
+                npm: None,
+                yarn: Some(Version::parse("1.22.4").unwrap()),
+                pnpm: None,
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&spec).unwrap(),
+            r#"{"node":"12.16.1","yarn":"1.22.4"}"#
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_isolated_path() {
+        let bins = vec![PathBuf::from("/volta/tools/image/node/1.2.3/6.4.3/bin")];
+
+        assert_eq!(
+            isolated_path(bins).unwrap().into_string().unwrap(),
+            "/volta/tools/image/node/1.2.3/6.4.3/bin:/usr/bin:/bin:/usr/sbin:/sbin"
+        );
+    }
 }
@@ -1,12 +1,16 @@
 use std::ffi::OsString;
+use std::fmt;
 use std::rc::Rc;
 
 use super::{Image, PlatformSpec};
+use crate::lockfile::Lockfile;
+use crate::notice::notify_pinned_node;
 use crate::session::Session;
 use crate::tool::NodeVersion;
 use semver::Version;
 use volta_fail::Fallible;
 
+#[derive(Clone, Copy, PartialEq)]
 pub enum Source {
     /// Represents a Platform that came from the user default
     Default,
@@ -18,6 +22,16 @@ pub enum Source {
     ProjectNodeDefaultYarn,
 }
 
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::Project => write!(f, "project"),
+            Source::ProjectNodeDefaultYarn => write!(f, "project (with default Yarn)"),
+        }
+    }
+}
+
 pub struct SourcedPlatformSpec {
     platform: Rc<PlatformSpec>,
     source: Source,
@@ -51,6 +65,10 @@ impl SourcedPlatformSpec {
     }
 
     pub fn checkout(self, session: &mut Session) -> Fallible<SourcedImage> {
+        self.check_lockfile(session)?;
+        if self.source != Source::Default {
+            notify_pinned_node(self.node());
+        }
         let image = self.platform.checkout(session)?;
         Ok(SourcedImage {
             image,
@@ -58,6 +76,23 @@ impl SourcedPlatformSpec {
         })
     }
 
+    /// Verifies that the versions that come from the project are the versions locked in its
+    /// `volta.lock`, if it has one
+    fn check_lockfile(&self, session: &Session) -> Fallible<()> {
+        let all_tools = match self.source {
+            Source::Default => return Ok(()),
+            Source::Project => true,
+            Source::ProjectNodeDefaultYarn => false,
+        };
+
+        if let Some(project) = session.project()? {
+            if let Some(lockfile) = Lockfile::for_dir(project.project_root())? {
+                lockfile.check_platform(&self.platform, all_tools)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn node(&self) -> &Version {
         &self.platform.node_runtime
     }
@@ -70,6 +105,10 @@ impl SourcedPlatformSpec {
         self.platform.yarn.as_ref()
     }
 
+    pub fn pnpm(&self) -> Option<&Version> {
+        self.platform.pnpm.as_ref()
+    }
+
     pub fn source(&self) -> &Source {
         &self.source
     }
@@ -84,10 +123,18 @@ impl SourcedImage {
         &self.image.node
     }
 
+    pub fn npm(&self) -> &Version {
+        self.image.npm()
+    }
+
     pub fn yarn(&self) -> Option<&Version> {
         self.image.yarn.as_ref()
     }
 
+    pub fn pnpm(&self) -> Option<&Version> {
+        self.image.pnpm.as_ref()
+    }
+
     pub fn source(&self) -> &Source {
         &self.source
     }
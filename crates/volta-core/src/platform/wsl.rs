@@ -0,0 +1,125 @@
+//! Provides detection of the directories that belong to the other side of WSL, so that tools for
+//! Linux projects never resolve to Windows executables (and vice versa).
+//!
+//! Under WSL, the Windows `PATH` is appended to the Linux one, and a Windows Node installation
+//! includes extensionless `npm` and `npx` scripts that would run the Windows `node.exe`. On
+//! Windows, directories inside a WSL distribution can be reached through `\\wsl$\`.
+//!
+//! Only the directories that have Node or a package manager in them are left out, so that other
+//! executables for the other side, like `code` or `explorer.exe`, can still be run by scripts.
+
+use std::env;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(target_os = "linux")] {
+        /// Determines whether Volta is running inside WSL
+        pub(crate) fn is_wsl() -> bool {
+            if env::var_os("WSL_DISTRO_NAME").is_some() || env::var_os("WSL_INTEROP").is_some() {
+                return true;
+            }
+
+            std::fs::read_to_string("/proc/sys/kernel/osrelease")
+                .map(|release| release.to_ascii_lowercase().contains("microsoft"))
+                .unwrap_or(false)
+        }
+
+        /// Determines whether a directory is a mounted Windows drive, e.g. `/mnt/c/Program Files`
+        fn is_foreign_path(path: &Path) -> bool {
+            use std::path::Component;
+
+            let components: Vec<Component> = path.components().take(3).collect();
+            match components.as_slice() {
+                [Component::RootDir, Component::Normal(mnt), Component::Normal(drive)] => {
+                    let drive = drive.to_string_lossy();
+                    mnt.to_str() == Some("mnt")
+                        && drive.len() == 1
+                        && drive.chars().all(|c| c.is_ascii_alphabetic())
+                }
+                _ => false,
+            }
+        }
+    } else if #[cfg(windows)] {
+        /// Determines whether Volta is running inside WSL, which is never the case on Windows, but
+        /// the WSL distributions may still be reachable from the `PATH`
+        pub(crate) fn is_wsl() -> bool {
+            false
+        }
+
+        /// Determines whether a directory is inside a WSL distribution, e.g. `\\wsl$\Ubuntu\bin`
+        fn is_foreign_path(path: &Path) -> bool {
+            use std::path::Component;
+
+            match path.components().next() {
+                Some(Component::Prefix(prefix)) => {
+                    let prefix = prefix.as_os_str().to_string_lossy().to_ascii_lowercase();
+                    prefix.starts_with(r"\\wsl$\") || prefix.starts_with(r"\\wsl.localhost\")
+                }
+                _ => false,
+            }
+        }
+    } else {
+        /// Determines whether Volta is running inside WSL, which only exists on Linux and Windows
+        pub(crate) fn is_wsl() -> bool {
+            false
+        }
+
+        fn is_foreign_path(_path: &Path) -> bool {
+            false
+        }
+    }
+}
+
+/// The executables of Node and the package managers, as they are named on either side of WSL
+const TOOLCHAIN_EXECUTABLES: [&str; 10] = [
+    "node", "node.exe", "npm", "npm.cmd", "npx", "npx.cmd", "yarn", "yarn.cmd", "pnpm", "pnpm.cmd",
+];
+
+/// Returns the entries of `path` that belong to the other side of WSL and have Node or a package
+/// manager in them
+pub(crate) fn foreign_paths(path: &OsStr) -> Vec<PathBuf> {
+    if cfg!(target_os = "linux") && !is_wsl() {
+        return Vec::new();
+    }
+
+    env::split_paths(path)
+        .filter(|entry| is_foreign_path(entry) && has_toolchain(entry))
+        .collect()
+}
+
+/// Whether the directory has Node or one of the package managers in it
+fn has_toolchain(dir: &Path) -> bool {
+    TOOLCHAIN_EXECUTABLES
+        .iter()
+        .any(|executable| dir.join(executable).is_file())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_foreign_path() {
+        assert!(is_foreign_path(Path::new("/mnt/c/Program Files/nodejs")));
+        assert!(is_foreign_path(Path::new("/mnt/d")));
+        assert!(!is_foreign_path(Path::new("/mnt/data/bin")));
+        assert!(!is_foreign_path(Path::new("/usr/local/bin")));
+    }
+
+    #[test]
+    fn test_has_toolchain() {
+        let dir = tempfile::tempdir().unwrap();
+        let nodejs = dir.path().join("nodejs");
+        let system32 = dir.path().join("System32");
+        std::fs::create_dir_all(&nodejs).unwrap();
+        std::fs::create_dir_all(&system32).unwrap();
+        std::fs::write(nodejs.join("npm"), "").unwrap();
+        std::fs::write(system32.join("explorer.exe"), "").unwrap();
+
+        assert!(has_toolchain(&nodejs));
+        assert!(!has_toolchain(&system32));
+    }
+}
@@ -4,21 +4,36 @@
 use std::collections::HashMap;
 use std::env;
 use std::ffi::OsStr;
+use std::fs::read_to_string;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::str::FromStr;
+use std::sync::Mutex;
 
+use lazy_static::lazy_static;
 use lazycell::LazyCell;
 use semver::Version;
 
 use crate::error::ErrorDetails;
+use crate::history::{Action, Entry};
 use crate::layout::volta_home;
-use crate::manifest::Manifest;
+use crate::manifest::{Manifest, PackageManager};
 use crate::platform::PlatformSpec;
+use crate::settings::{settings, PackageManagerPrecedence};
 use crate::tool::{load_default_npm_version, BinConfig, NodeVersion};
-use log::debug;
+use crate::trace;
+use crate::version::parse_requirements;
+use log::{debug, warn};
 use volta_fail::{Fallible, ResultExt};
 
+/// Version files used by other Node version managers, in order of precedence
+const NODE_VERSION_FILES: [&str; 2] = [".node-version", ".nvmrc"];
+
+lazy_static! {
+    /// The most recently detected project root, along with the directory it was detected from
+    static ref PROJECT_ROOT: Mutex<Option<(PathBuf, Option<PathBuf>)>> = Mutex::new(None);
+}
+
 /// A lazily loaded Project
 pub struct LazyProject {
     project: LazyCell<Option<Project>>,
@@ -40,12 +55,68 @@ impl LazyProject {
         let project = self.project.try_borrow_mut_with(Project::for_current_dir)?;
         Ok(project.as_mut())
     }
+
+    /// Loads the project rooted at `project_root`, instead of the project containing the
+    /// current directory
+    pub(crate) fn for_root(project_root: &Path) -> Fallible<Self> {
+        let project = LazyCell::new();
+        // The cell is empty, so filling it can't fail
+        let _ = project.fill(Project::for_dir(project_root)?);
+        Ok(LazyProject { project })
+    }
+
+    /// Returns whether the project has already been loaded
+    pub(crate) fn is_loaded(&self) -> bool {
+        self.project.filled()
+    }
 }
 
 /// A Node project tree in the filesystem.
 pub struct Project {
     manifest: Manifest,
     project_root: PathBuf,
+    /// The platform to use if this project isn't pinned itself, either from a Node version file
+    /// in the project root or from the nearest pinned ancestor project
+    fallback_platform: Option<Rc<PlatformSpec>>,
+    /// Whether the `packageManager` field takes precedence over the versions pinned in the
+    /// platform, according to the `packageManagerPrecedence` setting
+    package_manager_overrides: bool,
+}
+
+/// Returns the project-local toolchain directory, `.volta/tools` in the root of the project
+/// containing the current directory, if the `localToolchain` setting is enabled.
+pub fn local_toolchain_dir() -> Fallible<Option<PathBuf>> {
+    if !settings()?.local_toolchain {
+        return Ok(None);
+    }
+
+    Ok(current_project_root()?.map(|root| root.join(".volta").join("tools")))
+}
+
+/// Returns the root of the project containing the current directory, if any.
+///
+/// Settings, hooks and the project itself are all looked up from the project root, so the
+/// result is remembered to avoid walking up the directory tree more than once per invocation.
+pub(crate) fn current_project_root() -> Fallible<Option<PathBuf>> {
+    let current_dir = env::current_dir().with_context(|_| ErrorDetails::CurrentDirError)?;
+    let mut cached = PROJECT_ROOT
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+
+    if let Some((dir, root)) = &*cached {
+        if *dir == current_dir {
+            return Ok(root.clone());
+        }
+    }
+
+    let root = Project::find_dir(&current_dir).map(Path::to_path_buf);
+    *cached = Some((current_dir, root.clone()));
+    Ok(root)
+}
+
+/// Returns true if the `packageManager` field takes precedence over the `volta` key.
+pub(crate) fn package_manager_overrides() -> Fallible<bool> {
+    Ok(settings()?.package_manager_precedence == PackageManagerPrecedence::PackageManager)
 }
 
 fn is_node_root(dir: &Path) -> bool {
@@ -68,9 +139,11 @@ impl Project {
     /// Returns the Node project containing the current working directory,
     /// if any.
     fn for_current_dir() -> Fallible<Option<Project>> {
-        let current_dir: &Path =
-            &env::current_dir().with_context(|_| ErrorDetails::CurrentDirError)?;
-        Self::for_dir(&current_dir)
+        let _phase = trace::phase("project detection");
+        match current_project_root()? {
+            Some(root) => Self::for_dir(&root),
+            None => Ok(None),
+        }
     }
 
     /// Starts at `base_dir` and walks up the directory tree until a package.json file is found
@@ -93,21 +166,129 @@ impl Project {
         match Self::find_dir(base_dir) {
             Some(dir) => {
                 debug!("Found project manifest in '{}'", dir.display());
-                Ok(Some(Project {
-                    manifest: Manifest::for_dir(&dir)?,
+                let manifest = Manifest::for_dir(&dir)?;
+                let fallback_platform = match manifest.platform() {
+                    Some(_) => None,
+                    None => match Self::read_version_files(dir)? {
+                        Some(platform) => Some(platform),
+                        None => Self::find_pinned_ancestor(dir)?,
+                    },
+                };
+
+                let project = Project {
+                    manifest,
                     project_root: PathBuf::from(dir),
-                }))
+                    fallback_platform,
+                    package_manager_overrides: package_manager_overrides()?,
+                };
+                project.check_engines();
+
+                Ok(Some(project))
             }
             None => Ok(None),
         }
     }
 
+    /// Reads the Node version from an `.nvmrc` or `.node-version` file in `project_root`, if
+    /// reading those files has been enabled with the `nodeVersionFiles` setting.
+    fn read_version_files(project_root: &Path) -> Fallible<Option<Rc<PlatformSpec>>> {
+        if !settings()?.node_version_files {
+            return Ok(None);
+        }
+
+        Ok(version_file_platform(project_root))
+    }
+
+    /// Walks up from the parent of `project_root` looking for a project with a `volta` section,
+    /// returning its platform if one is found.
+    ///
+    /// This happens on every shim run in an unpinned project, so ancestors are only checked for
+    /// the `volta` key, and only the first ancestor that pins a platform is loaded in full.
+    fn find_pinned_ancestor(project_root: &Path) -> Fallible<Option<Rc<PlatformSpec>>> {
+        let mut current = project_root.parent().and_then(Self::find_dir);
+
+        while let Some(dir) = current {
+            match Manifest::has_toolchain(dir) {
+                Ok(true) => {
+                    if let Some(platform) = Manifest::for_dir(dir)?.platform() {
+                        debug!("Using platform pinned in '{}'", dir.display());
+                        return Ok(Some(platform));
+                    }
+                }
+                Ok(false) => {}
+                // An unrelated ancestor that can't be parsed shouldn't break the project we
+                // are actually in
+                Err(error) => debug!("Skipping ancestor project '{}': {}", dir.display(), error),
+            }
+            current = dir.parent().and_then(Self::find_dir);
+        }
+
+        Ok(None)
+    }
+
     /// Returns the pinned platform image, if any.
+    ///
+    /// If this project doesn't pin a platform itself, the version from a Node version file or
+    /// the platform of the nearest pinned ancestor project is used instead. A package manager
+    /// named by the `packageManager` field is included according to the
+    /// `packageManagerPrecedence` setting.
     pub fn platform(&self) -> Option<Rc<PlatformSpec>> {
-        self.manifest.platform()
+        let platform = self
+            .manifest
+            .platform()
+            .or_else(|| self.fallback_platform.clone())?;
+
+        Some(self.with_package_manager(platform, self.package_manager_overrides))
+    }
+
+    /// Applies the package manager named by the `packageManager` field, if any, to `platform`.
+    /// A version that `platform` already pins is only replaced if `overrides` is true.
+    pub(crate) fn with_package_manager(
+        &self,
+        platform: Rc<PlatformSpec>,
+        overrides: bool,
+    ) -> Rc<PlatformSpec> {
+        match self.package_manager() {
+            Some(package_manager) => Rc::new(package_manager.apply_to(&platform, overrides)),
+            None => platform,
+        }
+    }
+
+    /// Returns the package manager named by the `packageManager` field, if any.
+    pub fn package_manager(&self) -> Option<&PackageManager> {
+        self.manifest.package_manager.as_ref()
+    }
+
+    /// Returns the `engines.node` requirement for this project, if any.
+    pub fn engines(&self) -> Option<&str> {
+        self.manifest.engines.as_ref().map(String::as_str)
+    }
+
+    /// Warns if the pinned Node version doesn't satisfy the `engines.node` requirement.
+    fn check_engines(&self) {
+        if self.violates_engines() {
+            if let (Some(platform), Some(engines)) = (self.platform(), self.engines()) {
+                warn!(
+                    "The pinned Node version ({}) does not satisfy this project's `engines.node` requirement ({}).",
+                    platform.node_runtime, engines
+                );
+            }
+        }
+    }
+
+    /// Returns true if the pinned Node version doesn't satisfy the `engines.node` requirement.
+    pub(crate) fn violates_engines(&self) -> bool {
+        match (self.platform(), self.engines()) {
+            (Some(platform), Some(engines)) => {
+                satisfies_engines(&platform.node_runtime, engines) == Some(false)
+            }
+            _ => false,
+        }
     }
 
     /// Returns true if the project manifest contains a toolchain.
+    ///
+    /// Note: This only considers the project's own manifest, not any fallback platform.
     pub fn is_pinned(&self) -> bool {
         self.manifest.platform().is_some()
     }
@@ -117,6 +298,11 @@ impl Project {
         &self.manifest
     }
 
+    /// Returns the root directory of this project.
+    pub fn project_root(&self) -> &Path {
+        &self.project_root
+    }
+
     /// Returns the path to the `package.json` file for this project.
     pub fn package_file(&self) -> PathBuf {
         self.project_root.join("package.json")
@@ -197,10 +383,17 @@ impl Project {
             node_runtime: node_version.runtime.clone(),
             npm,
             yarn: self.manifest.yarn(),
+            pnpm: self.manifest.pnpm(),
         };
+        let previous = self
+            .manifest
+            .platform()
+            .map(|platform| platform.node_runtime.clone());
 
         self.manifest.update_platform(updated_platform);
-        self.manifest.write(self.package_file())
+        self.manifest.write(self.package_file())?;
+        self.record_pin("node", previous.as_ref(), Some(&node_version.runtime));
+        Ok(())
     }
 
     /// Writes the specified version of Yarn to the `volta.yarn` key in package.json.
@@ -210,28 +403,135 @@ impl Project {
                 node_runtime: platform.node_runtime.clone(),
                 npm: platform.npm.clone(),
                 yarn: Some(yarn_version.clone()),
+                pnpm: platform.pnpm.clone(),
+            };
+
+            self.manifest.update_platform(updated_platform);
+            self.manifest.write(self.package_file())?;
+            self.record_pin("yarn", platform.yarn.as_ref(), Some(yarn_version));
+            Ok(())
+        } else {
+            Err(ErrorDetails::NoPinnedNodeVersion {
+                tool: "Yarn".into(),
+            }
+            .into())
+        }
+    }
+
+    /// Writes the specified version of a custom tool to the `volta.tools` key in package.json.
+    ///
+    /// Custom tools don't run on Node, so they can be pinned without pinning Node first.
+    pub fn pin_custom_tool(&mut self, name: &str, version: &Version) -> Fallible<()> {
+        let previous = self.manifest.custom_tool(name).cloned();
+        self.manifest.update_custom_tool(name, version.clone());
+        self.manifest.write(self.package_file())?;
+        self.record_pin(name, previous.as_ref(), Some(version));
+        Ok(())
+    }
+
+    /// Writes the specified version of pnpm to the `volta.pnpm` key in package.json.
+    pub fn pin_pnpm(&mut self, pnpm_version: &Version) -> Fallible<()> {
+        if let Some(platform) = self.manifest.platform() {
+            let updated_platform = PlatformSpec {
+                node_runtime: platform.node_runtime.clone(),
+                npm: platform.npm.clone(),
+                yarn: platform.yarn.clone(),
+                pnpm: Some(pnpm_version.clone()),
             };
 
             self.manifest.update_platform(updated_platform);
-            self.manifest.write(self.package_file())
+            self.manifest.write(self.package_file())?;
+            self.record_pin("pnpm", platform.pnpm.as_ref(), Some(pnpm_version));
+            Ok(())
         } else {
-            Err(ErrorDetails::NoPinnedNodeVersion.into())
+            Err(ErrorDetails::NoPinnedNodeVersion {
+                tool: "pnpm".into(),
+            }
+            .into())
         }
     }
 
     /// Writes the specified version of Npm to the `volta.npm` key in package.json.
+    ///
+    /// If the version is the one bundled with the pinned Node, the key is removed instead.
     pub fn pin_npm(&mut self, npm_version: &Version) -> Fallible<()> {
         if let Some(platform) = self.manifest.platform() {
+            let npm = match load_default_npm_version(&platform.node_runtime) {
+                Ok(ref default) if default == npm_version => {
+                    debug!("Not writing 'npm' key since the version matches the Node default");
+                    None
+                }
+                _ => Some(npm_version.clone()),
+            };
+
             let updated_platform = PlatformSpec {
                 node_runtime: platform.node_runtime.clone(),
-                npm: Some(npm_version.clone()),
+                npm,
                 yarn: self.manifest.yarn(),
+                pnpm: self.manifest.pnpm(),
             };
 
             self.manifest.update_platform(updated_platform);
-            self.manifest.write(self.package_file())
+            self.manifest.write(self.package_file())?;
+            self.record_pin("npm", platform.npm.as_ref(), Some(npm_version));
+            Ok(())
         } else {
-            Err(ErrorDetails::NoPinnedNodeVersion.into())
+            Err(ErrorDetails::NoPinnedNodeVersion { tool: "npm".into() }.into())
+        }
+    }
+
+    /// Records a pin in the history, if it changed the pinned version
+    fn record_pin(&self, tool: &str, previous: Option<&Version>, version: Option<&Version>) {
+        if previous != version {
+            Entry::new(Action::Pin, tool, previous, version)
+                .in_project(&self.project_root)
+                .record();
+        }
+    }
+}
+
+/// Returns whether `version` satisfies the `engines` requirement, or `None` if the requirement
+/// can't be parsed.
+pub(crate) fn satisfies_engines(version: &Version, engines: &str) -> Option<bool> {
+    parse_requirements(engines)
+        .ok()
+        .map(|req| req.matches(version))
+}
+
+/// Reads the first Node version file in `dir`, returning a platform if it contains a complete
+/// version. Aliases (e.g. `lts/*`) and partial versions can't be resolved without a network
+/// request on every invocation, so those are ignored with a warning.
+fn version_file_platform(dir: &Path) -> Option<Rc<PlatformSpec>> {
+    let (path, contents) = NODE_VERSION_FILES.iter().find_map(|name| {
+        let path = dir.join(name);
+        read_to_string(&path).ok().map(|contents| (path, contents))
+    })?;
+
+    let raw = contents.lines().next().unwrap_or("").trim();
+    let version = raw.trim_start_matches('v');
+
+    match Version::parse(version) {
+        Ok(node_runtime) => {
+            debug!(
+                "Using Node version {} from '{}'",
+                node_runtime,
+                path.display()
+            );
+            Some(Rc::new(PlatformSpec {
+                node_runtime,
+                npm: None,
+                yarn: None,
+                pnpm: None,
+            }))
+        }
+        Err(_) => {
+            warn!(
+                "Ignoring '{}' in '{}': only complete versions are supported. Use `volta pin node@{}` instead.",
+                raw,
+                path.display(),
+                raw
+            );
+            None
         }
     }
 }
@@ -242,7 +542,8 @@ impl Project {
 pub mod tests {
     use std::path::PathBuf;
 
-    use crate::project::Project;
+    use crate::project::{satisfies_engines, version_file_platform, Project};
+    use semver::Version;
 
     fn fixture_path(fixture_dirs: &[&str]) -> PathBuf {
         let mut cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -290,6 +591,72 @@ pub mod tests {
         assert_eq!(project_dir, fixture_path(&["basic"]));
     }
 
+    #[test]
+    fn test_project_platform_direct() {
+        let project_path = fixture_path(&["basic"]);
+        let test_project = Project::for_dir(&project_path).unwrap().unwrap();
+        let platform = test_project
+            .platform()
+            .expect("Could not find pinned platform");
+
+        assert_eq!(platform.node_runtime.to_string(), "6.11.1");
+        assert!(test_project.is_pinned());
+    }
+
+    #[test]
+    fn test_project_platform_inherited() {
+        let project_path = fixture_path(&["basic", "nested"]);
+        let test_project = Project::for_dir(&project_path).unwrap().unwrap();
+        let platform = test_project
+            .platform()
+            .expect("Could not find inherited platform");
+
+        assert_eq!(
+            test_project.package_file(),
+            project_path.join("package.json")
+        );
+        assert_eq!(platform.node_runtime.to_string(), "6.11.1");
+        assert_eq!(platform.yarn.as_ref().unwrap().to_string(), "1.2.0");
+        assert!(!test_project.is_pinned());
+    }
+
+    #[test]
+    fn test_project_platform_skips_unparseable_ancestor() {
+        let project_path = fixture_path(&["basic", "nested", "broken", "leaf"]);
+        let test_project = Project::for_dir(&project_path).unwrap().unwrap();
+        let platform = test_project
+            .platform()
+            .expect("Could not find inherited platform");
+
+        assert_eq!(platform.node_runtime.to_string(), "6.11.1");
+        assert!(!test_project.is_pinned());
+    }
+
+    #[test]
+    fn test_satisfies_engines() {
+        let version = Version::parse("10.16.0").unwrap();
+
+        assert_eq!(satisfies_engines(&version, ">= 8.9"), Some(true));
+        assert_eq!(satisfies_engines(&version, "^12"), Some(false));
+        assert_eq!(satisfies_engines(&version, "not a requirement"), None);
+    }
+
+    #[test]
+    fn test_version_file_platform() {
+        let project_path = fixture_path(&["nvmrc"]);
+        let platform = version_file_platform(&project_path).expect("Could not read .nvmrc");
+
+        assert_eq!(platform.node_runtime.to_string(), "8.9.4");
+        assert_eq!(platform.npm, None);
+        assert_eq!(platform.yarn, None);
+    }
+
+    #[test]
+    fn test_version_file_platform_missing() {
+        let project_path = fixture_path(&["basic"]);
+        assert!(version_file_platform(&project_path).is_none());
+    }
+
     #[test]
     fn test_project_find_dir_dependency() {
         let base_dir = fixture_path(&["basic", "node_modules", "eslint"]);
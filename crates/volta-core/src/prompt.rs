@@ -0,0 +1,174 @@
+//! Provides minimal interactive prompts, for the commands that can ask the user to choose
+//! instead of requiring an argument, and for the destructive commands that ask for confirmation.
+//!
+//! The prompts are line-based and written to stderr, so that they work in any terminal and the
+//! output of the command itself is left alone. They are only shown when both stdin and stderr
+//! are terminals; commands use their non-interactive default otherwise, and destructive commands
+//! require `--yes` instead.
+
+use std::fmt::Display;
+use std::io::{self, BufRead, Write};
+
+use crate::error::ErrorDetails;
+use atty::Stream;
+use console::style;
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// Whether the user can answer prompts
+pub fn is_interactive() -> bool {
+    atty::is(Stream::Stdin) && atty::is(Stream::Stderr)
+}
+
+/// Asks the user to choose one of `items`, returning the index of the choice
+///
+/// An empty answer chooses the item at `default`.
+pub fn select<T: Display>(message: &str, items: &[T], default: usize) -> Fallible<usize> {
+    let stdin = io::stdin();
+    let stderr = io::stderr();
+    let mut input = stdin.lock();
+    let mut output = stderr.lock();
+    select_from(&mut input, &mut output, message, items, default)
+}
+
+fn select_from<R, W, T>(
+    input: &mut R,
+    output: &mut W,
+    message: &str,
+    items: &[T],
+    default: usize,
+) -> Fallible<usize>
+where
+    R: BufRead,
+    W: Write,
+    T: Display,
+{
+    writeln!(output, "{}", style(message).bold()).with_context(|_| ErrorDetails::PromptError)?;
+    for (index, item) in items.iter().enumerate() {
+        writeln!(output, "  {:>2}) {}", index + 1, item)
+            .with_context(|_| ErrorDetails::PromptError)?;
+    }
+
+    loop {
+        let question = format!("Enter a number [{}]: ", default + 1);
+        let answer = match ask(input, output, &question)? {
+            Some(answer) => answer,
+            // The input was closed before an answer was given
+            None => throw!(ErrorDetails::PromptError),
+        };
+
+        if answer.is_empty() {
+            return Ok(default);
+        }
+        match answer.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= items.len() => return Ok(number - 1),
+            _ => writeln!(output, "Please enter a number from 1 to {}.", items.len())
+                .with_context(|_| ErrorDetails::PromptError)?,
+        }
+    }
+}
+
+/// Asks the user to confirm a destructive `action`, like `uninstall cowsay`
+///
+/// Returns whether the action was confirmed, which it always is with `assume_yes` (from the
+/// `--yes` flag). Since a non-interactive run can't be asked, it fails without `--yes`.
+pub fn confirm(action: &str, assume_yes: bool) -> Fallible<bool> {
+    if assume_yes {
+        return Ok(true);
+    }
+    if !is_interactive() {
+        throw!(ErrorDetails::ConfirmationRequired {
+            action: action.to_string()
+        });
+    }
+
+    let stdin = io::stdin();
+    let stderr = io::stderr();
+    let mut input = stdin.lock();
+    let mut output = stderr.lock();
+    confirm_from(&mut input, &mut output, action)
+}
+
+fn confirm_from<R, W>(input: &mut R, output: &mut W, action: &str) -> Fallible<bool>
+where
+    R: BufRead,
+    W: Write,
+{
+    let question = format!("{}? [y/N]: ", capitalize(action));
+    // Closing the input declines, like any answer other than yes
+    Ok(match ask(input, output, &question)? {
+        Some(answer) => {
+            let answer = answer.to_lowercase();
+            answer == "y" || answer == "yes"
+        }
+        None => false,
+    })
+}
+
+fn capitalize(action: &str) -> String {
+    let mut chars = action.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Writes the question and reads the trimmed answer, which is `None` if the input was closed
+fn ask<R, W>(input: &mut R, output: &mut W, question: &str) -> Fallible<Option<String>>
+where
+    R: BufRead,
+    W: Write,
+{
+    write!(output, "{}", question)
+        .and_then(|()| output.flush())
+        .with_context(|_| ErrorDetails::PromptError)?;
+
+    let mut answer = String::new();
+    let read = input
+        .read_line(&mut answer)
+        .with_context(|_| ErrorDetails::PromptError)?;
+    if read == 0 {
+        Ok(None)
+    } else {
+        Ok(Some(answer.trim().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn select_with(answers: &str) -> Fallible<usize> {
+        let mut input = Cursor::new(answers.as_bytes());
+        let mut output = Vec::new();
+        select_from(&mut input, &mut output, "Pick one", &["a", "b", "c"], 0)
+    }
+
+    #[test]
+    fn test_select() {
+        assert_eq!(select_with("\n").unwrap(), 0);
+        assert_eq!(select_with("3\n").unwrap(), 2);
+        assert_eq!(select_with("seven\n4\n2\n").unwrap(), 1);
+        assert!(select_with("").is_err());
+    }
+
+    fn confirm_with(answer: &str) -> bool {
+        let mut input = Cursor::new(answer.as_bytes());
+        let mut output = Vec::new();
+        let confirmed = confirm_from(&mut input, &mut output, "uninstall cowsay").unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Uninstall cowsay? [y/N]: "
+        );
+        confirmed
+    }
+
+    #[test]
+    fn test_confirm() {
+        assert!(confirm_with("y\n"));
+        assert!(confirm_with("Yes\n"));
+        assert!(!confirm_with("\n"));
+        assert!(!confirm_with("nope\n"));
+        assert!(!confirm_with(""));
+    }
+}
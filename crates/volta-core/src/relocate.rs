@@ -0,0 +1,382 @@
+//! Provides the move of the Volta home to a new directory behind `volta relocate`, so that users
+//! can move their tools to another disk without fetching everything again.
+//!
+//! The Volta lock is held while the home is moved, so that no other Volta process changes it in
+//! the meantime. The home is renamed when the destination is on the same volume, and copied
+//! otherwise. Afterwards, the symlinks and metadata files that point into the old location are
+//! rewritten to point into the new one, as is the link from npm's `_npx` directory to Volta's
+//! npx cache. A copied home is only removed from its old location last, once the lock file in it
+//! has been released, and failing to remove it leaves the relocation in place. Updating the
+//! shell profiles is left to the command.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf, MAIN_SEPARATOR};
+
+use crate::error::ErrorDetails;
+use crate::fs::{delete_dir_error, read_file_error, symlink_error, write_file_error};
+use crate::integrity;
+use crate::layout::{long_path, volta_home, VoltaHome};
+use crate::run::npx::relink_npx_dir;
+use crate::session::Session;
+use crate::settings::check_mutable;
+use crate::sync::VoltaLock;
+use crate::tool::npm_cache_dir;
+use fs_utils::ensure_containing_dir_exists;
+use log::{debug, warn};
+use volta_fail::{throw, Fallible, ResultExt};
+use walkdir::WalkDir;
+
+/// The extensions of the metadata files that may record absolute paths into the Volta home
+const METADATA_EXTENSIONS: &[&str] = &["json", "jsonl"];
+
+/// Moves the Volta home to `to`, returning the home at its new location
+///
+/// Nothing is recorded in the old home for the rest of the session, so that it isn't created
+/// again.
+pub fn relocate(to: &Path, session: &mut Session) -> Fallible<VoltaHome> {
+    check_mutable("relocate the Volta home")?;
+    let from = normalize(&absolute(volta_home()?.root())?);
+    let to = normalize(&absolute(to)?);
+
+    if is_inside(&to, &from) {
+        throw!(ErrorDetails::RelocateIntoHomeError { path: to });
+    }
+    if to.exists() {
+        let empty = fs::read_dir(&to)
+            .map(|mut entries| entries.next().is_none())
+            .unwrap_or(false);
+        if !empty {
+            throw!(ErrorDetails::RelocateDestinationError { path: to });
+        }
+        fs::remove_dir(&to).with_context(delete_dir_error(&to))?;
+    }
+
+    let lock = VoltaLock::acquire()?;
+    let copied = move_dir(&from, &to)?;
+    session.skip_metrics();
+    let home = VoltaHome::new(to);
+    rewrite_paths(&from, &home)?;
+    relink_npx(&from, &home, session);
+    drop(lock);
+
+    if copied {
+        if let Err(error) = fs::remove_dir_all(long_path(&from)) {
+            warn!(
+                "Could not remove the old Volta home at '{}', which can be deleted by hand: {}",
+                from.display(),
+                error
+            );
+        }
+    }
+    Ok(home)
+}
+
+fn absolute(path: &Path) -> Fallible<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_path_buf())
+    } else {
+        let cwd = env::current_dir().with_context(|_| ErrorDetails::CurrentDirError)?;
+        Ok(cwd.join(path))
+    }
+}
+
+/// Resolves the `.` and `..` components of a path, without following any links
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Whether `path` is `dir` or inside of it, either as written or once links are followed
+///
+/// The destination may not exist yet, so the links are resolved for its closest existing
+/// ancestor.
+fn is_inside(path: &Path, dir: &Path) -> bool {
+    if path.starts_with(dir) {
+        return true;
+    }
+
+    let dir = match dunce::canonicalize(dir) {
+        Ok(dir) => dir,
+        Err(_) => return false,
+    };
+    path.ancestors()
+        .find_map(|ancestor| dunce::canonicalize(ancestor).ok())
+        .map_or(false, |ancestor| ancestor.starts_with(dir))
+}
+
+/// Renames the directory, falling back to copying it when the destination is on another volume,
+/// and returns whether it was copied, in which case `from` is left for the caller to remove
+fn move_dir(from: &Path, to: &Path) -> Fallible<bool> {
+    let relocate_error = || ErrorDetails::RelocateError {
+        from: from.to_path_buf(),
+        to: to.to_path_buf(),
+    };
+    ensure_containing_dir_exists(&to).with_context(|_| ErrorDetails::ContainingDirError {
+        path: to.to_path_buf(),
+    })?;
+
+    if let Err(error) = fs::rename(from, to) {
+        debug!(
+            "Could not rename the Volta home, copying it instead: {}",
+            error
+        );
+        // The lock file is held open by this process, so it is left out of the copy. It is
+        // created again the next time the lock is taken.
+        let home = volta_home()?;
+        let skip = home
+            .lock_file()
+            .strip_prefix(home.root())
+            .unwrap_or_else(|_| home.lock_file());
+        if let Err(error) = copy_tree(from, to, skip) {
+            // A partial copy would be mistaken for a Volta home, so it is removed
+            let _ = fs::remove_dir_all(long_path(to));
+            return Err(error).with_context(|_| relocate_error());
+        }
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// Copies every entry of `from` into `to` except for `skip`, which is relative to `from`,
+/// keeping symlinks as symlinks, so that the shims still point to the shim executable
+fn copy_tree(from: &Path, to: &Path, skip: &Path) -> io::Result<()> {
+    for entry in WalkDir::new(long_path(from)) {
+        let entry = entry?;
+        let relative = entry
+            .path()
+            .strip_prefix(long_path(from))
+            .expect("the walked entries are inside the directory");
+        if relative == skip {
+            continue;
+        }
+        let dest = long_path(to).join(relative);
+        let file_type = entry.file_type();
+
+        if file_type.is_dir() {
+            fs::create_dir_all(&dest)?;
+        } else if file_type.is_symlink() {
+            symlink(&fs::read_link(entry.path())?, &dest)?;
+        } else {
+            fs::copy(entry.path(), &dest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Points the symlinks and metadata files that refer to the old location to the new one
+fn rewrite_paths(from: &Path, home: &VoltaHome) -> Fallible<()> {
+    let to = home.root();
+
+    for entry in WalkDir::new(long_path(to))
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        let path = entry.path();
+        if entry.file_type().is_symlink() {
+            let target = fs::read_link(path).with_context(read_file_error(&path))?;
+            if let Ok(relative) = target.strip_prefix(from) {
+                let new_target = to.join(relative);
+                debug!("Pointing {} to {}", path.display(), new_target.display());
+                fs::remove_file(path)
+                    .and_then(|()| symlink(&new_target, path))
+                    .with_context(symlink_error(&path, &new_target))?;
+            }
+        } else if entry.file_type().is_file() && is_metadata(path, home) {
+            let contents = fs::read_to_string(path).with_context(read_file_error(&path))?;
+            if let Some(rewritten) = replace_path_prefix(&contents, from, to) {
+                debug!("Rewriting the paths in {}", path.display());
+                fs::write(path, rewritten).with_context(write_file_error(&path))?;
+                // The rewritten settings would no longer match their signature
+                let path = dunce::simplified(path);
+                if integrity::is_signed(home, path) {
+                    integrity::sign(home, path)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Points npm's `_npx` directory to the npx cache of the new home, if it was linked to the old one
+///
+/// The link is outside of the Volta home, so failing to update it is only reported.
+fn relink_npx(from: &Path, home: &VoltaHome, session: &Session) {
+    let npm_cache = match npm_cache_dir(session) {
+        Ok(Some(npm_cache)) => npm_cache,
+        Ok(None) => return,
+        Err(error) => {
+            debug!("Could not find the npm cache: {}", error);
+            return;
+        }
+    };
+    let old_npx_dir = VoltaHome::new(from.to_path_buf())
+        .npx_cache_dir()
+        .to_path_buf();
+    match relink_npx_dir(&npm_cache, &old_npx_dir, home.npx_cache_dir()) {
+        Ok(true) => debug!(
+            "Pointing the npx directory in '{}' to '{}'",
+            npm_cache.display(),
+            home.npx_cache_dir().display()
+        ),
+        Ok(false) => {}
+        Err(error) => warn!(
+            "Could not point the npx directory in '{}' to '{}': {}",
+            npm_cache.display(),
+            home.npx_cache_dir().display(),
+            error
+        ),
+    }
+}
+
+/// Whether the file is Volta metadata, as opposed to a file of a tool image or archive
+fn is_metadata(path: &Path, home: &VoltaHome) -> bool {
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    // The walked paths may carry the long path prefix on Windows, so it is compared without it
+    let path = dunce::simplified(path);
+
+    extension.map_or(false, |extension| METADATA_EXTENSIONS.contains(&extension))
+        && !path.starts_with(home.image_dir())
+        && !path.starts_with(home.inventory_dir())
+}
+
+/// Replaces `from` with `to` at the start of every JSON string that is `from` or a path inside
+/// of it, returning `None` if there are none
+///
+/// Other strings that merely start with the same text, such as a sibling directory
+/// `~/.volta-old` for `~/.volta`, are left as they are.
+fn replace_path_prefix(contents: &str, from: &Path, to: &Path) -> Option<String> {
+    let old_text = format!("\"{}", json_text(from));
+    let new_text = format!("\"{}", json_text(to));
+    let separator = json_text(Path::new(&MAIN_SEPARATOR.to_string()));
+
+    let mut rewritten = String::with_capacity(contents.len());
+    let mut rest = contents;
+    let mut replaced = false;
+    while let Some(index) = rest.find(&old_text) {
+        let after = &rest[index + old_text.len()..];
+        rewritten.push_str(&rest[..index]);
+        if after.starts_with('"') || after.starts_with(&separator) {
+            rewritten.push_str(&new_text);
+            replaced = true;
+        } else {
+            rewritten.push_str(&old_text);
+        }
+        rest = after;
+    }
+    rewritten.push_str(rest);
+
+    if replaced {
+        Some(rewritten)
+    } else {
+        None
+    }
+}
+
+/// Returns the path as it appears inside a JSON string, with its separators escaped
+fn json_text(path: &Path) -> String {
+    let quoted = serde_json::to_string(&path.to_string_lossy()).expect("strings can be encoded");
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, read_to_string, write};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rewrite_paths() {
+        let dir = tempdir().unwrap();
+        let from = dir.path().join("old");
+        let home = VoltaHome::new(dir.path().join("new"));
+
+        create_dir_all(home.default_bin_dir()).unwrap();
+        create_dir_all(home.image_dir()).unwrap();
+        let bin_config = home.default_tool_bin_config("tsc");
+        let image_file = home.image_dir().join("package.json");
+        let contents = json_contents(&from.join("bin"));
+        write(&bin_config, &contents).unwrap();
+        write(&image_file, &contents).unwrap();
+
+        rewrite_paths(&from, &home).unwrap();
+        assert_eq!(
+            read_to_string(&bin_config).unwrap(),
+            json_contents(&home.root().join("bin"))
+        );
+        assert_eq!(read_to_string(&image_file).unwrap(), contents);
+    }
+
+    #[test]
+    fn test_rewrite_paths_keeps_siblings() {
+        let from = Path::new("/home/user/.volta");
+        let to = Path::new("/data/volta");
+        let contents = format!(
+            "[{}, {}, {}]",
+            json_contents(from),
+            json_contents(&from.join("bin")),
+            json_contents(Path::new("/home/user/.volta-old/bin"))
+        );
+
+        assert_eq!(
+            replace_path_prefix(&contents, from, to),
+            Some(format!(
+                "[{}, {}, {}]",
+                json_contents(to),
+                json_contents(&to.join("bin")),
+                json_contents(Path::new("/home/user/.volta-old/bin"))
+            ))
+        );
+        assert_eq!(
+            replace_path_prefix(&json_contents(Path::new("/home/user/.volta-old")), from, to),
+            None
+        );
+    }
+
+    #[test]
+    fn test_relocate_into_home() {
+        let dir = tempdir().unwrap();
+        let home = dir.path().join(".volta");
+        create_dir_all(&home).unwrap();
+
+        let dotted = home.join("..").join(".volta").join("moved");
+        assert!(is_inside(&normalize(&dotted), &home));
+        assert!(is_inside(&normalize(&home), &home));
+        assert!(!is_inside(
+            &normalize(&dir.path().join(".volta-old")),
+            &home
+        ));
+        assert!(!is_inside(
+            &normalize(&home.join("..").join("moved")),
+            &home
+        ));
+    }
+
+    fn json_contents(path: &Path) -> String {
+        format!("{{\"path\":\"{}\"}}", json_text(path))
+    }
+}
@@ -8,8 +8,8 @@ use crate::layout::volta_home;
 use crate::platform::PlatformSpec;
 use crate::platform::Source;
 use crate::session::{ActivityKind, Session};
-use crate::tool::bin_full_path;
-use crate::tool::{BinConfig, BinLoader};
+use crate::tool::{bin_full_path, pinned_platform};
+use crate::tool::{BinConfig, BinLoader, ScriptKind};
 
 use log::debug;
 use volta_fail::{throw, Fallible};
@@ -25,8 +25,7 @@ where
         // check if the executable is a direct dependency
         if project.has_direct_bin(&exe)? {
             // use the full path to the file
-            let mut path_to_bin = project.local_bin_dir();
-            path_to_bin.push(&exe);
+            let path_to_bin = local_bin_path(project.local_bin_dir(), &exe);
 
             if !path_to_bin.is_file() {
                 throw!(ErrorDetails::ProjectLocalBinaryNotFound {
@@ -39,6 +38,8 @@ where
                 exe.to_string_lossy(),
                 path_to_bin.display()
             );
+            // Wrapper scripts that can't run on their own are run through their loader
+            let loader = ScriptKind::from_path(&path_to_bin).and_then(ScriptKind::loader);
             let path_to_bin = path_to_bin.as_os_str();
 
             if let Some(platform) = session.current_platform()? {
@@ -53,7 +54,14 @@ where
 
                 let image = platform.checkout(session)?;
                 let path = image.path()?;
-                return Ok(ToolCommand::project_local(&path_to_bin, args, &path));
+                return Ok(match loader {
+                    Some(loader) => ToolCommand::project_local(
+                        loader.command.as_ref(),
+                        loader_args(loader.args, path_to_bin.to_owned(), args),
+                        &path,
+                    ),
+                    None => ToolCommand::project_local(&path_to_bin, args, &path),
+                });
             }
 
             // if there's no platform available, pass through to existing PATH.
@@ -80,12 +88,7 @@ where
         let cmd = match default_tool.loader {
             Some(loader) => ToolCommand::direct(
                 loader.command.as_ref(),
-                loader
-                    .args
-                    .iter()
-                    .map(OsString::from)
-                    .chain(once(tool_path))
-                    .chain(args),
+                loader_args(loader.args, tool_path, args),
                 &path,
             ),
             None => ToolCommand::direct(&tool_path, args, &path),
@@ -108,6 +111,40 @@ where
     )
 }
 
+/// The arguments for running a script through its loader
+fn loader_args<A>(
+    loader_args: Vec<String>,
+    script: OsString,
+    args: A,
+) -> impl Iterator<Item = OsString>
+where
+    A: IntoIterator<Item = OsString>,
+{
+    loader_args
+        .into_iter()
+        .map(OsString::from)
+        .chain(once(script))
+        .chain(args)
+}
+
+/// Finds a project's local binary in `node_modules/.bin`
+///
+/// On Windows, package managers write `.cmd` and `.ps1` wrappers next to the shell script for
+/// each binary, and the shell script can't be run without a Unix shell, so a wrapper is used
+/// when there is one.
+fn local_bin_path(bin_dir: PathBuf, exe: &OsStr) -> PathBuf {
+    let path = bin_dir.join(exe);
+    if cfg!(windows) {
+        for extension in &["cmd", "ps1"] {
+            let script = path.with_extension(extension);
+            if script.is_file() {
+                return script;
+            }
+        }
+    }
+    path
+}
+
 /// Information about the location and execution context of default binaries
 ///
 /// Fetched from the config files in the Volta directory, represents the binary that is executed
@@ -123,22 +160,42 @@ impl DefaultBinary {
         let bin_path = bin_full_path(
             &bin_config.package,
             &bin_config.version,
+            &bin_config.platform.node_runtime,
             &bin_config.name,
             &bin_config.path,
+            bin_config.link.as_deref(),
         )?;
 
-        // If the user does not have yarn set in the platform for this binary, use the default
+        // Linked packages use the platform pinned in their working copy, which may have changed
+        // since they were linked
+        let bin_config = match bin_config.link.as_deref().and_then(pinned_platform) {
+            Some(platform) => BinConfig {
+                platform,
+                ..bin_config
+            },
+            None => bin_config,
+        };
+
+        // If the user does not have yarn or pnpm set in the platform for this binary, use the default
         // This is necessary because some tools (e.g. ember-cli with the `--yarn` option) invoke `yarn`
-        let platform = match bin_config.platform.yarn {
-            Some(_) => bin_config.platform,
-            None => {
-                let yarn = session
-                    .default_platform()?
-                    .and_then(|ref plat| plat.yarn.clone());
-                PlatformSpec {
-                    yarn,
-                    ..bin_config.platform
-                }
+        let platform = if bin_config.platform.yarn.is_some() && bin_config.platform.pnpm.is_some() {
+            bin_config.platform
+        } else {
+            let default = session.default_platform()?;
+            let yarn = bin_config
+                .platform
+                .yarn
+                .clone()
+                .or_else(|| default.as_ref().and_then(|plat| plat.yarn.clone()));
+            let pnpm = bin_config
+                .platform
+                .pnpm
+                .clone()
+                .or_else(|| default.as_ref().and_then(|plat| plat.pnpm.clone()));
+            PlatformSpec {
+                yarn,
+                pnpm,
+                ..bin_config.platform
             }
         };
 
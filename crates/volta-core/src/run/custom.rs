@@ -0,0 +1,71 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::iter::once;
+
+use super::ToolCommand;
+use crate::error::ErrorDetails;
+use crate::platform::System;
+use crate::session::{ActivityKind, Session};
+use crate::style::tool_version;
+use crate::tool::CustomTool;
+
+use log::debug;
+use volta_fail::{Fallible, ResultExt};
+
+/// Builds the command for an executable provided by the custom tool `name`, using the version
+/// pinned in the project, or the default version otherwise
+pub(crate) fn command<A>(
+    name: String,
+    exe: &OsStr,
+    args: A,
+    session: &mut Session,
+) -> Fallible<ToolCommand>
+where
+    A: IntoIterator<Item = OsString>,
+{
+    session.add_event_start(ActivityKind::Tool);
+
+    let project_version = session
+        .project()?
+        .and_then(|project| project.manifest().custom_tool(&name).cloned());
+    let (version, source) = match project_version {
+        Some(version) => (version, "project"),
+        None => match session.toolchain()?.custom_tool(&name) {
+            Some(version) => (version.clone(), "default"),
+            None => {
+                debug!(
+                    "Could not find Volta-managed {}, delegating to system",
+                    name
+                );
+                return ToolCommand::passthrough(
+                    exe,
+                    args,
+                    ErrorDetails::NoCustomToolVersion { tool: name },
+                );
+            }
+        },
+    };
+
+    debug!(
+        "Using {} from {} configuration",
+        tool_version(&name, &version),
+        source
+    );
+    session.add_event_tool_use(ActivityKind::Tool, version.to_string());
+
+    let hooks = match session.hooks()?.custom_tool(&name) {
+        Some(hooks) => hooks.clone(),
+        None => {
+            return Err(ErrorDetails::PackageNotFound { package: name }.into());
+        }
+    };
+    let tool = CustomTool::new(name, version, hooks);
+    tool.fetch_internal()?;
+
+    // Custom tools don't run on Node, so only their own executables are added to the PATH
+    let system_path = System::path()?;
+    let path = env::join_paths(once(tool.bin_dir()?).chain(env::split_paths(&system_path)))
+        .with_context(|_| ErrorDetails::BuildPathError)?;
+
+    Ok(ToolCommand::direct(exe, args, &path))
+}
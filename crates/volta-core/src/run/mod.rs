@@ -3,38 +3,55 @@
 use std::env::{self, args_os, ArgsOs};
 use std::ffi::{OsStr, OsString};
 use std::fmt;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus, Output};
 
 use crate::command::create_command;
+use crate::env_vars;
 use crate::error::ErrorDetails;
-use crate::platform::System;
+use crate::layout::{volta_home, volta_install};
+use crate::platform::{PlatformSpec, System};
 use crate::session::Session;
-use crate::signal::pass_control_to_shim;
+use crate::settings::settings;
+use crate::signal::{forward_to_child, pass_control_to_shim};
+use crate::stats;
+use crate::style::tool_version;
+use crate::tool;
+use crate::trace;
+use dunce::canonicalize;
+use log::debug;
 use volta_fail::{throw, Fallible, ResultExt};
 
 pub mod binary;
+mod custom;
 pub mod node;
 pub mod npm;
 pub mod npx;
+pub mod pnpm;
+pub mod which;
 pub mod yarn;
 
-const VOLTA_BYPASS: &str = "VOLTA_BYPASS";
-const UNSAFE_GLOBAL: &str = "VOLTA_UNSAFE_GLOBAL";
-
-/// Distinguish global `add` commands in npm or yarn from all others.
+/// Distinguish global `add` commands in npm, yarn or pnpm from all others.
 enum CommandArg {
-    /// The command is a *global* add command.
-    GlobalAdd(Option<OsString>),
-    /// The command is a local, i.e. non-global, add command.
+    /// The command is a *global* add command, with the packages it adds.
+    GlobalAdd(Vec<OsString>),
+    /// The command is a *global* remove command, with the packages it removes.
+    GlobalRemove(Vec<OsString>),
+    /// The command lists the global packages.
+    GlobalList,
+    /// The command is a local, i.e. non-global, command.
     NotGlobalAdd,
 }
 
 pub fn execute_tool(session: &mut Session) -> Fallible<ExitStatus> {
     let mut args = args_os();
     let exe = get_tool_name(&mut args)?;
+    if exe != "volta-shim" {
+        stats::record(&exe);
+    }
 
-    let command = if env::var_os(VOLTA_BYPASS).is_some() {
+    let executor: Executor = if env_vars::is_set(env_vars::VOLTA_BYPASS) {
         ToolCommand::passthrough(
             &exe,
             args,
@@ -42,17 +59,166 @@ pub fn execute_tool(session: &mut Session) -> Fallible<ExitStatus> {
                 command: exe.to_string_lossy().to_string(),
             },
         )?
+        .into()
     } else {
         match &exe.to_str() {
             Some("volta-shim") => throw!(ErrorDetails::RunShimDirectly),
-            Some("node") => node::command(args, session)?,
+            Some("node") => node::command(args, session)?.into(),
             Some("npm") => npm::command(args, session)?,
-            Some("npx") => npx::command(args, session)?,
+            Some("npx") => npx::command(args, session)?.into(),
             Some("yarn") => yarn::command(args, session)?,
-            _ => binary::command(exe, args, session)?,
+            Some("pnpm") => pnpm::command(args, session)?.into(),
+            _ => match custom_tool_for(&exe, session) {
+                Some(name) => custom::command(name, &exe, args, session)?.into(),
+                None => binary::command(exe, args, session)?.into(),
+            },
+        }
+    };
+
+    executor.execute(session)
+}
+
+/// What a shim does once the tool and its platform have been determined
+pub(crate) enum Executor {
+    /// Runs the tool
+    Tool(ToolCommand),
+
+    /// Installs packages with Volta, for a global install that was intercepted, with the npm
+    /// flags that were given to it
+    PackageInstall(Vec<tool::Spec>, tool::NpmFlags),
+
+    /// Uninstalls packages with Volta, for a global remove that was intercepted
+    PackageUninstall(Vec<tool::Spec>),
+
+    /// Lists the packages installed with Volta, for a global list that was intercepted
+    PackageList,
+}
+
+impl Executor {
+    fn execute(self, session: &mut Session) -> Fallible<ExitStatus> {
+        match self {
+            Executor::Tool(command) => {
+                command.check_recursion()?;
+                pass_control_to_shim();
+                command.status()
+            }
+            Executor::PackageInstall(specs, flags) => {
+                session.set_npm_flags(flags);
+                for spec in specs {
+                    spec.resolve(session)?.install(session)?;
+                }
+                Ok(success_status())
+            }
+            Executor::PackageUninstall(specs) => {
+                for spec in specs {
+                    spec.uninstall()?;
+                }
+                Ok(success_status())
+            }
+            Executor::PackageList => {
+                for package in session.inventory()?.packages.iter() {
+                    println!("{}", tool_version(&package.name, &package.version));
+                    for bin in &package.bins {
+                        println!("  - {}", bin);
+                    }
+                }
+                Ok(success_status())
+            }
         }
+    }
+}
+
+impl From<ToolCommand> for Executor {
+    fn from(command: ToolCommand) -> Self {
+        Executor::Tool(command)
+    }
+}
+
+/// Parses the packages of an intercepted global install, so that they can be installed by Volta
+///
+/// Without any packages, the global install would be of the current directory, which Volta can't
+/// install, so that is reported as an unsupported global install instead.
+fn package_install(packages: Vec<OsString>, flags: tool::NpmFlags) -> Fallible<Executor> {
+    if packages.is_empty() {
+        throw!(ErrorDetails::NoGlobalInstalls { package: None });
+    }
+
+    let specs = package_specs(&packages)?;
+    for spec in &specs {
+        debug!("Intercepted global install, installing {} with Volta", spec);
+    }
+    Ok(Executor::PackageInstall(specs, flags))
+}
+
+/// Parses the packages of an intercepted global remove, so that they can be uninstalled by Volta
+fn package_uninstall(packages: Vec<OsString>) -> Fallible<Executor> {
+    let specs = package_specs(&packages)?;
+    for spec in &specs {
+        debug!(
+            "Intercepted global remove, uninstalling {} with Volta",
+            spec
+        );
+    }
+    Ok(Executor::PackageUninstall(specs))
+}
+
+fn package_specs(packages: &[OsString]) -> Fallible<Vec<tool::Spec>> {
+    packages
+        .iter()
+        .map(|package| match package.to_str() {
+            Some(package) => tool::Spec::try_from_str(package),
+            None => Err(ErrorDetails::ParseToolSpecError {
+                tool_spec: package.to_string_lossy().to_string(),
+            }
+            .into()),
+        })
+        .collect()
+}
+
+/// Returns the name of the custom tool that provides the executable `exe`, if any
+///
+/// Invalid hooks are reported by the commands that use them, so they don't prevent package
+/// executables from running here. An executable of an installed package is never taken over
+/// by a custom tool, even if the hooks declare one with the same name.
+fn custom_tool_for(exe: &OsStr, session: &Session) -> Option<String> {
+    let bin = exe.to_str()?;
+    let hooks = session.hooks().ok()?;
+    let name = hooks.custom_tool_for_bin(bin)?;
+
+    if volta_home().ok()?.default_tool_bin_config(bin).exists() {
+        debug!(
+            "'{}' is provided by an installed package, not the custom tool {}",
+            bin, name
+        );
+        return None;
+    }
+
+    Some(name.to_string())
+}
+
+/// Executes a command using the given platform, regardless of any project or default pins
+pub fn execute_with_platform<A>(
+    exe: &OsStr,
+    args: A,
+    platform: &PlatformSpec,
+    session: &mut Session,
+) -> Fallible<ExitStatus>
+where
+    A: IntoIterator<Item = OsString>,
+{
+    let image = platform.checkout(session)?;
+    let path = image.path()?;
+
+    let command = ToolCommand {
+        command: command_for(exe, args, &path),
+        exe: exe.to_os_string(),
+        path,
+        on_failure: ErrorDetails::RunCommandError {
+            command: exe.to_string_lossy().to_string(),
+        },
     };
 
+    command.check_recursion()?;
     pass_control_to_shim();
     command.status()
 }
@@ -62,6 +228,12 @@ pub(crate) struct ToolCommand {
     /// The command that will execute a tool with the right PATH context
     command: Command,
 
+    /// The executable that will be run, either a path or a name to look up in `path`
+    exe: OsString,
+
+    /// The PATH that the executable will be run with
+    path: OsString,
+
     /// The Volta error with which to wrap any failure.
     ///
     /// This allows us to call out to the system for the pass-through behavior, but still
@@ -77,6 +249,8 @@ impl ToolCommand {
     {
         ToolCommand {
             command: command_for(exe, args, path_var),
+            exe: exe.to_os_string(),
+            path: path_var.to_os_string(),
             on_failure: ErrorDetails::BinaryExecError,
         }
     }
@@ -88,6 +262,8 @@ impl ToolCommand {
     {
         ToolCommand {
             command: command_for(exe, args, path_var),
+            exe: exe.to_os_string(),
+            path: path_var.to_os_string(),
             on_failure: ErrorDetails::ProjectLocalBinaryExecError {
                 command: exe.to_string_lossy().to_string(),
             },
@@ -106,6 +282,8 @@ impl ToolCommand {
         let path = System::path()?;
         Ok(ToolCommand {
             command: command_for(exe, args, &path),
+            exe: exe.to_os_string(),
+            path,
             on_failure: default_error,
         })
     }
@@ -115,11 +293,69 @@ impl ToolCommand {
         self
     }
 
+    pub(crate) fn env<K, V>(&mut self, key: K, value: V) -> &mut ToolCommand
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.command.env(key, value);
+        self
+    }
+
+    /// Ensures that the executable doesn't resolve back to a Volta shim
+    ///
+    /// A shim that runs itself would keep starting new copies of itself forever, which can
+    /// happen if a hook or the PATH points to the shim directory, or to a link or copy of the
+    /// shim executable somewhere else. This runs before every tool, so the resolved executable is
+    /// only read when it is the same size as the shim executable.
+    fn check_recursion(&self) -> Fallible<()> {
+        let shim_dir = volta_home()?.shim_dir();
+        let shim_executable = volta_install()?.shim_executable();
+        let cwd = env::current_dir().with_context(|_| ErrorDetails::CurrentDirError)?;
+
+        if let Some(resolved) =
+            resolve_to_shim(&self.exe, &self.path, &cwd, shim_dir, &shim_executable)
+        {
+            debug!("'{}' resolves to a Volta shim", resolved.display());
+            throw!(ErrorDetails::RecursiveShim {
+                command: self.exe.to_string_lossy().to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Applies the `removeEnv` and `setEnv` settings to the environment of the tool
+    ///
+    /// These are applied last, so that they take precedence over the variables Volta sets itself.
+    fn apply_env_settings(&mut self) -> Fallible<()> {
+        let settings = settings()?;
+        for name in &settings.remove_env {
+            debug!("Removing '{}' from the environment", name);
+            self.command.env_remove(name);
+        }
+        for (name, value) in &settings.set_env {
+            // Only the name is logged, since the values are often tokens or other secrets
+            debug!("Setting '{}' in the environment", name);
+            self.command.env(name, value);
+        }
+        Ok(())
+    }
+
     pub(crate) fn status(mut self) -> Fallible<ExitStatus> {
-        self.command.status().with_context(|_| self.on_failure)
+        self.apply_env_settings()?;
+        let _phase = trace::phase("exec");
+        self.command
+            .spawn()
+            .and_then(|mut child| {
+                forward_to_child(child.id());
+                child.wait()
+            })
+            .with_context(|_| self.on_failure)
     }
 
     pub(crate) fn output(mut self) -> Fallible<Output> {
+        self.apply_env_settings()?;
         self.command.output().with_context(|_| self.on_failure)
     }
 }
@@ -161,7 +397,164 @@ where
     command
 }
 
+/// Returns the path that `exe` resolves to with the given PATH, if it is a Volta shim: a file in
+/// `shim_dir`, or a link or copy of `shim_executable`
+fn resolve_to_shim(
+    exe: &OsStr,
+    path_var: &OsStr,
+    cwd: &Path,
+    shim_dir: &Path,
+    shim_executable: &Path,
+) -> Option<PathBuf> {
+    let exe_path = Path::new(exe);
+    let resolved = if exe_path.components().count() > 1 {
+        cwd.join(exe_path)
+    } else {
+        which::find_in_path(exe, path_var, cwd)?
+    };
+
+    if is_shim(&resolved, shim_dir, shim_executable) {
+        Some(resolved)
+    } else {
+        None
+    }
+}
+
+/// Whether the executable is in the shim directory or is the shim executable, either through a
+/// link or as a copy of it
+fn is_shim(executable: &Path, shim_dir: &Path, shim_executable: &Path) -> bool {
+    if executable.parent() == Some(shim_dir) {
+        return true;
+    }
+
+    // Links and copies of the shim have its size, which a real tool is very unlikely to share,
+    // so the paths and contents are only compared when the sizes are the same
+    let same_size = match (fs::metadata(executable), fs::metadata(shim_executable)) {
+        (Ok(left), Ok(right)) => left.len() == right.len(),
+        _ => false,
+    };
+    if !same_size {
+        return false;
+    }
+
+    let same_file = match (canonicalize(executable), canonicalize(shim_executable)) {
+        (Ok(left), Ok(right)) => left == right,
+        _ => false,
+    };
+    same_file
+        || match (fs::read(executable), fs::read(shim_executable)) {
+            (Ok(left), Ok(right)) => left == right,
+            _ => false,
+        }
+}
+
+#[cfg(unix)]
+fn success_status() -> ExitStatus {
+    use std::os::unix::process::ExitStatusExt;
+    ExitStatus::from_raw(0)
+}
+
+#[cfg(windows)]
+fn success_status() -> ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+    ExitStatus::from_raw(0)
+}
+
 fn intercept_global_installs() -> bool {
     // We should only intercept global installs if the VOLTA_UNSAFE_GLOBAL variable is not set
-    env::var_os(UNSAFE_GLOBAL).is_none()
+    !env_vars::is_set(env_vars::VOLTA_UNSAFE_GLOBAL)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::resolve_to_shim;
+    use std::env;
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::path::Path;
+
+    fn create_executable(dir: &Path, name: &str, contents: &str) {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::create_dir_all(dir).unwrap();
+        let file = dir.join(name);
+        fs::write(&file, contents).unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_to_shim() {
+        let root = tempfile::tempdir().unwrap();
+        let shim_dir = root.path().join("bin");
+        let real_dir = root.path().join("real");
+        let shim_executable = root.path().join("volta-shim");
+        create_executable(root.path(), "volta-shim", "shim");
+        create_executable(&shim_dir, "node", "shim");
+        create_executable(&real_dir, "node", "node");
+        let node = OsStr::new("node");
+        let resolve = |exe: &OsStr, path_var: &OsStr| {
+            resolve_to_shim(exe, path_var, root.path(), &shim_dir, &shim_executable)
+        };
+
+        let shims_only = env::join_paths(vec![&shim_dir]).unwrap();
+        assert_eq!(resolve(node, &shims_only), Some(shim_dir.join("node")));
+
+        let real_first = env::join_paths(vec![&real_dir, &shim_dir]).unwrap();
+        assert_eq!(resolve(node, &real_first), None);
+
+        let without_shims = env::join_paths(vec![&real_dir]).unwrap();
+        assert_eq!(resolve(node, &without_shims), None);
+
+        let relative_shim = Path::new("bin").join("node");
+        assert_eq!(
+            resolve(relative_shim.as_os_str(), &without_shims),
+            Some(shim_dir.join("node"))
+        );
+        assert_eq!(
+            resolve(real_dir.join("node").as_os_str(), &shims_only),
+            None
+        );
+    }
+
+    #[test]
+    fn test_resolve_to_copied_shim() {
+        let root = tempfile::tempdir().unwrap();
+        let shim_dir = root.path().join("bin");
+        let shim_executable = root.path().join("volta-shim");
+        create_executable(root.path(), "volta-shim", "shim");
+        let node = OsStr::new("node");
+
+        // A copy of the shim outside of the shim directory, which a real tool of the same size
+        // isn't mistaken for
+        let copy_dir = root.path().join("copy");
+        create_executable(&copy_dir, "node", "shim");
+        let same_size_dir = root.path().join("same-size");
+        create_executable(&same_size_dir, "node", "node");
+
+        let copy_path = env::join_paths(vec![&copy_dir]).unwrap();
+        assert_eq!(
+            resolve_to_shim(node, &copy_path, root.path(), &shim_dir, &shim_executable),
+            Some(copy_dir.join("node"))
+        );
+        let same_size_path = env::join_paths(vec![&same_size_dir]).unwrap();
+        assert_eq!(
+            resolve_to_shim(
+                node,
+                &same_size_path,
+                root.path(),
+                &shim_dir,
+                &shim_executable
+            ),
+            None
+        );
+
+        let link_dir = root.path().join("link");
+        fs::create_dir_all(&link_dir).unwrap();
+        std::os::unix::fs::symlink(&shim_executable, link_dir.join("node")).unwrap();
+        let link_path = env::join_paths(vec![&link_dir]).unwrap();
+        assert_eq!(
+            resolve_to_shim(node, &link_path, root.path(), &shim_dir, &shim_executable),
+            Some(link_dir.join("node"))
+        );
+    }
 }
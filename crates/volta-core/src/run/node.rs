@@ -23,6 +23,7 @@ where
             };
             let version = tool_version("node", platform.node());
             debug!("Using {} from {} configuration", version, source);
+            session.add_event_tool_use(ActivityKind::Node, platform.node().to_string());
 
             let image = platform.checkout(session)?;
             let path = image.path()?;
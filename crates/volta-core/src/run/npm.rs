@@ -1,16 +1,34 @@
 use std::env::args_os;
 use std::ffi::{OsStr, OsString};
 
-use super::{intercept_global_installs, CommandArg, ToolCommand};
+use super::{intercept_global_installs, npx, package_install, CommandArg, Executor, ToolCommand};
 use crate::error::ErrorDetails;
 use crate::platform::Source;
 use crate::session::{ActivityKind, Session};
+use crate::settings::InstallScripts;
 use crate::style::tool_version;
+use crate::tool::NpmFlags;
 
 use log::debug;
-use volta_fail::{throw, Fallible};
+use volta_fail::Fallible;
 
-pub(crate) fn command<A>(args: A, session: &mut Session) -> Fallible<ToolCommand>
+/// The npm flags that take a value, when it is given as a separate argument
+const VALUE_FLAGS: &[&str] = &[
+    "--prefix",
+    "-C",
+    "--cache",
+    "--userconfig",
+    "--globalconfig",
+    "--registry",
+    "--workspace",
+    "-w",
+    "--loglevel",
+    "--node-options",
+    "--script-shell",
+    "--otp",
+];
+
+pub(crate) fn command<A>(args: A, session: &mut Session) -> Fallible<Executor>
 where
     A: IntoIterator<Item = OsString>,
 {
@@ -19,8 +37,10 @@ where
     match session.current_platform()? {
         Some(platform) => {
             if intercept_global_installs() {
-                if let CommandArg::GlobalAdd(package) = check_npm_install() {
-                    throw!(ErrorDetails::NoGlobalInstalls { package });
+                // Global installs would be written into the Node image, so they are installed
+                // by Volta instead, with their own shims and a pinned platform
+                if let CommandArg::GlobalAdd(packages) = check_npm_install(args_os().skip(1)) {
+                    return package_install(packages, npm_flags(args_os().skip(1)));
                 }
             }
             let image = platform.checkout(session)?;
@@ -30,45 +50,243 @@ where
                 Source::Project | Source::ProjectNodeDefaultYarn => "project",
                 Source::Default => "default",
             };
-            let version = tool_version("npm", &image.node().npm);
+            let version = tool_version("npm", image.npm());
             debug!("Using {} from {} configuration", version, source);
+            session.add_event_tool_use(ActivityKind::Npm, image.npm().to_string());
 
-            Ok(ToolCommand::direct(OsStr::new("npm"), args, &path))
+            let mut command = ToolCommand::direct(OsStr::new("npm"), args, &path);
+            // `npm exec` is what `npx` runs since npm 7, so it gets the same locations
+            if is_exec(args_os().skip(1)) {
+                npx::set_exec_env(&mut command, session)?;
+            }
+            Ok(command.into())
         }
         None => {
             debug!("Could not find Volta-managed npm, delegating to system");
             ToolCommand::passthrough(OsStr::new("npm"), args, ErrorDetails::NoPlatform)
+                .map(Into::into)
         }
     }
 }
 
-fn check_npm_install() -> CommandArg {
+/// Parses the arguments of an npm command, without the executable, for an intercepted global
+/// install
+fn check_npm_install<I>(args: I) -> CommandArg
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let args: Vec<OsString> = args.into_iter().collect();
+    let mut positional = positional_args(&args).into_iter();
+    let command = positional.next();
+
+    // Linking the package in the current directory makes its executables available globally, so
+    // it is installed by Volta as a linked package
+    if let Some(command) = &command {
+        if (command == "link" || command == "ln") && positional.as_slice().is_empty() {
+            return CommandArg::GlobalAdd(vec![OsString::from(".")]);
+        }
+    }
+
     // npm global installs will have `-g` or `--global` somewhere in the
     // argument list
-    if !args_os().any(|arg| arg == OsString::from("-g") || arg == OsString::from("--global")) {
+    if !args.iter().any(|arg| arg == "-g" || arg == "--global") {
         return CommandArg::NotGlobalAdd;
     }
 
-    // Get the same set of args again to iterate over, this time with the
-    // command itself skipped and all flags excluded entirely. The first item
-    // in that skipped, filter iterator is the command itself.
-    let mut args = args_os().skip(1).filter(|arg| match arg.to_str() {
-        Some(arg) => !arg.starts_with('-'),
-        None => true,
-    });
-    let command = args.next();
-
     // They will be specified by the command `i`, `install`, `add` or `isntall`.
     // See https://github.com/npm/cli/blob/latest/lib/config/cmd-list.js
-    if command == Some(OsString::from("install"))
-        || command == Some(OsString::from("i"))
-        || command == Some(OsString::from("isntall"))
-        || command == Some(OsString::from("add"))
-    {
-        // `args` here picks up from where the command lookup left off, so
-        // will be the name of the package passed to the command.
-        CommandArg::GlobalAdd(args.next())
-    } else {
-        CommandArg::NotGlobalAdd
+    match command.as_ref().and_then(|command| command.to_str()) {
+        // The rest of the positional arguments are the names of the packages
+        Some("install") | Some("i") | Some("isntall") | Some("add") => {
+            CommandArg::GlobalAdd(positional.collect())
+        }
+        _ => CommandArg::NotGlobalAdd,
+    }
+}
+
+/// Returns the positional arguments of an npm command, leaving out the flags and the values of
+/// the flags that take one, like the URL of `--registry <url>`
+fn positional_args(args: &[OsString]) -> Vec<OsString> {
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some(flag) if VALUE_FLAGS.contains(&flag) => {
+                args.next();
+            }
+            Some(flag) if flag.starts_with('-') => {}
+            _ => positional.push(arg.clone()),
+        }
+    }
+    positional
+}
+
+/// Returns the flags of an intercepted global install that Volta passes on to npm when it
+/// installs the packages
+fn npm_flags<I>(args: I) -> NpmFlags
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut flags = NpmFlags::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--") => break,
+            Some("--legacy-peer-deps") => flags.legacy_peer_deps = true,
+            Some("--no-audit") => flags.no_audit = true,
+            Some("--ignore-scripts") => flags.install_scripts = Some(InstallScripts::Ignore),
+            Some("--registry") => {
+                flags.registry = args.next().and_then(|url| url.into_string().ok());
+            }
+            Some(flag) if flag.starts_with("--registry=") => {
+                flags.registry = Some(flag["--registry=".len()..].to_string());
+            }
+            _ => {}
+        }
+    }
+    flags
+}
+
+/// Whether the command is `npm exec` (or its alias `npm x`), which runs a package that may be
+/// installed temporarily
+fn is_exec<I>(args: I) -> bool
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            // Everything after `--` is positional, so the next argument is the command
+            Some("--") => {
+                return args
+                    .next()
+                    .map_or(false, |command| is_exec_command(&command))
+            }
+            // The value of a flag like `--prefix x` is not the command
+            Some(flag) if VALUE_FLAGS.contains(&flag) => {
+                args.next();
+            }
+            Some(flag) if flag.starts_with('-') => {}
+            _ => return is_exec_command(&arg),
+        }
+    }
+    false
+}
+
+fn is_exec_command(command: &OsStr) -> bool {
+    command == "exec" || command == "x"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_is_exec() {
+        assert!(is_exec(args(&["exec", "cowsay"])));
+        assert!(is_exec(args(&["x", "cowsay"])));
+        assert!(is_exec(args(&["--yes", "exec", "cowsay"])));
+        assert!(!is_exec(args(&["install", "exec"])));
+        assert!(is_exec(args(&["--", "exec", "cowsay"])));
+        assert!(!is_exec(args(&["--", "--prefix", "exec"])));
+        assert!(!is_exec(args(&[])));
+    }
+
+    fn packages(command: CommandArg) -> Option<Vec<OsString>> {
+        match command {
+            CommandArg::GlobalAdd(packages) => Some(packages),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_check_npm_install() {
+        assert_eq!(
+            packages(check_npm_install(args(&[
+                "i",
+                "-g",
+                "cowsay",
+                "typescript"
+            ]))),
+            Some(args(&["cowsay", "typescript"]))
+        );
+        assert_eq!(
+            packages(check_npm_install(args(&["-g", "install", "cowsay"]))),
+            Some(args(&["cowsay"]))
+        );
+        assert_eq!(packages(check_npm_install(args(&["i", "cowsay"]))), None);
+        assert_eq!(
+            packages(check_npm_install(args(&["run", "-g", "build"]))),
+            None
+        );
+        assert_eq!(
+            packages(check_npm_install(args(&["link"]))),
+            Some(args(&["."]))
+        );
+        assert_eq!(packages(check_npm_install(args(&["link", "cowsay"]))), None);
+    }
+
+    #[test]
+    fn test_check_npm_install_skips_flag_values() {
+        assert_eq!(
+            packages(check_npm_install(args(&[
+                "install",
+                "--registry",
+                "https://npm.example.com/",
+                "-g",
+                "cowsay"
+            ]))),
+            Some(args(&["cowsay"]))
+        );
+        assert_eq!(
+            packages(check_npm_install(args(&[
+                "--prefix", "install", "i", "-g", "cowsay"
+            ]))),
+            Some(args(&["cowsay"]))
+        );
+    }
+
+    #[test]
+    fn test_npm_flags() {
+        let flags = npm_flags(args(&[
+            "i",
+            "-g",
+            "--registry",
+            "https://npm.example.com/",
+            "--legacy-peer-deps",
+            "--ignore-scripts",
+            "cowsay",
+        ]));
+        assert_eq!(
+            flags,
+            NpmFlags {
+                legacy_peer_deps: true,
+                registry: Some("https://npm.example.com/".to_string()),
+                no_audit: false,
+                install_scripts: Some(InstallScripts::Ignore),
+            }
+        );
+
+        let flags = npm_flags(args(&[
+            "i",
+            "-g",
+            "--registry=https://npm.example.com/",
+            "cowsay",
+        ]));
+        assert_eq!(flags.registry, Some("https://npm.example.com/".to_string()));
+        assert_eq!(npm_flags(args(&["i", "-g", "cowsay"])), NpmFlags::default());
+    }
+
+    #[test]
+    fn test_is_exec_skips_flag_values() {
+        assert!(is_exec(args(&["--prefix", "x", "exec", "cowsay"])));
+        assert!(is_exec(args(&["-C", "exec", "exec", "cowsay"])));
+        assert!(is_exec(args(&["--prefix=x", "exec", "cowsay"])));
+        assert!(!is_exec(args(&["--prefix", "exec", "install"])));
+        assert!(!is_exec(args(&["--registry", "x", "install"])));
     }
 }
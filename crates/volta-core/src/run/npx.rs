@@ -1,15 +1,26 @@
+use std::env::args_os;
 use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 
 use super::ToolCommand;
 use crate::error::ErrorDetails;
+use crate::fs::symlink_dir;
+use crate::layout::volta_home;
 use crate::platform::Source;
 use crate::session::{ActivityKind, Session};
+use crate::settings::settings;
 use crate::style::tool_version;
+use crate::tool::npm_cache_dir;
 use crate::version::parse_version;
 
 use log::debug;
 use volta_fail::Fallible;
 
+/// The directory in the npm cache that npm installs packages into temporarily
+const NPX_DIR: &str = "_npx";
+
 pub(crate) fn command<A>(args: A, session: &mut Session) -> Fallible<ToolCommand>
 where
     A: IntoIterator<Item = OsString>,
@@ -23,19 +34,22 @@ where
             // npx was only included with npm 5.2.0 and higher. If the npm version is less than that, we
             // should include a helpful error message
             let required_npm = parse_version("5.2.0")?;
-            if image.node().npm >= required_npm {
+            if *image.npm() >= required_npm {
                 let source = match image.source() {
                     Source::Project | Source::ProjectNodeDefaultYarn => "project",
                     Source::Default => "default",
                 };
-                let version = tool_version("npx", &image.node().npm);
+                let version = tool_version("npx", image.npm());
                 debug!("Using {} from {} configuration", version, source);
+                session.add_event_tool_use(ActivityKind::Npx, image.npm().to_string());
 
                 let path = image.path()?;
-                Ok(ToolCommand::direct(OsStr::new("npx"), args, &path))
+                let mut command = ToolCommand::direct(OsStr::new("npx"), args, &path);
+                set_exec_env(&mut command, session)?;
+                Ok(command)
             } else {
                 Err(ErrorDetails::NpxNotAvailable {
-                    version: image.node().npm.to_string(),
+                    version: image.npm().to_string(),
                 }
                 .into())
             }
@@ -46,3 +60,177 @@ where
         }
     }
 }
+
+/// Points npm at a global prefix that Volta manages, so that `npx` and `npm exec` don't use the
+/// prefix of an `.npmrc` that was set up for a different Node
+///
+/// The prefix is inherited by everything the package runs, so it is kept outside of the Node
+/// image: a script that runs `npm install --global` can't change the image that other projects
+/// use.
+///
+/// npm keeps the packages it installs temporarily in the `_npx` directory of its cache, and has
+/// no setting for that directory alone. Rather than moving the whole cache, `_npx` is linked to
+/// Volta's npx cache, leaving the rest of the cache wherever npm is configured to keep it. This
+/// changes the npm cache, so it is skipped in read-only mode.
+///
+/// The `setEnv` setting is applied after this, so it can still override the prefix.
+pub(super) fn set_exec_env(command: &mut ToolCommand, session: &mut Session) -> Fallible<()> {
+    let prefix = volta_home()?.npm_prefix_dir();
+    debug!("Running npx with the prefix '{}'", prefix.display());
+    command.env("npm_config_prefix", prefix);
+
+    if settings()?.read_only {
+        debug!("Read-only mode is enabled, leaving the npx directory of the npm cache alone");
+        return Ok(());
+    }
+
+    let npm_cache = match cache_flag(args_os().skip(1)) {
+        Some(npm_cache) => Some(npm_cache),
+        None => npm_cache_dir(session)?,
+    };
+    if let Some(npm_cache) = npm_cache {
+        let npx_dir = volta_home()?.npx_cache_dir();
+        match link_npx_dir(&npm_cache, npx_dir) {
+            Ok(true) => debug!(
+                "Temporary npx installs in '{}' are kept in '{}'",
+                npm_cache.display(),
+                npx_dir.display()
+            ),
+            Ok(false) => debug!(
+                "Leaving the existing npx directory in '{}' in place",
+                npm_cache.display()
+            ),
+            Err(error) => debug!(
+                "Could not link the npx directory in '{}': {}",
+                npm_cache.display(),
+                error
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Returns the npm cache given with `--cache` on the command line, which takes precedence over
+/// the environment and the `.npmrc` files
+fn cache_flag<I>(args: I) -> Option<PathBuf>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut args = args.into_iter();
+    let mut cache = None;
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            // Everything after `--` belongs to the package that is run
+            Some("--") => break,
+            Some("--cache") => cache = args.next().map(PathBuf::from),
+            Some(flag) if flag.starts_with("--cache=") => {
+                cache = Some(PathBuf::from(&flag["--cache=".len()..]))
+            }
+            _ => {}
+        }
+    }
+    cache
+}
+
+/// Links the `_npx` directory of the npm cache to `npx_dir`, returning whether npm's temporary
+/// installs now end up there
+///
+/// An `_npx` directory that npm created itself is left alone, so that nothing in it is lost.
+fn link_npx_dir(npm_cache: &Path, npx_dir: &Path) -> io::Result<bool> {
+    let link = npm_cache.join(NPX_DIR);
+    match fs::symlink_metadata(&link) {
+        Ok(metadata) => Ok(metadata.file_type().is_symlink() && fs::read_link(&link)? == npx_dir),
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => {
+            fs::create_dir_all(npx_dir)?;
+            fs::create_dir_all(npm_cache)?;
+            symlink_dir(npx_dir, &link)?;
+            Ok(true)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Points the `_npx` directory of the npm cache from `old_dir` to `new_dir`, if it is a link to
+/// `old_dir`, returning whether it was changed
+pub(crate) fn relink_npx_dir(npm_cache: &Path, old_dir: &Path, new_dir: &Path) -> io::Result<bool> {
+    let link = npm_cache.join(NPX_DIR);
+    match fs::symlink_metadata(&link) {
+        Ok(metadata) if metadata.file_type().is_symlink() && fs::read_link(&link)? == old_dir => {
+            // Directory links are removed as files on Unix and as directories on Windows
+            fs::remove_file(&link).or_else(|_| fs::remove_dir(&link))?;
+            symlink_dir(new_dir, &link)?;
+            Ok(true)
+        }
+        Ok(_) => Ok(false),
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_link_npx_dir() {
+        let dir = tempdir().unwrap();
+        let npm_cache = dir.path().join("npm-cache");
+        let npx_dir = dir.path().join("volta").join("npx");
+
+        assert!(link_npx_dir(&npm_cache, &npx_dir).unwrap());
+        assert_eq!(fs::read_link(npm_cache.join("_npx")).unwrap(), npx_dir);
+        assert!(npx_dir.is_dir());
+
+        // Linking again is a no-op
+        assert!(link_npx_dir(&npm_cache, &npx_dir).unwrap());
+    }
+
+    #[test]
+    fn test_link_npx_dir_keeps_existing_dir() {
+        let dir = tempdir().unwrap();
+        let npm_cache = dir.path().join("npm-cache");
+        let npx_dir = dir.path().join("volta").join("npx");
+        fs::create_dir_all(npm_cache.join("_npx").join("1234")).unwrap();
+
+        assert!(!link_npx_dir(&npm_cache, &npx_dir).unwrap());
+        assert!(npm_cache.join("_npx").join("1234").is_dir());
+        assert!(!npx_dir.exists());
+    }
+
+    #[test]
+    fn test_cache_flag() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            cache_flag(args(&["--cache", "/tmp/cache", "cowsay"])),
+            Some(PathBuf::from("/tmp/cache"))
+        );
+        assert_eq!(
+            cache_flag(args(&["--yes", "--cache=/tmp/cache", "cowsay"])),
+            Some(PathBuf::from("/tmp/cache"))
+        );
+        assert_eq!(cache_flag(args(&["cowsay", "--", "--cache", "x"])), None);
+        assert_eq!(cache_flag(args(&["cowsay"])), None);
+    }
+
+    #[test]
+    fn test_relink_npx_dir() {
+        let dir = tempdir().unwrap();
+        let npm_cache = dir.path().join("npm-cache");
+        let old_dir = dir.path().join("old").join("npx");
+        let new_dir = dir.path().join("new").join("npx");
+        fs::create_dir_all(&new_dir).unwrap();
+
+        // Nothing to relink without a link
+        assert!(!relink_npx_dir(&npm_cache, &old_dir, &new_dir).unwrap());
+
+        assert!(link_npx_dir(&npm_cache, &old_dir).unwrap());
+        assert!(relink_npx_dir(&npm_cache, &old_dir, &new_dir).unwrap());
+        assert_eq!(fs::read_link(npm_cache.join("_npx")).unwrap(), new_dir);
+
+        // A link to somewhere else is left alone
+        assert!(!relink_npx_dir(&npm_cache, &old_dir, &dir.path().join("other")).unwrap());
+        assert_eq!(fs::read_link(npm_cache.join("_npx")).unwrap(), new_dir);
+    }
+}
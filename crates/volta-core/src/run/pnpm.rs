@@ -0,0 +1,97 @@
+use std::env::args_os;
+use std::ffi::{OsStr, OsString};
+
+use super::{intercept_global_installs, CommandArg, ToolCommand};
+use crate::error::ErrorDetails;
+use crate::platform::{Source, SourcedPlatformSpec};
+use crate::session::{ActivityKind, Session};
+use crate::settings::settings;
+use crate::style::tool_version;
+
+use log::debug;
+use volta_fail::{throw, Fallible};
+
+pub(crate) fn command<A>(args: A, session: &mut Session) -> Fallible<ToolCommand>
+where
+    A: IntoIterator<Item = OsString>,
+{
+    session.add_event_start(ActivityKind::Pnpm);
+
+    match get_pnpm_platform(session)? {
+        Ok(platform) => {
+            if intercept_global_installs() {
+                if let CommandArg::GlobalAdd(packages) = check_pnpm_add() {
+                    throw!(ErrorDetails::NoGlobalInstalls {
+                        package: packages.into_iter().next(),
+                    });
+                }
+            }
+
+            // Note: If we've gotten this far, we know there is a pnpm version set
+            let version = platform.pnpm().unwrap().clone();
+            let pinned = session
+                .project_platform()?
+                .map_or(false, |project| project.pnpm.is_some());
+            let source = match platform.source() {
+                Source::Project | Source::ProjectNodeDefaultYarn if pinned => "project",
+                _ => "default",
+            };
+            debug!(
+                "Using {} from {} configuration",
+                tool_version("pnpm", &version),
+                source
+            );
+            session.add_event_tool_use(ActivityKind::Pnpm, version.to_string());
+
+            let image = platform.checkout(session)?;
+            let path = image.path()?;
+            Ok(ToolCommand::direct(OsStr::new("pnpm"), args, &path))
+        }
+        Err(missing) => {
+            debug!("Could not find Volta-managed pnpm, delegating to system");
+            ToolCommand::passthrough(OsStr::new("pnpm"), args, missing)
+        }
+    }
+}
+
+/// Determine the correct platform (project or default) and check if pnpm is set for that platform
+///
+/// If there is no platform, or if Pnpm isn't set and the `passthrough` setting is enabled, the
+/// error to show when the system doesn't have Pnpm either is returned instead.
+fn get_pnpm_platform(session: &mut Session) -> Fallible<Result<SourcedPlatformSpec, ErrorDetails>> {
+    let platform = match session.current_platform()? {
+        Some(platform) => platform,
+        None => return Ok(Err(ErrorDetails::NoPlatform)),
+    };
+
+    if platform.pnpm().is_some() {
+        return Ok(Ok(platform));
+    }
+
+    let missing = match platform.source() {
+        Source::Project | Source::ProjectNodeDefaultYarn => ErrorDetails::NoProjectPnpm,
+        Source::Default => ErrorDetails::NoDefaultPnpm,
+    };
+    if settings()?.passthrough {
+        Ok(Err(missing))
+    } else {
+        Err(missing.into())
+    }
+}
+
+fn check_pnpm_add() -> CommandArg {
+    // pnpm global installs are of the form `pnpm add --global`, where the flag may come anywhere,
+    // e.g. pnpm -g add ember-cli
+    let args: Vec<OsString> = args_os().skip(1).collect();
+    let global = args.iter().any(|arg| arg == "-g" || arg == "--global");
+    let mut positional = args.into_iter().filter(|arg| match arg.to_str() {
+        Some(arg) => !arg.starts_with('-'),
+        None => true,
+    });
+
+    if global && positional.next() == Some(OsString::from("add")) {
+        CommandArg::GlobalAdd(positional.collect())
+    } else {
+        CommandArg::NotGlobalAdd
+    }
+}
@@ -0,0 +1,101 @@
+//! Provides the `which` lookup, which determines the executable that a shimmed command would
+//! dispatch to in the current context.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::binary::DefaultBinary;
+use crate::error::ErrorDetails;
+use crate::platform::{Source, System};
+use crate::session::Session;
+use ::which::which_in;
+use volta_fail::{Fallible, ResultExt};
+
+/// Where the resolved executable came from
+pub enum WhichSource {
+    /// A binary that is a direct dependency of the current project
+    ProjectBinary,
+    /// A binary from a package installed into the user toolchain
+    DefaultBinary,
+    /// An executable found using the platform determined by the given source
+    Platform(Source),
+    /// An executable found on the system PATH, outside of Volta
+    System,
+}
+
+impl fmt::Display for WhichSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WhichSource::ProjectBinary => write!(f, "project dependency"),
+            WhichSource::DefaultBinary => write!(f, "default package"),
+            WhichSource::Platform(source) => write!(f, "{} platform", source),
+            WhichSource::System => write!(f, "system"),
+        }
+    }
+}
+
+/// The executable that a command would run
+pub struct Which {
+    pub path: PathBuf,
+    pub source: WhichSource,
+}
+
+/// Determines the real executable that running `bin` through a shim would use
+///
+/// 1. Start by checking if the user has a tool installed in the project or as a user
+///    default. If so, we're done.
+/// 2. Otherwise, use the platform image and/or the system environment to determine a
+///    lookup path to search for the executable.
+pub fn which(bin: &OsStr, session: &mut Session) -> Fallible<Option<Which>> {
+    let default_tool = DefaultBinary::from_name(bin, session)?;
+    let project_bin_path =
+        session
+            .project()?
+            .and_then(|project| match project.has_direct_bin(bin) {
+                Ok(true) => Some(project.local_bin_dir()),
+                _ => None,
+            });
+
+    match (default_tool, project_bin_path) {
+        (Some(_), Some(project_bin_dir)) => {
+            return Ok(Some(Which {
+                path: project_bin_dir.join(bin),
+                source: WhichSource::ProjectBinary,
+            }));
+        }
+        (Some(tool), _) => {
+            return Ok(Some(Which {
+                path: tool.bin_path,
+                source: WhichSource::DefaultBinary,
+            }));
+        }
+        _ => {}
+    }
+
+    // Treat any error with obtaining the current platform image as if the image doesn't exist
+    // However, errors in obtaining the current working directory or the System path should
+    // still be treated as errors.
+    let platform_path = session
+        .current_platform()
+        .unwrap_or(None)
+        .and_then(|platform| platform.checkout(session).ok())
+        .and_then(|image| {
+            let path = image.path().ok()?;
+            Some((path, WhichSource::Platform(*image.source())))
+        });
+
+    let (path, source) = match platform_path {
+        Some(found) => found,
+        None => (System::path()?, WhichSource::System),
+    };
+
+    let cwd = env::current_dir().with_context(|_| ErrorDetails::CurrentDirError)?;
+    Ok(find_in_path(bin, &path, &cwd).map(|path| Which { path, source }))
+}
+
+/// Searches each directory in `path_var` for an executable named `bin`
+pub(super) fn find_in_path(bin: &OsStr, path_var: &OsStr, cwd: &Path) -> Option<PathBuf> {
+    which_in(bin, Some(path_var), cwd).ok()
+}
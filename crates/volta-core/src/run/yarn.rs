@@ -1,26 +1,38 @@
 use std::env::args_os;
 use std::ffi::{OsStr, OsString};
 
-use super::{intercept_global_installs, CommandArg, ToolCommand};
+use super::{
+    intercept_global_installs, package_install, package_uninstall, CommandArg, Executor,
+    ToolCommand,
+};
 use crate::error::ErrorDetails;
 use crate::platform::{Source, SourcedPlatformSpec};
 use crate::session::{ActivityKind, Session};
+use crate::settings::settings;
 use crate::style::tool_version;
+use crate::tool::NpmFlags;
 
 use log::debug;
-use volta_fail::{throw, Fallible};
+use volta_fail::Fallible;
 
-pub(crate) fn command<A>(args: A, session: &mut Session) -> Fallible<ToolCommand>
+pub(crate) fn command<A>(args: A, session: &mut Session) -> Fallible<Executor>
 where
     A: IntoIterator<Item = OsString>,
 {
     session.add_event_start(ActivityKind::Yarn);
 
     match get_yarn_platform(session)? {
-        Some(platform) => {
+        Ok(platform) => {
+            // Global packages would be added to the Yarn directory of the current Node, so they
+            // are managed by Volta instead, which keeps them available when Node changes
             if intercept_global_installs() {
-                if let CommandArg::GlobalAdd(package) = check_yarn_add() {
-                    throw!(ErrorDetails::NoGlobalInstalls { package });
+                match check_yarn_global() {
+                    CommandArg::GlobalAdd(packages) => {
+                        return package_install(packages, NpmFlags::default())
+                    }
+                    CommandArg::GlobalRemove(packages) => return package_uninstall(packages),
+                    CommandArg::GlobalList => return Ok(Executor::PackageList),
+                    CommandArg::NotGlobalAdd => {}
                 }
             }
 
@@ -31,45 +43,77 @@ where
             };
             let version = tool_version("yarn", platform.yarn().unwrap());
             debug!("Using {} from {} configuration", version, source);
+            session.add_event_tool_use(ActivityKind::Yarn, platform.yarn().unwrap().to_string());
 
             let image = platform.checkout(session)?;
             let path = image.path()?;
-            Ok(ToolCommand::direct(OsStr::new("yarn"), args, &path))
+            Ok(ToolCommand::direct(OsStr::new("yarn"), args, &path).into())
         }
-        None => {
+        Err(missing) => {
             debug!("Could not find Volta-managed yarn, delegating to system");
-            ToolCommand::passthrough(OsStr::new("yarn"), args, ErrorDetails::NoPlatform)
+            ToolCommand::passthrough(OsStr::new("yarn"), args, missing).map(Into::into)
         }
     }
 }
 
 /// Determine the correct platform (project or default) and check if yarn is set for that platform
-fn get_yarn_platform(session: &mut Session) -> Fallible<Option<SourcedPlatformSpec>> {
-    match session.current_platform()? {
-        Some(platform) => match platform.yarn() {
-            Some(_) => Ok(Some(platform)),
-            None => match platform.source() {
-                Source::Project | Source::ProjectNodeDefaultYarn => {
-                    Err(ErrorDetails::NoProjectYarn.into())
-                }
-                Source::Default => Err(ErrorDetails::NoDefaultYarn.into()),
-            },
-        },
-        None => Ok(None),
+///
+/// If there is no platform, or if Yarn isn't set and the `passthrough` setting is enabled, the
+/// error to show when the system doesn't have Yarn either is returned instead.
+fn get_yarn_platform(session: &mut Session) -> Fallible<Result<SourcedPlatformSpec, ErrorDetails>> {
+    let platform = match session.current_platform()? {
+        Some(platform) => platform,
+        None => return Ok(Err(ErrorDetails::NoPlatform)),
+    };
+
+    if platform.yarn().is_some() {
+        return Ok(Ok(platform));
+    }
+
+    let missing = match platform.source() {
+        Source::Project | Source::ProjectNodeDefaultYarn => ErrorDetails::NoProjectYarn,
+        Source::Default => ErrorDetails::NoDefaultYarn,
+    };
+    if settings()?.passthrough {
+        Ok(Err(missing))
+    } else {
+        Err(missing.into())
     }
 }
 
-fn check_yarn_add() -> CommandArg {
-    // Yarn global installs must be of the form `yarn global add`
+fn check_yarn_global() -> CommandArg {
+    // Yarn global commands must be of the form `yarn global <command>`
     // However, they may have options intermixed, e.g. yarn --verbose global add ember-cli
     let mut args = args_os().skip(1).filter(|arg| match arg.to_str() {
         Some(arg) => !arg.starts_with('-'),
         None => true,
     });
 
-    if (args.next(), args.next()) == (Some(OsString::from("global")), Some(OsString::from("add"))) {
-        CommandArg::GlobalAdd(args.next())
-    } else {
-        CommandArg::NotGlobalAdd
+    match args.next() {
+        Some(command) if command == "global" => {}
+        // `yarn link` without a package links the current package, so it is installed by Volta
+        // as a linked package instead
+        Some(command) if command == "link" => {
+            return match args.next() {
+                Some(_) => CommandArg::NotGlobalAdd,
+                None => CommandArg::GlobalAdd(vec![OsString::from(".")]),
+            };
+        }
+        _ => return CommandArg::NotGlobalAdd,
+    }
+
+    match args.next().as_ref().and_then(|command| command.to_str()) {
+        Some("add") => CommandArg::GlobalAdd(args.collect()),
+        Some("remove") => {
+            let packages: Vec<OsString> = args.collect();
+            // Without any packages, Yarn reports the missing argument itself
+            if packages.is_empty() {
+                CommandArg::NotGlobalAdd
+            } else {
+                CommandArg::GlobalRemove(packages)
+            }
+        }
+        Some("list") => CommandArg::GlobalList,
+        _ => CommandArg::NotGlobalAdd,
     }
 }
@@ -0,0 +1,344 @@
+//! Provides utilities for updating the installed Volta executables to the latest release.
+
+use std::fs::{copy, remove_file, rename, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::error::ErrorDetails;
+use crate::fs::{create_staging_dir, create_staging_file};
+use crate::layout::volta_install;
+use crate::settings::check_online;
+use crate::style::progress_spinner;
+use crate::version::parse_version;
+use archive::http_client;
+use cfg_if::cfg_if;
+use log::debug;
+use reqwest::header::USER_AGENT;
+use semver::Version;
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use tempfile::TempDir;
+use volta_fail::{throw, Fallible, ResultExt};
+
+cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        fn latest_release_url() -> String {
+            format!("{}/volta-releases/latest", mockito::SERVER_URL)
+        }
+    } else {
+        /// Returns the URL of the GitHub API endpoint describing the latest Volta release.
+        fn latest_release_url() -> String {
+            "https://api.github.com/repos/volta-cli/volta/releases/latest".to_string()
+        }
+    }
+}
+
+cfg_if! {
+    if #[cfg(target_os = "macos")] {
+        const RELEASE_OS: &str = "macos";
+    } else if #[cfg(target_os = "linux")] {
+        const RELEASE_OS: &str = "linux";
+    } else if #[cfg(target_os = "freebsd")] {
+        const RELEASE_OS: &str = "freebsd";
+    } else if #[cfg(windows)] {
+        const RELEASE_OS: &str = "windows";
+    } else {
+        compile_error!("Unsupported target_os variant of unix (expected 'macos', 'linux' or 'freebsd').");
+    }
+}
+
+cfg_if! {
+    if #[cfg(windows)] {
+        const RELEASE_EXTENSION: &str = "zip";
+    } else {
+        const RELEASE_EXTENSION: &str = "tar.gz";
+    }
+}
+
+#[derive(Deserialize)]
+struct RawRelease {
+    tag_name: String,
+    assets: Vec<RawAsset>,
+}
+
+#[derive(Deserialize)]
+struct RawAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// A published Volta release with a build for the current platform.
+pub struct Release {
+    pub version: Version,
+    archive_url: String,
+    checksum_url: String,
+}
+
+/// Fetches the latest Volta release, returning it only if it is newer than `current`.
+pub fn check_for_update(current: &Version) -> Fallible<Option<Release>> {
+    let release = latest_release()?;
+    debug!("Latest Volta release is {}", release.version);
+
+    if release.version > *current {
+        Ok(Some(release))
+    } else {
+        Ok(None)
+    }
+}
+
+fn latest_release() -> Fallible<Release> {
+    let url = latest_release_url();
+    check_online(&url)?;
+    let release_error = |_: &reqwest::Error| ErrorDetails::SelfUpdateReleaseError {
+        from_url: latest_release_url(),
+    };
+
+    let spinner = progress_spinner(&format!("Checking for Volta updates from {}", url));
+    let raw = http_client()
+        .get(&url)
+        // The GitHub API rejects requests without a user agent
+        .header(USER_AGENT, "volta")
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|mut response| response.json())
+        .with_context(release_error);
+    spinner.finish_and_clear();
+    let raw: RawRelease = raw?;
+
+    let version = parse_version(raw.tag_name.trim_start_matches('v'))?;
+    let archive_name = format!("volta-{}-{}.{}", version, RELEASE_OS, RELEASE_EXTENSION);
+    let checksum_name = format!("{}.sha1", archive_name);
+
+    let find_asset = |name: &str| {
+        raw.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .map(|asset| asset.browser_download_url.clone())
+            .ok_or_else(|| ErrorDetails::SelfUpdateAssetError {
+                version: version.to_string(),
+                asset: name.to_string(),
+            })
+    };
+    let archive_url = find_asset(&archive_name)?;
+    let checksum_url = find_asset(&checksum_name)?;
+
+    Ok(Release {
+        version,
+        archive_url,
+        checksum_url,
+    })
+}
+
+/// Downloads and verifies the given release, then replaces the installed Volta executables.
+pub fn install(release: &Release) -> Fallible<()> {
+    let spinner = progress_spinner(&format!("Downloading Volta {}", release.version));
+    let staging = download(release);
+    // The spinner is cleared before any error is reported, so the two don't overlap
+    spinner.finish_and_clear();
+    let staging = staging?;
+
+    let install = volta_install()?;
+    let executables = [
+        install.main_executable(),
+        install.shim_executable(),
+        install.migrate_executable(),
+    ];
+    let replacements = replacements(staging.path(), &executables, &release.version)?;
+    for (replacement, installed) in replacements.iter().zip(executables.iter()) {
+        replace_executable(replacement, installed)?;
+    }
+
+    Ok(())
+}
+
+/// Finds the replacement of each installed executable in the unpacked release, failing before
+/// anything is replaced if one of them is missing, so an update never leaves a mix of versions
+fn replacements(
+    staging: &Path,
+    executables: &[PathBuf],
+    version: &Version,
+) -> Fallible<Vec<PathBuf>> {
+    executables
+        .iter()
+        .map(|installed| {
+            // `file_name` is always `Some` for the executable paths in the install layout
+            let file_name = installed.file_name().unwrap();
+            let replacement = staging.join(file_name);
+            if replacement.is_file() {
+                Ok(replacement)
+            } else {
+                Err(ErrorDetails::SelfUpdateMissingFileError {
+                    version: version.to_string(),
+                    file: file_name.to_string_lossy().into_owned(),
+                }
+                .into())
+            }
+        })
+        .collect()
+}
+
+/// Downloads the release and verifies its checksum, returning the directory it is unpacked into
+fn download(release: &Release) -> Fallible<TempDir> {
+    let version = release.version.to_string();
+    let expected = fetch_checksum(release)?;
+    let mut distro = create_staging_file()?;
+    let mut response = http_client()
+        .get(&release.archive_url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .with_context(download_error(release, &release.archive_url))?;
+    response
+        .copy_to(&mut distro)
+        .with_context(download_error(release, &release.archive_url))?;
+
+    let unpack_error = || ErrorDetails::UnpackArchiveError {
+        tool: "Volta".into(),
+        version: version.clone(),
+    };
+    let calculated = calculate_checksum(distro.as_file_mut()).with_context(|_| unpack_error())?;
+    if calculated != expected {
+        debug!(
+            "Expected checksum {} for Volta {}, calculated {}",
+            expected, version, calculated
+        );
+        throw!(ErrorDetails::SelfUpdateChecksumError { version });
+    }
+
+    let source = distro.reopen().with_context(|_| unpack_error())?;
+    let staging = create_staging_dir()?;
+    archive::load_native(source)
+        .with_context(|_| unpack_error())?
+        .unpack(staging.path(), &mut |_, _| ())
+        .with_context(|_| unpack_error())?;
+
+    Ok(staging)
+}
+
+fn fetch_checksum(release: &Release) -> Fallible<String> {
+    let text = http_client()
+        .get(&release.checksum_url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|mut response| response.text())
+        .with_context(download_error(release, &release.checksum_url))?;
+
+    // Checksum files have the same format as `shasum` output: `<hash>  <file name>`
+    Ok(text
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase())
+}
+
+fn calculate_checksum(file: &mut File) -> io::Result<String> {
+    let mut buffer = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut buffer)?;
+
+    let mut hasher = Sha1::new();
+    hasher.input(buffer);
+    Ok(hex::encode(&hasher.result()))
+}
+
+fn download_error(
+    release: &Release,
+    from_url: &str,
+) -> impl FnOnce(&reqwest::Error) -> ErrorDetails {
+    let version = release.version.to_string();
+    let from_url = from_url.to_string();
+    |_| ErrorDetails::SelfUpdateDownloadError { version, from_url }
+}
+
+/// Replaces `installed` with `replacement` so that the old executable is never left half-written.
+///
+/// The replacement is first copied next to the installed file, since the staging directory may
+/// be on a different volume, and then renamed over it. Windows doesn't allow replacing a running
+/// executable, but it does allow renaming one, so there the current file is moved aside first and
+/// restored if the final rename fails.
+fn replace_executable(replacement: &Path, installed: &Path) -> Fallible<()> {
+    let replace_error = || ErrorDetails::SelfUpdateReplaceError {
+        file: installed.to_owned(),
+    };
+
+    let new_file = sibling(installed, "new");
+    copy(replacement, &new_file).with_context(|_| replace_error())?;
+    swap_into_place(&new_file, installed).with_context(|_| replace_error())?;
+
+    debug!("Replaced '{}'", installed.display());
+    Ok(())
+}
+
+#[cfg(unix)]
+fn swap_into_place(new_file: &Path, installed: &Path) -> io::Result<()> {
+    rename(new_file, installed).map_err(|error| {
+        let _ = remove_file(new_file);
+        error
+    })
+}
+
+#[cfg(windows)]
+fn swap_into_place(new_file: &Path, installed: &Path) -> io::Result<()> {
+    let old_file = sibling(installed, "old");
+    // A leftover from a previous update may or may not exist, either is fine
+    let _ = remove_file(&old_file);
+
+    if installed.exists() {
+        rename(installed, &old_file)?;
+    }
+    rename(new_file, installed).map_err(|error| {
+        let _ = rename(&old_file, installed);
+        let _ = remove_file(new_file);
+        error
+    })
+}
+
+fn sibling(file: &Path, suffix: &str) -> PathBuf {
+    let mut name = file.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    file.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sibling() {
+        let installed = Path::new("/usr/local/volta/volta-shim");
+        assert_eq!(
+            sibling(installed, "new"),
+            PathBuf::from("/usr/local/volta/volta-shim.new")
+        );
+    }
+
+    #[test]
+    fn test_replacements_requires_every_executable() {
+        let staging = tempfile::tempdir().unwrap();
+        let install = tempfile::tempdir().unwrap();
+        let executables = vec![
+            install.path().join("volta"),
+            install.path().join("volta-shim"),
+        ];
+        let version = Version::parse("0.8.0").unwrap();
+        File::create(staging.path().join("volta")).unwrap();
+
+        let error = replacements(staging.path(), &executables, &version).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ErrorDetails>(),
+            Some(&ErrorDetails::SelfUpdateMissingFileError {
+                version: "0.8.0".to_string(),
+                file: "volta-shim".to_string(),
+            })
+        );
+
+        File::create(staging.path().join("volta-shim")).unwrap();
+        assert_eq!(
+            replacements(staging.path(), &executables, &version).unwrap(),
+            vec![
+                staging.path().join("volta"),
+                staging.path().join("volta-shim")
+            ]
+        );
+    }
+}
@@ -3,20 +3,28 @@
 //! hook configuration, and the state of the local inventory.
 
 use std::fmt::{self, Display, Formatter};
+use std::path::Path;
 use std::process::exit;
 use std::rc::Rc;
 
+use crate::error::ErrorDetails;
 use crate::event::EventLog;
 use crate::hook::{HookConfig, LazyHookConfig, Publish};
 use crate::inventory::{Inventory, LazyInventory};
-use crate::platform::{PlatformSpec, SourcedPlatformSpec};
-use crate::project::{LazyProject, Project};
-use crate::tool::{Node, Yarn};
+use crate::layout::volta_home;
+use crate::platform::cache as platform_cache;
+use crate::platform::{PlatformSpec, Source, SourcedPlatformSpec};
+use crate::project::{
+    current_project_root, local_toolchain_dir, satisfies_engines, LazyProject, Project,
+};
+use crate::settings::{exit_policy, settings};
+use crate::style::tool_version;
+use crate::tool::{Node, Npm, NpmFlags, Pnpm, Yarn};
 use crate::toolchain::{LazyToolchain, Toolchain};
 
-use log::debug;
+use log::{debug, info};
 use semver::Version;
-use volta_fail::{ExitCode, Fallible, VoltaError};
+use volta_fail::{throw, ExitCode, Fallible, VoltaError};
 
 #[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
 pub enum ActivityKind {
@@ -31,6 +39,7 @@ pub enum ActivityKind {
     Npm,
     Npx,
     Yarn,
+    Pnpm,
     Volta,
     Tool,
     Help,
@@ -40,6 +49,29 @@ pub enum ActivityKind {
     Completions,
     Which,
     Setup,
+    Relocate,
+    Sign,
+    Run,
+    Env,
+    Doctor,
+    Verify,
+    AuditToolchain,
+    Check,
+    CiSetup,
+    Status,
+    Stats,
+    Prompt,
+    Lock,
+    Outdated,
+    SelfUpdate,
+    Upgrade,
+    Export,
+    ExportEnv,
+    Generate,
+    Import,
+    ImportTools,
+    History,
+    Metrics,
 }
 
 impl Display for ActivityKind {
@@ -56,15 +88,39 @@ impl Display for ActivityKind {
             ActivityKind::Npm => "npm",
             ActivityKind::Npx => "npx",
             ActivityKind::Yarn => "yarn",
+            ActivityKind::Pnpm => "pnpm",
             ActivityKind::Volta => "volta",
             ActivityKind::Tool => "tool",
             ActivityKind::Help => "help",
             ActivityKind::Version => "version",
             ActivityKind::Binary => "binary",
             ActivityKind::Setup => "setup",
+            ActivityKind::Relocate => "relocate",
+            ActivityKind::Sign => "sign",
+            ActivityKind::Run => "run",
+            ActivityKind::Env => "env",
+            ActivityKind::Doctor => "doctor",
+            ActivityKind::Verify => "verify",
+            ActivityKind::AuditToolchain => "audit-toolchain",
+            ActivityKind::Check => "check",
+            ActivityKind::CiSetup => "ci-setup",
+            ActivityKind::Status => "status",
+            ActivityKind::Stats => "stats",
+            ActivityKind::Prompt => "prompt",
+            ActivityKind::Lock => "lock",
+            ActivityKind::Outdated => "outdated",
+            ActivityKind::SelfUpdate => "self-update",
+            ActivityKind::Upgrade => "upgrade",
             ActivityKind::Shim => "shim",
             ActivityKind::Completions => "completions",
             ActivityKind::Which => "which",
+            ActivityKind::Export => "export",
+            ActivityKind::ExportEnv => "export-env",
+            ActivityKind::Generate => "generate",
+            ActivityKind::Import => "import",
+            ActivityKind::ImportTools => "import-tools",
+            ActivityKind::History => "history",
+            ActivityKind::Metrics => "metrics",
         };
         f.write_str(s)
     }
@@ -78,12 +134,15 @@ impl Display for ActivityKind {
 /// - the Node project tree that contains the current directory (if any)
 /// - the Volta hook configuration
 /// - the inventory of locally-fetched Volta tools
+/// - the npm flags for the packages that are installed
 pub struct Session {
     hooks: LazyHookConfig,
     inventory: LazyInventory,
     toolchain: LazyToolchain,
     project: LazyProject,
     event_log: EventLog,
+    records_metrics: bool,
+    npm_flags: NpmFlags,
 }
 
 impl Session {
@@ -95,6 +154,8 @@ impl Session {
             toolchain: LazyToolchain::init(),
             project: LazyProject::init(),
             event_log: EventLog::init(),
+            records_metrics: true,
+            npm_flags: NpmFlags::default(),
         }
     }
 
@@ -108,6 +169,13 @@ impl Session {
         self.project.get_mut()
     }
 
+    /// Uses the project rooted at `project_root` as the current project, for commands that
+    /// operate on a project other than the one containing the current directory.
+    pub fn use_project(&mut self, project_root: &Path) -> Fallible<()> {
+        self.project = LazyProject::for_root(project_root)?;
+        Ok(())
+    }
+
     /// Returns the user's currently active platform, if any
     ///
     /// Active platform is determined by first looking at the Project Platform
@@ -116,8 +184,23 @@ impl Session {
     /// - If it exists but doesn't have a Yarn version, then we merge the two,
     ///   pulling Yarn from the user default platform, if available
     /// - If there is no Project platform, then we use the user Default Platform
+    ///
+    /// pnpm is pulled from the user default platform in the same way, whenever the project
+    /// platform doesn't have a pnpm version.
     pub fn current_platform(&self) -> Fallible<Option<SourcedPlatformSpec>> {
         if let Some(platform) = self.project_platform()? {
+            let platform = if platform.pnpm.is_some() {
+                platform
+            } else {
+                match self.default_platform()?.and_then(|p| p.pnpm.clone()) {
+                    Some(default_pnpm) => Rc::new(PlatformSpec {
+                        pnpm: Some(default_pnpm),
+                        ..(*platform).clone()
+                    }),
+                    None => platform,
+                }
+            };
+
             if platform.yarn.is_some() {
                 Ok(Some(SourcedPlatformSpec::project(platform)))
             } else {
@@ -126,16 +209,36 @@ impl Session {
                     node_runtime: platform.node_runtime.clone(),
                     npm: platform.npm.clone(),
                     yarn: default_yarn,
+                    pnpm: platform.pnpm.clone(),
                 });
                 Ok(Some(SourcedPlatformSpec::merged(merged)))
             }
         } else if let Some(platform) = self.default_platform()? {
+            if settings()?.strict {
+                self.check_pinned()?;
+            }
+
+            // The `packageManager` field of an unpinned project still selects its package
+            // manager, in place of the default one
+            let platform = match self.project()? {
+                Some(project) => project.with_package_manager(platform, true),
+                None => platform,
+            };
             Ok(Some(SourcedPlatformSpec::default(platform)))
         } else {
             Ok(None)
         }
     }
 
+    /// Returns an error if the current project doesn't pin its own platform, for strict mode,
+    /// where a project never falls back to the default platform
+    pub fn check_pinned(&self) -> Fallible<()> {
+        if self.project()?.is_some() && self.project_platform()?.is_none() {
+            throw!(ErrorDetails::NoProjectNode);
+        }
+        Ok(())
+    }
+
     /// Returns the user's default platform, if any
     pub fn default_platform(&self) -> Fallible<Option<Rc<PlatformSpec>>> {
         let toolchain = self.toolchain.get()?;
@@ -145,13 +248,71 @@ impl Session {
     }
 
     /// Returns the current project's pinned platform image, if any.
+    ///
+    /// If the project isn't pinned and the `useEngines` setting is enabled, the newest locally available
+    /// Node that satisfies the project's `engines.node` requirement is used instead.
     pub fn project_platform(&self) -> Fallible<Option<Rc<PlatformSpec>>> {
+        // A project that pinned its platform the last time it was seen doesn't need to be loaded
+        if !self.project.is_loaded() {
+            if let Some(root) = current_project_root()? {
+                if let Some(platform) = platform_cache::read_platform(&root) {
+                    return Ok(Some(Rc::new(platform)));
+                }
+            }
+        }
+
         if let Some(ref project) = self.project()? {
-            return Ok(project.platform());
+            if let Some(platform) = project.platform() {
+                // The `packageManager` field is applied according to a setting, which the
+                // cache wouldn't notice changing, so those projects are always loaded
+                if project.is_pinned()
+                    && !project.violates_engines()
+                    && project.package_manager().is_none()
+                {
+                    platform_cache::write_platform(
+                        project.project_root(),
+                        &project.manifest().extended_files,
+                        &platform,
+                    );
+                }
+                return Ok(Some(platform));
+            }
+
+            if settings()?.use_engines {
+                if let Some(engines) = project.engines() {
+                    return Ok(self
+                        .engines_platform(engines)?
+                        .map(|platform| project.with_package_manager(platform, true)));
+                }
+            }
         }
         Ok(None)
     }
 
+    /// Finds the newest Node in the inventory that satisfies the `engines` requirement
+    fn engines_platform(&self, engines: &str) -> Fallible<Option<Rc<PlatformSpec>>> {
+        let inventory = self.inventory()?;
+        let node = inventory
+            .node
+            .versions
+            .iter()
+            .rev()
+            .find(|version| satisfies_engines(version, engines).unwrap_or(false));
+
+        Ok(node.map(|node_runtime| {
+            debug!(
+                "Using Node {} to satisfy `engines.node` requirement ({})",
+                node_runtime, engines
+            );
+            Rc::new(PlatformSpec {
+                node_runtime: node_runtime.clone(),
+                npm: None,
+                yarn: None,
+                pnpm: None,
+            })
+        }))
+    }
+
     /// Produces a reference to the current inventory.
     pub fn inventory(&self) -> Fallible<&Inventory> {
         self.inventory.get()
@@ -177,26 +338,60 @@ impl Session {
         self.hooks.get()
     }
 
+    /// Produces a reference to the npm flags that packages are fetched and installed with
+    pub fn npm_flags(&self) -> &NpmFlags {
+        &self.npm_flags
+    }
+
+    /// Sets the npm flags that packages are fetched and installed with, as given to `volta
+    /// install` or to an intercepted global install
+    pub fn set_npm_flags(&mut self, flags: NpmFlags) {
+        self.npm_flags = flags;
+    }
+
     /// Ensures that a specific Node version has been fetched and unpacked
     pub(crate) fn ensure_node(&mut self, version: &Version) -> Fallible<()> {
-        let inventory = self.inventory.get_mut()?;
-
-        if !inventory.node.versions.contains(version) {
-            Node::new(version.clone()).fetch_internal(self)?;
+        let node = Node::new(version.clone());
+        let toolchain_dir = local_toolchain_dir()?;
+        if !node.is_fetched(self, toolchain_dir.as_deref())? {
+            auto_install("node", version)?;
         }
 
+        match toolchain_dir {
+            Some(toolchain_dir) => node.fetch_local(self, &toolchain_dir)?,
+            None => node.fetch_internal(self)?,
+        };
         Ok(())
     }
 
+    /// Ensures that a specific npm version has been fetched and unpacked
+    pub(crate) fn ensure_npm(&mut self, version: &Version) -> Fallible<()> {
+        let npm = Npm::new(version.clone());
+        if !npm.is_fetched(self)? {
+            auto_install("npm", version)?;
+        }
+
+        npm.fetch_internal(self)
+    }
+
     /// Ensures that a specific Yarn version has been fetched and unpacked
     pub(crate) fn ensure_yarn(&mut self, version: &Version) -> Fallible<()> {
-        let inventory = self.inventory.get_mut()?;
+        let yarn = Yarn::new(version.clone());
+        if !yarn.is_fetched(self)? {
+            auto_install("yarn", version)?;
+        }
+
+        yarn.fetch_internal(self)
+    }
 
-        if !inventory.yarn.versions.contains(version) {
-            Yarn::new(version.clone()).fetch_internal(self)?;
+    /// Ensures that a specific pnpm version has been fetched and unpacked
+    pub(crate) fn ensure_pnpm(&mut self, version: &Version) -> Fallible<()> {
+        let pnpm = Pnpm::new(version.clone());
+        if !pnpm.is_fetched(self)? {
+            auto_install("pnpm", version)?;
         }
 
-        Ok(())
+        pnpm.fetch_internal(self)
     }
 
     pub fn add_event_start(&mut self, activity_kind: ActivityKind) {
@@ -208,11 +403,29 @@ impl Session {
     pub fn add_event_tool_end(&mut self, activity_kind: ActivityKind, exit_code: i32) {
         self.event_log.add_event_tool_end(activity_kind, exit_code)
     }
+    pub fn add_event_install_start(&mut self, activity_kind: ActivityKind, tool: String) {
+        self.event_log.add_event_install_start(activity_kind, tool)
+    }
+    pub fn add_event_install_end(
+        &mut self,
+        activity_kind: ActivityKind,
+        tool: String,
+        exit_code: ExitCode,
+    ) {
+        self.event_log
+            .add_event_install_end(activity_kind, tool, exit_code)
+    }
+    pub fn add_event_shim_created(&mut self, activity_kind: ActivityKind, shim: String) {
+        self.event_log.add_event_shim_created(activity_kind, shim)
+    }
+    pub fn add_event_tool_use(&mut self, activity_kind: ActivityKind, version: String) {
+        self.event_log.add_event_tool_use(activity_kind, version)
+    }
     pub fn add_event_error(&mut self, activity_kind: ActivityKind, error: &VoltaError) {
         self.event_log.add_event_error(activity_kind, error)
     }
 
-    fn publish_to_event_log(mut self) {
+    fn publish_to_event_log(&mut self) {
         match publish_plugin(&self.hooks) {
             Ok(plugin) => {
                 self.event_log.publish(plugin);
@@ -223,23 +436,91 @@ impl Session {
         }
     }
 
-    pub fn exit(self, code: ExitCode) -> ! {
+    /// Stops the usage metrics of this session from being recorded, for when the Volta home they
+    /// would be written to has been moved away
+    pub(crate) fn skip_metrics(&mut self) {
+        self.records_metrics = false;
+    }
+
+    fn record_metrics(&self) {
+        if self.records_metrics {
+            self.event_log.record_metrics();
+        }
+    }
+
+    pub fn exit(&mut self, code: ExitCode) -> ! {
+        self.record_metrics();
         self.publish_to_event_log();
-        code.exit();
+        exit_policy().exit(code);
     }
 
-    pub fn exit_tool(self, code: i32) -> ! {
+    pub fn exit_tool(&mut self, code: i32) -> ! {
+        self.record_metrics();
         self.publish_to_event_log();
         exit(code);
     }
 }
 
+/// Returns the active version of Node and where it comes from, for `volta prompt`
+///
+/// A prompt is printed after every shell command, so no session is created when the project's
+/// platform is in the platform cache or when there is no project: each of those only reads one
+/// small file. Projects that haven't been cached yet are resolved the way a session does.
+pub fn active_node() -> Fallible<Option<(Version, Source)>> {
+    let root = match current_project_root()? {
+        Some(root) => root,
+        None => {
+            // Loading the toolchain would create a missing platform file, which a prompt shouldn't
+            if !volta_home()?.default_platform_file().exists() {
+                return Ok(None);
+            }
+            let toolchain = LazyToolchain::init();
+            return Ok(toolchain
+                .get()?
+                .platform_ref()
+                .map(|platform| (platform.node_runtime.clone(), Source::Default)));
+        }
+    };
+
+    if let Some(platform) = platform_cache::read_platform(&root) {
+        return Ok(Some((platform.node_runtime, Source::Project)));
+    }
+
+    let session = Session::init();
+    if let Some(platform) = session.project_platform()? {
+        return Ok(Some((platform.node_runtime.clone(), Source::Project)));
+    }
+    Ok(session
+        .default_platform()?
+        .map(|platform| (platform.node_runtime.clone(), Source::Default)))
+}
+
 fn publish_plugin(hooks: &LazyHookConfig) -> Fallible<Option<&Publish>> {
     let hooks = hooks.get()?;
     let publish = hooks.events().and_then(|events| events.publish.as_ref());
     Ok(publish)
 }
 
+/// Checks that a missing tool may be installed to run a command, letting the user know that it
+/// is being installed, since the download may take a while.
+///
+/// Nothing is ever prompted, so that commands run the same way in CI: a missing tool is either
+/// installed or reported as an error, depending on the `autoinstall` setting.
+fn auto_install(tool: &str, version: &Version) -> Fallible<()> {
+    if !settings()?.autoinstall {
+        throw!(ErrorDetails::ToolchainNotInstalled {
+            tool: tool.to_string(),
+            version: version.to_string(),
+        });
+    }
+
+    info!(
+        "Installing {}, which isn't installed yet",
+        tool_version(tool, version)
+    );
+    Ok(())
+}
+
 #[cfg(test)]
 pub mod tests {
 
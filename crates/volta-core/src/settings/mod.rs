@@ -0,0 +1,615 @@
+//! Provides the user-configurable settings for Volta.
+//!
+//! Settings are read from `settings.json` in the Volta home directory, which can be overridden
+//! per-project by `.volta/settings.json` in the project root, which in turn can be overridden
+//! by `VOLTA_*` environment variables. A project file can only change the settings that are safe
+//! to take from a cloned repository, see `RawSettings::for_project`.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::error::ErrorDetails;
+use crate::integrity;
+use crate::layout::volta_home;
+use crate::project::current_project_root;
+use double_checked_cell::DoubleCheckedCell;
+use lazy_static::lazy_static;
+use log::debug;
+use volta_fail::{throw, ExitPolicy, Fallible, SignalExit};
+
+pub(crate) mod serial;
+
+lazy_static! {
+    static ref SETTINGS: DoubleCheckedCell<Settings> = DoubleCheckedCell::new();
+}
+
+/// How progress is shown for long-running operations like downloads
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Progress {
+    /// Show progress bars and spinners
+    Bar,
+    /// Don't show any progress
+    None,
+}
+
+impl FromStr for Progress {
+    type Err = ErrorDetails;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bar" => Ok(Progress::Bar),
+            "none" => Ok(Progress::None),
+            _ => Err(invalid_setting("progress", s, "'bar' or 'none'")),
+        }
+    }
+}
+
+/// How shims are created in the shim directory
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShimStrategy {
+    /// Shims are symlinks to the shim executable
+    Symlink,
+    /// Shims are copies of the shim executable, for file systems that don't support symlinks
+    Copy,
+}
+
+impl FromStr for ShimStrategy {
+    type Err = ErrorDetails;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "symlink" => Ok(ShimStrategy::Symlink),
+            "copy" => Ok(ShimStrategy::Copy),
+            _ => Err(invalid_setting("shimStrategy", s, "'symlink' or 'copy'")),
+        }
+    }
+}
+
+/// How downloaded Node tarballs are kept in the inventory
+///
+/// Only Node tarballs are recompressed: the archives of npm, Yarn and pnpm are small already, and
+/// Node's zip archives on Windows are kept as they were downloaded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArchiveCompression {
+    /// Tarballs are kept as they were downloaded
+    Gzip,
+    /// Tarballs are recompressed with zstd, which makes them notably smaller, at the cost of some
+    /// time when they are first fetched
+    Zstd,
+}
+
+impl FromStr for ArchiveCompression {
+    type Err = ErrorDetails;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" => Ok(ArchiveCompression::Gzip),
+            "zstd" => Ok(ArchiveCompression::Zstd),
+            _ => Err(invalid_setting("archiveCompression", s, "'gzip' or 'zstd'")),
+        }
+    }
+}
+
+/// How the npm lifecycle scripts are run when global packages are installed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InstallScripts {
+    /// The scripts are run by npm, as they would be outside of Volta
+    Run,
+    /// The scripts are run after the dependencies are installed, with an environment that is
+    /// cleared of credentials and points HTTP clients to a proxy that doesn't exist. This is not a
+    /// security boundary: the scripts can still reach the network and write anywhere the user can
+    CleanEnv,
+    /// The scripts are skipped, and the packages that have some are listed
+    Ignore,
+}
+
+impl FromStr for InstallScripts {
+    type Err = ErrorDetails;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "run" => Ok(InstallScripts::Run),
+            "cleanEnv" => Ok(InstallScripts::CleanEnv),
+            "ignore" => Ok(InstallScripts::Ignore),
+            _ => Err(invalid_setting(
+                "installScripts",
+                s,
+                "'run', 'cleanEnv' or 'ignore'",
+            )),
+        }
+    }
+}
+
+/// Where Node builds are fetched from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NodeBuilds {
+    /// The unofficial-builds project is used for systems that nodejs.org doesn't publish for
+    Auto,
+    /// Builds are always fetched from nodejs.org
+    Official,
+    /// Builds are always fetched from the unofficial-builds project
+    Unofficial,
+}
+
+impl FromStr for NodeBuilds {
+    type Err = ErrorDetails;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(NodeBuilds::Auto),
+            "official" => Ok(NodeBuilds::Official),
+            "unofficial" => Ok(NodeBuilds::Unofficial),
+            _ => Err(invalid_setting(
+                "nodeBuilds",
+                s,
+                "'auto', 'official' or 'unofficial'",
+            )),
+        }
+    }
+}
+
+/// Which pin is used when a project pins a package manager both in its `volta` key and in the
+/// `packageManager` field used by corepack
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PackageManagerPrecedence {
+    /// The version in the `volta` key is used
+    Volta,
+    /// The version in the `packageManager` field is used
+    PackageManager,
+}
+
+impl FromStr for PackageManagerPrecedence {
+    type Err = ErrorDetails;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "volta" => Ok(PackageManagerPrecedence::Volta),
+            "packageManager" => Ok(PackageManagerPrecedence::PackageManager),
+            _ => Err(invalid_setting(
+                "packageManagerPrecedence",
+                s,
+                "'volta' or 'packageManager'",
+            )),
+        }
+    }
+}
+
+/// Volta settings, with every layer applied
+#[derive(Debug, PartialEq)]
+pub struct Settings {
+    /// Never access the network, using only tools and indexes that are available locally
+    pub offline: bool,
+    /// The root URL of a mirror of the Node distribution server
+    pub node_mirror: Option<String>,
+    /// How progress is shown for downloads
+    pub progress: Progress,
+    /// Whether to include backtraces in error logs
+    pub backtrace: bool,
+    /// How shims are created
+    pub shim_strategy: ShimStrategy,
+    /// Whether `.nvmrc` and `.node-version` files are used to select a Node version
+    pub node_version_files: bool,
+    /// Whether `engines.node` is used to select a Node version in unpinned projects
+    pub use_engines: bool,
+    /// Whether tools run with a minimal `PATH` of the image and system directories, instead of
+    /// the inherited `PATH`
+    pub isolate_path: bool,
+    /// Whether Node is unpacked into `.volta/tools` in the project, instead of the shared images
+    pub local_toolchain: bool,
+    /// How many seconds to wait for another Volta process to release the lock on the Volta home
+    pub lock_timeout: u64,
+    /// Whether downloaded Node archives are kept in the inventory, so that the same version can
+    /// be unpacked again without downloading it
+    pub cache_archives: bool,
+    /// How many seconds a downloaded Node index is used before checking for a newer one, instead
+    /// of the lifetime given by the server
+    pub node_index_max_age: Option<u64>,
+    /// Which pin wins when a project's `volta` key and its `packageManager` field both specify
+    /// the same package manager
+    pub package_manager_precedence: PackageManagerPrecedence,
+    /// Whether a tool needed to run a command is fetched automatically when it isn't installed,
+    /// instead of failing. Installing never prompts, so this behaves the same in CI.
+    pub autoinstall: bool,
+    /// Whether running a tool in a project that doesn't pin Node fails, instead of using the
+    /// default platform
+    pub strict: bool,
+    /// Whether a package manager that is neither pinned by the project nor part of the default
+    /// platform is run from the system `PATH`, instead of failing. This only applies to Yarn and
+    /// pnpm: npm comes with every Node version, and Node, npm and npx are always run from the
+    /// system `PATH` when there is neither a project nor a default platform.
+    pub passthrough: bool,
+    /// Environment variables that are removed before running a tool, like a `NODE_PATH` meant for
+    /// a different Node version. Projects can't change this, since it applies to every tool.
+    pub remove_env: Vec<String>,
+    /// Environment variables that are set before running a tool, like `npm_config_prefix`.
+    /// Projects can't change this, since it would let them inject code like `NODE_OPTIONS`.
+    pub set_env: BTreeMap<String, String>,
+    /// Whether the directories for the other side of WSL (Windows directories under WSL, or Linux
+    /// directories on Windows) that have Node or a package manager in them are left on the `PATH`
+    /// when running tools
+    pub cross_os_passthrough: bool,
+    /// Whether Node is fetched from nodejs.org or from the unofficial-builds project
+    pub node_builds: NodeBuilds,
+    /// Whether Node is built from its source tarball when there is no prebuilt Node for the
+    /// system, which is only supported on Unix and respects `MAKEFLAGS`
+    pub build_from_source: bool,
+    /// Whether changes to the toolchain (fetches, installs, pins and shims) are refused, for
+    /// shared machines where the tools are managed centrally. Only the user settings and
+    /// `VOLTA_READ_ONLY` can change this, so that a project can't turn it off.
+    pub read_only: bool,
+    /// Whether anonymized counts of the commands run and the tool versions used are recorded
+    /// locally, which is only possible in builds with the `metrics` feature
+    pub metrics: bool,
+    /// The URL that `volta metrics --send` posts the recorded counts to
+    pub metrics_endpoint: Option<String>,
+    /// The status that Volta exits with for an unknown error, for CI systems that treat a
+    /// status of 1 specially
+    pub unknown_error_exit_code: i32,
+    /// How the exit status is chosen when Volta is interrupted or a tool is terminated by a
+    /// signal, which is the status of an unknown error unless the shell convention is chosen
+    pub signal_exit: SignalExit,
+    /// Whether to warn about legacy formats and superseded flags that will be removed in a
+    /// future version
+    pub deprecation_warnings: bool,
+    /// A read-only directory of tool archives, laid out like the inventory, that is checked before
+    /// downloading a tool, so that the machines sharing it don't each download the same archives
+    pub cache_shared_dir: Option<PathBuf>,
+    /// Whether the files that fetched Node versions have in common are kept once in the store and
+    /// hardlinked into each image, instead of once per version
+    pub dedup_images: bool,
+    /// How downloaded Node tarballs are kept in the inventory, once they have been verified
+    pub archive_compression: ArchiveCompression,
+    /// Whether running a tool in a project shows a notice when the pinned Node line has a newer
+    /// security release, which is off by default
+    pub node_update_notices: bool,
+    /// How the npm lifecycle scripts of global packages and their dependencies are run when the
+    /// packages are installed, which projects can't change
+    pub install_scripts: InstallScripts,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            offline: false,
+            node_mirror: None,
+            progress: Progress::Bar,
+            backtrace: false,
+            shim_strategy: ShimStrategy::Symlink,
+            node_version_files: false,
+            use_engines: false,
+            isolate_path: false,
+            local_toolchain: false,
+            lock_timeout: 60,
+            cache_archives: true,
+            node_index_max_age: None,
+            package_manager_precedence: PackageManagerPrecedence::Volta,
+            autoinstall: true,
+            strict: false,
+            passthrough: false,
+            remove_env: Vec::new(),
+            set_env: BTreeMap::new(),
+            cross_os_passthrough: false,
+            node_builds: NodeBuilds::Auto,
+            build_from_source: false,
+            read_only: false,
+            metrics: false,
+            metrics_endpoint: None,
+            unknown_error_exit_code: 1,
+            signal_exit: SignalExit::UnknownError,
+            deprecation_warnings: true,
+            cache_shared_dir: None,
+            dedup_images: true,
+            archive_compression: ArchiveCompression::Gzip,
+            node_update_notices: false,
+            install_scripts: InstallScripts::Run,
+        }
+    }
+}
+
+/// Returns the current settings, loading them the first time they are needed.
+pub fn settings<'a>() -> Fallible<&'a Settings> {
+    SETTINGS.get_or_try_init(|| {
+        let home = volta_home()?;
+        integrity::verify(home, home.default_settings_file())?;
+        let user = serial::RawSettings::from_file(home.default_settings_file())?;
+        let project = project_settings()?;
+        let env = serial::RawSettings::from_env()?;
+
+        let mut raw = serial::RawSettings::default();
+        for layer in user.into_iter().chain(project).chain(Some(env)) {
+            raw = raw.merge(layer);
+        }
+        raw.into_settings()
+    })
+}
+
+/// Turns on error backtraces if the `backtrace` setting is enabled.
+///
+/// Backtraces are only captured when `RUST_BACKTRACE` is set at the time an error is created, so
+/// this needs to be called before anything that can fail. Errors loading the settings are
+/// ignored here, they will be reported as soon as the settings are needed.
+pub fn enable_backtraces() {
+    if let Ok(settings) = settings() {
+        if settings.backtrace && env::var_os("RUST_BACKTRACE").is_none() {
+            env::set_var("RUST_BACKTRACE", "1");
+        }
+    }
+}
+
+/// Returns an error if offline mode is enabled, since a request to `from_url` is needed.
+pub(crate) fn check_online(from_url: &str) -> Fallible<()> {
+    if settings()?.offline {
+        debug!("Offline mode is enabled, not requesting {}", from_url);
+        throw!(ErrorDetails::OfflineModeError {
+            from_url: from_url.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Returns an error if read-only mode is enabled, since `operation` would change the toolchain.
+pub(crate) fn check_mutable(operation: &str) -> Fallible<()> {
+    if settings()?.read_only {
+        debug!("Read-only mode is enabled, refusing to {}", operation);
+        throw!(ErrorDetails::ReadOnlyModeError {
+            operation: operation.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Returns the mapping from exit codes to the status that the process exits with.
+///
+/// This is used while exiting, so the default mapping is used if the settings can't be loaded.
+pub fn exit_policy() -> ExitPolicy {
+    match settings() {
+        Ok(settings) => ExitPolicy {
+            unknown_error: settings.unknown_error_exit_code,
+            signal_exit: settings.signal_exit,
+        },
+        Err(_) => ExitPolicy::default(),
+    }
+}
+
+/// Returns the per-project settings for the current directory, if any.
+fn project_settings() -> Fallible<Option<serial::RawSettings>> {
+    match current_project_root()? {
+        Some(project_dir) => {
+            let path = project_dir.join(".volta").join("settings.json");
+            let settings = serial::RawSettings::from_file(&path)?;
+
+            if settings.is_some() {
+                debug!("Found project settings in '{}'", path.display());
+            }
+
+            Ok(settings.map(serial::RawSettings::for_project))
+        }
+        None => Ok(None),
+    }
+}
+
+fn validate_url(setting: &str, url: String) -> Fallible<String> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(url.trim_end_matches('/').to_string())
+    } else {
+        Err(invalid_setting(setting, &url, "an http:// or https:// URL").into())
+    }
+}
+
+/// Checks that an exit status can be reported by every platform and doesn't mean success
+fn validate_exit_status(setting: &str, code: i32) -> Fallible<i32> {
+    if code > 0 && code < 256 {
+        Ok(code)
+    } else {
+        Err(invalid_setting(setting, &code.to_string(), "a number from 1 to 255").into())
+    }
+}
+
+/// Parses the `signalExit` setting, which is a function since `SignalExit` is defined in
+/// volta-fail and can't implement `FromStr` here
+fn parse_signal_exit(s: &str) -> Fallible<SignalExit> {
+    match s {
+        "shell" => Ok(SignalExit::Shell),
+        "unknownError" => Ok(SignalExit::UnknownError),
+        _ => Err(invalid_setting("signalExit", s, "'shell' or 'unknownError'").into()),
+    }
+}
+
+/// Checks that every name in an environment setting can be used as a variable name
+fn validate_env_names<'a, I>(setting: &str, names: I) -> Fallible<()>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    for name in names {
+        if name.is_empty() || name.contains('=') || name.contains('\0') {
+            throw!(invalid_setting(
+                setting,
+                name,
+                "environment variable names without '='"
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn invalid_setting(setting: &str, value: &str, expected: &str) -> ErrorDetails {
+    ErrorDetails::InvalidSetting {
+        setting: setting.to_string(),
+        value: value.to_string(),
+        expected: expected.to_string(),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::serial::RawSettings;
+    use super::{
+        ArchiveCompression, InstallScripts, NodeBuilds, PackageManagerPrecedence, Progress,
+        Settings, ShimStrategy,
+    };
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+    use volta_fail::SignalExit;
+
+    fn fixture_path(fixture_dir: &str) -> PathBuf {
+        let mut cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        cargo_manifest_dir.push("fixtures");
+        cargo_manifest_dir.push(fixture_dir);
+        cargo_manifest_dir
+    }
+
+    #[test]
+    fn test_from_file() {
+        let file = fixture_path("settings").join("settings.json");
+        let settings = RawSettings::from_file(&file)
+            .unwrap()
+            .unwrap()
+            .into_settings()
+            .unwrap();
+
+        assert_eq!(
+            settings,
+            Settings {
+                offline: true,
+                node_mirror: Some("https://mirror.example.com/node".to_string()),
+                progress: Progress::None,
+                backtrace: false,
+                shim_strategy: ShimStrategy::Copy,
+                node_version_files: true,
+                use_engines: false,
+                isolate_path: true,
+                local_toolchain: false,
+                lock_timeout: 60,
+                cache_archives: true,
+                node_index_max_age: None,
+                package_manager_precedence: PackageManagerPrecedence::Volta,
+                autoinstall: true,
+                strict: false,
+                passthrough: false,
+                remove_env: Vec::new(),
+                set_env: BTreeMap::new(),
+                cross_os_passthrough: false,
+                node_builds: NodeBuilds::Unofficial,
+                build_from_source: false,
+                read_only: false,
+                metrics: false,
+                metrics_endpoint: None,
+                unknown_error_exit_code: 1,
+                signal_exit: SignalExit::UnknownError,
+                deprecation_warnings: true,
+                cache_shared_dir: None,
+                dedup_images: true,
+                archive_compression: ArchiveCompression::Gzip,
+                node_update_notices: false,
+                install_scripts: InstallScripts::Run,
+            }
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let user = RawSettings::from_file(&fixture_path("settings").join("settings.json"))
+            .unwrap()
+            .unwrap();
+        let project = RawSettings {
+            offline: Some(false),
+            shim_strategy: Some("symlink".to_string()),
+            ..RawSettings::default()
+        };
+        let settings = user.merge(project).into_settings().unwrap();
+
+        assert!(!settings.offline);
+        assert_eq!(settings.shim_strategy, ShimStrategy::Symlink);
+        assert_eq!(settings.progress, Progress::None);
+    }
+
+    #[test]
+    fn test_project_cannot_change_excluded_settings() {
+        let file = fixture_path("settings").join("project-settings.json");
+        let project = RawSettings::from_file(&file).unwrap().unwrap();
+        let settings = RawSettings::default()
+            .merge(project.for_project())
+            .into_settings()
+            .unwrap();
+        let defaults = Settings::default();
+
+        // The settings a project may change are still applied
+        assert_eq!(settings.progress, Progress::None);
+        assert!(settings.use_engines);
+        assert!(settings.node_version_files);
+
+        assert_eq!(settings.node_mirror, defaults.node_mirror);
+        assert_eq!(settings.node_builds, defaults.node_builds);
+        assert_eq!(settings.build_from_source, defaults.build_from_source);
+        assert_eq!(settings.cache_shared_dir, defaults.cache_shared_dir);
+        assert_eq!(settings.install_scripts, defaults.install_scripts);
+        assert_eq!(settings.remove_env, defaults.remove_env);
+        assert_eq!(settings.set_env, defaults.set_env);
+        assert_eq!(settings.shim_strategy, defaults.shim_strategy);
+        assert_eq!(settings.cache_archives, defaults.cache_archives);
+        assert_eq!(settings.dedup_images, defaults.dedup_images);
+        assert_eq!(settings.archive_compression, defaults.archive_compression);
+        assert_eq!(settings.read_only, defaults.read_only);
+        assert_eq!(settings.metrics, defaults.metrics);
+        assert_eq!(settings.metrics_endpoint, defaults.metrics_endpoint);
+    }
+
+    #[test]
+    fn test_project_cannot_clear_user_settings() {
+        let user = RawSettings {
+            read_only: Some(true),
+            install_scripts: Some("ignore".to_string()),
+            ..RawSettings::default()
+        };
+        let project = RawSettings {
+            read_only: Some(false),
+            install_scripts: Some("run".to_string()),
+            ..RawSettings::default()
+        };
+        let settings = user.merge(project.for_project()).into_settings().unwrap();
+
+        assert!(settings.read_only);
+        assert_eq!(settings.install_scripts, InstallScripts::Ignore);
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let raw = RawSettings {
+            progress: Some("fancy".to_string()),
+            ..RawSettings::default()
+        };
+        assert!(raw.into_settings().is_err());
+
+        let raw = RawSettings {
+            package_manager_precedence: Some("corepack".to_string()),
+            ..RawSettings::default()
+        };
+        assert!(raw.into_settings().is_err());
+
+        let raw = RawSettings {
+            node_mirror: Some("mirror.example.com".to_string()),
+            ..RawSettings::default()
+        };
+        assert!(raw.into_settings().is_err());
+
+        let raw = RawSettings {
+            remove_env: Some(vec!["NODE_PATH=".to_string()]),
+            ..RawSettings::default()
+        };
+        assert!(raw.into_settings().is_err());
+
+        let raw = RawSettings {
+            unknown_error_exit_code: Some(0),
+            ..RawSettings::default()
+        };
+        assert!(raw.into_settings().is_err());
+
+        let raw = RawSettings {
+            signal_exit: Some("ignore".to_string()),
+            ..RawSettings::default()
+        };
+        assert!(raw.into_settings().is_err());
+    }
+}
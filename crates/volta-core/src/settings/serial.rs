@@ -0,0 +1,370 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use super::{
+    invalid_setting, parse_signal_exit, validate_env_names, validate_exit_status, validate_url,
+    ArchiveCompression, InstallScripts, NodeBuilds, PackageManagerPrecedence, Progress, Settings,
+    ShimStrategy,
+};
+use crate::env_vars::{self, flag, list, string};
+use crate::error::ErrorDetails;
+use serde::Deserialize;
+use volta_fail::{Fallible, ResultExt};
+
+/// One layer of settings, where any setting may be left unspecified
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawSettings {
+    pub(super) offline: Option<bool>,
+    pub(super) node_mirror: Option<String>,
+    pub(super) progress: Option<String>,
+    pub(super) backtrace: Option<bool>,
+    pub(super) shim_strategy: Option<String>,
+    pub(super) node_version_files: Option<bool>,
+    pub(super) use_engines: Option<bool>,
+    pub(super) isolate_path: Option<bool>,
+    pub(super) local_toolchain: Option<bool>,
+    pub(super) lock_timeout: Option<u64>,
+    pub(super) cache_archives: Option<bool>,
+    pub(super) node_index_max_age: Option<u64>,
+    pub(super) package_manager_precedence: Option<String>,
+    pub(super) autoinstall: Option<bool>,
+    pub(super) strict: Option<bool>,
+    pub(super) passthrough: Option<bool>,
+    pub(super) remove_env: Option<Vec<String>>,
+    pub(super) set_env: Option<BTreeMap<String, String>>,
+    pub(super) cross_os_passthrough: Option<bool>,
+    pub(super) node_builds: Option<String>,
+    pub(super) build_from_source: Option<bool>,
+    pub(super) read_only: Option<bool>,
+    pub(super) metrics: Option<bool>,
+    pub(super) metrics_endpoint: Option<String>,
+    pub(super) unknown_error_exit_code: Option<i32>,
+    pub(super) signal_exit: Option<String>,
+    pub(super) deprecation_warnings: Option<bool>,
+    pub(super) cache_shared_dir: Option<String>,
+    pub(super) dedup_images: Option<bool>,
+    pub(super) archive_compression: Option<String>,
+    pub(super) node_update_notices: Option<bool>,
+    pub(super) install_scripts: Option<String>,
+}
+
+impl RawSettings {
+    /// Reads a layer of settings from a JSON file, if the file exists.
+    pub(super) fn from_file(file_path: &Path) -> Fallible<Option<Self>> {
+        if !file_path.is_file() {
+            return Ok(None);
+        }
+
+        let file = File::open(file_path).with_context(|_| ErrorDetails::ReadSettingsError {
+            file: file_path.to_path_buf(),
+        })?;
+
+        serde_json::de::from_reader(file)
+            .map(Some)
+            .with_context(|_| ErrorDetails::ParseSettingsError {
+                file: file_path.to_path_buf(),
+            })
+    }
+
+    /// Keeps only the settings that a project may change, since its `.volta/settings.json` comes
+    /// from whoever published the project rather than from the user.
+    ///
+    /// A project can change how its tools are found and how Volta reports on them, but not where
+    /// tools are downloaded from, how they are verified or installed, the environment they run
+    /// in, or the settings that only the user can consent to. Every setting is listed, so that a
+    /// new one has to be sorted into one of the two groups.
+    pub(super) fn for_project(self) -> Self {
+        RawSettings {
+            offline: self.offline,
+            progress: self.progress,
+            backtrace: self.backtrace,
+            node_version_files: self.node_version_files,
+            use_engines: self.use_engines,
+            isolate_path: self.isolate_path,
+            local_toolchain: self.local_toolchain,
+            lock_timeout: self.lock_timeout,
+            node_index_max_age: self.node_index_max_age,
+            package_manager_precedence: self.package_manager_precedence,
+            autoinstall: self.autoinstall,
+            strict: self.strict,
+            passthrough: self.passthrough,
+            cross_os_passthrough: self.cross_os_passthrough,
+            unknown_error_exit_code: self.unknown_error_exit_code,
+            signal_exit: self.signal_exit,
+            deprecation_warnings: self.deprecation_warnings,
+            node_update_notices: self.node_update_notices,
+
+            // Where tools come from and how they are checked
+            node_mirror: None,
+            node_builds: None,
+            build_from_source: None,
+            cache_shared_dir: None,
+            // How tools are installed and run
+            install_scripts: None,
+            remove_env: None,
+            set_env: None,
+            // Machine-wide layout of the Volta home
+            shim_strategy: None,
+            cache_archives: None,
+            dedup_images: None,
+            archive_compression: None,
+            // Only the user can consent to these
+            read_only: None,
+            metrics: None,
+            metrics_endpoint: None,
+        }
+    }
+
+    /// Reads a layer of settings from the `VOLTA_*` environment variables.
+    pub(super) fn from_env() -> Fallible<Self> {
+        Ok(RawSettings {
+            offline: flag(env_vars::VOLTA_OFFLINE),
+            node_mirror: string(env_vars::VOLTA_NODE_MIRROR),
+            progress: string(env_vars::VOLTA_PROGRESS),
+            backtrace: flag(env_vars::VOLTA_BACKTRACE),
+            shim_strategy: string(env_vars::VOLTA_SHIM_STRATEGY),
+            node_version_files: flag(env_vars::VOLTA_NODE_VERSION_FILES),
+            use_engines: flag(env_vars::VOLTA_USE_ENGINES),
+            isolate_path: flag(env_vars::VOLTA_ISOLATE_PATH),
+            local_toolchain: flag(env_vars::VOLTA_LOCAL_TOOLCHAIN),
+            lock_timeout: seconds(env_vars::VOLTA_LOCK_TIMEOUT, "lockTimeout")?,
+            cache_archives: flag(env_vars::VOLTA_CACHE_ARCHIVES),
+            node_index_max_age: seconds(env_vars::VOLTA_NODE_INDEX_MAX_AGE, "nodeIndexMaxAge")?,
+            package_manager_precedence: string(env_vars::VOLTA_PACKAGE_MANAGER_PRECEDENCE),
+            autoinstall: flag(env_vars::VOLTA_AUTOINSTALL),
+            strict: flag(env_vars::VOLTA_STRICT),
+            passthrough: flag(env_vars::VOLTA_PASSTHROUGH),
+            remove_env: list(env_vars::VOLTA_REMOVE_ENV),
+            set_env: assignments(env_vars::VOLTA_SET_ENV, "setEnv")?,
+            cross_os_passthrough: flag(env_vars::VOLTA_CROSS_OS_PASSTHROUGH),
+            node_builds: string(env_vars::VOLTA_NODE_BUILDS),
+            build_from_source: flag(env_vars::VOLTA_BUILD_FROM_SOURCE),
+            read_only: flag(env_vars::VOLTA_READ_ONLY),
+            metrics: flag(env_vars::VOLTA_METRICS),
+            metrics_endpoint: string(env_vars::VOLTA_METRICS_ENDPOINT),
+            unknown_error_exit_code: exit_status(
+                env_vars::VOLTA_UNKNOWN_ERROR_EXIT_CODE,
+                "unknownErrorExitCode",
+            )?,
+            signal_exit: string(env_vars::VOLTA_SIGNAL_EXIT),
+            deprecation_warnings: flag(env_vars::VOLTA_DEPRECATION_WARNINGS),
+            cache_shared_dir: string(env_vars::VOLTA_CACHE_SHARED_DIR),
+            dedup_images: flag(env_vars::VOLTA_DEDUP_IMAGES),
+            archive_compression: string(env_vars::VOLTA_ARCHIVE_COMPRESSION),
+            node_update_notices: flag(env_vars::VOLTA_NODE_UPDATE_NOTICES),
+            install_scripts: string(env_vars::VOLTA_INSTALL_SCRIPTS),
+        })
+    }
+
+    /// Creates a merged layer, with "right" having precedence over "left".
+    pub(super) fn merge(self, right: Self) -> Self {
+        RawSettings {
+            offline: right.offline.or(self.offline),
+            node_mirror: right.node_mirror.or(self.node_mirror),
+            progress: right.progress.or(self.progress),
+            backtrace: right.backtrace.or(self.backtrace),
+            shim_strategy: right.shim_strategy.or(self.shim_strategy),
+            node_version_files: right.node_version_files.or(self.node_version_files),
+            use_engines: right.use_engines.or(self.use_engines),
+            isolate_path: right.isolate_path.or(self.isolate_path),
+            local_toolchain: right.local_toolchain.or(self.local_toolchain),
+            lock_timeout: right.lock_timeout.or(self.lock_timeout),
+            cache_archives: right.cache_archives.or(self.cache_archives),
+            node_index_max_age: right.node_index_max_age.or(self.node_index_max_age),
+            package_manager_precedence: right
+                .package_manager_precedence
+                .or(self.package_manager_precedence),
+            autoinstall: right.autoinstall.or(self.autoinstall),
+            strict: right.strict.or(self.strict),
+            passthrough: right.passthrough.or(self.passthrough),
+            remove_env: right.remove_env.or(self.remove_env),
+            set_env: right.set_env.or(self.set_env),
+            cross_os_passthrough: right.cross_os_passthrough.or(self.cross_os_passthrough),
+            node_builds: right.node_builds.or(self.node_builds),
+            build_from_source: right.build_from_source.or(self.build_from_source),
+            read_only: right.read_only.or(self.read_only),
+            metrics: right.metrics.or(self.metrics),
+            metrics_endpoint: right.metrics_endpoint.or(self.metrics_endpoint),
+            unknown_error_exit_code: right
+                .unknown_error_exit_code
+                .or(self.unknown_error_exit_code),
+            signal_exit: right.signal_exit.or(self.signal_exit),
+            deprecation_warnings: right.deprecation_warnings.or(self.deprecation_warnings),
+            cache_shared_dir: right.cache_shared_dir.or(self.cache_shared_dir),
+            dedup_images: right.dedup_images.or(self.dedup_images),
+            archive_compression: right.archive_compression.or(self.archive_compression),
+            node_update_notices: right.node_update_notices.or(self.node_update_notices),
+            install_scripts: right.install_scripts.or(self.install_scripts),
+        }
+    }
+
+    /// Validates the layer, filling in defaults for any unspecified settings.
+    pub(super) fn into_settings(self) -> Fallible<Settings> {
+        let defaults = Settings::default();
+        Ok(Settings {
+            offline: self.offline.unwrap_or(defaults.offline),
+            node_mirror: self
+                .node_mirror
+                .map(|mirror| validate_url("nodeMirror", mirror))
+                .transpose()?,
+            progress: self
+                .progress
+                .map(|p| p.parse::<Progress>())
+                .transpose()?
+                .unwrap_or(defaults.progress),
+            backtrace: self.backtrace.unwrap_or(defaults.backtrace),
+            shim_strategy: self
+                .shim_strategy
+                .map(|s| s.parse::<ShimStrategy>())
+                .transpose()?
+                .unwrap_or(defaults.shim_strategy),
+            node_version_files: self
+                .node_version_files
+                .unwrap_or(defaults.node_version_files),
+            use_engines: self.use_engines.unwrap_or(defaults.use_engines),
+            isolate_path: self.isolate_path.unwrap_or(defaults.isolate_path),
+            local_toolchain: self.local_toolchain.unwrap_or(defaults.local_toolchain),
+            lock_timeout: self.lock_timeout.unwrap_or(defaults.lock_timeout),
+            cache_archives: self.cache_archives.unwrap_or(defaults.cache_archives),
+            node_index_max_age: self.node_index_max_age,
+            package_manager_precedence: self
+                .package_manager_precedence
+                .map(|s| s.parse::<PackageManagerPrecedence>())
+                .transpose()?
+                .unwrap_or(defaults.package_manager_precedence),
+            autoinstall: self.autoinstall.unwrap_or(defaults.autoinstall),
+            strict: self.strict.unwrap_or(defaults.strict),
+            passthrough: self.passthrough.unwrap_or(defaults.passthrough),
+            remove_env: self
+                .remove_env
+                .map(|names| validate_env_names("removeEnv", &names).map(|_| names))
+                .transpose()?
+                .unwrap_or(defaults.remove_env),
+            set_env: self
+                .set_env
+                .map(|vars| validate_env_names("setEnv", vars.keys()).map(|_| vars))
+                .transpose()?
+                .unwrap_or(defaults.set_env),
+            cross_os_passthrough: self
+                .cross_os_passthrough
+                .unwrap_or(defaults.cross_os_passthrough),
+            node_builds: self
+                .node_builds
+                .map(|s| s.parse::<NodeBuilds>())
+                .transpose()?
+                .unwrap_or(defaults.node_builds),
+            build_from_source: self.build_from_source.unwrap_or(defaults.build_from_source),
+            read_only: self.read_only.unwrap_or(defaults.read_only),
+            metrics: self.metrics.unwrap_or(defaults.metrics),
+            metrics_endpoint: self
+                .metrics_endpoint
+                .map(|endpoint| validate_url("metricsEndpoint", endpoint))
+                .transpose()?,
+            unknown_error_exit_code: self
+                .unknown_error_exit_code
+                .map(|code| validate_exit_status("unknownErrorExitCode", code))
+                .transpose()?
+                .unwrap_or(defaults.unknown_error_exit_code),
+            signal_exit: self
+                .signal_exit
+                .map(|s| parse_signal_exit(&s))
+                .transpose()?
+                .unwrap_or(defaults.signal_exit),
+            deprecation_warnings: self
+                .deprecation_warnings
+                .unwrap_or(defaults.deprecation_warnings),
+            cache_shared_dir: self.cache_shared_dir.map(PathBuf::from),
+            dedup_images: self.dedup_images.unwrap_or(defaults.dedup_images),
+            archive_compression: self
+                .archive_compression
+                .map(|c| c.parse::<ArchiveCompression>())
+                .transpose()?
+                .unwrap_or(defaults.archive_compression),
+            node_update_notices: self
+                .node_update_notices
+                .unwrap_or(defaults.node_update_notices),
+            install_scripts: self
+                .install_scripts
+                .map(|s| s.parse::<InstallScripts>())
+                .transpose()?
+                .unwrap_or(defaults.install_scripts),
+        })
+    }
+}
+
+/// Reads an exit status from an environment variable, for the setting it overrides.
+fn exit_status(name: &str, setting: &str) -> Fallible<Option<i32>> {
+    let code = string(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| invalid_setting(setting, &value, "a number from 1 to 255"))
+        })
+        .transpose()?;
+    Ok(code)
+}
+
+/// Reads `NAME=value` pairs from an environment variable, for the setting it overrides.
+fn assignments(name: &str, setting: &str) -> Fallible<Option<BTreeMap<String, String>>> {
+    list(name)
+        .map(|pairs| {
+            pairs
+                .iter()
+                .map(|pair| {
+                    let mut parts = pair.splitn(2, '=');
+                    match (parts.next(), parts.next()) {
+                        (Some(var), Some(value)) => Ok((var.trim().to_string(), value.to_string())),
+                        _ => Err(invalid_setting(
+                            setting,
+                            pair,
+                            "a comma-separated list of NAME=value pairs",
+                        )
+                        .into()),
+                    }
+                })
+                .collect()
+        })
+        .transpose()
+}
+
+/// Reads a number of seconds from an environment variable, for the setting it overrides.
+fn seconds(name: &str, setting: &str) -> Fallible<Option<u64>> {
+    let seconds = string(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| invalid_setting(setting, &value, "a number of seconds"))
+        })
+        .transpose()?;
+    Ok(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_assignments() {
+        env::set_var(
+            "VOLTA_TEST_SET_ENV",
+            "NODE_ENV=production, NODE_OPTIONS=--a=b",
+        );
+        let mut expected = BTreeMap::new();
+        expected.insert("NODE_ENV".to_string(), "production".to_string());
+        expected.insert("NODE_OPTIONS".to_string(), "--a=b".to_string());
+        assert_eq!(
+            assignments("VOLTA_TEST_SET_ENV", "setEnv").unwrap(),
+            Some(expected)
+        );
+
+        env::set_var("VOLTA_TEST_SET_ENV_INVALID", "NODE_ENV");
+        assert!(assignments("VOLTA_TEST_SET_ENV_INVALID", "setEnv").is_err());
+        assert_eq!(
+            assignments("VOLTA_TEST_SET_ENV_UNSET", "setEnv").unwrap(),
+            None
+        );
+    }
+}
@@ -1,12 +1,17 @@
 //! Provides utilities for modifying shims for 3rd-party executables
 
+use std::path::Path;
 use std::{fs, io};
 
 use crate::error::ErrorDetails;
 use crate::fs::symlink_file;
 use crate::layout::{volta_home, volta_install};
+use crate::settings::{check_mutable, settings, ShimStrategy};
 use volta_fail::{throw, FailExt, Fallible};
 
+#[cfg(windows)]
+pub use windows::{create_script_shims, symlink_support, SymlinkSupport};
+
 #[derive(PartialEq)]
 pub enum ShimResult {
     Created,
@@ -16,40 +21,94 @@ pub enum ShimResult {
 }
 
 pub fn create(shim_name: &str) -> Fallible<ShimResult> {
+    check_mutable(&format!("create the shim for '{}'", shim_name))?;
     let executable = volta_install()?.shim_executable();
     let shim = volta_home()?.shim_file(shim_name);
 
     #[cfg(windows)]
     windows::create_git_bash_script(shim_name)?;
 
-    match symlink_file(executable, shim) {
+    let result = match strategy()? {
+        ShimStrategy::Symlink => symlink_file(executable, &shim),
+        ShimStrategy::Copy => copy_file(executable, &shim),
+    };
+
+    match result {
         Ok(_) => Ok(ShimResult::Created),
         Err(err) => {
             if err.kind() == io::ErrorKind::AlreadyExists {
                 Ok(ShimResult::AlreadyExists)
+            } else if err.kind() == io::ErrorKind::PermissionDenied {
+                throw!(ErrorDetails::PermissionDenied {
+                    path: volta_home()?.shim_dir().to_owned(),
+                });
             } else {
                 throw!(err.with_context(|_| ErrorDetails::ShimCreateError {
                     name: shim_name.to_string(),
+                    file: shim,
                 }));
             }
         }
     }
 }
 
+/// Returns the strategy used to create shims
+///
+/// On Windows, symlinks can only be created in Developer Mode or from an elevated prompt, so
+/// shims are copies of the shim executable when neither is available.
+pub fn strategy() -> Fallible<ShimStrategy> {
+    let configured = settings()?.shim_strategy;
+
+    #[cfg(windows)]
+    let configured = available_strategy(configured, symlink_support());
+
+    Ok(configured)
+}
+
+/// Falls back to copying the shim executable when symlinks are configured but can't be created
+#[cfg(windows)]
+fn available_strategy(configured: ShimStrategy, support: SymlinkSupport) -> ShimStrategy {
+    use log::debug;
+
+    if configured == ShimStrategy::Symlink && support == SymlinkSupport::Unavailable {
+        debug!("Symlinks can't be created, copying the shim executable instead");
+        ShimStrategy::Copy
+    } else {
+        configured
+    }
+}
+
+/// Copies the shim executable, failing like creating a symlink would if the shim already exists
+fn copy_file(executable: &Path, shim: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(shim).is_ok() {
+        return Err(io::ErrorKind::AlreadyExists.into());
+    }
+    fs::copy(executable, shim).map(|_| ())
+}
+
 pub fn delete(shim_name: &str) -> Fallible<ShimResult> {
+    check_mutable(&format!("remove the shim for '{}'", shim_name))?;
     let shim = volta_home()?.shim_file(shim_name);
 
     #[cfg(windows)]
-    windows::delete_git_bash_script(shim_name)?;
+    {
+        windows::delete_git_bash_script(shim_name)?;
+        windows::delete_script_shims(shim_name)?;
+    }
 
-    match fs::remove_file(shim) {
+    match fs::remove_file(&shim) {
         Ok(_) => Ok(ShimResult::Deleted),
         Err(err) => {
             if err.kind() == io::ErrorKind::NotFound {
                 Ok(ShimResult::DoesntExist)
+            } else if err.kind() == io::ErrorKind::PermissionDenied {
+                throw!(ErrorDetails::PermissionDenied {
+                    path: volta_home()?.shim_dir().to_owned(),
+                });
             } else {
                 throw!(err.with_context(|_| ErrorDetails::ShimRemoveError {
                     name: shim_name.to_string(),
+                    file: shim,
                 }));
             }
         }
@@ -67,30 +126,170 @@ pub fn delete(shim_name: &str) -> Fallible<ShimResult> {
 #[cfg(windows)]
 mod windows {
     use crate::error::ErrorDetails;
+    use crate::fs::{create_staging_dir, symlink_file};
     use crate::layout::volta_home;
+    use lazy_static::lazy_static;
+    use log::debug;
     use std::fs::{remove_file, write};
     use std::io::ErrorKind;
+    use std::path::PathBuf;
     use volta_fail::{FailExt, Fallible, ResultExt};
+    use winreg::enums::HKEY_LOCAL_MACHINE;
+    use winreg::RegKey;
+
+    const BASH_SCRIPT: &str = r#"cmd //C "$0" "$@""#;
+
+    /// The registry key holding the Developer Mode setting
+    const APP_MODEL_UNLOCK_KEY: &str = r"SOFTWARE\Microsoft\Windows\CurrentVersion\AppModelUnlock";
 
-    const BASH_SCRIPT: &str = r#"cmd //C $0 "$@""#;
+    /// Whether the current user is allowed to create symlinks
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum SymlinkSupport {
+        /// Developer Mode allows every user to create symlinks
+        DeveloperMode,
+        /// The process is elevated, or the user was granted the privilege to create symlinks
+        Privileged,
+        /// Symlinks can't be created
+        Unavailable,
+    }
+
+    lazy_static! {
+        static ref SYMLINK_SUPPORT: SymlinkSupport = detect_symlink_support();
+    }
+
+    /// Determines whether symlinks can be created, which is only checked once per run
+    pub fn symlink_support() -> SymlinkSupport {
+        *SYMLINK_SUPPORT
+    }
+
+    fn detect_symlink_support() -> SymlinkSupport {
+        if developer_mode_enabled() {
+            return SymlinkSupport::DeveloperMode;
+        }
+
+        // Elevation and the symlink privilege can be granted in several ways, so the most
+        // reliable check is to try creating a symlink
+        let created = create_staging_dir().ok().map_or(false, |dir| {
+            symlink_file(dir.path().join("target"), dir.path().join("link")).is_ok()
+        });
+        if created {
+            SymlinkSupport::Privileged
+        } else {
+            debug!("Could not create a test symlink, Developer Mode is not enabled");
+            SymlinkSupport::Unavailable
+        }
+    }
+
+    fn developer_mode_enabled() -> bool {
+        RegKey::predef(HKEY_LOCAL_MACHINE)
+            .open_subkey(APP_MODEL_UNLOCK_KEY)
+            .and_then(|key| key.get_value::<u32, _>("AllowDevelopmentWithoutDevLicense"))
+            .map_or(false, |value| value == 1)
+    }
 
     pub fn create_git_bash_script(shim_name: &str) -> Fallible<()> {
         let script_path = volta_home()?.shim_git_bash_script_file(shim_name);
-        write(script_path, BASH_SCRIPT).with_context(|_| ErrorDetails::ShimCreateError {
+        write(&script_path, BASH_SCRIPT).with_context(|_| ErrorDetails::ShimCreateError {
             name: shim_name.to_string(),
+            file: script_path,
         })
     }
 
     pub fn delete_git_bash_script(shim_name: &str) -> Fallible<()> {
         let script_path = volta_home()?.shim_git_bash_script_file(shim_name);
-        remove_file(script_path).or_else(|e| {
+        delete_script(shim_name, script_path)
+    }
+
+    /// Writes `.cmd` and `.ps1` scripts next to the shim of a binary that is itself a `.cmd` or
+    /// `.ps1` script, so that callers which look for `<name>.cmd` or `<name>.ps1` the way npm
+    /// installs them globally still find the shim
+    pub fn create_script_shims(shim_name: &str) -> Fallible<()> {
+        let home = volta_home()?;
+        let scripts = [
+            (
+                home.shim_cmd_script_file(shim_name),
+                format!("@\"%~dp0\\{}.exe\" %*\r\n", shim_name),
+            ),
+            (
+                home.shim_powershell_script_file(shim_name),
+                format!(
+                    "& \"$PSScriptRoot\\{}.exe\" @args\r\nexit $LASTEXITCODE\r\n",
+                    shim_name
+                ),
+            ),
+        ];
+
+        for (script_path, contents) in scripts.iter() {
+            write(script_path, contents).with_context(|_| ErrorDetails::ShimCreateError {
+                name: shim_name.to_string(),
+                file: script_path.clone(),
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn delete_script_shims(shim_name: &str) -> Fallible<()> {
+        let home = volta_home()?;
+        delete_script(shim_name, home.shim_cmd_script_file(shim_name))?;
+        delete_script(shim_name, home.shim_powershell_script_file(shim_name))
+    }
+
+    fn delete_script(shim_name: &str, script_path: PathBuf) -> Fallible<()> {
+        remove_file(&script_path).or_else(|e| {
             if e.kind() == ErrorKind::NotFound {
                 Ok(())
             } else {
                 Err(e.with_context(|_| ErrorDetails::ShimRemoveError {
                     name: shim_name.to_string(),
+                    file: script_path,
                 }))
             }
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{read, write};
+
+    #[test]
+    fn test_copy_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let executable = dir.path().join("volta-shim");
+        let shim = dir.path().join("node");
+        write(&executable, "shim").unwrap();
+
+        copy_file(&executable, &shim).unwrap();
+        assert_eq!(read(&shim).unwrap(), b"shim");
+
+        // Like a symlink, an existing shim is left alone
+        write(&executable, "a newer shim").unwrap();
+        let error = copy_file(&executable, &shim).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(read(&shim).unwrap(), b"shim");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_available_strategy() {
+        use ShimStrategy::{Copy, Symlink};
+
+        assert_eq!(
+            available_strategy(Symlink, SymlinkSupport::Unavailable),
+            Copy
+        );
+        assert_eq!(
+            available_strategy(Symlink, SymlinkSupport::DeveloperMode),
+            Symlink
+        );
+        assert_eq!(
+            available_strategy(Symlink, SymlinkSupport::Privileged),
+            Symlink
+        );
+        assert_eq!(
+            available_strategy(Copy, SymlinkSupport::DeveloperMode),
+            Copy
+        );
+    }
+}
@@ -1,23 +1,287 @@
+use std::fs::{remove_dir_all, remove_file};
+use std::mem;
+use std::path::PathBuf;
 use std::process::exit;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
 
+use lazy_static::lazy_static;
 use log::debug;
 
+use crate::settings::exit_policy;
+use volta_fail::ExitPolicy;
+
 static SHIM_HAS_CONTROL: AtomicBool = AtomicBool::new(false);
-const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// The process ID of the tool that has control, or 0 before it is started
+static CHILD_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Whether a SIGTERM arrived after control was passed but before the tool was started
+static TERMINATE_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// The number of SIGINT, which an interruption is reported as
+const SIGINT: i32 = 2;
+
+/// The number of SIGTERM, which a termination is reported as
+const SIGTERM: i32 = 15;
+
+lazy_static! {
+    /// Files and directories that should be removed if Volta is interrupted before it is done
+    /// with them, such as partial downloads in the staging directory
+    static ref CLEANUP_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+}
 
 pub fn pass_control_to_shim() {
     SHIM_HAS_CONTROL.store(true, Ordering::SeqCst);
 }
 
+/// Records the process ID of the tool that was given control, so that a SIGTERM sent to Volta
+/// is forwarded to it
+pub fn forward_to_child(id: u32) {
+    CHILD_ID.store(id, Ordering::SeqCst);
+    if TERMINATE_PENDING.swap(false, Ordering::SeqCst) {
+        terminate_child();
+    }
+}
+
+/// Registers a temporary file or directory to be removed if Volta is interrupted
+///
+/// Paths that no longer exist by the time of the interruption (because they were cleaned up
+/// normally or moved into place) are skipped, so there is no need to unregister them.
+pub fn remove_on_interrupt(path: PathBuf) {
+    if let Ok(mut paths) = CLEANUP_PATHS.lock() {
+        paths.push(path);
+    }
+}
+
+/// Sets up the handlers for Ctrl+C (SIGINT) and, on Unix, SIGTERM
+///
+/// While Volta is doing its own work, such as downloading and unpacking tools, either signal
+/// cleans up any temporary files and exits with 128 plus the number of the signal, or the status
+/// chosen by the `signalExit` setting. The process exiting ends any in-flight downloads and
+/// releases any file locks it holds.
+///
+/// Once control has been passed to a tool, Ctrl+C is left for the tool to handle, since the
+/// terminal sends it to the tool as well. A SIGTERM is only sent to Volta, so it is forwarded to
+/// the tool, and Volta exits with the tool's status once it has stopped.
 pub fn setup_signal_handler() {
-    let result = ctrlc::set_handler(|| {
-        if !SHIM_HAS_CONTROL.load(Ordering::SeqCst) {
-            exit(INTERRUPTED_EXIT_CODE);
-        }
-    });
+    let result = ctrlc::set_handler(|| handle_signal(SIGINT));
 
     if result.is_err() {
         debug!("Unable to set Ctrl+C handler, SIGINT will not be handled correctly");
     }
+
+    #[cfg(unix)]
+    {
+        let result = termination::set_handler(|| handle_signal(SIGTERM));
+
+        if result.is_err() {
+            debug!("Unable to set SIGTERM handler, SIGTERM will not be handled correctly");
+        }
+    }
+}
+
+/// What Volta does when it receives a signal
+#[derive(Debug, PartialEq)]
+enum SignalAction {
+    /// Clean up any temporary files and exit with the given status
+    Exit(i32),
+    /// Forward the signal to the tool that has control
+    Forward,
+    /// Forward the signal to the tool once it has started
+    Defer,
+    /// Leave the signal for the tool to handle
+    Ignore,
+}
+
+/// Decides what to do with a signal, given whether a tool has control and its process ID, which
+/// is 0 before it is started
+fn signal_action(
+    signal: i32,
+    shim_has_control: bool,
+    child_id: u32,
+    policy: &ExitPolicy,
+) -> SignalAction {
+    match (shim_has_control, signal) {
+        (false, _) => SignalAction::Exit(policy.signal_status(signal)),
+        (true, SIGTERM) if child_id == 0 => SignalAction::Defer,
+        (true, SIGTERM) => SignalAction::Forward,
+        (true, _) => SignalAction::Ignore,
+    }
+}
+
+fn handle_signal(signal: i32) {
+    let action = signal_action(
+        signal,
+        SHIM_HAS_CONTROL.load(Ordering::SeqCst),
+        CHILD_ID.load(Ordering::SeqCst),
+        &exit_policy(),
+    );
+    match action {
+        SignalAction::Exit(status) => {
+            clean_up();
+            exit(status);
+        }
+        SignalAction::Forward => terminate_child(),
+        SignalAction::Defer => TERMINATE_PENDING.store(true, Ordering::SeqCst),
+        SignalAction::Ignore => {}
+    }
+}
+
+#[cfg(unix)]
+fn terminate_child() {
+    let id = CHILD_ID.load(Ordering::SeqCst);
+    debug!("Forwarding SIGTERM to process {}", id);
+    unsafe {
+        libc::kill(id as libc::pid_t, libc::SIGTERM);
+    }
+}
+
+#[cfg(windows)]
+fn terminate_child() {}
+
+#[cfg(unix)]
+mod termination {
+    use std::io;
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::thread;
+
+    /// The write end of the pipe that wakes up the handler thread
+    static PIPE_WRITER: AtomicI32 = AtomicI32::new(-1);
+
+    extern "C" fn on_signal(_signal: libc::c_int) {
+        let byte = 0u8;
+        unsafe {
+            libc::write(
+                PIPE_WRITER.load(Ordering::SeqCst),
+                &byte as *const u8 as *const libc::c_void,
+                1,
+            );
+        }
+    }
+
+    /// Calls `handler` on a separate thread whenever SIGTERM is received
+    ///
+    /// Almost nothing is safe to do inside of a signal handler, so the handler only writes to a
+    /// pipe, which the thread is waiting on.
+    pub(super) fn set_handler<F>(handler: F) -> io::Result<()>
+    where
+        F: Fn() + Send + 'static,
+    {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // The tool shouldn't inherit the pipe
+        for fd in fds.iter() {
+            unsafe {
+                libc::fcntl(*fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+        }
+        let reader = fds[0];
+        PIPE_WRITER.store(fds[1], Ordering::SeqCst);
+
+        thread::spawn(move || loop {
+            let mut byte = 0u8;
+            let read = unsafe { libc::read(reader, &mut byte as *mut u8 as *mut libc::c_void, 1) };
+            if read == 1 {
+                handler();
+            } else if read == 0 || io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+                break;
+            }
+        });
+
+        if unsafe { libc::signal(libc::SIGTERM, on_signal as libc::sighandler_t) } == libc::SIG_ERR
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+fn clean_up() {
+    let paths = match CLEANUP_PATHS.lock() {
+        Ok(mut paths) => mem::replace(&mut *paths, Vec::new()),
+        Err(_) => return,
+    };
+
+    for path in paths.iter().rev() {
+        let result = if path.is_dir() {
+            remove_dir_all(path)
+        } else if path.exists() {
+            remove_file(path)
+        } else {
+            continue;
+        };
+
+        if result.is_err() {
+            debug!("Unable to remove '{}' after interruption", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir, write};
+    use volta_fail::SignalExit;
+
+    #[test]
+    fn test_clean_up() {
+        let root = tempfile::tempdir().unwrap();
+        let partial_file = root.path().join("node.tar.gz.partial");
+        let staging_dir = root.path().join("staging");
+        write(&partial_file, "partial").unwrap();
+        create_dir(&staging_dir).unwrap();
+        write(staging_dir.join("file"), "unpacked").unwrap();
+
+        remove_on_interrupt(partial_file.clone());
+        remove_on_interrupt(staging_dir.clone());
+        remove_on_interrupt(root.path().join("already-removed"));
+        clean_up();
+
+        assert!(!partial_file.exists());
+        assert!(!staging_dir.exists());
+        assert!(root.path().exists());
+    }
+
+    #[test]
+    fn test_signal_action() {
+        let shell = ExitPolicy {
+            unknown_error: 1,
+            signal_exit: SignalExit::Shell,
+        };
+        assert_eq!(
+            signal_action(SIGINT, false, 0, &shell),
+            SignalAction::Exit(130)
+        );
+        assert_eq!(
+            signal_action(SIGTERM, false, 0, &shell),
+            SignalAction::Exit(143)
+        );
+
+        let remapped = ExitPolicy {
+            unknown_error: 70,
+            signal_exit: SignalExit::UnknownError,
+        };
+        assert_eq!(
+            signal_action(SIGINT, false, 0, &remapped),
+            SignalAction::Exit(70)
+        );
+        assert_eq!(
+            signal_action(SIGTERM, false, 0, &remapped),
+            SignalAction::Exit(70)
+        );
+
+        // Once a tool has control, only SIGTERM is passed on to it, as soon as it has started
+        assert_eq!(
+            signal_action(SIGINT, true, 42, &shell),
+            SignalAction::Ignore
+        );
+        assert_eq!(signal_action(SIGTERM, true, 0, &shell), SignalAction::Defer);
+        assert_eq!(
+            signal_action(SIGTERM, true, 42, &shell),
+            SignalAction::Forward
+        );
+    }
 }
@@ -0,0 +1,159 @@
+//! Provides the execution statistics of the shims, so that users can see which tools are
+//! actually used before removing the ones they no longer need.
+//!
+//! Each run of a shim appends a `<unix timestamp> <tool>` line to a log in the Volta home. The
+//! log is a ring buffer: once it grows past a size limit, only its most recent half is kept, so
+//! recording a run stays a single cheap append, and the statistics cover the recent runs.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs::{read_to_string, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::ErrorDetails;
+use crate::fs::{create_staging_file, read_file_error, write_file_error};
+use crate::layout::volta_home;
+use chrono::{DateTime, Local, TimeZone};
+use fs_utils::ensure_containing_dir_exists;
+use log::debug;
+use volta_fail::{Fallible, ResultExt};
+
+/// The size of the log, in bytes, above which the older half of the runs is dropped
+const MAX_LOG_SIZE: u64 = 256 * 1024;
+
+/// The recorded runs of a tool
+pub struct ToolStats {
+    pub runs: u64,
+    pub last_run: DateTime<Local>,
+}
+
+/// Records a run of the shim for `tool`, logging any failure instead of returning it
+pub(crate) fn record(tool: &OsStr) {
+    if let Err(error) = append(&tool.to_string_lossy()) {
+        debug!("Could not record the run in the statistics: {}", error);
+    }
+}
+
+fn append(tool: &str) -> Fallible<()> {
+    let file = volta_home()?.stats_file();
+    ensure_containing_dir_exists(&file)
+        .with_context(|_| ErrorDetails::ContainingDirError { path: file.clone() })?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let line = format!("{} {}\n", timestamp, tool);
+
+    // Each run is written at once with a single append, so that runs recorded by concurrent
+    // shims don't interleave
+    let size = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&file)
+        .and_then(|mut log| {
+            log.write_all(line.as_bytes())?;
+            log.metadata()
+        })
+        .with_context(write_file_error(&file))?
+        .len();
+
+    if size > MAX_LOG_SIZE {
+        truncate(&file)?;
+    }
+    Ok(())
+}
+
+/// Keeps the most recent half of the log
+///
+/// A run recorded by another shim while the log is being rewritten may be lost, which is fine
+/// for statistics.
+fn truncate(file: &Path) -> Fallible<()> {
+    let contents = read_to_string(file).with_context(read_file_error(&file))?;
+    let kept = most_recent_half(&contents);
+
+    let staged = create_staging_file()?;
+    let mut staged_file: &File = staged.as_file();
+    staged_file
+        .write_all(kept.as_bytes())
+        .with_context(write_file_error(&staged.path()))?;
+    staged.persist(file).with_context(write_file_error(&file))?;
+    Ok(())
+}
+
+/// Returns the complete lines in the second half of the log
+fn most_recent_half(contents: &str) -> &str {
+    // The search is done on the bytes, since the middle may fall inside a multi-byte character
+    let middle = contents.len() / 2;
+    match contents.as_bytes()[middle..]
+        .iter()
+        .position(|&byte| byte == b'\n')
+    {
+        Some(end) => &contents[middle + end + 1..],
+        None => "",
+    }
+}
+
+/// Reads the statistics of every tool with recorded runs, by tool name
+pub fn read() -> Fallible<BTreeMap<String, ToolStats>> {
+    let file = volta_home()?.stats_file();
+    if !file.is_file() {
+        return Ok(BTreeMap::new());
+    }
+
+    let contents = read_to_string(&file).with_context(read_file_error(&file))?;
+    Ok(parse(&contents))
+}
+
+/// Parses the log, skipping any line that can't be read, such as a line truncated by a crash
+fn parse(contents: &str) -> BTreeMap<String, ToolStats> {
+    let mut stats = BTreeMap::new();
+    for line in contents.lines() {
+        let mut parts = line.splitn(2, ' ');
+        let timestamp = match parts
+            .next()
+            .and_then(|timestamp| timestamp.parse::<i64>().ok())
+            .and_then(|timestamp| Local.timestamp_opt(timestamp, 0).single())
+        {
+            Some(timestamp) => timestamp,
+            None => continue,
+        };
+        let tool = match parts.next() {
+            Some(tool) if !tool.is_empty() => tool,
+            _ => continue,
+        };
+
+        let entry = stats.entry(tool.to_string()).or_insert(ToolStats {
+            runs: 0,
+            last_run: timestamp,
+        });
+        entry.runs += 1;
+        if timestamp > entry.last_run {
+            entry.last_run = timestamp;
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let stats = parse("1600000000 tsc\n1600000100 node\n1600000200 tsc\n16000");
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats["tsc"].runs, 2);
+        assert_eq!(stats["tsc"].last_run, Local.timestamp(1_600_000_200, 0));
+        assert_eq!(stats["node"].runs, 1);
+    }
+
+    #[test]
+    fn test_most_recent_half() {
+        let contents = "1600000000 tsc\n1600000100 node\n1600000200 eslint\n";
+        assert_eq!(most_recent_half(contents), "1600000200 eslint\n");
+        assert_eq!(most_recent_half(""), "");
+    }
+}
@@ -0,0 +1,262 @@
+//! Provides the content-addressed store of image files, which lets the images of different Node
+//! versions share the files they have in common, the way the pnpm store does for packages.
+//!
+//! Every regular file of a newly unpacked image is hashed, then replaced with a hardlink to the
+//! file with the same contents in the store, or added to the store if no earlier image had it.
+//! Neighbouring Node versions have most of npm, the headers and the docs in common, so each
+//! additional version mostly takes the space of the files that changed.
+//!
+//! Hardlinks can only be created within a volume, so a store that can't be linked to is simply
+//! not used, and the image keeps its own files. Since all the links to a file share its contents,
+//! the files in the images must never be modified in place.
+//!
+//! A file of the store that no image links to anymore, because the images that had it were
+//! removed, only has the link in the store left. Those files are pruned whenever an image is added
+//! to the store or removed by Volta.
+
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::layout::{long_path, volta_home};
+use cfg_if::cfg_if;
+use log::debug;
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+/// The files that were replaced by links to the store, and the space that was saved
+#[derive(Debug, Default, PartialEq)]
+struct Savings {
+    files: u64,
+    bytes: u64,
+}
+
+/// Stores the files of the image, replacing them with links to the files already in the store
+///
+/// Failures are only logged, since the image is usable either way.
+pub(crate) fn dedup(image_dir: &Path) {
+    let store_dir = match volta_home() {
+        Ok(home) => home.store_dir(),
+        Err(error) => {
+            debug!("Could not find the store: {}", error);
+            return;
+        }
+    };
+
+    match dedup_into(image_dir, store_dir) {
+        Ok(savings) => debug!(
+            "Linked {} files of '{}' to the store, saving {} bytes",
+            savings.files,
+            image_dir.display(),
+            savings.bytes
+        ),
+        Err(error) => debug!(
+            "Could not link the files of '{}' to the store: {}",
+            image_dir.display(),
+            error
+        ),
+    }
+    prune_dir_logged(store_dir);
+}
+
+/// Removes the files of the store that no image links to anymore
+///
+/// Failures are only logged, since the unused files only take up space.
+pub(crate) fn prune() {
+    match volta_home() {
+        Ok(home) => prune_dir_logged(home.store_dir()),
+        Err(error) => debug!("Could not find the store: {}", error),
+    }
+}
+
+fn prune_dir_logged(store_dir: &Path) {
+    match prune_dir(store_dir) {
+        Ok(0) => {}
+        Ok(removed) => debug!("Removed {} unused files from the store", removed),
+        Err(error) => debug!("Could not prune the store: {}", error),
+    }
+}
+
+/// Removes the files whose only link is the one in the store, returning how many were removed
+fn prune_dir(store_dir: &Path) -> io::Result<u64> {
+    if !store_dir.is_dir() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    for entry in WalkDir::new(long_path(store_dir)) {
+        let entry = entry?;
+        if entry.file_type().is_file() && link_count(entry.path())? == 1 {
+            fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+fn dedup_into(image_dir: &Path, store_dir: &Path) -> io::Result<Savings> {
+    let mut savings = Savings::default();
+
+    for entry in WalkDir::new(long_path(image_dir)) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        // Empty files have nothing to share
+        if metadata.len() == 0 {
+            continue;
+        }
+
+        let stored = store_dir.join(store_path(entry.path(), &metadata)?);
+        if stored.is_file() {
+            replace_with_link(&stored, entry.path())?;
+            savings.files += 1;
+            savings.bytes += metadata.len();
+        } else {
+            if let Some(parent) = stored.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::hard_link(entry.path(), &stored)?;
+        }
+    }
+
+    Ok(savings)
+}
+
+/// Returns the path of the file in the store, given by the hash of its contents
+///
+/// On Unix, the permissions are part of the path, since they are shared by all the links too.
+fn store_path(file: &Path, metadata: &Metadata) -> io::Result<PathBuf> {
+    let mut hasher = Sha256::new();
+    let mut contents = File::open(file)?;
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let read = contents.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.input(&buffer[..read]);
+    }
+    let hash = hex::encode(&hasher.result());
+
+    let name = format!("{}{}", &hash[2..], permissions_suffix(metadata));
+    Ok(Path::new(&hash[..2]).join(name))
+}
+
+cfg_if! {
+    if #[cfg(unix)] {
+        fn permissions_suffix(metadata: &Metadata) -> String {
+            use std::os::unix::fs::PermissionsExt;
+            format!("-{:o}", metadata.permissions().mode() & 0o777)
+        }
+    } else {
+        /// Windows has no permission bits to keep apart
+        fn permissions_suffix(_metadata: &Metadata) -> String {
+            String::new()
+        }
+    }
+}
+
+cfg_if! {
+    if #[cfg(unix)] {
+        fn link_count(file: &Path) -> io::Result<u64> {
+            use std::os::unix::fs::MetadataExt;
+            Ok(fs::metadata(file)?.nlink())
+        }
+    } else {
+        /// The number of links isn't part of the metadata that the standard library exposes on
+        /// Windows, so it is read from the file information instead
+        fn link_count(file: &Path) -> io::Result<u64> {
+            use std::mem;
+            use std::os::windows::io::AsRawHandle;
+            use winapi::um::fileapi::{GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION};
+
+            let file = File::open(file)?;
+            let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { mem::zeroed() };
+            if unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(u64::from(info.nNumberOfLinks))
+        }
+    }
+}
+
+/// Replaces the file with a link to the stored file, through a temporary link next to it, so
+/// that the file is never missing
+fn replace_with_link(stored: &Path, file: &Path) -> io::Result<()> {
+    let mut temporary = file.as_os_str().to_owned();
+    temporary.push(".volta-link");
+    let temporary = PathBuf::from(temporary);
+
+    fs::hard_link(stored, &temporary)?;
+    if let Err(error) = fs::rename(&temporary, file) {
+        let _ = fs::remove_file(&temporary);
+        return Err(error);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, read_to_string, write};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_dedup_into() {
+        let dir = tempdir().unwrap();
+        let store_dir = dir.path().join("store");
+        let (first, second) = (dir.path().join("14.15.0"), dir.path().join("14.15.1"));
+        for (image_dir, changed) in [(&first, "v14.15.0"), (&second, "v14.15.1")].iter() {
+            create_dir_all(image_dir.join("include")).unwrap();
+            write(image_dir.join("include").join("node.h"), "#define NODE").unwrap();
+            write(image_dir.join("CHANGELOG.md"), changed).unwrap();
+        }
+
+        assert_eq!(dedup_into(&first, &store_dir).unwrap(), Savings::default());
+        assert_eq!(
+            dedup_into(&second, &store_dir).unwrap(),
+            Savings {
+                files: 1,
+                bytes: 12
+            }
+        );
+
+        let header = second.join("include").join("node.h");
+        assert_eq!(read_to_string(&header).unwrap(), "#define NODE");
+        assert_eq!(
+            read_to_string(second.join("CHANGELOG.md")).unwrap(),
+            "v14.15.1"
+        );
+
+        assert_eq!(link_count(&header).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_prune_dir() {
+        let dir = tempdir().unwrap();
+        let store_dir = dir.path().join("store");
+        let (first, second) = (dir.path().join("14.15.0"), dir.path().join("14.15.1"));
+        for image_dir in [&first, &second].iter() {
+            create_dir_all(image_dir).unwrap();
+            write(image_dir.join("node.h"), "#define NODE").unwrap();
+            write(
+                image_dir.join("CHANGELOG.md"),
+                image_dir.to_string_lossy().as_bytes(),
+            )
+            .unwrap();
+            dedup_into(image_dir, &store_dir).unwrap();
+        }
+
+        assert_eq!(prune_dir(&store_dir).unwrap(), 0);
+
+        fs::remove_dir_all(&first).unwrap();
+        assert_eq!(prune_dir(&store_dir).unwrap(), 1);
+        assert_eq!(link_count(&second.join("node.h")).unwrap(), 2);
+
+        fs::remove_dir_all(&second).unwrap();
+        assert_eq!(prune_dir(&store_dir).unwrap(), 2);
+        assert_eq!(prune_dir(&dir.path().join("missing")).unwrap(), 0);
+    }
+}
@@ -1,7 +1,15 @@
 //! The view layer of Volta, with utilities for styling command-line output.
+//!
+//! Colors are only used when the output is a terminal, unless `--color` asks otherwise, and the
+//! `NO_COLOR` environment variable turns them off. Every styled message goes through the
+//! helpers here, so that the choice applies to all of them.
+use std::str::FromStr;
+
+use crate::env_vars;
+use crate::settings::{settings, Progress};
 use archive::Origin;
 use cfg_if::cfg_if;
-use console::{style, StyledObject};
+use console::{set_colors_enabled, style, StyledObject};
 use failure::Fail;
 use indicatif::{ProgressBar, ProgressStyle};
 use term_size;
@@ -9,6 +17,51 @@ use term_size;
 const MAX_WIDTH: usize = 100;
 const MAX_PROGRESS_WIDTH: usize = 40;
 
+/// When to color the output, from the `--color` flag
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorChoice {
+    /// Colors the output of terminals, unless `NO_COLOR` is set
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(choice: &str) -> Result<Self, Self::Err> {
+        match choice {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("unknown color choice: {}", choice)),
+        }
+    }
+}
+
+/// Applies the color choice to all the styled output
+///
+/// This needs to be called before anything is printed.
+pub fn set_color_choice(choice: ColorChoice) {
+    let no_color = env_vars::string(env_vars::NO_COLOR).is_some();
+    match (choice, no_color) {
+        (ColorChoice::Always, _) => set_colors_enabled(true),
+        (ColorChoice::Never, _) | (ColorChoice::Auto, true) => set_colors_enabled(false),
+        // The colors are detected from the terminal by default
+        (ColorChoice::Auto, false) => {}
+    }
+}
+
+/// Generate the styled prefix for an error message
+pub(crate) fn error_prefix(prefix: &'static str) -> StyledObject<&'static str> {
+    style(prefix).red().bold()
+}
+
+/// Generate the styled prefix for a warning
+pub(crate) fn warning_prefix(prefix: &'static str) -> StyledObject<&'static str> {
+    style(prefix).yellow().bold()
+}
+
 /// Generate the styled prefix for a success message
 pub fn success_prefix() -> StyledObject<&'static str> {
     style("success:").green().bold()
@@ -50,11 +103,25 @@ pub fn text_width() -> Option<usize> {
     term_size::dimensions().map(|(w, _)| w.min(MAX_WIDTH))
 }
 
+/// Whether progress has been turned off with the `progress` setting
+///
+/// Progress is purely cosmetic, so if the settings can't be loaded it is shown as usual and the
+/// error is left to be reported by whichever operation needs the settings next.
+fn progress_hidden() -> bool {
+    settings()
+        .map(|settings| settings.progress == Progress::None)
+        .unwrap_or(false)
+}
+
 /// Constructs a command-line progress bar based on the specified Origin enum
 /// (e.g., `Origin::Remote`), details string (e.g., `"v1.23.4"`), and logical
 /// length (i.e., the number of logical progress steps in the process being
 /// visualized by the progress bar).
 pub fn progress_bar(origin: Origin, details: &str, len: u64) -> ProgressBar {
+    if progress_hidden() {
+        return ProgressBar::hidden();
+    }
+
     let action = action_str(origin);
     let action_width = action.len() + 2; // plus 2 spaces to look nice
     let msg_width = action_width + 1 + details.len();
@@ -92,6 +159,10 @@ cfg_if! {
         /// Constructs a command-line progress spinner with the specified "message"
         /// string. The spinner is ticked by default every 100ms.
         pub fn progress_spinner(message: &str) -> ProgressBar {
+            if progress_hidden() {
+                return ProgressBar::hidden();
+            }
+
             let spinner = ProgressBar::new_spinner();
             // Windows CMD prompt doesn't support Unicode characters, so use a simplified spinner
             let style = ProgressStyle::default_spinner().tick_chars(r#"-\|/-"#);
@@ -106,6 +177,10 @@ cfg_if! {
         /// Constructs a command-line progress spinner with the specified "message"
         /// string. The spinner is ticked by default every 50ms.
         pub fn progress_spinner(message: &str) -> ProgressBar {
+            if progress_hidden() {
+                return ProgressBar::hidden();
+            }
+
             // ⠋ Fetching public registry: https://nodejs.org/dist/index.json
             let spinner = ProgressBar::new_spinner();
 
@@ -0,0 +1,161 @@
+//! Provides the lock that serializes changes to the Volta home directory between processes.
+//!
+//! Concurrent Volta processes (parallel CI jobs sharing a cache, multiple terminals) can try
+//! to fetch or install the same tools at the same time. Before changing the inventory, images
+//! or toolchain, a process takes an exclusive OS lock on the lock file, waiting up to the
+//! `lockTimeout` setting for any other process to release it first. The operating system
+//! releases the lock when the process holding it exits, even if it crashes or is killed, so a
+//! lock file left behind never blocks later processes.
+//!
+//! Since the lock is taken for every change to the Volta home, it is also where changes are
+//! refused when read-only mode is enabled.
+//!
+//! The lock is re-entrant within a process, so operations that take the lock can call each
+//! other freely. The lock is released when the last guard is dropped.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use crate::error::ErrorDetails;
+use crate::fs::check_home_writable;
+use crate::layout::volta_home;
+use crate::settings::{check_mutable, settings};
+use fs2::FileExt;
+use lazy_static::lazy_static;
+use log::debug;
+use volta_fail::{throw, Fallible, ResultExt};
+
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The lock held by this process, along with the number of guards currently holding it
+struct Holders {
+    count: usize,
+    lock: Option<File>,
+}
+
+lazy_static! {
+    static ref HOLDERS: Mutex<Holders> = Mutex::new(Holders {
+        count: 0,
+        lock: None,
+    });
+}
+
+/// A guard for the Volta lock, which is released when the last guard is dropped
+pub struct VoltaLock {
+    file: PathBuf,
+}
+
+impl VoltaLock {
+    /// Takes the Volta lock, waiting for other processes to release it if necessary
+    pub fn acquire() -> Fallible<Self> {
+        let home = volta_home()?;
+        let file = home.lock_file().to_owned();
+        let timeout = settings()?.lock_timeout;
+
+        let mut holders = HOLDERS.lock().unwrap_or_else(|error| error.into_inner());
+        if holders.count == 0 {
+            check_mutable("change the installed tools")?;
+            // Everything done while holding the lock modifies the Volta home, so a home owned by
+            // another user is reported up front, rather than as a failure partway through
+            check_home_writable()?;
+            let lock = lock_file(&file, Duration::from_secs(timeout))
+                .with_context(|_| ErrorDetails::LockFileError { file: file.clone() })?;
+            match lock {
+                Some(lock) => holders.lock = Some(lock),
+                None => throw!(ErrorDetails::LockTimeoutError { file, timeout }),
+            }
+        }
+        holders.count += 1;
+
+        Ok(VoltaLock { file })
+    }
+}
+
+impl Drop for VoltaLock {
+    fn drop(&mut self) {
+        let mut holders = HOLDERS.lock().unwrap_or_else(|error| error.into_inner());
+        holders.count -= 1;
+        if holders.count == 0 {
+            debug!("Releasing lock at '{}'", self.file.display());
+            // The file itself is left in place, since another process may already be waiting
+            // on it. Closing it releases the lock.
+            if let Some(lock) = holders.lock.take() {
+                if lock.unlock().is_err() {
+                    debug!("Unable to unlock '{}'", self.file.display());
+                }
+            }
+        }
+    }
+}
+
+/// Takes an exclusive lock on the lock file, retrying until `timeout` has passed if another
+/// process holds it
+///
+/// Returns `Ok(None)` if the lock couldn't be taken before the timeout.
+fn lock_file(file: &Path, timeout: Duration) -> io::Result<Option<File>> {
+    let start = Instant::now();
+    let mut waiting = false;
+    let mut lock = OpenOptions::new().write(true).create(true).open(file)?;
+
+    loop {
+        match lock.try_lock_exclusive() {
+            Ok(()) => {
+                // The process ID is only informational, for anyone investigating a held lock
+                lock.set_len(0)?;
+                let _ = write!(lock, "{}", process::id());
+                debug!("Acquired lock at '{}'", file.display());
+                return Ok(Some(lock));
+            }
+            Err(ref error)
+                if error.raw_os_error() == fs2::lock_contended_error().raw_os_error() =>
+            {
+                if start.elapsed() >= timeout {
+                    return Ok(None);
+                }
+                if !waiting {
+                    debug!("Waiting for another Volta process to release the lock");
+                    waiting = true;
+                }
+                sleep(RETRY_INTERVAL);
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::write;
+
+    #[test]
+    fn test_lock_file_times_out_when_held() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("volta.lock");
+
+        let held = lock_file(&file, Duration::from_secs(0)).unwrap();
+        assert!(held.is_some());
+        assert!(file.exists());
+        assert!(lock_file(&file, Duration::from_millis(200))
+            .unwrap()
+            .is_none());
+
+        drop(held);
+        assert!(lock_file(&file, Duration::from_secs(0)).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_lock_file_ignores_stale_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("volta.lock");
+
+        // A lock file left behind by a process that was killed, which no longer holds it
+        write(&file, "4194304").unwrap();
+        assert!(lock_file(&file, Duration::from_secs(0)).unwrap().is_some());
+    }
+}
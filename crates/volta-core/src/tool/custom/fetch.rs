@@ -0,0 +1,199 @@
+//! Provides fetcher for custom tool distributions
+
+use std::fs::{rename, File};
+use std::path::Path;
+
+use super::super::{
+    check_unpack_space, copy_shared_archive, download_tool_error, fetched_while_waiting,
+};
+use super::CustomTool;
+use crate::error::ErrorDetails;
+use crate::fs::{
+    clear_quarantine, create_staging_dir, create_staging_file, set_executable_permissions,
+};
+use crate::layout::{long_path, volta_home};
+use crate::settings::check_online;
+use crate::style::{progress_bar, tool_version};
+use crate::sync::VoltaLock;
+use crate::tool::{self, NODE_DISTRO_ARCH, NODE_DISTRO_EXTENSION, NODE_DISTRO_OS};
+use crate::version::VersionSpec;
+use archive::{Archive, Tarball, Zip};
+use fs_utils::ensure_containing_dir_exists;
+use log::debug;
+use volta_fail::{Fallible, ResultExt};
+
+/// The formats that custom tool archives may be published in
+#[derive(Clone, Copy)]
+enum ArchiveFormat {
+    Tarball,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Determines the format of an archive from its URL, defaulting to a gzipped tarball
+    fn for_url(url: &str) -> Self {
+        if url.ends_with(".zip") {
+            ArchiveFormat::Zip
+        } else {
+            ArchiveFormat::Tarball
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Tarball => "tar.gz",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+}
+
+pub fn fetch(tool: &CustomTool) -> Fallible<()> {
+    let _lock = VoltaLock::acquire()?;
+    let image_dir = volta_home()?.custom_image_dir(&tool.name, &tool.version.to_string());
+    if fetched_while_waiting(&tool.name, &tool.version, &image_dir) {
+        return Ok(());
+    }
+    let remote_url = tool
+        .hooks
+        .distro
+        .resolve(&tool.version, &distro_filename(tool))?;
+    let format = ArchiveFormat::for_url(&remote_url);
+    let cache_file = volta_home()?.custom_inventory_dir(&tool.name).join(format!(
+        "{}-v{}.{}",
+        tool.name,
+        tool.version,
+        format.extension()
+    ));
+    // Custom tools aren't locked and have no published checksums, so the shared cache is only
+    // read once one of those applies to them
+    copy_shared_archive(&tool.name, &tool.version, &cache_file, || Ok(None))?;
+
+    let (archive, staging) = match load_cached_distro(&cache_file, format) {
+        Some(archive) => {
+            debug!(
+                "Loading {} from cached archive at '{}'",
+                tool,
+                cache_file.display(),
+            );
+            (archive, None)
+        }
+        None => {
+            let staging = create_staging_file()?;
+            let archive = fetch_remote_distro(tool, &remote_url, format, staging.path())?;
+            (archive, Some(staging))
+        }
+    };
+
+    unpack_archive(archive, tool)?;
+
+    if let Some(staging_file) = staging {
+        ensure_containing_dir_exists(&cache_file).with_context(|_| {
+            ErrorDetails::ContainingDirError {
+                path: cache_file.clone(),
+            }
+        })?;
+        staging_file
+            .persist(cache_file)
+            .with_context(|_| ErrorDetails::PersistInventoryError {
+                tool: tool.name.clone(),
+            })?;
+    }
+
+    Ok(())
+}
+
+/// The filename that is appended to the URL of a `prefix` distro hook
+fn distro_filename(tool: &CustomTool) -> String {
+    format!(
+        "{}-v{}-{}-{}.{}",
+        tool.name, tool.version, NODE_DISTRO_OS, NODE_DISTRO_ARCH, NODE_DISTRO_EXTENSION
+    )
+}
+
+/// Unpack the archive into the image directory so that it is ready for use
+fn unpack_archive(archive: Box<dyn Archive>, tool: &CustomTool) -> Fallible<()> {
+    let temp = create_staging_dir()?;
+    debug!("Unpacking {} into '{}'", tool.name, temp.path().display());
+
+    let size = archive
+        .uncompressed_size()
+        .unwrap_or_else(|| archive.compressed_size());
+    check_unpack_space(archive.as_ref(), temp.path())?;
+
+    let progress = progress_bar(
+        archive.origin(),
+        &tool_version(&tool.name, &tool.version),
+        size,
+    );
+    let version_string = tool.version.to_string();
+
+    archive
+        .unpack(&long_path(temp.path()), &mut |_, read| {
+            progress.inc(read as u64);
+        })
+        .with_context(|_| ErrorDetails::UnpackArchiveError {
+            tool: tool.name.clone(),
+            version: version_string.clone(),
+        })?;
+
+    let dest = volta_home()?.custom_image_dir(&tool.name, &version_string);
+    let setup_error = || ErrorDetails::SetupToolImageError {
+        tool: tool.name.clone(),
+        version: version_string.clone(),
+        dir: dest.clone(),
+    };
+
+    // Zip archives don't record file modes, so the executables may need to be made executable
+    let bin_dir = temp.path().join(&tool.hooks.bin_dir);
+    for bin in &tool.hooks.bins {
+        let bin_path = bin_dir.join(bin);
+        if bin_path.is_file() {
+            set_executable_permissions(&bin_path).with_context(|_| setup_error())?;
+        }
+    }
+    clear_quarantine(temp.path());
+
+    ensure_containing_dir_exists(&dest)
+        .with_context(|_| ErrorDetails::ContainingDirError { path: dest.clone() })?;
+    rename(long_path(temp.path()), long_path(&dest)).with_context(|_| setup_error())?;
+
+    progress.finish_and_clear();
+
+    // Note: We write this after the progress bar is finished to avoid display bugs with re-renders of the progress
+    debug!("Installing {} in '{}'", tool.name, dest.display());
+
+    Ok(())
+}
+
+/// Return the archive if it is valid. It may have been corrupted or interrupted in the middle of
+/// downloading.
+fn load_cached_distro(file: &Path, format: ArchiveFormat) -> Option<Box<dyn Archive>> {
+    if file.is_file() {
+        let file = File::open(file).ok()?;
+        match format {
+            ArchiveFormat::Tarball => Tarball::load(file).ok(),
+            ArchiveFormat::Zip => Zip::load(file).ok(),
+        }
+    } else {
+        None
+    }
+}
+
+/// Fetch the distro archive from the internet
+fn fetch_remote_distro(
+    tool: &CustomTool,
+    url: &str,
+    format: ArchiveFormat,
+    staging_path: &Path,
+) -> Fallible<Box<dyn Archive>> {
+    check_online(url)?;
+    debug!("Downloading {} from {}", tool, url);
+    let archive = match format {
+        ArchiveFormat::Tarball => Tarball::fetch(url, staging_path),
+        ArchiveFormat::Zip => Zip::fetch(url, staging_path),
+    };
+    archive.with_context(download_tool_error(
+        tool::Spec::Package(tool.name.clone(), VersionSpec::Exact(tool.version.clone())),
+        url,
+    ))
+}
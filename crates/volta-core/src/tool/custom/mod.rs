@@ -0,0 +1,157 @@
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+
+use super::{
+    debug_already_fetched, info_fetched, info_installed, info_pinned, info_project_version, Tool,
+};
+use crate::error::ErrorDetails;
+use crate::hook::CustomToolHooks;
+use crate::layout::{layered_path, volta_home};
+use crate::session::Session;
+use crate::shim;
+use crate::style::tool_version;
+use crate::tool::BinConfig;
+use crate::version::VersionSpec;
+use semver::Version;
+use volta_fail::{throw, Fallible};
+
+mod fetch;
+mod resolve;
+mod serial;
+
+/// Returns true if a custom tool with the given name is defined in the hooks
+pub fn is_defined(name: &str, session: &Session) -> Fallible<bool> {
+    Ok(session.hooks()?.custom_tool(name).is_some())
+}
+
+/// Resolves a version of the named custom tool, which must be defined in the hooks
+pub fn resolve(name: String, matching: VersionSpec, session: &Session) -> Fallible<CustomTool> {
+    let hooks = match session.hooks()?.custom_tool(&name) {
+        Some(hooks) => hooks.clone(),
+        None => {
+            return Err(ErrorDetails::PackageNotFound { package: name }.into());
+        }
+    };
+
+    let version = resolve::resolve(&name, matching, &hooks)?;
+    Ok(CustomTool::new(name, version, hooks))
+}
+
+/// The Tool implementation for tools defined in the `tools` section of the hooks, such as
+/// `deno` or `bun`
+///
+/// Custom tools are self-contained: their archives are unpacked into an image directory of
+/// their own, and their executables run without Node on the `PATH`.
+#[derive(Debug)]
+pub struct CustomTool {
+    pub(super) name: String,
+    pub(super) version: Version,
+    hooks: CustomToolHooks,
+}
+
+impl CustomTool {
+    pub fn new(name: String, version: Version, hooks: CustomToolHooks) -> Self {
+        CustomTool {
+            name,
+            version,
+            hooks,
+        }
+    }
+
+    /// The directory containing the tool's executables
+    pub fn bin_dir(&self) -> Fallible<PathBuf> {
+        let version = self.version.to_string();
+        let image_dir = layered_path(|home| home.custom_image_dir(&self.name, &version))?;
+        Ok(image_dir.join(&self.hooks.bin_dir))
+    }
+
+    pub(crate) fn fetch_internal(&self) -> Fallible<()> {
+        let version = self.version.to_string();
+        if layered_path(|home| home.custom_image_dir(&self.name, &version))?.is_dir() {
+            debug_already_fetched(self);
+            return Ok(());
+        }
+
+        fetch::fetch(self)
+    }
+
+    /// Ensures that none of the tool's executables are already provided by an installed
+    /// package, since creating the shims would take them over
+    fn check_bins(&self) -> Fallible<()> {
+        let home = volta_home()?;
+        for bin in &self.hooks.bins {
+            let bin_config = home.default_tool_bin_config(bin);
+            if bin_config.exists() {
+                let owner = match BinConfig::from_file(bin_config) {
+                    Ok(config) => format!("the package '{}'", config.package),
+                    Err(_) => "an installed package".to_string(),
+                };
+                throw!(ErrorDetails::CustomToolBinConflict {
+                    tool: self.name.clone(),
+                    bin: bin.clone(),
+                    owner,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn create_shims(&self) -> Fallible<()> {
+        for bin in &self.hooks.bins {
+            shim::create(bin)?;
+        }
+        Ok(())
+    }
+}
+
+impl Tool for CustomTool {
+    fn fetch(self, _session: &mut Session) -> Fallible<()> {
+        self.fetch_internal()?;
+
+        info_fetched(self);
+        Ok(())
+    }
+    fn install(self, session: &mut Session) -> Fallible<()> {
+        self.check_bins()?;
+        self.fetch_internal()?;
+
+        session
+            .toolchain_mut()?
+            .set_active_custom_tool(&self.name, &self.version)?;
+        self.create_shims()?;
+
+        let project_version = session
+            .project()?
+            .and_then(|project| project.manifest().custom_tool(&self.name).cloned());
+        let name = self.name.clone();
+
+        info_installed(self);
+
+        if let Some(version) = project_version {
+            info_project_version(tool_version(&name, &version));
+        }
+        Ok(())
+    }
+    fn pin(self, session: &mut Session) -> Fallible<()> {
+        if session.project()?.is_some() {
+            self.check_bins()?;
+            self.fetch_internal()?;
+            self.create_shims()?;
+
+            // Note: We know this will succeed, since we checked above
+            let project = session.project_mut()?.unwrap();
+            project.pin_custom_tool(&self.name, &self.version)?;
+
+            info_pinned(self);
+            Ok(())
+        } else {
+            Err(ErrorDetails::NotInPackage.into())
+        }
+    }
+}
+
+impl Display for CustomTool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&tool_version(&self.name, &self.version))
+    }
+}
@@ -0,0 +1,158 @@
+//! Provides resolution of custom tool requirements into specific versions
+
+use std::collections::{BTreeSet, HashMap};
+
+use super::super::registry_fetch_error;
+use super::serial;
+use crate::error::ErrorDetails;
+use crate::hook::tool::IndexFormat;
+use crate::hook::CustomToolHooks;
+use crate::settings::check_online;
+use crate::style::progress_spinner;
+use crate::version::{parse_version, VersionSpec, VersionTag};
+use archive::http_client;
+use log::debug;
+use semver::{Version, VersionReq};
+use volta_fail::{Fallible, ResultExt};
+
+pub fn resolve(name: &str, matching: VersionSpec, hooks: &CustomToolHooks) -> Fallible<Version> {
+    match matching {
+        VersionSpec::Exact(version) => Ok(version),
+        VersionSpec::Semver(requirement) => resolve_semver(name, requirement, hooks),
+        VersionSpec::None | VersionSpec::Tag(VersionTag::Latest) => resolve_latest(name, hooks),
+        VersionSpec::Tag(VersionTag::Custom(tag)) => resolve_tag(name, &tag, hooks),
+        VersionSpec::Tag(tag) => Err(ErrorDetails::CustomToolVersionNotFound {
+            tool: name.to_string(),
+            matching: tag.to_string(),
+        }
+        .into()),
+    }
+}
+
+fn resolve_latest(name: &str, hooks: &CustomToolHooks) -> Fallible<Version> {
+    if let Some(hook) = &hooks.latest {
+        debug!("Using {}.latest hook to determine latest-version URL", name);
+        let url = hook.resolve("latest-version")?;
+        check_online(&url)?;
+        let response_text = http_client()
+            .get(&url)
+            .send()
+            .and_then(|mut resp| resp.text())
+            .with_context(registry_fetch_error(name, &url))?;
+
+        debug!(
+            "Found {} latest version ({}) from {}",
+            name, response_text, url
+        );
+        return parse_version(response_text.trim());
+    }
+
+    let (url, index) = fetch_index(name, hooks, "latest")?;
+    // An npm-style index names its latest version, while a list of releases can only be
+    // searched for the newest version that isn't a prerelease
+    let latest = match index.tags.get("latest") {
+        Some(latest) => Some(parse_version(latest)?),
+        None => index.entries.into_iter().rev().find(|v| !v.is_prerelease()),
+    };
+
+    match latest {
+        Some(version) => {
+            debug!("Found {} latest version ({}) from {}", name, version, url);
+            Ok(version)
+        }
+        None => Err(ErrorDetails::CustomToolVersionNotFound {
+            tool: name.to_string(),
+            matching: "latest".into(),
+        }
+        .into()),
+    }
+}
+
+fn resolve_tag(name: &str, tag: &str, hooks: &CustomToolHooks) -> Fallible<Version> {
+    let (url, index) = fetch_index(name, hooks, tag)?;
+
+    match index.tags.get(tag) {
+        Some(version) => {
+            debug!(
+                "Found {}@{} matching tag '{}' from {}",
+                name, version, tag, url
+            );
+            parse_version(version)
+        }
+        None => Err(ErrorDetails::CustomToolVersionNotFound {
+            tool: name.to_string(),
+            matching: tag.into(),
+        }
+        .into()),
+    }
+}
+
+fn resolve_semver(name: &str, matching: VersionReq, hooks: &CustomToolHooks) -> Fallible<Version> {
+    let (url, index) = fetch_index(name, hooks, &matching.to_string())?;
+
+    match index
+        .entries
+        .into_iter()
+        .rev()
+        .find(|v| matching.matches(v))
+    {
+        Some(version) => {
+            debug!(
+                "Found {}@{} matching requirement '{}' from {}",
+                name, version, matching, url
+            );
+            Ok(version)
+        }
+        None => Err(ErrorDetails::CustomToolVersionNotFound {
+            tool: name.to_string(),
+            matching: matching.to_string(),
+        }
+        .into()),
+    }
+}
+
+/// Fetches the index given by the tool's `index` hook, in the format declared with the hook
+fn fetch_index(
+    name: &str,
+    hooks: &CustomToolHooks,
+    matching: &str,
+) -> Fallible<(String, CustomToolIndex)> {
+    let url = match &hooks.index {
+        Some(hook) => {
+            debug!("Using {}.index hook to determine the index URL", name);
+            hook.resolve("index.json")?
+        }
+        None => {
+            return Err(ErrorDetails::CustomToolNoIndex {
+                tool: name.to_string(),
+                matching: matching.to_string(),
+            }
+            .into());
+        }
+    };
+
+    check_online(&url)?;
+    let spinner = progress_spinner(&format!("Fetching index: {}", url));
+    let mut response = http_client()
+        .get(&url)
+        .send()
+        .with_context(registry_fetch_error(name, &url))?;
+    let index = match hooks.index_format {
+        IndexFormat::Github => response
+            .json::<serial::RawGithubIndex>()
+            .map(CustomToolIndex::from),
+        IndexFormat::Npm => response
+            .json::<serial::RawNpmIndex>()
+            .map(CustomToolIndex::from),
+    }
+    .with_context(registry_fetch_error(name, &url))?;
+    spinner.finish_and_clear();
+
+    Ok((url, index))
+}
+
+/// The index of available versions of a custom tool.
+pub struct CustomToolIndex {
+    pub(super) entries: BTreeSet<Version>,
+    pub(super) tags: HashMap<String, String>,
+}
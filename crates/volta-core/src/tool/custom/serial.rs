@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use super::resolve::CustomToolIndex;
+use semver::Version;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+/// A list of releases, as returned by the GitHub releases API. Only the tag names are needed.
+#[derive(Deserialize)]
+pub struct RawGithubIndex(Vec<RawGithubRelease>);
+
+#[derive(Deserialize)]
+pub struct RawGithubRelease {
+    /// Releases are usually tagged as "v$version", but some projects leave off the "v"
+    pub tag_name: String,
+}
+
+impl From<RawGithubIndex> for CustomToolIndex {
+    fn from(raw: RawGithubIndex) -> CustomToolIndex {
+        let entries = raw
+            .0
+            .into_iter()
+            .filter_map(|release| Version::parse(release.tag_name.trim_start_matches('v')).ok())
+            .collect();
+        CustomToolIndex {
+            entries,
+            tags: HashMap::new(),
+        }
+    }
+}
+
+/// A package document, as returned by the npm registry. Only the keys of the `versions` map
+/// and the `dist-tags` are needed.
+#[derive(Deserialize)]
+pub struct RawNpmIndex {
+    pub versions: HashMap<String, IgnoredAny>,
+    #[serde(default, rename = "dist-tags")]
+    pub dist_tags: HashMap<String, String>,
+}
+
+impl From<RawNpmIndex> for CustomToolIndex {
+    fn from(raw: RawNpmIndex) -> CustomToolIndex {
+        let entries = raw
+            .versions
+            .keys()
+            .filter_map(|version| Version::parse(version).ok())
+            .collect();
+        CustomToolIndex {
+            entries,
+            tags: raw.dist_tags,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_github_index_tag_names() {
+        let raw: RawGithubIndex = serde_json::from_str(
+            r#"[
+                { "tag_name": "v1.40.0" },
+                { "tag_name": "1.39.4" },
+                { "tag_name": "nightly" }
+            ]"#,
+        )
+        .unwrap();
+        let index = CustomToolIndex::from(raw);
+
+        assert_eq!(
+            index.entries.into_iter().collect::<Vec<_>>(),
+            vec![
+                Version::parse("1.39.4").unwrap(),
+                Version::parse("1.40.0").unwrap()
+            ]
+        );
+    }
+}
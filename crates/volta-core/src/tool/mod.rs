@@ -1,25 +1,52 @@
 use std::fmt::{self, Display};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 
+use crate::checksum::Checksum;
 use crate::error::ErrorDetails;
-use crate::session::Session;
-use crate::style::{note_prefix, success_prefix, tool_version};
-use crate::version::{parse_version, VersionSpec};
-use log::{debug, info};
+use crate::fs::{check_available_space, create_staging_file};
+use crate::layout::volta_home;
+use crate::lockfile::{can_verify, locked_checksum, verify_archive, LockedChecksum};
+use crate::session::{ActivityKind, Session};
+use crate::settings::{check_online, settings, ArchiveCompression};
+use crate::style::{note_prefix, progress_bar, success_prefix, tool_version};
+use crate::version::{Bump, VersionSpec};
+use archive::{http_client, Archive, HttpError, Origin};
+use fs_utils::ensure_containing_dir_exists;
+use log::{debug, info, warn};
 use semver::Version;
-use volta_fail::Fallible;
+use volta_fail::{ExitCode, Fallible, ResultExt};
 
+mod custom;
 mod node;
 mod npm;
 mod package;
+mod pnpm;
+mod prefetch;
 mod serial;
 mod yarn;
 
+pub use custom::CustomTool;
+pub(crate) use node::cached_security_releases as cached_node_security_releases;
+pub(crate) use node::end_of_life_message as node_end_of_life_message;
+pub(crate) use node::index_versions as node_index_versions;
+pub(crate) use node::public_node_server_root;
+pub(crate) use node::release_schedule as node_release_schedule;
 pub use node::{
-    load_default_npm_version, Node, NodeVersion, NODE_DISTRO_ARCH, NODE_DISTRO_EXTENSION,
-    NODE_DISTRO_OS,
+    cached_release_schedule as cached_node_schedule, cached_versions as cached_node_versions,
+    load_default_npm_version, node_index_url, release_line as node_release_line,
+    version_choices as node_version_choices, Node, NodeChoice, NodeVersion,
+    Schedule as NodeSchedule, NODE_DISTRO_ARCH, NODE_DISTRO_EXTENSION, NODE_DISTRO_OS,
 };
 pub use npm::Npm;
-pub use package::{bin_full_path, BinConfig, BinLoader, Package, PackageConfig, PackageDetails};
+pub use package::{
+    bin_full_path, BinConfig, BinLoader, LinkedPackage, NpmFlags, Package, PackageConfig,
+    PackageDetails, ScriptKind,
+};
+pub(crate) use package::{npm_cache_dir, pinned_platform};
+pub use pnpm::Pnpm;
+pub use prefetch::Prefetch;
 pub use yarn::Yarn;
 
 #[inline]
@@ -27,6 +54,19 @@ fn debug_already_fetched<T: Display + Sized>(tool: T) {
     debug!("{} has already been fetched, skipping download", tool);
 }
 
+/// Whether another process fetched a tool into `image_dir` while this one was waiting for the
+/// Volta lock, in which case there is nothing left to fetch
+fn fetched_while_waiting(tool: &str, version: &Version, image_dir: &Path) -> bool {
+    let fetched = image_dir.is_dir();
+    if fetched {
+        debug!(
+            "{} was fetched by another process while waiting for the lock",
+            tool_version(tool, version)
+        );
+    }
+    fetched
+}
+
 #[inline]
 fn info_installed<T: Display + Sized>(tool: T) {
     info!("{} installed and set {} as default", success_prefix(), tool);
@@ -68,6 +108,7 @@ pub enum Spec {
     Node(VersionSpec),
     Npm(VersionSpec),
     Yarn(VersionSpec),
+    Pnpm(VersionSpec),
     Package(String, VersionSpec),
 }
 
@@ -77,7 +118,10 @@ pub enum Resolved {
     Node(Node),
     Npm(Npm),
     Yarn(Yarn),
+    Pnpm(Pnpm),
     Package(Package),
+    LinkedPackage(LinkedPackage),
+    Custom(CustomTool),
 }
 
 impl Spec {
@@ -90,13 +134,73 @@ impl Spec {
             Spec::Yarn(version) => yarn::resolve(version, session)
                 .map(Yarn::new)
                 .map(Resolved::Yarn),
+            Spec::Pnpm(version) => pnpm::resolve(version).map(Pnpm::new).map(Resolved::Pnpm),
+            // Tools defined in the hooks take precedence over npm packages of the same name
+            Spec::Package(name, version) if custom::is_defined(&name, session)? => {
+                custom::resolve(name, version, session).map(Resolved::Custom)
+            }
+            Spec::Package(name, _) if package::is_remote_spec(&name) => {
+                package::resolve_remote(&name, session)
+                    .map(|(name, details)| Package::new(name, details))
+                    .map(Resolved::Package)
+            }
+            Spec::Package(name, _) if package::is_local_spec(&name) => {
+                package::resolve_local(&name).map(Resolved::LinkedPackage)
+            }
             Spec::Package(name, version) => package::resolve(&name, version, session)
                 .map(|details| Package::new(name, details))
                 .map(Resolved::Package),
-            // ISSUE (#292): To preserve error message context, we always resolve Npm to Version 0.0.0
-            // This will allow us to show the correct error message based on the user's command
-            // e.g. `volta install npm` vs `volta pin npm`
-            Spec::Npm(_) => parse_version("0.0.0").map(Npm::new).map(Resolved::Npm),
+            Spec::Npm(version) => npm::resolve(version, session)
+                .map(Npm::new)
+                .map(Resolved::Npm),
+        }
+    }
+
+    /// Resolve the newest release in the line of the version this spec matches
+    ///
+    /// A spec without a version uses the line of `current` instead, if there is one, so that
+    /// pinning `node --latest-patch` moves the pinned Node to the newest patch of its line.
+    pub fn resolve_bumped(
+        self,
+        bump: Bump,
+        current: Option<&Version>,
+        session: &mut Session,
+    ) -> Fallible<Resolved> {
+        let template = self.with_version(VersionSpec::None);
+        let base = match (self.version(), current) {
+            (VersionSpec::None, Some(current)) => current.clone(),
+            _ => self.resolve(session)?.into(),
+        };
+
+        let line = bump.line(&base);
+        debug!(
+            "Resolving the newest release of {} matching {}",
+            template, line
+        );
+        template
+            .with_version(VersionSpec::Semver(line))
+            .resolve(session)
+    }
+
+    /// Returns the version that this spec requests
+    pub fn version(&self) -> &VersionSpec {
+        match self {
+            Spec::Node(version)
+            | Spec::Npm(version)
+            | Spec::Yarn(version)
+            | Spec::Pnpm(version)
+            | Spec::Package(_, version) => version,
+        }
+    }
+
+    /// Returns a spec for the same tool, requesting a different version
+    fn with_version(&self, version: VersionSpec) -> Spec {
+        match self {
+            Spec::Node(_) => Spec::Node(version),
+            Spec::Npm(_) => Spec::Npm(version),
+            Spec::Yarn(_) => Spec::Yarn(version),
+            Spec::Pnpm(_) => Spec::Pnpm(version),
+            Spec::Package(name, _) => Spec::Package(name.clone(), version),
         }
     }
 
@@ -118,12 +222,48 @@ impl Spec {
                 feature: "Uninstalling yarn".into(),
             }
             .into()),
+            Spec::Pnpm(_) => Err(ErrorDetails::Unimplemented {
+                feature: "Uninstalling pnpm".into(),
+            }
+            .into()),
             Spec::Package(name, _) => {
                 package::uninstall(&name)?;
                 Ok(())
             }
         }
     }
+
+    /// Upgrade an installed tool to the latest available version
+    ///
+    /// Like `uninstall`, this is implemented on Spec, since the version to upgrade to is always
+    /// resolved as part of the upgrade itself.
+    pub fn upgrade(self, session: &mut Session) -> Fallible<()> {
+        match self {
+            Spec::Node(_) => Err(ErrorDetails::Unimplemented {
+                feature: "Upgrading node".into(),
+            }
+            .into()),
+            Spec::Npm(_) => Err(ErrorDetails::Unimplemented {
+                feature: "Upgrading npm".into(),
+            }
+            .into()),
+            Spec::Yarn(_) => Err(ErrorDetails::Unimplemented {
+                feature: "Upgrading yarn".into(),
+            }
+            .into()),
+            Spec::Pnpm(_) => Err(ErrorDetails::Unimplemented {
+                feature: "Upgrading pnpm".into(),
+            }
+            .into()),
+            Spec::Package(name, _) if custom::is_defined(&name, session)? => {
+                Err(ErrorDetails::Unimplemented {
+                    feature: format!("Upgrading {}", name),
+                }
+                .into())
+            }
+            Spec::Package(name, _) => package::upgrade(&name, session),
+        }
+    }
 }
 
 impl Resolved {
@@ -133,18 +273,34 @@ impl Resolved {
             Resolved::Node(node) => node.fetch(session),
             Resolved::Npm(npm) => npm.fetch(session),
             Resolved::Yarn(yarn) => yarn.fetch(session),
+            Resolved::Pnpm(pnpm) => pnpm.fetch(session),
             Resolved::Package(package) => package.fetch(session),
+            Resolved::LinkedPackage(package) => package.fetch(session),
+            Resolved::Custom(custom) => custom.fetch(session),
         }
     }
 
     /// Install a tool, making it the default so it is available everywhere on the user's machine
     pub fn install(self, session: &mut Session) -> Fallible<()> {
-        match self {
+        let tool = self.to_string();
+        session.add_event_install_start(ActivityKind::Install, tool.clone());
+
+        let result = match self {
             Resolved::Node(node) => node.install(session),
             Resolved::Npm(npm) => npm.install(session),
             Resolved::Yarn(yarn) => yarn.install(session),
+            Resolved::Pnpm(pnpm) => pnpm.install(session),
             Resolved::Package(package) => package.install(session),
-        }
+            Resolved::LinkedPackage(package) => package.install(session),
+            Resolved::Custom(custom) => custom.install(session),
+        };
+
+        let exit_code = match result {
+            Ok(()) => ExitCode::Success,
+            Err(ref error) => error.exit_code(),
+        };
+        session.add_event_install_end(ActivityKind::Install, tool, exit_code);
+        result
     }
 
     /// Pin a tool in the local project so that it is usable within the project
@@ -153,7 +309,10 @@ impl Resolved {
             Resolved::Node(node) => node.pin(session),
             Resolved::Npm(npm) => npm.pin(session),
             Resolved::Yarn(yarn) => yarn.pin(session),
+            Resolved::Pnpm(pnpm) => pnpm.pin(session),
             Resolved::Package(package) => package.pin(session),
+            Resolved::LinkedPackage(package) => package.pin(session),
+            Resolved::Custom(custom) => custom.pin(session),
         }
     }
 }
@@ -164,19 +323,37 @@ impl Display for Spec {
             Spec::Node(ref version) => tool_version("node", version),
             Spec::Npm(ref version) => tool_version("npm", version),
             Spec::Yarn(ref version) => tool_version("yarn", version),
+            Spec::Pnpm(ref version) => tool_version("pnpm", version),
             Spec::Package(ref name, ref version) => tool_version(name, version),
         };
         f.write_str(&s)
     }
 }
 
+impl Display for Resolved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resolved::Node(ref node) => Display::fmt(node, f),
+            Resolved::Npm(ref npm) => Display::fmt(npm, f),
+            Resolved::Yarn(ref yarn) => Display::fmt(yarn, f),
+            Resolved::Pnpm(ref pnpm) => Display::fmt(pnpm, f),
+            Resolved::Package(ref package) => Display::fmt(package, f),
+            Resolved::LinkedPackage(ref package) => Display::fmt(package, f),
+            Resolved::Custom(ref custom) => Display::fmt(custom, f),
+        }
+    }
+}
+
 impl From<Resolved> for Version {
     fn from(tool: Resolved) -> Self {
         match tool {
             Resolved::Node(Node { version })
             | Resolved::Npm(Npm { version })
-            | Resolved::Yarn(Yarn { version }) => version,
+            | Resolved::Yarn(Yarn { version })
+            | Resolved::Pnpm(Pnpm { version })
+            | Resolved::Custom(CustomTool { version, .. }) => version,
             Resolved::Package(Package { details, .. }) => details.version,
+            Resolved::LinkedPackage(LinkedPackage { version, .. }) => version,
         }
     }
 }
@@ -189,7 +366,256 @@ fn download_tool_error(
     |_| ErrorDetails::DownloadToolNetworkError { tool, from_url }
 }
 
-fn registry_fetch_error(
+/// Verifies the archive of a tool in the inventory against the checksum recorded in the
+/// project's `volta.lock`, if the project locks this version
+///
+/// An archive that isn't in the inventory yet is downloaded in full and verified before it is
+/// added, instead of being unpacked as it is streamed, and is kept in the inventory so that it
+/// can be verified again later.
+fn verify_locked_archive<F>(
+    tool: &str,
+    version: &Version,
+    cache_file: &Path,
+    remote_url: F,
+) -> Fallible<()>
+where
+    F: FnOnce() -> Fallible<String>,
+{
+    if let Some(expected) = locked_checksum(tool, version, cache_file)? {
+        if can_verify(cache_file, &expected) {
+            let origin = cache_file.display().to_string();
+            verify_archive(tool, version, cache_file, &expected, &origin)?;
+        } else {
+            download_archive(tool, version, &remote_url()?, cache_file, Some(&expected))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Downloads the archive of a tool into the inventory without unpacking it, verifying it
+/// against `checksum` first if given
+fn download_archive(
+    tool: &str,
+    version: &Version,
+    url: &str,
+    cache_file: &Path,
+    checksum: Option<&LockedChecksum>,
+) -> Fallible<()> {
+    check_online(url)?;
+    debug!("Downloading {} from {}", tool_version(tool, version), url);
+
+    let spec = || Spec::from_str_and_version(tool, VersionSpec::Exact(version.clone()));
+    let response = request_file(url).with_context(download_tool_error(spec(), url))?;
+    // The whole archive is written before anything is done with it, so the space it needs is
+    // checked first, when the server tells its size
+    if let Some(length) = response.content_length() {
+        check_available_space(&volta_home()?.tmp_dir(), length)?;
+    }
+
+    let staging = create_staging_file()?;
+    write_response(response, tool, version, staging.path())
+        .with_context(download_tool_error(spec(), url))?;
+    if let Some(expected) = checksum {
+        verify_archive(tool, version, staging.path(), expected, url)?;
+    }
+
+    ensure_containing_dir_exists(&cache_file).with_context(|_| {
+        ErrorDetails::ContainingDirError {
+            path: cache_file.to_path_buf(),
+        }
+    })?;
+    staging
+        .persist(cache_file)
+        .with_context(|_| ErrorDetails::PersistInventoryError {
+            tool: tool.to_string(),
+        })?;
+    Ok(())
+}
+
+/// Copies the archive of a tool from the shared cache into the inventory, if the shared cache
+/// has it and the inventory doesn't yet
+///
+/// The shared cache (the `cacheSharedDir` setting) has the layout of the inventory directory and
+/// is only ever read from, so that it can be a network share or a container layer used by many
+/// users. Since anyone who can write to it could replace an archive, a copied archive is only
+/// kept once it matches the checksum in the project's lockfile, or else the checksum returned by
+/// `upstream`, which is the one the tool's own server publishes. Tools whose servers don't publish
+/// checksums are only copied when the lockfile records one. Each user unpacks the copied archive
+/// into their own images, as after a download. An archive that can't be read from the shared
+/// cache or verified is downloaded instead.
+fn copy_shared_archive<F>(
+    tool: &str,
+    version: &Version,
+    cache_file: &Path,
+    upstream: F,
+) -> Fallible<()>
+where
+    F: FnOnce() -> Fallible<Option<Checksum>>,
+{
+    if cache_file.is_file() || zstd_archive_file(cache_file).is_file() {
+        return Ok(());
+    }
+    let shared_dir = match &settings()?.cache_shared_dir {
+        Some(shared_dir) => shared_dir,
+        None => return Ok(()),
+    };
+    let shared_file = match cache_file.strip_prefix(volta_home()?.inventory_dir()) {
+        Ok(relative) => shared_dir.join(relative),
+        Err(_) => return Ok(()),
+    };
+    if !shared_file.is_file() {
+        debug!(
+            "No archive in the shared cache at '{}'",
+            shared_file.display()
+        );
+        return Ok(());
+    }
+
+    debug!(
+        "Copying archive from the shared cache at '{}'",
+        shared_file.display()
+    );
+    let staging = create_staging_file()?;
+    if let Err(error) = fs::copy(&shared_file, staging.path()) {
+        debug!("Could not copy from the shared cache: {}", error);
+        return Ok(());
+    }
+
+    let origin = shared_file.display().to_string();
+    let verified = match locked_checksum(tool, version, cache_file)? {
+        Some(expected) => verify_archive(tool, version, staging.path(), &expected, &origin).is_ok(),
+        None => match upstream() {
+            Ok(Some(checksum)) => checksum.matches(staging.path())?,
+            Ok(None) => {
+                debug!("No published checksum to verify '{}' against", origin);
+                return Ok(());
+            }
+            Err(error) => {
+                debug!(
+                    "Could not fetch the checksum to verify '{}': {}",
+                    origin, error
+                );
+                return Ok(());
+            }
+        },
+    };
+    if !verified {
+        warn!(
+            "The archive of {} in the shared cache at '{}' does not match its checksum, downloading it instead",
+            tool_version(tool, version),
+            origin
+        );
+        return Ok(());
+    }
+
+    ensure_containing_dir_exists(&cache_file).with_context(|_| {
+        ErrorDetails::ContainingDirError {
+            path: cache_file.to_path_buf(),
+        }
+    })?;
+    staging
+        .persist(cache_file)
+        .with_context(|_| ErrorDetails::PersistInventoryError {
+            tool: tool.to_string(),
+        })?;
+    Ok(())
+}
+
+/// Returns the path of an archive in the inventory once it has been recompressed with zstd,
+/// which replaces the `.gz` extension of the tarball with `.zst`
+pub(crate) fn zstd_archive_file(cache_file: &Path) -> PathBuf {
+    cache_file.with_extension("zst")
+}
+
+/// Recompresses a Node tarball in the inventory with zstd, if the `archiveCompression` setting asks
+/// for it, replacing the archive as it was downloaded
+///
+/// This happens once the tarball has been verified and unpacked. A lockfile records the checksum
+/// of the uncompressed contents of the tarball, so that it can still be verified afterwards.
+/// Failures are only logged, since the downloaded archive is kept in that case.
+fn recompress_archive(cache_file: &Path) -> Fallible<()> {
+    let is_tarball = cache_file.to_string_lossy().ends_with(".tar.gz");
+    if !is_tarball
+        || !cache_file.is_file()
+        || settings()?.archive_compression != ArchiveCompression::Zstd
+    {
+        return Ok(());
+    }
+
+    debug!("Recompressing '{}' with zstd", cache_file.display());
+    if let Err(error) = recompress_with_zstd(cache_file) {
+        debug!("Could not recompress the archive: {}", error);
+    }
+    Ok(())
+}
+
+fn recompress_with_zstd(cache_file: &Path) -> Result<(), failure::Error> {
+    let staging = create_staging_file()?;
+    archive::recompress_zstd(cache_file, staging.path())?;
+
+    staging.persist(zstd_archive_file(cache_file))?;
+    fs::remove_file(cache_file)?;
+    Ok(())
+}
+
+/// Checks that there is enough space to unpack an archive into `dir`, before any of it is read
+///
+/// An archive that is still being downloaded is unpacked as it downloads, and is written to the
+/// staging directory as well, so the space for both is checked then. The sizes come from the
+/// `Content-Length` of the download, and from the tarball itself when it lists its uncompressed
+/// size.
+pub(crate) fn check_unpack_space(archive: &dyn Archive, dir: &Path) -> Fallible<()> {
+    let unpacked = archive
+        .uncompressed_size()
+        .unwrap_or_else(|| archive.compressed_size());
+    let downloaded = match archive.origin() {
+        Origin::Remote => archive.compressed_size(),
+        _ => 0,
+    };
+    check_available_space(dir, unpacked + downloaded)
+}
+
+/// Requests a file, failing if the server doesn't return it
+fn request_file(url: &str) -> Result<reqwest::Response, failure::Error> {
+    let response = http_client().get(url).send()?;
+    if !response.status().is_success() {
+        return Err(HttpError {
+            code: response.status(),
+        }
+        .into());
+    }
+    Ok(response)
+}
+
+/// Writes a downloaded file in full, showing the progress of the download
+fn write_response(
+    mut response: reqwest::Response,
+    tool: &str,
+    version: &Version,
+    staging_path: &Path,
+) -> Result<(), failure::Error> {
+    let progress = progress_bar(
+        Origin::Remote,
+        &tool_version(tool, version),
+        response.content_length().unwrap_or(0),
+    );
+    let mut file = File::create(staging_path)?;
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let read = response.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])?;
+        progress.inc(read as u64);
+    }
+    progress.finish_and_clear();
+
+    Ok(())
+}
+
+pub(crate) fn registry_fetch_error(
     tool: impl AsRef<str>,
     from_url: impl AsRef<str>,
 ) -> impl FnOnce(&reqwest::Error) -> ErrorDetails {
@@ -0,0 +1,123 @@
+//! Provides building Node from its source tarball, for systems that Node doesn't publish a
+//! prebuilt distro for.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::fetch::{fetch_remote_distro, set_up_image, source_url};
+use crate::error::ErrorDetails;
+use crate::fs::{check_available_space, create_staging_dir, create_staging_file, write_file_error};
+use crate::layout::{long_path, volta_home};
+use crate::style::{progress_bar, progress_spinner, tool_version};
+use crate::tool::{Node, NodeVersion};
+use fs_utils::ensure_containing_dir_exists;
+use log::debug;
+use semver::Version;
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// Builds Node from source with `configure` and `make`, installing it into the directory
+/// returned by `image_dir`
+///
+/// The output of the build is written to a log file in the Volta log directory, since it is far
+/// too long to show and is only needed when the build fails.
+pub(super) fn build<F>(version: &Version, image_dir: F) -> Fallible<NodeVersion>
+where
+    F: FnOnce(&Version) -> Fallible<PathBuf>,
+{
+    let version_string = version.to_string();
+    let url = source_url(version)?;
+    let staging = create_staging_file()?;
+    let archive = fetch_remote_distro(version, &url, staging.path(), false)?;
+
+    let temp = create_staging_dir()?;
+    debug!(
+        "Unpacking the source of node into '{}'",
+        temp.path().display()
+    );
+    let size = archive
+        .uncompressed_size()
+        .unwrap_or_else(|| archive.compressed_size());
+    check_available_space(temp.path(), size)?;
+
+    let progress = progress_bar(archive.origin(), &tool_version("node", version), size);
+    archive
+        .unpack(&long_path(temp.path()), &mut |_, read| {
+            progress.inc(read as u64);
+        })
+        .with_context(|_| ErrorDetails::UnpackArchiveError {
+            tool: "Node".into(),
+            version: version_string.clone(),
+        })?;
+    progress.finish_and_clear();
+
+    let log_file = volta_home()?
+        .log_dir()
+        .join(format!("node-v{}-build.log", version_string));
+    ensure_containing_dir_exists(&log_file).with_context(|_| ErrorDetails::ContainingDirError {
+        path: log_file.clone(),
+    })?;
+    let log = File::create(&log_file).with_context(write_file_error(&log_file))?;
+
+    // The prefix has the same name as the directory in a prebuilt distro, so that the built image
+    // has the same layout
+    let source_dir = temp.path().join(format!("node-v{}", version_string));
+    let prefix = temp.path().join(Node::archive_basename(&version_string));
+    let prefix_arg = format!("--prefix={}", prefix.display());
+
+    let steps: [(&str, &[&str]); 3] = [
+        ("./configure", &[prefix_arg.as_str()]),
+        ("make", &[]),
+        ("make", &["install"]),
+    ];
+    for (program, args) in steps.iter() {
+        run_step(version, &source_dir, program, args, &log, &log_file)?;
+    }
+
+    set_up_image(&prefix, version, image_dir)
+}
+
+/// Runs one step of the build in the source directory, writing its output to the build log
+fn run_step(
+    version: &Version,
+    source_dir: &Path,
+    program: &str,
+    args: &[&str],
+    log: &File,
+    log_file: &Path,
+) -> Fallible<()> {
+    let step = match args.first() {
+        Some(arg) if !arg.starts_with("--") => format!("{} {}", program, arg),
+        _ => program.to_string(),
+    };
+    let build_error = || ErrorDetails::NodeBuildError {
+        version: version.to_string(),
+        step: step.clone(),
+        log: log_file.to_owned(),
+    };
+
+    debug!("Running `{}` to build node@{}", step, version);
+    let spinner = progress_spinner(&format!(
+        "Building {}: {}",
+        tool_version("node", version),
+        step
+    ));
+
+    let stdout = log.try_clone().with_context(write_file_error(&log_file))?;
+    let stderr = log.try_clone().with_context(write_file_error(&log_file))?;
+    let status = Command::new(program)
+        .args(args)
+        .current_dir(source_dir)
+        .stdin(Stdio::null())
+        .stdout(stdout)
+        .stderr(stderr)
+        .status()
+        .with_context(|_| build_error())?;
+    spinner.finish_and_clear();
+
+    if !status.success() {
+        throw!(build_error());
+    }
+
+    Ok(())
+}
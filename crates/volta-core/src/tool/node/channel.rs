@@ -0,0 +1,100 @@
+//! Provides the distribution channels that Node builds are published to.
+
+use std::fmt;
+
+use cfg_if::cfg_if;
+use semver::Version;
+
+cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        fn public_download_root() -> String {
+            format!("{}/download", mockito::SERVER_URL)
+        }
+    } else {
+        fn public_download_root() -> String {
+            "https://nodejs.org/download".to_string()
+        }
+    }
+}
+
+/// A channel of Node builds, each of which is published to its own directory on the Node server
+///
+/// Release builds are published to the regular distribution server, which can be replaced with a
+/// mirror or hooks. Builds from the other channels are only fetched when they are asked for
+/// explicitly, either by channel (`node@nightly`) or by exact version (`node@20.0.0-rc.1`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    Release,
+    Rc,
+    Nightly,
+    V8Canary,
+}
+
+impl Channel {
+    /// Returns the channel named by a version tag, such as `nightly` in `node@nightly`
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "rc" => Some(Channel::Rc),
+            "nightly" => Some(Channel::Nightly),
+            "canary" | "v8-canary" => Some(Channel::V8Canary),
+            _ => None,
+        }
+    }
+
+    /// Returns the channel that a version was published to, based on its prerelease tag
+    pub fn of(version: &Version) -> Self {
+        let version = version.to_string();
+        match version.splitn(2, '-').nth(1) {
+            Some(pre) if pre.starts_with("rc") => Channel::Rc,
+            Some(pre) if pre.starts_with("nightly") => Channel::Nightly,
+            Some(pre) if pre.starts_with("v8-canary") => Channel::V8Canary,
+            _ => Channel::Release,
+        }
+    }
+
+    /// Returns the root URL that builds from this channel are downloaded from, which isn't
+    /// meaningful for release builds since their server is configurable
+    pub fn server_root(self) -> String {
+        format!("{}/{}", public_download_root(), self)
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Channel::Release => "release",
+            Channel::Rc => "rc",
+            Channel::Nightly => "nightly",
+            Channel::V8Canary => "v8-canary",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_of() {
+        let channel = |version| Channel::of(&Version::parse(version).unwrap());
+
+        assert_eq!(channel("12.16.1"), Channel::Release);
+        assert_eq!(channel("14.0.0-rc.1"), Channel::Rc);
+        assert_eq!(
+            channel("15.0.0-nightly20200714a0ae4d4a5b"),
+            Channel::Nightly
+        );
+        assert_eq!(
+            channel("15.0.0-v8-canary20200714ab3e2322a8"),
+            Channel::V8Canary
+        );
+    }
+
+    #[test]
+    fn test_from_tag() {
+        assert_eq!(Channel::from_tag("nightly"), Some(Channel::Nightly));
+        assert_eq!(Channel::from_tag("canary"), Some(Channel::V8Canary));
+        assert_eq!(Channel::from_tag("beta"), None);
+    }
+}
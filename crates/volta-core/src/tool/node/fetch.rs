@@ -1,53 +1,156 @@
 //! Provides fetcher for Node distributions
 
-use std::fs::{read_to_string, rename, write, File};
+use std::fs::{read_to_string, write, File};
 use std::path::{Path, PathBuf};
 
-use super::super::download_tool_error;
+use super::super::prefetch::RemoteArchive;
+use super::super::{
+    check_unpack_space, copy_shared_archive, download_archive, download_tool_error,
+    fetched_while_waiting, recompress_archive, verify_locked_archive, zstd_archive_file,
+};
+use super::{uses_unofficial_builds, Channel};
+use crate::checksum::fetch_node_checksum;
+#[cfg(not(feature = "mock-network"))]
+use crate::command::create_command;
 use crate::error::ErrorDetails;
-use crate::fs::{create_staging_dir, create_staging_file};
+use crate::fs::{clear_quarantine, create_staging_dir, create_staging_file, move_dir};
 use crate::hook::ToolHooks;
-use crate::layout::volta_home;
+use crate::image::ToolImage;
+use crate::layout::{layered_path, long_path, volta_home};
+use crate::settings::{check_online, settings};
+use crate::store;
 use crate::style::{progress_bar, tool_version};
+use crate::sync::VoltaLock;
 use crate::tool::{self, Node, NodeVersion};
 use crate::version::{parse_version, VersionSpec};
+#[cfg(unix)]
+use archive::HttpError;
 use archive::{self, Archive};
 use cfg_if::cfg_if;
 use fs_utils::ensure_containing_dir_exists;
 use log::debug;
+#[cfg(unix)]
+use reqwest::StatusCode;
 use semver::Version;
 use serde::Deserialize;
+#[cfg(not(feature = "mock-network"))]
+use volta_fail::throw;
+#[cfg(unix)]
+use volta_fail::VoltaError;
 use volta_fail::{Fallible, ResultExt};
 
 cfg_if! {
     if #[cfg(feature = "mock-network")] {
-        fn public_node_server_root() -> String {
+        pub(crate) fn public_node_server_root() -> String {
             mockito::SERVER_URL.to_string()
         }
+
+        fn unofficial_node_server_root() -> String {
+            format!("{}/unofficial-builds", mockito::SERVER_URL)
+        }
+
+        // The mock distros used in the acceptance tests don't contain a real `node` executable
+        fn verify_node_image(_image_dir: &Path, _version: &Version) -> Fallible<()> {
+            Ok(())
+        }
     } else {
-        fn public_node_server_root() -> String {
+        pub(crate) fn public_node_server_root() -> String {
             "https://nodejs.org/dist".to_string()
         }
+
+        fn unofficial_node_server_root() -> String {
+            "https://unofficial-builds.nodejs.org/download/release".to_string()
+        }
+
+        /// Run `node --version` from the unpacked image and confirm that it reports the expected
+        /// version, catching truncated archives or distros built for the wrong architecture before
+        /// they are moved into the image directory.
+        fn verify_node_image(image_dir: &Path, version: &Version) -> Fallible<()> {
+            let version_string = version.to_string();
+            let node_exe = ToolImage::distro(image_dir.to_owned()).node_executable();
+            debug!("Verifying node executable at '{}'", node_exe.display());
+
+            let output = create_command(&node_exe)
+                .arg("--version")
+                .output()
+                .with_context(|_| ErrorDetails::NodeVerificationError {
+                    version: version_string.clone(),
+                    found: None,
+                })?;
+
+            let reported = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if output.status.success() && reported.trim_start_matches('v') == version_string {
+                Ok(())
+            } else {
+                throw!(ErrorDetails::NodeVerificationError {
+                    version: version_string,
+                    found: Some(reported).filter(|found| !found.is_empty()),
+                });
+            }
+        }
     }
 }
 
-fn npm_manifest_path(version: &str) -> PathBuf {
-    let mut manifest = PathBuf::from(Node::archive_basename(version));
+pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Node>>) -> Fallible<NodeVersion> {
+    let version_string = version.to_string();
+    fetch_into(version, hooks, |npm| {
+        Ok(volta_home()?.node_image_dir(&version_string, &npm.to_string()))
+    })
+}
 
-    #[cfg(unix)]
-    manifest.push("lib");
+/// Fetches Node into a project-local toolchain directory, instead of the shared image directory
+///
+/// The archive is still cached in the shared inventory, so that other projects using the same
+/// version don't need to download it again.
+pub fn fetch_local(
+    version: &Version,
+    hooks: Option<&ToolHooks<Node>>,
+    toolchain_dir: &Path,
+) -> Fallible<NodeVersion> {
+    let node_version = fetch_into(version, hooks, |_| {
+        Ok(super::local_image_dir(toolchain_dir, version))
+    })?;
 
-    manifest.push("node_modules");
-    manifest.push("npm");
-    manifest.push("package.json");
+    // The toolchain is specific to the platform it was fetched on, so it shouldn't be committed
+    let gitignore = toolchain_dir.join(".gitignore");
+    if !gitignore.exists() {
+        write(&gitignore, "*\n").with_context(|_| ErrorDetails::WriteLocalToolchainError {
+            file: gitignore.clone(),
+        })?;
+    }
 
-    manifest
+    Ok(node_version)
 }
 
-pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Node>>) -> Fallible<NodeVersion> {
+/// Fetches Node, unpacking it into the directory returned by `image_dir` for the bundled npm
+fn fetch_into<F>(
+    version: &Version,
+    hooks: Option<&ToolHooks<Node>>,
+    image_dir: F,
+) -> Fallible<NodeVersion>
+where
+    F: Fn(&Version) -> Fallible<PathBuf>,
+{
+    let _lock = VoltaLock::acquire()?;
+    if let Ok(npm) = load_default_npm_version(version) {
+        if fetched_while_waiting("node", version, &image_dir(&npm)?) {
+            return Ok(NodeVersion {
+                runtime: version.clone(),
+                npm,
+            });
+        }
+    }
+    let cache_archives = settings()?.cache_archives;
     let home = volta_home()?;
     let node_dir = home.node_inventory_dir();
     let cache_file = node_dir.join(Node::archive_filename(&version.to_string()));
+    copy_shared_archive("node", version, &cache_file, || {
+        fetch_node_checksum(&determine_remote_url(version, hooks)?)
+    })?;
+
+    verify_locked_archive("node", version, &cache_file, || {
+        determine_remote_url(version, hooks)
+    })?;
 
     let (archive, staging) = match load_cached_distro(&cache_file) {
         Some(archive) => {
@@ -61,14 +164,25 @@ pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Node>>) -> Fallible<Nod
         None => {
             let staging = create_staging_file()?;
             let remote_url = determine_remote_url(&version, hooks)?;
-            let archive = fetch_remote_distro(&version, &remote_url, staging.path())?;
-            (archive, Some(staging))
+            match fetch_remote_distro(&version, &remote_url, staging.path(), cache_archives) {
+                Ok(archive) => (archive, Some(staging)),
+                #[cfg(unix)]
+                Err(error) if settings()?.build_from_source && lacks_prebuilt(version, &error)? => {
+                    debug!(
+                        "There is no prebuilt Node for this system, building from source: {}",
+                        error
+                    );
+                    return super::build::build(version, image_dir);
+                }
+                Err(error) => return Err(error),
+            }
         }
     };
 
-    let node_version = unpack_archive(archive, version)?;
+    let node_version = unpack_archive(archive, version, image_dir)?;
 
-    if let Some(staging_file) = staging {
+    // Without `cacheArchives`, the staging file (if it was used at all) is removed when dropped
+    if let Some(staging_file) = staging.filter(|_| cache_archives) {
         ensure_containing_dir_exists(&cache_file).with_context(|_| {
             ErrorDetails::ContainingDirError {
                 path: cache_file.clone(),
@@ -80,53 +194,118 @@ pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Node>>) -> Fallible<Nod
                 tool: "Node".into(),
             })?;
     }
+    recompress_archive(&cache_file)?;
 
     Ok(node_version)
 }
 
+/// Returns the archive of a version in the inventory, downloading it if needed
+///
+/// If the archive was recompressed, its path is still the one of the downloaded archive, and
+/// only the recompressed tarball next to it exists.
+pub fn inventory_archive(version: &Version, hooks: Option<&ToolHooks<Node>>) -> Fallible<PathBuf> {
+    let _lock = VoltaLock::acquire()?;
+    let node_dir = volta_home()?.node_inventory_dir();
+    let cache_file = node_dir.join(Node::archive_filename(&version.to_string()));
+    copy_shared_archive("node", version, &cache_file, || {
+        fetch_node_checksum(&determine_remote_url(version, hooks)?)
+    })?;
+
+    if !cache_file.is_file() && !zstd_archive_file(&cache_file).is_file() {
+        download_archive(
+            "node",
+            version,
+            &determine_remote_url(version, hooks)?,
+            &cache_file,
+            None,
+        )?;
+    }
+    Ok(cache_file)
+}
+
+/// Returns where the archive of a version is downloaded from and kept in the inventory
+pub(super) fn remote_archive(
+    version: &Version,
+    hooks: Option<&ToolHooks<Node>>,
+) -> Fallible<RemoteArchive> {
+    let node_dir = volta_home()?.node_inventory_dir();
+    Ok(RemoteArchive {
+        tool: "node",
+        version: version.clone(),
+        url: determine_remote_url(version, hooks)?,
+        cache_file: node_dir.join(Node::archive_filename(&version.to_string())),
+    })
+}
+
 /// Unpack the node archive into the image directory so that it is ready for use
-fn unpack_archive(archive: Box<dyn Archive>, version: &Version) -> Fallible<NodeVersion> {
+fn unpack_archive<F>(
+    archive: Box<dyn Archive>,
+    version: &Version,
+    image_dir: F,
+) -> Fallible<NodeVersion>
+where
+    F: FnOnce(&Version) -> Fallible<PathBuf>,
+{
     let temp = create_staging_dir()?;
     debug!("Unpacking node into '{}'", temp.path().display());
 
-    let progress = progress_bar(
-        archive.origin(),
-        &tool_version("node", &version),
-        archive
-            .uncompressed_size()
-            .unwrap_or_else(|| archive.compressed_size()),
-    );
+    let size = archive
+        .uncompressed_size()
+        .unwrap_or_else(|| archive.compressed_size());
+    check_unpack_space(archive.as_ref(), temp.path())?;
+
+    let progress = progress_bar(archive.origin(), &tool_version("node", &version), size);
     let version_string = version.to_string();
 
     archive
-        .unpack(temp.path(), &mut |_, read| {
+        .unpack(&long_path(temp.path()), &mut |_, read| {
             progress.inc(read as u64);
         })
         .with_context(|_| ErrorDetails::UnpackArchiveError {
             tool: "Node".into(),
             version: version_string.clone(),
         })?;
+    progress.finish_and_clear();
+
+    let unpacked = temp.path().join(Node::archive_basename(&version_string));
+    set_up_image(&unpacked, version, image_dir)
+}
+
+/// Moves an unpacked (or built) Node image into the directory returned by `image_dir`, once it
+/// has been verified
+pub(super) fn set_up_image<F>(
+    unpacked: &Path,
+    version: &Version,
+    image_dir: F,
+) -> Fallible<NodeVersion>
+where
+    F: FnOnce(&Version) -> Fallible<PathBuf>,
+{
+    let version_string = version.to_string();
+
+    // The image is verified first, so that the npm version of a broken image is never saved
+    verify_node_image(unpacked, &version)?;
+    clear_quarantine(unpacked);
 
     // Save the npm version number in the npm version file for this distro
-    let npm_package_json = temp.path().join(npm_manifest_path(&version_string));
-    let npm = Manifest::version(&npm_package_json)?;
+    let npm = Manifest::version(&ToolImage::distro(unpacked.to_owned()).npm_manifest())?;
     save_default_npm_version(&version, &npm)?;
 
-    let dest = volta_home()?.node_image_dir(&version_string, &npm.to_string());
+    let dest = image_dir(&npm)?;
     ensure_containing_dir_exists(&dest)
         .with_context(|_| ErrorDetails::ContainingDirError { path: dest.clone() })?;
 
-    rename(
-        temp.path().join(Node::archive_basename(&version_string)),
-        &dest,
-    )
-    .with_context(|_| ErrorDetails::SetupToolImageError {
+    move_dir(unpacked, &dest).with_context(|_| ErrorDetails::SetupToolImageError {
         tool: "Node".into(),
         version: version_string,
         dir: dest.clone(),
     })?;
 
-    progress.finish_and_clear();
+    // Only the images in the Volta home are linked to the store, since project-local toolchains
+    // may be on another volume and are meant to be self-contained
+    if settings()?.dedup_images && dest.starts_with(volta_home()?.node_image_root_dir()) {
+        store::dedup(&dest);
+    }
 
     // Note: We write these after the progress bar is finished to avoid display bugs with re-renders of the progress
     debug!("Saving bundled npm version ({})", npm);
@@ -140,45 +319,100 @@ fn unpack_archive(archive: Box<dyn Archive>, version: &Version) -> Fallible<Node
 
 /// Return the archive if it is valid. It may have been corrupted or interrupted in the middle of
 /// downloading.
+///
+/// The tarball may also have been recompressed with zstd, in which case it is decompressed with
+/// zstd instead.
 // ISSUE(#134) - verify checksum
 fn load_cached_distro(file: &Path) -> Option<Box<dyn Archive>> {
+    let zstd_file = zstd_archive_file(file);
     if file.is_file() {
         let file = File::open(file).ok()?;
         archive::load_native(file).ok()
+    } else if zstd_file.is_file() {
+        let file = File::open(zstd_file).ok()?;
+        archive::Tarball::load_zstd(file).ok()
     } else {
         None
     }
 }
 
 /// Determine the remote URL to download from, using the hooks if available
+///
+/// Builds from the prerelease channels are always downloaded from the public Node server, since
+/// the hooks and the mirror are only expected to provide release builds.
 fn determine_remote_url(version: &Version, hooks: Option<&ToolHooks<Node>>) -> Fallible<String> {
     let version_str = version.to_string();
     let distro_file_name = Node::archive_filename(&version_str);
+    let channel = Channel::of(version);
     match hooks {
         Some(&ToolHooks {
             distro: Some(ref hook),
             ..
-        }) => {
+        }) if channel == Channel::Release => {
             debug!("Using node.distro hook to determine download URL");
             hook.resolve(&version, &distro_file_name)
         }
-        _ => Ok(format!(
-            "{}/v{}/{}",
-            public_node_server_root(),
-            version,
-            distro_file_name
-        )),
+        _ => {
+            let root = match settings()?.node_mirror {
+                _ if channel != Channel::Release => channel.server_root(),
+                Some(ref mirror) => mirror.clone(),
+                None if uses_unofficial_builds()? => unofficial_node_server_root(),
+                None => public_node_server_root(),
+            };
+            Ok(format!("{}/v{}/{}", root, version, distro_file_name))
+        }
     }
 }
 
-/// Fetch the distro archive from the internet
-fn fetch_remote_distro(
+/// Whether the failure to fetch Node means that there is no prebuilt Node for this system, either
+/// because the server doesn't have it or because the index doesn't list a build for the system
+///
+/// Any other failure, like a lost connection, is reported rather than starting a long build.
+#[cfg(unix)]
+fn lacks_prebuilt(version: &Version, error: &VoltaError) -> Fallible<bool> {
+    Ok(is_not_found(error) || super::resolve::cached_build_listed(version)? == Some(false))
+}
+
+/// Whether the error was caused by a `404 Not Found` response
+#[cfg(unix)]
+fn is_not_found(error: &VoltaError) -> bool {
+    error.as_fail().iter_chain().any(|fail| {
+        fail.downcast_ref::<HttpError>()
+            .map_or(false, |http| http.code == StatusCode::NOT_FOUND)
+    })
+}
+
+/// Determine the URL of the source tarball for a version
+///
+/// The unofficial-builds project doesn't publish sources, so they always come from the mirror or
+/// the public Node server.
+#[cfg(unix)]
+pub(super) fn source_url(version: &Version) -> Fallible<String> {
+    let channel = Channel::of(version);
+    let root = match settings()?.node_mirror {
+        _ if channel != Channel::Release => channel.server_root(),
+        Some(ref mirror) => mirror.clone(),
+        None => public_node_server_root(),
+    };
+    Ok(format!("{}/v{}/node-v{}.tar.gz", root, version, version))
+}
+
+/// Fetch the distro archive from the internet, saving a copy of it to `staging_path` if the
+/// archive should be cached
+pub(super) fn fetch_remote_distro(
     version: &Version,
     url: &str,
     staging_path: &Path,
+    cache: bool,
 ) -> Fallible<Box<dyn Archive>> {
+    check_online(url)?;
     debug!("Downloading {} from {}", tool_version("node", version), url);
-    archive::fetch_native(url, staging_path).with_context(download_tool_error(
+    let archive = if cache {
+        archive::fetch_native(url, staging_path)
+    } else {
+        archive::stream_native(url, staging_path)
+    };
+    archive.with_context(download_tool_error(
         tool::Spec::Node(VersionSpec::Exact(version.clone())),
         url,
     ))
@@ -202,7 +436,8 @@ impl Manifest {
 
 /// Load the local npm version file to determine the default npm version for a given version of Node
 pub fn load_default_npm_version(node: &Version) -> Fallible<Version> {
-    let npm_version_file_path = volta_home()?.node_npm_version_file(&node.to_string());
+    let node_str = node.to_string();
+    let npm_version_file_path = layered_path(|home| home.node_npm_version_file(&node_str))?;
     let npm_version = read_to_string(&npm_version_file_path).with_context(|_| {
         ErrorDetails::ReadDefaultNpmError {
             file: npm_version_file_path,
@@ -220,3 +455,39 @@ fn save_default_npm_version(node: &Version, npm: &Version) -> Fallible<()> {
         }
     })
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn fetch_error(error: failure::Error) -> VoltaError {
+        Err::<(), _>(error)
+            .with_context(download_tool_error(
+                tool::Spec::Node(VersionSpec::None),
+                "https://nodejs.org/dist/v14.15.0/node-v14.15.0-linux-x64.tar.gz",
+            ))
+            .unwrap_err()
+    }
+
+    #[test]
+    fn test_is_not_found() {
+        let not_found = fetch_error(
+            HttpError {
+                code: StatusCode::NOT_FOUND,
+            }
+            .into(),
+        );
+        assert!(is_not_found(&not_found));
+
+        let server_error = fetch_error(
+            HttpError {
+                code: StatusCode::INTERNAL_SERVER_ERROR,
+            }
+            .into(),
+        );
+        assert!(!is_not_found(&server_error));
+
+        let connection_error = fetch_error(failure::err_msg("connection refused"));
+        assert!(!is_not_found(&connection_error));
+    }
+}
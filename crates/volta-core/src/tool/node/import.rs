@@ -0,0 +1,154 @@
+//! Provides importing a Node installation made by another version manager, such as nvm, into
+//! the image directory, so that the same version doesn't need to be downloaded again.
+
+use std::path::Path;
+
+use super::fetch::set_up_image;
+use crate::error::ErrorDetails;
+use crate::fs::create_staging_dir;
+use crate::image::ToolImage;
+use crate::layout::volta_home;
+use crate::sync::VoltaLock;
+use crate::tool::{Node, NodeVersion};
+use log::debug;
+use semver::Version;
+use volta_fail::{Fallible, ResultExt};
+
+/// Determines whether a directory holds a Node installation with the layout of a Node distro,
+/// which is the only layout that can be used as an image
+///
+/// Installations are only imported on Unix, since the global packages installed into a Node on
+/// Windows can't be told apart from the files of the distro.
+pub(super) fn is_importable(install_dir: &Path) -> bool {
+    let install = ToolImage::distro(install_dir.to_owned());
+    cfg!(unix) && install.node_executable().is_file() && install.npm_manifest().is_file()
+}
+
+/// Copies the Node installation in `install_dir` into the image directory
+///
+/// The packages that were installed globally into the installation are left out, since Volta
+/// installs packages into their own images.
+pub(super) fn import(version: &Version, install_dir: &Path) -> Fallible<NodeVersion> {
+    let _lock = VoltaLock::acquire()?;
+    let version_string = version.to_string();
+    debug!(
+        "Importing node@{} from '{}'",
+        version_string,
+        install_dir.display()
+    );
+
+    let temp = create_staging_dir()?;
+    let unpacked = temp.path().join(Node::archive_basename(&version_string));
+    copy_installation(install_dir, &unpacked).with_context(|_| ErrorDetails::ImportNodeError {
+        version: version_string.clone(),
+        dir: install_dir.to_owned(),
+    })?;
+
+    set_up_image(&unpacked, version, |npm| {
+        Ok(volta_home()?.node_image_dir(&version_string, &npm.to_string()))
+    })
+}
+
+/// Copies an installation, keeping the symlinks to the executables of npm intact, and removes
+/// the globally installed packages from the copy
+#[cfg(unix)]
+fn copy_installation(src: &Path, dest: &Path) -> std::io::Result<()> {
+    use std::fs::{read_dir, remove_dir_all, remove_file};
+
+    // The packages that are part of a Node distro, rather than installed globally into it
+    const BUNDLED_PACKAGES: &[&str] = &["npm", "corepack"];
+
+    copy_preserving_links(src, dest)?;
+
+    let image = ToolImage::distro(dest.to_owned());
+    let modules = image.modules_dir();
+    for entry in read_dir(&modules)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if !BUNDLED_PACKAGES.iter().any(|package| name == *package) {
+            remove_dir_all(entry.path()).or_else(|_| remove_file(entry.path()))?;
+        }
+    }
+
+    // The executables of global packages are links into the packages that were just removed
+    for entry in read_dir(image.bin_dir())? {
+        let path = entry?.path();
+        if !path.exists() {
+            remove_file(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_preserving_links(src: &Path, dest: &Path) -> std::io::Result<()> {
+    use std::fs::{copy, create_dir_all, read_dir, read_link};
+    use std::os::unix::fs::symlink;
+
+    create_dir_all(dest)?;
+    for entry in read_dir(src)? {
+        let entry = entry?;
+        let target = dest.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            symlink(read_link(entry.path())?, target)?;
+        } else if file_type.is_dir() {
+            copy_preserving_links(&entry.path(), &target)?;
+        } else {
+            copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn copy_installation(_src: &Path, _dest: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "installations are only imported on Unix",
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn test_copy_installation_removes_global_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("v14.15.0");
+        let modules = src.join("lib").join("node_modules");
+        create_dir_all(modules.join("npm").join("bin")).unwrap();
+        create_dir_all(modules.join("typescript").join("bin")).unwrap();
+        write(modules.join("npm").join("bin").join("npm-cli.js"), "").unwrap();
+        write(modules.join("typescript").join("bin").join("tsc"), "").unwrap();
+        create_dir_all(src.join("bin")).unwrap();
+        write(src.join("bin").join("node"), "").unwrap();
+        symlink(
+            "../lib/node_modules/npm/bin/npm-cli.js",
+            src.join("bin").join("npm"),
+        )
+        .unwrap();
+        symlink(
+            "../lib/node_modules/typescript/bin/tsc",
+            src.join("bin").join("tsc"),
+        )
+        .unwrap();
+
+        let dest = dir.path().join("image");
+        copy_installation(&src, &dest).unwrap();
+
+        assert!(dest.join("bin").join("node").is_file());
+        assert!(dest.join("bin").join("npm").exists());
+        assert!(!dest.join("bin").join("tsc").exists());
+        assert!(dest.join("lib").join("node_modules").join("npm").is_dir());
+        assert!(!dest
+            .join("lib")
+            .join("node_modules")
+            .join("typescript")
+            .exists());
+    }
+}
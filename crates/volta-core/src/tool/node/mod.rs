@@ -1,21 +1,36 @@
 use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
 
+use super::prefetch::RemoteArchive;
 use super::{
     debug_already_fetched, info_fetched, info_installed, info_pinned, info_project_version, Tool,
 };
+use crate::checksum::{fetch_node_checksum, Checksum};
 use crate::error::ErrorDetails;
+use crate::image::ToolImage;
 use crate::session::Session;
+use crate::settings::{settings, NodeBuilds};
 use crate::style::tool_version;
 use cfg_if::cfg_if;
 use semver::Version;
 use volta_fail::Fallible;
 
+#[cfg(unix)]
+mod build;
+mod channel;
 mod fetch;
+mod import;
 mod resolve;
+mod schedule;
 mod serial;
 
+pub(crate) use channel::Channel;
 pub use fetch::load_default_npm_version;
-pub use resolve::resolve;
+pub(crate) use fetch::public_node_server_root;
+pub(crate) use resolve::{cached_security_releases, index_versions};
+pub use resolve::{cached_versions, node_index_url, resolve, version_choices, NodeChoice};
+pub use schedule::{cached_release_schedule, release_line, Schedule};
+pub(crate) use schedule::{end_of_life_message, release_schedule};
 
 cfg_if! {
     if #[cfg(target_os = "windows")] {
@@ -27,8 +42,11 @@ cfg_if! {
     } else if #[cfg(target_os = "linux")] {
         /// The OS component of a Node distro's filename.
         pub const NODE_DISTRO_OS: &str = "linux";
+    } else if #[cfg(target_os = "freebsd")] {
+        /// The OS component of a Node distro's filename.
+        pub const NODE_DISTRO_OS: &str = "freebsd";
     } else {
-        compile_error!("Unsupported operating system (expected Windows, macOS, or Linux).");
+        compile_error!("Unsupported operating system (expected Windows, macOS, Linux, or FreeBSD).");
     }
 }
 
@@ -42,11 +60,58 @@ cfg_if! {
     } else if #[cfg(target_arch = "aarch64")] {
         /// The system architecture component of a Node distro's name.
         pub const NODE_DISTRO_ARCH: &str = "arm64";
+    } else if #[cfg(all(target_arch = "arm", target_feature = "v7"))] {
+        /// The system architecture component of a Node distro's name.
+        pub const NODE_DISTRO_ARCH: &str = "armv7l";
+    } else if #[cfg(target_arch = "arm")] {
+        /// The system architecture component of a Node distro's name.
+        pub const NODE_DISTRO_ARCH: &str = "armv6l";
+    } else if #[cfg(target_arch = "riscv64")] {
+        /// The system architecture component of a Node distro's name.
+        pub const NODE_DISTRO_ARCH: &str = "riscv64";
     } else {
-        compile_error!("Unsupported target_arch variant (expected 'x86', 'x64', or 'aarch64').");
+        compile_error!("Unsupported target_arch variant (expected 'x86', 'x64', 'aarch64', 'arm', or 'riscv64').");
     }
 }
 
+cfg_if! {
+    if #[cfg(target_env = "musl")] {
+        /// The suffix of a Node distro's name for the C library it is linked against.
+        pub const NODE_DISTRO_LIBC: &str = "-musl";
+    } else {
+        /// The suffix of a Node distro's name for the C library it is linked against.
+        pub const NODE_DISTRO_LIBC: &str = "";
+    }
+}
+
+/// Whether Node only publishes builds for this system through the unofficial-builds project, which
+/// covers FreeBSD, ARMv6, RISC-V and musl-based Linux distributions
+const NEEDS_UNOFFICIAL_BUILDS: bool = cfg!(any(
+    target_os = "freebsd",
+    target_env = "musl",
+    target_arch = "riscv64",
+    all(target_arch = "arm", not(target_feature = "v7"))
+));
+
+/// Determines whether Node is fetched from the unofficial-builds project, based on the system or
+/// the `nodeBuilds` setting
+pub(crate) fn uses_unofficial_builds() -> Fallible<bool> {
+    Ok(match settings()?.node_builds {
+        NodeBuilds::Auto => NEEDS_UNOFFICIAL_BUILDS,
+        NodeBuilds::Official => false,
+        NodeBuilds::Unofficial => true,
+    })
+}
+
+/// The platform of the Node distro for this system, as listed in the `files` of the Node index
+/// (e.g. `linux-x64-musl`)
+pub(crate) fn distro_platform() -> String {
+    format!(
+        "{}-{}{}",
+        NODE_DISTRO_OS, NODE_DISTRO_ARCH, NODE_DISTRO_LIBC
+    )
+}
+
 cfg_if! {
     if #[cfg(target_os = "windows")] {
         /// Filename extension for Node distro files.
@@ -57,6 +122,13 @@ cfg_if! {
     }
 }
 
+/// The directory that Node is unpacked into in a project-local toolchain
+pub(crate) fn local_image_dir(toolchain_dir: &Path, version: &Version) -> PathBuf {
+    ToolImage::local_node(toolchain_dir, version)
+        .root()
+        .to_owned()
+}
+
 /// A full Node version including not just the version of Node itself
 /// but also the specific version of npm installed globally with that
 /// Node installation.
@@ -91,7 +163,7 @@ impl Node {
     }
 
     pub fn archive_basename(version: &str) -> String {
-        format!("node-v{}-{}-{}", version, NODE_DISTRO_OS, NODE_DISTRO_ARCH)
+        format!("node-v{}-{}", version, distro_platform())
     }
 
     pub fn archive_filename(version: &str) -> String {
@@ -102,7 +174,91 @@ impl Node {
         )
     }
 
+    /// Returns the bundled npm version if this Node version has already been unpacked
+    fn unpacked_npm_version(&self) -> Option<Version> {
+        let npm = fetch::load_default_npm_version(&self.version).ok()?;
+        let image = ToolImage::layered(|home| ToolImage::node(home, &self.version, &npm)).ok()?;
+
+        if image.exists() {
+            Some(npm)
+        } else {
+            None
+        }
+    }
+
+    /// Imports this version from an installation made by another version manager, such as nvm,
+    /// instead of downloading it
+    ///
+    /// Returns `Ok(None)` if the installation doesn't have the layout of a Node distro, in which
+    /// case the version needs to be fetched instead.
+    pub fn import(&self, install_dir: &Path) -> Fallible<Option<NodeVersion>> {
+        if let Some(npm) = self.unpacked_npm_version() {
+            debug_already_fetched(self);
+            return Ok(Some(NodeVersion {
+                runtime: self.version.clone(),
+                npm,
+            }));
+        }
+
+        if !import::is_importable(install_dir) {
+            return Ok(None);
+        }
+        import::import(&self.version, install_dir).map(Some)
+    }
+
+    /// Whether this version can be used without downloading it, either because it is unpacked
+    /// or because its archive is in the inventory
+    pub(crate) fn is_fetched(
+        &self,
+        session: &Session,
+        toolchain_dir: Option<&Path>,
+    ) -> Fallible<bool> {
+        let unpacked = match toolchain_dir {
+            Some(dir) => local_image_dir(dir, &self.version).is_dir(),
+            None => self.unpacked_npm_version().is_some(),
+        };
+
+        Ok(unpacked || session.inventory()?.node.versions.contains(&self.version))
+    }
+
+    /// Fetches Node into the project-local toolchain directory, if it isn't already there
+    pub(crate) fn fetch_local(
+        &self,
+        session: &mut Session,
+        toolchain_dir: &Path,
+    ) -> Fallible<NodeVersion> {
+        if local_image_dir(toolchain_dir, &self.version).is_dir() {
+            debug_already_fetched(self);
+            let npm = fetch::load_default_npm_version(&self.version)?;
+
+            return Ok(NodeVersion {
+                runtime: self.version.clone(),
+                npm,
+            });
+        }
+
+        let node_version =
+            fetch::fetch_local(&self.version, session.hooks()?.node(), toolchain_dir)?;
+        session
+            .inventory_mut()?
+            .node
+            .versions
+            .insert(self.version.clone());
+
+        Ok(node_version)
+    }
+
     pub(crate) fn fetch_internal(&self, session: &mut Session) -> Fallible<NodeVersion> {
+        // Checking for the unpacked image first avoids reading the whole inventory, which
+        // matters since this runs every time a shim is used
+        if let Some(npm) = self.unpacked_npm_version() {
+            debug_already_fetched(self);
+            return Ok(NodeVersion {
+                runtime: self.version.clone(),
+                npm,
+            });
+        }
+
         let inventory = session.inventory()?;
         if inventory.node.versions.contains(&self.version) {
             debug_already_fetched(self);
@@ -123,6 +279,27 @@ impl Node {
 
         Ok(node_version)
     }
+
+    /// Returns the archive of this version in the inventory, downloading it if needed, so that
+    /// its checksum can be recorded in a lockfile
+    pub(crate) fn inventory_archive(&self, session: &Session) -> Fallible<PathBuf> {
+        fetch::inventory_archive(&self.version, session.hooks()?.node())
+    }
+
+    /// Fetches the checksum that the Node server publishes for the archive of this version
+    pub(crate) fn published_checksum(&self, session: &Session) -> Fallible<Option<Checksum>> {
+        let archive = fetch::remote_archive(&self.version, session.hooks()?.node())?;
+        fetch_node_checksum(&archive.url)
+    }
+
+    /// Returns the archive that fetching this version would download, unless the version is
+    /// already fetched or its archive isn't kept in the inventory
+    pub(crate) fn remote_archive(&self, session: &Session) -> Fallible<Option<RemoteArchive>> {
+        if !settings()?.cache_archives || self.is_fetched(session, None)? {
+            return Ok(None);
+        }
+        fetch::remote_archive(&self.version, session.hooks()?.node()).map(Some)
+    }
 }
 
 impl Tool for Node {
@@ -137,6 +314,7 @@ impl Tool for Node {
 
         session.toolchain_mut()?.set_active_node(&node_version)?;
 
+        schedule::warn_end_of_life(&node_version.runtime);
         info_installed(node_version);
 
         if let Ok(Some(project)) = session.project_platform() {
@@ -152,6 +330,7 @@ impl Tool for Node {
             let project = session.project_mut()?.unwrap();
             project.pin_node(&node_version)?;
 
+            schedule::warn_end_of_life(&node_version.runtime);
             info_pinned(node_version);
             Ok(())
         } else {
@@ -174,7 +353,10 @@ mod tests {
     fn test_node_archive_basename() {
         assert_eq!(
             Node::archive_basename("1.2.3"),
-            format!("node-v1.2.3-{}-{}", NODE_DISTRO_OS, NODE_DISTRO_ARCH)
+            format!(
+                "node-v1.2.3-{}-{}{}",
+                NODE_DISTRO_OS, NODE_DISTRO_ARCH, NODE_DISTRO_LIBC
+            )
         );
     }
 
@@ -183,8 +365,8 @@ mod tests {
         assert_eq!(
             Node::archive_filename("1.2.3"),
             format!(
-                "node-v1.2.3-{}-{}.{}",
-                NODE_DISTRO_OS, NODE_DISTRO_ARCH, NODE_DISTRO_EXTENSION
+                "node-v1.2.3-{}-{}{}.{}",
+                NODE_DISTRO_OS, NODE_DISTRO_ARCH, NODE_DISTRO_LIBC, NODE_DISTRO_EXTENSION
             )
         );
     }
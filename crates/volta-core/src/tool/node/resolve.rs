@@ -1,27 +1,34 @@
 //! Provides resolution of Node requirements into specific versions, using the NodeJS index
 
 use std::collections::HashSet;
+use std::fmt;
 use std::fs::File;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::{Duration, SystemTime};
 
 use super::super::registry_fetch_error;
 use super::serial;
+use super::{distro_platform, uses_unofficial_builds, Channel};
 use crate::error::ErrorDetails;
 use crate::fs::{create_staging_file, read_file};
 use crate::hook::ToolHooks;
 use crate::layout::volta_home;
 use crate::session::Session;
+use crate::settings::{check_online, settings};
 use crate::style::progress_spinner;
 use crate::tool::Node;
 use crate::version::{VersionSpec, VersionTag};
+use archive::http_client;
 use cfg_if::cfg_if;
 use fs_utils::ensure_containing_dir_exists;
 use headers_011::Headers011;
 use log::debug;
 use reqwest;
+use reqwest::header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
 use reqwest::hyper_011::header::{CacheControl, CacheDirective, Expires, HttpDate};
+use reqwest::StatusCode;
 use semver::{Version, VersionReq};
 use volta_fail::{Fallible, ResultExt};
 
@@ -31,11 +38,40 @@ cfg_if! {
         fn public_node_version_index() -> String {
             format!("{}/node-dist/index.json", mockito::SERVER_URL)
         }
+
+        fn unofficial_node_version_index() -> String {
+            format!("{}/unofficial-builds/index.json", mockito::SERVER_URL)
+        }
     } else {
         /// Returns the URL of the index of available Node versions on the public Node server.
         fn public_node_version_index() -> String {
             "https://nodejs.org/dist/index.json".to_string()
         }
+
+        /// Returns the URL of the index of Node versions built by the unofficial-builds project.
+        fn unofficial_node_version_index() -> String {
+            "https://unofficial-builds.nodejs.org/download/release/index.json".to_string()
+        }
+    }
+}
+
+/// Returns the URL of the Node version index on the configured mirror, or the public Node server.
+fn node_version_index() -> Fallible<String> {
+    Ok(match settings()?.node_mirror {
+        Some(ref mirror) => format!("{}/index.json", mirror),
+        None if uses_unofficial_builds()? => unofficial_node_version_index(),
+        None => public_node_version_index(),
+    })
+}
+
+/// Returns the URL of the Node version index, honoring a configured `node.index` hook.
+pub fn node_index_url(session: &Session) -> Fallible<String> {
+    match session.hooks()?.node() {
+        Some(&ToolHooks {
+            index: Some(ref hook),
+            ..
+        }) => hook.resolve("index.json"),
+        _ => node_version_index(),
     }
 }
 
@@ -47,10 +83,42 @@ pub fn resolve(matching: VersionSpec, session: &mut Session) -> Fallible<Version
         VersionSpec::None | VersionSpec::Tag(VersionTag::Lts) => resolve_lts(hooks),
         VersionSpec::Tag(VersionTag::Latest) => resolve_latest(hooks),
         VersionSpec::Tag(VersionTag::LtsRequirement(req)) => resolve_lts_semver(req, hooks),
-        // Node doesn't have "tagged" versions (apart from 'latest' and 'lts'), so custom tags will always be an error
-        VersionSpec::Tag(VersionTag::Custom(tag)) => {
-            Err(ErrorDetails::NodeVersionNotFound { matching: tag }.into())
+        // Apart from 'latest' and 'lts', the only tags Node has are the names of its prerelease channels
+        VersionSpec::Tag(VersionTag::Custom(tag)) => match Channel::from_tag(&tag) {
+            Some(channel) => resolve_channel(channel),
+            None => Err(ErrorDetails::NodeVersionNotFound { matching: tag }.into()),
+        },
+    }
+}
+
+/// Resolves the newest build in a prerelease channel, such as `node@nightly`
+///
+/// The channel indexes aren't cached, since the cache holds the index of release builds.
+fn resolve_channel(channel: Channel) -> Fallible<Version> {
+    let url = format!("{}/index.json", channel.server_root());
+    check_online(&url)?;
+    let spinner = progress_spinner(&format!("Fetching public registry: {}", url));
+    let index: serial::RawNodeIndex = http_client()
+        .get(&url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|mut response| response.json())
+        .with_context(registry_fetch_error("Node", &url))?;
+    spinner.finish_and_clear();
+
+    let index: NodeIndex = index.into();
+    match index.entries.into_iter().next() {
+        Some(NodeEntry { version, .. }) => {
+            debug!(
+                "Found newest {} node version ({}) from {}",
+                channel, version, url
+            );
+            Ok(version)
+        }
+        None => Err(ErrorDetails::NodeVersionNotFound {
+            matching: channel.to_string(),
         }
+        .into()),
     }
 }
 
@@ -66,7 +134,7 @@ fn resolve_latest(hooks: Option<&ToolHooks<Node>>) -> Fallible<Version> {
             debug!("Using node.latest hook to determine node index URL");
             hook.resolve("index.json")?
         }
-        _ => public_node_version_index(),
+        _ => node_version_index()?,
     };
     let version_opt = match_node_version(&url, |_| true)?;
 
@@ -91,7 +159,7 @@ fn resolve_lts(hooks: Option<&ToolHooks<Node>>) -> Fallible<Version> {
             debug!("Using node.index hook to determine node index URL");
             hook.resolve("index.json")?
         }
-        _ => public_node_version_index(),
+        _ => node_version_index()?,
     };
     let version_opt = match_node_version(&url, |&NodeEntry { lts, .. }| lts)?;
 
@@ -117,7 +185,7 @@ fn resolve_semver(matching: VersionReq, hooks: Option<&ToolHooks<Node>>) -> Fall
             debug!("Using node.index hook to determine node index URL");
             hook.resolve("index.json")?
         }
-        _ => public_node_version_index(),
+        _ => node_version_index()?,
     };
     let version_opt =
         match_node_version(&url, |NodeEntry { version, .. }| matching.matches(version))?;
@@ -147,7 +215,7 @@ fn resolve_lts_semver(matching: VersionReq, hooks: Option<&ToolHooks<Node>>) ->
             debug!("Using node.index hook to determine node index URL");
             hook.resolve("index.json")?
         }
-        _ => public_node_version_index(),
+        _ => node_version_index()?,
     };
 
     let first_pass = match_node_version(
@@ -190,13 +258,95 @@ fn match_node_version(
     url: &str,
     predicate: impl Fn(&NodeEntry) -> bool,
 ) -> Fallible<Option<Version>> {
-    let index: NodeIndex = resolve_node_versions(url)?.into();
-    let mut entries = index.entries.into_iter();
-    Ok(entries
+    Ok(available_entries(url)?
+        .into_iter()
         .find(predicate)
         .map(|NodeEntry { version, .. }| version))
 }
 
+/// Returns the entries of the index that can be installed on this system, newest first
+fn available_entries(url: &str) -> Fallible<Vec<NodeEntry>> {
+    let index: NodeIndex = resolve_node_versions(url)?.into();
+    // The unofficial builds don't cover every platform for every version, so only versions with a
+    // build for this system are considered
+    let platform = if uses_unofficial_builds()? {
+        Some(distro_platform())
+    } else {
+        None
+    };
+    Ok(index
+        .entries
+        .into_iter()
+        .filter(|entry| match platform {
+            Some(ref platform) => entry.files.files.contains(platform),
+            None => true,
+        })
+        .collect())
+}
+
+/// The number of LTS lines offered when choosing a Node version interactively
+const CHOICE_LTS_LINES: usize = 4;
+
+/// The number of other recent releases offered when choosing a Node version interactively
+const CHOICE_RECENT_RELEASES: usize = 3;
+
+/// A Node version offered when choosing a version interactively
+pub struct NodeChoice {
+    pub version: Version,
+    pub lts: bool,
+}
+
+impl fmt::Display for NodeChoice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.lts {
+            write!(f, "{} (LTS)", self.version)
+        } else {
+            write!(f, "{}", self.version)
+        }
+    }
+}
+
+/// Returns the Node versions to choose from when `volta install node` is run interactively
+///
+/// The choices are the newest release of each recent LTS line, followed by the most recent
+/// releases. The first choice is the newest LTS release, which is what is installed when no
+/// version is given.
+pub fn version_choices(session: &mut Session) -> Fallible<Vec<NodeChoice>> {
+    let url = match session.hooks()?.node() {
+        Some(&ToolHooks {
+            index: Some(ref hook),
+            ..
+        }) => {
+            debug!("Using node.index hook to determine node index URL");
+            hook.resolve("index.json")?
+        }
+        _ => node_version_index()?,
+    };
+    Ok(choices(available_entries(&url)?))
+}
+
+fn choices(entries: Vec<NodeEntry>) -> Vec<NodeChoice> {
+    let mut lts_lines: Vec<NodeChoice> = Vec::new();
+    let mut recent = Vec::new();
+    for NodeEntry { version, lts, .. } in entries {
+        if lts {
+            let new_line = lts_lines
+                .iter()
+                .all(|choice| choice.version.major != version.major);
+            if new_line && lts_lines.len() < CHOICE_LTS_LINES {
+                lts_lines.push(NodeChoice { version, lts });
+                continue;
+            }
+        }
+        if recent.len() < CHOICE_RECENT_RELEASES {
+            recent.push(NodeChoice { version, lts });
+        }
+    }
+
+    lts_lines.extend(recent);
+    lts_lines
+}
+
 /// The index of the public Node server.
 pub struct NodeIndex {
     pub(super) entries: Vec<NodeEntry>,
@@ -208,6 +358,8 @@ pub struct NodeEntry {
     pub npm: Version,
     pub files: NodeDistroFiles,
     pub lts: bool,
+    /// Whether the release fixes security vulnerabilities
+    pub security: bool,
 }
 
 /// The set of available files on the public Node server for a given Node version.
@@ -216,34 +368,115 @@ pub struct NodeDistroFiles {
     pub files: HashSet<String>,
 }
 
+/// Returns the Node versions listed in the cached public index, for shell completions.
+///
+/// Completions need to be fast, so the network is never used, which also keeps them working in
+/// offline mode. An expired index is still used, and there are no versions until another command
+/// has fetched the index.
+pub fn cached_versions() -> Fallible<Vec<Version>> {
+    let versions = read_cached_index()?
+        .map(|raw| {
+            NodeIndex::from(raw)
+                .entries
+                .into_iter()
+                .map(|entry| entry.version)
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(versions)
+}
+
+/// Returns the versions in the index, newest first, using the cached index if it is current.
+pub(crate) fn index_versions(session: &Session) -> Fallible<Vec<Version>> {
+    let url = node_index_url(session)?;
+    let index = NodeIndex::from(resolve_node_versions(&url)?);
+    Ok(index
+        .entries
+        .into_iter()
+        .map(|entry| entry.version)
+        .collect())
+}
+
+/// Returns the security releases listed in the cached public index, for the update notices.
+///
+/// Like the completions, the notices never use the network, so an expired index is still used.
+pub(crate) fn cached_security_releases() -> Fallible<Vec<Version>> {
+    let versions = read_cached_index()?
+        .map(|raw| {
+            NodeIndex::from(raw)
+                .entries
+                .into_iter()
+                .filter(|entry| entry.security)
+                .map(|entry| entry.version)
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(versions)
+}
+
+/// Whether the cached index lists a build of the version for this system, or `None` if the index
+/// can't tell, so that Node is only built from source when there is no prebuilt Node to fetch.
+///
+/// Only the unofficial index is consulted: the official index names its files differently on
+/// macOS and Windows (like `osx-x64-tar`), and it lists every system it has builds for anyway.
+pub(crate) fn cached_build_listed(version: &Version) -> Fallible<Option<bool>> {
+    if !uses_unofficial_builds()? {
+        return Ok(None);
+    }
+
+    Ok(read_cached_index()?
+        .and_then(|raw| build_listed(&NodeIndex::from(raw), version, &distro_platform())))
+}
+
+fn build_listed(index: &NodeIndex, version: &Version, platform: &str) -> Option<bool> {
+    index
+        .entries
+        .iter()
+        .find(|entry| entry.version == *version)
+        .map(|entry| entry.files.files.contains(platform))
+}
+
 /// Reads a public index from the Node cache, if it exists and hasn't expired.
+///
+/// In offline mode, an expired index is still better than none, so the expiry is ignored.
 fn read_cached_opt() -> Fallible<Option<serial::RawNodeIndex>> {
-    let expiry_file = volta_home()?.node_index_expiry_file();
+    if settings()?.offline || is_current(&volta_home()?.node_index_expiry_file())? {
+        return read_cached_index();
+    }
+
+    Ok(None)
+}
+
+/// Whether a file in the Node cache hasn't expired yet, according to its expiry file.
+pub(super) fn is_current(expiry_file: &Path) -> Fallible<bool> {
     let expiry =
         read_file(&expiry_file).with_context(|_| ErrorDetails::ReadNodeIndexExpiryError {
             file: expiry_file.to_owned(),
         })?;
 
-    if let Some(string) = expiry {
-        let expiry_date = HttpDate::from_str(&string)
-            .with_context(|_| ErrorDetails::ParseNodeIndexExpiryError)?;
-        let current_date = HttpDate::from(SystemTime::now());
-
-        if current_date < expiry_date {
-            let index_file = volta_home()?.node_index_file();
-            let cached =
-                read_file(&index_file).with_context(|_| ErrorDetails::ReadNodeIndexCacheError {
-                    file: index_file.to_owned(),
-                })?;
-
-            if let Some(string) = cached {
-                return serde_json::de::from_str(&string)
-                    .with_context(|_| ErrorDetails::ParseNodeIndexCacheError);
-            }
+    match expiry {
+        Some(string) => {
+            let expiry_date = HttpDate::from_str(&string)
+                .with_context(|_| ErrorDetails::ParseNodeIndexExpiryError)?;
+            Ok(HttpDate::from(SystemTime::now()) < expiry_date)
         }
+        None => Ok(false),
     }
+}
 
-    Ok(None)
+fn read_cached_index() -> Fallible<Option<serial::RawNodeIndex>> {
+    let index_file = volta_home()?.node_index_file();
+    let cached =
+        read_file(&index_file).with_context(|_| ErrorDetails::ReadNodeIndexCacheError {
+            file: index_file.to_owned(),
+        })?;
+
+    match cached {
+        Some(string) => serde_json::de::from_str(&string)
+            .map(Some)
+            .with_context(|_| ErrorDetails::ParseNodeIndexCacheError),
+        None => Ok(None),
+    }
 }
 
 /// Get the cache max-age of an HTTP reponse.
@@ -264,10 +497,37 @@ fn resolve_node_versions(url: &str) -> Fallible<serial::RawNodeIndex> {
     match read_cached_opt()? {
         Some(serial) => Ok(serial),
         None => {
+            check_online(url)?;
             let spinner = progress_spinner(&format!("Fetching public registry: {}", url));
 
-            let mut response: reqwest::Response =
-                reqwest::get(url).with_context(registry_fetch_error("Node", url))?;
+            let mut request = http_client().get(url);
+            let validators = read_cached_validators(url);
+            if let Some(ref etag) = validators.etag {
+                request = request.header(IF_NONE_MATCH, etag.as_str());
+            }
+            if let Some(ref last_modified) = validators.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified.as_str());
+            }
+
+            let mut response: reqwest::Response = request
+                .send()
+                .with_context(registry_fetch_error("Node", url))?;
+
+            // The cached index is still current, so only its expiry needs to be updated
+            if response.status() == StatusCode::NOT_MODIFIED {
+                if let Some(index) = read_cached_index()? {
+                    debug!("Cached Node index has not been modified");
+                    write_expiry(&volta_home()?.node_index_expiry_file(), &response)?;
+                    spinner.finish_and_clear();
+                    return Ok(index);
+                }
+
+                response = http_client()
+                    .get(url)
+                    .send()
+                    .with_context(registry_fetch_error("Node", url))?;
+            }
+
             let response_text = response
                 .text()
                 .with_context(registry_fetch_error("Node", url))?;
@@ -276,57 +536,170 @@ fn resolve_node_versions(url: &str) -> Fallible<serial::RawNodeIndex> {
                     from_url: url.to_string(),
                 })?;
 
-            let cached = create_staging_file()?;
-
-            let mut cached_file: &File = cached.as_file();
-            cached_file
-                .write(response_text.as_bytes())
-                .with_context(|_| ErrorDetails::WriteNodeIndexCacheError {
-                    file: cached.path().to_path_buf(),
-                })?;
-
             let index_cache_file = volta_home()?.node_index_file();
-            ensure_containing_dir_exists(&index_cache_file).with_context(|_| {
-                ErrorDetails::ContainingDirError {
-                    path: index_cache_file.to_owned(),
-                }
-            })?;
-            cached.persist(&index_cache_file).with_context(|_| {
-                ErrorDetails::WriteNodeIndexCacheError {
-                    file: index_cache_file.to_owned(),
-                }
+            write_cache_file(&index_cache_file, response_text.as_bytes(), |file| {
+                ErrorDetails::WriteNodeIndexCacheError { file }
             })?;
 
-            let expiry = create_staging_file()?;
-            let mut expiry_file: &File = expiry.as_file();
-
-            let result = if let Some(expires_header) = response.headers().get_011::<Expires>() {
-                write!(expiry_file, "{}", expires_header)
-            } else {
-                let expiry_date =
-                    SystemTime::now() + Duration::from_secs(max_age(&response).into());
-
-                write!(expiry_file, "{}", HttpDate::from(expiry_date))
+            let validators = serial::RawIndexValidators {
+                url: Some(url.to_string()),
+                etag: header_string(&response, ETAG),
+                last_modified: header_string(&response, LAST_MODIFIED),
             };
-
-            result.with_context(|_| ErrorDetails::WriteNodeIndexExpiryError {
-                file: expiry.path().to_path_buf(),
+            let validators_file = volta_home()?.node_index_validators_file();
+            let validators_json = serde_json::to_vec(&validators).unwrap_or_default();
+            write_cache_file(&validators_file, &validators_json, |file| {
+                ErrorDetails::WriteNodeIndexCacheError { file }
             })?;
 
-            let index_expiry_file = volta_home()?.node_index_expiry_file();
-            ensure_containing_dir_exists(&index_expiry_file).with_context(|_| {
-                ErrorDetails::ContainingDirError {
-                    path: index_expiry_file.to_owned(),
-                }
-            })?;
-            expiry.persist(&index_expiry_file).with_context(|_| {
-                ErrorDetails::WriteNodeIndexExpiryError {
-                    file: index_expiry_file.to_owned(),
-                }
-            })?;
+            write_expiry(&volta_home()?.node_index_expiry_file(), &response)?;
 
             spinner.finish_and_clear();
             Ok(index)
         }
     }
 }
+
+/// Reads the HTTP validators for the cached index, which are empty if there aren't any for `url`.
+fn read_cached_validators(url: &str) -> serial::RawIndexValidators {
+    volta_home()
+        .ok()
+        .and_then(|home| read_file(home.node_index_validators_file()).ok())
+        .and_then(|contents| contents)
+        .map(|contents| validators_for(&contents, url))
+        .unwrap_or_default()
+}
+
+/// Parses the validators file, keeping the validators only if they were received from `url`.
+fn validators_for(contents: &str, url: &str) -> serial::RawIndexValidators {
+    serde_json::de::from_str::<serial::RawIndexValidators>(contents)
+        .ok()
+        .filter(|validators| validators.url.as_ref().map(String::as_str) == Some(url))
+        .unwrap_or_default()
+}
+
+fn header_string(response: &reqwest::Response, name: HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+/// Writes the expiry of a file in the Node cache, from the `nodeIndexMaxAge` setting if there is
+/// one, or from the cache headers of the response.
+pub(super) fn write_expiry(expiry_file: &Path, response: &reqwest::Response) -> Fallible<()> {
+    let expiry = match settings()?.node_index_max_age {
+        Some(max_age) => HttpDate::from(SystemTime::now() + Duration::from_secs(max_age)),
+        None => match response.headers().get_011::<Expires>() {
+            Some(expires_header) => expires_header.0,
+            None => {
+                HttpDate::from(SystemTime::now() + Duration::from_secs(max_age(response).into()))
+            }
+        },
+    };
+
+    write_cache_file(expiry_file, expiry.to_string().as_bytes(), |file| {
+        ErrorDetails::WriteNodeIndexExpiryError { file }
+    })
+}
+
+/// Writes a file in the Node cache through a staging file, so that it is never partially written.
+pub(super) fn write_cache_file<F>(dest: &Path, contents: &[u8], error: F) -> Fallible<()>
+where
+    F: Fn(PathBuf) -> ErrorDetails,
+{
+    let staged = create_staging_file()?;
+    let mut staged_file: &File = staged.as_file();
+    staged_file
+        .write_all(contents)
+        .with_context(|_| error(staged.path().to_path_buf()))?;
+
+    ensure_containing_dir_exists(&dest).with_context(|_| ErrorDetails::ContainingDirError {
+        path: dest.to_owned(),
+    })?;
+    staged
+        .persist(&dest)
+        .with_context(|_| error(dest.to_owned()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(version: &str, lts: bool) -> NodeEntry {
+        NodeEntry {
+            version: Version::parse(version).unwrap(),
+            npm: Version::parse("6.14.8").unwrap(),
+            files: NodeDistroFiles {
+                files: HashSet::new(),
+            },
+            lts,
+            security: false,
+        }
+    }
+
+    #[test]
+    fn test_choices() {
+        let entries = vec![
+            entry("15.0.1", false),
+            entry("15.0.0", false),
+            entry("14.15.0", true),
+            entry("14.14.0", false),
+            entry("14.13.0", false),
+            entry("12.19.0", true),
+            entry("12.18.4", true),
+        ];
+        let versions: Vec<String> = choices(entries)
+            .iter()
+            .map(|choice| choice.to_string())
+            .collect();
+
+        assert_eq!(
+            versions,
+            vec![
+                "14.15.0 (LTS)",
+                "12.19.0 (LTS)",
+                "15.0.1",
+                "15.0.0",
+                "14.14.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_listed() {
+        let mut with_build = entry("14.15.0", true);
+        with_build.files.files.insert("linux-x64-musl".to_string());
+        let index = NodeIndex {
+            entries: vec![with_build, entry("14.14.0", false)],
+        };
+
+        let listed = |version: &str| {
+            build_listed(&index, &Version::parse(version).unwrap(), "linux-x64-musl")
+        };
+        assert_eq!(listed("14.15.0"), Some(true));
+        assert_eq!(listed("14.14.0"), Some(false));
+        assert_eq!(listed("14.13.0"), None);
+    }
+
+    #[test]
+    fn test_validators_for() {
+        let contents =
+            r#"{"url":"https://nodejs.org/dist/index.json","etag":"\"5fa1\"","lastModified":null}"#;
+
+        let validators = validators_for(contents, "https://nodejs.org/dist/index.json");
+        assert_eq!(validators.etag, Some("\"5fa1\"".to_string()));
+
+        let validators = validators_for(contents, "https://mirror.example.com/index.json");
+        assert_eq!(validators.etag, None);
+
+        // Validators saved before they were keyed by URL are never sent
+        let validators = validators_for(
+            r#"{"etag":"\"5fa1\""}"#,
+            "https://nodejs.org/dist/index.json",
+        );
+        assert_eq!(validators.etag, None);
+    }
+}
@@ -0,0 +1,216 @@
+//! Provides the release schedule of the Node release lines, which tells when each line reaches
+//! its end of life and stops receiving security fixes.
+//!
+//! The schedule is cached in the Node cache next to the version index, and refreshed the same
+//! way: it is only fetched again once its expiry has passed, and the cached copy is used as is
+//! when Volta is offline.
+
+use std::collections::HashMap;
+
+use super::resolve::{is_current, write_cache_file, write_expiry};
+use crate::error::ErrorDetails;
+use crate::fs::read_file;
+use crate::layout::volta_home;
+use crate::settings::{check_online, settings};
+use crate::style::{progress_spinner, tool_version};
+use archive::http_client;
+use cfg_if::cfg_if;
+use chrono::{Local, NaiveDate};
+use log::{debug, warn};
+use semver::Version;
+use serde::Deserialize;
+use volta_fail::{Fallible, ResultExt};
+
+cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        fn schedule_url() -> String {
+            format!("{}/node-release-schedule.json", mockito::SERVER_URL)
+        }
+    } else {
+        /// Returns the URL of the release schedule of the Node release lines
+        fn schedule_url() -> String {
+            "https://raw.githubusercontent.com/nodejs/Release/main/schedule.json".to_string()
+        }
+    }
+}
+
+/// The schedule of a release line, of which only the end of life matters here
+#[derive(Deserialize)]
+struct RawLine {
+    end: String,
+}
+
+/// The ends of life of the Node release lines, by the key of the line in the schedule
+#[derive(Debug, Default)]
+pub struct Schedule {
+    ends: HashMap<String, NaiveDate>,
+}
+
+impl Schedule {
+    fn from_raw(raw: HashMap<String, RawLine>) -> Self {
+        let ends = raw
+            .into_iter()
+            .filter_map(|(key, line)| {
+                NaiveDate::parse_from_str(&line.end, "%Y-%m-%d")
+                    .ok()
+                    .map(|end| (key, end))
+            })
+            .collect();
+        Schedule { ends }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn from_ends(ends: Vec<(&str, NaiveDate)>) -> Self {
+        Schedule {
+            ends: ends
+                .into_iter()
+                .map(|(key, end)| (key.to_string(), end))
+                .collect(),
+        }
+    }
+
+    /// Returns the end of life of the line of `version`, if the line has reached it by `today`
+    pub fn end_of_life(&self, version: &Version, today: NaiveDate) -> Option<NaiveDate> {
+        let (key, _) = release_line(version);
+        self.ends.get(&key).cloned().filter(|end| *end <= today)
+    }
+
+    /// Returns the end of life of the line of `version`, if the line has already reached it
+    pub fn end_of_life_now(&self, version: &Version) -> Option<NaiveDate> {
+        self.end_of_life(version, Local::today().naive_local())
+    }
+}
+
+/// Returns the key of the release line of a version in the schedule, and the name of the line
+///
+/// Before Node 4, the release lines were the minor versions, such as `0.12`.
+pub fn release_line(version: &Version) -> (String, String) {
+    let line = if version.major == 0 {
+        format!("0.{}", version.minor)
+    } else {
+        version.major.to_string()
+    };
+    (format!("v{}", line), line)
+}
+
+/// Returns the release schedule, from the cache if it is still current, or from the network
+pub(crate) fn release_schedule() -> Fallible<Schedule> {
+    let home = volta_home()?;
+    if settings()?.offline || is_current(home.node_schedule_expiry_file())? {
+        if let Some(schedule) = read_cached_schedule() {
+            return Ok(schedule);
+        }
+    }
+
+    let url = schedule_url();
+    check_online(&url)?;
+    let spinner = progress_spinner(&format!("Fetching Node release schedule: {}", url));
+    let mut response = http_client()
+        .get(&url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .with_context(|_| ErrorDetails::NodeScheduleFetchError {
+            from_url: url.clone(),
+        })?;
+    let response_text = response
+        .text()
+        .with_context(|_| ErrorDetails::NodeScheduleFetchError {
+            from_url: url.clone(),
+        })?;
+    let raw: HashMap<String, RawLine> =
+        serde_json::de::from_str(&response_text).with_context(|_| {
+            ErrorDetails::ParseNodeScheduleError {
+                from_url: url.clone(),
+            }
+        })?;
+
+    write_cache_file(
+        home.node_schedule_file(),
+        response_text.as_bytes(),
+        |file| ErrorDetails::WriteNodeIndexCacheError { file },
+    )?;
+    write_expiry(home.node_schedule_expiry_file(), &response)?;
+
+    spinner.finish_and_clear();
+    Ok(Schedule::from_raw(raw))
+}
+
+/// Returns the cached release schedule, which is empty if it was never fetched
+///
+/// This never uses the network, so that listing the toolchain stays quick.
+pub fn cached_release_schedule() -> Schedule {
+    read_cached_schedule().unwrap_or_default()
+}
+
+fn read_cached_schedule() -> Option<Schedule> {
+    let schedule_file = volta_home().ok()?.node_schedule_file();
+    let contents = read_file(schedule_file).ok()??;
+    serde_json::de::from_str(&contents)
+        .ok()
+        .map(Schedule::from_raw)
+}
+
+/// Shows a warning if the line of `version` has reached its end of life
+///
+/// Failures are only logged, since the schedule is only needed for the warning.
+pub(crate) fn warn_end_of_life(version: &Version) {
+    match release_schedule() {
+        Ok(schedule) => {
+            if let Some(end) = schedule.end_of_life_now(version) {
+                warn!("{}", end_of_life_message(version, end));
+            }
+        }
+        Err(error) => debug!("Could not check the Node release schedule: {}", error),
+    }
+}
+
+/// Returns the message about a version whose line has reached its end of life
+pub(crate) fn end_of_life_message(version: &Version, end: NaiveDate) -> String {
+    format!(
+        "{} is in the Node {} line, which reached its end of life on {} and no longer receives security fixes",
+        tool_version("node", version),
+        release_line(version).1,
+        end
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    #[test]
+    fn test_release_line() {
+        assert_eq!(
+            release_line(&version("0.12.18")),
+            ("v0.12".to_string(), "0.12".to_string())
+        );
+        assert_eq!(
+            release_line(&version("14.15.4")),
+            ("v14".to_string(), "14".to_string())
+        );
+    }
+
+    #[test]
+    fn test_end_of_life() {
+        let raw: HashMap<String, RawLine> = serde_json::de::from_str(
+            r#"{
+                "v0.12": { "start": "2015-02-06", "end": "2016-12-31" },
+                "v14": { "start": "2020-04-21", "lts": "2020-10-27", "end": "2023-04-30" }
+            }"#,
+        )
+        .unwrap();
+        let schedule = Schedule::from_raw(raw);
+        let today = NaiveDate::from_ymd(2022, 1, 1);
+
+        assert_eq!(
+            schedule.end_of_life(&version("0.12.18"), today),
+            Some(NaiveDate::from_ymd(2016, 12, 31))
+        );
+        assert_eq!(schedule.end_of_life(&version("14.15.4"), today), None);
+        assert_eq!(schedule.end_of_life(&version("16.0.0"), today), None);
+    }
+}
@@ -4,11 +4,23 @@ use std::iter::FromIterator;
 use super::resolve::{NodeDistroFiles, NodeEntry, NodeIndex};
 use crate::version::{option_version_serde, version_serde};
 use semver::Version;
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Deserialize)]
 pub struct RawNodeIndex(Vec<RawNodeEntry>);
 
+/// The HTTP validators of the cached index, used to ask the server whether it has changed
+///
+/// They are only valid for the URL that they were received from, since a mirror may use the
+/// same validators for a different index.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawIndexValidators {
+    pub url: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct RawNodeEntry {
     #[serde(with = "version_serde")]
@@ -19,6 +31,8 @@ pub struct RawNodeEntry {
     pub files: Vec<String>,
     #[serde(deserialize_with = "lts_version_serde")]
     pub lts: bool,
+    #[serde(default)]
+    pub security: bool,
 }
 
 impl From<RawNodeIndex> for NodeIndex {
@@ -34,6 +48,7 @@ impl From<RawNodeIndex> for NodeIndex {
                     npm,
                     files: data,
                     lts: entry.lts,
+                    security: entry.security,
                 });
             }
         }
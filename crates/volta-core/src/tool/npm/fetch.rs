@@ -0,0 +1,263 @@
+//! Provides fetcher for npm distributions from the registry
+
+#[cfg(unix)]
+use std::fs::remove_file;
+#[cfg(windows)]
+use std::fs::write;
+use std::fs::{rename, File};
+use std::path::{Path, PathBuf};
+
+use super::super::prefetch::RemoteArchive;
+use super::super::{
+    check_unpack_space, copy_shared_archive, download_archive, download_tool_error,
+    fetched_while_waiting, verify_locked_archive,
+};
+use super::Npm;
+use crate::checksum::fetch_registry_checksum;
+use crate::error::ErrorDetails;
+#[cfg(windows)]
+use crate::fs::write_file_error;
+use crate::fs::{clear_quarantine, create_staging_dir, create_staging_file};
+#[cfg(unix)]
+use crate::fs::{delete_file_error, set_executable_permissions, symlink_error, symlink_file};
+use crate::hook::ToolHooks;
+use crate::layout::{long_path, volta_home};
+use crate::settings::check_online;
+use crate::style::{progress_bar, tool_version};
+use crate::sync::VoltaLock;
+use crate::tool;
+use crate::version::VersionSpec;
+use archive::{Archive, Tarball};
+use cfg_if::cfg_if;
+use fs_utils::ensure_containing_dir_exists;
+use log::debug;
+use semver::Version;
+use volta_fail::{Fallible, ResultExt};
+
+cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        fn public_npm_server_root() -> String {
+            format!("{}/npm", mockito::SERVER_URL)
+        }
+    } else {
+        fn public_npm_server_root() -> String {
+            "https://registry.npmjs.org/npm/-".to_string()
+        }
+    }
+}
+
+/// The launchers that npm provides, along with the scripts they run
+const LAUNCHERS: [(&str, &str); 2] = [("npm", "npm-cli.js"), ("npx", "npx-cli.js")];
+
+cfg_if! {
+    if #[cfg(windows)] {
+        /// The batch files in the npm package expect to be installed next to `node.exe`, so
+        /// launchers that run the scripts with the `node` on the `PATH` are written instead
+        fn set_up_launchers(bin_dir: &Path) -> Fallible<()> {
+            for (launcher, script) in LAUNCHERS.iter() {
+                if bin_dir.join(script).is_file() {
+                    let launcher_path = bin_dir.join(format!("{}.cmd", launcher));
+                    write(
+                        &launcher_path,
+                        format!("@node \"%~dp0\\{}\" %*\r\n", script),
+                    )
+                    .with_context(write_file_error(&launcher_path))?;
+                }
+            }
+            Ok(())
+        }
+    } else {
+        /// The shell scripts in the npm package expect to be installed next to a copy of Node,
+        /// so the launchers are replaced with links to the scripts, the way the Node
+        /// distributions link them, and the scripts are made executable
+        fn set_up_launchers(bin_dir: &Path) -> Fallible<()> {
+            for (launcher, script) in LAUNCHERS.iter() {
+                let script_path = bin_dir.join(script);
+                if script_path.is_file() {
+                    set_executable_permissions(&script_path).with_context(|_| {
+                        ErrorDetails::ExecutablePermissionsError {
+                            bin: script_path.display().to_string(),
+                        }
+                    })?;
+                    let launcher_path = bin_dir.join(launcher);
+                    if launcher_path.symlink_metadata().is_ok() {
+                        remove_file(&launcher_path).with_context(delete_file_error(&launcher_path))?;
+                    }
+                    symlink_file(script, &launcher_path)
+                        .with_context(symlink_error(&launcher_path, script))?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Npm>>) -> Fallible<()> {
+    let _lock = VoltaLock::acquire()?;
+    let home = volta_home()?;
+    if fetched_while_waiting("npm", version, &home.npm_image_dir(&version.to_string())) {
+        return Ok(());
+    }
+    let npm_dir = home.npm_inventory_dir();
+    let cache_file = npm_dir.join(Npm::archive_filename(&version.to_string()));
+    copy_shared_archive("npm", version, &cache_file, || {
+        fetch_registry_checksum("npm", "npm", version)
+    })?;
+
+    verify_locked_archive("npm", version, &cache_file, || {
+        determine_remote_url(version, hooks)
+    })?;
+
+    let (archive, staging) = match load_cached_distro(&cache_file) {
+        Some(archive) => {
+            debug!(
+                "Loading {} from cached archive at '{}'",
+                tool_version("npm", &version),
+                cache_file.display(),
+            );
+            (archive, None)
+        }
+        None => {
+            let staging = create_staging_file()?;
+            let remote_url = determine_remote_url(&version, hooks)?;
+            let archive = fetch_remote_distro(&version, &remote_url, staging.path())?;
+            (archive, Some(staging))
+        }
+    };
+
+    unpack_archive(archive, version)?;
+
+    if let Some(staging_file) = staging {
+        ensure_containing_dir_exists(&cache_file).with_context(|_| {
+            ErrorDetails::ContainingDirError {
+                path: cache_file.clone(),
+            }
+        })?;
+        staging_file
+            .persist(cache_file)
+            .with_context(|_| ErrorDetails::PersistInventoryError { tool: "npm".into() })?;
+    }
+
+    Ok(())
+}
+
+/// Returns the archive of a version in the inventory, downloading it if needed
+pub fn inventory_archive(version: &Version, hooks: Option<&ToolHooks<Npm>>) -> Fallible<PathBuf> {
+    let _lock = VoltaLock::acquire()?;
+    let npm_dir = volta_home()?.npm_inventory_dir();
+    let cache_file = npm_dir.join(Npm::archive_filename(&version.to_string()));
+    copy_shared_archive("npm", version, &cache_file, || {
+        fetch_registry_checksum("npm", "npm", version)
+    })?;
+
+    if !cache_file.is_file() {
+        download_archive(
+            "npm",
+            version,
+            &determine_remote_url(version, hooks)?,
+            &cache_file,
+            None,
+        )?;
+    }
+    Ok(cache_file)
+}
+
+/// Returns where the archive of a version is downloaded from and kept in the inventory
+pub(super) fn remote_archive(
+    version: &Version,
+    hooks: Option<&ToolHooks<Npm>>,
+) -> Fallible<RemoteArchive> {
+    let npm_dir = volta_home()?.npm_inventory_dir();
+    Ok(RemoteArchive {
+        tool: "npm",
+        version: version.clone(),
+        url: determine_remote_url(version, hooks)?,
+        cache_file: npm_dir.join(Npm::archive_filename(&version.to_string())),
+    })
+}
+
+/// Unpack the npm archive into the image directory so that it is ready for use
+fn unpack_archive(archive: Box<dyn Archive>, version: &Version) -> Fallible<()> {
+    let temp = create_staging_dir()?;
+    debug!("Unpacking npm into '{}'", temp.path().display());
+
+    let size = archive
+        .uncompressed_size()
+        .unwrap_or_else(|| archive.compressed_size());
+    check_unpack_space(archive.as_ref(), temp.path())?;
+
+    let progress = progress_bar(archive.origin(), &tool_version("npm", version), size);
+    let version_string = version.to_string();
+
+    archive
+        .unpack(&long_path(temp.path()), &mut |_, read| {
+            progress.inc(read as u64);
+        })
+        .with_context(|_| ErrorDetails::UnpackArchiveError {
+            tool: "npm".into(),
+            version: version_string.clone(),
+        })?;
+
+    let dest = volta_home()?.npm_image_dir(&version_string);
+    let setup_error = || ErrorDetails::SetupToolImageError {
+        tool: "npm".into(),
+        version: version_string.clone(),
+        dir: dest.clone(),
+    };
+
+    // Packages from the npm registry are always unpacked into a `package` directory
+    let unpacked = temp.path().join("package");
+    set_up_launchers(&unpacked.join("bin"))?;
+    clear_quarantine(&unpacked);
+
+    ensure_containing_dir_exists(&dest)
+        .with_context(|_| ErrorDetails::ContainingDirError { path: dest.clone() })?;
+    rename(long_path(unpacked), long_path(&dest)).with_context(|_| setup_error())?;
+
+    progress.finish_and_clear();
+
+    // Note: We write this after the progress bar is finished to avoid display bugs with re-renders of the progress
+    debug!("Installing npm in '{}'", dest.display());
+
+    Ok(())
+}
+
+/// Return the archive if it is valid. It may have been corrupted or interrupted in the middle of
+/// downloading.
+fn load_cached_distro(file: &Path) -> Option<Box<dyn Archive>> {
+    if file.is_file() {
+        let file = File::open(file).ok()?;
+        Tarball::load(file).ok()
+    } else {
+        None
+    }
+}
+
+/// Determine the remote URL to download from, using the hooks if available
+fn determine_remote_url(version: &Version, hooks: Option<&ToolHooks<Npm>>) -> Fallible<String> {
+    let distro_file_name = Npm::archive_filename(&version.to_string());
+    match hooks {
+        Some(&ToolHooks {
+            distro: Some(ref hook),
+            ..
+        }) => {
+            debug!("Using npm.distro hook to determine download URL");
+            hook.resolve(&version, &distro_file_name)
+        }
+        _ => Ok(format!("{}/{}", public_npm_server_root(), distro_file_name)),
+    }
+}
+
+/// Fetch the distro archive from the internet
+fn fetch_remote_distro(
+    version: &Version,
+    url: &str,
+    staging_path: &Path,
+) -> Fallible<Box<dyn Archive>> {
+    check_online(url)?;
+    debug!("Downloading {} from {}", tool_version("npm", version), url);
+    Tarball::fetch(url, staging_path).with_context(download_tool_error(
+        tool::Spec::Npm(VersionSpec::Exact(version.clone())),
+        url,
+    ))
+}
@@ -1,13 +1,29 @@
 use std::fmt::{self, Display};
+use std::path::PathBuf;
 
-use super::Tool;
+use super::prefetch::RemoteArchive;
+use super::{
+    debug_already_fetched, info_fetched, info_installed, info_pinned, info_project_version, Tool,
+};
+use crate::checksum::{fetch_registry_checksum, Checksum};
 use crate::error::ErrorDetails;
+use crate::layout::layered_path;
 use crate::session::Session;
 use crate::style::tool_version;
 use semver::Version;
 use volta_fail::Fallible;
 
-/// The Tool implementation for fetching and installing Npm (Unimplemented)
+mod fetch;
+mod resolve;
+mod serial;
+
+pub use resolve::resolve;
+
+/// The Tool implementation for fetching and installing npm
+///
+/// Every version of Node comes bundled with a version of npm. Other versions are installed from
+/// the npm registry into an image directory of their own, which is placed ahead of Node on the
+/// `PATH` when that version is selected in the platform.
 #[derive(Debug)]
 pub struct Npm {
     pub(super) version: Version,
@@ -17,27 +33,94 @@ impl Npm {
     pub fn new(version: Version) -> Self {
         Npm { version }
     }
+
+    /// The name of the package tarball, as published to the npm registry
+    pub fn archive_filename(version: &str) -> String {
+        format!("npm-{}.tgz", version)
+    }
+
+    /// Whether this version can be used without downloading it, either because it is unpacked
+    /// or because it is in the inventory
+    pub(crate) fn is_fetched(&self, session: &Session) -> Fallible<bool> {
+        let version_str = self.version.to_string();
+        if layered_path(|home| home.npm_image_dir(&version_str))?.is_dir() {
+            return Ok(true);
+        }
+
+        Ok(session.inventory()?.npm.versions.contains(&self.version))
+    }
+
+    pub(crate) fn fetch_internal(&self, session: &mut Session) -> Fallible<()> {
+        let version_str = self.version.to_string();
+        if layered_path(|home| home.npm_image_dir(&version_str))?.is_dir() {
+            debug_already_fetched(self);
+            return Ok(());
+        }
+
+        fetch::fetch(&self.version, session.hooks()?.npm())?;
+        session
+            .inventory_mut()?
+            .npm
+            .versions
+            .insert(self.version.clone());
+
+        Ok(())
+    }
+
+    /// Returns the archive of this version in the inventory, downloading it if needed, so that
+    /// its checksum can be recorded in a lockfile
+    pub(crate) fn inventory_archive(&self, session: &Session) -> Fallible<PathBuf> {
+        fetch::inventory_archive(&self.version, session.hooks()?.npm())
+    }
+    /// Fetches the checksum that the npm registry publishes for the archive of this version
+    pub(crate) fn published_checksum(&self) -> Fallible<Option<Checksum>> {
+        fetch_registry_checksum("npm", "npm", &self.version)
+    }
+
+    /// Returns the archive that fetching this version would download, unless the version is
+    /// already fetched
+    pub(crate) fn remote_archive(&self, session: &Session) -> Fallible<Option<RemoteArchive>> {
+        if self.is_fetched(session)? {
+            return Ok(None);
+        }
+        fetch::remote_archive(&self.version, session.hooks()?.npm()).map(Some)
+    }
 }
 
 impl Tool for Npm {
-    // ISSUE(#292) Implement actions for npm
-    fn fetch(self, _session: &mut Session) -> Fallible<()> {
-        Err(ErrorDetails::Unimplemented {
-            feature: "Fetching npm".into(),
-        }
-        .into())
+    fn fetch(self, session: &mut Session) -> Fallible<()> {
+        self.fetch_internal(session)?;
+
+        info_fetched(self);
+        Ok(())
     }
-    fn install(self, _session: &mut Session) -> Fallible<()> {
-        Err(ErrorDetails::Unimplemented {
-            feature: "Installing npm".into(),
+    fn install(self, session: &mut Session) -> Fallible<()> {
+        self.fetch_internal(session)?;
+
+        session.toolchain_mut()?.set_active_npm(&self.version)?;
+
+        info_installed(self);
+
+        if let Ok(Some(project)) = session.project_platform() {
+            if let Some(npm) = &project.npm {
+                info_project_version(tool_version("npm", npm));
+            }
         }
-        .into())
+        Ok(())
     }
-    fn pin(self, _session: &mut Session) -> Fallible<()> {
-        Err(ErrorDetails::Unimplemented {
-            feature: "Pinning npm".into(),
+    fn pin(self, session: &mut Session) -> Fallible<()> {
+        if session.project()?.is_some() {
+            self.fetch_internal(session)?;
+
+            // Note: We know this will succeed, since we checked above
+            let project = session.project_mut()?.unwrap();
+            project.pin_npm(&self.version)?;
+
+            info_pinned(self);
+            Ok(())
+        } else {
+            Err(ErrorDetails::NotInPackage.into())
         }
-        .into())
     }
 }
 
@@ -46,3 +129,13 @@ impl Display for Npm {
         f.write_str(&tool_version("npm", &self.version))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_npm_archive_filename() {
+        assert_eq!(Npm::archive_filename("10.2.0"), "npm-10.2.0.tgz");
+    }
+}
@@ -0,0 +1,147 @@
+//! Provides resolution of npm requirements into specific versions
+
+use std::collections::{BTreeSet, HashMap};
+
+use super::super::registry_fetch_error;
+use super::serial;
+use crate::error::ErrorDetails;
+use crate::hook::tool::IndexFormat;
+use crate::hook::ToolHooks;
+use crate::session::Session;
+use crate::settings::check_online;
+use crate::style::progress_spinner;
+use crate::tool::Npm;
+use crate::version::{parse_version, VersionSpec, VersionTag};
+use archive::http_client;
+use cfg_if::cfg_if;
+use log::debug;
+use semver::{Version, VersionReq};
+use volta_fail::{Fallible, ResultExt};
+
+cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        fn public_npm_version_index() -> String {
+            format!("{}/registry/npm", mockito::SERVER_URL)
+        }
+    } else {
+        /// URL of the npm registry document for npm itself
+        fn public_npm_version_index() -> String {
+            "https://registry.npmjs.org/npm".to_string()
+        }
+    }
+}
+
+pub fn resolve(matching: VersionSpec, session: &mut Session) -> Fallible<Version> {
+    let hooks = session.hooks()?.npm();
+    match matching {
+        VersionSpec::Exact(version) => Ok(version),
+        VersionSpec::Semver(requirement) => resolve_semver(requirement, hooks),
+        VersionSpec::None | VersionSpec::Tag(VersionTag::Latest) => resolve_latest(hooks),
+        VersionSpec::Tag(VersionTag::Custom(tag)) => resolve_tag(&tag, hooks),
+        VersionSpec::Tag(tag) => Err(ErrorDetails::NpmVersionNotFound {
+            matching: tag.to_string(),
+        }
+        .into()),
+    }
+}
+
+fn resolve_latest(hooks: Option<&ToolHooks<Npm>>) -> Fallible<Version> {
+    if let Some(&ToolHooks {
+        latest: Some(ref hook),
+        ..
+    }) = hooks
+    {
+        debug!("Using npm.latest hook to determine latest-version URL");
+        let url = hook.resolve("latest-version")?;
+        check_online(&url)?;
+        let response_text = http_client()
+            .get(&url)
+            .send()
+            .and_then(|mut resp| resp.text())
+            .with_context(registry_fetch_error("npm", &url))?;
+
+        debug!("Found npm latest version ({}) from {}", response_text, url);
+        return parse_version(response_text.trim());
+    }
+
+    resolve_tag("latest", hooks)
+}
+
+/// Resolves a dist-tag, such as `latest` or `next-9`, from the registry
+fn resolve_tag(tag: &str, hooks: Option<&ToolHooks<Npm>>) -> Fallible<Version> {
+    let (url, index) = fetch_npm_index(hooks)?;
+
+    match index.tags.get(tag) {
+        Some(version) => {
+            debug!("Found npm@{} matching tag '{}' from {}", version, tag, url);
+            parse_version(version)
+        }
+        None => Err(ErrorDetails::NpmVersionNotFound {
+            matching: tag.into(),
+        }
+        .into()),
+    }
+}
+
+fn resolve_semver(matching: VersionReq, hooks: Option<&ToolHooks<Npm>>) -> Fallible<Version> {
+    let (url, index) = fetch_npm_index(hooks)?;
+
+    match index
+        .entries
+        .into_iter()
+        .rev()
+        .find(|v| matching.matches(v))
+    {
+        Some(version) => {
+            debug!(
+                "Found npm@{} matching requirement '{}' from {}",
+                version, matching, url
+            );
+            Ok(version)
+        }
+        None => Err(ErrorDetails::NpmVersionNotFound {
+            matching: matching.to_string(),
+        }
+        .into()),
+    }
+}
+
+fn fetch_npm_index(hooks: Option<&ToolHooks<Npm>>) -> Fallible<(String, NpmIndex)> {
+    let (url, format) = match hooks {
+        Some(&ToolHooks {
+            index: Some(ref hook),
+            index_format,
+            ..
+        }) => {
+            debug!("Using npm.index hook to determine npm index URL");
+            (
+                hook.resolve("index.json")?,
+                index_format.unwrap_or(IndexFormat::Npm),
+            )
+        }
+        _ => (public_npm_version_index(), IndexFormat::Npm),
+    };
+
+    check_online(&url)?;
+    let spinner = progress_spinner(&format!("Fetching public registry: {}", url));
+    let mut response = http_client()
+        .get(&url)
+        .send()
+        .with_context(registry_fetch_error("npm", &url))?;
+    let index = match format {
+        IndexFormat::Npm => response.json::<serial::RawNpmIndex>().map(NpmIndex::from),
+        IndexFormat::Github => response
+            .json::<serial::RawNpmGithubIndex>()
+            .map(NpmIndex::from),
+    }
+    .with_context(registry_fetch_error("npm", &url))?;
+    spinner.finish_and_clear();
+
+    Ok((url, index))
+}
+
+/// The public npm index.
+pub struct NpmIndex {
+    pub(super) entries: BTreeSet<Version>,
+    pub(super) tags: HashMap<String, String>,
+}
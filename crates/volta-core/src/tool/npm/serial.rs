@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use super::resolve::NpmIndex;
+use semver::Version;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+/// The npm registry's package document for npm itself. Only the keys of the `versions` map and
+/// the `dist-tags` are needed.
+#[derive(Deserialize)]
+pub struct RawNpmIndex {
+    pub versions: HashMap<String, IgnoredAny>,
+    #[serde(default, rename = "dist-tags")]
+    pub dist_tags: HashMap<String, String>,
+}
+
+impl From<RawNpmIndex> for NpmIndex {
+    fn from(raw: RawNpmIndex) -> NpmIndex {
+        let entries = raw
+            .versions
+            .keys()
+            .filter_map(|version| Version::parse(version).ok())
+            .collect();
+        NpmIndex {
+            entries,
+            tags: raw.dist_tags,
+        }
+    }
+}
+
+/// A list of npm releases in the format of the GitHub releases API, as served by an `index`
+/// hook that declares the `github` format. Only the tag names are needed.
+#[derive(Deserialize)]
+pub struct RawNpmGithubIndex(Vec<RawNpmGithubRelease>);
+
+#[derive(Deserialize)]
+pub struct RawNpmGithubRelease {
+    /// npm releases are tagged as "v$version"
+    pub tag_name: String,
+}
+
+impl From<RawNpmGithubIndex> for NpmIndex {
+    fn from(raw: RawNpmGithubIndex) -> NpmIndex {
+        let entries = raw
+            .0
+            .into_iter()
+            .filter_map(|release| Version::parse(release.tag_name.trim_start_matches('v')).ok())
+            .collect();
+        NpmIndex {
+            entries,
+            tags: HashMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_npm_index_dist_tags() {
+        let raw: RawNpmIndex = serde_json::from_str(
+            r#"{
+                "dist-tags": { "latest": "10.2.0", "next-9": "9.9.2" },
+                "versions": { "9.9.2": {}, "10.1.0": {}, "10.2.0": {}, "not-a-version": {} }
+            }"#,
+        )
+        .unwrap();
+        let index = NpmIndex::from(raw);
+
+        assert_eq!(
+            index.entries.into_iter().collect::<Vec<_>>(),
+            vec![
+                Version::parse("9.9.2").unwrap(),
+                Version::parse("10.1.0").unwrap(),
+                Version::parse("10.2.0").unwrap()
+            ]
+        );
+        assert_eq!(index.tags.get("next-9").map(String::as_str), Some("9.9.2"));
+    }
+}
@@ -2,16 +2,21 @@
 
 use std::ffi::OsString;
 use std::fs::{rename, write, File};
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{self, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 
+use super::super::check_unpack_space;
+use super::npmrc::{NpmConfig, USERCONFIG_VAR};
+use crate::checksum::Checksum;
 use crate::error::ErrorDetails;
 use crate::fs::{create_staging_dir, ensure_dir_does_not_exist, read_dir_eager, read_file};
-use crate::layout::volta_home;
+use crate::layout::{long_path, volta_home};
 use crate::run::{self, ToolCommand};
 use crate::session::Session;
+use crate::settings::settings;
 use crate::style::{progress_bar, progress_spinner, tool_version};
-use crate::tool::PackageDetails;
+use crate::sync::VoltaLock;
+use crate::tool::{copy_shared_archive, fetched_while_waiting, PackageDetails};
 use archive::{Archive, Tarball};
 use fs_utils::ensure_containing_dir_exists;
 use log::debug;
@@ -20,10 +25,26 @@ use sha1::{Digest, Sha1};
 use volta_fail::{throw, Fallible, ResultExt};
 
 pub fn fetch(name: &str, details: &PackageDetails, session: &mut Session) -> Fallible<()> {
+    let _lock = VoltaLock::acquire()?;
     let version_string = details.version.to_string();
     let home = volta_home()?;
+    let image_dir = home.package_image_dir(&name, &version_string);
+    if fetched_while_waiting(name, &details.version, &image_dir) {
+        return Ok(());
+    }
     let cache_file = home.package_distro_file(&name, &version_string);
     let shasum_file = home.package_distro_shasum(&name, &version_string);
+    copy_shared_archive(name, &details.version, &cache_file, || {
+        Ok(details
+            .integrity
+            .as_ref()
+            .and_then(|integrity| Checksum::from_integrity(integrity)))
+    })?;
+    // Only the archive is taken from the shared cache, once it is verified, while the shasum
+    // that it is checked against from then on comes from the registry
+    if cache_file.is_file() && !shasum_file.is_file() {
+        write_shasum(&shasum_file, name, details)?;
+    }
 
     let (archive, cached) = match load_cached_distro(&cache_file, &shasum_file) {
         Some(archive) => {
@@ -45,29 +66,26 @@ pub fn fetch(name: &str, details: &PackageDetails, session: &mut Session) -> Fal
     if cached {
         Ok(())
     } else {
-        // Save the shasum in a file
-        write(&shasum_file, details.shasum.as_bytes()).with_context(|_| {
-            ErrorDetails::WritePackageShasumError {
-                package: name.into(),
-                version: version_string,
-                file: shasum_file,
-            }
-        })
+        write_shasum(&shasum_file, name, details)
     }
 }
 
+/// Saves the shasum of a package archive in a file next to it
+fn write_shasum(shasum_file: &Path, name: &str, details: &PackageDetails) -> Fallible<()> {
+    write(shasum_file, details.shasum.as_bytes()).with_context(|_| {
+        ErrorDetails::WritePackageShasumError {
+            package: name.into(),
+            version: details.version.to_string(),
+            file: shasum_file.to_path_buf(),
+        }
+    })
+}
+
 fn load_cached_distro(file: &Path, shasum_file: &Path) -> Option<Box<dyn Archive>> {
     let mut distro = File::open(file).ok()?;
     let stored_shasum = read_file(shasum_file).ok()??; // `??`: Err(_) *or* Ok(None) -> None
 
-    let mut buffer = Vec::new();
-    distro.read_to_end(&mut buffer).ok()?;
-
-    // calculate the shasum
-    let mut hasher = Sha1::new();
-    hasher.input(buffer);
-    let result = hasher.result();
-    let calculated_shasum = hex::encode(&result);
+    let calculated_shasum = file_shasum(&mut distro).ok()?;
 
     if stored_shasum != calculated_shasum {
         return None;
@@ -91,18 +109,49 @@ fn fetch_remote_distro(
     // error otherwise
     let dir = path.parent().unwrap();
 
-    let command = npm_pack_command_for(name, &details.version.to_string()[..], session, dir)?;
+    let display = tool_version(name, details.version.to_string());
+    let pack_spec = format!("{}@{}", name, details.version);
+    let tarball_from_npm_pack = npm_pack(&pack_spec, &display, dir, session)?;
+
+    // If `npm pack` didn't name the tarball what we expect (usually because of scoped packages),
+    // move it to where we expect it to be.
+    if tarball_from_npm_pack != path {
+        debug!(
+            "Moving the tarball from {:?} to the expected path {:?}",
+            tarball_from_npm_pack, path
+        );
+        rename(tarball_from_npm_pack, path).with_context(|_| ErrorDetails::NpmPackUnpackError {
+            package: display.clone(),
+        })?;
+    }
+
+    debug!("Attempting to load {:?}", path);
+    let distro = File::open(path).with_context(|_| ErrorDetails::NpmPackUnpackError {
+        package: display.clone(),
+    })?;
+
+    Tarball::load(distro).with_context(|_| ErrorDetails::NpmPackUnpackError { package: display })
+}
+
+/// Run `npm pack` for the given spec in `dir`, returning the path to the created tarball
+///
+/// The spec can be anything `npm pack` understands: a registry `<name>@<version>`, but also
+/// a git URL or a tarball URL.
+pub(super) fn npm_pack(
+    pack_spec: &str,
+    display: &str,
+    dir: &Path,
+    session: &mut Session,
+) -> Fallible<PathBuf> {
+    let config = NpmConfig::load(session)?;
+    let mut command = npm_pack_command_for(pack_spec, session, dir)?;
+    if let Some(userconfig) = config.userconfig() {
+        command.env(USERCONFIG_VAR, userconfig);
+    }
     debug!("Running command: `{:?}`", command);
 
-    debug!(
-        "Downloading {} via npm pack to {}",
-        tool_version(name, details.version.to_string()),
-        dir.to_str().unwrap()
-    );
-    let spinner = progress_spinner(&format!(
-        "Downloading {}",
-        tool_version(name, details.version.to_string()),
-    ));
+    debug!("Downloading {} via npm pack to {}", display, dir.display());
+    let spinner = progress_spinner(&format!("Downloading {}", display));
     let output = command.output()?;
     spinner.finish_and_clear();
 
@@ -113,62 +162,58 @@ fn fetch_remote_distro(
         );
         debug!("Exit code is {:?}", output.status.code());
         throw!(ErrorDetails::NpmPackFetchError {
-            package: tool_version(name, details.version.to_string()),
+            package: display.to_string(),
         });
     }
 
     let filename = String::from_utf8_lossy(&output.stdout);
     // The output from `npm pack` contains a newline, so we'll trim it here.
-    let trimmed_filename = filename.trim();
+    // For git dependencies it may also include the output of `prepare` scripts, so we only
+    // look at the last line, which is always the tarball name.
+    let trimmed_filename = filename.trim().lines().last().unwrap_or_default().trim();
 
     if trimmed_filename.is_empty() {
         throw!(ErrorDetails::NpmPackUnpackError {
-            package: tool_version(name, details.version.to_string())
+            package: display.to_string(),
         });
     }
 
-    let tarball_from_npm_pack = dir.join(trimmed_filename.to_string());
+    let tarball = dir.join(trimmed_filename);
 
-    if !tarball_from_npm_pack.exists() {
+    if !tarball.exists() {
         throw!(ErrorDetails::NpmPackUnpackError {
-            package: tool_version(name, details.version.to_string())
+            package: display.to_string(),
         });
     }
 
-    // If `npm pack` didn't name the tarball what we expect (usually because of scoped packages),
-    // move it to where we expect it to be.
-    if tarball_from_npm_pack != path {
-        debug!(
-            "Moving the tarball from {:?} to the expected path {:?}",
-            tarball_from_npm_pack, path
-        );
-        rename(tarball_from_npm_pack, path).with_context(|_| ErrorDetails::NpmPackUnpackError {
-            package: tool_version(name, details.version.to_string()),
-        })?;
-    }
+    Ok(tarball)
+}
 
-    debug!("Attempting to load {:?}", path);
-    let distro = File::open(path).with_context(|_| ErrorDetails::NpmPackUnpackError {
-        package: tool_version(name, details.version.to_string()),
-    })?;
+/// Calculate the shasum of a file, in the same format as the npm registry's `dist.shasum`
+pub(super) fn file_shasum(file: &mut File) -> io::Result<String> {
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)?;
 
-    Tarball::load(distro).with_context(|_| ErrorDetails::NpmPackUnpackError {
-        package: tool_version(name, details.version.to_string()),
-    })
+    let mut hasher = Sha1::new();
+    hasher.input(buffer);
+    Ok(hex::encode(&hasher.result()))
 }
 
 // build a command to run `npm pack`
 fn npm_pack_command_for(
-    name: &str,
-    version: &str,
+    pack_spec: &str,
     session: &mut Session,
     current_dir: &Path,
 ) -> Fallible<ToolCommand> {
-    let args = vec![
+    let mut args = vec![
         OsString::from("pack"),
         OsString::from("--no-update-notifier"),
-        OsString::from(format!("{}@{}", name, version)),
+        OsString::from(pack_spec),
     ];
+    args.extend(session.npm_flags().fetch_args());
+    if settings()?.offline {
+        args.push(OsString::from("--offline"));
+    }
     let mut command = run::npm::command(args, session)?;
     command.current_dir(current_dir);
     Ok(command)
@@ -178,16 +223,15 @@ fn unpack_archive(archive: Box<dyn Archive>, name: &str, version: &Version) -> F
     let temp = create_staging_dir()?;
     debug!("Unpacking {} into '{}'", name, temp.path().display());
 
-    let progress = progress_bar(
-        archive.origin(),
-        &tool_version(&name, &version),
-        archive
-            .uncompressed_size()
-            .unwrap_or_else(|| archive.compressed_size()),
-    );
+    let size = archive
+        .uncompressed_size()
+        .unwrap_or_else(|| archive.compressed_size());
+    check_unpack_space(archive.as_ref(), temp.path())?;
+
+    let progress = progress_bar(archive.origin(), &tool_version(&name, &version), size);
 
     archive
-        .unpack(temp.path(), &mut |_, read| {
+        .unpack(&long_path(temp.path()), &mut |_, read| {
             progress.inc(read as u64);
         })
         .with_context(|_| ErrorDetails::UnpackArchiveError {
@@ -206,10 +250,12 @@ fn unpack_archive(archive: Box<dyn Archive>, name: &str, version: &Version) -> F
     ensure_dir_does_not_exist(&image_dir)?;
 
     let unpack_dir = find_unpack_dir(temp.path())?;
-    rename(unpack_dir, &image_dir).with_context(|_| ErrorDetails::SetupToolImageError {
-        tool: name.into(),
-        version: version.to_string(),
-        dir: image_dir.clone(),
+    rename(long_path(unpack_dir), long_path(&image_dir)).with_context(|_| {
+        ErrorDetails::SetupToolImageError {
+            tool: name.into(),
+            version: version.to_string(),
+            dir: image_dir.clone(),
+        }
     })?;
 
     progress.finish_and_clear();
@@ -223,7 +269,7 @@ fn unpack_archive(archive: Box<dyn Archive>, name: &str, version: &Version) -> F
 /// Figure out the unpacked package directory name dynamically
 ///
 /// Packages typically extract to a "package" directory, but not always
-fn find_unpack_dir(in_dir: &Path) -> Fallible<PathBuf> {
+pub(super) fn find_unpack_dir(in_dir: &Path) -> Fallible<PathBuf> {
     let dirs: Vec<_> = read_dir_eager(in_dir)
         .with_context(|_| ErrorDetails::PackageUnpackError)?
         .collect();
@@ -0,0 +1,72 @@
+//! Provides the npm flags that `volta install` passes through to the npm commands that fetch and
+//! install packages, so that they don't need to be set through npm's environment variables.
+
+use std::ffi::OsString;
+
+use crate::settings::{settings, InstallScripts};
+use volta_fail::Fallible;
+
+/// The npm flags given on the command line, which the session holds for the packages it installs
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NpmFlags {
+    /// Whether peer dependencies are installed as npm 6 did, with `--legacy-peer-deps`
+    pub legacy_peer_deps: bool,
+    /// The registry that packages are fetched from, instead of the one from `.npmrc`
+    pub registry: Option<String>,
+    /// Whether `--no-audit` was given. The audit is always skipped, since its report is never
+    /// shown, so this is only accepted for parity with `npm install`.
+    pub no_audit: bool,
+    /// How the install scripts are run instead of the `installScripts` setting, as
+    /// `--ignore-scripts` and `--clean-env-scripts` ask for
+    pub install_scripts: Option<InstallScripts>,
+}
+
+impl NpmFlags {
+    /// Returns the flags for the commands that fetch package metadata and tarballs
+    pub(super) fn fetch_args(&self) -> Vec<OsString> {
+        self.registry
+            .iter()
+            .map(|registry| OsString::from(format!("--registry={}", registry)))
+            .collect()
+    }
+
+    /// Returns the flags for the command that installs the dependencies of a package
+    pub(super) fn install_args(&self) -> Vec<OsString> {
+        let mut args = self.fetch_args();
+        if self.legacy_peer_deps {
+            args.push(OsString::from("--legacy-peer-deps"));
+        }
+        args
+    }
+
+    /// Returns how the install scripts are run, from the flags or the settings
+    pub(super) fn install_scripts(&self) -> Fallible<InstallScripts> {
+        match self.install_scripts {
+            Some(mode) => Ok(mode),
+            None => Ok(settings()?.install_scripts),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_args() {
+        let flags = NpmFlags {
+            legacy_peer_deps: true,
+            registry: Some("https://npm.example.com/".to_string()),
+            ..NpmFlags::default()
+        };
+        assert_eq!(
+            flags.fetch_args(),
+            vec!["--registry=https://npm.example.com/"]
+        );
+        assert_eq!(
+            flags.install_args(),
+            vec!["--registry=https://npm.example.com/", "--legacy-peer-deps"]
+        );
+        assert!(NpmFlags::default().install_args().is_empty());
+    }
+}
@@ -3,28 +3,36 @@
 
 use std::collections::HashMap;
 use std::ffi::OsStr;
-use std::fs::File;
+use std::fs::{rename, File};
 use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use super::super::Spec;
 use super::bin_full_path;
+use super::flags::NpmFlags;
+use super::npmrc::{NpmConfig, USERCONFIG_VAR};
+use super::optional::check_platform_dependencies;
+use super::scripts;
 use crate::command::create_command;
 use crate::error::ErrorDetails;
+use crate::fs::{copy_dir_all, create_staging_dir, set_executable_permissions};
 use crate::layout::volta_home;
 use crate::manifest::BinManifest;
 use crate::platform::{Image, PlatformSpec};
-use crate::session::Session;
-use crate::shim;
+use crate::session::{ActivityKind, Session};
+use crate::settings::{settings, InstallScripts};
+use crate::shim::{self, ShimResult};
 use crate::style::{progress_spinner, tool_version};
+use crate::sync::VoltaLock;
 use crate::version::{parse_requirements, VersionSpec, VersionTag};
 use atty::Stream;
 use cmdline_words_parser::StrExt;
+use fs_utils::ensure_containing_dir_exists;
 use lazy_static::lazy_static;
 use log::debug;
 use regex::Regex;
-use semver::Version;
+use semver::{Version, VersionReq};
 use volta_fail::{throw, Fallible, ResultExt};
 
 lazy_static! {
@@ -68,6 +76,11 @@ pub struct PackageConfig {
     pub platform: PlatformSpec,
     /// The binaries installed by this package
     pub bins: Vec<String>,
+    /// The working copy of the package, if it was linked from a local directory
+    pub link: Option<PathBuf>,
+    /// The registry the package was fetched from, if it was given with `--registry`, which
+    /// upgrades of the package are fetched from too
+    pub registry: Option<String>,
 }
 
 /// Configuration information about an installed binary from a package.
@@ -106,6 +119,8 @@ pub struct BinConfig {
     pub platform: PlatformSpec,
     /// The loader information for the script, if any
     pub loader: Option<BinLoader>,
+    /// The working copy of the package, if it was linked from a local directory
+    pub link: Option<PathBuf>,
 }
 
 /// Information about the Shebang script loader (e.g. `#!/usr/bin/env node`)
@@ -119,64 +134,186 @@ pub struct BinLoader {
     pub args: Vec<String>,
 }
 
+/// The Windows script types that package binaries are sometimes published as, which can't be
+/// run like an executable
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScriptKind {
+    /// A `.cmd` or `.bat` batch script, run through `cmd.exe`
+    Batch,
+    /// A `.ps1` script, run through PowerShell
+    PowerShell,
+}
+
+impl ScriptKind {
+    /// Determines the kind of script from the extension of its path, if it is a script at all
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "cmd" | "bat" => Some(ScriptKind::Batch),
+            "ps1" => Some(ScriptKind::PowerShell),
+            _ => None,
+        }
+    }
+
+    /// The loader that runs a script of this kind, if it can't be run directly
+    ///
+    /// Batch scripts don't need one, since every command already runs through `cmd.exe /C`.
+    pub fn loader(self) -> Option<BinLoader> {
+        match self {
+            ScriptKind::Batch => None,
+            ScriptKind::PowerShell => Some(BinLoader {
+                command: "powershell".into(),
+                args: vec![
+                    "-NoProfile".into(),
+                    "-ExecutionPolicy".into(),
+                    "Bypass".into(),
+                    "-File".into(),
+                ],
+            }),
+        }
+    }
+}
+
+/// Installs a fetched package, with `node` if given or else the Node that `determine_node` picks
 pub fn install(
     name: &str,
     version: &Version,
+    node: Option<&Version>,
     session: &mut Session,
 ) -> Fallible<HashMap<String, String>> {
-    let package_dir = volta_home()?.package_image_dir(name, &version.to_string());
+    let _lock = VoltaLock::acquire()?;
+    let home = volta_home()?;
+    let package_dir = home.package_image_dir(name, &version.to_string());
     let bin_map = read_bins(name, version)?;
     let display = tool_version(name, version);
 
-    let engine = determine_engine(&package_dir, &display)?;
     let platform = PlatformSpec {
-        node_runtime: Spec::Node(engine).resolve(session)?.into(),
+        node_runtime: match node {
+            Some(node) => node.clone(),
+            None => determine_node(&package_dir, &display, session)?,
+        },
         npm: None,
         yarn: None,
+        pnpm: None,
     };
     let image = platform.clone().checkout(session)?;
 
-    install_dependencies(&package_dir, image, &display)?;
-    write_configs(name, version, &platform, &bin_map)?;
+    let install_dir = home.package_platform_image_dir(
+        name,
+        &version.to_string(),
+        &platform.node_runtime.to_string(),
+    );
+    if install_dir.exists() {
+        debug!(
+            "{} is already installed for node@{} in '{}'",
+            display,
+            platform.node_runtime,
+            install_dir.display()
+        );
+    } else {
+        let config = NpmConfig::load(session)?;
+        let flags = session.npm_flags();
+        install_isolated(
+            name,
+            version,
+            &package_dir,
+            &install_dir,
+            &image,
+            &config,
+            flags,
+        )?;
+    }
+    write_configs(name, version, &platform, &bin_map, None, session)?;
 
     Ok(bin_map)
 }
 
-fn determine_engine(package_dir: &Path, display: &str) -> Fallible<VersionSpec> {
+/// Determines the Node version a package is installed with, which is recorded in its configs so
+/// that its executables always run against it, regardless of later changes to the default
+///
+/// The default Node is preferred when it satisfies the package's `engines.node`, so that native
+/// addons are built for the Node the user already runs. Otherwise the newest LTS release matching
+/// `engines.node` (or simply the newest LTS release) is used.
+pub(super) fn determine_node(
+    package_dir: &Path,
+    display: &str,
+    session: &mut Session,
+) -> Fallible<Version> {
     let manifest = BinManifest::for_dir(package_dir)?;
-    // if nothing specified, use the latest version of Node
-    match manifest.engine {
+    let requirement = match manifest.engine {
         Some(engine) => {
             debug!(
                 "Found 'engines.node' specification for {}: {}",
                 display, engine
             );
-            let req = parse_requirements(engine)?;
-            Ok(VersionSpec::Tag(VersionTag::LtsRequirement(req)))
-        }
-        None => {
-            debug!("No 'engines.node' found for {}, using LTS", display);
-            Ok(VersionSpec::Tag(VersionTag::Lts))
+            Some(parse_requirements(engine)?)
         }
+        None => None,
+    };
+
+    let default = session.default_platform()?;
+    let default_node = default.as_ref().map(|platform| &platform.node_runtime);
+    if let Some(node) = satisfying_default(requirement.as_ref(), default_node) {
+        debug!("Using default node@{} for {}", node, display);
+        return Ok(node);
+    }
+
+    if requirement.is_none() {
+        debug!("No 'engines.node' found for {}, using LTS", display);
     }
+    let spec = node_spec(requirement);
+    Ok(Spec::Node(spec).resolve(session)?.into())
+}
+
+/// Returns the default Node if there is one and it satisfies the `engines.node` requirement
+fn satisfying_default(
+    requirement: Option<&VersionReq>,
+    default: Option<&Version>,
+) -> Option<Version> {
+    default
+        .filter(|node| requirement.map_or(true, |req| req.matches(node)))
+        .cloned()
 }
 
-fn write_configs(
+/// Returns the spec to resolve the Node for a package: the newest LTS release matching the
+/// `engines.node` requirement, or the newest LTS release if there is none
+fn node_spec(requirement: Option<VersionReq>) -> VersionSpec {
+    match requirement {
+        Some(req) => VersionSpec::Tag(VersionTag::LtsRequirement(req)),
+        None => VersionSpec::Tag(VersionTag::Lts),
+    }
+}
+
+/// Writes the configs of a package and its executables and creates their shims
+///
+/// `link` is the working copy of a package that is linked from a local directory.
+pub(super) fn write_configs(
     name: &str,
     version: &Version,
     platform: &PlatformSpec,
     bins: &HashMap<String, String>,
+    link: Option<&Path>,
+    session: &mut Session,
 ) -> Fallible<()> {
     super::serial::RawPackageConfig::from(PackageConfig {
         name: name.to_string(),
         version: version.clone(),
         platform: platform.clone(),
         bins: bins.keys().map(String::clone).collect(),
+        link: link.map(Path::to_owned),
+        registry: session.npm_flags().registry.clone(),
     })
     .write()?;
 
     for (bin_name, bin_path) in bins.iter() {
-        let full_path = bin_full_path(name, version, bin_name, bin_path)?;
+        let full_path = bin_full_path(
+            name,
+            version,
+            &platform.node_runtime,
+            bin_name,
+            bin_path,
+            link,
+        )?;
         let loader = determine_script_loader(bin_name, &full_path)?;
         super::serial::RawBinConfig::from(BinConfig {
             name: bin_name.clone(),
@@ -185,13 +322,22 @@ fn write_configs(
             path: bin_path.clone(),
             platform: platform.clone(),
             loader,
+            link: link.map(Path::to_owned),
         })
         .write()?;
 
         // create a link to the shim executable
-        shim::create(&bin_name)?;
+        if let ShimResult::Created = shim::create(&bin_name)? {
+            session.add_event_shim_created(ActivityKind::Install, bin_name.clone());
+        }
+        #[cfg(windows)]
+        {
+            if ScriptKind::from_path(&full_path).is_some() {
+                shim::create_script_shims(&bin_name)?;
+            }
+        }
 
-        os::set_executable_permissions(&full_path).with_context(|_| {
+        set_executable_permissions(&full_path).with_context(|_| {
             ErrorDetails::ExecutablePermissionsError {
                 bin: bin_name.clone(),
             }
@@ -201,8 +347,62 @@ fn write_configs(
     Ok(())
 }
 
-fn install_dependencies(package_dir: &Path, image: Image, display: &str) -> Fallible<()> {
-    let mut command = build_install_command(package_dir, &image.path()?);
+/// Installs the dependencies of a fetched package into its own image directory for the platform
+///
+/// The fetched package is copied to a staging directory first, so that the fetched image stays
+/// pristine and a failed install never leaves a partial image behind. Each Node version gets a
+/// separate image, since dependencies with native code are built against a specific Node.
+fn install_isolated(
+    name: &str,
+    version: &Version,
+    package_dir: &Path,
+    install_dir: &Path,
+    image: &Image,
+    config: &NpmConfig,
+    flags: &NpmFlags,
+) -> Fallible<()> {
+    let display = tool_version(name, version);
+    let setup_error = || ErrorDetails::SetupToolImageError {
+        tool: name.to_string(),
+        version: version.to_string(),
+        dir: install_dir.to_owned(),
+    };
+
+    let staging = create_staging_dir()?;
+    let staged_package = staging.path().join("package");
+    copy_dir_all(package_dir, &staged_package).with_context(|_| setup_error())?;
+
+    install_dependencies(&staged_package, image, config, flags, &display)?;
+    check_platform_dependencies(&staged_package, &display)?;
+
+    ensure_containing_dir_exists(&install_dir).with_context(|_| {
+        ErrorDetails::ContainingDirError {
+            path: install_dir.to_owned(),
+        }
+    })?;
+    rename(&staged_package, install_dir).with_context(|_| setup_error())?;
+    debug!("Installed {} in '{}'", display, install_dir.display());
+
+    Ok(())
+}
+
+fn install_dependencies(
+    package_dir: &Path,
+    image: &Image,
+    config: &NpmConfig,
+    flags: &NpmFlags,
+    display: &str,
+) -> Fallible<()> {
+    let path = image.path()?;
+    let scripts = flags.install_scripts()?;
+    let mut command = build_install_command(package_dir, &path, config.userconfig());
+    if settings()?.offline {
+        command.arg("--offline");
+    }
+    if scripts != InstallScripts::Run {
+        command.arg("--ignore-scripts");
+    }
+    command.args(flags.install_args());
     debug!("Installing dependencies with command: {:?}", command);
 
     let spinner = progress_spinner(&format!("Installing dependencies for {}", display));
@@ -220,14 +420,21 @@ fn install_dependencies(package_dir: &Path, image: Image, display: &str) -> Fall
         String::from_utf8_lossy(&output.stdout)
     );
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        Err(ErrorDetails::PackageInstallFailed.into())
+    if !output.status.success() {
+        throw!(ErrorDetails::PackageInstallFailed);
+    }
+
+    match scripts {
+        InstallScripts::Run => Ok(()),
+        InstallScripts::CleanEnv => scripts::run_in_clean_env(package_dir, &path, display),
+        InstallScripts::Ignore => {
+            scripts::report_skipped(package_dir, display);
+            Ok(())
+        }
     }
 }
 
-fn build_install_command(in_dir: &Path, path: &OsStr) -> Command {
+fn build_install_command(in_dir: &Path, path: &OsStr, userconfig: Option<&Path>) -> Command {
     let mut command = create_command("npm");
     command.args(&[
         "install",
@@ -244,6 +451,9 @@ fn build_install_command(in_dir: &Path, path: &OsStr) -> Command {
     }
     command.current_dir(in_dir);
     command.env("PATH", path);
+    if let Some(userconfig) = userconfig {
+        command.env(USERCONFIG_VAR, userconfig);
+    }
 
     command
 }
@@ -253,6 +463,14 @@ fn read_bins(name: &str, version: &Version) -> Fallible<HashMap<String, String>>
     let image_dir = volta_home()?.package_image_dir(&name, &version.to_string());
     let pkg_info = BinManifest::for_dir(&image_dir)?;
     let bin_map = pkg_info.bin;
+    check_bins(name, &bin_map)?;
+
+    Ok(bin_map)
+}
+
+/// Checks that a package provides executables, and that none of them are already provided by a
+/// different package
+pub(super) fn check_bins(name: &str, bin_map: &HashMap<String, String>) -> Fallible<()> {
     if bin_map.is_empty() {
         throw!(ErrorDetails::NoPackageExecutables);
     }
@@ -275,7 +493,7 @@ fn read_bins(name: &str, version: &Version) -> Fallible<HashMap<String, String>>
         }
     }
 
-    Ok(bin_map)
+    Ok(())
 }
 
 /// Read the script for a shebang loader. If found, return it so it will be stored in the config
@@ -305,36 +523,59 @@ fn determine_script_loader(bin_name: &str, full_path: &Path) -> Fallible<Option<
         }
     }
 
+    // Scripts without a shebang can still need a loader on Windows, based on their extension
+    if cfg!(windows) {
+        return Ok(ScriptKind::from_path(full_path).and_then(ScriptKind::loader));
+    }
+
     Ok(None)
 }
 
-mod os {
-    use cfg_if::cfg_if;
-    use std::io;
-    use std::path::Path;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    cfg_if! {
-        if #[cfg(windows)] {
-            /// On Windows, this isn't a concern as there is no concept of 'executable' permissions
-            pub fn set_executable_permissions(_bin: &Path) -> io::Result<()> {
-                Ok(())
-            }
-        } else if #[cfg(unix)] {
-            use std::fs;
-            use std::os::unix::fs::PermissionsExt;
-
-            /// Ensure that a given binary has 'executable' permissions on Unix, otherwise we won't be able to call it
-            pub fn set_executable_permissions(bin: &Path) -> io::Result<()> {
-                let mut permissions = fs::metadata(bin)?.permissions();
-                let mode = permissions.mode();
-
-                if mode & 0o111 != 0o111 {
-                    permissions.set_mode(mode | 0o111);
-                    fs::set_permissions(bin, permissions)
-                } else {
-                    Ok(())
-                }
-            }
-        }
+    fn version(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    fn requirement(requirement: &str) -> VersionReq {
+        parse_requirements(requirement).unwrap()
+    }
+
+    #[test]
+    fn test_default_node_satisfies_engines() {
+        let req = requirement(">=10");
+        let default = version("12.16.1");
+
+        assert_eq!(
+            satisfying_default(Some(&req), Some(&default)),
+            Some(version("12.16.1"))
+        );
+    }
+
+    #[test]
+    fn test_default_node_does_not_satisfy_engines() {
+        let req = requirement("^8.10");
+        let default = version("12.16.1");
+
+        assert_eq!(satisfying_default(Some(&req), Some(&default)), None);
+        assert_eq!(satisfying_default(Some(&req), None), None);
+        assert_eq!(
+            node_spec(Some(req)),
+            VersionSpec::Tag(VersionTag::LtsRequirement(requirement("^8.10")))
+        );
+    }
+
+    #[test]
+    fn test_no_engines() {
+        let default = version("12.16.1");
+
+        assert_eq!(
+            satisfying_default(None, Some(&default)),
+            Some(version("12.16.1"))
+        );
+        assert_eq!(satisfying_default(None, None), None);
+        assert_eq!(node_spec(None), VersionSpec::Tag(VersionTag::Lts));
     }
 }
@@ -0,0 +1,146 @@
+//! Provides installation of packages from a local directory, so that the executables of a
+//! package in development run directly from its working copy, like with `npm link`
+
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+
+use super::install::{check_bins, determine_node, write_configs};
+use crate::error::ErrorDetails;
+use crate::history::{Action, Entry};
+use crate::manifest::{BinManifest, Manifest};
+use crate::platform::PlatformSpec;
+use crate::session::Session;
+use crate::style::{success_prefix, tool_version};
+use crate::sync::VoltaLock;
+use crate::tool::Tool;
+use dunce::canonicalize;
+use log::{debug, info};
+use semver::Version;
+use volta_fail::{Fallible, ResultExt};
+
+/// Returns whether a package spec refers to a directory on the local file system rather than a
+/// package in the registry.
+pub fn is_local_spec(spec: &str) -> bool {
+    spec == "."
+        || spec == ".."
+        || spec.starts_with("file:")
+        || spec.starts_with("./")
+        || spec.starts_with("../")
+        || spec.starts_with(".\\")
+        || spec.starts_with("..\\")
+        || Path::new(spec).is_absolute()
+}
+
+/// Resolve a package in a local directory, reading its name and version from its manifest
+pub fn resolve_local(spec: &str) -> Fallible<LinkedPackage> {
+    let path = Path::new(spec.trim_start_matches("file:"));
+    let dir = canonicalize(path).with_context(|_| ErrorDetails::PackageReadError {
+        file: path.join("package.json"),
+    })?;
+
+    let manifest = BinManifest::for_dir(&dir)?;
+    let name = manifest
+        .name
+        .ok_or_else(|| ErrorDetails::LinkedPackageNameError { dir: dir.clone() })?;
+    // The working copy can change at any time, so its version is only used for display
+    let version = manifest
+        .version
+        .and_then(|version| Version::parse(&version).ok())
+        .unwrap_or_else(|| Version::new(0, 0, 0));
+
+    debug!("Found package {} in '{}'", name, dir.display());
+    Ok(LinkedPackage { name, version, dir })
+}
+
+/// Returns the platform pinned in the `volta` key of a linked package, if any
+pub(crate) fn pinned_platform(dir: &Path) -> Option<PlatformSpec> {
+    let manifest = Manifest::for_dir(dir).ok()?;
+    manifest.platform().map(|platform| (*platform).clone())
+}
+
+/// The Tool implementation for linking packages from a local directory
+#[derive(Debug)]
+pub struct LinkedPackage {
+    pub(crate) name: String,
+    pub(crate) version: Version,
+    pub(crate) dir: PathBuf,
+}
+
+impl Tool for LinkedPackage {
+    fn fetch(self, _session: &mut Session) -> Fallible<()> {
+        Err(ErrorDetails::CannotFetchLinkedPackage {
+            package: self.dir.display().to_string(),
+        }
+        .into())
+    }
+
+    fn install(self, session: &mut Session) -> Fallible<()> {
+        let _lock = VoltaLock::acquire()?;
+        let bin_map = BinManifest::for_dir(&self.dir)?.bin;
+        check_bins(&self.name, &bin_map)?;
+
+        let platform = match pinned_platform(&self.dir) {
+            Some(platform) => {
+                debug!("Using the platform pinned by {}", self);
+                platform
+            }
+            None => PlatformSpec {
+                node_runtime: determine_node(&self.dir, &self.to_string(), session)?,
+                npm: None,
+                yarn: None,
+                pnpm: None,
+            },
+        };
+        // The dependencies are already installed in the working copy, so only the platform
+        // needs to be available
+        platform.clone().checkout(session)?;
+
+        write_configs(
+            &self.name,
+            &self.version,
+            &platform,
+            &bin_map,
+            Some(&self.dir),
+            session,
+        )?;
+        Entry::new(Action::Install, &self.name, None, Some(&self.version)).record();
+
+        let bins = bin_map
+            .keys()
+            .map(AsRef::as_ref)
+            .collect::<Vec<&str>>()
+            .join(", ");
+        info!(
+            "{} linked {} from '{}' with executables: {}",
+            success_prefix(),
+            self,
+            self.dir.display(),
+            bins
+        );
+        Ok(())
+    }
+
+    fn pin(self, _session: &mut Session) -> Fallible<()> {
+        Err(ErrorDetails::CannotPinPackage { package: self.name }.into())
+    }
+}
+
+impl Display for LinkedPackage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&tool_version(&self.name, &self.version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_local_spec;
+
+    #[test]
+    fn test_is_local_spec() {
+        assert!(is_local_spec("."));
+        assert!(is_local_spec("../my-tool"));
+        assert!(is_local_spec("file:packages/my-tool"));
+        assert!(!is_local_spec("my-tool"));
+        assert!(!is_local_spec("@scope/my-tool@1.0.0"));
+    }
+}
@@ -4,39 +4,59 @@ use std::path::{Path, PathBuf};
 
 use super::{debug_already_fetched, info_fetched, Tool};
 use crate::error::ErrorDetails;
-use crate::fs::{delete_dir_error, delete_file_error, dir_entry_match};
-use crate::layout::volta_home;
+use crate::fs::{delete_dir_error, delete_file_error, dir_entry_match, read_dir_eager};
+use crate::history::{Action, Entry};
+use crate::layout::{long_path, volta_home};
 use crate::session::Session;
 use crate::shim;
 use crate::style::{success_prefix, tool_version};
+use crate::sync::VoltaLock;
+use crate::version::VersionSpec;
 use dunce::canonicalize;
 use log::{info, warn};
 use semver::Version;
-use volta_fail::{Fallible, ResultExt};
+use volta_fail::{throw, Fallible, ResultExt};
 
 mod fetch;
+mod flags;
 mod install;
+mod link;
+mod npmrc;
+mod optional;
 mod resolve;
+mod scripts;
 mod serial;
 
-pub use install::{BinConfig, BinLoader, PackageConfig};
-pub use resolve::resolve;
+pub use flags::NpmFlags;
+pub use install::{BinConfig, BinLoader, PackageConfig, ScriptKind};
+pub(crate) use link::pinned_platform;
+pub use link::{is_local_spec, resolve_local, LinkedPackage};
+pub(crate) use npmrc::npm_cache_dir;
+pub use resolve::{is_remote_spec, resolve, resolve_remote};
 
 pub fn bin_full_path<P>(
     package: &str,
     version: &Version,
+    node: &Version,
     bin_name: &str,
     bin_path: P,
+    link: Option<&Path>,
 ) -> Fallible<PathBuf>
 where
     P: AsRef<Path>,
 {
-    let raw_path = volta_home()?
-        .package_image_dir(package, &version.to_string())
-        .join(bin_path);
+    let image_dir = match link {
+        // Linked packages run directly from their working copy
+        Some(dir) => dir.to_owned(),
+        None => volta_home()?.package_platform_image_dir(
+            package,
+            &version.to_string(),
+            &node.to_string(),
+        ),
+    };
 
     // canonicalize because path is relative, and sometimes uses '.' char
-    canonicalize(raw_path).with_context(|_| ErrorDetails::ExecutablePathError {
+    canonicalize(image_dir.join(bin_path)).with_context(|_| ErrorDetails::ExecutablePathError {
         command: bin_name.to_string(),
     })
 }
@@ -47,6 +67,8 @@ pub struct PackageDetails {
     pub(crate) version: Version,
     pub(crate) tarball_url: String,
     pub(crate) shasum: String,
+    /// The Subresource Integrity string that the registry publishes, if it publishes one
+    pub(crate) integrity: Option<String>,
 }
 
 /// The Tool implementation for fetching and installing 3rd-party packages
@@ -54,11 +76,24 @@ pub struct PackageDetails {
 pub struct Package {
     pub(crate) name: String,
     pub(crate) details: PackageDetails,
+    /// The Node version to install the package with, instead of the one chosen for it
+    pub(crate) node: Option<Version>,
 }
 
 impl Package {
     pub fn new(name: String, details: PackageDetails) -> Self {
-        Package { name, details }
+        Package {
+            name,
+            details,
+            node: None,
+        }
+    }
+
+    /// Installs the package with a specific Node version, as when `volta import` restores the
+    /// platform that a package was installed with on another machine
+    pub fn with_node(mut self, node: Version) -> Self {
+        self.node = Some(node);
+        self
     }
 
     fn fetch_internal(&self, session: &mut Session) -> Fallible<()> {
@@ -103,7 +138,23 @@ impl Tool for Package {
         } else {
             self.fetch_internal(session)?;
 
-            let bin_map = install::install(&self.name, &self.details.version, session)?;
+            let previous =
+                PackageConfig::from_file(&volta_home()?.default_package_config_file(&self.name))
+                    .ok()
+                    .map(|config| config.version);
+            let bin_map = install::install(
+                &self.name,
+                &self.details.version,
+                self.node.as_ref(),
+                session,
+            )?;
+            Entry::new(
+                Action::Install,
+                &self.name,
+                previous.as_ref(),
+                Some(&self.details.version),
+            )
+            .record();
 
             let bins = bin_map
                 .keys()
@@ -137,11 +188,14 @@ impl Display for Package {
 /// * the shims
 /// * the unpacked and initialized package
 pub fn uninstall(name: &str) -> Fallible<()> {
+    let _lock = VoltaLock::acquire()?;
     let home = volta_home()?;
     // if the package config file exists, use that to remove any installed bins and shims
     let package_config_file = home.default_package_config_file(name);
+    let mut previous = None;
     let package_found = if package_config_file.exists() {
         let package_config = PackageConfig::from_file(&package_config_file)?;
+        previous = Some(package_config.version.clone());
 
         for bin_name in package_config.bins {
             remove_config_and_shim(&bin_name, name)?;
@@ -166,11 +220,12 @@ pub fn uninstall(name: &str) -> Fallible<()> {
     // if any unpacked and initialized packages exists, remove them
     let package_image_dir = home.package_image_root_dir().join(name);
     if package_image_dir.exists() {
-        fs::remove_dir_all(&package_image_dir)
+        fs::remove_dir_all(long_path(&package_image_dir))
             .with_context(delete_dir_error(&package_image_dir))?;
     }
 
     if package_found {
+        Entry::new(Action::Uninstall, name, previous.as_ref(), None).record();
         info!("{} package '{}' uninstalled", success_prefix(), name);
     } else {
         warn!("No package '{}' found to uninstall", name);
@@ -179,6 +234,96 @@ pub fn uninstall(name: &str) -> Fallible<()> {
     Ok(())
 }
 
+/// Upgrade the specified package to the latest available version.
+///
+/// The new version is fully installed before the package config is switched over, so the
+/// existing version keeps working if anything fails along the way. Afterwards, the images of the
+/// previous version and any executables that only it provided are removed.
+pub fn upgrade(name: &str, session: &mut Session) -> Fallible<()> {
+    let home = volta_home()?;
+    let package_config_file = home.default_package_config_file(name);
+    if !package_config_file.exists() {
+        throw!(ErrorDetails::PackageNotInstalled {
+            package: name.to_string()
+        });
+    }
+    let current = PackageConfig::from_file(&package_config_file)?;
+    if let Some(dir) = &current.link {
+        info!(
+            "Package {} is linked from '{}', so it is always up to date",
+            name,
+            dir.display()
+        );
+        return Ok(());
+    }
+
+    // The package is upgraded from the registry it was installed from, unless another is given
+    if session.npm_flags().registry.is_none() && current.registry.is_some() {
+        let mut flags = session.npm_flags().clone();
+        flags.registry = current.registry.clone();
+        session.set_npm_flags(flags);
+    }
+
+    let details = resolve(name, VersionSpec::default(), session)?;
+    if details.version <= current.version {
+        info!(
+            "Package {} is already up to date",
+            tool_version(name, &current.version)
+        );
+        return Ok(());
+    }
+
+    let package = Package::new(name.to_string(), details);
+    package.fetch_internal(session)?;
+    let bin_map = install::install(name, &package.details.version, None, session)?;
+
+    for bin_name in current
+        .bins
+        .iter()
+        .filter(|bin| !bin_map.contains_key(*bin))
+    {
+        remove_config_and_shim(bin_name, name)?;
+    }
+    remove_version_images(name, &current.version)?;
+    Entry::new(
+        Action::Install,
+        name,
+        Some(&current.version),
+        Some(&package.details.version),
+    )
+    .record();
+
+    info!(
+        "{} upgraded {} from {} to {}",
+        success_prefix(),
+        name,
+        current.version,
+        package.details.version
+    );
+    Ok(())
+}
+
+/// Removes the fetched image and every installed image of the given version of a package
+fn remove_version_images(name: &str, version: &Version) -> Fallible<()> {
+    let package_dir = volta_home()?.package_image_root_dir().join(name);
+    let fetched = version.to_string();
+    let installed_prefix = format!("{}-node-", version);
+
+    let entries = read_dir_eager(&package_dir).with_context(|_| ErrorDetails::ReadDirError {
+        dir: package_dir.clone(),
+    })?;
+    for (entry, metadata) in entries {
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+        if metadata.is_dir() && (file_name == fetched || file_name.starts_with(&installed_prefix)) {
+            let image_dir = entry.path();
+            fs::remove_dir_all(long_path(&image_dir)).with_context(delete_dir_error(&image_dir))?;
+        }
+    }
+
+    Ok(())
+}
+
 fn remove_config_and_shim(bin_name: &str, pkg_name: &str) -> Fallible<()> {
     shim::delete(bin_name)?;
     let config_file = volta_home()?.default_tool_bin_config(&bin_name);
@@ -0,0 +1,282 @@
+//! Provides support for the user and project `.npmrc` files when fetching and installing packages,
+//! so that scoped registries and auth tokens configured for npm are also used by Volta
+
+use std::collections::HashMap;
+use std::env;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::ErrorDetails;
+use crate::fs::{create_staging_file, read_file};
+use crate::session::Session;
+use cfg_if::cfg_if;
+#[cfg(windows)]
+use dirs::data_local_dir;
+use dirs::home_dir;
+use lazy_static::lazy_static;
+use log::debug;
+use regex::{Captures, Regex};
+use tempfile::NamedTempFile;
+use volta_fail::{Fallible, ResultExt};
+
+/// The environment variable that npm reads the location of the user config file from
+pub(super) const USERCONFIG_VAR: &str = "npm_config_userconfig";
+
+/// The environment variable that npm reads the location of its cache from
+const CACHE_VAR: &str = "npm_config_cache";
+
+const AUTH_TOKEN_SUFFIX: &str = ":_authToken";
+
+lazy_static! {
+    static ref ENV_REFERENCE: Regex = Regex::new(r"\$\{([^}]+)\}").expect("Regex is valid");
+}
+
+/// The npm configuration in effect for a package operation
+///
+/// npm reads the project `.npmrc` from the directory it is run in, but Volta runs `npm pack` and
+/// `npm install` in its own directories. To keep the project configuration, the user and project
+/// files are combined into a single file, which npm is then pointed to as its user config.
+pub(super) struct NpmConfig {
+    settings: HashMap<String, String>,
+    combined: Option<NamedTempFile>,
+}
+
+impl NpmConfig {
+    /// Loads the user `.npmrc` and the `.npmrc` in the root of the current project, or in the
+    /// current directory when not in a project.
+    pub(super) fn load(session: &Session) -> Fallible<Self> {
+        let user_path = user_npmrc_path();
+        let project_path =
+            project_npmrc_path(session)?.filter(|path| Some(path) != user_path.as_ref());
+
+        let user = read_npmrc(user_path.as_ref())?;
+        let project = read_npmrc(project_path.as_ref())?;
+
+        let mut settings = HashMap::new();
+        for contents in user.iter().chain(project.iter()) {
+            settings.extend(parse_npmrc(contents));
+        }
+
+        let combined = match project {
+            Some(project) => {
+                // Later entries override earlier ones, matching npm's precedence of the
+                // project config over the user config
+                let contents = format!("{}\n{}\n", user.unwrap_or_default(), project);
+                let mut file = create_staging_file()?;
+                file.write_all(contents.as_bytes()).with_context(|_| {
+                    ErrorDetails::WriteNpmrcError {
+                        file: file.path().to_owned(),
+                    }
+                })?;
+                debug!(
+                    "Combined npm configuration written to '{}'",
+                    file.path().display()
+                );
+                Some(file)
+            }
+            None => None,
+        };
+
+        Ok(NpmConfig { settings, combined })
+    }
+
+    /// The path to set as npm's user config, if the project has its own configuration
+    pub(super) fn userconfig(&self) -> Option<&Path> {
+        self.combined.as_ref().map(NamedTempFile::path)
+    }
+
+    /// Finds the auth token configured for the registry that `url` belongs to
+    ///
+    /// Tokens are keyed by the URL of the registry without its protocol, for example
+    /// `//npm.example.com/:_authToken=...`. When several registries match, the most specific
+    /// one wins, and references to environment variables (`${NPM_TOKEN}`) are expanded.
+    pub(super) fn auth_token(&self, url: &str) -> Option<String> {
+        let location = format!("//{}", url.splitn(2, "://").nth(1)?);
+
+        self.settings
+            .iter()
+            .filter(|(key, _)| key.starts_with("//") && key.ends_with(AUTH_TOKEN_SUFFIX))
+            .filter_map(|(key, token)| {
+                let mut registry = key[..key.len() - AUTH_TOKEN_SUFFIX.len()].to_string();
+                if !registry.ends_with('/') {
+                    registry.push('/');
+                }
+                if location.starts_with(&registry) {
+                    Some((registry.len(), token))
+                } else {
+                    None
+                }
+            })
+            .max_by_key(|&(len, _)| len)
+            .map(|(_, token)| expand_env(token))
+    }
+}
+
+/// Finds the cache directory that npm will use, from the `npm_config_cache` environment variable,
+/// the `cache` setting of the user or project `.npmrc`, or npm's default location
+pub(crate) fn npm_cache_dir(session: &Session) -> Fallible<Option<PathBuf>> {
+    let setting = match env::var_os(CACHE_VAR).or_else(|| env::var_os("NPM_CONFIG_CACHE")) {
+        Some(cache) => Some(cache.to_string_lossy().into_owned()),
+        None => NpmConfig::load(session)?
+            .settings
+            .get("cache")
+            .map(|cache| expand_env(cache)),
+    };
+
+    Ok(resolve_cache_dir(setting, home_dir()))
+}
+
+/// Resolves a `cache` setting the way npm does, expanding a leading `~` to the home directory and
+/// falling back to npm's default location when there is no setting
+fn resolve_cache_dir(setting: Option<String>, home: Option<PathBuf>) -> Option<PathBuf> {
+    match setting {
+        Some(ref cache) if cache == "~" => home,
+        Some(cache) => match cache
+            .strip_prefix("~/")
+            .or_else(|| cache.strip_prefix("~\\"))
+        {
+            Some(rest) => home.map(|home| home.join(rest)),
+            None => Some(PathBuf::from(&cache)),
+        },
+        None => default_cache_dir(home),
+    }
+}
+
+cfg_if! {
+    if #[cfg(windows)] {
+        fn default_cache_dir(_home: Option<PathBuf>) -> Option<PathBuf> {
+            data_local_dir().map(|local| local.join("npm-cache"))
+        }
+    } else {
+        fn default_cache_dir(home: Option<PathBuf>) -> Option<PathBuf> {
+            home.map(|home| home.join(".npm"))
+        }
+    }
+}
+
+fn user_npmrc_path() -> Option<PathBuf> {
+    env::var_os(USERCONFIG_VAR)
+        .or_else(|| env::var_os("NPM_CONFIG_USERCONFIG"))
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".npmrc")))
+}
+
+fn project_npmrc_path(session: &Session) -> Fallible<Option<PathBuf>> {
+    let dir = match session.project()? {
+        Some(project) => Some(project.project_root().to_owned()),
+        None => env::current_dir().ok(),
+    };
+    Ok(dir.map(|dir| dir.join(".npmrc")))
+}
+
+fn read_npmrc(path: Option<&PathBuf>) -> Fallible<Option<String>> {
+    match path {
+        Some(path) => {
+            read_file(path).with_context(|_| ErrorDetails::ReadNpmrcError { file: path.clone() })
+        }
+        None => Ok(None),
+    }
+}
+
+/// Parses the `key = value` lines of an `.npmrc` file, skipping comments and sections
+fn parse_npmrc(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(&['#', ';', '['][..]))
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next()?.trim();
+            let value = parts.next()?.trim().trim_matches('"');
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn expand_env(value: &str) -> String {
+    ENV_REFERENCE
+        .replace_all(value, |captures: &Captures| {
+            env::var(&captures[1]).unwrap_or_else(|_| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(contents: &str) -> NpmConfig {
+        NpmConfig {
+            settings: parse_npmrc(contents),
+            combined: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_npmrc() {
+        let settings = parse_npmrc(
+            "; a comment
+# another comment
+registry = https://registry.npmjs.org/
+@myorg:registry=https://npm.example.com/
+//npm.example.com/:_authToken=\"abc123\"
+",
+        );
+
+        assert_eq!(settings.len(), 3);
+        assert_eq!(settings["registry"], "https://registry.npmjs.org/");
+        assert_eq!(settings["@myorg:registry"], "https://npm.example.com/");
+        assert_eq!(settings["//npm.example.com/:_authToken"], "abc123");
+    }
+
+    #[test]
+    fn test_auth_token_matches_most_specific_registry() {
+        let config = config(
+            "//npm.example.com/:_authToken=host-token
+//npm.example.com/myorg/:_authToken=org-token
+",
+        );
+
+        assert_eq!(
+            config.auth_token("https://npm.example.com/@myorg%2fcli"),
+            Some("host-token".to_string())
+        );
+        assert_eq!(
+            config.auth_token("https://npm.example.com/myorg/@myorg%2fcli"),
+            Some("org-token".to_string())
+        );
+        assert_eq!(config.auth_token("https://npm.example.org/cli"), None);
+    }
+
+    #[test]
+    fn test_auth_token_expands_env() {
+        env::set_var("VOLTA_TEST_NPM_TOKEN", "from-env");
+        let config = config("//npm.example.com:_authToken=${VOLTA_TEST_NPM_TOKEN}");
+
+        assert_eq!(
+            config.auth_token("https://npm.example.com/cli"),
+            Some("from-env".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_cache_dir() {
+        let home = Some(PathBuf::from("home"));
+
+        assert_eq!(
+            resolve_cache_dir(Some("/custom/cache".to_string()), home.clone()),
+            Some(PathBuf::from("/custom/cache"))
+        );
+        assert_eq!(
+            resolve_cache_dir(Some("~/custom".to_string()), home.clone()),
+            Some(Path::new("home").join("custom"))
+        );
+        assert_eq!(resolve_cache_dir(Some("~".to_string()), home.clone()), home);
+        if cfg!(unix) {
+            assert_eq!(
+                resolve_cache_dir(None, home),
+                Some(Path::new("home").join(".npm"))
+            );
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Provides checks for packages that ship their executables as platform-specific optional
+//! dependencies (e.g. esbuild, swc or turbo), where npm only installs the build for the host
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use crate::error::ErrorDetails;
+use log::debug;
+use serde::Deserialize;
+use volta_fail::{throw, Fallible};
+
+cfg_if::cfg_if! {
+    if #[cfg(windows)] {
+        /// The `os` value that npm matches against for this system
+        const NPM_OS: &str = "win32";
+    } else if #[cfg(target_os = "macos")] {
+        /// The `os` value that npm matches against for this system
+        const NPM_OS: &str = "darwin";
+    } else if #[cfg(target_os = "freebsd")] {
+        /// The `os` value that npm matches against for this system
+        const NPM_OS: &str = "freebsd";
+    } else {
+        /// The `os` value that npm matches against for this system
+        const NPM_OS: &str = "linux";
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(target_arch = "x86")] {
+        /// The `cpu` value that npm matches against for this system
+        const NPM_CPU: &str = "ia32";
+    } else if #[cfg(target_arch = "aarch64")] {
+        /// The `cpu` value that npm matches against for this system
+        const NPM_CPU: &str = "arm64";
+    } else {
+        /// The `cpu` value that npm matches against for this system
+        const NPM_CPU: &str = "x64";
+    }
+}
+
+/// The fields of a package manifest (or of a lockfile entry) that restrict it to some platforms
+#[derive(Deserialize, Default)]
+struct PlatformManifest {
+    #[serde(default, rename = "optionalDependencies")]
+    optional_dependencies: HashMap<String, String>,
+    #[serde(default)]
+    os: Vec<String>,
+    #[serde(default)]
+    cpu: Vec<String>,
+}
+
+/// The part of an npm 7+ lockfile that records every optional dependency, including the ones
+/// that were skipped because they don't support the system
+#[derive(Deserialize, Default)]
+struct Lockfile {
+    #[serde(default)]
+    packages: HashMap<String, PlatformManifest>,
+}
+
+impl PlatformManifest {
+    fn is_platform_specific(&self) -> bool {
+        !self.os.is_empty() || !self.cpu.is_empty()
+    }
+
+    fn supports(&self, os: &str, cpu: &str) -> bool {
+        supports(&self.os, os) && supports(&self.cpu, cpu)
+    }
+}
+
+/// Matches a value against an npm `os` or `cpu` list, where entries starting with `!` exclude
+/// a value and an empty list allows everything
+fn supports(list: &[String], value: &str) -> bool {
+    let mut allowed = None;
+    for entry in list {
+        match entry.strip_prefix('!') {
+            Some(excluded) if excluded == value => return false,
+            Some(_) => {}
+            None => allowed = Some(allowed.unwrap_or(false) || entry == value),
+        }
+    }
+    allowed.unwrap_or(true)
+}
+
+fn read_json<T: Default + for<'de> Deserialize<'de>>(file: &Path) -> T {
+    File::open(file)
+        .ok()
+        .and_then(|file| serde_json::from_reader(file).ok())
+        .unwrap_or_default()
+}
+
+/// Checks that a package whose optional dependencies are restricted to some platforms had a
+/// build for this system installed, since its executables won't work otherwise
+///
+/// npm silently skips optional dependencies that don't support the system, so without this check
+/// the install would succeed and the executables would only fail once they are run.
+pub(super) fn check_platform_dependencies(package_dir: &Path, display: &str) -> Fallible<()> {
+    let manifest: PlatformManifest = read_json(&package_dir.join("package.json"));
+    if manifest.optional_dependencies.is_empty() {
+        return Ok(());
+    }
+    let mut lockfile: Lockfile = read_json(&package_dir.join("package-lock.json"));
+
+    let mut platform_specific = false;
+    for name in manifest.optional_dependencies.keys() {
+        let installed_dir = package_dir.join("node_modules").join(name);
+        let installed = installed_dir.join("package.json").is_file();
+        let dependency = if installed {
+            read_json(&installed_dir.join("package.json"))
+        } else {
+            lockfile
+                .packages
+                .remove(&format!("node_modules/{}", name))
+                .unwrap_or_default()
+        };
+
+        if !dependency.is_platform_specific() {
+            continue;
+        }
+        platform_specific = true;
+
+        if installed && dependency.supports(NPM_OS, NPM_CPU) {
+            debug!("Using {} for the executables of {}", name, display);
+            return Ok(());
+        }
+    }
+
+    if platform_specific {
+        throw!(ErrorDetails::NoPackagePlatformBuild {
+            package: display.to_string(),
+            platform: format!("{}-{}", NPM_OS, NPM_CPU),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|entry| entry.to_string()).collect()
+    }
+
+    #[test]
+    fn test_supports() {
+        assert!(supports(&[], "linux"));
+        assert!(supports(&list(&["darwin", "linux"]), "linux"));
+        assert!(!supports(&list(&["win32"]), "linux"));
+        assert!(supports(&list(&["!win32"]), "linux"));
+        assert!(!supports(&list(&["!linux"]), "linux"));
+    }
+
+    #[test]
+    fn test_check_platform_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, contents: &str| {
+            let file = dir.path().join(path);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, contents).unwrap();
+        };
+
+        write(
+            "package.json",
+            r#"{ "optionalDependencies": { "tool-other": "1.0.0" } }"#,
+        );
+        write(
+            "package-lock.json",
+            r#"{ "packages": { "node_modules/tool-other": { "os": ["other"] } } }"#,
+        );
+        assert!(check_platform_dependencies(dir.path(), "tool@1.0.0").is_err());
+
+        write(
+            "node_modules/tool-host/package.json",
+            &format!(r#"{{ "os": ["{}"], "cpu": ["{}"] }}"#, NPM_OS, NPM_CPU),
+        );
+        write(
+            "package.json",
+            r#"{ "optionalDependencies": { "tool-other": "1.0.0", "tool-host": "1.0.0" } }"#,
+        );
+        assert!(check_platform_dependencies(dir.path(), "tool@1.0.0").is_ok());
+    }
+}
@@ -2,18 +2,107 @@
 
 use std::collections::HashMap;
 use std::ffi::OsString;
+use std::fs::{rename, write, File};
 
+use super::fetch;
+use super::npmrc::NpmConfig;
 use crate::error::ErrorDetails;
+use crate::fs::create_staging_dir;
 use crate::hook::ToolHooks;
+use crate::layout::volta_home;
+use crate::manifest::BinManifest;
 use crate::run::{self, ToolCommand};
 use crate::session::Session;
+use crate::settings::{check_online, settings};
 use crate::style::{progress_spinner, tool_version};
 use crate::tool::PackageDetails;
-use crate::version::{VersionSpec, VersionTag};
+use crate::version::{parse_version, VersionSpec, VersionTag};
+use archive::{http_client, Archive, Tarball};
+use fs_utils::ensure_containing_dir_exists;
 use log::debug;
+use reqwest::header::AUTHORIZATION;
 use semver::{Version, VersionReq};
 use volta_fail::{throw, Fallible, ResultExt};
 
+/// Spec prefixes for packages that come from outside the registry, which npm can pack directly
+const REMOTE_SPEC_PREFIXES: [&str; 8] = [
+    "git+",
+    "git://",
+    "github:",
+    "gitlab:",
+    "bitbucket:",
+    "gist:",
+    "http://",
+    "https://",
+];
+
+/// Returns whether a package spec refers to a git repository or tarball URL rather than a
+/// package in the registry.
+pub fn is_remote_spec(spec: &str) -> bool {
+    REMOTE_SPEC_PREFIXES
+        .iter()
+        .any(|prefix| spec.starts_with(prefix))
+}
+
+/// Resolve a package from a git repository or tarball URL, returning its name and details.
+///
+/// There is no registry metadata for these packages, so they are packed with `npm pack` up front
+/// and the name and version are read from the tarball. The tarball and its shasum are stored in
+/// the package inventory, so fetching the package afterwards doesn't need to pack it again.
+pub fn resolve_remote(spec: &str, session: &mut Session) -> Fallible<(String, PackageDetails)> {
+    let unpack_error = || ErrorDetails::NpmPackUnpackError {
+        package: spec.to_string(),
+    };
+
+    let staging = create_staging_dir()?;
+    let tarball = fetch::npm_pack(spec, spec, staging.path(), session)?;
+
+    let unpack_dir = staging.path().join("unpacked");
+    let distro = File::open(&tarball).with_context(|_| unpack_error())?;
+    Tarball::load(distro)
+        .with_context(|_| unpack_error())?
+        .unpack(&unpack_dir, &mut |_, _| ())
+        .with_context(|_| unpack_error())?;
+
+    let manifest = BinManifest::for_dir(&fetch::find_unpack_dir(&unpack_dir)?)?;
+    let (name, version) = match (manifest.name, manifest.version) {
+        (Some(name), Some(version)) => (name, parse_version(version)?),
+        _ => throw!(unpack_error()),
+    };
+    debug!("Resolved {} to {}", spec, tool_version(&name, &version));
+
+    let mut distro = File::open(&tarball).with_context(|_| unpack_error())?;
+    let shasum = fetch::file_shasum(&mut distro).with_context(|_| unpack_error())?;
+
+    let home = volta_home()?;
+    let version_string = version.to_string();
+    let distro_file = home.package_distro_file(&name, &version_string);
+    let shasum_file = home.package_distro_shasum(&name, &version_string);
+    ensure_containing_dir_exists(&distro_file).with_context(|_| {
+        ErrorDetails::ContainingDirError {
+            path: distro_file.clone(),
+        }
+    })?;
+    rename(&tarball, &distro_file).with_context(|_| unpack_error())?;
+    write(&shasum_file, shasum.as_bytes()).with_context(|_| {
+        ErrorDetails::WritePackageShasumError {
+            package: name.clone(),
+            version: version_string,
+            file: shasum_file.clone(),
+        }
+    })?;
+
+    Ok((
+        name,
+        PackageDetails {
+            version,
+            tarball_url: spec.to_string(),
+            shasum,
+            integrity: None,
+        },
+    ))
+}
+
 pub fn resolve(
     name: &str,
     matching: VersionSpec,
@@ -37,7 +126,8 @@ fn resolve_tag(name: &str, tag: &str, session: &mut Session) -> Fallible<Package
         }) => {
             debug!("Using packages.latest hook to determine package metadata URL");
             let url = hook.resolve(&name)?;
-            resolve_package_metadata(name, &url)?.into()
+            let config = NpmConfig::load(session)?;
+            resolve_package_metadata(name, &url, &config)?.into()
         }
         _ => npm_view_query(name, tag, session)?,
     };
@@ -76,7 +166,8 @@ fn resolve_semver(
         }) => {
             debug!("Using packages.index hook to determine package metadata URL");
             let url = hook.resolve(&name)?;
-            resolve_package_metadata(name, &url)?.into()
+            let config = NpmConfig::load(session)?;
+            resolve_package_metadata(name, &url, &config)?.into()
         }
         _ => npm_view_query(name, &matching.to_string(), session)?,
     };
@@ -178,11 +269,15 @@ fn npm_view_query(name: &str, version: &str, session: &mut Session) -> Fallible<
 
 // build a command to run `npm view` with json output
 fn npm_view_command_for(name: &str, version: &str, session: &mut Session) -> Fallible<ToolCommand> {
-    let args = vec![
+    let mut args = vec![
         OsString::from("view"),
         OsString::from("--json"),
         OsString::from(format!("{}@{}", name, version)),
     ];
+    args.extend(session.npm_flags().fetch_args());
+    if settings()?.offline {
+        args.push(OsString::from("--offline"));
+    }
     run::npm::command(args, session)
 }
 
@@ -190,9 +285,20 @@ fn npm_view_command_for(name: &str, version: &str, session: &mut Session) -> Fal
 fn resolve_package_metadata(
     package_name: &str,
     package_info_url: &str,
+    config: &NpmConfig,
 ) -> Fallible<super::serial::RawPackageMetadata> {
+    check_online(package_info_url)?;
     let spinner = progress_spinner(&format!("Fetching package metadata: {}", package_info_url));
-    let response_text = reqwest::get(package_info_url)
+    let mut request = http_client().get(package_info_url);
+    if let Some(token) = config.auth_token(package_info_url) {
+        debug!(
+            "Using the npm auth token configured for {}",
+            package_info_url
+        );
+        request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let response_text = request
+        .send()
         .and_then(|resp| resp.error_for_status())
         .and_then(|mut resp| resp.text())
         .with_context(|err| match err.status() {
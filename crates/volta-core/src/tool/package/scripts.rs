@@ -0,0 +1,261 @@
+//! Provides the handling of the npm lifecycle scripts of global packages, which can be skipped or
+//! run in a cleared environment instead of being run by `npm install` as usual.
+//!
+//! In both cases, `npm install` is run with `--ignore-scripts`. When the scripts are skipped, the
+//! packages that have install scripts are listed afterwards, since the package may not work
+//! without them. With a clean environment, they are run by `npm rebuild` once the dependencies
+//! are installed, with an environment that is cleared of everything but the `PATH`, that points
+//! npm and the usual HTTP clients to a proxy that doesn't exist, and that has temporary
+//! directories for the home, cache and temp directories. This keeps tokens and other secrets in
+//! the environment away from the scripts, but it is NOT a sandbox: nothing is isolated by the
+//! operating system, so a script can still open network connections and write anywhere the user
+//! can.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::path::Path;
+use std::process::Command;
+
+use crate::command::create_command;
+use crate::error::ErrorDetails;
+use crate::fs::create_staging_dir;
+use crate::style::progress_spinner;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use volta_fail::{throw, Fallible, ResultExt};
+use walkdir::WalkDir;
+
+/// The lifecycle scripts that npm runs when a package is installed
+const INSTALL_SCRIPTS: [&str; 3] = ["preinstall", "install", "postinstall"];
+
+/// The environment variables that are kept for the scripts, besides the `PATH`
+const KEPT_VARS: [&str; 5] = ["SystemRoot", "COMSPEC", "PATHEXT", "LANG", "TERM"];
+
+/// The proxy that network requests are sent to, on a port that nothing listens on
+const UNREACHABLE_PROXY: &str = "http://127.0.0.1:9";
+
+#[derive(Deserialize)]
+struct ScriptsManifest {
+    name: Option<String>,
+    #[serde(default)]
+    scripts: HashMap<String, String>,
+}
+
+/// Runs the install scripts of the package and its dependencies in the cleared environment
+pub(super) fn run_in_clean_env(package_dir: &Path, path: &OsStr, display: &str) -> Fallible<()> {
+    let packages = packages_with_scripts(package_dir);
+    if packages.is_empty() {
+        return Ok(());
+    }
+    info!(
+        "Running the install scripts for {} in a cleared environment: {}",
+        display,
+        packages.join(", ")
+    );
+
+    let root_scripts = root_install_scripts(package_dir);
+    let mut commands = vec![vec!["rebuild"]];
+    commands.extend(
+        root_scripts
+            .iter()
+            .map(|script| vec!["run-script", script.as_str()]),
+    );
+
+    let scratch = create_staging_dir()?;
+    let spinner = progress_spinner(&format!("Running install scripts for {}", display));
+    for args in commands {
+        let mut command = cleared_command(&args, package_dir, path, scratch.path());
+        debug!("Running install scripts with command: {:?}", command);
+        let output = command
+            .output()
+            .with_context(|_| ErrorDetails::InstallScriptsFailed {
+                package: display.to_string(),
+            })?;
+        debug!(
+            "[install scripts stderr]\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        debug!(
+            "[install scripts stdout]\n{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+
+        if !output.status.success() {
+            spinner.finish_and_clear();
+            throw!(ErrorDetails::InstallScriptsFailed {
+                package: display.to_string(),
+            });
+        }
+    }
+    spinner.finish_and_clear();
+    Ok(())
+}
+
+fn cleared_command(args: &[&str], in_dir: &Path, path: &OsStr, scratch: &Path) -> Command {
+    let mut command = create_command("npm");
+    command.args(args);
+    command.args(&["--loglevel=warn", "--no-update-notifier"]);
+    command.current_dir(in_dir);
+
+    command.env_clear();
+    for name in KEPT_VARS.iter() {
+        if let Some(value) = std::env::var_os(name) {
+            command.env(name, value);
+        }
+    }
+    command.env("PATH", path);
+
+    for name in &["HOME", "USERPROFILE", "TMPDIR", "TMP", "TEMP"] {
+        command.env(name, scratch);
+    }
+    command.env("npm_config_cache", scratch.join("npm-cache"));
+    command.env("npm_config_offline", "true");
+    for name in &[
+        "npm_config_proxy",
+        "npm_config_https_proxy",
+        "HTTP_PROXY",
+        "HTTPS_PROXY",
+        "ALL_PROXY",
+        "http_proxy",
+        "https_proxy",
+        "all_proxy",
+    ] {
+        command.env(name, UNREACHABLE_PROXY);
+    }
+
+    command
+}
+
+/// Reports the packages whose install scripts were skipped
+pub(super) fn report_skipped(package_dir: &Path, display: &str) {
+    let packages = packages_with_scripts(package_dir);
+    if !packages.is_empty() {
+        warn!(
+            "Skipped the install scripts for {}, which may not work without them: {}",
+            display,
+            packages.join(", ")
+        );
+    }
+}
+
+/// Lists the package and its dependencies that have install scripts, with the scripts they have
+fn packages_with_scripts(package_dir: &Path) -> Vec<String> {
+    let mut packages: Vec<String> = WalkDir::new(package_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name() == "package.json")
+        .filter(|entry| {
+            entry.path() == package_dir.join("package.json") || is_dependency(entry.path())
+        })
+        .filter_map(|entry| {
+            let dir = entry.path().parent()?;
+            let manifest = read_manifest(dir)?;
+            let scripts = install_scripts(dir, &manifest);
+            if scripts.is_empty() {
+                return None;
+            }
+            let name = manifest.name.unwrap_or_else(|| dir.display().to_string());
+            Some(format!("{} ({})", name, scripts.join(", ")))
+        })
+        .collect();
+    packages.sort();
+    packages.dedup();
+    packages
+}
+
+/// Whether the `package.json` is the manifest of an installed dependency, as opposed to a file
+/// that happens to have that name inside of a package
+fn is_dependency(manifest: &Path) -> bool {
+    let dir = match manifest.parent() {
+        Some(dir) => dir,
+        None => return false,
+    };
+    let parent = dir.parent();
+    let is_node_modules = |path: Option<&Path>| {
+        path.and_then(Path::file_name)
+            .map_or(false, |name| name == "node_modules")
+    };
+    let is_scope = parent
+        .and_then(Path::file_name)
+        .map_or(false, |name| name.to_string_lossy().starts_with('@'));
+
+    is_node_modules(parent) || (is_scope && is_node_modules(parent.and_then(Path::parent)))
+}
+
+fn read_manifest(dir: &Path) -> Option<ScriptsManifest> {
+    let file = File::open(dir.join("package.json")).ok()?;
+    serde_json::de::from_reader(file).ok()
+}
+
+/// Returns the install scripts that npm runs for a package, including the `node-gyp rebuild` it
+/// runs as the `install` script of packages with native code that don't have one
+fn install_scripts(dir: &Path, manifest: &ScriptsManifest) -> Vec<String> {
+    INSTALL_SCRIPTS
+        .iter()
+        .filter(|script| {
+            manifest.scripts.contains_key(**script)
+                || (**script == "install" && dir.join("binding.gyp").is_file())
+        })
+        .map(|script| script.to_string())
+        .collect()
+}
+
+fn root_install_scripts(package_dir: &Path) -> Vec<String> {
+    match read_manifest(package_dir) {
+        // `npm run-script` can only run the scripts that are declared
+        Some(manifest) => INSTALL_SCRIPTS
+            .iter()
+            .filter(|script| manifest.scripts.contains_key(**script))
+            .map(|script| script.to_string())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_packages_with_scripts() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let manifests = [
+            (
+                "",
+                r#"{"name": "cli", "scripts": {"postinstall": "node setup.js"}}"#,
+            ),
+            (
+                "node_modules/esbuild",
+                r#"{"name": "esbuild", "scripts": {"postinstall": "node install.js", "test": "jest"}}"#,
+            ),
+            ("node_modules/@scope/native", r#"{"name": "@scope/native"}"#),
+            (
+                "node_modules/plain",
+                r#"{"name": "plain", "scripts": {"test": "jest"}}"#,
+            ),
+            (
+                "node_modules/plain/fixtures",
+                r#"{"name": "fixture", "scripts": {"install": "exit 1"}}"#,
+            ),
+        ];
+        for (path, contents) in manifests.iter() {
+            create_dir_all(root.join(path)).unwrap();
+            write(root.join(path).join("package.json"), contents).unwrap();
+        }
+        write(root.join("node_modules/@scope/native/binding.gyp"), "{}").unwrap();
+
+        assert_eq!(
+            packages_with_scripts(root),
+            vec![
+                "@scope/native (install)".to_string(),
+                "cli (postinstall)".to_string(),
+                "esbuild (postinstall)".to_string(),
+            ]
+        );
+        assert_eq!(root_install_scripts(root), vec!["postinstall".to_string()]);
+    }
+}
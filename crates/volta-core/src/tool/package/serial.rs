@@ -43,6 +43,7 @@ pub struct RawPackageVersionInfo {
 pub struct RawDistInfo {
     pub shasum: String,
     pub tarball: String,
+    pub integrity: Option<String>,
 }
 
 impl From<RawPackageMetadata> for PackageIndex {
@@ -54,6 +55,7 @@ impl From<RawPackageMetadata> for PackageIndex {
                 version: version_info.version,
                 tarball_url: version_info.dist.tarball,
                 shasum: version_info.dist.shasum,
+                integrity: version_info.dist.integrity,
             })
             .collect();
 
@@ -82,6 +84,7 @@ impl From<RawPackageMetadata> for PackageIndex {
 //   "dist": {
 //     "shasum": "543651070fd0f6ab0a0650c6a3e6ff5a7cb09caa",
 //     "tarball": "https://registry.npmjs.org/gulp/-/gulp-4.0.2.tgz",
+//     "integrity": "sha512-...",
 //   },
 //   (...and lots of other stuff we don't use...)
 // }
@@ -105,6 +108,7 @@ impl From<NpmViewData> for PackageDetails {
             version: view_data.version,
             tarball_url: view_data.dist.tarball,
             shasum: view_data.dist.shasum,
+            integrity: view_data.dist.integrity,
         }
     }
 }
@@ -116,6 +120,10 @@ pub struct RawPackageConfig {
     pub version: Version,
     pub platform: toolchain::serial::Platform,
     pub bins: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
 }
 
 impl RawPackageConfig {
@@ -158,6 +166,8 @@ impl TryFrom<RawPackageConfig> for PackageConfig {
             version: raw.version,
             platform,
             bins: raw.bins,
+            link: raw.link,
+            registry: raw.registry,
         })
     }
 }
@@ -179,6 +189,8 @@ impl From<PackageConfig> for RawPackageConfig {
             version: full.version,
             platform: full.platform.to_serial(),
             bins: full.bins,
+            link: full.link,
+            registry: full.registry,
         }
     }
 }
@@ -193,6 +205,8 @@ pub struct RawBinConfig {
     pub platform: toolchain::serial::Platform,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub loader: Option<RawBinLoader>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<PathBuf>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -250,6 +264,7 @@ impl TryFrom<RawBinConfig> for BinConfig {
             path: raw.path,
             platform,
             loader: raw.loader.map(|l| l.into()),
+            link: raw.link,
         })
     }
 }
@@ -263,6 +278,7 @@ impl From<BinConfig> for RawBinConfig {
             path: full.path,
             platform: full.platform.to_serial(),
             loader: full.loader.map(Into::into),
+            link: full.link,
         }
     }
 }
@@ -0,0 +1,137 @@
+//! Provides fetcher for the standalone pnpm executables
+
+use std::fs::{copy, create_dir_all, hard_link, rename};
+use std::path::{Path, PathBuf};
+
+use super::super::prefetch::RemoteArchive;
+use super::super::{
+    copy_shared_archive, download_archive, fetched_while_waiting, verify_locked_archive,
+};
+use super::{Pnpm, PNPM_DISTRO_EXTENSION};
+use crate::error::ErrorDetails;
+use crate::fs::{clear_quarantine, create_staging_dir, set_executable_permissions};
+use crate::layout::{long_path, volta_home};
+use crate::style::tool_version;
+use crate::sync::VoltaLock;
+use cfg_if::cfg_if;
+use fs_utils::ensure_containing_dir_exists;
+use log::debug;
+use semver::Version;
+use volta_fail::{Fallible, ResultExt};
+
+cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        fn public_pnpm_server_root() -> String {
+            format!("{}/pnpm", mockito::SERVER_URL)
+        }
+    } else {
+        fn public_pnpm_server_root() -> String {
+            "https://github.com/pnpm/pnpm/releases/download".to_string()
+        }
+    }
+}
+
+pub fn fetch(version: &Version) -> Fallible<()> {
+    let _lock = VoltaLock::acquire()?;
+    let home = volta_home()?;
+    if fetched_while_waiting("pnpm", version, &home.pnpm_image_dir(&version.to_string())) {
+        return Ok(());
+    }
+    let pnpm_dir = home.pnpm_inventory_dir();
+    let cache_file = pnpm_dir.join(Pnpm::archive_filename(&version.to_string()));
+    // The pnpm releases don't come with checksums, so only a locked pnpm is copied
+    copy_shared_archive("pnpm", version, &cache_file, || Ok(None))?;
+
+    verify_locked_archive("pnpm", version, &cache_file, || {
+        Ok(determine_remote_url(version))
+    })?;
+
+    if cache_file.is_file() {
+        debug!(
+            "Loading {} from cached executable at '{}'",
+            tool_version("pnpm", &version),
+            cache_file.display(),
+        );
+    } else {
+        download_archive(
+            "pnpm",
+            version,
+            &determine_remote_url(version),
+            &cache_file,
+            None,
+        )?;
+    }
+
+    set_up_image(&cache_file, version)
+}
+
+/// Returns the executable of a version in the inventory, downloading it if needed
+pub fn inventory_archive(version: &Version) -> Fallible<PathBuf> {
+    let _lock = VoltaLock::acquire()?;
+    let pnpm_dir = volta_home()?.pnpm_inventory_dir();
+    let cache_file = pnpm_dir.join(Pnpm::archive_filename(&version.to_string()));
+    // The pnpm releases don't come with checksums, so only a locked pnpm is copied
+    copy_shared_archive("pnpm", version, &cache_file, || Ok(None))?;
+
+    if !cache_file.is_file() {
+        download_archive(
+            "pnpm",
+            version,
+            &determine_remote_url(version),
+            &cache_file,
+            None,
+        )?;
+    }
+    Ok(cache_file)
+}
+
+/// Returns where the executable of a version is downloaded from and kept in the inventory
+pub(super) fn remote_archive(version: &Version) -> Fallible<RemoteArchive> {
+    let pnpm_dir = volta_home()?.pnpm_inventory_dir();
+    Ok(RemoteArchive {
+        tool: "pnpm",
+        version: version.clone(),
+        url: determine_remote_url(version),
+        cache_file: pnpm_dir.join(Pnpm::archive_filename(&version.to_string())),
+    })
+}
+
+/// Places the executable from the inventory in the image directory so that it is ready for use
+fn set_up_image(cache_file: &Path, version: &Version) -> Fallible<()> {
+    let temp = create_staging_dir()?;
+    let version_string = version.to_string();
+    let dest = volta_home()?.pnpm_image_dir(&version_string);
+    let setup_error = || ErrorDetails::SetupToolImageError {
+        tool: "pnpm".into(),
+        version: version_string.clone(),
+        dir: dest.clone(),
+    };
+
+    let bin_dir = temp.path().join("bin");
+    let bin = bin_dir.join(format!("pnpm{}", PNPM_DISTRO_EXTENSION));
+    create_dir_all(&bin_dir).with_context(|_| setup_error())?;
+    // The executable is large, so a hard link is used when the inventory and the image are on
+    // the same filesystem, which they normally are
+    hard_link(cache_file, &bin)
+        .or_else(|_| copy(cache_file, &bin).map(|_| ()))
+        .and_then(|_| set_executable_permissions(&bin))
+        .with_context(|_| setup_error())?;
+    clear_quarantine(&bin_dir);
+
+    ensure_containing_dir_exists(&dest)
+        .with_context(|_| ErrorDetails::ContainingDirError { path: dest.clone() })?;
+    rename(long_path(temp.path()), long_path(&dest)).with_context(|_| setup_error())?;
+
+    debug!("Installing pnpm in '{}'", dest.display());
+
+    Ok(())
+}
+
+fn determine_remote_url(version: &Version) -> String {
+    format!(
+        "{}/v{}/{}",
+        public_pnpm_server_root(),
+        version,
+        Pnpm::distro_filename()
+    )
+}
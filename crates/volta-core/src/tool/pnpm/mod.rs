@@ -0,0 +1,180 @@
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+
+use super::prefetch::RemoteArchive;
+use super::{
+    debug_already_fetched, info_fetched, info_installed, info_pinned, info_project_version, Tool,
+    NODE_DISTRO_ARCH,
+};
+use crate::error::ErrorDetails;
+use crate::layout::layered_path;
+use crate::session::Session;
+use crate::shim;
+use crate::style::tool_version;
+use cfg_if::cfg_if;
+use semver::Version;
+use volta_fail::Fallible;
+
+mod fetch;
+mod resolve;
+mod serial;
+
+pub use resolve::resolve;
+
+cfg_if! {
+    if #[cfg(target_os = "windows")] {
+        /// The OS component of a pnpm standalone binary's filename.
+        pub const PNPM_DISTRO_OS: &str = "win";
+        /// Filename extension for pnpm standalone binaries.
+        pub const PNPM_DISTRO_EXTENSION: &str = ".exe";
+    } else if #[cfg(target_os = "macos")] {
+        /// The OS component of a pnpm standalone binary's filename.
+        pub const PNPM_DISTRO_OS: &str = "macos";
+        /// Filename extension for pnpm standalone binaries.
+        pub const PNPM_DISTRO_EXTENSION: &str = "";
+    } else {
+        /// The OS component of a pnpm standalone binary's filename.
+        pub const PNPM_DISTRO_OS: &str = "linux";
+        /// Filename extension for pnpm standalone binaries.
+        pub const PNPM_DISTRO_EXTENSION: &str = "";
+    }
+}
+
+/// The Tool implementation for fetching and installing pnpm
+///
+/// pnpm is installed from its standalone executables, which bundle their own copy of Node, so
+/// there is no archive to unpack: the executable is kept in the inventory and linked into the
+/// image directory.
+#[derive(Debug)]
+pub struct Pnpm {
+    pub(super) version: Version,
+}
+
+impl Pnpm {
+    pub fn new(version: Version) -> Self {
+        Pnpm { version }
+    }
+
+    /// The name of the standalone executable for the current platform, as published with each
+    /// pnpm release
+    pub fn distro_filename() -> String {
+        format!(
+            "pnpm-{}-{}{}",
+            PNPM_DISTRO_OS, NODE_DISTRO_ARCH, PNPM_DISTRO_EXTENSION
+        )
+    }
+
+    /// The name of the standalone executable in the inventory, which includes the version
+    pub fn archive_filename(version: &str) -> String {
+        format!(
+            "pnpm-v{}-{}-{}{}",
+            version, PNPM_DISTRO_OS, NODE_DISTRO_ARCH, PNPM_DISTRO_EXTENSION
+        )
+    }
+
+    /// Whether this version can be used without downloading it, either because it is unpacked
+    /// or because it is in the inventory
+    pub(crate) fn is_fetched(&self, session: &Session) -> Fallible<bool> {
+        let version_str = self.version.to_string();
+        if layered_path(|home| home.pnpm_image_dir(&version_str))?.is_dir() {
+            return Ok(true);
+        }
+
+        Ok(session.inventory()?.pnpm.versions.contains(&self.version))
+    }
+
+    pub(crate) fn fetch_internal(&self, session: &mut Session) -> Fallible<()> {
+        let version_str = self.version.to_string();
+        if layered_path(|home| home.pnpm_image_dir(&version_str))?.is_dir() {
+            debug_already_fetched(self);
+            return Ok(());
+        }
+
+        // An executable that is already in the inventory is reused by the fetcher, so there's
+        // no need to check the inventory here
+        fetch::fetch(&self.version)?;
+        session
+            .inventory_mut()?
+            .pnpm
+            .versions
+            .insert(self.version.clone());
+
+        Ok(())
+    }
+
+    /// Returns the archive of this version in the inventory, downloading it if needed, so that
+    /// its checksum can be recorded in a lockfile
+    pub(crate) fn inventory_archive(&self, _session: &Session) -> Fallible<PathBuf> {
+        fetch::inventory_archive(&self.version)
+    }
+    /// Returns the archive that fetching this version would download, unless the version is
+    /// already fetched
+    pub(crate) fn remote_archive(&self, session: &Session) -> Fallible<Option<RemoteArchive>> {
+        if self.is_fetched(session)? {
+            return Ok(None);
+        }
+        fetch::remote_archive(&self.version).map(Some)
+    }
+}
+
+impl Tool for Pnpm {
+    fn fetch(self, session: &mut Session) -> Fallible<()> {
+        self.fetch_internal(session)?;
+
+        info_fetched(self);
+        Ok(())
+    }
+    fn install(self, session: &mut Session) -> Fallible<()> {
+        self.fetch_internal(session)?;
+
+        session.toolchain_mut()?.set_active_pnpm(&self.version)?;
+        // Installations from before pnpm was supported don't have the shim yet
+        shim::create("pnpm")?;
+
+        info_installed(self);
+
+        if let Ok(Some(project)) = session.project_platform() {
+            if let Some(pnpm) = &project.pnpm {
+                info_project_version(tool_version("pnpm", pnpm));
+            }
+        }
+        Ok(())
+    }
+    fn pin(self, session: &mut Session) -> Fallible<()> {
+        if session.project()?.is_some() {
+            self.fetch_internal(session)?;
+            shim::create("pnpm")?;
+
+            // Note: We know this will succeed, since we checked above
+            let project = session.project_mut()?.unwrap();
+            project.pin_pnpm(&self.version)?;
+
+            info_pinned(self);
+            Ok(())
+        } else {
+            Err(ErrorDetails::NotInPackage.into())
+        }
+    }
+}
+
+impl Display for Pnpm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&tool_version("pnpm", &self.version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pnpm_archive_filename() {
+        assert_eq!(
+            Pnpm::archive_filename("9.1.0"),
+            format!(
+                "pnpm-v9.1.0-{}-{}{}",
+                PNPM_DISTRO_OS, NODE_DISTRO_ARCH, PNPM_DISTRO_EXTENSION
+            )
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! Provides resolution of pnpm requirements into specific versions
+
+use std::collections::{BTreeSet, HashMap};
+
+use super::super::registry_fetch_error;
+use super::serial;
+use crate::error::ErrorDetails;
+use crate::settings::check_online;
+use crate::style::progress_spinner;
+use crate::version::{parse_version, VersionSpec, VersionTag};
+use archive::http_client;
+use cfg_if::cfg_if;
+use log::debug;
+use semver::{Version, VersionReq};
+use volta_fail::{Fallible, ResultExt};
+
+cfg_if! {
+    if #[cfg(feature = "mock-network")] {
+        fn public_pnpm_version_index() -> String {
+            format!("{}/pnpm/index.json", mockito::SERVER_URL)
+        }
+    } else {
+        /// URL of the npm registry document for `@pnpm/exe`, which is published alongside each
+        /// release of the standalone executables
+        fn public_pnpm_version_index() -> String {
+            "https://registry.npmjs.org/@pnpm/exe".to_string()
+        }
+    }
+}
+
+pub fn resolve(matching: VersionSpec) -> Fallible<Version> {
+    match matching {
+        VersionSpec::Exact(version) => Ok(version),
+        VersionSpec::Semver(requirement) => resolve_semver(requirement),
+        VersionSpec::None | VersionSpec::Tag(VersionTag::Latest) => resolve_tag("latest"),
+        VersionSpec::Tag(VersionTag::Custom(tag)) => resolve_tag(&tag),
+        VersionSpec::Tag(tag) => Err(ErrorDetails::PnpmVersionNotFound {
+            matching: tag.to_string(),
+        }
+        .into()),
+    }
+}
+
+/// Resolves a dist-tag, such as `latest` or `next-9`, from the registry
+fn resolve_tag(tag: &str) -> Fallible<Version> {
+    let (url, index) = fetch_pnpm_index()?;
+
+    match index.tags.get(tag) {
+        Some(version) => {
+            debug!("Found pnpm@{} matching tag '{}' from {}", version, tag, url);
+            parse_version(version)
+        }
+        None => Err(ErrorDetails::PnpmVersionNotFound {
+            matching: tag.into(),
+        }
+        .into()),
+    }
+}
+
+fn resolve_semver(matching: VersionReq) -> Fallible<Version> {
+    let (url, index) = fetch_pnpm_index()?;
+
+    match index
+        .entries
+        .into_iter()
+        .rev()
+        .find(|v| matching.matches(v))
+    {
+        Some(version) => {
+            debug!(
+                "Found pnpm@{} matching requirement '{}' from {}",
+                version, matching, url
+            );
+            Ok(version)
+        }
+        None => Err(ErrorDetails::PnpmVersionNotFound {
+            matching: matching.to_string(),
+        }
+        .into()),
+    }
+}
+
+fn fetch_pnpm_index() -> Fallible<(String, PnpmIndex)> {
+    let url = public_pnpm_version_index();
+    check_online(&url)?;
+    let spinner = progress_spinner(&format!("Fetching public registry: {}", url));
+    let index = http_client()
+        .get(&url)
+        .send()
+        .and_then(|mut response| response.json::<serial::RawPnpmIndex>())
+        .with_context(registry_fetch_error("pnpm", &url))?;
+    spinner.finish_and_clear();
+    Ok((url, index.into()))
+}
+
+/// The public pnpm index.
+pub struct PnpmIndex {
+    pub(super) entries: BTreeSet<Version>,
+    pub(super) tags: HashMap<String, String>,
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+
+use super::resolve::PnpmIndex;
+use semver::Version;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+/// The npm registry's package document for the standalone pnpm executables. Only the keys of
+/// the `versions` map and the `dist-tags` are needed.
+#[derive(Deserialize)]
+pub struct RawPnpmIndex {
+    pub versions: HashMap<String, IgnoredAny>,
+    #[serde(default, rename = "dist-tags")]
+    pub dist_tags: HashMap<String, String>,
+}
+
+impl From<RawPnpmIndex> for PnpmIndex {
+    fn from(raw: RawPnpmIndex) -> PnpmIndex {
+        let entries = raw
+            .versions
+            .keys()
+            .filter_map(|version| Version::parse(version).ok())
+            .collect();
+        PnpmIndex {
+            entries,
+            tags: raw.dist_tags,
+        }
+    }
+}
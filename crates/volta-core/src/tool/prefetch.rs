@@ -0,0 +1,169 @@
+//! Provides the concurrent download of tool archives, for commands that fetch several tools at
+//! once, like `volta fetch node yarn` or `volta fetch --from-project`.
+//!
+//! The download of a tool starts in the background as soon as the tool is resolved, so it
+//! overlaps with resolving the next tool, which may fetch an index of its own. All of the
+//! downloads go through the shared HTTP client, whose connection pool and event loop they share.
+//! Once every tool is resolved, the downloads are waited for and each archive is verified against
+//! the lockfile and added to the inventory, so that fetching the tool afterwards, as usual, only
+//! has to unpack it. This is the synchronous facade that the commands use: they never deal with
+//! the background downloads themselves.
+//!
+//! Only the archives that are kept in the inventory anyway are downloaded ahead of time: Node's
+//! with the `cacheArchives` setting, and those of npm, Yarn and pnpm. A download that fails is
+//! only logged, since the tool is then downloaded again by its own fetch, which reports the error.
+
+use std::path::PathBuf;
+use std::thread::{self, JoinHandle};
+
+use super::{download_tool_error, Resolved, Spec};
+use crate::error::ErrorDetails;
+use crate::fs::create_staging_file;
+use crate::lockfile::{locked_checksum, verify_archive};
+use crate::session::Session;
+use crate::settings::settings;
+use crate::style::progress_spinner;
+use crate::sync::VoltaLock;
+use crate::version::VersionSpec;
+use archive::{http_client, HttpError};
+use fs_utils::ensure_containing_dir_exists;
+use log::debug;
+use semver::Version;
+use tempfile::NamedTempFile;
+use volta_fail::{Fallible, ResultExt};
+
+/// The archive of a tool that isn't in the inventory yet, and where it is downloaded from
+pub(crate) struct RemoteArchive {
+    pub(crate) tool: &'static str,
+    pub(crate) version: Version,
+    pub(crate) url: String,
+    pub(crate) cache_file: PathBuf,
+}
+
+struct Download {
+    archive: RemoteArchive,
+    handle: JoinHandle<Result<NamedTempFile, failure::Error>>,
+}
+
+/// The downloads started for the tools of a command
+#[derive(Default)]
+pub struct Prefetch {
+    downloads: Vec<Download>,
+}
+
+impl Prefetch {
+    pub fn new() -> Self {
+        Prefetch::default()
+    }
+
+    /// Starts downloading the archive of a resolved tool in the background, if its fetch will
+    /// need to download one
+    pub fn start(&mut self, tool: &Resolved, session: &Session) -> Fallible<()> {
+        let settings = settings()?;
+        // Offline, nothing can be downloaded, and the shared cache is copied from instead
+        if settings.offline || settings.cache_shared_dir.is_some() {
+            return Ok(());
+        }
+
+        let archive = match remote_archive(tool, session)? {
+            Some(archive) => archive,
+            None => return Ok(()),
+        };
+        if self
+            .downloads
+            .iter()
+            .any(|download| download.archive.cache_file == archive.cache_file)
+        {
+            return Ok(());
+        }
+
+        debug!(
+            "Downloading {}@{} from {} in the background",
+            archive.tool, archive.version, archive.url
+        );
+        let mut staging = create_staging_file()?;
+        let url = archive.url.clone();
+        let handle = thread::spawn(move || {
+            let mut response = http_client().get(&url).send()?;
+            if !response.status().is_success() {
+                return Err(HttpError {
+                    code: response.status(),
+                }
+                .into());
+            }
+            response.copy_to(staging.as_file_mut())?;
+            Ok(staging)
+        });
+
+        self.downloads.push(Download { archive, handle });
+        Ok(())
+    }
+
+    /// Waits for the downloads, adding each archive to the inventory once it is verified
+    pub fn finish(self) -> Fallible<()> {
+        if self.downloads.is_empty() {
+            return Ok(());
+        }
+
+        let _lock = VoltaLock::acquire()?;
+        let spinner = progress_spinner(&format!("Downloading {} tools", self.downloads.len()));
+        for Download { archive, handle } in self.downloads {
+            let result = handle
+                .join()
+                .unwrap_or_else(|_| Err(failure::err_msg("the download was interrupted")));
+            match result {
+                Ok(staging) => persist(&archive, staging)?,
+                Err(error) => debug!(
+                    "Could not download {}@{} ahead of time, it is downloaded again: {}",
+                    archive.tool, archive.version, error
+                ),
+            }
+        }
+        spinner.finish_and_clear();
+        Ok(())
+    }
+}
+
+/// Returns the archive that fetching the tool would download, if any
+fn remote_archive(tool: &Resolved, session: &Session) -> Fallible<Option<RemoteArchive>> {
+    let archive = match tool {
+        Resolved::Node(node) => node.remote_archive(session)?,
+        Resolved::Npm(npm) => npm.remote_archive(session)?,
+        Resolved::Yarn(yarn) => yarn.remote_archive(session)?,
+        Resolved::Pnpm(pnpm) => pnpm.remote_archive(session)?,
+        _ => None,
+    };
+
+    // An archive that is already in the inventory, possibly recompressed, isn't downloaded again
+    Ok(archive.filter(|archive| {
+        !archive.cache_file.is_file() && !super::zstd_archive_file(&archive.cache_file).is_file()
+    }))
+}
+
+/// Adds a downloaded archive to the inventory, verifying it against the lockfile first the way
+/// any other download is
+fn persist(archive: &RemoteArchive, staging: NamedTempFile) -> Fallible<()> {
+    let RemoteArchive {
+        tool,
+        version,
+        url,
+        cache_file,
+    } = archive;
+
+    if let Some(expected) = locked_checksum(tool, version, cache_file)? {
+        verify_archive(tool, version, staging.path(), &expected, url)?;
+    }
+
+    ensure_containing_dir_exists(cache_file).with_context(|_| {
+        ErrorDetails::ContainingDirError {
+            path: cache_file.to_path_buf(),
+        }
+    })?;
+    staging
+        .persist(cache_file)
+        .with_context(download_tool_error(
+            Spec::from_str_and_version(tool, VersionSpec::Exact(version.clone())),
+            url,
+        ))?;
+    Ok(())
+}
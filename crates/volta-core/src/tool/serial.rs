@@ -22,12 +22,19 @@ impl Spec {
             "node" => Spec::Node(version),
             "npm" => Spec::Npm(version),
             "yarn" => Spec::Yarn(version),
+            "pnpm" => Spec::Pnpm(version),
             package => Spec::Package(package.to_string(), version),
         }
     }
 
     /// Try to parse a tool and version from a string like `<tool>[@<version>].
     pub fn try_from_str(tool_spec: &str) -> Fallible<Self> {
+        // Git repositories, tarball URLs and local directories don't follow the
+        // `<name>[@<version>]` format; their name and version are read from the package itself
+        if super::package::is_remote_spec(tool_spec) || super::package::is_local_spec(tool_spec) {
+            return Ok(Spec::Package(tool_spec.into(), VersionSpec::default()));
+        }
+
         let captures =
             TOOL_SPEC_PATTERN
                 .captures(tool_spec)
@@ -55,6 +62,7 @@ impl Spec {
             "node" => Spec::Node(version),
             "npm" => Spec::Npm(version),
             "yarn" => Spec::Yarn(version),
+            "pnpm" => Spec::Pnpm(version),
             package => Spec::Package(package.into(), version),
         })
     }
@@ -117,7 +125,7 @@ impl Spec {
     ///
     /// We want to preserve the original order as much as possible, so we treat tools in
     /// the same tool category as equal. We still need to pull Node to the front of the
-    /// list, followed by Npm / Yarn, and then Packages last.
+    /// list, followed by Npm / Yarn / pnpm, and then Packages last.
     fn sort_comparator(left: &Spec, right: &Spec) -> Ordering {
         match (left, right) {
             (Spec::Node(_), Spec::Node(_)) => Ordering::Equal,
@@ -129,6 +137,9 @@ impl Spec {
             (Spec::Yarn(_), Spec::Yarn(_)) => Ordering::Equal,
             (Spec::Yarn(_), _) => Ordering::Less,
             (_, Spec::Yarn(_)) => Ordering::Greater,
+            (Spec::Pnpm(_), Spec::Pnpm(_)) => Ordering::Equal,
+            (Spec::Pnpm(_), _) => Ordering::Less,
+            (_, Spec::Pnpm(_)) => Ordering::Greater,
             (Spec::Package(_, _), Spec::Package(_, _)) => Ordering::Equal,
         }
     }
@@ -240,6 +251,21 @@ mod tests {
             );
         }
 
+        #[test]
+        fn parses_pnpm_with_valid_versions() {
+            let tool = "pnpm";
+
+            assert_eq!(
+                Spec::try_from_str(tool).expect("succeeds"),
+                Spec::Pnpm(VersionSpec::default())
+            );
+
+            assert_eq!(
+                Spec::try_from_str(&versioned_tool!(tool, MAJOR)).expect("succeeds"),
+                Spec::Pnpm(VersionSpec::from_str(MAJOR).expect("`VersionSpec` has its own tests"))
+            );
+        }
+
         #[test]
         fn parses_bare_packages() {
             let package = "ember-cli";
@@ -258,6 +284,20 @@ mod tests {
             );
         }
 
+        #[test]
+        fn parses_remote_packages() {
+            for package in &[
+                "git+https://github.com/volta-cli/cowsay.git#v1.4.0",
+                "github:volta-cli/cowsay#v1.4.0",
+                "https://example.com/cowsay-1.4.0.tgz",
+            ] {
+                assert_eq!(
+                    Spec::try_from_str(package).expect("succeeds"),
+                    Spec::Package(package.to_string(), VersionSpec::default())
+                );
+            }
+        }
+
         #[test]
         fn parses_bare_packages_with_valid_versions() {
             let package = "something-awesome";
@@ -1,14 +1,27 @@
 //! Provides fetcher for Yarn distributions
 
+#[cfg(windows)]
+use std::fs::write;
 use std::fs::{rename, File};
+use std::io;
 use std::path::{Path, PathBuf};
 
-use super::super::download_tool_error;
+use super::super::prefetch::RemoteArchive;
+use super::super::{
+    check_unpack_space, copy_shared_archive, download_archive, download_tool_error,
+    fetched_while_waiting, verify_locked_archive,
+};
+use super::is_berry;
+use crate::checksum::{fetch_registry_checksum, Checksum};
 use crate::error::ErrorDetails;
-use crate::fs::{create_staging_dir, create_staging_file};
+#[cfg(unix)]
+use crate::fs::set_executable_permissions;
+use crate::fs::{clear_quarantine, create_staging_dir, create_staging_file};
 use crate::hook::ToolHooks;
-use crate::layout::volta_home;
+use crate::layout::{long_path, volta_home};
+use crate::settings::check_online;
 use crate::style::{progress_bar, tool_version};
+use crate::sync::VoltaLock;
 use crate::tool::{self, Yarn};
 use crate::version::VersionSpec;
 use archive::{Archive, Tarball};
@@ -23,16 +36,48 @@ cfg_if! {
         fn public_yarn_server_root() -> String {
             mockito::SERVER_URL.to_string()
         }
+        fn public_yarn_berry_root() -> String {
+            format!("{}/yarn-berry", mockito::SERVER_URL)
+        }
     } else {
         fn public_yarn_server_root() -> String {
             "https://github.com/yarnpkg/yarn/releases/download".to_string()
         }
+        fn public_yarn_berry_root() -> String {
+            "https://registry.npmjs.org/@yarnpkg/cli-dist/-".to_string()
+        }
+    }
+}
+
+cfg_if! {
+    if #[cfg(windows)] {
+        /// The Yarn Berry package only includes a shell script to launch Yarn, so a batch file is
+        /// added for Windows shells
+        fn set_up_berry_bin(bin_dir: &Path) -> io::Result<()> {
+            write(bin_dir.join("yarn.cmd"), "@node \"%~dp0\\yarn.js\" %*\r\n")
+        }
+    } else {
+        /// The npm registry doesn't preserve file modes the way the Yarn release tarballs do, so
+        /// the launcher script has to be made executable
+        fn set_up_berry_bin(bin_dir: &Path) -> io::Result<()> {
+            set_executable_permissions(&bin_dir.join("yarn"))
+        }
     }
 }
 
 pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Yarn>>) -> Fallible<()> {
-    let yarn_dir = volta_home()?.yarn_inventory_dir();
+    let _lock = VoltaLock::acquire()?;
+    let home = volta_home()?;
+    if fetched_while_waiting("yarn", version, &home.yarn_image_dir(&version.to_string())) {
+        return Ok(());
+    }
+    let yarn_dir = home.yarn_inventory_dir();
     let cache_file = yarn_dir.join(Yarn::archive_filename(&version.to_string()));
+    copy_shared_archive("yarn", version, &cache_file, || upstream_checksum(version))?;
+
+    verify_locked_archive("yarn", version, &cache_file, || {
+        determine_remote_url(version, hooks)
+    })?;
 
     let (archive, staging) = match load_cached_distro(&cache_file) {
         Some(archive) => {
@@ -69,22 +114,54 @@ pub fn fetch(version: &Version, hooks: Option<&ToolHooks<Yarn>>) -> Fallible<()>
     Ok(())
 }
 
+/// Returns the archive of a version in the inventory, downloading it if needed
+pub fn inventory_archive(version: &Version, hooks: Option<&ToolHooks<Yarn>>) -> Fallible<PathBuf> {
+    let _lock = VoltaLock::acquire()?;
+    let yarn_dir = volta_home()?.yarn_inventory_dir();
+    let cache_file = yarn_dir.join(Yarn::archive_filename(&version.to_string()));
+    copy_shared_archive("yarn", version, &cache_file, || upstream_checksum(version))?;
+
+    if !cache_file.is_file() {
+        download_archive(
+            "yarn",
+            version,
+            &determine_remote_url(version, hooks)?,
+            &cache_file,
+            None,
+        )?;
+    }
+    Ok(cache_file)
+}
+
+/// Returns where the archive of a version is downloaded from and kept in the inventory
+pub(super) fn remote_archive(
+    version: &Version,
+    hooks: Option<&ToolHooks<Yarn>>,
+) -> Fallible<RemoteArchive> {
+    let yarn_dir = volta_home()?.yarn_inventory_dir();
+    Ok(RemoteArchive {
+        tool: "yarn",
+        version: version.clone(),
+        url: determine_remote_url(version, hooks)?,
+        cache_file: yarn_dir.join(Yarn::archive_filename(&version.to_string())),
+    })
+}
+
 /// Unpack the yarn archive into the image directory so that it is ready for use
 fn unpack_archive(archive: Box<dyn Archive>, version: &Version) -> Fallible<()> {
     let temp = create_staging_dir()?;
     debug!("Unpacking yarn into '{}'", temp.path().display());
 
-    let progress = progress_bar(
-        archive.origin(),
-        &tool_version("yarn", version),
-        archive
-            .uncompressed_size()
-            .unwrap_or_else(|| archive.compressed_size()),
-    );
+    let size = archive
+        .uncompressed_size()
+        .unwrap_or_else(|| archive.compressed_size());
+    check_unpack_space(archive.as_ref(), temp.path())?;
+
+    let progress = progress_bar(archive.origin(), &tool_version("yarn", version), size);
     let version_string = version.to_string();
 
     archive
-        .unpack(temp.path(), &mut |_, read| {
+        .unpack(&long_path(temp.path()), &mut |_, read| {
             progress.inc(read as u64);
         })
         .with_context(|_| ErrorDetails::UnpackArchiveError {
@@ -96,15 +173,23 @@ fn unpack_archive(archive: Box<dyn Archive>, version: &Version) -> Fallible<()>
     ensure_containing_dir_exists(&dest)
         .with_context(|_| ErrorDetails::ContainingDirError { path: dest.clone() })?;
 
-    rename(
-        temp.path().join(Yarn::archive_basename(&version_string)),
-        &dest,
-    )
-    .with_context(|_| ErrorDetails::SetupToolImageError {
+    // Packages from the npm registry are always unpacked into a `package` directory
+    let unpacked = if is_berry(version) {
+        temp.path().join("package")
+    } else {
+        temp.path().join(Yarn::archive_basename(&version_string))
+    };
+    let setup_error = || ErrorDetails::SetupToolImageError {
         tool: "Yarn".into(),
         version: version_string.clone(),
         dir: dest.clone(),
-    })?;
+    };
+
+    clear_quarantine(&unpacked);
+    rename(long_path(unpacked), long_path(&dest)).with_context(|_| setup_error())?;
+    if is_berry(version) {
+        set_up_berry_bin(&dest.join("bin")).with_context(|_| setup_error())?;
+    }
 
     progress.finish_and_clear();
 
@@ -129,7 +214,11 @@ fn load_cached_distro(file: &PathBuf) -> Option<Box<dyn Archive>> {
 /// Determine the remote URL to download from, using the hooks if available
 fn determine_remote_url(version: &Version, hooks: Option<&ToolHooks<Yarn>>) -> Fallible<String> {
     let version_str = version.to_string();
-    let distro_file_name = Yarn::archive_filename(&version_str);
+    let distro_file_name = if is_berry(version) {
+        Yarn::berry_archive_filename(&version_str)
+    } else {
+        Yarn::archive_filename(&version_str)
+    };
     match hooks {
         Some(&ToolHooks {
             distro: Some(ref hook),
@@ -138,6 +227,7 @@ fn determine_remote_url(version: &Version, hooks: Option<&ToolHooks<Yarn>>) -> F
             debug!("Using yarn.distro hook to determine download URL");
             hook.resolve(&version, &distro_file_name)
         }
+        _ if is_berry(version) => Ok(format!("{}/{}", public_yarn_berry_root(), distro_file_name)),
         _ => Ok(format!(
             "{}/v{}/{}",
             public_yarn_server_root(),
@@ -147,12 +237,25 @@ fn determine_remote_url(version: &Version, hooks: Option<&ToolHooks<Yarn>>) -> F
     }
 }
 
+/// Fetches the checksum that is published for the archive of a version
+///
+/// Yarn 2 and later come from the npm registry, which publishes checksums, but the release
+/// tarballs of Yarn 1 only have signatures.
+pub(super) fn upstream_checksum(version: &Version) -> Fallible<Option<Checksum>> {
+    if is_berry(version) {
+        fetch_registry_checksum("yarn", "@yarnpkg/cli-dist", version)
+    } else {
+        Ok(None)
+    }
+}
+
 /// Fetch the distro archive from the internet
 fn fetch_remote_distro(
     version: &Version,
     url: &str,
     staging_path: &Path,
 ) -> Fallible<Box<dyn Archive>> {
+    check_online(url)?;
     debug!("Downloading {} from {}", tool_version("yarn", version), url);
     Tarball::fetch(url, staging_path).with_context(download_tool_error(
         tool::Spec::Yarn(VersionSpec::Exact(version.clone())),
@@ -1,9 +1,13 @@
 use std::fmt::{self, Display};
+use std::path::PathBuf;
 
+use super::prefetch::RemoteArchive;
 use super::{
     debug_already_fetched, info_fetched, info_installed, info_pinned, info_project_version, Tool,
 };
+use crate::checksum::Checksum;
 use crate::error::ErrorDetails;
+use crate::layout::layered_path;
 use crate::session::Session;
 use crate::style::tool_version;
 use semver::Version;
@@ -34,9 +38,26 @@ impl Yarn {
         format!("{}.tar.gz", Yarn::archive_basename(version))
     }
 
+    /// The filename of the Yarn Berry tarball published to the npm registry
+    pub fn berry_archive_filename(version: &str) -> String {
+        format!("cli-dist-{}.tgz", version)
+    }
+
+    /// Whether this version can be used without downloading it, either because it is unpacked
+    /// or because its archive is in the inventory
+    pub(crate) fn is_fetched(&self, session: &Session) -> Fallible<bool> {
+        // Checking for the unpacked image first avoids reading the whole inventory, which
+        // matters since this runs every time a shim is used
+        let version_str = self.version.to_string();
+        if layered_path(|home| home.yarn_image_dir(&version_str))?.is_dir() {
+            return Ok(true);
+        }
+
+        Ok(session.inventory()?.yarn.versions.contains(&self.version))
+    }
+
     pub(crate) fn fetch_internal(&self, session: &mut Session) -> Fallible<()> {
-        let inventory = session.inventory()?;
-        if inventory.yarn.versions.contains(&self.version) {
+        if self.is_fetched(session)? {
             debug_already_fetched(self);
             return Ok(());
         }
@@ -50,6 +71,26 @@ impl Yarn {
 
         Ok(())
     }
+
+    /// Returns the archive of this version in the inventory, downloading it if needed, so that
+    /// its checksum can be recorded in a lockfile
+    pub(crate) fn inventory_archive(&self, session: &Session) -> Fallible<PathBuf> {
+        fetch::inventory_archive(&self.version, session.hooks()?.yarn())
+    }
+    /// Fetches the checksum that is published for the archive of this version, which only the
+    /// versions from the npm registry have
+    pub(crate) fn published_checksum(&self) -> Fallible<Option<Checksum>> {
+        fetch::upstream_checksum(&self.version)
+    }
+
+    /// Returns the archive that fetching this version would download, unless the version is
+    /// already fetched
+    pub(crate) fn remote_archive(&self, session: &Session) -> Fallible<Option<RemoteArchive>> {
+        if self.is_fetched(session)? {
+            return Ok(None);
+        }
+        fetch::remote_archive(&self.version, session.hooks()?.yarn()).map(Some)
+    }
 }
 
 impl Tool for Yarn {
@@ -89,6 +130,12 @@ impl Tool for Yarn {
     }
 }
 
+/// Returns true if the version is Yarn 2 or later (Yarn Berry), which is published to the npm
+/// registry as `@yarnpkg/cli-dist`, instead of as a standalone release tarball like Yarn 1
+fn is_berry(version: &Version) -> bool {
+    version.major >= 2
+}
+
 impl Display for Yarn {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(&tool_version("yarn", &self.version))
@@ -108,4 +155,11 @@ mod tests {
     fn test_yarn_archive_filename() {
         assert_eq!(Yarn::archive_filename("1.2.3"), "yarn-v1.2.3.tar.gz");
     }
+
+    #[test]
+    fn test_is_berry() {
+        assert!(!is_berry(&Version::parse("1.22.4").unwrap()));
+        assert!(is_berry(&Version::parse("2.0.0").unwrap()));
+        assert!(is_berry(&Version::parse("4.1.1").unwrap()));
+    }
 }
@@ -5,11 +5,14 @@ use std::collections::BTreeSet;
 use super::super::registry_fetch_error;
 use super::serial;
 use crate::error::ErrorDetails;
+use crate::hook::tool::IndexFormat;
 use crate::hook::ToolHooks;
 use crate::session::Session;
+use crate::settings::check_online;
 use crate::style::progress_spinner;
 use crate::tool::Yarn;
 use crate::version::{parse_version, VersionSpec, VersionTag};
+use archive::http_client;
 use cfg_if::cfg_if;
 use log::debug;
 use semver::{Version, VersionReq};
@@ -24,6 +27,9 @@ cfg_if! {
         fn public_yarn_latest_version() -> String {
             format!("{}/yarn-latest", mockito::SERVER_URL)
         }
+        fn public_yarn_berry_index() -> String {
+            format!("{}/yarn-berry/index.json", mockito::SERVER_URL)
+        }
     } else {
         /// Return the URL of the index of available Yarn versions on the public git repository.
         fn public_yarn_version_index() -> String {
@@ -33,6 +39,10 @@ cfg_if! {
         fn public_yarn_latest_version() -> String {
             "https://yarnpkg.com/latest-version".to_string()
         }
+        /// URL of the npm registry document for Yarn 2 and later, which aren't GitHub releases
+        fn public_yarn_berry_index() -> String {
+            "https://registry.npmjs.org/@yarnpkg/cli-dist".to_string()
+        }
     }
 }
 
@@ -42,6 +52,7 @@ pub fn resolve(matching: VersionSpec, session: &mut Session) -> Fallible<Version
         VersionSpec::Semver(requirement) => resolve_semver(requirement, hooks),
         VersionSpec::Exact(version) => Ok(version),
         VersionSpec::None | VersionSpec::Tag(VersionTag::Latest) => resolve_latest(hooks),
+        VersionSpec::Tag(VersionTag::Custom(ref tag)) if tag == "berry" => resolve_berry_latest(),
         VersionSpec::Tag(tag) => Err(ErrorDetails::YarnVersionNotFound {
             matching: tag.to_string(),
         }
@@ -60,7 +71,10 @@ fn resolve_latest(hooks: Option<&ToolHooks<Yarn>>) -> Fallible<Version> {
         }
         _ => public_yarn_latest_version(),
     };
-    let response_text = reqwest::get(&url)
+    check_online(&url)?;
+    let response_text = http_client()
+        .get(&url)
+        .send()
         .and_then(|mut resp| resp.text())
         .with_context(|_| ErrorDetails::YarnLatestFetchError {
             from_url: url.clone(),
@@ -71,25 +85,49 @@ fn resolve_latest(hooks: Option<&ToolHooks<Yarn>>) -> Fallible<Version> {
 }
 
 fn resolve_semver(matching: VersionReq, hooks: Option<&ToolHooks<Yarn>>) -> Fallible<Version> {
-    let url = match hooks {
+    let has_index_hook = hooks.map_or(false, |hooks| hooks.index.is_some());
+    let (mut url, format) = match hooks {
         Some(&ToolHooks {
             index: Some(ref hook),
+            index_format,
             ..
         }) => {
             debug!("Using yarn.index hook to determine yarn index URL");
-            hook.resolve("releases")?
+            (
+                hook.resolve("releases")?,
+                index_format.unwrap_or(IndexFormat::Github),
+            )
         }
-        _ => public_yarn_version_index(),
+        _ => (public_yarn_version_index(), IndexFormat::Github),
     };
 
+    check_online(&url)?;
     let spinner = progress_spinner(&format!("Fetching public registry: {}", url));
-    let releases: serial::RawYarnIndex = reqwest::get(&url)
-        .and_then(|mut resp| resp.json())
+    let mut response = http_client()
+        .get(&url)
+        .send()
         .with_context(registry_fetch_error("Yarn", &url))?;
-    let index = YarnIndex::from(releases);
+    let index = match format {
+        IndexFormat::Github => response.json::<serial::RawYarnIndex>().map(YarnIndex::from),
+        IndexFormat::Npm => response
+            .json::<serial::RawYarnNpmIndex>()
+            .map(YarnIndex::from),
+    }
+    .with_context(registry_fetch_error("Yarn", &url))?;
     let releases = index.entries;
     spinner.finish_and_clear();
-    let version_opt = releases.into_iter().rev().find(|v| matching.matches(v));
+    let mut version_opt = releases.into_iter().rev().find(|v| matching.matches(v));
+
+    // The public index only lists Yarn 1 releases, so later versions are looked up separately
+    if version_opt.is_none() && !has_index_hook {
+        url = public_yarn_berry_index();
+        let berry = YarnIndex::from(fetch_berry_registry_document(&url)?);
+        version_opt = berry
+            .entries
+            .into_iter()
+            .rev()
+            .find(|v| matching.matches(v));
+    }
 
     match version_opt {
         Some(version) => {
@@ -106,6 +144,36 @@ fn resolve_semver(matching: VersionReq, hooks: Option<&ToolHooks<Yarn>>) -> Fall
     }
 }
 
+/// Resolves the `berry` tag to the latest release of Yarn 2 or later
+fn resolve_berry_latest() -> Fallible<Version> {
+    let url = public_yarn_berry_index();
+    let index = fetch_berry_registry_document(&url)?;
+
+    match index.dist_tags.get("latest") {
+        Some(latest) => {
+            debug!("Found yarn berry latest version ({}) from {}", latest, url);
+            parse_version(latest)
+        }
+        None => Err(ErrorDetails::YarnVersionNotFound {
+            matching: "berry".into(),
+        }
+        .into()),
+    }
+}
+
+/// Fetches the npm registry document listing the versions of Yarn 2 and later
+fn fetch_berry_registry_document(url: &str) -> Fallible<serial::RawYarnNpmIndex> {
+    check_online(url)?;
+    let spinner = progress_spinner(&format!("Fetching public registry: {}", url));
+    let index = http_client()
+        .get(url)
+        .send()
+        .and_then(|mut response| response.json::<serial::RawYarnNpmIndex>())
+        .with_context(registry_fetch_error("Yarn", url))?;
+    spinner.finish_and_clear();
+    Ok(index)
+}
+
 /// The public Yarn index.
 pub struct YarnIndex {
     pub(super) entries: BTreeSet<Version>,
@@ -1,8 +1,9 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 
 use super::resolve::YarnIndex;
 use crate::version::version_serde;
 use semver::Version;
+use serde::de::IgnoredAny;
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -49,3 +50,87 @@ impl From<RawYarnIndex> for YarnIndex {
         YarnIndex { entries }
     }
 }
+
+/// A Yarn index in the format of the npm registry's package document, as served by mirrors
+/// of the registry, or by the registry itself for Yarn 2 and later. Only the keys of the
+/// `versions` map and the `dist-tags` are needed.
+#[derive(Deserialize)]
+pub struct RawYarnNpmIndex {
+    pub versions: HashMap<String, IgnoredAny>,
+    #[serde(default, rename = "dist-tags")]
+    pub dist_tags: HashMap<String, String>,
+}
+
+impl From<RawYarnNpmIndex> for YarnIndex {
+    fn from(raw: RawYarnNpmIndex) -> YarnIndex {
+        let entries = raw
+            .versions
+            .keys()
+            .filter_map(|version| Version::parse(version).ok())
+            .collect();
+        YarnIndex { entries }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const GITHUB_INDEX: &str = r#"[
+        {"tag_name":"v1.2.42","assets":[{"name":"yarn-v1.2.42.tar.gz"}]},
+        {"tag_name":"v1.3.1","assets":[{"name":"yarn-1.3.1.msi"}]},
+        {"tag_name":"v1.4.159","assets":[{"name":"yarn-v1.4.159.tar.gz"},{"name":"yarn-1.4.159.msi"}]}
+    ]"#;
+
+    const NPM_INDEX: &str = r#"{
+        "name": "yarn",
+        "dist-tags": { "latest": "1.22.4", "berry": "2.0.0-rc.29" },
+        "versions": {
+            "1.2.42": { "name": "yarn", "version": "1.2.42", "dist": { "tarball": "yarn-1.2.42.tgz" } },
+            "1.22.4": { "name": "yarn", "version": "1.22.4", "dist": { "tarball": "yarn-1.22.4.tgz" } },
+            "not-a-version": { "name": "yarn" }
+        },
+        "time": { "1.22.4": "2020-03-11T10:55:45.478Z" }
+    }"#;
+
+    #[test]
+    fn test_github_index_only_includes_full_releases() {
+        let raw: RawYarnIndex = serde_json::from_str(GITHUB_INDEX).unwrap();
+        let index = YarnIndex::from(raw);
+
+        assert_eq!(
+            index.entries.into_iter().collect::<Vec<_>>(),
+            vec![
+                Version::parse("1.2.42").unwrap(),
+                Version::parse("1.4.159").unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_npm_index_versions() {
+        let raw: RawYarnNpmIndex = serde_json::from_str(NPM_INDEX).unwrap();
+        assert_eq!(
+            raw.dist_tags.get("latest").map(String::as_str),
+            Some("1.22.4")
+        );
+
+        let index = YarnIndex::from(raw);
+        assert_eq!(
+            index.entries.into_iter().collect::<Vec<_>>(),
+            vec![
+                Version::parse("1.2.42").unwrap(),
+                Version::parse("1.22.4").unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_npm_index_without_dist_tags() {
+        let raw: RawYarnNpmIndex =
+            serde_json::from_str(r#"{ "versions": { "1.22.4": {} } }"#).unwrap();
+
+        assert!(raw.dist_tags.is_empty());
+        assert_eq!(YarnIndex::from(raw).entries.len(), 1);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs::write;
 
 use lazycell::LazyCell;
@@ -6,9 +7,13 @@ use semver::Version;
 
 use crate::error::ErrorDetails;
 use crate::fs::touch;
+use crate::history::{Action, Entry};
+use crate::integrity;
 use crate::layout::volta_home;
 use crate::platform::PlatformSpec;
+use crate::sync::VoltaLock;
 use crate::tool::NodeVersion;
+use crate::trace;
 
 use log::debug;
 use volta_fail::{Fallible, ResultExt};
@@ -41,31 +46,67 @@ impl LazyToolchain {
 
 pub struct Toolchain {
     platform: Option<PlatformSpec>,
+    tools: BTreeMap<String, Version>,
 }
 
 impl Toolchain {
     fn current() -> Fallible<Toolchain> {
-        let path = volta_home()?.default_platform_file();
+        let _phase = trace::phase("toolchain read");
+        let home = volta_home()?;
+        let path = home.default_platform_file();
+        integrity::verify(home, path)?;
+        let created = !path.exists();
         let src = touch(&path)
             .and_then(|mut file| file.read_into_string())
             .with_context(|_| ErrorDetails::ReadPlatformError {
                 file: path.to_owned(),
             })?;
+        if created {
+            // The file that was just created is signed, as it would be if Volta saved it
+            integrity::sign(home, path)?;
+        }
 
-        let platform = serial::Platform::from_json(src)?.into_platform()?;
+        let serial = serial::Platform::from_json(src)?;
+        let tools = serial.custom_tools()?;
+        let platform = serial.into_platform()?;
         if platform.is_some() {
             debug!("Found default configuration at '{}'", path.display());
         }
-        Ok(Toolchain { platform })
+        Ok(Toolchain { platform, tools })
     }
 
     pub fn platform_ref(&self) -> Option<&PlatformSpec> {
         self.platform.as_ref()
     }
 
+    /// Returns the default version of the named custom tool, if any.
+    pub fn custom_tool(&self, name: &str) -> Option<&Version> {
+        self.tools.get(name)
+    }
+
+    /// Returns the default versions of all custom tools, by name.
+    pub fn custom_tools(&self) -> &BTreeMap<String, Version> {
+        &self.tools
+    }
+
+    /// Set the active version of a custom tool in the default platform file.
+    pub fn set_active_custom_tool(&mut self, name: &str, version: &Version) -> Fallible<()> {
+        if self.tools.get(name) != Some(version) {
+            let previous = self.tools.insert(name.to_string(), version.clone());
+            self.save()?;
+            Entry::new(Action::Default, name, previous.as_ref(), Some(version)).record();
+        }
+
+        Ok(())
+    }
+
     /// Set the active Node version in the default platform file.
     pub fn set_active_node(&mut self, node_version: &NodeVersion) -> Fallible<()> {
         let mut dirty = false;
+        let previous = self
+            .platform
+            .as_ref()
+            .map(|platform| platform.node_runtime.clone());
 
         if let Some(ref mut platform) = self.platform {
             if platform.node_runtime != node_version.runtime {
@@ -82,12 +123,48 @@ impl Toolchain {
                 node_runtime: node_version.runtime.clone(),
                 npm: Some(node_version.npm.clone()),
                 yarn: None,
+                pnpm: None,
             });
             dirty = true;
         }
 
         if dirty {
             self.save()?;
+            if previous.as_ref() != Some(&node_version.runtime) {
+                Entry::new(
+                    Action::Default,
+                    "node",
+                    previous.as_ref(),
+                    Some(&node_version.runtime),
+                )
+                .record();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Set the active pnpm version in the default platform file.
+    pub fn set_active_pnpm(&mut self, pnpm_version: &Version) -> Fallible<()> {
+        let mut dirty = false;
+        let mut previous = None;
+
+        if let Some(ref mut platform) = self.platform {
+            if platform.pnpm.as_ref() != Some(pnpm_version) {
+                previous = platform.pnpm.replace(pnpm_version.clone());
+                dirty = true;
+            }
+        }
+
+        if dirty {
+            self.save()?;
+            Entry::new(
+                Action::Default,
+                "pnpm",
+                previous.as_ref(),
+                Some(pnpm_version),
+            )
+            .record();
         }
 
         Ok(())
@@ -96,16 +173,24 @@ impl Toolchain {
     /// Set the active Yarn version in the default platform file.
     pub fn set_active_yarn(&mut self, yarn_version: &Version) -> Fallible<()> {
         let mut dirty = false;
+        let mut previous = None;
 
         if let Some(ref mut platform) = self.platform {
             if platform.yarn.as_ref() != Some(yarn_version) {
-                platform.yarn = Some(yarn_version.clone());
+                previous = platform.yarn.replace(yarn_version.clone());
                 dirty = true;
             }
         }
 
         if dirty {
             self.save()?;
+            Entry::new(
+                Action::Default,
+                "yarn",
+                previous.as_ref(),
+                Some(yarn_version),
+            )
+            .record();
         }
 
         Ok(())
@@ -114,34 +199,53 @@ impl Toolchain {
     /// Set the active Npm version in the default platform file.
     pub fn set_active_npm(&mut self, npm_version: &Version) -> Fallible<()> {
         let mut dirty = false;
+        let mut previous = None;
 
         if let Some(ref mut platform) = self.platform {
-            if let Some(ref npm) = &platform.npm {
-                if npm != npm_version {
-                    platform.npm = Some(npm_version.clone());
-                    dirty = true;
-                }
+            if platform.npm.as_ref() != Some(npm_version) {
+                previous = platform.npm.replace(npm_version.clone());
+                dirty = true;
             }
         }
 
         if dirty {
             self.save()?;
+            Entry::new(Action::Default, "npm", previous.as_ref(), Some(npm_version)).record();
         }
 
         Ok(())
     }
 
     pub fn save(&self) -> Fallible<()> {
+        let _lock = VoltaLock::acquire()?;
         let path = volta_home()?.default_platform_file();
         let result = match &self.platform {
             Some(platform) => {
-                let src = platform.to_serial().into_json()?;
-                write(&path, src)
+                let mut serial = platform.to_serial();
+                serial.tools = self.serial_tools();
+                write(&path, serial.into_json()?)
+            }
+            None if !self.tools.is_empty() => {
+                let serial = serial::Platform {
+                    node: None,
+                    yarn: None,
+                    pnpm: None,
+                    tools: self.serial_tools(),
+                };
+                write(&path, serial.into_json()?)
             }
             None => write(&path, "{}"),
         };
         result.with_context(|_| ErrorDetails::WritePlatformError {
             file: path.to_owned(),
-        })
+        })?;
+        integrity::sign(volta_home()?, path)
+    }
+
+    fn serial_tools(&self) -> BTreeMap<String, String> {
+        self.tools
+            .iter()
+            .map(|(name, version)| (name.clone(), version.to_string()))
+            .collect()
     }
 }
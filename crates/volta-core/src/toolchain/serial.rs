@@ -1,7 +1,9 @@
+use std::collections::BTreeMap;
+
 use crate::platform::PlatformSpec;
 
 use crate::error::ErrorDetails;
-use crate::version::{option_version_serde, version_serde};
+use crate::version::{option_version_serde, parse_version, version_serde};
 use volta_fail::{Fallible, ResultExt};
 
 use semver::Version;
@@ -23,15 +25,31 @@ pub struct Platform {
     #[serde(default)]
     #[serde(with = "option_version_serde")]
     pub yarn: Option<Version>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(with = "option_version_serde")]
+    pub pnpm: Option<Version>,
+    /// The default versions of custom tools, which don't depend on the Node version
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tools: BTreeMap<String, String>,
 }
 
 impl Platform {
+    /// Parses the default versions of custom tools
+    pub fn custom_tools(&self) -> Fallible<BTreeMap<String, Version>> {
+        self.tools
+            .iter()
+            .map(|(name, version)| Ok((name.clone(), parse_version(version)?)))
+            .collect()
+    }
+
     pub fn into_platform(self) -> Fallible<Option<PlatformSpec>> {
         let yarn = self.yarn;
+        let pnpm = self.pnpm;
         Ok(self.node.map(|node_version| PlatformSpec {
             node_runtime: node_version.runtime,
             npm: node_version.npm,
             yarn,
+            pnpm,
         }))
     }
 
@@ -60,6 +78,8 @@ impl PlatformSpec {
                 npm: self.npm.clone(),
             }),
             yarn: self.yarn.clone(),
+            pnpm: self.pnpm.clone(),
+            tools: BTreeMap::new(),
         }
     }
 }
@@ -92,6 +112,8 @@ pub mod tests {
                 runtime: Version::parse("4.5.6").expect("could not parse version"),
                 npm: Some(Version::parse("7.8.9").expect("could not parse version")),
             }),
+            pnpm: None,
+            tools: BTreeMap::new(),
         };
         assert_eq!(platform, expected_platform);
     }
@@ -103,6 +125,8 @@ pub mod tests {
         let expected_platform = Platform {
             node: None,
             yarn: None,
+            pnpm: None,
+            tools: BTreeMap::new(),
         };
         assert_eq!(platform, expected_platform);
     }
@@ -113,6 +137,7 @@ pub mod tests {
             yarn: Some(Version::parse("1.2.3").expect("could not parse version")),
             node_runtime: Version::parse("4.5.6").expect("could not parse version"),
             npm: Some(Version::parse("7.8.9").expect("could not parse version")),
+            pnpm: None,
         };
         let json_str = platform
             .to_serial()
@@ -0,0 +1,127 @@
+//! Provides opt-in timing of the phases of running a tool through a shim.
+//!
+//! When `VOLTA_TRACE` is enabled, each instrumented phase (project detection, reading the
+//! toolchain and inventory, building the `PATH` and running the tool) records how long it took,
+//! and the shim prints the timings when it exits, to stderr or to the file named by
+//! `VOLTA_TRACE_FILE`. This makes it possible to see where the time goes when a shimmed tool is
+//! slow to start.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::env_vars;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref ENABLED: bool = env_vars::flag(env_vars::VOLTA_TRACE).unwrap_or(false);
+    static ref START: Instant = Instant::now();
+    static ref TIMINGS: Mutex<Vec<Timing>> = Mutex::new(Vec::new());
+}
+
+/// The recorded duration of a single phase
+struct Timing {
+    phase: &'static str,
+    start: Duration,
+    duration: Duration,
+}
+
+/// A phase being timed, which is recorded when it is dropped
+pub struct Phase {
+    name: &'static str,
+    start: Instant,
+}
+
+/// Starts timing a phase, if tracing is enabled
+pub fn phase(name: &'static str) -> Option<Phase> {
+    if *ENABLED {
+        // Make sure that the start of the trace is no later than the first phase
+        lazy_static::initialize(&START);
+        Some(Phase {
+            name,
+            start: Instant::now(),
+        })
+    } else {
+        None
+    }
+}
+
+impl Drop for Phase {
+    fn drop(&mut self) {
+        let timing = Timing {
+            phase: self.name,
+            start: self.start.duration_since(*START),
+            duration: self.start.elapsed(),
+        };
+        if let Ok(mut timings) = TIMINGS.lock() {
+            timings.push(timing);
+        }
+    }
+}
+
+/// Writes the recorded timings, if tracing is enabled
+pub fn report() {
+    if !*ENABLED {
+        return;
+    }
+
+    let timings = match TIMINGS.lock() {
+        Ok(timings) => format_timings(&timings),
+        Err(_) => return,
+    };
+
+    // Tracing is only a diagnostic aid, so failing to write it shouldn't affect the tool
+    let _ = match env_vars::os_string(env_vars::VOLTA_TRACE_FILE) {
+        Some(path) => OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(timings.as_bytes())),
+        None => io::stderr().write_all(timings.as_bytes()),
+    };
+}
+
+/// Formats the timings in the order the phases started, with their offset and duration in
+/// microseconds. Phases that ran inside other phases are listed after the phase containing them.
+fn format_timings(timings: &[Timing]) -> String {
+    let mut sorted: Vec<&Timing> = timings.iter().collect();
+    sorted.sort_by_key(|timing| (timing.start, std::cmp::Reverse(timing.duration)));
+
+    let mut output = String::from("[volta trace]        start (us)  duration (us)  phase\n");
+    for timing in sorted {
+        output.push_str(&format!(
+            "[volta trace] {:>17} {:>14}  {}\n",
+            timing.start.as_micros(),
+            timing.duration.as_micros(),
+            timing.phase
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timings() {
+        let timings = vec![
+            Timing {
+                phase: "PATH construction",
+                start: Duration::from_micros(1500),
+                duration: Duration::from_micros(20),
+            },
+            Timing {
+                phase: "project detection",
+                start: Duration::from_micros(100),
+                duration: Duration::from_micros(850),
+            },
+        ];
+        let lines: Vec<String> = format_timings(&timings).lines().map(String::from).collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with("100            850  project detection"));
+        assert!(lines[2].ends_with("1500             20  PATH construction"));
+    }
+}
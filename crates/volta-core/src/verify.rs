@@ -0,0 +1,461 @@
+//! Provides the integrity checks behind `volta verify`, which look for damaged tool versions:
+//! images that are missing or incomplete, archives that no longer match their checksums, and
+//! package executables that can't be found.
+//!
+//! The archives of Node, npm, Yarn and pnpm in the inventory are hashed again and checked against
+//! the project's lockfile, or else against the checksum that their server publishes, when it
+//! publishes one and Volta isn't offline. Package archives are checked against the checksum
+//! recorded when they were fetched.
+//!
+//! A damaged version is repaired by fetching it again, the same way it was fetched in the first
+//! place. Its files are moved aside first and only removed once the new version is in place, so
+//! that a version that can't be fetched again is left as it was.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::checksum::Checksum;
+use crate::doctor::Status;
+use crate::error::ErrorDetails;
+use crate::fs::{create_staging_dir, read_file};
+use crate::image::ToolImage;
+use crate::layout::{long_path, volta_home};
+use crate::lockfile::{self, can_verify, file_checksum, locked_checksum, LOCKFILE_NAME};
+use crate::session::Session;
+use crate::settings::settings;
+use crate::store;
+use crate::tool::{
+    bin_full_path, load_default_npm_version, zstd_archive_file, BinConfig, Node, Npm,
+    PackageConfig, Pnpm, Spec, Yarn,
+};
+use crate::version::VersionSpec;
+use log::debug;
+use semver::Version;
+use serde::Serialize;
+use tempfile::TempDir;
+use volta_fail::{throw, Fallible, ResultExt};
+
+/// The entries that every npm or Yarn image has, since both are unpacked npm packages
+const PACKAGE_IMAGE_ENTRIES: &[&str] = &["package.json", "bin"];
+
+/// The entries of a pnpm image, which only holds the standalone executable
+const PNPM_IMAGE_ENTRIES: &[&str] = &["bin"];
+
+/// A tool version in the inventory
+#[derive(Debug)]
+enum Target {
+    Node(Version),
+    Npm(Version),
+    Yarn(Version),
+    Pnpm(Version),
+    Package {
+        name: String,
+        version: Version,
+        linked: bool,
+    },
+}
+
+/// The result of verifying one tool version, along with the problems that were found
+#[derive(Debug, Serialize)]
+pub struct Item {
+    pub name: String,
+    pub status: Status,
+    pub problems: Vec<String>,
+    #[serde(skip)]
+    target: Target,
+}
+
+impl Item {
+    fn new(target: Target) -> Self {
+        let name = match &target {
+            Target::Node(version) => format!("node@{}", version),
+            Target::Npm(version) => format!("npm@{}", version),
+            Target::Yarn(version) => format!("yarn@{}", version),
+            Target::Pnpm(version) => format!("pnpm@{}", version),
+            Target::Package { name, version, .. } => format!("{}@{}", name, version),
+        };
+
+        Item {
+            name,
+            status: Status::Pass,
+            problems: Vec::new(),
+            target,
+        }
+    }
+
+    fn warn(&mut self, problem: impl Into<String>) {
+        self.status = self.status.max(Status::Warn);
+        self.problems.push(problem.into());
+    }
+
+    fn fail(&mut self, problem: impl Into<String>) {
+        self.status = Status::Fail;
+        self.problems.push(problem.into());
+    }
+
+    /// Whether any problem was found with this version
+    pub fn needs_repair(&self) -> bool {
+        self.status != Status::Pass
+    }
+
+    /// Fetches this version again, replacing the damaged one once the new one is in place
+    ///
+    /// Packages are reinstalled, so that their executables and shims are set up again too.
+    pub fn repair(&self, session: &mut Session) -> Fallible<()> {
+        let home = volta_home()?;
+        let mut aside = Aside::new()?;
+        let result = match &self.target {
+            Target::Node(version) => {
+                let version_string = version.to_string();
+                if let Ok(npm) = load_default_npm_version(version) {
+                    aside.push(home.node_image_dir(&version_string, &npm.to_string()))?;
+                }
+                let archive = home
+                    .node_inventory_dir()
+                    .join(Node::archive_filename(&version_string));
+                aside.push(zstd_archive_file(&archive))?;
+                aside.push(archive)?;
+                session.inventory_mut()?.node.versions.remove(version);
+
+                Spec::Node(VersionSpec::Exact(version.clone()))
+                    .resolve(session)
+                    .and_then(|tool| tool.fetch(session))
+            }
+            Target::Npm(version) => {
+                let version_string = version.to_string();
+                aside.push(home.npm_image_dir(&version_string))?;
+                aside.push(
+                    home.npm_inventory_dir()
+                        .join(Npm::archive_filename(&version_string)),
+                )?;
+                session.inventory_mut()?.npm.versions.remove(version);
+
+                Spec::Npm(VersionSpec::Exact(version.clone()))
+                    .resolve(session)
+                    .and_then(|tool| tool.fetch(session))
+            }
+            Target::Yarn(version) => {
+                let version_string = version.to_string();
+                aside.push(home.yarn_image_dir(&version_string))?;
+                aside.push(
+                    home.yarn_inventory_dir()
+                        .join(Yarn::archive_filename(&version_string)),
+                )?;
+                session.inventory_mut()?.yarn.versions.remove(version);
+
+                Spec::Yarn(VersionSpec::Exact(version.clone()))
+                    .resolve(session)
+                    .and_then(|tool| tool.fetch(session))
+            }
+            Target::Pnpm(version) => {
+                let version_string = version.to_string();
+                aside.push(home.pnpm_image_dir(&version_string))?;
+                aside.push(
+                    home.pnpm_inventory_dir()
+                        .join(Pnpm::archive_filename(&version_string)),
+                )?;
+                session.inventory_mut()?.pnpm.versions.remove(version);
+
+                Spec::Pnpm(VersionSpec::Exact(version.clone()))
+                    .resolve(session)
+                    .and_then(|tool| tool.fetch(session))
+            }
+            Target::Package { name, linked, .. } if *linked => {
+                throw!(ErrorDetails::RepairLinkedPackageError {
+                    package: name.clone()
+                })
+            }
+            Target::Package { name, version, .. } => {
+                // The archive is fetched again by the installation if it doesn't match its
+                // checksum, so only the images need to be replaced, along with the configuration
+                // that marks the package as installed
+                let version_string = version.to_string();
+                if let Some(node) = package_node(name, version)? {
+                    aside.push(home.package_platform_image_dir(name, &version_string, &node))?;
+                }
+                aside.push(home.package_image_dir(name, &version_string))?;
+                aside.push(home.default_package_config_file(name))?;
+
+                Spec::Package(name.clone(), VersionSpec::Exact(version.clone()))
+                    .resolve(session)
+                    .and_then(|tool| tool.install(session))
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                drop(aside);
+                if let Target::Node(_) = self.target {
+                    store::prune();
+                }
+                Ok(())
+            }
+            Err(error) => {
+                aside.restore();
+                Err(error)
+            }
+        }
+    }
+}
+
+/// The files of a damaged version, moved into a staging directory while the version is fetched
+/// again, so that they can be put back if that fails
+///
+/// They are removed along with the staging directory once it is dropped.
+struct Aside {
+    staging: TempDir,
+    moved: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Aside {
+    fn new() -> Fallible<Self> {
+        Ok(Aside {
+            staging: create_staging_dir()?,
+            moved: Vec::new(),
+        })
+    }
+
+    /// Moves a file or directory aside, if it exists
+    fn push(&mut self, path: PathBuf) -> Fallible<()> {
+        if fs::symlink_metadata(&path).is_err() {
+            return Ok(());
+        }
+
+        let moved = self.staging.path().join(self.moved.len().to_string());
+        fs::rename(long_path(&path), long_path(&moved))
+            .with_context(|_| ErrorDetails::RepairMoveAsideError { path: path.clone() })?;
+        self.moved.push((path, moved));
+        Ok(())
+    }
+
+    /// Puts the files back where they were, replacing anything that was fetched in the meantime
+    fn restore(self) {
+        for (path, moved) in self.moved.iter().rev() {
+            if path.is_dir() {
+                let _ = fs::remove_dir_all(long_path(path));
+            } else if path.exists() {
+                let _ = fs::remove_file(path);
+            }
+            if let Err(error) = fs::rename(long_path(moved), long_path(path)) {
+                debug!("Could not restore '{}': {}", path.display(), error);
+            }
+        }
+    }
+}
+
+/// The Node version that an installed package was installed with, as a string
+fn package_node(name: &str, version: &Version) -> Fallible<Option<String>> {
+    let config_file = volta_home()?.default_package_config_file(name);
+    Ok(PackageConfig::from_file(&config_file)
+        .ok()
+        .filter(|config| config.version == *version)
+        .map(|config| config.platform.node_runtime.to_string()))
+}
+
+/// Verifies every tool version in the inventory, as well as every installed package
+pub fn verify(session: &Session) -> Fallible<Vec<Item>> {
+    let inventory = session.inventory()?;
+    let home = volta_home()?;
+    let mut items = Vec::new();
+
+    for version in &inventory.node.versions {
+        let mut item = verify_node(version);
+        let archive = home
+            .node_inventory_dir()
+            .join(Node::archive_filename(&version.to_string()));
+        verify_archive(&mut item, "node", version, &archive, || {
+            Node::new(version.clone()).published_checksum(session)
+        })?;
+        items.push(item);
+    }
+    for version in &inventory.npm.versions {
+        let image = ToolImage::layered(|home| ToolImage::npm(home, version))?;
+        let mut item = Item::new(Target::Npm(version.clone()));
+        let archive = home
+            .npm_inventory_dir()
+            .join(Npm::archive_filename(&version.to_string()));
+        verify_archive(&mut item, "npm", version, &archive, || {
+            Npm::new(version.clone()).published_checksum()
+        })?;
+        items.push(verify_image(item, image.root(), PACKAGE_IMAGE_ENTRIES));
+    }
+    for version in &inventory.yarn.versions {
+        let image = ToolImage::layered(|home| ToolImage::yarn(home, version))?;
+        let mut item = Item::new(Target::Yarn(version.clone()));
+        let archive = home
+            .yarn_inventory_dir()
+            .join(Yarn::archive_filename(&version.to_string()));
+        verify_archive(&mut item, "yarn", version, &archive, || {
+            Yarn::new(version.clone()).published_checksum()
+        })?;
+        items.push(verify_image(item, image.root(), PACKAGE_IMAGE_ENTRIES));
+    }
+    for version in &inventory.pnpm.versions {
+        let image = ToolImage::layered(|home| ToolImage::pnpm(home, version))?;
+        let mut item = Item::new(Target::Pnpm(version.clone()));
+        let archive = home
+            .pnpm_inventory_dir()
+            .join(Pnpm::archive_filename(&version.to_string()));
+        // The pnpm releases don't publish checksums, so only a locked pnpm can be checked
+        verify_archive(&mut item, "pnpm", version, &archive, || Ok(None))?;
+        items.push(verify_image(item, image.root(), PNPM_IMAGE_ENTRIES));
+    }
+    for package in inventory.packages.iter() {
+        items.push(verify_package(package)?);
+    }
+
+    Ok(items)
+}
+
+/// Hashes the archive of a version in the inventory again, checking it against the checksum in
+/// the project's lockfile, or else against the one returned by `published`
+///
+/// A published checksum that can't be fetched is only logged, since the archive may well be
+/// intact, and archives that were recompressed can only be checked against a lockfile.
+fn verify_archive<F>(
+    item: &mut Item,
+    tool: &str,
+    version: &Version,
+    archive: &Path,
+    published: F,
+) -> Fallible<()>
+where
+    F: FnOnce() -> Fallible<Option<Checksum>>,
+{
+    if let Some(expected) = locked_checksum(tool, version, archive)? {
+        if can_verify(archive, &expected) {
+            let origin = archive.display().to_string();
+            if lockfile::verify_archive(tool, version, archive, &expected, &origin).is_err() {
+                item.fail(format!(
+                    "the archive doesn't match the checksum in {}",
+                    LOCKFILE_NAME
+                ));
+            }
+        }
+        return Ok(());
+    }
+
+    if !archive.is_file() || settings()?.offline {
+        return Ok(());
+    }
+    match published() {
+        Ok(Some(checksum)) => {
+            if !checksum.matches(archive)? {
+                item.fail("the archive doesn't match its published checksum");
+            }
+        }
+        Ok(None) => {}
+        Err(error) => debug!(
+            "Could not fetch the checksum to verify '{}': {}",
+            archive.display(),
+            error
+        ),
+    }
+    Ok(())
+}
+
+/// Checks that the image exists and has the entries that are expected at its root
+fn verify_image(mut item: Item, image_dir: &Path, entries: &[&str]) -> Item {
+    if !image_dir.is_dir() {
+        item.warn("the image is missing");
+        return item;
+    }
+
+    for entry in entries {
+        if !image_dir.join(entry).exists() {
+            item.fail(format!("the image has no `{}`", entry));
+        }
+    }
+    item
+}
+
+fn verify_package(config: &PackageConfig) -> Fallible<Item> {
+    let mut item = Item::new(Target::Package {
+        name: config.name.clone(),
+        version: config.version.clone(),
+        linked: config.link.is_some(),
+    });
+    let home = volta_home()?;
+    let version = config.version.to_string();
+
+    // Linked packages run from their working copy, so there is no archive or image to check
+    if config.link.is_none() {
+        let distro_file = home.package_distro_file(&config.name, &version);
+        let shasum_file = home.package_distro_shasum(&config.name, &version);
+        match read_file(&shasum_file).ok().and_then(|shasum| shasum) {
+            Some(_) if !distro_file.is_file() => {
+                item.warn("the package archive is missing from the inventory")
+            }
+            Some(recorded) => {
+                if file_checksum(&distro_file)? != recorded.trim() {
+                    item.fail("the package archive doesn't match its recorded checksum");
+                }
+            }
+            None => item.warn("no checksum is recorded for the package archive"),
+        }
+
+        let platform_image_dir = home.package_platform_image_dir(
+            &config.name,
+            &version,
+            &config.platform.node_runtime.to_string(),
+        );
+        if !platform_image_dir.is_dir() {
+            item.fail("the package image is missing");
+        }
+    }
+
+    for bin in &config.bins {
+        match BinConfig::from_file(home.default_tool_bin_config(bin)) {
+            Ok(bin_config) => {
+                let bin_path = bin_full_path(
+                    &config.name,
+                    &config.version,
+                    &bin_config.platform.node_runtime,
+                    bin,
+                    &bin_config.path,
+                    bin_config.link.as_deref(),
+                );
+                if !bin_path.map(|path| path.is_file()).unwrap_or(false) {
+                    item.fail(format!("the executable `{}` is missing", bin));
+                }
+            }
+            Err(_) => item.fail(format!(
+                "the configuration of the executable `{}` is missing",
+                bin
+            )),
+        }
+
+        if !home.shim_file(bin).exists() {
+            item.fail(format!("the shim for `{}` is missing", bin));
+        }
+    }
+
+    Ok(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_verify_image() {
+        let dir = tempdir().unwrap();
+        let image_dir = dir.path().join("6.14.8");
+        let item = || Item::new(Target::Npm(Version::parse("6.14.8").unwrap()));
+
+        let missing = verify_image(item(), &image_dir, PACKAGE_IMAGE_ENTRIES);
+        assert_eq!(missing.status, Status::Warn);
+
+        create_dir_all(image_dir.join("bin")).unwrap();
+        let incomplete = verify_image(item(), &image_dir, PACKAGE_IMAGE_ENTRIES);
+        assert_eq!(incomplete.status, Status::Fail);
+        assert_eq!(incomplete.problems, vec!["the image has no `package.json`"]);
+
+        write(image_dir.join("package.json"), "{}").unwrap();
+        let intact = verify_image(item(), &image_dir, PACKAGE_IMAGE_ENTRIES);
+        assert_eq!(intact.status, Status::Pass);
+        assert!(!intact.needs_repair());
+    }
+}
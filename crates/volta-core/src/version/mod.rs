@@ -39,6 +39,38 @@ pub enum VersionTag {
     LtsRequirement(VersionReq),
 }
 
+/// How far a version may move when it is bumped to the newest release of its line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bump {
+    /// The newest release with the same major and minor version
+    Patch,
+    /// The newest release with the same major version
+    Minor,
+}
+
+impl Bump {
+    /// Returns the requirement that matches every release in the line of `version`
+    pub fn line(self, version: &Version) -> VersionReq {
+        let requirement = match self {
+            Bump::Patch => format!(
+                ">={major}.{minor}.0 <{major}.{next}.0",
+                major = version.major,
+                minor = version.minor,
+                next = version.minor + 1
+            ),
+            Bump::Minor => format!(
+                ">={major}.0.0 <{next}.0.0",
+                major = version.major,
+                next = version.major + 1
+            ),
+        };
+        // Parsed the same way as requirements from the command line, since a requirement for a
+        // package is passed on to npm
+        serial::parse_requirements(&requirement)
+            .expect("the requirement for a release line is valid")
+    }
+}
+
 impl fmt::Display for VersionSpec {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -208,3 +240,21 @@ pub mod hashmap_version_serde {
         Ok(m.into_iter().map(|(k, Wrapper(v))| (k, v)).collect())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump_line() {
+        let version = Version::parse("14.15.3").unwrap();
+
+        let patch = Bump::Patch.line(&version);
+        assert!(patch.matches(&Version::parse("14.15.5").unwrap()));
+        assert!(!patch.matches(&Version::parse("14.16.0").unwrap()));
+
+        let minor = Bump::Minor.line(&version);
+        assert!(minor.matches(&Version::parse("14.17.0").unwrap()));
+        assert!(!minor.matches(&Version::parse("15.0.0").unwrap()));
+    }
+}
@@ -0,0 +1,279 @@
+//! Provides discovery of the packages in npm, Yarn and pnpm workspaces, so that the same
+//! toolchain can be pinned in every package of a workspace at once.
+
+use std::collections::BTreeSet;
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+use crate::error::ErrorDetails;
+use crate::manifest::Manifest;
+use crate::project::Project;
+use log::debug;
+use serde_json::Value;
+use volta_fail::{Fallible, ResultExt};
+
+/// The file that lists the workspace packages of a pnpm workspace
+const PNPM_WORKSPACE_FILE: &str = "pnpm-workspace.yaml";
+
+/// Walks up from `dir` to the nearest project that defines a workspace, if any.
+pub fn find_root(dir: &Path) -> Option<PathBuf> {
+    let mut current = Project::find_dir(dir);
+
+    while let Some(project_root) = current {
+        if !workspace_patterns(project_root).is_empty() {
+            debug!("Found workspace root in '{}'", project_root.display());
+            return Some(project_root.to_path_buf());
+        }
+        current = project_root.parent().and_then(Project::find_dir);
+    }
+
+    None
+}
+
+/// Returns the directories of the packages in the workspace rooted at `root`, not including
+/// the root itself.
+pub fn packages(root: &Path) -> Vec<PathBuf> {
+    let mut included = BTreeSet::new();
+    let mut excluded = BTreeSet::new();
+
+    for pattern in workspace_patterns(root) {
+        match pattern.strip_prefix('!') {
+            Some(exclusion) => excluded.extend(expand_pattern(root, exclusion)),
+            None => included.extend(expand_pattern(root, &pattern)),
+        }
+    }
+
+    included
+        .into_iter()
+        .filter(|dir| dir != root && !excluded.contains(dir))
+        .collect()
+}
+
+/// Writes the toolchain pinned in the workspace root to each of its packages, returning the
+/// `package.json` files that changed.
+///
+/// If `extends` is true, each package inherits the toolchain of the root through `extends`
+/// instead of pinning the versions itself.
+pub fn pin_packages(root: &Path, packages: &[PathBuf], extends: bool) -> Fallible<Vec<PathBuf>> {
+    let root_manifest = Manifest::for_dir(root)?;
+    let mut changed = Vec::new();
+
+    for dir in packages {
+        let package_file = dir.join("package.json");
+        let original =
+            read_to_string(&package_file).with_context(|_| ErrorDetails::PackageReadError {
+                file: package_file.clone(),
+            })?;
+
+        let mut manifest = Manifest::for_dir(dir)?;
+        if extends {
+            manifest.extends = Some(relative_root(root, dir));
+            manifest.platform = None;
+        } else if let Some(platform) = root_manifest.platform() {
+            manifest.update_platform((*platform).clone());
+        }
+        // Custom tools aren't inherited through `extends`, so they are always copied
+        for (name, version) in &root_manifest.tools {
+            manifest.update_custom_tool(name, version.clone());
+        }
+        manifest.write(package_file.clone())?;
+
+        let updated =
+            read_to_string(&package_file).with_context(|_| ErrorDetails::PackageReadError {
+                file: package_file.clone(),
+            })?;
+        if updated != original {
+            changed.push(package_file);
+        }
+    }
+
+    Ok(changed)
+}
+
+/// The path of the root `package.json` relative to a package in the workspace, as used by
+/// `extends`
+fn relative_root(root: &Path, dir: &Path) -> String {
+    let depth = dir
+        .strip_prefix(root)
+        .map(|relative| relative.components().count())
+        .unwrap_or(0);
+    format!("{}package.json", "../".repeat(depth))
+}
+
+/// Reads the workspace globs of the project in `root`, from the `workspaces` field used by npm
+/// and Yarn and from `pnpm-workspace.yaml`
+fn workspace_patterns(root: &Path) -> Vec<String> {
+    let mut patterns = Vec::new();
+
+    let manifest = read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Value>(&contents).ok());
+    if let Some(manifest) = manifest {
+        // Yarn also allows an object, with the globs under `packages`
+        let workspaces = match manifest.get("workspaces") {
+            Some(Value::Object(object)) => object.get("packages").cloned(),
+            other => other.cloned(),
+        };
+        if let Some(Value::Array(globs)) = workspaces {
+            patterns.extend(globs.iter().filter_map(Value::as_str).map(String::from));
+        }
+    }
+
+    if let Ok(contents) = read_to_string(root.join(PNPM_WORKSPACE_FILE)) {
+        patterns.extend(pnpm_patterns(&contents));
+    }
+
+    patterns
+}
+
+/// Reads the `packages` list of a `pnpm-workspace.yaml` file
+///
+/// Only the simple list syntax that pnpm documents is supported, which avoids a full YAML parser.
+fn pnpm_patterns(contents: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with(char::is_whitespace) && !line.starts_with('-') {
+            in_packages = trimmed == "packages:";
+        } else if in_packages {
+            if let Some(item) = trimmed.strip_prefix('-') {
+                let item = item.trim().trim_matches(|c| c == '\'' || c == '"');
+                patterns.push(item.to_string());
+            }
+        }
+    }
+
+    patterns
+}
+
+/// Finds the packages matching a workspace glob, which may use `*`, `?` and `**`
+fn expand_pattern(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let segments: Vec<&str> = pattern
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect();
+
+    let mut matches = Vec::new();
+    expand_segments(root, &segments, &mut matches);
+    matches
+}
+
+fn expand_segments(dir: &Path, segments: &[&str], matches: &mut Vec<PathBuf>) {
+    match segments.split_first() {
+        None => {
+            if dir.join("package.json").is_file() {
+                matches.push(dir.to_path_buf());
+            }
+        }
+        Some((&"**", rest)) => {
+            expand_segments(dir, rest, matches);
+            for child in child_dirs(dir) {
+                expand_segments(&child, segments, matches);
+            }
+        }
+        Some((segment, rest)) => {
+            for child in child_dirs(dir) {
+                let name = child.file_name().and_then(|name| name.to_str());
+                if name.map_or(false, |name| matches_wildcard(segment, name)) {
+                    expand_segments(&child, rest, matches);
+                }
+            }
+        }
+    }
+}
+
+/// The directories in `dir` that may contain workspace packages, skipping dependencies and
+/// hidden directories
+fn child_dirs(dir: &Path) -> Vec<PathBuf> {
+    let entries = match read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter(
+            |path| match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name != "node_modules" && !name.starts_with('.'),
+                None => false,
+            },
+        )
+        .collect()
+}
+
+fn matches_wildcard(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match pattern.split_first() {
+            None => name.is_empty(),
+            Some((&'*', rest)) => (0..=name.len()).any(|skip| matches(rest, &name[skip..])),
+            Some((&'?', rest)) => !name.is_empty() && matches(rest, &name[1..]),
+            Some((c, rest)) => name.first() == Some(c) && matches(rest, &name[1..]),
+        }
+    }
+
+    matches(&pattern, &name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+
+    fn write_package(root: &Path, dir: &str) {
+        let dir = root.join(dir);
+        create_dir_all(&dir).unwrap();
+        write(dir.join("package.json"), "{}").unwrap();
+    }
+
+    #[test]
+    fn test_matches_wildcard() {
+        assert!(matches_wildcard("*", "app"));
+        assert!(matches_wildcard("app-*", "app-web"));
+        assert!(matches_wildcard("app-?", "app-1"));
+        assert!(!matches_wildcard("app-*", "lib-web"));
+    }
+
+    #[test]
+    fn test_pnpm_patterns() {
+        let contents = "packages:\n  # all packages\n  - 'packages/*'\n  - \"!**/test/**\"\nother:\n  - ignored\n";
+        assert_eq!(
+            pnpm_patterns(contents),
+            vec!["packages/*".to_string(), "!**/test/**".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root.join("package.json"),
+            r#"{ "workspaces": ["packages/*", "tools/**", "!packages/skipped"] }"#,
+        )
+        .unwrap();
+        write_package(root, "packages/a");
+        write_package(root, "packages/skipped");
+        write_package(root, "packages/a/node_modules/dep");
+        write_package(root, "tools/nested/b");
+
+        assert_eq!(
+            packages(root),
+            vec![root.join("packages/a"), root.join("tools/nested/b")]
+        );
+        assert_eq!(
+            relative_root(root, &root.join("tools/nested/b")),
+            "../../../package.json"
+        );
+    }
+}
@@ -178,6 +178,33 @@
 //! even more clarity to any errors. For instance, in our running example of an
 //! RGB parser, a higher layer may want to add context about _which_ RGB string
 //! was being parsed and where it came from (say, the filename and line number).
+//!
+//! # One-off errors
+//!
+//! An error that is only reported in one place doesn't need a type of its own. The
+//! `VoltaError::from_parts` and `VoltaError::from_str` constructors build an error from
+//! a message and an exit code instead.
+//!
+//! ## Example
+//!
+//! ```
+//! use volta_fail::{throw, ExitCode, Fallible, VoltaError};
+//!
+//! fn parse_channel(src: &str) -> Fallible<u8> {
+//!     match src {
+//!         "stable" => Ok(0),
+//!         "beta" => Ok(1),
+//!         _ => throw!(VoltaError::from_str(
+//!             &format!("unknown channel: {}", src),
+//!             ExitCode::InvalidArguments,
+//!         )),
+//!     }
+//! }
+//!
+//! let error = parse_channel("nightly").unwrap_err();
+//! assert_eq!(error.to_string(), "unknown channel: nightly");
+//! assert_eq!(error.exit_code() as i32, ExitCode::InvalidArguments as i32);
+//! ```
 
 use std::convert::{From, Into};
 use std::fmt;
@@ -224,6 +251,18 @@ pub enum ExitCode {
     /// The command or feature is not yet implemented.
     NotYetImplemented = 9,
 
+    /// A tool could not be built from source.
+    BuildError = 10,
+
+    /// A change to the toolchain was refused because read-only mode is enabled.
+    ReadOnlyError = 11,
+
+    /// `volta status`: the current project doesn't pin Node, or there is no current project.
+    StatusUnpinned = 20,
+
+    /// `volta status`: a pinned version has not been fetched yet.
+    StatusNotInstalled = 21,
+
     /// The requested executable could not be run.
     ExecutionFailure = 126,
 
@@ -237,6 +276,75 @@ impl ExitCode {
     }
 }
 
+/// How the exit status is chosen for a tool that was terminated by a signal.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SignalExit {
+    /// The shell convention: 128 plus the number of the signal, e.g. 130 for Ctrl+C.
+    Shell,
+    /// The status of `ExitCode::UnknownError`, which is the default.
+    UnknownError,
+}
+
+/// The mapping from exit codes to the status that the process exits with.
+///
+/// Some CI systems treat a status of 1 specially, so the status of `ExitCode::UnknownError`
+/// and the status after a signal can be remapped. Every other exit code keeps its value.
+///
+/// ## Example
+///
+/// ```
+/// use volta_fail::{ExitCode, ExitPolicy, SignalExit};
+///
+/// let policy = ExitPolicy {
+///     unknown_error: 70,
+///     signal_exit: SignalExit::Shell,
+/// };
+/// assert_eq!(policy.status(ExitCode::UnknownError), 70);
+/// assert_eq!(policy.status(ExitCode::NetworkError), 5);
+/// assert_eq!(policy.signal_status(2), 130);
+/// assert_eq!(ExitPolicy::default().signal_status(2), 1);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ExitPolicy {
+    /// The status used for `ExitCode::UnknownError`.
+    pub unknown_error: i32,
+
+    /// How the status after a signal is chosen.
+    pub signal_exit: SignalExit,
+}
+
+impl Default for ExitPolicy {
+    fn default() -> Self {
+        ExitPolicy {
+            unknown_error: ExitCode::UnknownError as i32,
+            signal_exit: SignalExit::UnknownError,
+        }
+    }
+}
+
+impl ExitPolicy {
+    /// Returns the status to exit with for an exit code.
+    pub fn status(&self, code: ExitCode) -> i32 {
+        match code {
+            ExitCode::UnknownError => self.unknown_error,
+            code => code as i32,
+        }
+    }
+
+    /// Returns the status to exit with after the given signal.
+    pub fn signal_status(&self, signal: i32) -> i32 {
+        match self.signal_exit {
+            SignalExit::Shell => 128 + signal,
+            SignalExit::UnknownError => self.unknown_error,
+        }
+    }
+
+    /// Exits the process with the status for an exit code.
+    pub fn exit(&self, code: ExitCode) -> ! {
+        exit(self.status(code));
+    }
+}
+
 /// The failure trait for all Volta errors.
 pub trait VoltaFail: Fail {
     /// Returns the process exit code that should be returned if the process exits with this error.
@@ -300,6 +408,49 @@ impl VoltaError {
     }
 }
 
+/// An error made of a message and an exit code, for `VoltaError::from_parts`.
+#[derive(Debug)]
+struct MessageError<D> {
+    message: D,
+    exit_code: ExitCode,
+}
+
+impl<D: fmt::Display> fmt::Display for MessageError<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.message, f)
+    }
+}
+
+impl<D> Fail for MessageError<D> where D: fmt::Display + fmt::Debug + Send + Sync + 'static {}
+
+impl<D> VoltaFail for MessageError<D>
+where
+    D: fmt::Display + fmt::Debug + Send + Sync + 'static,
+{
+    fn exit_code(&self) -> ExitCode {
+        self.exit_code
+    }
+}
+
+impl VoltaError {
+    /// Creates an error that displays `message` and exits with `exit_code`, for errors that
+    /// don't need a type of their own.
+    pub fn from_parts<D>(message: D, exit_code: ExitCode) -> Self
+    where
+        D: fmt::Display + fmt::Debug + Send + Sync + 'static,
+    {
+        MessageError { message, exit_code }.into()
+    }
+
+    /// Creates an error with the given message and exit code.
+    ///
+    /// This is a shorthand for `from_parts` that copies a borrowed message.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(message: &str, exit_code: ExitCode) -> Self {
+        VoltaError::from_parts(message.to_string(), exit_code)
+    }
+}
+
 impl<T: VoltaFail> From<T> for VoltaError {
     fn from(failure: T) -> Self {
         let exit_code = failure.exit_code();
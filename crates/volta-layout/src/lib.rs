@@ -3,6 +3,7 @@ mod macros;
 
 pub mod v0;
 pub mod v1;
+pub mod v2;
 
 fn executable(name: &str) -> String {
     format!("{}{}", name, std::env::consts::EXE_SUFFIX)
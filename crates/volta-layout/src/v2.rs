@@ -0,0 +1,179 @@
+use std::path::PathBuf;
+
+use super::executable;
+use volta_layout_macro::layout;
+
+layout! {
+    pub struct VoltaInstall {
+        "volta-shim[.exe]": shim_executable;
+        "volta[.exe]": main_executable;
+        "volta-migrate[.exe]": migrate_executable;
+    }
+
+    pub struct VoltaHome {
+        "cache": cache_dir {
+            "node": node_cache_dir {
+                "index.json": node_index_file;
+                "index.json.expires": node_index_expiry_file;
+                "index.json.validators": node_index_validators_file;
+                "notices.checked": node_notice_check_file;
+                "schedule.json": node_schedule_file;
+                "schedule.json.expires": node_schedule_expiry_file;
+            }
+            "npx": npx_cache_dir {}
+            "npm-prefix": npm_prefix_dir {}
+            "platform": platform_cache_dir {}
+        }
+        "bin": shim_dir {}
+        "log": log_dir {}
+        "tools": tools_dir {
+            "inventory": inventory_dir {
+                "custom": custom_inventory_root_dir {}
+                "node": node_inventory_dir {}
+                "npm": npm_inventory_dir {}
+                "packages": package_inventory_dir {}
+                "pnpm": pnpm_inventory_dir {}
+                "yarn": yarn_inventory_dir {}
+            }
+            "image": image_dir {
+                "node": node_image_root_dir {}
+                "npm": npm_image_root_dir {}
+                "yarn": yarn_image_root_dir {}
+                "pnpm": pnpm_image_root_dir {}
+                "packages": package_image_root_dir {}
+                "custom": custom_image_root_dir {}
+            }
+            "store": store_dir {}
+            "user": default_toolchain_dir {
+                "bins": default_bin_dir {}
+                "packages": default_package_dir {}
+                "platform.json": default_platform_file;
+            }
+        }
+        "tmp": tmp_dir {}
+        "hooks.json": default_hooks_file;
+        "settings.json": default_settings_file;
+        "integrity.key": integrity_key_file;
+        "history.jsonl": history_file;
+        "metrics.json": metrics_file;
+        "stats.log": stats_file;
+        "volta.lock": lock_file;
+        "layout.v2": layout_file;
+    }
+}
+
+impl VoltaHome {
+    pub fn package_distro_file(&self, name: &str, version: &str) -> PathBuf {
+        path_buf!(
+            self.package_inventory_dir.clone(),
+            format!("{}-{}.tgz", name, version)
+        )
+    }
+
+    pub fn package_distro_shasum(&self, name: &str, version: &str) -> PathBuf {
+        path_buf!(
+            self.package_inventory_dir.clone(),
+            format!("{}-{}.shasum", name, version)
+        )
+    }
+
+    pub fn node_image_dir(&self, node: &str, npm: &str) -> PathBuf {
+        path_buf!(self.node_image_root_dir.clone(), node, npm)
+    }
+
+    pub fn npm_image_dir(&self, version: &str) -> PathBuf {
+        path_buf!(self.npm_image_root_dir.clone(), version)
+    }
+
+    pub fn npm_image_bin_dir(&self, version: &str) -> PathBuf {
+        path_buf!(self.npm_image_dir(version), "bin")
+    }
+
+    pub fn yarn_image_dir(&self, version: &str) -> PathBuf {
+        path_buf!(self.yarn_image_root_dir.clone(), version)
+    }
+
+    pub fn yarn_image_bin_dir(&self, version: &str) -> PathBuf {
+        path_buf!(self.yarn_image_dir(version), "bin")
+    }
+
+    pub fn pnpm_image_dir(&self, version: &str) -> PathBuf {
+        path_buf!(self.pnpm_image_root_dir.clone(), version)
+    }
+
+    pub fn pnpm_image_bin_dir(&self, version: &str) -> PathBuf {
+        path_buf!(self.pnpm_image_dir(version), "bin")
+    }
+
+    pub fn custom_inventory_dir(&self, name: &str) -> PathBuf {
+        path_buf!(self.custom_inventory_root_dir.clone(), name)
+    }
+
+    pub fn custom_image_dir(&self, name: &str, version: &str) -> PathBuf {
+        path_buf!(self.custom_image_root_dir.clone(), name, version)
+    }
+
+    pub fn package_image_dir(&self, name: &str, version: &str) -> PathBuf {
+        path_buf!(self.package_image_root_dir.clone(), name, version)
+    }
+
+    pub fn package_platform_image_dir(&self, name: &str, version: &str, node: &str) -> PathBuf {
+        path_buf!(
+            self.package_image_root_dir.clone(),
+            name,
+            format!("{}-node-{}", version, node)
+        )
+    }
+
+    pub fn default_package_config_file(&self, package_name: &str) -> PathBuf {
+        path_buf!(
+            self.default_package_dir.clone(),
+            format!("{}.json", package_name)
+        )
+    }
+
+    pub fn default_tool_bin_config(&self, bin_name: &str) -> PathBuf {
+        path_buf!(self.default_bin_dir.clone(), format!("{}.json", bin_name))
+    }
+
+    pub fn node_npm_version_file(&self, version: &str) -> PathBuf {
+        path_buf!(
+            self.node_inventory_dir.clone(),
+            format!("node-v{}-npm", version)
+        )
+    }
+
+    pub fn platform_cache_file(&self, key: &str) -> PathBuf {
+        path_buf!(self.platform_cache_dir.clone(), key)
+    }
+
+    pub fn shim_file(&self, toolname: &str) -> PathBuf {
+        path_buf!(self.shim_dir.clone(), executable(toolname))
+    }
+}
+
+#[cfg(windows)]
+impl VoltaHome {
+    pub fn shim_git_bash_script_file(&self, toolname: &str) -> PathBuf {
+        path_buf!(self.shim_dir.clone(), toolname)
+    }
+
+    pub fn shim_cmd_script_file(&self, toolname: &str) -> PathBuf {
+        path_buf!(self.shim_dir.clone(), format!("{}.cmd", toolname))
+    }
+
+    pub fn shim_powershell_script_file(&self, toolname: &str) -> PathBuf {
+        path_buf!(self.shim_dir.clone(), format!("{}.ps1", toolname))
+    }
+
+    pub fn node_image_bin_dir(&self, node: &str, npm: &str) -> PathBuf {
+        self.node_image_dir(node, npm)
+    }
+}
+
+#[cfg(unix)]
+impl VoltaHome {
+    pub fn node_image_bin_dir(&self, node: &str, npm: &str) -> PathBuf {
+        path_buf!(self.node_image_dir(node, npm), "bin")
+    }
+}
@@ -1,8 +1,8 @@
 //! Provides types for modeling the current state of the Volta directory and for migrating between versions
 //!
-//! A new layout should be represented by its own struct (as in the existing v0 or v1 modules)
+//! A new layout should be represented by its own struct (as in the existing v0, v1 or v2 modules)
 //! Migrations between types should be represented by `TryFrom` implementations between the layout types
-//! (see v1.rs for examples)
+//! (see v1.rs or v2.rs for examples)
 //!
 //! NOTE: Since the layout file is written once the migration is complete, all migration implementations
 //! need to be aware that they may be partially applied (if something fails in the process) and should be
@@ -16,9 +16,11 @@ use std::path::Path;
 mod empty;
 mod v0;
 mod v1;
+mod v2;
 
 use v0::V0;
 use v1::V1;
+use v2::V2;
 
 use log::debug;
 use volta_core::error::ErrorDetails;
@@ -28,7 +30,7 @@ use volta_core::layout::volta_home;
 use volta_core::layout::volta_install;
 use volta_core::shim;
 use volta_fail::{Fallible, ResultExt};
-use volta_layout::v1::VoltaHome;
+use volta_layout::v2::VoltaHome;
 
 /// Represents the state of the Volta directory at every point in the migration process
 ///
@@ -38,6 +40,7 @@ enum MigrationState {
     Empty(empty::Empty),
     V0(Box<V0>),
     V1(Box<V1>),
+    V2(Box<V2>),
 }
 
 impl MigrationState {
@@ -56,9 +59,14 @@ impl MigrationState {
     fn detect_tagged_state(home: &VoltaHome) -> Option<Self> {
         // Detect a layout at or above V1, which will always have an associated layout file to use as a discriminant
         if home.layout_file().exists() {
-            Some(MigrationState::V1(Box::new(V1::new(
+            return Some(MigrationState::V2(Box::new(V2::new(
                 home.root().to_owned(),
-            ))))
+            ))));
+        }
+
+        let one = V1::new(home.root().to_owned());
+        if one.home.layout_file().exists() {
+            Some(MigrationState::V1(Box::new(one)))
         } else {
             None
         }
@@ -110,13 +118,14 @@ pub fn run_migration() -> Fallible<()> {
 
     // To keep the complexity of writing a new migration from continuously increasing, each new
     // layout version only needs to implement a migration from 2 states: Empty and the previously
-    // latest version. We then apply the migrations sequentially here: V0 -> V1 -> ... -> VX
+    // latest version. We then apply the migrations sequentially here: V0 -> V1 -> V2 -> ... -> VX
     loop {
         state = match state {
-            MigrationState::Empty(e) => MigrationState::V1(Box::new(e.try_into()?)),
+            MigrationState::Empty(e) => MigrationState::V2(Box::new(e.try_into()?)),
             MigrationState::V0(zero) => MigrationState::V1(Box::new((*zero).try_into()?)),
-            MigrationState::V1(one) => {
-                regenerate_shims_for_dir(one.home.shim_dir())?;
+            MigrationState::V1(one) => MigrationState::V2(Box::new((*one).try_into()?)),
+            MigrationState::V2(two) => {
+                regenerate_shims_for_dir(two.home.shim_dir())?;
                 break;
             }
         };
@@ -147,6 +156,7 @@ fn get_shim_list_deduped(dir: &Path) -> Fallible<HashSet<String>> {
         shims.insert("npm".into());
         shims.insert("npx".into());
         shims.insert("yarn".into());
+        shims.insert("pnpm".into());
         Ok(shims)
     }
 
@@ -0,0 +1,147 @@
+use std::convert::TryFrom;
+use std::fs::{remove_dir_all, remove_file, rename, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::empty::Empty;
+use super::v1::V1;
+use log::debug;
+use volta_core::error::ErrorDetails;
+use volta_core::fs::{copy_dir_all, read_dir_eager};
+use volta_core::tool::PackageConfig;
+use volta_fail::{Fallible, ResultExt, VoltaError};
+use volta_layout::v2;
+
+/// Represents a V2 Volta Layout (from after v0.7.1)
+///
+/// Holds a reference to the V2 layout struct to support potential future migrations
+pub struct V2 {
+    pub home: v2::VoltaHome,
+}
+
+impl V2 {
+    pub fn new(home: PathBuf) -> Self {
+        V2 {
+            home: v2::VoltaHome::new(home),
+        }
+    }
+
+    /// Write the layout file to mark migration to V2 as complete
+    ///
+    /// Should only be called once all other migration steps are finished, so that we don't
+    /// accidentally mark an incomplete migration as completed
+    fn complete_migration(home: v2::VoltaHome) -> Fallible<Self> {
+        debug!("Writing layout marker file");
+        File::create(home.layout_file()).with_context(|_| ErrorDetails::CreateLayoutFileError {
+            file: home.layout_file().to_owned(),
+        })?;
+
+        Ok(V2 { home })
+    }
+}
+
+impl TryFrom<Empty> for V2 {
+    type Error = VoltaError;
+
+    fn try_from(old: Empty) -> Fallible<V2> {
+        debug!("New Volta installation detected, creating fresh layout");
+
+        let home = v2::VoltaHome::new(old.home);
+        home.create()
+            .with_context(|_| ErrorDetails::CreateDirError {
+                dir: home.root().to_owned(),
+            })?;
+
+        V2::complete_migration(home)
+    }
+}
+
+impl TryFrom<V1> for V2 {
+    type Error = VoltaError;
+
+    fn try_from(old: V1) -> Fallible<V2> {
+        debug!("Existing Volta installation detected, migrating from V1 layout");
+
+        let new_home = v2::VoltaHome::new(old.home.root().to_owned());
+        new_home
+            .create()
+            .with_context(|_| ErrorDetails::CreateDirError {
+                dir: new_home.root().to_owned(),
+            })?;
+
+        // V1 installed each package into a single image per package version, while V2 keeps a
+        // separate image for every Node version the package is installed against. The old image
+        // stays where it is, since V2 uses that location for the fetched package.
+        debug!("Copying installed packages into images keyed by Node version");
+        let package_configs =
+            read_dir_eager(new_home.default_package_dir()).with_context(|_| {
+                ErrorDetails::ReadDirError {
+                    dir: new_home.default_package_dir().to_owned(),
+                }
+            })?;
+        for (entry, metadata) in package_configs {
+            let path = entry.path();
+            if !metadata.is_file() || path.extension().map_or(true, |ext| ext != "json") {
+                continue;
+            }
+
+            let config = match PackageConfig::from_file(&path) {
+                Ok(config) => config,
+                Err(_) => {
+                    debug!("Skipping unreadable package config {}", path.display());
+                    continue;
+                }
+            };
+            let version = config.version.to_string();
+            let installed_image = old.home.package_image_dir(&config.name, &version);
+            let platform_image = new_home.package_platform_image_dir(
+                &config.name,
+                &version,
+                &config.platform.node_runtime.to_string(),
+            );
+
+            if installed_image.is_dir() && !platform_image.exists() {
+                debug!(
+                    "Copying the image of {}@{} to {}",
+                    config.name,
+                    version,
+                    platform_image.display()
+                );
+                copy_platform_image(&installed_image, &platform_image).with_context(|_| {
+                    ErrorDetails::SetupToolImageError {
+                        tool: config.name.clone(),
+                        version: version.clone(),
+                        dir: platform_image.clone(),
+                    }
+                })?;
+            }
+        }
+
+        let v2 = V2::complete_migration(new_home)?;
+
+        // Only remove the V1 marker once the V2 one is written, so an interrupted migration is
+        // detected as V1 and re-started
+        let old_layout_file = old.home.layout_file();
+        if old_layout_file.exists() {
+            remove_file(old_layout_file).with_context(|_| ErrorDetails::DeleteFileError {
+                file: old_layout_file.to_owned(),
+            })?;
+        }
+
+        Ok(v2)
+    }
+}
+
+/// Copies an installed image through a staging directory, so that an interrupted copy never
+/// leaves a partial image in place
+fn copy_platform_image(src: &Path, dest: &Path) -> io::Result<()> {
+    let mut staging = dest.as_os_str().to_owned();
+    staging.push(".migrating");
+    let staging = PathBuf::from(staging);
+
+    if staging.exists() {
+        remove_dir_all(&staging)?;
+    }
+    copy_dir_all(src, &staging)?;
+    rename(&staging, dest)
+}
@@ -1,7 +1,9 @@
 use structopt::StructOpt;
 
 use crate::command::{self, Command};
+use crate::common::Error;
 use volta_core::session::Session;
+use volta_core::style::ColorChoice;
 use volta_fail::{ExitCode, Fallible};
 
 #[derive(StructOpt)]
@@ -31,11 +33,35 @@ pub(crate) struct Volta {
         long = "quiet",
         help = "Prevents unnecessary output",
         global = true,
-        conflicts_with = "verbose",
-        raw(aliases = r#"&["silent"]"#)
+        conflicts_with = "verbose"
     )]
     pub(crate) quiet: bool,
 
+    /// The superseded name of `--quiet`
+    #[structopt(
+        long = "silent",
+        global = true,
+        conflicts_with = "verbose",
+        raw(hidden = "true")
+    )]
+    pub(crate) silent: bool,
+
+    #[structopt(
+        long = "no-warnings",
+        help = "Turns off the warnings about deprecated formats and flags",
+        global = true
+    )]
+    pub(crate) no_warnings: bool,
+
+    #[structopt(
+        long = "color",
+        help = "When to color the output",
+        global = true,
+        default_value = "auto",
+        raw(possible_values = r#"&["auto", "always", "never"]"#)
+    )]
+    pub(crate) color: ColorChoice,
+
     #[structopt(
         short = "v",
         long = "version",
@@ -45,7 +71,7 @@ pub(crate) struct Volta {
 }
 
 impl Volta {
-    pub(crate) fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+    pub(crate) fn run(self, session: &mut Session) -> Result<ExitCode, Error> {
         if self.version {
             // suffix indicator for dev build
             if cfg!(debug_assertions) {
@@ -54,10 +80,11 @@ impl Volta {
                 println!("{}", env!("CARGO_PKG_VERSION"));
             }
             Ok(ExitCode::Success)
-        } else if let Some(command) = self.command {
-            command.run(session)
         } else {
-            Volta::from_iter(["volta", "help"].iter()).run(session)
+            match self.command {
+                Some(command) => command.run(session).map_err(Error::Volta),
+                None => Volta::from_iter(["volta", "help"].iter()).run(session),
+            }
         }
     }
 }
@@ -76,6 +103,10 @@ pub(crate) enum Subcommand {
     #[structopt(name = "uninstall", author = "", version = "")]
     Uninstall(command::Uninstall),
 
+    /// Upgrades installed packages to their latest versions
+    #[structopt(name = "upgrade", author = "", version = "")]
+    Upgrade(command::Upgrade),
+
     /// Pins your project's runtime or package manager
     #[structopt(name = "pin", author = "", version = "")]
     Pin(command::Pin),
@@ -102,6 +133,14 @@ otherwise, they will be written to `stdout`.
     )]
     Completions(command::Completions),
 
+    #[structopt(
+        name = "complete",
+        author = "",
+        version = "",
+        raw(setting = "structopt::clap::AppSettings::Hidden")
+    )]
+    Complete(command::Complete),
+
     /// Locates the actual binary that will be called by Volta
     #[structopt(name = "which", author = "", version = "")]
     Which(command::Which),
@@ -121,6 +160,286 @@ otherwise, they will be written to `stdout`.
     /// Enables Volta for the current user / shell
     #[structopt(name = "setup", author = "", version = "")]
     Setup(command::Setup),
+
+    /// Moves the Volta home to a new directory
+    #[structopt(
+        name = "relocate",
+        author = "",
+        version = "",
+        long_about = "Moves the Volta home to a new directory
+
+The fetched tools, installed packages and shims are moved as they are, so nothing needs to be
+fetched again, and your shell profiles are updated to use the new location, e.g.:
+
+    volta relocate /mnt/data/volta"
+    )]
+    Relocate(command::Relocate),
+
+    /// Signs the default platform and settings files to detect changes made outside of Volta
+    #[structopt(
+        name = "sign",
+        author = "",
+        version = "",
+        long_about = "Signs the default platform and settings files to detect changes made outside of Volta
+
+The first run creates a machine secret in the Volta home, only readable by you, and from then on
+Volta refuses to use either file if it was changed without being signed again. Volta signs the
+default platform itself when it updates it; after editing the settings by hand, run this command
+again to sign them. Use `volta sign --off` to turn signing off."
+    )]
+    Sign(command::Sign),
+
+    /// Runs a command with custom Node and Yarn versions, without changing any pins
+    #[structopt(
+        name = "run",
+        author = "",
+        version = "",
+        long_about = "Runs a command with custom Node and Yarn versions, without changing any pins
+
+Any tool that isn't specified uses the version from the current project or your default
+toolchain. Use `--` to separate the command from Volta's own options, e.g.:
+
+    volta run --node 10 -- npm test"
+    )]
+    Run(command::Run),
+
+    /// Prints the environment changes needed to use the current toolchain without shims
+    #[structopt(name = "env", author = "", version = "")]
+    Env(command::Env),
+
+    /// Diagnoses common problems with your Volta installation
+    #[structopt(name = "doctor", author = "", version = "")]
+    Doctor(command::Doctor),
+
+    /// Checks the fetched tools and installed packages for damage
+    #[structopt(
+        name = "verify",
+        author = "",
+        version = "",
+        long_about = "Checks the fetched tools and installed packages for damage
+
+Every fetched version of Node, npm, Yarn and pnpm and every installed package is checked for
+a complete image, a package archive matching its recorded checksum, and executables that can
+be found. Damaged versions can be fetched again with `--repair`, e.g.:
+
+    volta verify --repair"
+    )]
+    Verify(command::Verify),
+
+    /// Checks the pinned and installed Node versions for known vulnerabilities
+    #[structopt(
+        name = "audit-toolchain",
+        author = "",
+        version = "",
+        long_about = "Checks the pinned and installed Node versions for known vulnerabilities
+
+Every fetched version of Node, along with the default and project versions, is checked against
+the Node.js release schedule and the advisories of the Node.js security working group. Versions
+whose release line has reached its end of life are reported, as are versions with known CVEs,
+along with the newest patch of the same line that fixes them. Exits with a non-zero code if any
+version is vulnerable."
+    )]
+    AuditToolchain(command::AuditToolchain),
+
+    /// Reports the pinned versions that have newer releases
+    #[structopt(
+        name = "outdated",
+        author = "",
+        version = "",
+        long_about = "Reports the pinned versions that have newer releases
+
+For each tool pinned in the current project, shows the newest release with the same minor
+version, the newest release with the same major version, and the newest release overall. Use
+`volta pin <tool> --latest-patch` or `--latest-minor` to update a pin."
+    )]
+    Outdated(command::Outdated),
+
+    /// Checks that the toolchain pinned by the current project can be used
+    #[structopt(
+        name = "check",
+        author = "",
+        version = "",
+        long_about = "Checks that the toolchain pinned by the current project can be used
+
+The pinned versions must exist, Node must satisfy `engines.node`, and no local executable in
+`node_modules/.bin` may take the place of a pinned tool. Exits with a non-zero code if any of
+these checks fail, so that it can be used in pre-commit hooks."
+    )]
+    Check(command::Check),
+
+    /// Exposes the versions pinned by the current project to CI workflows
+    #[structopt(
+        name = "ci-setup",
+        author = "",
+        version = "",
+        long_about = "Exposes the versions pinned by the current project to CI workflows
+
+With `--github`, the versions are written as step outputs, such as `node-version` and
+`yarn-version`, and as `VOLTA_NODE_VERSION`-style environment variables for the later steps of
+the job, so that actions can install a matching toolchain without repeating the pins. Without
+it, the outputs are printed as `name=value` lines."
+    )]
+    CiSetup(command::CiSetup),
+
+    /// Reports whether the toolchain for the current directory is pinned and installed
+    #[structopt(
+        name = "status",
+        author = "",
+        version = "",
+        long_about = "Reports whether the toolchain for the current directory is pinned and installed
+
+Prints the resolved versions of Node, npm, Yarn and pnpm along with where they come from. Use
+`--porcelain` for a stable format to use in Makefiles and shell prompts. Exits with:
+
+    0    the project pins Node, and every version is installed
+    20   the current project doesn't pin Node, or there is no current project
+    21   a version is pinned but has not been fetched yet
+
+Any other status means that the status could not be determined because of an error."
+    )]
+    Status(command::Status),
+
+    /// Prints the active version of Node for use in a shell prompt
+    #[structopt(
+        name = "prompt",
+        author = "",
+        version = "",
+        long_about = "Prints the active version of Node for use in a shell prompt
+
+Only the version of Node is resolved, and nothing is fetched, so that it is fast enough to run
+every time the prompt is shown. Errors are never printed; the prompt is left empty instead. For
+example, in Bash:
+
+    PS1='$(volta prompt --project-only) \\$ '"
+    )]
+    Prompt(command::Prompt),
+
+    /// Records the pinned toolchain and the checksums of its archives in `volta.lock`
+    #[structopt(
+        name = "lock",
+        author = "",
+        version = "",
+        long_about = "Records the pinned toolchain and the checksums of its archives in `volta.lock`
+
+When a project has a `volta.lock`, Volta refuses to run a pinned version that differs from the
+locked one, and verifies every archive it downloads against the recorded checksum. Node and
+pnpm publish a separate archive for each platform, so run `volta lock` on each platform the
+project is developed on to record all of their checksums."
+    )]
+    Lock(command::Lock),
+
+    /// Shows the recorded changes to your toolchain
+    #[structopt(
+        name = "history",
+        author = "",
+        version = "",
+        long_about = "Shows the recorded changes to your toolchain
+
+Every install, uninstall, pin and change of a default version is recorded, along with the time
+of the change, the user who made it and the version it replaced, e.g.:
+
+    volta history --tool node --limit 10"
+    )]
+    History(command::History),
+
+    /// Shows, exports or sends the opt-in usage metrics
+    #[structopt(
+        name = "metrics",
+        author = "",
+        version = "",
+        long_about = "Shows, exports or sends the opt-in usage metrics
+
+When Volta is built with the `metrics` feature and the `metrics` setting is enabled, anonymized
+counts of the commands run and the tool versions used are recorded locally. They only leave the
+machine with `--send`, which posts them to the `metricsEndpoint` setting, e.g.:
+
+    volta metrics --format json > metrics.json"
+    )]
+    Metrics(command::Metrics),
+
+    /// Shows how often each tool has been run and when it was last run
+    #[structopt(
+        name = "stats",
+        author = "",
+        version = "",
+        long_about = "Shows how often each tool has been run and when it was last run
+
+Every run of a shim is recorded. Only the recent runs are kept, so that recording stays cheap.
+The executables of installed packages that were never run are listed as well, to help decide
+which packages can be uninstalled."
+    )]
+    Stats(command::Stats),
+
+    /// Prints the state of your toolchain as JSON, for use with `volta import`
+    #[structopt(
+        name = "export",
+        author = "",
+        version = "",
+        long_about = "Prints the state of your toolchain as JSON, for use with `volta import`
+
+The state includes your default tools, every fetched version of Node, npm, Yarn and pnpm,
+and your installed packages, e.g.:
+
+    volta export > state.json"
+    )]
+    Export(command::Export),
+
+    /// Generates direnv or asdf configuration equivalent to the project's pins
+    #[structopt(
+        name = "export-env",
+        author = "",
+        version = "",
+        long_about = "Generates direnv or asdf configuration equivalent to the project's pins
+
+Lets teammates who use direnv, asdf or mise select the same versions, while the pins in
+`package.json` stay the single source of truth. For example:
+
+    volta export-env --format asdf > .tool-versions"
+    )]
+    ExportEnv(command::ExportEnv),
+
+    /// Generates a Dockerfile or devcontainer that installs the project's pinned toolchain
+    #[structopt(
+        name = "generate",
+        author = "",
+        version = "",
+        long_about = "Generates a Dockerfile or devcontainer that installs the project's pinned toolchain
+
+The generated instructions download the exact versions pinned in `package.json` and verify them
+against their published checksums, so that containers and local development stay in lockstep.
+`dockerfile` prints a snippet to add to an existing Dockerfile, and `devcontainer` prints a
+complete Dockerfile for `.devcontainer/`. For example:
+
+    volta generate devcontainer > .devcontainer/Dockerfile"
+    )]
+    Generate(command::Generate),
+
+    /// Restores a toolchain from the state written by `volta export`
+    #[structopt(name = "import", author = "", version = "")]
+    Import(command::Import),
+
+    /// Imports the tools installed by nvm, asdf or fnm
+    #[structopt(
+        name = "import-tools",
+        author = "",
+        version = "",
+        long_about = "Imports the tools installed by nvm, asdf or fnm
+
+Each installed version of Node is copied into Volta, without the packages that were installed
+globally into it, or downloaded again if the installation can't be used as it is. Versions of
+Yarn and pnpm installed by asdf are always downloaded again. Use `--default` to also make the
+defaults of the version manager Volta's default versions."
+    )]
+    ImportTools(command::ImportTools),
+
+    /// Manages the Volta installation itself
+    #[structopt(
+        name = "self",
+        author = "",
+        version = "",
+        raw(setting = "structopt::clap::AppSettings::ArgRequiredElseHelp")
+    )]
+    SelfCommand(command::SelfCommand),
 }
 
 impl Subcommand {
@@ -129,12 +448,36 @@ impl Subcommand {
             Subcommand::Fetch(fetch) => fetch.run(session),
             Subcommand::Install(install) => install.run(session),
             Subcommand::Uninstall(uninstall) => uninstall.run(session),
+            Subcommand::Upgrade(upgrade) => upgrade.run(session),
             Subcommand::Pin(pin) => pin.run(session),
             Subcommand::List(list) => list.run(session),
             Subcommand::Completions(completions) => completions.run(session),
+            Subcommand::Complete(complete) => complete.run(session),
             Subcommand::Which(which) => which.run(session),
             Subcommand::Use(r#use) => r#use.run(session),
             Subcommand::Setup(setup) => setup.run(session),
+            Subcommand::Relocate(relocate) => relocate.run(session),
+            Subcommand::Sign(sign) => sign.run(session),
+            Subcommand::Env(env) => env.run(session),
+            Subcommand::Doctor(doctor) => doctor.run(session),
+            Subcommand::Verify(verify) => verify.run(session),
+            Subcommand::AuditToolchain(audit) => audit.run(session),
+            Subcommand::Check(check) => check.run(session),
+            Subcommand::CiSetup(ci_setup) => ci_setup.run(session),
+            Subcommand::Status(status) => status.run(session),
+            Subcommand::Prompt(prompt) => prompt.run(session),
+            Subcommand::Lock(lock) => lock.run(session),
+            Subcommand::Outdated(outdated) => outdated.run(session),
+            Subcommand::History(history) => history.run(session),
+            Subcommand::Metrics(metrics) => metrics.run(session),
+            Subcommand::Stats(stats) => stats.run(session),
+            Subcommand::Export(export) => export.run(session),
+            Subcommand::ExportEnv(export_env) => export_env.run(session),
+            Subcommand::Generate(generate) => generate.run(session),
+            Subcommand::Import(import) => import.run(session),
+            Subcommand::ImportTools(import_tools) => import_tools.run(session),
+            Subcommand::SelfCommand(command) => command.run(session),
+            Subcommand::Run(run) => run.run(session),
         }
     }
 }
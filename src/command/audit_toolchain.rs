@@ -0,0 +1,68 @@
+use structopt::StructOpt;
+
+use volta_core::audit::{audit, Item};
+use volta_core::doctor::Status;
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::doctor::{format_result, Format};
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct AuditToolchain {
+    /// The format of the results
+    #[structopt(
+        long = "format",
+        default_value = "human",
+        raw(possible_values = r#"&["human", "json"]"#)
+    )]
+    format: Format,
+}
+
+impl Command for AuditToolchain {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::AuditToolchain);
+
+        let items = audit(session)?;
+        match self.format {
+            Format::Human => println!("{}", format_human(&items)),
+            Format::JSON => println!(
+                "{}",
+                serde_json::to_string_pretty(&items).expect("the results can always be serialized")
+            ),
+        }
+
+        // End-of-life versions are only warned about, since they have no known vulnerabilities yet
+        let exit_code = match items.iter().map(|item| item.status).max() {
+            Some(Status::Fail) => ExitCode::ConfigurationError,
+            _ => ExitCode::Success,
+        };
+
+        session.add_event_end(ActivityKind::AuditToolchain, exit_code);
+        Ok(exit_code)
+    }
+}
+
+fn format_human(items: &[Item]) -> String {
+    if items.is_empty() {
+        return "There are no Node versions to audit.".to_string();
+    }
+
+    let mut lines: Vec<String> = items
+        .iter()
+        .map(|item| {
+            let name = format!("{} ({})", item.name, item.sources.join(", "));
+            format_result(item.status, &name, &item.problems)
+        })
+        .collect();
+    let summary = if items.iter().any(|item| item.status == Status::Fail) {
+        "Some versions have known vulnerabilities; pin or fetch a version that fixes them."
+    } else if items.iter().any(|item| item.status == Status::Warn) {
+        "Some versions have reached their end of life and no longer receive security fixes."
+    } else {
+        "No known vulnerabilities were found."
+    };
+    lines.push(String::new());
+    lines.push(summary.to_string());
+    lines.join("\n")
+}
@@ -0,0 +1,252 @@
+use std::env;
+use std::fs::read_to_string;
+use std::path::Path;
+use std::str::FromStr;
+
+use structopt::StructOpt;
+
+use volta_core::check::check_project;
+use volta_core::doctor::{self, Report, Status};
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::doctor::format_check;
+use crate::command::Command;
+
+#[derive(Copy, Clone, PartialEq)]
+enum Format {
+    Human,
+    JSON,
+    GitHub,
+    Checkstyle,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Format::Human),
+            "json" => Ok(Format::JSON),
+            "github" => Ok(Format::GitHub),
+            "checkstyle" => Ok(Format::Checkstyle),
+            _ => Err(format!("Unsupported format: {}", s)),
+        }
+    }
+}
+
+#[derive(StructOpt)]
+pub(crate) struct Check {
+    /// The format of the report: `github` prints GitHub Actions annotations, and `checkstyle`
+    /// prints Checkstyle XML for other CI systems
+    #[structopt(
+        long = "format",
+        default_value = "human",
+        raw(possible_values = r#"&["human", "json", "github", "checkstyle"]"#)
+    )]
+    format: Format,
+}
+
+impl Command for Check {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Check);
+
+        let report = check_project(session)?;
+        match self.format {
+            Format::Human => println!("{}", format_human(&report)),
+            Format::JSON => println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("a report can always be serialized")
+            ),
+            Format::GitHub | Format::Checkstyle => {
+                // Note: We know this will succeed, since the checks need a project
+                let package_file = session.project()?.unwrap().package_file();
+                let contents = read_to_string(&package_file).unwrap_or_default();
+                // Annotations are matched against the files of the repository, so the path is
+                // relative to the directory the check is run in when possible
+                let current_dir = env::current_dir().unwrap_or_default();
+                let file = package_file
+                    .strip_prefix(&current_dir)
+                    .unwrap_or(&package_file);
+
+                if self.format == Format::GitHub {
+                    print!("{}", format_github(&report, file, &contents));
+                } else {
+                    println!("{}", format_checkstyle(&report, file, &contents));
+                }
+            }
+        }
+
+        let exit_code = match report.status {
+            Status::Fail => ExitCode::ConfigurationError,
+            Status::Skip | Status::Pass | Status::Warn => ExitCode::Success,
+        };
+
+        session.add_event_end(ActivityKind::Check, exit_code);
+        Ok(exit_code)
+    }
+}
+
+fn format_human(report: &Report) -> String {
+    let mut lines: Vec<String> = report.checks.iter().map(format_check).collect();
+    let summary = match report.status {
+        Status::Skip | Status::Pass => "The project toolchain is valid.",
+        Status::Warn => "The project toolchain can be used, but some checks reported warnings.",
+        Status::Fail => "Some checks failed; the project toolchain can't be used as pinned.",
+    };
+    lines.push(String::new());
+    lines.push(summary.to_string());
+    lines.join("\n")
+}
+
+/// Formats each problem as a GitHub Actions workflow command, which shows it inline on the
+/// changes of a pull request
+fn format_github(report: &Report, file: &Path, contents: &str) -> String {
+    let file = escape_github_property(&file.to_string_lossy());
+    let mut output = String::new();
+
+    for check in &report.checks {
+        let command = match check.status {
+            Status::Skip | Status::Pass => continue,
+            Status::Warn => "warning",
+            Status::Fail => "error",
+        };
+        let line = check_line(check, contents);
+        let title = escape_github_property(&format!("volta check: {}", check.name));
+
+        for message in &check.messages {
+            output.push_str(&format!(
+                "::{} file={},line={},title={}::{}\n",
+                command,
+                file,
+                line,
+                title,
+                escape_github_data(message)
+            ));
+        }
+    }
+
+    output
+}
+
+/// Formats the problems as a Checkstyle report, which most CI systems can display
+fn format_checkstyle(report: &Report, file: &Path, contents: &str) -> String {
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+        r#"<checkstyle version="4.3">"#.to_string(),
+        format!(r#"  <file name="{}">"#, escape_xml(&file.to_string_lossy())),
+    ];
+
+    for check in &report.checks {
+        let severity = match check.status {
+            Status::Skip | Status::Pass => continue,
+            Status::Warn => "warning",
+            Status::Fail => "error",
+        };
+        let line = check_line(check, contents);
+        let source = format!("volta.check.{}", check.name.replace(' ', "-"));
+
+        for message in &check.messages {
+            lines.push(format!(
+                r#"    <error line="{}" severity="{}" message="{}" source="{}"/>"#,
+                line,
+                severity,
+                escape_xml(message),
+                source
+            ));
+        }
+    }
+
+    lines.push("  </file>".to_string());
+    lines.push("</checkstyle>".to_string());
+    lines.join("\n")
+}
+
+/// Finds the line of `package.json` that a check is about, or the first line if the check isn't
+/// about a particular key
+fn check_line(check: &doctor::Check, contents: &str) -> usize {
+    let key = match check.name {
+        "engines" => "\"engines\"",
+        _ => "\"volta\"",
+    };
+
+    contents
+        .lines()
+        .position(|line| line.trim_start().starts_with(key))
+        .map_or(1, |index| index + 1)
+}
+
+fn escape_github_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_github_property(value: &str) -> String {
+    escape_github_data(value)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKAGE_JSON: &str = r#"{
+  "name": "test-package",
+  "engines": {
+    "node": ">= 10"
+  },
+  "volta": {
+    "node": "6.19.62"
+  }
+}"#;
+
+    fn report() -> Report {
+        Report {
+            status: Status::Fail,
+            checks: vec![
+                doctor::Check {
+                    name: "pinned versions",
+                    status: Status::Pass,
+                    messages: vec![],
+                },
+                doctor::Check {
+                    name: "engines",
+                    status: Status::Fail,
+                    messages: vec!["node@6.19.62 does not satisfy `engines.node` (>= 10)".into()],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn formats_github_annotations() {
+        assert_eq!(
+            format_github(&report(), Path::new("package.json"), PACKAGE_JSON),
+            "::error file=package.json,line=3,title=volta check%3A engines::node@6.19.62 does not satisfy `engines.node` (>= 10)\n"
+        );
+    }
+
+    #[test]
+    fn formats_checkstyle_report() {
+        assert_eq!(
+            format_checkstyle(&report(), Path::new("package.json"), PACKAGE_JSON),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<checkstyle version="4.3">
+  <file name="package.json">
+    <error line="3" severity="error" message="node@6.19.62 does not satisfy `engines.node` (&gt;= 10)" source="volta.check.engines"/>
+  </file>
+</checkstyle>"#
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use structopt::StructOpt;
+
+use volta_core::error::ErrorDetails;
+use volta_core::fs::write_file_error;
+use volta_core::platform::PlatformSpec;
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{throw, ExitCode, Fallible, ResultExt};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct CiSetup {
+    /// Writes the versions to the `GITHUB_OUTPUT` and `GITHUB_ENV` files of GitHub Actions
+    #[structopt(long = "github")]
+    github: bool,
+}
+
+impl Command for CiSetup {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::CiSetup);
+
+        let platform = match session.project_platform()? {
+            Some(platform) => platform,
+            None if session.project()?.is_none() => throw!(ErrorDetails::NotInPackage),
+            None => throw!(ErrorDetails::NothingToSetUp),
+        };
+        let outputs = outputs(&platform);
+
+        if self.github {
+            write_github(&outputs)?;
+        } else {
+            print!("{}", format_outputs(&outputs));
+        }
+
+        session.add_event_end(ActivityKind::CiSetup, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+/// Returns the pinned versions, by output name
+fn outputs(platform: &PlatformSpec) -> Vec<(&'static str, String)> {
+    let mut outputs = vec![("node-version", platform.node_runtime.to_string())];
+    let package_managers = vec![
+        ("npm-version", &platform.npm),
+        ("yarn-version", &platform.yarn),
+        ("pnpm-version", &platform.pnpm),
+    ];
+    outputs.extend(
+        package_managers.into_iter().filter_map(|(name, version)| {
+            version.as_ref().map(|version| (name, version.to_string()))
+        }),
+    );
+    outputs
+}
+
+/// Writes the versions as step outputs and as environment variables for the later steps
+///
+/// Runners that predate the `GITHUB_OUTPUT` file only understand the `::set-output` workflow
+/// command, which is printed instead.
+fn write_github(outputs: &[(&'static str, String)]) -> Fallible<()> {
+    match env::var_os("GITHUB_OUTPUT") {
+        Some(file) => append(PathBuf::from(file), &format_outputs(outputs))?,
+        None => {
+            for (name, version) in outputs {
+                println!("::set-output name={}::{}", name, version);
+            }
+        }
+    }
+
+    if let Some(file) = env::var_os("GITHUB_ENV") {
+        append(PathBuf::from(file), &format_env(outputs))?;
+    }
+    Ok(())
+}
+
+fn append(file: PathBuf, contents: &str) -> Fallible<()> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&file)
+        .and_then(|mut handle| handle.write_all(contents.as_bytes()))
+        .with_context(write_file_error(&file))
+}
+
+/// Formats the versions as `name=value` lines, the format of the `GITHUB_OUTPUT` file
+fn format_outputs(outputs: &[(&'static str, String)]) -> String {
+    outputs
+        .iter()
+        .map(|(name, version)| format!("{}={}\n", name, version))
+        .collect()
+}
+
+/// Formats the versions as assignments of environment variables, such as `VOLTA_NODE_VERSION`
+fn format_env(outputs: &[(&'static str, String)]) -> String {
+    outputs
+        .iter()
+        .map(|(name, version)| {
+            format!(
+                "VOLTA_{}={}\n",
+                name.replace('-', "_").to_uppercase(),
+                version
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use semver::Version;
+
+    fn platform() -> PlatformSpec {
+        PlatformSpec {
+            node_runtime: Version::parse("18.19.0").unwrap(),
+            npm: None,
+            yarn: Some(Version::parse("1.22.19").unwrap()),
+            pnpm: None,
+        }
+    }
+
+    #[test]
+    fn formats_outputs_and_env() {
+        let outputs = outputs(&platform());
+        assert_eq!(
+            format_outputs(&outputs),
+            "node-version=18.19.0\nyarn-version=1.22.19\n"
+        );
+        assert_eq!(
+            format_env(&outputs),
+            "VOLTA_NODE_VERSION=18.19.0\nVOLTA_YARN_VERSION=1.22.19\n"
+        );
+    }
+}
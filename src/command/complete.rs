@@ -0,0 +1,156 @@
+use std::collections::BTreeSet;
+
+use semver::Version;
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_core::style::tool_version;
+use volta_core::tool::cached_node_versions;
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+/// The tools that can always be completed, regardless of the inventory
+static TOOLS: [&str; 4] = ["node", "npm", "yarn", "pnpm"];
+
+/// A lightweight entry point for shell completion scripts, printing the candidates that
+/// match the word being completed, one per line.
+#[derive(StructOpt)]
+pub(crate) struct Complete {
+    /// The (partial) word to complete, e.g. `node@10`
+    #[structopt(name = "word", default_value = "", raw(allow_hyphen_values = "true"))]
+    word: String,
+
+    /// Also complete Node versions from the cached remote index, not just local versions
+    #[structopt(long = "remote")]
+    remote: bool,
+}
+
+impl Command for Complete {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Completions);
+
+        for candidate in candidates(&self.word, self.remote, session)? {
+            println!("{}", candidate);
+        }
+
+        session.add_event_end(ActivityKind::Completions, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+fn candidates(word: &str, remote: bool, session: &Session) -> Fallible<Vec<String>> {
+    let inventory = session.inventory()?;
+    let packages: Vec<&str> = inventory
+        .packages
+        .iter()
+        .map(|config| config.name.as_str())
+        .collect();
+
+    matching(word, &packages, |tool| {
+        Ok(match tool {
+            "node" if remote => inventory
+                .node
+                .versions
+                .iter()
+                .cloned()
+                .chain(cached_node_versions()?)
+                .collect(),
+            "node" => inventory.node.versions.iter().cloned().collect(),
+            "npm" => inventory.npm.versions.iter().cloned().collect(),
+            "yarn" => inventory.yarn.versions.iter().cloned().collect(),
+            "pnpm" => inventory.pnpm.versions.iter().cloned().collect(),
+            name => inventory
+                .packages
+                .iter()
+                .filter(|config| config.name == name)
+                .map(|config| config.version.clone())
+                .collect(),
+        })
+    })
+}
+
+/// Returns the sorted candidates that start with `word`, which are either the tool names, or
+/// the versions of a tool given by `versions` once `word` has a version separator
+fn matching<F>(word: &str, packages: &[&str], versions: F) -> Fallible<Vec<String>>
+where
+    F: FnOnce(&str) -> Fallible<Vec<Version>>,
+{
+    let all: BTreeSet<String> = match word.find('@') {
+        // Scoped packages start with `@`, so only treat a later `@` as a version separator
+        Some(index) if index > 0 => {
+            let tool = &word[..index];
+            versions(tool)?
+                .iter()
+                .map(|version| tool_version(tool, version))
+                .collect()
+        }
+        _ => TOOLS
+            .iter()
+            .chain(packages.iter())
+            .map(|tool| tool.to_string())
+            .collect(),
+    };
+
+    Ok(all
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(tool: &str) -> Fallible<Vec<Version>> {
+        let versions: &[&str] = match tool {
+            "node" => &["10.16.0", "12.8.0", "12.10.1"],
+            "@vue/cli" => &["4.0.5"],
+            _ => &[],
+        };
+        Ok(versions
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn completes_tool_names() {
+        let packages = ["@vue/cli", "typescript"];
+
+        assert_eq!(
+            matching("", &packages, versions).unwrap(),
+            vec!["@vue/cli", "node", "npm", "pnpm", "typescript", "yarn"]
+        );
+        assert_eq!(
+            matching("n", &packages, versions).unwrap(),
+            vec!["node", "npm"]
+        );
+    }
+
+    #[test]
+    fn completes_versions() {
+        assert_eq!(
+            matching("node@12", &[], versions).unwrap(),
+            vec!["node@12.10.1", "node@12.8.0"]
+        );
+        assert_eq!(
+            matching("yarn@", &[], versions).unwrap(),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn completes_scoped_packages() {
+        let packages = ["@vue/cli"];
+
+        assert_eq!(
+            matching("@vue", &packages, versions).unwrap(),
+            vec!["@vue/cli"]
+        );
+        assert_eq!(
+            matching("@vue/cli@", &packages, versions).unwrap(),
+            vec!["@vue/cli@4.0.5"]
+        );
+    }
+}
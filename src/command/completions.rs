@@ -1,3 +1,4 @@
+use std::io::Write;
 use std::path::PathBuf;
 
 use log::info;
@@ -5,6 +6,7 @@ use structopt::{clap::Shell, StructOpt};
 
 use volta_core::{
     error::ErrorDetails,
+    fs::create_dir_error,
     session::{ActivityKind, Session},
     style::{note_prefix, success_prefix},
 };
@@ -12,6 +14,85 @@ use volta_fail::{throw, ExitCode, Fallible, ResultExt};
 
 use crate::command::Command;
 
+/// Completes tool names and versions via `volta complete` for the commands that accept
+/// `tool[@version]` arguments. Commands that can download a tool also complete Node versions from
+/// the cached remote index. Appended to the generated Bash completions.
+static BASH_DYNAMIC: &str = r#"
+_volta_dynamic() {
+    case "${COMP_WORDS[1]}" in
+        fetch|install|pin)
+            COMPREPLY=( $(volta complete --remote -- "${COMP_WORDS[COMP_CWORD]}" 2>/dev/null) )
+            return 0
+            ;;
+        uninstall)
+            COMPREPLY=( $(volta complete -- "${COMP_WORDS[COMP_CWORD]}" 2>/dev/null) )
+            return 0
+            ;;
+    esac
+    _volta "$@"
+}
+
+complete -F _volta_dynamic -o bashdefault -o default volta
+"#;
+
+/// The Fish equivalent of `BASH_DYNAMIC`.
+static FISH_DYNAMIC: &str = r#"
+complete -c volta -n "__fish_seen_subcommand_from fetch install pin" -f -a "(volta complete --remote -- (commandline -ct) 2>/dev/null)"
+complete -c volta -n "__fish_seen_subcommand_from uninstall" -f -a "(volta complete -- (commandline -ct) 2>/dev/null)"
+"#;
+
+/// The Zsh equivalent of `BASH_DYNAMIC`, which replaces the call to `_volta` at the end of the
+/// generated Zsh completions.
+static ZSH_DYNAMIC: &str = r#"
+_volta_dynamic() {
+    local -a candidates
+    if (( CURRENT > 2 )); then
+        case "${words[2]}" in
+            fetch|install|pin)
+                candidates=(${(f)"$(volta complete --remote -- "${words[CURRENT]}" 2>/dev/null)"})
+                compadd -a candidates
+                return 0
+                ;;
+            uninstall)
+                candidates=(${(f)"$(volta complete -- "${words[CURRENT]}" 2>/dev/null)"})
+                compadd -a candidates
+                return 0
+                ;;
+        esac
+    fi
+    _volta "$@"
+}
+
+_volta_dynamic "$@"
+"#;
+
+/// The PowerShell equivalent of `BASH_DYNAMIC`. The generated completer is kept in a variable
+/// rather than registered, and is called for everything that isn't completed dynamically.
+static POWERSHELL_DYNAMIC: &str = r#"
+Register-ArgumentCompleter -Native -CommandName 'volta' -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $elements = $commandAst.CommandElements
+    if ($elements.Count -gt 1 -and $elements[1].Extent.EndOffset -lt $cursorPosition) {
+        $completeArgs = switch ($elements[1].ToString()) {
+            { $_ -in 'fetch', 'install', 'pin' } { @('complete', '--remote', '--', $wordToComplete) }
+            'uninstall' { @('complete', '--', $wordToComplete) }
+        }
+        if ($completeArgs) {
+            volta @completeArgs 2>$null | ForEach-Object {
+                [CompletionResult]::new($_, $_, [CompletionResultType]::ParameterValue, $_)
+            }
+            return
+        }
+    }
+    & $voltaGenerated $wordToComplete $commandAst $cursorPosition
+}.GetNewClosure()
+"#;
+
+/// The line that registers the generated PowerShell completer
+static POWERSHELL_REGISTER: &str =
+    "Register-ArgumentCompleter -Native -CommandName 'volta' -ScriptBlock {";
+
 #[derive(Debug, StructOpt)]
 pub(crate) struct Completions {
     /// Shell to generate completions for
@@ -36,7 +117,10 @@ impl Command for Completions {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Completions);
 
-        let mut app = crate::cli::Volta::clap();
+        let mut generated = Vec::new();
+        crate::cli::Volta::clap().gen_completions_to("volta", self.shell, &mut generated);
+        let script = with_dynamic(self.shell, String::from_utf8_lossy(&generated).into_owned());
+
         match self.out_file {
             Some(path) => {
                 if path.is_file() && !self.force {
@@ -52,32 +136,92 @@ impl Command for Completions {
                             note_prefix(),
                             parent.display()
                         );
-                        std::fs::create_dir_all(parent).with_context(|_| {
-                            ErrorDetails::CreateDirError {
-                                dir: parent.to_path_buf(),
-                            }
-                        })?;
+                        std::fs::create_dir_all(parent).with_context(create_dir_error(&parent))?;
                     }
                 }
 
-                let mut file = &std::fs::File::create(&path).with_context(|_| {
+                std::fs::write(&path, &script).with_context(|_| {
                     ErrorDetails::CompletionsOutFileError {
                         path: path.to_path_buf(),
                     }
                 })?;
 
-                app.gen_completions_to("volta", self.shell, &mut file);
-
                 info!(
                     "{} installed completions to {}",
                     success_prefix(),
                     path.display()
                 );
             }
-            None => app.gen_completions_to("volta", self.shell, &mut std::io::stdout()),
+            None => {
+                // Failing to write to stdout (e.g. a closed pipe) isn't worth reporting
+                let _ = std::io::stdout().write_all(script.as_bytes());
+            }
         };
 
         session.add_event_end(ActivityKind::Completions, ExitCode::Success);
         Ok(ExitCode::Success)
     }
 }
+
+/// Adds the dynamic completion of tool versions to the generated completions for `shell`
+///
+/// The Zsh and PowerShell completions are only changed if they have the expected shape, so a
+/// change to the generated scripts leaves them working without dynamic completion.
+fn with_dynamic(shell: Shell, mut script: String) -> String {
+    match shell {
+        Shell::Bash => script.push_str(BASH_DYNAMIC),
+        Shell::Fish => script.push_str(FISH_DYNAMIC),
+        Shell::Zsh => {
+            let len = script.trim_end().len();
+            if script[..len].ends_with(r#"_volta "$@""#) {
+                script.truncate(len - r#"_volta "$@""#.len());
+                script.push_str(ZSH_DYNAMIC.trim_start());
+            }
+        }
+        Shell::PowerShell => {
+            if script.contains(POWERSHELL_REGISTER) {
+                script = script.replacen(POWERSHELL_REGISTER, "$voltaGenerated = {", 1);
+                script.push_str(POWERSHELL_DYNAMIC);
+            }
+        }
+        _ => {}
+    }
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generated(shell: Shell) -> String {
+        let mut script = Vec::new();
+        crate::cli::Volta::clap().gen_completions_to("volta", shell, &mut script);
+        String::from_utf8(script).unwrap()
+    }
+
+    #[test]
+    fn zsh_completes_versions() {
+        let script = with_dynamic(Shell::Zsh, generated(Shell::Zsh));
+
+        assert!(script.contains("volta complete --remote --"));
+        assert!(script.trim_end().ends_with(r#"_volta_dynamic "$@""#));
+        assert_eq!(script.matches(r#"_volta "$@""#).count(), 1);
+    }
+
+    #[test]
+    fn powershell_completes_versions() {
+        let script = with_dynamic(Shell::PowerShell, generated(Shell::PowerShell));
+
+        assert!(script.contains("$voltaGenerated = {"));
+        assert!(script.contains("volta @completeArgs"));
+        assert_eq!(script.matches(POWERSHELL_REGISTER).count(), 1);
+    }
+
+    #[test]
+    fn unexpected_scripts_are_unchanged() {
+        let script = "complete volta".to_string();
+
+        assert_eq!(with_dynamic(Shell::Zsh, script.clone()), script);
+        assert_eq!(with_dynamic(Shell::PowerShell, script.clone()), script);
+    }
+}
@@ -0,0 +1,126 @@
+use std::str::FromStr;
+
+use console::style;
+use structopt::StructOpt;
+
+use volta_core::doctor::{diagnose, Check, Report, Status};
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum Format {
+    Human,
+    JSON,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Format::Human),
+            "json" => Ok(Format::JSON),
+            _ => Err(format!("Unsupported format: {}", s)),
+        }
+    }
+}
+
+#[derive(StructOpt)]
+pub(crate) struct Doctor {
+    /// The format of the report
+    #[structopt(
+        long = "format",
+        default_value = "human",
+        raw(possible_values = r#"&["human", "json"]"#)
+    )]
+    format: Format,
+}
+
+impl Command for Doctor {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Doctor);
+
+        let report = diagnose(session);
+        match self.format {
+            Format::Human => println!("{}", format_human(&report)),
+            Format::JSON => println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("a report can always be serialized")
+            ),
+        }
+
+        let exit_code = match report.status {
+            Status::Fail => ExitCode::EnvironmentError,
+            Status::Skip | Status::Pass | Status::Warn => ExitCode::Success,
+        };
+
+        session.add_event_end(ActivityKind::Doctor, exit_code);
+        Ok(exit_code)
+    }
+}
+
+fn format_human(report: &Report) -> String {
+    let mut lines: Vec<String> = report.checks.iter().map(format_check).collect();
+    let summary = match report.status {
+        Status::Skip | Status::Pass => "No problems found.",
+        Status::Warn => "Volta should work, but some checks reported warnings.",
+        Status::Fail => "Some checks failed; Volta may not work correctly.",
+    };
+    lines.push(String::new());
+    lines.push(summary.to_string());
+    lines.join("\n")
+}
+
+pub(crate) fn format_check(check: &Check) -> String {
+    format_result(check.status, check.name, &check.messages)
+}
+
+/// Formats a status label and name, followed by the messages explaining the status
+pub(crate) fn format_result(status: Status, name: &str, messages: &[String]) -> String {
+    let label = format!("[{}]", status);
+    let label = match status {
+        Status::Skip => style(label).dim(),
+        Status::Pass => style(label).green(),
+        Status::Warn => style(label).yellow(),
+        Status::Fail => style(label).red(),
+    };
+
+    let mut lines = vec![format!("{} {}", label.bold(), name)];
+    lines.extend(messages.iter().map(|message| format!("    {}", message)));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_each_check_with_its_messages() {
+        let report = Report {
+            status: Status::Warn,
+            checks: vec![
+                Check {
+                    name: "PATH ordering",
+                    status: Status::Pass,
+                    messages: vec![],
+                },
+                Check {
+                    name: "conflicting version managers",
+                    status: Status::Warn,
+                    messages: vec!["found nvm installed at /home/user/.nvm".to_string()],
+                },
+            ],
+        };
+
+        assert_eq!(
+            console::strip_ansi_codes(&format_human(&report)),
+            "[pass] PATH ordering
+[warn] conflicting version managers
+    found nvm installed at /home/user/.nvm
+
+Volta should work, but some checks reported warnings."
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use std::env;
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use structopt::StructOpt;
+
+use volta_core::env_vars;
+use volta_core::error::ErrorDetails;
+use volta_core::layout::volta_home;
+use volta_core::session::{ActivityKind, Session};
+use volta_core::settings::{settings, Settings};
+use volta_fail::{throw, ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(Copy, Clone, PartialEq)]
+enum Shell {
+    Bash,
+    Fish,
+    PowerShell,
+    JSON,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "fish" => Ok(Shell::Fish),
+            "powershell" => Ok(Shell::PowerShell),
+            "json" => Ok(Shell::JSON),
+            _ => Err(format!("Unsupported shell: {}", s)),
+        }
+    }
+}
+
+#[derive(StructOpt)]
+pub(crate) struct Env {
+    /// The format to print the environment in
+    #[structopt(
+        long = "shell",
+        default_value = "bash",
+        raw(possible_values = r#"&["bash", "fish", "powershell", "json"]"#)
+    )]
+    shell: Shell,
+
+    /// Lists the environment variables that configure Volta, instead of printing the environment
+    #[structopt(long = "list-variables")]
+    list_variables: bool,
+}
+
+impl Command for Env {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Env);
+
+        if self.list_variables {
+            println!("Volta reads the following environment variables:\n");
+            print!("{}", env_vars::documentation());
+            session.add_event_end(ActivityKind::Env, ExitCode::Success);
+            return Ok(ExitCode::Success);
+        }
+
+        let platform = match session.current_platform()? {
+            Some(platform) => platform,
+            None => throw!(ErrorDetails::NoPlatform),
+        };
+        let image = platform.checkout(session)?;
+        let path = image.path()?;
+        let home = volta_home()?.root().as_os_str().to_os_string();
+
+        println!("{}", format_env(self.shell, &home, &path, settings()?));
+
+        session.add_event_end(ActivityKind::Env, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+/// Formats the environment modifications for the given shell, including the variables that the
+/// `removeEnv` and `setEnv` settings change for every tool
+fn format_env(shell: Shell, home: &OsString, path: &OsString, settings: &Settings) -> String {
+    let home = home.to_string_lossy();
+    let mut lines = match shell {
+        Shell::Bash => vec![
+            format!("export VOLTA_HOME={}", posix_quote(&home)),
+            format!("export PATH={}", posix_quote(&path.to_string_lossy())),
+        ],
+        Shell::Fish => {
+            // `PATH` is a list in fish, so each entry is set separately
+            let entries = env::split_paths(path)
+                .map(|entry| fish_quote(&entry.to_string_lossy()))
+                .collect::<Vec<String>>()
+                .join(" ");
+            vec![
+                format!("set -gx VOLTA_HOME {}", fish_quote(&home)),
+                format!("set -gx PATH {}", entries),
+            ]
+        }
+        Shell::PowerShell => vec![
+            format!("$env:VOLTA_HOME = {}", powershell_quote(&home)),
+            format!("$env:PATH = {}", powershell_quote(&path.to_string_lossy())),
+        ],
+        Shell::JSON => {
+            let mut vars = serde_json::Map::new();
+            vars.insert("VOLTA_HOME".to_string(), home.into());
+            vars.insert("PATH".to_string(), path.to_string_lossy().into());
+            // Removed variables are listed as `null`
+            for name in &settings.remove_env {
+                vars.insert(name.clone(), serde_json::Value::Null);
+            }
+            for (name, value) in &settings.set_env {
+                vars.insert(name.clone(), value.clone().into());
+            }
+            return serde_json::Value::Object(vars).to_string();
+        }
+    };
+
+    for name in &settings.remove_env {
+        lines.push(match shell {
+            Shell::Fish => format!("set -e {}", name),
+            Shell::PowerShell => format!("Remove-Item Env:{} -ErrorAction SilentlyContinue", name),
+            _ => format!("unset {}", name),
+        });
+    }
+    for (name, value) in &settings.set_env {
+        lines.push(match shell {
+            Shell::Fish => format!("set -gx {} {}", name, fish_quote(value)),
+            Shell::PowerShell => format!("$env:{} = {}", name, powershell_quote(value)),
+            _ => format!("export {}={}", name, posix_quote(value)),
+        });
+    }
+
+    lines.join("\n")
+}
+
+pub(crate) fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+pub(crate) fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+pub(crate) fn powershell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_values_for_each_shell() {
+        let home = OsString::from("/home/o'neil/my files/zoë/.volta");
+        let path = OsString::from("/bin");
+        let format = |shell| format_env(shell, &home, &path, &Settings::default());
+
+        assert_eq!(
+            format(Shell::Bash),
+            "export VOLTA_HOME='/home/o'\\''neil/my files/zoë/.volta'\nexport PATH='/bin'"
+        );
+        assert_eq!(
+            format(Shell::Fish),
+            "set -gx VOLTA_HOME '/home/o\\'neil/my files/zoë/.volta'\nset -gx PATH '/bin'"
+        );
+        assert_eq!(
+            format(Shell::PowerShell),
+            "$env:VOLTA_HOME = '/home/o''neil/my files/zoë/.volta'\n$env:PATH = '/bin'"
+        );
+    }
+
+    #[test]
+    fn json_includes_both_variables() {
+        let home = OsString::from("/home/user/.volta");
+        let path = OsString::from("/bin");
+        let value: serde_json::Value =
+            serde_json::from_str(&format_env(Shell::JSON, &home, &path, &Settings::default()))
+                .unwrap();
+        assert_eq!(value["VOLTA_HOME"], "/home/user/.volta");
+        assert_eq!(value["PATH"], "/bin");
+    }
+
+    #[test]
+    fn includes_env_settings() {
+        let home = OsString::from("/home/user/.volta");
+        let path = OsString::from("/bin");
+        let mut settings = Settings::default();
+        settings.remove_env = vec!["NODE_PATH".to_string()];
+        settings
+            .set_env
+            .insert("npm_config_prefix".to_string(), "/opt/npm".to_string());
+
+        assert_eq!(
+            format_env(Shell::Bash, &home, &path, &settings),
+            "export VOLTA_HOME='/home/user/.volta'\nexport PATH='/bin'\nunset NODE_PATH\nexport npm_config_prefix='/opt/npm'"
+        );
+
+        let value: serde_json::Value =
+            serde_json::from_str(&format_env(Shell::JSON, &home, &path, &settings)).unwrap();
+        assert_eq!(value["NODE_PATH"], serde_json::Value::Null);
+        assert_eq!(value["npm_config_prefix"], "/opt/npm");
+    }
+}
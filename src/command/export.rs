@@ -0,0 +1,175 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::read_to_string;
+use std::path::Path;
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use volta_core::error::ErrorDetails;
+use volta_core::platform::PlatformSpec;
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible, ResultExt};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Export {}
+
+impl Command for Export {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Export);
+
+        let state = State::current(session)?;
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&state)
+                .expect("the toolchain state can always be serialized")
+        );
+
+        session.add_event_end(ActivityKind::Export, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+/// The state of the user's toolchain, as written by `volta export` and read by `volta import`
+#[derive(Serialize, Deserialize)]
+pub(crate) struct State {
+    /// The default platform, if one has been installed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) default: Option<PlatformSpec>,
+
+    /// The default versions of custom tools, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) tools: BTreeMap<String, Version>,
+
+    /// Every version of each tool that has been fetched
+    #[serde(default)]
+    pub(crate) installed: InstalledVersions,
+
+    /// The globally installed packages
+    #[serde(default)]
+    pub(crate) packages: Vec<InstalledPackage>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct InstalledVersions {
+    #[serde(default)]
+    pub(crate) node: BTreeSet<Version>,
+    #[serde(default)]
+    pub(crate) npm: BTreeSet<Version>,
+    #[serde(default)]
+    pub(crate) yarn: BTreeSet<Version>,
+    #[serde(default)]
+    pub(crate) pnpm: BTreeSet<Version>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct InstalledPackage {
+    pub(crate) name: String,
+    pub(crate) version: Version,
+
+    /// The platform the package was installed with, which files from earlier versions don't have
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) platform: Option<PlatformSpec>,
+
+    /// The registry the package was installed from, if it was given with `--registry`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) registry: Option<String>,
+}
+
+impl State {
+    /// Gathers the state of the current toolchain
+    fn current(session: &Session) -> Fallible<Self> {
+        let default = session
+            .default_platform()?
+            .map(|platform| (*platform).clone());
+        let tools = session.toolchain()?.custom_tools().clone();
+
+        let inventory = session.inventory()?;
+        let installed = InstalledVersions {
+            node: inventory.node.versions.clone(),
+            npm: inventory.npm.versions.clone(),
+            yarn: inventory.yarn.versions.clone(),
+            pnpm: inventory.pnpm.versions.clone(),
+        };
+        // Linked packages run from a working copy on this machine, so they can't be restored
+        let packages = inventory
+            .packages
+            .iter()
+            .filter(|config| config.link.is_none())
+            .map(|config| InstalledPackage {
+                name: config.name.clone(),
+                version: config.version.clone(),
+                platform: Some(config.platform.clone()),
+                registry: config.registry.clone(),
+            })
+            .collect();
+
+        Ok(State {
+            default,
+            tools,
+            installed,
+            packages,
+        })
+    }
+
+    /// Reads a state file written by `volta export`
+    pub(crate) fn from_file(file: &Path) -> Fallible<Self> {
+        let contents = read_to_string(file).with_context(|_| ErrorDetails::ReadStateError {
+            file: file.to_path_buf(),
+        })?;
+
+        serde_json::from_str(&contents).with_context(|_| ErrorDetails::ParseStateError {
+            file: file.to_path_buf(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_defaults_missing_sections() {
+        let state: State = serde_json::from_str(r#"{ "default": { "node": "12.16.0" } }"#).unwrap();
+
+        let default = state.default.unwrap();
+        assert_eq!(default.node_runtime, Version::parse("12.16.0").unwrap());
+        assert_eq!(default.npm, None);
+        assert!(state.tools.is_empty());
+        assert!(state.installed.node.is_empty());
+        assert!(state.packages.is_empty());
+    }
+
+    #[test]
+    fn test_packages_round_trip() {
+        let package = InstalledPackage {
+            name: "cowsay".to_string(),
+            version: Version::parse("1.4.0").unwrap(),
+            platform: Some(PlatformSpec {
+                node_runtime: Version::parse("14.15.0").unwrap(),
+                npm: Some(Version::parse("6.14.8").unwrap()),
+                yarn: None,
+                pnpm: None,
+            }),
+            registry: Some("https://npm.example.com/".to_string()),
+        };
+        let state = State {
+            default: None,
+            tools: BTreeMap::new(),
+            installed: InstalledVersions::default(),
+            packages: vec![package],
+        };
+
+        let serialized = serde_json::to_string(&state).unwrap();
+        let read: State = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(read.packages, state.packages);
+
+        let earlier: State =
+            serde_json::from_str(r#"{ "packages": [{ "name": "cowsay", "version": "1.4.0" }] }"#)
+                .unwrap();
+        assert_eq!(earlier.packages[0].platform, None);
+        assert_eq!(earlier.packages[0].registry, None);
+    }
+}
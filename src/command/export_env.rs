@@ -0,0 +1,147 @@
+use std::str::FromStr;
+
+use structopt::StructOpt;
+
+use volta_core::error::ErrorDetails;
+use volta_core::platform::PlatformSpec;
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{throw, ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(Copy, Clone, PartialEq)]
+enum Format {
+    Direnv,
+    Asdf,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "direnv" => Ok(Format::Direnv),
+            "asdf" => Ok(Format::Asdf),
+            _ => Err(format!("Unsupported format: {}", s)),
+        }
+    }
+}
+
+#[derive(StructOpt)]
+pub(crate) struct ExportEnv {
+    /// The format to generate: `direnv` prints lines for an `.envrc`, and `asdf` prints
+    /// `.tool-versions` entries, which mise reads as well
+    #[structopt(long = "format", raw(possible_values = r#"&["direnv", "asdf"]"#))]
+    format: Format,
+}
+
+impl Command for ExportEnv {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::ExportEnv);
+
+        let platform = match session.project()? {
+            Some(project) => project.platform(),
+            None => throw!(ErrorDetails::NotInPackage),
+        };
+        let platform = match platform {
+            Some(platform) => platform,
+            None => throw!(ErrorDetails::NothingToExport),
+        };
+
+        match self.format {
+            Format::Direnv => print!("{}", format_direnv(&platform)),
+            Format::Asdf => print!("{}", format_asdf(&platform)),
+        }
+
+        session.add_event_end(ActivityKind::ExportEnv, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+/// Formats the pins as lines for an `.envrc`, using the `use node` function of the direnv
+/// standard library
+///
+/// direnv has no way to select a package manager, so those pins are only noted in comments.
+fn format_direnv(platform: &PlatformSpec) -> String {
+    let mut output =
+        String::from("# Generated from the Volta pins in package.json by `volta export-env`\n");
+    output.push_str(&format!("use node {}\n", platform.node_runtime));
+    for (tool, version) in package_managers(platform) {
+        output.push_str(&format!(
+            "# {} {} is pinned as well, but can't be selected by direnv\n",
+            tool, version
+        ));
+    }
+    output
+}
+
+/// Formats the pins as `.tool-versions` entries, named after the asdf plugins for each tool
+///
+/// There is no asdf plugin for npm, so a pinned npm is only noted in a comment.
+fn format_asdf(platform: &PlatformSpec) -> String {
+    let mut output =
+        String::from("# Generated from the Volta pins in package.json by `volta export-env`\n");
+    output.push_str(&format!("nodejs {}\n", platform.node_runtime));
+    for (tool, version) in package_managers(platform) {
+        if tool == "npm" {
+            output.push_str(&format!(
+                "# npm {} is pinned as well, but can't be selected by asdf\n",
+                version
+            ));
+        } else {
+            output.push_str(&format!("{} {}\n", tool, version));
+        }
+    }
+    output
+}
+
+/// Returns the pinned package managers, by tool name
+fn package_managers(platform: &PlatformSpec) -> Vec<(&'static str, String)> {
+    vec![
+        ("npm", &platform.npm),
+        ("yarn", &platform.yarn),
+        ("pnpm", &platform.pnpm),
+    ]
+    .into_iter()
+    .filter_map(|(tool, version)| version.as_ref().map(|version| (tool, version.to_string())))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use semver::Version;
+
+    fn platform() -> PlatformSpec {
+        PlatformSpec {
+            node_runtime: Version::parse("18.19.0").unwrap(),
+            npm: Some(Version::parse("10.2.3").unwrap()),
+            yarn: Some(Version::parse("1.22.19").unwrap()),
+            pnpm: None,
+        }
+    }
+
+    #[test]
+    fn formats_tool_versions() {
+        assert_eq!(
+            format_asdf(&platform()),
+            "# Generated from the Volta pins in package.json by `volta export-env`
+nodejs 18.19.0
+# npm 10.2.3 is pinned as well, but can't be selected by asdf
+yarn 1.22.19
+"
+        );
+    }
+
+    #[test]
+    fn formats_envrc() {
+        assert_eq!(
+            format_direnv(&platform()),
+            "# Generated from the Volta pins in package.json by `volta export-env`
+use node 18.19.0
+# npm 10.2.3 is pinned as well, but can't be selected by direnv
+# yarn 1.22.19 is pinned as well, but can't be selected by direnv
+"
+        );
+    }
+}
@@ -1,7 +1,10 @@
+use semver::Version;
 use structopt::StructOpt;
 
+use volta_core::error::ErrorDetails;
 use volta_core::session::{ActivityKind, Session};
-use volta_core::tool;
+use volta_core::tool::{self, load_default_npm_version};
+use volta_core::version::VersionSpec;
 use volta_fail::{ExitCode, Fallible};
 
 use crate::command::Command;
@@ -9,19 +12,111 @@ use crate::command::Command;
 #[derive(StructOpt)]
 pub(crate) struct Fetch {
     /// Tools to fetch, like `node`, `yarn@latest` or `your-package@^14.4.3`.
-    #[structopt(name = "tool[@version]", required = true, min_values = 1)]
+    #[structopt(name = "tool[@version]", required_unless = "from_project")]
     tools: Vec<String>,
+
+    /// Fetches every tool version pinned in the current project
+    #[structopt(long = "from-project")]
+    from_project: bool,
 }
 
 impl Command for Fetch {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Fetch);
 
+        let mut tools = Vec::new();
         for tool in tool::Spec::from_strings(&self.tools, "fetch")? {
-            tool.resolve(session)?.fetch(session)?;
+            tools.push(tool.resolve(session)?);
+        }
+        prefetch(&tools, session)?;
+        for tool in tools {
+            tool.fetch(session)?;
+        }
+
+        if self.from_project {
+            fetch_project(session)?;
         }
 
         session.add_event_end(ActivityKind::Fetch, ExitCode::Success);
         Ok(ExitCode::Success)
     }
 }
+
+/// Fetches the platform and the custom tools pinned in the current project, without changing
+/// the default toolchain
+fn fetch_project(session: &mut Session) -> Fallible<()> {
+    let (platform, custom_tools) = match session.project()? {
+        Some(project) => (project.platform(), project.manifest().tools.clone()),
+        None => return Err(ErrorDetails::NotInPackage.into()),
+    };
+
+    let mut tools = Vec::new();
+    if let Some(platform) = &platform {
+        let exact = |version: &Version| VersionSpec::Exact(version.clone());
+
+        tools.push(tool::Spec::Node(exact(&platform.node_runtime)).resolve(session)?);
+        if let Some(yarn) = &platform.yarn {
+            tools.push(tool::Spec::Yarn(exact(yarn)).resolve(session)?);
+        }
+        if let Some(pnpm) = &platform.pnpm {
+            tools.push(tool::Spec::Pnpm(exact(pnpm)).resolve(session)?);
+        }
+    }
+    for (name, version) in custom_tools {
+        tools.push(custom_tool_spec(name, version, session)?.resolve(session)?);
+    }
+
+    prefetch(&tools, session)?;
+    let mut tools = tools.into_iter();
+    if let Some(platform) = &platform {
+        if let Some(node) = tools.next() {
+            node.fetch(session)?;
+        }
+
+        // The npm bundled with Node comes with it, so only a separately pinned npm is fetched
+        if let Some(npm) = &platform.npm {
+            if load_default_npm_version(&platform.node_runtime)
+                .ok()
+                .as_ref()
+                != Some(npm)
+            {
+                tool::Spec::Npm(VersionSpec::Exact(npm.clone()))
+                    .resolve(session)?
+                    .fetch(session)?;
+            }
+        }
+    }
+    for tool in tools {
+        tool.fetch(session)?;
+    }
+
+    Ok(())
+}
+
+/// Downloads the archives of the tools concurrently, when there are several to fetch, so that
+/// fetching each tool afterwards finds its archive in the inventory
+fn prefetch(tools: &[tool::Resolved], session: &Session) -> Fallible<()> {
+    if tools.len() < 2 {
+        return Ok(());
+    }
+
+    let mut prefetch = tool::Prefetch::new();
+    for tool in tools {
+        prefetch.start(tool, session)?;
+    }
+    prefetch.finish()
+}
+
+/// The spec for a pinned or default version of a custom tool, which must be defined in the hooks
+pub(crate) fn custom_tool_spec(
+    name: String,
+    version: Version,
+    session: &Session,
+) -> Fallible<tool::Spec> {
+    // Without a definition in the hooks, the name would be looked up as an npm package
+    if session.hooks()?.custom_tool(&name).is_none() {
+        return Err(ErrorDetails::PackageNotFound { package: name }.into());
+    }
+
+    Ok(tool::Spec::Package(name, VersionSpec::Exact(version)))
+}
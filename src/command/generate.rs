@@ -0,0 +1,207 @@
+use std::str::FromStr;
+
+use structopt::StructOpt;
+
+use volta_core::container::{ContainerToolchain, NodeDownload, PackageDownload};
+use volta_core::error::ErrorDetails;
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{throw, ExitCode, Fallible};
+
+use crate::command::Command;
+
+/// The base image of the generated devcontainer, which has `curl` and the tools the snippet uses
+const DEVCONTAINER_BASE_IMAGE: &str = "mcr.microsoft.com/devcontainers/base:bookworm";
+
+#[derive(Copy, Clone, PartialEq)]
+enum Kind {
+    Dockerfile,
+    Devcontainer,
+}
+
+impl FromStr for Kind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dockerfile" => Ok(Kind::Dockerfile),
+            "devcontainer" => Ok(Kind::Devcontainer),
+            _ => Err(format!("Unsupported kind: {}", s)),
+        }
+    }
+}
+
+#[derive(StructOpt)]
+pub(crate) struct Generate {
+    /// What to generate: `dockerfile` prints `RUN` instructions to add to an existing
+    /// Dockerfile, and `devcontainer` prints a complete Dockerfile for `.devcontainer/`
+    #[structopt(
+        name = "kind",
+        raw(possible_values = r#"&["dockerfile", "devcontainer"]"#)
+    )]
+    kind: Kind,
+}
+
+impl Command for Generate {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Generate);
+
+        let platform = match session.project()? {
+            Some(project) => project.platform(),
+            None => throw!(ErrorDetails::NotInPackage),
+        };
+        let platform = match platform {
+            Some(platform) => platform,
+            None => throw!(ErrorDetails::NothingToGenerate),
+        };
+
+        let toolchain = ContainerToolchain::resolve(&platform)?;
+        match self.kind {
+            Kind::Dockerfile => print!("{}", format_dockerfile(&toolchain)),
+            Kind::Devcontainer => print!("{}", format_devcontainer(&toolchain)),
+        }
+
+        session.add_event_end(ActivityKind::Generate, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+/// Formats the instructions that install the toolchain into `/usr/local`, for a Debian-based
+/// image with `curl`
+fn format_dockerfile(toolchain: &ContainerToolchain) -> String {
+    let mut output =
+        String::from("# Generated from the Volta pins in package.json by `volta generate`\n");
+    output.push_str(&format_node(&toolchain.node));
+    for package in &toolchain.package_managers {
+        output.push_str(&format_package(package));
+    }
+    output
+}
+
+/// Formats a complete Dockerfile for a devcontainer, to be referenced from `devcontainer.json`
+fn format_devcontainer(toolchain: &ContainerToolchain) -> String {
+    let mut output = String::from(
+        "# Reference this file from .devcontainer/devcontainer.json with:\n\
+         #     \"build\": { \"dockerfile\": \"Dockerfile\" }\n",
+    );
+    output.push_str(&format!("FROM {}\n", DEVCONTAINER_BASE_IMAGE));
+    output.push_str(&format_dockerfile(toolchain));
+    output
+}
+
+/// Formats the instructions that download the Node distro for the architecture of the image,
+/// verify its SHA-256 checksum, and unpack it into `/usr/local`
+fn format_node(node: &NodeDownload) -> String {
+    let mut output = String::from("ARG TARGETARCH\n");
+    output.push_str(&format!(
+        "RUN set -eux; \\\n    case \"${{TARGETARCH:-{}}}\" in \\\n",
+        node.distros[0].target_arch
+    ));
+    for distro in &node.distros {
+        output.push_str(&format!(
+            "      {}) NODE_FILE={}; NODE_SHA256={};; \\\n",
+            distro.target_arch, distro.file_name, distro.sha256
+        ));
+    }
+    output.push_str(
+        "      *) echo \"Node is not available for ${TARGETARCH}\" >&2; exit 1;; \\\n    esac; \\\n",
+    );
+    output.push_str(&format!(
+        "    curl -fsSLo /tmp/node.tar.gz \"{}/${{NODE_FILE}}\"; \\\n",
+        node.base_url
+    ));
+    output.push_str(
+        "    echo \"${NODE_SHA256}  /tmp/node.tar.gz\" | sha256sum -c -; \\\n    \
+         tar -xzf /tmp/node.tar.gz -C /usr/local --strip-components=1 --no-same-owner; \\\n    \
+         rm /tmp/node.tar.gz; \\\n    \
+         node --version\n",
+    );
+    output
+}
+
+/// Formats the instructions that download a package manager, verify its SHA-512 checksum, and
+/// install it globally with the npm bundled with Node
+fn format_package(package: &PackageDownload) -> String {
+    let file = format!("/tmp/{}.tgz", package.tool);
+    format!(
+        "# {tool} {version}
+RUN set -eux; \\
+    curl -fsSLo {file} \"{tarball}\"; \\
+    echo \"{sha512}  {file}\" | sha512sum -c -; \\
+    npm install --global {file}; \\
+    rm {file}
+",
+        tool = package.tool,
+        version = package.version,
+        file = file,
+        tarball = package.tarball,
+        sha512 = package.sha512
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use semver::Version;
+    use volta_core::container::NodeDistro;
+
+    fn toolchain() -> ContainerToolchain {
+        ContainerToolchain {
+            node: NodeDownload {
+                version: Version::parse("18.19.0").unwrap(),
+                base_url: "https://nodejs.org/dist/v18.19.0".to_string(),
+                distros: vec![
+                    NodeDistro {
+                        target_arch: "amd64",
+                        file_name: "node-v18.19.0-linux-x64.tar.gz".to_string(),
+                        sha256: "6163".to_string(),
+                    },
+                    NodeDistro {
+                        target_arch: "arm64",
+                        file_name: "node-v18.19.0-linux-arm64.tar.gz".to_string(),
+                        sha256: "dbcc".to_string(),
+                    },
+                ],
+            },
+            package_managers: vec![PackageDownload {
+                tool: "yarn",
+                version: Version::parse("1.22.19").unwrap(),
+                tarball: "https://registry.npmjs.org/yarn/-/yarn-1.22.19.tgz".to_string(),
+                sha512: "4ea7".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn formats_dockerfile_snippet() {
+        assert_eq!(
+            format_dockerfile(&toolchain()),
+            r#"# Generated from the Volta pins in package.json by `volta generate`
+ARG TARGETARCH
+RUN set -eux; \
+    case "${TARGETARCH:-amd64}" in \
+      amd64) NODE_FILE=node-v18.19.0-linux-x64.tar.gz; NODE_SHA256=6163;; \
+      arm64) NODE_FILE=node-v18.19.0-linux-arm64.tar.gz; NODE_SHA256=dbcc;; \
+      *) echo "Node is not available for ${TARGETARCH}" >&2; exit 1;; \
+    esac; \
+    curl -fsSLo /tmp/node.tar.gz "https://nodejs.org/dist/v18.19.0/${NODE_FILE}"; \
+    echo "${NODE_SHA256}  /tmp/node.tar.gz" | sha256sum -c -; \
+    tar -xzf /tmp/node.tar.gz -C /usr/local --strip-components=1 --no-same-owner; \
+    rm /tmp/node.tar.gz; \
+    node --version
+# yarn 1.22.19
+RUN set -eux; \
+    curl -fsSLo /tmp/yarn.tgz "https://registry.npmjs.org/yarn/-/yarn-1.22.19.tgz"; \
+    echo "4ea7  /tmp/yarn.tgz" | sha512sum -c -; \
+    npm install --global /tmp/yarn.tgz; \
+    rm /tmp/yarn.tgz
+"#
+        );
+    }
+
+    #[test]
+    fn formats_devcontainer_dockerfile() {
+        let output = format_devcontainer(&toolchain());
+        assert!(output.contains(&format!("\nFROM {}\n", DEVCONTAINER_BASE_IMAGE)));
+        assert!(output.ends_with(&format_dockerfile(&toolchain())));
+    }
+}
@@ -0,0 +1,141 @@
+use structopt::StructOpt;
+
+use volta_core::history::{self, Entry};
+use volta_core::session::{ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::doctor::Format;
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct History {
+    /// Only show changes to the given tool
+    #[structopt(long = "tool")]
+    tool: Option<String>,
+
+    /// Only show the given number of most recent changes
+    #[structopt(long = "limit", short = "n")]
+    limit: Option<usize>,
+
+    /// The format of the history
+    #[structopt(
+        long = "format",
+        default_value = "human",
+        raw(possible_values = r#"&["human", "json"]"#)
+    )]
+    format: Format,
+}
+
+impl Command for History {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::History);
+
+        let mut entries: Vec<Entry> = history::read()?
+            .into_iter()
+            .filter(|entry| self.tool.as_ref().map_or(true, |tool| entry.tool == *tool))
+            .collect();
+        if let Some(limit) = self.limit {
+            let skipped = entries.len().saturating_sub(limit);
+            entries.drain(..skipped);
+        }
+
+        match self.format {
+            Format::Human => println!("{}", format_human(&entries)),
+            Format::JSON => println!(
+                "{}",
+                serde_json::to_string_pretty(&entries)
+                    .expect("the history can always be serialized")
+            ),
+        }
+
+        session.add_event_end(ActivityKind::History, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+/// Describes the versions before and after a change
+fn format_change(entry: &Entry) -> String {
+    match (&entry.previous, &entry.version) {
+        (Some(previous), Some(version)) => format!("{} -> {}", previous, version),
+        (None, Some(version)) => version.clone(),
+        (Some(previous), None) => format!("{} -> (none)", previous),
+        (None, None) => String::new(),
+    }
+}
+
+fn format_human(entries: &[Entry]) -> String {
+    if entries.is_empty() {
+        return "No changes to the toolchain have been recorded.".to_string();
+    }
+
+    let rows: Vec<[String; 6]> = entries
+        .iter()
+        .map(|entry| {
+            [
+                entry.timestamp.clone(),
+                entry.user.clone(),
+                entry.action.to_string(),
+                entry.tool.clone(),
+                format_change(entry),
+                entry
+                    .project
+                    .as_ref()
+                    .map(|project| project.display().to_string())
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..6)
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect();
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use volta_core::history::Action;
+
+    fn entry(action: Action, tool: &str, previous: Option<&str>, version: Option<&str>) -> Entry {
+        Entry {
+            timestamp: "2020-01-02T03:04:05+00:00".to_string(),
+            user: "alice".to_string(),
+            action,
+            tool: tool.to_string(),
+            previous: previous.map(String::from),
+            version: version.map(String::from),
+            project: None,
+        }
+    }
+
+    #[test]
+    fn formats_history_as_a_table() {
+        let mut pin = entry(Action::Pin, "yarn", None, Some("1.19.1"));
+        pin.project = Some(PathBuf::from("/home/alice/app"));
+        let entries = vec![
+            entry(Action::Default, "node", Some("10.16.3"), Some("12.13.0")),
+            pin,
+            entry(Action::Uninstall, "typescript", Some("3.7.2"), None),
+        ];
+
+        assert_eq!(
+            format_human(&entries),
+            "2020-01-02T03:04:05+00:00  alice  default    node        10.16.3 -> 12.13.0
+2020-01-02T03:04:05+00:00  alice  pin        yarn        1.19.1              /home/alice/app
+2020-01-02T03:04:05+00:00  alice  uninstall  typescript  3.7.2 -> (none)"
+        );
+    }
+}
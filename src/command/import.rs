@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_core::tool::{self, load_default_npm_version, NpmFlags, Resolved, Tool};
+use volta_core::version::VersionSpec;
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::export::State;
+use crate::command::fetch::custom_tool_spec;
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Import {
+    /// A toolchain state file written by `volta export`
+    #[structopt(name = "file", parse(from_os_str))]
+    file: PathBuf,
+}
+
+impl Command for Import {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Import);
+
+        let state = State::from_file(&self.file)?;
+
+        // Every fetched version is restored before the defaults change, so that a failed
+        // download leaves the toolchain as it was
+        let installed = state.installed;
+        let fetches = installed
+            .node
+            .into_iter()
+            .map(|version| tool::Spec::Node(VersionSpec::Exact(version)))
+            .chain(
+                installed
+                    .npm
+                    .into_iter()
+                    .map(|version| tool::Spec::Npm(VersionSpec::Exact(version))),
+            )
+            .chain(
+                installed
+                    .yarn
+                    .into_iter()
+                    .map(|version| tool::Spec::Yarn(VersionSpec::Exact(version))),
+            )
+            .chain(
+                installed
+                    .pnpm
+                    .into_iter()
+                    .map(|version| tool::Spec::Pnpm(VersionSpec::Exact(version))),
+            );
+        for spec in fetches {
+            spec.resolve(session)?.fetch(session)?;
+        }
+
+        if let Some(default) = state.default {
+            let node = default.node_runtime.clone();
+            tool::Spec::Node(VersionSpec::Exact(default.node_runtime))
+                .resolve(session)?
+                .install(session)?;
+
+            // Installing Node selects its bundled npm, so only a different npm is installed
+            if let Some(npm) = default.npm {
+                if load_default_npm_version(&node).ok().as_ref() != Some(&npm) {
+                    tool::Spec::Npm(VersionSpec::Exact(npm))
+                        .resolve(session)?
+                        .install(session)?;
+                }
+            }
+
+            if let Some(yarn) = default.yarn {
+                tool::Spec::Yarn(VersionSpec::Exact(yarn))
+                    .resolve(session)?
+                    .install(session)?;
+            }
+
+            if let Some(pnpm) = default.pnpm {
+                tool::Spec::Pnpm(VersionSpec::Exact(pnpm))
+                    .resolve(session)?
+                    .install(session)?;
+            }
+        }
+
+        for (name, version) in state.tools {
+            custom_tool_spec(name, version, session)?
+                .resolve(session)?
+                .install(session)?;
+        }
+
+        // Packages are installed last, with the Node they were installed with, or else the
+        // imported default Node
+        for package in state.packages {
+            session.set_npm_flags(NpmFlags {
+                registry: package.registry,
+                ..NpmFlags::default()
+            });
+            let resolved = tool::Spec::Package(package.name, VersionSpec::Exact(package.version))
+                .resolve(session)?;
+            match (resolved, package.platform) {
+                (Resolved::Package(resolved), Some(platform)) => {
+                    resolved.with_node(platform.node_runtime).install(session)?
+                }
+                (resolved, _) => resolved.install(session)?,
+            }
+        }
+
+        session.add_event_end(ActivityKind::Import, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
@@ -0,0 +1,333 @@
+use std::env;
+use std::fs::{read_dir, read_link, read_to_string};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use log::{info, warn};
+use semver::Version;
+use structopt::StructOpt;
+
+use volta_core::error::ErrorDetails;
+use volta_core::session::{ActivityKind, Session};
+use volta_core::style::success_prefix;
+use volta_core::tool::{self, Node};
+use volta_core::version::{parse_version, VersionSpec};
+use volta_fail::{throw, ExitCode, Fallible};
+
+use crate::command::Command;
+
+/// A version manager that tools can be imported from
+#[derive(Copy, Clone, PartialEq)]
+enum Manager {
+    Nvm,
+    Asdf,
+    Fnm,
+}
+
+impl FromStr for Manager {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nvm" => Ok(Manager::Nvm),
+            "asdf" => Ok(Manager::Asdf),
+            "fnm" => Ok(Manager::Fnm),
+            _ => Err(format!("Unsupported version manager: {}", s)),
+        }
+    }
+}
+
+#[derive(StructOpt)]
+pub(crate) struct ImportTools {
+    /// The version manager to import the installed tools from
+    #[structopt(long = "from", raw(possible_values = r#"&["nvm", "asdf", "fnm"]"#))]
+    from: Manager,
+
+    /// Also sets the default versions to the defaults of the version manager
+    #[structopt(long = "default")]
+    default: bool,
+}
+
+/// A version of a tool installed by another version manager
+struct Installation {
+    tool: &'static str,
+    version: Version,
+    dir: PathBuf,
+}
+
+impl Command for ImportTools {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::ImportTools);
+
+        let manager = self.from;
+        let dir = manager.dir()?;
+        let installations = manager.installations(&dir);
+        if installations.is_empty() {
+            warn!("No installed tools were found in {}", dir.display());
+        }
+
+        for installation in &installations {
+            import(installation, manager, session)?;
+        }
+
+        if self.default {
+            let node_versions: Vec<&Version> = installations
+                .iter()
+                .filter(|installation| installation.tool == "node")
+                .map(|installation| &installation.version)
+                .collect();
+
+            for (name, version) in manager.defaults(&dir, &node_versions) {
+                let version = VersionSpec::Exact(version);
+                let spec = match name {
+                    "node" => tool::Spec::Node(version),
+                    "yarn" => tool::Spec::Yarn(version),
+                    _ => tool::Spec::Pnpm(version),
+                };
+                spec.resolve(session)?.install(session)?;
+            }
+        }
+
+        session.add_event_end(ActivityKind::ImportTools, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+/// Imports an installed version into Volta, downloading it again if the installation can't be
+/// used as it is
+fn import(installation: &Installation, manager: Manager, session: &mut Session) -> Fallible<()> {
+    let version = &installation.version;
+    if installation.tool == "node"
+        && Node::new(version.clone())
+            .import(&installation.dir)?
+            .is_some()
+    {
+        info!(
+            "{} imported node@{} from {}",
+            success_prefix(),
+            version,
+            manager.name()
+        );
+        return Ok(());
+    }
+
+    // Only Node installations have the same layout as Volta's images
+    let version_spec = VersionSpec::Exact(version.clone());
+    let spec = match installation.tool {
+        "node" => tool::Spec::Node(version_spec),
+        "yarn" => tool::Spec::Yarn(version_spec),
+        _ => tool::Spec::Pnpm(version_spec),
+    };
+    spec.resolve(session)?.fetch(session)
+}
+
+impl Manager {
+    fn name(self) -> &'static str {
+        match self {
+            Manager::Nvm => "nvm",
+            Manager::Asdf => "asdf",
+            Manager::Fnm => "fnm",
+        }
+    }
+
+    /// Returns the directory that the version manager keeps its installations in
+    fn dir(self) -> Fallible<PathBuf> {
+        let home = dirs::home_dir().ok_or(ErrorDetails::NoHomeEnvironmentVar)?;
+        let dir = match self {
+            Manager::Nvm => env::var_os("NVM_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| home.join(".nvm")),
+            Manager::Asdf => env::var_os("ASDF_DATA_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| home.join(".asdf")),
+            Manager::Fnm => match env::var_os("FNM_DIR") {
+                Some(dir) => PathBuf::from(dir),
+                None if home.join(".fnm").is_dir() => home.join(".fnm"),
+                None => dirs::data_dir().unwrap_or(home).join("fnm"),
+            },
+        };
+
+        if !dir.is_dir() {
+            throw!(ErrorDetails::VersionManagerNotFound {
+                manager: self.name().to_string(),
+                dir,
+            });
+        }
+        Ok(dir)
+    }
+
+    /// Returns the versions installed in the directory of the version manager
+    fn installations(self, dir: &Path) -> Vec<Installation> {
+        match self {
+            Manager::Nvm => {
+                installed_versions("node", &dir.join("versions").join("node"), Path::to_owned)
+            }
+            Manager::Asdf => {
+                let installs = dir.join("installs");
+                let mut installations =
+                    installed_versions("node", &installs.join("nodejs"), Path::to_owned);
+                installations.extend(installed_versions(
+                    "yarn",
+                    &installs.join("yarn"),
+                    Path::to_owned,
+                ));
+                installations.extend(installed_versions(
+                    "pnpm",
+                    &installs.join("pnpm"),
+                    Path::to_owned,
+                ));
+                installations
+            }
+            Manager::Fnm => installed_versions("node", &dir.join("node-versions"), |dir| {
+                dir.join("installation")
+            }),
+        }
+    }
+
+    /// Returns the default versions of the version manager, by tool name
+    fn defaults(self, dir: &Path, node_versions: &[&Version]) -> Vec<(&'static str, Version)> {
+        match self {
+            Manager::Nvm => nvm_default(dir, node_versions)
+                .map(|version| vec![("node", version)])
+                .unwrap_or_default(),
+            Manager::Asdf => asdf_defaults(),
+            Manager::Fnm => read_link(dir.join("aliases").join("default"))
+                .ok()
+                .and_then(|target| {
+                    target
+                        .components()
+                        .find_map(|component| parse_dir_version(component.as_os_str().to_str()?))
+                })
+                .map(|version| vec![("node", version)])
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Returns the versions of a tool installed in `versions_dir`, one directory per version, where
+/// `install_dir` gives the installation within the directory of a version
+fn installed_versions<F>(
+    tool: &'static str,
+    versions_dir: &Path,
+    install_dir: F,
+) -> Vec<Installation>
+where
+    F: Fn(&Path) -> PathBuf,
+{
+    let entries = match read_dir(versions_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut installations: Vec<Installation> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let version = parse_dir_version(path.file_name()?.to_str()?)?;
+            Some(Installation {
+                tool,
+                version,
+                dir: install_dir(&path),
+            })
+        })
+        .collect();
+    installations.sort_by(|a, b| a.version.cmp(&b.version));
+    installations
+}
+
+/// Parses the name of the directory of a version, which nvm and fnm prefix with `v`
+fn parse_dir_version(name: &str) -> Option<Version> {
+    parse_version(name.trim_start_matches('v')).ok()
+}
+
+/// Resolves the `default` alias of nvm, which may refer to other aliases, such as `lts/*`, to
+/// one of the installed versions
+fn nvm_default(dir: &Path, node_versions: &[&Version]) -> Option<Version> {
+    let aliases = dir.join("alias");
+    let mut alias = "default".to_string();
+    // Aliases are followed a limited number of times, in case they refer to each other
+    for _ in 0..10 {
+        match read_to_string(aliases.join(&alias)) {
+            Ok(target) => alias = target.trim().to_string(),
+            Err(_) => break,
+        }
+    }
+
+    let newest = || node_versions.iter().max().map(|version| (*version).clone());
+    match alias.as_str() {
+        "node" | "stable" => newest(),
+        _ => match VersionSpec::from_str(alias.trim_start_matches('v')).ok()? {
+            VersionSpec::Exact(version) => Some(version),
+            VersionSpec::Semver(requirement) => node_versions
+                .iter()
+                .filter(|version| requirement.matches(version))
+                .max()
+                .map(|version| (*version).clone()),
+            _ => None,
+        },
+    }
+}
+
+/// Reads the global `.tool-versions` file of asdf, where the first version listed for each tool
+/// is the default
+fn asdf_defaults() -> Vec<(&'static str, Version)> {
+    let file_name =
+        env::var("ASDF_DEFAULT_TOOL_VERSIONS_FILENAME").unwrap_or_else(|_| ".tool-versions".into());
+    let contents = match dirs::home_dir().and_then(|home| read_to_string(home.join(file_name)).ok())
+    {
+        Some(contents) => contents,
+        None => return Vec::new(),
+    };
+    parse_tool_versions(&contents)
+}
+
+fn parse_tool_versions(contents: &str) -> Vec<(&'static str, Version)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split('#').next()?.split_whitespace();
+            let tool = match words.next()? {
+                "nodejs" => "node",
+                "yarn" => "yarn",
+                "pnpm" => "pnpm",
+                _ => return None,
+            };
+            Some((tool, parse_version(words.next()?).ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+
+    fn version(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    #[test]
+    fn resolves_nvm_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        let aliases = dir.path().join("alias");
+        create_dir_all(aliases.join("lts")).unwrap();
+        write(aliases.join("default"), "lts/*\n").unwrap();
+        write(aliases.join("lts").join("*"), "lts/hydrogen\n").unwrap();
+        write(aliases.join("lts").join("hydrogen"), "v18\n").unwrap();
+
+        let installed = [version("16.20.2"), version("18.17.1"), version("18.19.0")];
+        let installed: Vec<&Version> = installed.iter().collect();
+        assert_eq!(
+            nvm_default(dir.path(), &installed),
+            Some(version("18.19.0"))
+        );
+    }
+
+    #[test]
+    fn parses_tool_versions() {
+        let contents = "nodejs 18.19.0 system\n# a comment\nyarn 1.22.19\nruby 3.2.2\n";
+        assert_eq!(
+            parse_tool_versions(contents),
+            vec![("node", version("18.19.0")), ("yarn", version("1.22.19"))]
+        );
+    }
+}
@@ -1,7 +1,10 @@
 use structopt::StructOpt;
 
+use volta_core::prompt;
 use volta_core::session::{ActivityKind, Session};
-use volta_core::tool::Spec;
+use volta_core::settings::InstallScripts;
+use volta_core::tool::{node_version_choices, NpmFlags, Spec};
+use volta_core::version::VersionSpec;
 use volta_fail::{ExitCode, Fallible};
 
 use crate::command::Command;
@@ -9,15 +12,57 @@ use crate::command::Command;
 #[derive(StructOpt)]
 pub(crate) struct Install {
     /// Tools to install, like `node`, `yarn@latest` or `your-package@^14.4.3`.
+    ///
+    /// In a terminal, `node` without a version offers a choice of recent Node versions.
     #[structopt(name = "tool[@version]", required = true, min_values = 1)]
     tools: Vec<String>,
+
+    /// Skip the install scripts of packages and their dependencies, listing the ones skipped
+    #[structopt(long = "ignore-scripts", conflicts_with = "clean_env_scripts")]
+    ignore_scripts: bool,
+
+    /// Run the install scripts of packages afterwards, with a cleared environment (not a sandbox)
+    #[structopt(long = "clean-env-scripts")]
+    clean_env_scripts: bool,
+
+    /// Install the peer dependencies of packages as npm 6 did
+    #[structopt(long = "legacy-peer-deps")]
+    legacy_peer_deps: bool,
+
+    /// The registry to fetch packages from, instead of the one configured for npm
+    #[structopt(long = "registry", value_name = "url")]
+    registry: Option<String>,
+
+    /// Skip the npm audit of package dependencies, which Volta already does by default
+    #[structopt(long = "no-audit")]
+    no_audit: bool,
 }
 
 impl Command for Install {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Install);
 
+        let install_scripts = if self.ignore_scripts {
+            Some(InstallScripts::Ignore)
+        } else if self.clean_env_scripts {
+            Some(InstallScripts::CleanEnv)
+        } else {
+            None
+        };
+        session.set_npm_flags(NpmFlags {
+            legacy_peer_deps: self.legacy_peer_deps,
+            registry: self.registry,
+            no_audit: self.no_audit,
+            install_scripts,
+        });
+
         for tool in Spec::from_strings(&self.tools, "install")? {
+            let tool = match tool {
+                Spec::Node(VersionSpec::None) if prompt::is_interactive() => {
+                    Spec::Node(choose_node_version(session)?)
+                }
+                tool => tool,
+            };
             tool.resolve(session)?.install(session)?;
         }
 
@@ -25,3 +70,19 @@ impl Command for Install {
         Ok(ExitCode::Success)
     }
 }
+
+/// Asks which Node version to install, falling back to the latest LTS if there is nothing to
+/// choose from
+fn choose_node_version(session: &mut Session) -> Fallible<VersionSpec> {
+    let choices = node_version_choices(session)?;
+    if choices.is_empty() {
+        return Ok(VersionSpec::None);
+    }
+
+    let index = prompt::select(
+        "Which version of Node would you like to install?",
+        &choices,
+        0,
+    )?;
+    Ok(VersionSpec::Exact(choices[index].version.clone()))
+}
@@ -1,16 +1,16 @@
 //! Define the "human" format style for list commands.
 
-use textwrap::Wrapper;
+use volta_core::style::tool_version;
 
-use volta_core::style::text_width;
+use super::{Node, Package, PackageDetails, PackageManager, PackageManagerKind, Source, Toolchain};
 
-use super::{Node, Package, PackageManager, Source, Toolchain};
-
-static INDENTATION: &'static str = "    ";
-static NO_RUNTIME: &'static str = "⚡️ No Node runtimes installed!
+static INDENTATION: &str = "    ";
+static NO_RUNTIME: &str = "⚡️ No Node runtimes installed!
 
 You can install a runtime by running `volta install node`. See `volta help install` for
 details and more options.";
+static MORE_OPTIONS: &str = "See options for more detailed reports by running `volta list --help`.";
+static HELP_INSTALL: &str = "See `volta help install` for details and more options.";
 
 pub(super) fn format(toolchain: &Toolchain) -> Option<String> {
     // Formatting here depends on the toolchain: we do different degrees of
@@ -21,14 +21,18 @@ pub(super) fn format(toolchain: &Toolchain) -> Option<String> {
             runtime,
             package_manager,
             packages,
-        } => display_active(runtime, package_manager, packages),
+        } => display_active(
+            runtime.as_ref().map(|r| &**r),
+            package_manager.as_ref().map(|p| &**p),
+            packages,
+        ),
         Toolchain::All {
             runtimes,
             package_managers,
             packages,
         } => display_all(runtimes, package_managers, packages),
-        Toolchain::PackageManagers(package_managers) => display_package_managers(package_managers),
-        Toolchain::Packages(packages) => display_packages(packages),
+        Toolchain::PackageManagers { kind, managers } => display_package_managers(*kind, managers),
+        Toolchain::Packages { name, packages } => display_packages(name, packages),
         Toolchain::Tool {
             name,
             host_packages,
@@ -39,17 +43,50 @@ pub(super) fn format(toolchain: &Toolchain) -> Option<String> {
 /// Format the output for `Toolchain::Active`.
 ///
 /// Accepts the components *from* the toolchain rather than the item itself so
-/// that
+/// that the printer can be tested without building a full `Toolchain`.
 fn display_active(
-    node: &Option<Node>,
-    package_manager: &Option<PackageManager>,
+    node: Option<&Node>,
+    package_manager: Option<&PackageManager>,
     packages: &[Package],
 ) -> String {
-    match (node, package_manager, packages) {
-        (None, _, _) => NO_RUNTIME.to_string(),
-        (Some(node), Some(package_manager), packages) => unimplemented!(),
-        _ => unimplemented!(),
+    let node = match node {
+        Some(node) => node,
+        None => return NO_RUNTIME.to_string(),
+    };
+
+    let mut lines = vec![format!(
+        "Node: v{}{}{}",
+        node.version,
+        node.source,
+        end_of_life_note(node)
+    )];
+    match package_manager {
+        Some(package_manager) => lines.push(format!(
+            "{}: v{}{}",
+            package_manager_title(&package_manager.kind),
+            package_manager.version,
+            package_manager.source
+        )),
+        None => lines.push(String::from("npm: built-in")),
     }
+
+    let tools = describe_active_tools(packages);
+    if tools.is_empty() {
+        lines.push(String::from("Tool binaries available: NONE"));
+    } else {
+        lines.push(String::from("Tool binaries available:"));
+        lines.extend(
+            tools
+                .into_iter()
+                .map(|tools| format!("{}{}", INDENTATION, tools)),
+        );
+    }
+
+    format!(
+        "⚡️ Currently active tools:\n\n{}\n\n{}",
+        indent(&lines.join("\n")),
+        MORE_OPTIONS
+    )
 }
 
 /// Format the output for `Toolchain::All`.
@@ -58,7 +95,67 @@ fn display_all(
     package_managers: &[PackageManager],
     packages: &[Package],
 ) -> String {
-    unimplemented!()
+    if runtimes.is_empty() {
+        return NO_RUNTIME.to_string();
+    }
+
+    let mut sections = vec![format!(
+        "Node runtimes:\n{}",
+        indent(&node_versions(runtimes))
+    )];
+
+    let mut managers = Vec::new();
+    for &kind in &[
+        PackageManagerKind::Npm,
+        PackageManagerKind::Yarn,
+        PackageManagerKind::Pnpm,
+    ] {
+        let versions = package_managers
+            .iter()
+            .filter(|package_manager| package_manager.kind == kind)
+            .cloned()
+            .collect::<Vec<_>>();
+        if !versions.is_empty() {
+            managers.push(format!(
+                "{}:\n{}",
+                package_manager_title(&kind),
+                indent(&package_manager_versions(&versions))
+            ));
+        }
+    }
+    if !managers.is_empty() {
+        sections.push(format!(
+            "Package managers:\n{}",
+            indent(&managers.join("\n"))
+        ));
+    }
+
+    if !packages.is_empty() {
+        let mut names = packages
+            .iter()
+            .map(|package| details(package).name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+
+        let tools = names
+            .into_iter()
+            .map(|name| {
+                let versions = by_version(packages)
+                    .into_iter()
+                    .filter(|package| details(package).name == name)
+                    .map(describe_package_version)
+                    .collect::<Vec<_>>();
+                format!("{}:\n{}", name, indent(&versions.join("\n\n")))
+            })
+            .collect::<Vec<_>>();
+        sections.push(format!("Tools:\n{}", indent(&tools.join("\n\n"))));
+    }
+
+    format!(
+        "⚡️ Default toolchain:\n\n{}",
+        indent(&sections.join("\n\n"))
+    )
 }
 
 /// Format the output for `Toolchain::Node`.
@@ -69,35 +166,281 @@ fn display_node(runtimes: &[Node]) -> String {
     if runtimes.is_empty() {
         NO_RUNTIME.to_string()
     } else {
-        let width = text_width().unwrap_or(0);
-        let versions = Wrapper::new(width)
-            .initial_indent(INDENTATION)
-            .subsequent_indent(INDENTATION)
-            .fill(
-                &runtimes
-                    .iter()
-                    .map(|runtime| format!("v{}{}", runtime.version, runtime.source))
-                    .collect::<Vec<String>>()
-                    .join("\n"),
-            );
+        format!(
+            "⚡️ Node runtimes in your toolchain:\n\n{}",
+            indent(&node_versions(runtimes))
+        )
+    }
+}
+
+fn node_versions(runtimes: &[Node]) -> String {
+    runtimes
+        .iter()
+        .map(|runtime| {
+            format!(
+                "v{}{}{}",
+                runtime.version,
+                runtime.source,
+                end_of_life_note(runtime)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
 
-        format!("⚡️ Node runtimes in your toolchain:\n\n{}", versions)
+/// Notes that the release line of a runtime has reached its end of life.
+fn end_of_life_note(runtime: &Node) -> String {
+    match &runtime.end_of_life {
+        Some(end) => format!(" (end of life since {})", end),
+        None => String::new(),
     }
 }
 
 /// Format the output for `Toolchain::PackageManager`.
-fn display_package_managers(package_managers: &[PackageManager]) -> String {
-    unimplemented!()
+fn display_package_managers(
+    kind: PackageManagerKind,
+    package_managers: &[PackageManager],
+) -> String {
+    let title = package_manager_title(&kind);
+    if package_managers.is_empty() {
+        format!(
+            "⚡️ No {} versions installed.
+
+You can install a {} version by running `volta install {}`.
+{}",
+            title, title, kind, HELP_INSTALL
+        )
+    } else {
+        format!(
+            "⚡️ {} versions in your toolchain:\n\n{}",
+            title,
+            indent(&package_manager_versions(package_managers))
+        )
+    }
+}
+
+/// List the versions of a package manager, newest first.
+fn package_manager_versions(package_managers: &[PackageManager]) -> String {
+    let mut package_managers = package_managers.iter().collect::<Vec<_>>();
+    package_managers.sort_by(|a, b| b.version.cmp(&a.version));
+    package_managers
+        .iter()
+        .map(|package_manager| format!("v{}{}", package_manager.version, package_manager.source))
+        .collect::<Vec<String>>()
+        .join("\n")
 }
 
 /// Format a set of `Toolchain::Package`s and their associated tools.
-fn display_packages(packages: &[Package]) -> String {
-    unimplemented!()
+fn display_packages(name: &str, packages: &[Package]) -> String {
+    if packages.is_empty() {
+        return no_match(name);
+    }
+
+    let packages = by_version(packages);
+    let header = if !packages.iter().all(|package| is_fetched(package)) {
+        format!("⚡️ `{}` package versions in your toolchain:", name)
+    } else if packages.len() == 1 {
+        format!("⚡️ tool `{}` exists in one package on your system:", name)
+    } else {
+        format!(
+            "⚡️ tool `{}` exists in the following packages on your system:",
+            name
+        )
+    };
+    let details = packages
+        .iter()
+        .map(|package| describe_package(package, true))
+        .collect::<Vec<String>>();
+
+    with_install_hint(
+        format!("{}\n\n{}", header, indent(&details.join("\n\n"))),
+        &packages,
+    )
 }
 
 /// Format the output for a specific tool from a set of `Toolchain::Package`s.
 fn display_tool(tool: &str, host_packages: &[Package]) -> String {
-    unimplemented!()
+    if host_packages.is_empty() {
+        return no_match(tool);
+    }
+
+    let packages = by_version(host_packages);
+    let details = packages
+        .iter()
+        .map(|package| describe_package(package, false))
+        .collect::<Vec<String>>();
+
+    with_install_hint(
+        format!(
+            "⚡️ tool `{}` available from:\n\n{}",
+            tool,
+            indent(&details.join("\n\n"))
+        ),
+        &packages,
+    )
+}
+
+fn no_match(name: &str) -> String {
+    format!(
+        "⚡️ No tools or packages named `{}` installed.
+
+You can safely install packages by running `volta install <package name>`.
+{}",
+        name, HELP_INSTALL
+    )
+}
+
+/// Add the command to install the packages, if they have only been fetched.
+fn with_install_hint(output: String, packages: &[&Package]) -> String {
+    if !packages.iter().all(|package| is_fetched(package)) {
+        return output;
+    }
+
+    let target = match packages {
+        [package] => tool_version(&details(package).name, &details(package).version),
+        _ => tool_version(&details(packages[0]).name, "<version>"),
+    };
+    format!(
+        "{}\n\nTo make it available to execute, run `volta install {}`.\n{}",
+        output, target, HELP_INSTALL
+    )
+}
+
+fn package_manager_title(kind: &PackageManagerKind) -> &'static str {
+    match kind {
+        PackageManagerKind::Npm => "npm",
+        PackageManagerKind::Yarn => "Yarn",
+        PackageManagerKind::Pnpm => "pnpm",
+    }
+}
+
+/// Indent each non-empty line by one level.
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", INDENTATION, line)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn details(package: &Package) -> &PackageDetails {
+    match package {
+        Package::Default { details, .. }
+        | Package::Project { details, .. }
+        | Package::Fetched(details) => details,
+    }
+}
+
+fn is_fetched(package: &Package) -> bool {
+    match package {
+        Package::Fetched(..) => true,
+        _ => false,
+    }
+}
+
+fn package_source(package: &Package) -> Source {
+    match package {
+        Package::Default { .. } => Source::Default,
+        Package::Project { path, .. } => Source::Project(path.clone()),
+        Package::Fetched(..) => Source::None,
+    }
+}
+
+/// Order packages from the newest version to the oldest.
+fn by_version(packages: &[Package]) -> Vec<&Package> {
+    let mut packages = packages.iter().collect::<Vec<_>>();
+    packages.sort_by(|a, b| details(b).version.cmp(&details(a).version));
+    packages
+}
+
+/// Group the tools provided by the active packages by where they come from, default tools first.
+fn describe_active_tools(packages: &[Package]) -> Vec<String> {
+    let mut default_tools = Vec::new();
+    let mut project_tools: Vec<(Source, Vec<String>)> = Vec::new();
+
+    for package in packages {
+        match package {
+            Package::Default { tools, .. } => default_tools.extend(tools.iter().cloned()),
+            Package::Project { tools, path, .. } => {
+                let source = Source::Project(path.clone());
+                match project_tools.iter_mut().find(|(s, _)| s == &source) {
+                    Some((_, existing)) => existing.extend(tools.iter().cloned()),
+                    None => project_tools.push((source, tools.clone())),
+                }
+            }
+            Package::Fetched(..) => {}
+        }
+    }
+
+    let mut lines = Vec::new();
+    if !default_tools.is_empty() {
+        default_tools.sort();
+        lines.push(format!("{}{}", default_tools.join(", "), Source::Default));
+    }
+    for (source, mut tools) in project_tools {
+        tools.sort();
+        lines.push(format!("{}{}", tools.join(", "), source));
+    }
+
+    lines
+}
+
+/// The platform lines shared by the package listings.
+fn describe_platform(node: &semver::Version) -> String {
+    // Should be updated when we support installing with custom package_managers,
+    // whether Yarn or non-built-in versions of npm
+    format!(
+        "platform:\n{0}runtime: {1}\n{0}package manager: built-in npm",
+        INDENTATION,
+        tool_version("node", format!("v{}", node))
+    )
+}
+
+/// Describe a single package, optionally including the binaries it provides.
+///
+/// Fetched packages were never installed, so they have no binaries or platform to show.
+fn describe_package(package: &Package, include_tools: bool) -> String {
+    let details = details(package);
+    let title = format!(
+        "{}{}",
+        tool_version(&details.name, &details.version),
+        package_source(package)
+    );
+
+    match package {
+        Package::Default { node, tools, .. } | Package::Project { node, tools, .. } => {
+            let mut lines = Vec::new();
+            if include_tools {
+                lines.push(format!("binary tools: {}", tools.join(", ")));
+            }
+            lines.push(describe_platform(node));
+            format!("{}\n{}", title, indent(&lines.join("\n")))
+        }
+        Package::Fetched(..) => title,
+    }
+}
+
+/// Describe a version of a package in the listing of the whole toolchain.
+fn describe_package_version(package: &Package) -> String {
+    let details = details(package);
+    match package {
+        Package::Default { node, tools, .. } | Package::Project { node, tools, .. } => format!(
+            "v{}{}:\n{}",
+            details.version,
+            package_source(package),
+            indent(&format!(
+                "binaries: {}\n{}",
+                tools.join(", "),
+                describe_platform(node)
+            ))
+        ),
+        Package::Fetched(..) => format!("v{}", details.version),
+    }
 }
 
 // These tests are organized by way of the *commands* supplied to `list`, unlike
@@ -120,11 +463,49 @@ mod tests {
         static ref PROJECT_PATH: PathBuf = PathBuf::from("~/path/to/project.json");
     }
 
+    /// Builds a package installed against `NODE_12`, or a fetched one for `Source::None`.
+    fn package(name: &str, version: Version, source: Source, tools: &[&str]) -> Package {
+        let details = PackageDetails {
+            name: name.to_string(),
+            version,
+        };
+        let node = NODE_12.clone();
+        let tools = tools.iter().map(|tool| tool.to_string()).collect();
+
+        match source {
+            Source::Project(path) => Package::Project {
+                details,
+                node,
+                tools,
+                path,
+            },
+            Source::Default => Package::Default {
+                details,
+                node,
+                tools,
+            },
+            Source::None => Package::Fetched(details),
+        }
+    }
+
+    fn node(source: Source, version: &Version) -> Node {
+        Node {
+            source,
+            version: version.clone(),
+            end_of_life: None,
+        }
+    }
+
+    fn yarn(source: Source, version: Version) -> PackageManager {
+        PackageManager {
+            kind: PackageManagerKind::Yarn,
+            source,
+            version,
+        }
+    }
+
     mod active {
         use super::*;
-        use crate::command::list::{
-            human::display_active, Node, PackageManager, PackageManagerKind, Source,
-        };
 
         #[test]
         fn no_runtimes() {
@@ -132,7 +513,7 @@ mod tests {
             let package_manager = None;
             let packages = vec![];
             assert_eq!(
-                display_active(&runtime, &package_manager, &packages).as_str(),
+                display_active(runtime, package_manager, &packages).as_str(),
                 NO_RUNTIME
             );
         }
@@ -142,43 +523,31 @@ mod tests {
             let expected = "⚡️ Currently active tools:
 
     Node: v12.2.0 (default)
+    npm: built-in
+    Tool binaries available: NONE
 
 See options for more detailed reports by running `volta list --help`.";
 
-            let runtime = Some(Node {
-                source: Source::Default,
-                version: NODE_12.clone(),
-            });
-            let package_manager = None;
+            let runtime = node(Source::Default, &NODE_12);
             let packages = vec![];
 
-            assert_eq!(
-                display_active(&runtime, &package_manager, &packages),
-                expected
-            );
+            assert_eq!(display_active(Some(&runtime), None, &packages), expected);
         }
 
         #[test]
         fn runtime_only_project() {
             let expected = "⚡️ Currently active tools:
 
-    Node: v12.2.0 (project @ ~/path/to/project.json)
+    Node: v12.2.0 (current @ ~/path/to/project.json)
     npm: built-in
     Tool binaries available: NONE
 
 See options for more detailed reports by running `volta list --help`.";
 
-            let runtime = Some(Node {
-                source: Source::Project(PROJECT_PATH.clone()),
-                version: NODE_12.clone(),
-            });
-            let package_manager = None;
+            let runtime = node(Source::Project(PROJECT_PATH.clone()), &NODE_12);
             let packages = vec![];
 
-            assert_eq!(
-                display_active(&runtime, &package_manager, &packages),
-                expected
-            );
+            assert_eq!(display_active(Some(&runtime), None, &packages), expected);
         }
 
         #[test]
@@ -191,19 +560,12 @@ See options for more detailed reports by running `volta list --help`.";
 
 See options for more detailed reports by running `volta list --help`.";
 
-            let runtime = Some(Node {
-                source: Source::Default,
-                version: NODE_12.clone(),
-            });
-            let package_manager = Some(PackageManager {
-                kind: PackageManagerKind::Yarn,
-                source: Source::Default,
-                version: YARN_VERSION.clone(),
-            });
+            let runtime = node(Source::Default, &NODE_12);
+            let package_manager = yarn(Source::Default, YARN_VERSION.clone());
             let packages = vec![];
 
             assert_eq!(
-                display_active(&runtime, &package_manager, &packages),
+                display_active(Some(&runtime), Some(&package_manager), &packages),
                 expected
             );
         }
@@ -218,19 +580,12 @@ See options for more detailed reports by running `volta list --help`.";
 
 See options for more detailed reports by running `volta list --help`.";
 
-            let runtime = Some(Node {
-                source: Source::Default,
-                version: NODE_12.clone(),
-            });
-            let package_manager = Some(PackageManager {
-                kind: PackageManagerKind::Yarn,
-                source: Source::Project(PROJECT_PATH.clone()),
-                version: YARN_VERSION.clone(),
-            });
+            let runtime = node(Source::Default, &NODE_12);
+            let package_manager = yarn(Source::Project(PROJECT_PATH.clone()), YARN_VERSION.clone());
             let packages = vec![];
 
             assert_eq!(
-                display_active(&runtime, &package_manager, &packages),
+                display_active(Some(&runtime), Some(&package_manager), &packages),
                 expected
             );
         }
@@ -245,19 +600,12 @@ See options for more detailed reports by running `volta list --help`.";
 
 See options for more detailed reports by running `volta list --help`.";
 
-            let runtime = Some(Node {
-                source: Source::Project(PROJECT_PATH.clone()),
-                version: NODE_12.clone(),
-            });
-            let package_manager = Some(PackageManager {
-                kind: PackageManagerKind::Yarn,
-                source: Source::Project(PROJECT_PATH.clone()),
-                version: YARN_VERSION.clone(),
-            });
+            let runtime = node(Source::Project(PROJECT_PATH.clone()), &NODE_12);
+            let package_manager = yarn(Source::Project(PROJECT_PATH.clone()), YARN_VERSION.clone());
             let packages = vec![];
 
             assert_eq!(
-                display_active(&runtime, &package_manager, &packages),
+                display_active(Some(&runtime), Some(&package_manager), &packages),
                 expected
             );
         }
@@ -273,34 +621,25 @@ See options for more detailed reports by running `volta list --help`.";
 
 See options for more detailed reports by running `volta list --help`.";
 
-            let runtime = Some(Node {
-                source: Source::Project(PROJECT_PATH.clone()),
-                version: NODE_12.clone(),
-            });
-            let package_manager = Some(PackageManager {
-                kind: PackageManagerKind::Yarn,
-                source: Source::Project(PROJECT_PATH.clone()),
-                version: YARN_VERSION.clone(),
-            });
+            let runtime = node(Source::Project(PROJECT_PATH.clone()), &NODE_12);
+            let package_manager = yarn(Source::Project(PROJECT_PATH.clone()), YARN_VERSION.clone());
             let packages = vec![
-                Package {
-                    name: "create-react-app".to_string(),
-                    source: Source::Default,
-                    version: Version::from((3, 0, 1)),
-                    node: NODE_12.clone(),
-                    tools: vec!["create-react-app".to_string()],
-                },
-                Package {
-                    name: "typescript".to_string(),
-                    source: Source::Default,
-                    version: Version::from((3, 4, 3)),
-                    node: NODE_12.clone(),
-                    tools: vec!["tsc".to_string(), "tsserver".to_string()],
-                },
+                package(
+                    "create-react-app",
+                    Version::from((3, 0, 1)),
+                    Source::Default,
+                    &["create-react-app"],
+                ),
+                package(
+                    "typescript",
+                    Version::from((3, 4, 3)),
+                    Source::Default,
+                    &["tsc", "tsserver"],
+                ),
             ];
 
             assert_eq!(
-                display_active(&runtime, &package_manager, &packages),
+                display_active(Some(&runtime), Some(&package_manager), &packages),
                 expected
             );
         }
@@ -313,49 +652,35 @@ See options for more detailed reports by running `volta list --help`.";
     Yarn: v1.16.0 (current @ ~/path/to/project.json)
     Tool binaries available:
         tsc, tsserver (default)
-        create-react-app (current @ ~/path-to-project.json)
+        create-react-app (current @ ~/path/to/project.json)
 
 See options for more detailed reports by running `volta list --help`.";
 
-            let runtime = Some(Node {
-                source: Source::Project(PROJECT_PATH.clone()),
-                version: NODE_12.clone(),
-            });
-            let package_manager = Some(PackageManager {
-                kind: PackageManagerKind::Yarn,
-                source: Source::Project(PROJECT_PATH.clone()),
-                version: YARN_VERSION.clone(),
-            });
+            let runtime = node(Source::Project(PROJECT_PATH.clone()), &NODE_12);
+            let package_manager = yarn(Source::Project(PROJECT_PATH.clone()), YARN_VERSION.clone());
             let packages = vec![
-                Package {
-                    name: "create-react-app".to_string(),
-                    source: Source::Project(PROJECT_PATH.clone()),
-                    version: Version::from((3, 0, 1)),
-                    node: NODE_12.clone(),
-                    tools: vec!["create-react-app".to_string()],
-                },
-                Package {
-                    name: "typescript".to_string(),
-                    source: Source::Default,
-                    version: Version::from((3, 4, 3)),
-                    node: NODE_12.clone(),
-                    tools: vec!["tsc".to_string(), "tsserver".to_string()],
-                },
+                package(
+                    "create-react-app",
+                    Version::from((3, 0, 1)),
+                    Source::Project(PROJECT_PATH.clone()),
+                    &["create-react-app"],
+                ),
+                package(
+                    "typescript",
+                    Version::from((3, 4, 3)),
+                    Source::Default,
+                    &["tsc", "tsserver"],
+                ),
             ];
 
             assert_eq!(
-                display_active(&runtime, &package_manager, &packages),
+                display_active(Some(&runtime), Some(&package_manager), &packages),
                 expected
             );
         }
     }
 
     mod node {
-        use std::path::PathBuf;
-
-        use semver::Version;
-
-        use super::super::*;
         use super::*;
 
         #[test]
@@ -371,10 +696,7 @@ See options for more detailed reports by running `volta list --help`.";
             let expected = "⚡️ Node runtimes in your toolchain:
 
     v10.15.3 (default)";
-            let runtimes = [Node {
-                source: Source::Default,
-                version: NODE_10.clone(),
-            }];
+            let runtimes = [node(Source::Default, &NODE_10)];
 
             assert_eq!(display_node(&runtimes).as_str(), expected);
         }
@@ -385,10 +707,7 @@ See options for more detailed reports by running `volta list --help`.";
 
     v12.2.0 (current @ ~/path/to/project.json)";
 
-            let runtimes = [Node {
-                source: Source::Project(PROJECT_PATH.clone()),
-                version: NODE_12.clone(),
-            }];
+            let runtimes = [node(Source::Project(PROJECT_PATH.clone()), &NODE_12)];
 
             assert_eq!(display_node(&runtimes).as_str(), expected);
         }
@@ -399,10 +718,7 @@ See options for more detailed reports by running `volta list --help`.";
 
     v11.9.0";
 
-            let runtimes = [Node {
-                source: Source::None,
-                version: NODE_11.clone(),
-            }];
+            let runtimes = [node(Source::None, &NODE_11)];
 
             assert_eq!(display_node(&runtimes).as_str(), expected);
         }
@@ -416,18 +732,9 @@ See options for more detailed reports by running `volta list --help`.";
     v10.15.3 (default)";
 
             let runtimes = [
-                Node {
-                    source: Source::Project(PROJECT_PATH.clone()),
-                    version: NODE_12.clone(),
-                },
-                Node {
-                    source: Source::None,
-                    version: NODE_11.clone(),
-                },
-                Node {
-                    source: Source::Default,
-                    version: NODE_10.clone(),
-                },
+                node(Source::Project(PROJECT_PATH.clone()), &NODE_12),
+                node(Source::None, &NODE_11),
+                node(Source::Default, &NODE_10),
             ];
 
             assert_eq!(display_node(&runtimes), expected);
@@ -436,17 +743,18 @@ See options for more detailed reports by running `volta list --help`.";
 
     mod package_managers {
         use super::*;
-        use crate::command::list::Subcommand;
-        use crate::command::list::{PackageManager, PackageManagerKind, Source};
 
         #[test]
         fn none_installed() {
-            let expected = "⚡️ No <npm|Yarn> versions installed.
+            let expected = "⚡️ No Yarn versions installed.
 
 You can install a Yarn version by running `volta install yarn`.
 See `volta help install` for details and more options.";
 
-            assert_eq!(display_package_managers(&[]), expected);
+            assert_eq!(
+                display_package_managers(PackageManagerKind::Yarn, &[]),
+                expected
+            );
         }
 
         #[test]
@@ -455,13 +763,12 @@ See `volta help install` for details and more options.";
 
     v1.16.0 (default)";
 
-            let package_managers = [PackageManager {
-                kind: PackageManagerKind::Yarn,
-                source: Source::Default,
-                version: YARN_VERSION.clone(),
-            }];
+            let package_managers = [yarn(Source::Default, YARN_VERSION.clone())];
 
-            assert_eq!(display_package_managers(&package_managers), expected);
+            assert_eq!(
+                display_package_managers(PackageManagerKind::Yarn, &package_managers),
+                expected
+            );
         }
 
         #[test]
@@ -470,13 +777,15 @@ See `volta help install` for details and more options.";
 
     v1.16.0 (current @ ~/path/to/project.json)";
 
-            let package_managers = [PackageManager {
-                kind: PackageManagerKind::Yarn,
-                source: Source::Project(PROJECT_PATH.clone()),
-                version: YARN_VERSION.clone(),
-            }];
+            let package_managers = [yarn(
+                Source::Project(PROJECT_PATH.clone()),
+                YARN_VERSION.clone(),
+            )];
 
-            assert_eq!(display_package_managers(&package_managers), expected);
+            assert_eq!(
+                display_package_managers(PackageManagerKind::Yarn, &package_managers),
+                expected
+            );
         }
 
         #[test]
@@ -485,13 +794,12 @@ See `volta help install` for details and more options.";
 
     v1.16.0";
 
-            let yarns = [PackageManager {
-                kind: PackageManagerKind::Yarn,
-                source: Source::None,
-                version: YARN_VERSION.clone(),
-            }];
+            let yarns = [yarn(Source::None, YARN_VERSION.clone())];
 
-            assert_eq!(display_package_managers(&yarns), expected);
+            assert_eq!(
+                display_package_managers(PackageManagerKind::Yarn, &yarns),
+                expected
+            );
         }
 
         #[test]
@@ -503,145 +811,120 @@ See `volta help install` for details and more options.";
     v1.3.0";
 
             let yarns = [
-                PackageManager {
-                    kind: PackageManagerKind::Yarn,
-                    source: Source::None,
-                    version: Version::from((1, 3, 0)),
-                },
-                PackageManager {
-                    kind: PackageManagerKind::Yarn,
-                    source: Source::Default,
-                    version: YARN_VERSION.clone(),
-                },
-                PackageManager {
-                    kind: PackageManagerKind::Yarn,
-                    source: Source::Project(PROJECT_PATH.clone()),
-                    version: Version::from((1, 17, 0)),
-                },
+                yarn(Source::None, Version::from((1, 3, 0))),
+                yarn(Source::Default, YARN_VERSION.clone()),
+                yarn(
+                    Source::Project(PROJECT_PATH.clone()),
+                    Version::from((1, 17, 0)),
+                ),
             ];
 
-            assert_eq!(display_package_managers(&yarns), expected);
+            assert_eq!(
+                display_package_managers(PackageManagerKind::Yarn, &yarns),
+                expected
+            );
         }
     }
 
     mod packages {
         use super::*;
-        use crate::command::list::{Package, Source};
-        use semver::{Identifier, Version};
 
         #[test]
         fn none() {
             let expected = "⚡️ No tools or packages named `ember` installed.
-            
+
 You can safely install packages by running `volta install <package name>`.
 See `volta help install` for details and more options.";
 
-            assert_eq!(display_packages(&[]), expected);
+            assert_eq!(display_packages("ember", &[]), expected);
         }
 
         #[test]
         fn single_default() {
             let expected = "⚡️ `ember` package versions in your toolchain:
-            
+
     ember-cli@3.10.1 (default)
         binary tools: ember
         platform:
             runtime: node@v12.2.0
             package manager: built-in npm";
 
-            let packages = [Package {
-                name: "ember-cli".to_string(),
-                version: Version::from((3, 10, 1)),
-                source: Source::Default,
-                node: NODE_12.clone(),
-                tools: vec!["ember".to_string()],
-            }];
+            let packages = [package(
+                "ember-cli",
+                Version::from((3, 10, 1)),
+                Source::Default,
+                &["ember"],
+            )];
 
-            assert_eq!(display_packages(&packages), expected);
+            assert_eq!(display_packages("ember", &packages), expected);
         }
 
         #[test]
         fn single_project() {
             let expected = "⚡️ `ember` package versions in your toolchain:
-            
+
     ember-cli@3.10.1 (current @ ~/path/to/project.json)
         binary tools: ember
         platform:
             runtime: node@v12.2.0
             package manager: built-in npm";
 
-            let packages = [Package {
-                name: "ember-cli".to_string(),
-                version: Version::from((3, 10, 1)),
-                source: Source::Project(PROJECT_PATH.clone()),
-                node: NODE_12.clone(),
-                tools: vec!["ember".to_string()],
-            }];
+            let packages = [package(
+                "ember-cli",
+                Version::from((3, 10, 1)),
+                Source::Project(PROJECT_PATH.clone()),
+                &["ember"],
+            )];
 
-            assert_eq!(display_packages(&packages), expected);
+            assert_eq!(display_packages("ember", &packages), expected);
         }
 
         #[test]
         fn single_fetched() {
             let expected = "⚡️ tool `ember` exists in one package on your system:
-            
+
     ember-cli@3.10.1
-        binary tools: ember
-        platform:
-            runtime: node@v12.2.0
-            package manager: built-in npm
 
 To make it available to execute, run `volta install ember-cli@3.10.1`.
 See `volta help install` for details and more options.";
 
-            let packages = [Package {
-                name: "ember-cli".to_string(),
-                version: Version::from((3, 10, 1)),
-                source: Source::None,
-                node: NODE_12.clone(),
-                tools: vec!["ember".to_string()],
-            }];
+            let packages = [package(
+                "ember-cli",
+                Version::from((3, 10, 1)),
+                Source::None,
+                &["ember"],
+            )];
 
-            assert_eq!(display_packages(&packages), expected);
+            assert_eq!(display_packages("ember", &packages), expected);
         }
 
         #[test]
         fn multi_fetched() {
             let expected = "⚡️ tool `ember` exists in the following packages on your system:
-            
+
     ember-cli@3.10.1
-        binary tools: ember
-        platform:
-            runtime: node@v12.2.0
-            package manager: built-in npm
 
     ember-cli@3.8.2
-        binary tools: ember
-        platform:
-            runtime: node@v12.2.0
-            package manager: built-in npm
 
 To make it available to execute, run `volta install ember-cli@<version>`.
 See `volta help install` for details and more options.";
 
             let packages = [
-                Package {
-                    name: "ember-cli".to_string(),
-                    version: Version::from((3, 10, 1)),
-                    source: Source::None,
-                    node: NODE_12.clone(),
-                    tools: vec!["ember".to_string()],
-                },
-                Package {
-                    name: "ember-cli".to_string(),
-                    version: Version::from((3, 8, 2)),
-                    source: Source::None,
-                    node: NODE_12.clone(),
-                    tools: vec!["ember".to_string()],
-                },
+                package(
+                    "ember-cli",
+                    Version::from((3, 10, 1)),
+                    Source::None,
+                    &["ember"],
+                ),
+                package(
+                    "ember-cli",
+                    Version::from((3, 8, 2)),
+                    Source::None,
+                    &["ember"],
+                ),
             ];
 
-            assert_eq!(display_packages(&packages), expected);
+            assert_eq!(display_packages("ember", &packages), expected);
         }
 
         #[test]
@@ -653,12 +936,8 @@ See `volta help install` for details and more options.";
         platform:
             runtime: node@v12.2.0
             package manager: built-in npm
-            
+
     ember-cli@3.10.1
-        binary tools: ember
-        platform:
-            runtime: node@v12.2.0
-            package manager: built-in npm
 
     ember-cli@3.8.2 (default)
         binary tools: ember
@@ -667,48 +946,37 @@ See `volta help install` for details and more options.";
             package manager: built-in npm";
 
             let packages = [
-                Package {
-                    name: "ember-cli".to_string(),
-                    version: Version::from((3, 10, 1)),
-                    source: Source::None,
-                    node: NODE_12.clone(),
-                    tools: vec!["ember".to_string()],
-                },
-                Package {
-                    name: "ember-cli".to_string(),
-                    version: Version::from((3, 8, 2)),
-                    source: Source::Default,
-                    node: NODE_12.clone(),
-                    tools: vec!["ember".to_string()],
-                },
-                Package {
-                    name: "ember-cli".to_string(),
-                    version: Version {
-                        major: 3,
-                        minor: 11,
-                        patch: 0,
-                        pre: vec![Identifier::AlphaNumeric("-beta.3".to_string())],
-                        build: vec![],
-                    },
-                    source: Source::Project(PROJECT_PATH.clone()),
-                    node: NODE_12.clone(),
-                    tools: vec!["ember".to_string()],
-                },
+                package(
+                    "ember-cli",
+                    Version::from((3, 10, 1)),
+                    Source::None,
+                    &["ember"],
+                ),
+                package(
+                    "ember-cli",
+                    Version::from((3, 8, 2)),
+                    Source::Default,
+                    &["ember"],
+                ),
+                package(
+                    "ember-cli",
+                    Version::parse("3.11.0-beta.3").unwrap(),
+                    Source::Project(PROJECT_PATH.clone()),
+                    &["ember"],
+                ),
             ];
 
-            assert_eq!(display_packages(&packages), expected);
+            assert_eq!(display_packages("ember", &packages), expected);
         }
     }
 
     mod tools {
         use super::*;
-        use crate::command::list::{Package, Source};
-        use semver::{Identifier, Version};
 
         #[test]
         fn none() {
             let expected = "⚡️ No tools or packages named `ember` installed.
-            
+
 You can safely install packages by running `volta install <package name>`.
 See `volta help install` for details and more options.";
 
@@ -718,19 +986,18 @@ See `volta help install` for details and more options.";
         #[test]
         fn single_default() {
             let expected = "⚡️ tool `ember` available from:
-            
+
     ember-cli@3.10.1 (default)
         platform:
             runtime: node@v12.2.0
             package manager: built-in npm";
 
-            let packages = [Package {
-                name: "ember-cli".to_string(),
-                version: Version::from((3, 10, 1)),
-                source: Source::Default,
-                node: NODE_12.clone(),
-                tools: vec!["ember".to_string()],
-            }];
+            let packages = [package(
+                "ember-cli",
+                Version::from((3, 10, 1)),
+                Source::Default,
+                &["ember"],
+            )];
 
             assert_eq!(display_tool("ember", &packages), expected);
         }
@@ -738,19 +1005,18 @@ See `volta help install` for details and more options.";
         #[test]
         fn single_project() {
             let expected = "⚡️ tool `ember` available from:
-            
+
     ember-cli@3.10.1 (current @ ~/path/to/project.json)
         platform:
             runtime: node@v12.2.0
             package manager: built-in npm";
 
-            let packages = [Package {
-                name: "ember-cli".to_string(),
-                version: Version::from((3, 10, 1)),
-                source: Source::Project(PROJECT_PATH.clone()),
-                node: NODE_12.clone(),
-                tools: vec!["ember".to_string()],
-            }];
+            let packages = [package(
+                "ember-cli",
+                Version::from((3, 10, 1)),
+                Source::Project(PROJECT_PATH.clone()),
+                &["ember"],
+            )];
 
             assert_eq!(display_tool("ember", &packages), expected);
         }
@@ -758,22 +1024,18 @@ See `volta help install` for details and more options.";
         #[test]
         fn single_fetched() {
             let expected = "⚡️ tool `ember` available from:
-            
+
     ember-cli@3.10.1
-        platform:
-            runtime: node@v12.2.0
-            package manager: built-in npm
 
 To make it available to execute, run `volta install ember-cli@3.10.1`.
 See `volta help install` for details and more options.";
 
-            let packages = [Package {
-                name: "ember-cli".to_string(),
-                version: Version::from((3, 10, 1)),
-                source: Source::None,
-                node: NODE_12.clone(),
-                tools: vec!["ember".to_string()],
-            }];
+            let packages = [package(
+                "ember-cli",
+                Version::from((3, 10, 1)),
+                Source::None,
+                &["ember"],
+            )];
 
             assert_eq!(display_tool("ember", &packages), expected);
         }
@@ -781,35 +1043,27 @@ See `volta help install` for details and more options.";
         #[test]
         fn multi_fetched() {
             let expected = "⚡️ tool `ember` available from:
-            
+
     ember-cli@3.10.1
-        platform:
-            runtime: node@v12.2.0
-            package manager: built-in npm
 
     ember-cli@3.8.2
-        platform:
-            runtime: node@v12.2.0
-            package manager: built-in npm
 
 To make it available to execute, run `volta install ember-cli@<version>`.
 See `volta help install` for details and more options.";
 
             let packages = [
-                Package {
-                    name: "ember-cli".to_string(),
-                    version: Version::from((3, 10, 1)),
-                    source: Source::None,
-                    node: NODE_12.clone(),
-                    tools: vec!["ember".to_string()],
-                },
-                Package {
-                    name: "ember-cli".to_string(),
-                    version: Version::from((3, 8, 2)),
-                    source: Source::None,
-                    node: NODE_12.clone(),
-                    tools: vec!["ember".to_string()],
-                },
+                package(
+                    "ember-cli",
+                    Version::from((3, 10, 1)),
+                    Source::None,
+                    &["ember"],
+                ),
+                package(
+                    "ember-cli",
+                    Version::from((3, 8, 2)),
+                    Source::None,
+                    &["ember"],
+                ),
             ];
 
             assert_eq!(display_tool("ember", &packages), expected);
@@ -823,11 +1077,8 @@ See `volta help install` for details and more options.";
         platform:
             runtime: node@v12.2.0
             package manager: built-in npm
-            
+
     ember-cli@3.10.1
-        platform:
-            runtime: node@v12.2.0
-            package manager: built-in npm
 
     ember-cli@3.8.2 (default)
         platform:
@@ -835,33 +1086,24 @@ See `volta help install` for details and more options.";
             package manager: built-in npm";
 
             let packages = [
-                Package {
-                    name: "ember-cli".to_string(),
-                    version: Version::from((3, 10, 1)),
-                    source: Source::None,
-                    node: NODE_12.clone(),
-                    tools: vec!["ember".to_string()],
-                },
-                Package {
-                    name: "ember-cli".to_string(),
-                    version: Version::from((3, 8, 2)),
-                    source: Source::Default,
-                    node: NODE_12.clone(),
-                    tools: vec!["ember".to_string()],
-                },
-                Package {
-                    name: "ember-cli".to_string(),
-                    version: Version {
-                        major: 3,
-                        minor: 11,
-                        patch: 0,
-                        pre: vec![Identifier::AlphaNumeric("-beta.3".to_string())],
-                        build: vec![],
-                    },
-                    source: Source::Project(PROJECT_PATH.clone()),
-                    node: NODE_12.clone(),
-                    tools: vec!["ember".to_string()],
-                },
+                package(
+                    "ember-cli",
+                    Version::from((3, 10, 1)),
+                    Source::None,
+                    &["ember"],
+                ),
+                package(
+                    "ember-cli",
+                    Version::from((3, 8, 2)),
+                    Source::Default,
+                    &["ember"],
+                ),
+                package(
+                    "ember-cli",
+                    Version::parse("3.11.0-beta.3").unwrap(),
+                    Source::Project(PROJECT_PATH.clone()),
+                    &["ember"],
+                ),
             ];
 
             assert_eq!(display_tool("ember", &packages), expected);
@@ -870,8 +1112,6 @@ See `volta help install` for details and more options.";
 
     mod all {
         use super::*;
-        use crate::command::list::PackageManagerKind;
-        use semver::Identifier;
 
         #[test]
         fn empty() {
@@ -885,7 +1125,10 @@ See `volta help install` for details and more options.";
             );
         }
 
+        // Upstream never asserted on this listing, and its data does not match the expected text,
+        // so it is kept as a record of the intended layout.
         #[test]
+        #[allow(unused_variables)]
         fn full() {
             let expected = "⚡️ Default toolchain:
 
@@ -936,31 +1179,17 @@ See `volta help install` for details and more options.";
             ";
 
             let runtimes = [
-                Node {
-                    source: Source::Project(PROJECT_PATH.clone()),
-                    version: NODE_12.clone(),
-                },
-                Node {
-                    source: Source::None,
-                    version: NODE_11.clone(),
-                },
-                Node {
-                    source: Source::Default,
-                    version: NODE_10.clone(),
-                },
+                node(Source::Project(PROJECT_PATH.clone()), &NODE_12),
+                node(Source::None, &NODE_11),
+                node(Source::Default, &NODE_10),
             ];
 
             let package_managers = [
-                PackageManager {
-                    kind: PackageManagerKind::Yarn,
-                    source: Source::Default,
-                    version: YARN_VERSION.clone(),
-                },
-                PackageManager {
-                    kind: PackageManagerKind::Yarn,
-                    source: Source::Project(PROJECT_PATH.clone()),
-                    version: Version::from((1, 17, 0)),
-                },
+                yarn(Source::Default, YARN_VERSION.clone()),
+                yarn(
+                    Source::Project(PROJECT_PATH.clone()),
+                    Version::from((1, 17, 0)),
+                ),
                 PackageManager {
                     kind: PackageManagerKind::Npm,
                     source: Source::None,
@@ -969,47 +1198,36 @@ See `volta help install` for details and more options.";
             ];
 
             let packages = [
-                Package {
-                    name: "typescript".to_string(),
-                    source: Source::Default,
-                    version: Version::from((3, 4, 3)),
-                    node: NODE_12.clone(),
-                    tools: vec!["tsc".to_string(), "tsserver".to_string()],
-                },
-                Package {
-                    name: "typescript".to_string(),
-                    source: Source::Project(PROJECT_PATH.clone()),
-                    version: Version::from((3, 5, 1)),
-                    node: NODE_12.clone(),
-                    tools: vec!["tsc".to_string(), "tsserver".to_string()],
-                },
-                Package {
-                    name: "ember".to_string(),
-                    source: Source::None,
-                    version: Version {
-                        major: 3,
-                        minor: 11,
-                        patch: 0,
-                        pre: vec![Identifier::AlphaNumeric("-beta.3".to_string())],
-                        build: vec![],
-                    },
-                    node: NODE_12.clone(),
-                    tools: vec!["tsc".to_string(), "tsserver".to_string()],
-                },
-                Package {
-                    name: "ember".to_string(),
-                    source: Source::Project(PROJECT_PATH.clone()),
-                    version: Version::from((3, 10, 1)),
-                    node: NODE_12.clone(),
-                    tools: vec!["tsc".to_string(), "tsserver".to_string()],
-                },
-                Package {
-                    name: "ember".to_string(),
-                    source: Source::Default,
-                    version: Version::from((3, 8, 2)),
-                    node: NODE_12.clone(),
-                    tools: vec!["tsc".to_string(), "tsserver".to_string()],
-                },
+                package(
+                    "typescript",
+                    Version::from((3, 4, 3)),
+                    Source::Default,
+                    &["tsc", "tsserver"],
+                ),
+                package(
+                    "typescript",
+                    Version::from((3, 5, 1)),
+                    Source::Project(PROJECT_PATH.clone()),
+                    &["tsc", "tsserver"],
+                ),
+                package(
+                    "ember",
+                    Version::parse("3.11.0-beta.3").unwrap(),
+                    Source::None,
+                    &["tsc", "tsserver"],
+                ),
+                package(
+                    "ember",
+                    Version::from((3, 10, 1)),
+                    Source::Project(PROJECT_PATH.clone()),
+                    &["tsc", "tsserver"],
+                ),
+                package(
+                    "ember",
+                    Version::from((3, 8, 2)),
+                    Source::Default,
+                    &["tsc", "tsserver"],
+                ),
             ];
         }
     }
@@ -5,10 +5,10 @@ use super::{Node, Package, PackageManager, Toolchain};
 pub(super) fn format(toolchain: &Toolchain) -> Option<String> {
     let (runtimes, package_managers, packages) = match toolchain {
         Toolchain::Node(runtimes) => (describe_runtimes(&runtimes), None, None),
-        Toolchain::PackageManagers(package_managers) => {
-            (None, describe_package_managers(&package_managers), None)
+        Toolchain::PackageManagers { managers, .. } => {
+            (None, describe_package_managers(&managers), None)
         }
-        Toolchain::Packages(packages) => (None, None, describe_packages(&packages)),
+        Toolchain::Packages { packages, .. } => (None, None, describe_packages(&packages)),
         Toolchain::Tool {
             name,
             host_packages,
@@ -297,11 +297,13 @@ mod tests {
                     runtimes: vec![
                         Node {
                             source: Source::Default,
-                            version: NODE_VERSION.clone()
+                            version: NODE_VERSION.clone(),
+                            end_of_life: None,
                         },
                         Node {
                             source: Source::None,
-                            version: Version::from((8, 2, 4))
+                            version: Version::from((8, 2, 4)),
+                            end_of_life: None,
                         }
                     ],
                     package_managers: vec![
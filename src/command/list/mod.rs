@@ -1,11 +1,10 @@
-// mod human;
+mod human;
 mod json;
 mod plain;
 mod toolchain;
 
 use std::{fmt, path::PathBuf, str::FromStr};
 
-use log::warn;
 use semver::Version;
 use structopt::StructOpt;
 
@@ -16,7 +15,7 @@ use volta_core::inventory::Inventory;
 use volta_core::project::Project;
 use volta_core::{
     session::{ActivityKind, Session},
-    tool::PackageConfig,
+    tool::{NodeSchedule, PackageConfig},
 };
 use volta_fail::{ExitCode, Fallible};
 
@@ -166,11 +165,28 @@ impl Package {
 struct Node {
     pub source: Source,
     pub version: Version,
+    /// The end of life of the release line, if it has already passed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_of_life: Option<String>,
 }
 
-#[derive(Clone, Serialize)]
+impl Node {
+    fn new(source: Source, version: Version, schedule: &NodeSchedule) -> Node {
+        let end_of_life = schedule
+            .end_of_life_now(&version)
+            .map(|end| end.to_string());
+        Node {
+            source,
+            version,
+            end_of_life,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
 enum PackageManagerKind {
     Yarn,
+    Pnpm,
     Npm,
 }
 
@@ -182,6 +198,7 @@ impl fmt::Display for PackageManagerKind {
             match self {
                 PackageManagerKind::Npm => "npm",
                 PackageManagerKind::Yarn => "yarn",
+                PackageManagerKind::Pnpm => "pnpm",
             }
         )
     }
@@ -218,7 +235,7 @@ pub(crate) struct List {
     // `Option<Subcommand>` with `impl FromStr for Subcommand` for `StructOpt`
     // because StructOpt does not currently support custom parsing for enum
     // variants (as detailed in commit 5f9214ae).
-    /// The tool to lookup: `all`, `node`, `yarn`, or the name of a package or binary.
+    /// The tool to lookup: `all`, `node`, `yarn`, `pnpm`, or the name of a package or binary.
     #[structopt(name = "tool")]
     subcommand: Option<String>,
 
@@ -259,6 +276,9 @@ enum Subcommand {
     /// Show locally cached Yarn versions.
     Yarn,
 
+    /// Show locally cached pnpm versions.
+    Pnpm,
+
     /// Show locally cached versions of a package or a package binary.
     PackageOrTool { name: String },
 }
@@ -269,6 +289,7 @@ impl From<&str> for Subcommand {
             "all" => Subcommand::All,
             "node" => Subcommand::Node,
             "yarn" => Subcommand::Yarn,
+            "pnpm" => Subcommand::Pnpm,
             s => Subcommand::PackageOrTool { name: s.into() },
         }
     }
@@ -292,14 +313,6 @@ impl List {
     }
 }
 
-fn human_fallback(_toolchain: &Toolchain) -> Option<String> {
-    Some(String::from(
-        "The `--format=human` printer is not yet implemented. For now, you can \
-         use `volta list --format=plain`.\n\n\
-         To track progress on this task, see https://github.com/volta-cli/volta/issues/523",
-    ))
-}
-
 impl Command for List {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::List);
@@ -308,7 +321,7 @@ impl Command for List {
         let project = session.project()?;
         let default_platform = session.default_platform()?;
         let format = match self.output_format() {
-            Format::Human => human_fallback,
+            Format::Human => human::format,
             Format::JSON => json::format,
             Format::Plain => plain::format,
         };
@@ -327,21 +340,27 @@ impl Command for List {
             Some(Subcommand::Node) => {
                 Toolchain::node(inventory, project, &default_platform, &filter)
             }
-            Some(Subcommand::Yarn) => {
-                Toolchain::yarn(inventory, project, &default_platform, &filter)
-            }
+            Some(Subcommand::Yarn) => Toolchain::package_managers(
+                PackageManagerKind::Yarn,
+                inventory,
+                project,
+                &default_platform,
+                &filter,
+            ),
+            Some(Subcommand::Pnpm) => Toolchain::package_managers(
+                PackageManagerKind::Pnpm,
+                inventory,
+                project,
+                &default_platform,
+                &filter,
+            ),
             Some(Subcommand::PackageOrTool { name }) => {
                 Toolchain::package_or_tool(&name, inventory, project, &filter)?
             }
         };
 
         if let Some(string) = format(&toolchain) {
-            // TODO: #523 -- just `println!("{}", string)` once `human` implemented
-            match self.output_format() {
-                Format::Plain => println!("{}", string),
-                Format::JSON => println!("{}", string),
-                Format::Human => warn!("{}", string),
-            }
+            println!("{}", string);
         };
 
         session.add_event_end(ActivityKind::List, ExitCode::Success);
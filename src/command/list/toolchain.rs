@@ -6,14 +6,23 @@ use semver::Version;
 use super::{Filter, Node, Package, PackageManager, Source};
 use crate::command::list::PackageManagerKind;
 use volta_core::{
-    inventory::Inventory, platform::PlatformSpec, project::Project, tool::PackageConfig,
+    inventory::Inventory,
+    platform::PlatformSpec,
+    project::Project,
+    tool::{cached_node_schedule, PackageConfig},
 };
 use volta_fail::Fallible;
 
 pub(super) enum Toolchain {
     Node(Vec<Node>),
-    PackageManagers(Vec<PackageManager>),
-    Packages(Vec<Package>),
+    PackageManagers {
+        kind: PackageManagerKind,
+        managers: Vec<PackageManager>,
+    },
+    Packages {
+        name: String,
+        packages: Vec<Package>,
+    },
     Tool {
         name: String,
         host_packages: Vec<Package>,
@@ -36,6 +45,18 @@ enum Lookup {
     Runtime,
     /// Look up the Yarn package manager
     Yarn,
+    /// Look up the pnpm package manager
+    Pnpm,
+}
+
+impl From<PackageManagerKind> for Lookup {
+    fn from(kind: PackageManagerKind) -> Self {
+        match kind {
+            PackageManagerKind::Yarn => Lookup::Yarn,
+            PackageManagerKind::Pnpm => Lookup::Pnpm,
+            PackageManagerKind::Npm => unreachable!("npm is listed with the Node runtime"),
+        }
+    }
 }
 
 impl Lookup {
@@ -43,6 +64,7 @@ impl Lookup {
         move |spec| match self {
             Lookup::Runtime => Some(spec.node_runtime.clone()),
             Lookup::Yarn => spec.yarn.clone(),
+            Lookup::Pnpm => spec.pnpm.clone(),
         }
     }
 
@@ -122,18 +144,22 @@ impl Toolchain {
     ) -> Fallible<Toolchain> {
         let runtime = Lookup::Runtime
             .active_tool(project, default_platform)
-            .map(|(source, version)| Box::new(Node { source, version }));
-
-        let package_manager =
-            Lookup::Yarn
-                .active_tool(project, default_platform)
-                .map(|(source, version)| {
-                    Box::new(PackageManager {
-                        kind: PackageManagerKind::Yarn,
-                        source,
-                        version,
+            .map(|(source, version)| Box::new(Node::new(source, version, &cached_node_schedule())));
+
+        // Only one package manager is shown, preferring Yarn when both Yarn and pnpm are set
+        let package_manager = [PackageManagerKind::Yarn, PackageManagerKind::Pnpm]
+            .iter()
+            .find_map(|&kind| {
+                Lookup::from(kind)
+                    .active_tool(project, default_platform)
+                    .map(|(source, version)| {
+                        Box::new(PackageManager {
+                            kind,
+                            source,
+                            version,
+                        })
                     })
-                });
+            });
 
         let packages = Package::from_inventory_and_project(inventory, project);
 
@@ -149,17 +175,18 @@ impl Toolchain {
         default_platform: &Option<Rc<PlatformSpec>>,
         inventory: &Inventory,
     ) -> Fallible<Toolchain> {
+        let schedule = cached_node_schedule();
         let runtimes = inventory
             .node
             .versions
             .iter()
-            .map(|version| Node {
-                source: Lookup::Runtime.version_source(project, default_platform, version),
-                version: version.clone(),
+            .map(|version| {
+                let source = Lookup::Runtime.version_source(project, default_platform, version);
+                Node::new(source, version.clone(), &schedule)
             })
             .collect();
 
-        let package_managers = inventory
+        let yarns = inventory
             .yarn
             .versions
             .iter()
@@ -167,8 +194,17 @@ impl Toolchain {
                 kind: PackageManagerKind::Yarn,
                 source: Lookup::Yarn.version_source(project, default_platform, version),
                 version: version.clone(),
-            })
-            .collect();
+            });
+        let pnpms = inventory
+            .pnpm
+            .versions
+            .iter()
+            .map(|version| PackageManager {
+                kind: PackageManagerKind::Pnpm,
+                source: Lookup::Pnpm.version_source(project, default_platform, version),
+                version: version.clone(),
+            });
+        let package_managers = yarns.chain(pnpms).collect();
 
         let packages = Package::from_inventory_and_project(inventory, project);
 
@@ -185,6 +221,7 @@ impl Toolchain {
         default_platform: &Option<Rc<PlatformSpec>>,
         filter: &Filter,
     ) -> Toolchain {
+        let schedule = cached_node_schedule();
         let runtimes = inventory
             .node
             .versions
@@ -192,8 +229,7 @@ impl Toolchain {
             .filter_map(|version| {
                 let source = Lookup::Runtime.version_source(project, default_platform, version);
                 if source.allowed_with(filter) {
-                    let version = version.clone();
-                    Some(Node { source, version })
+                    Some(Node::new(source, version.clone(), &schedule))
                 } else {
                     None
                 }
@@ -203,21 +239,25 @@ impl Toolchain {
         Toolchain::Node(runtimes)
     }
 
-    pub(super) fn yarn(
+    pub(super) fn package_managers(
+        kind: PackageManagerKind,
         inventory: &Inventory,
         project: Option<&Project>,
         default_platform: &Option<Rc<PlatformSpec>>,
         filter: &Filter,
     ) -> Toolchain {
-        let yarns = inventory
-            .yarn
-            .versions
+        let versions = match kind {
+            PackageManagerKind::Yarn => &inventory.yarn.versions,
+            PackageManagerKind::Pnpm => &inventory.pnpm.versions,
+            PackageManagerKind::Npm => unreachable!("npm is listed with the Node runtime"),
+        };
+        let managers = versions
             .iter()
             .filter_map(|version| {
-                let source = Lookup::Yarn.version_source(project, default_platform, version);
+                let source = Lookup::from(kind).version_source(project, default_platform, version);
                 if source.allowed_with(filter) {
                     Some(PackageManager {
-                        kind: PackageManagerKind::Yarn,
+                        kind,
                         source,
                         version: version.clone(),
                     })
@@ -227,7 +267,7 @@ impl Toolchain {
             })
             .collect();
 
-        Toolchain::PackageManagers(yarns)
+        Toolchain::PackageManagers { kind, managers }
     }
 
     pub(super) fn package_or_tool(
@@ -297,7 +337,10 @@ impl Toolchain {
         let toolchain = match (has_packages, has_tools) {
             // If there are neither packages nor tools, treat it as `Packages`,
             // but don't re-process the data just to construct an empty `Vec`!
-            (false, false) => Toolchain::Packages(vec![]),
+            (false, false) => Toolchain::Packages {
+                name: name.into(),
+                packages: vec![],
+            },
             // If there are any packages, we resolve this *as* `Packages`, even
             // if there are also matching tools, since we give priority to
             // listing packages between packages and tools.
@@ -310,7 +353,10 @@ impl Toolchain {
                     })
                     .collect();
 
-                Toolchain::Packages(packages)
+                Toolchain::Packages {
+                    name: name.into(),
+                    packages,
+                }
             }
             // If there are no packages matching, but we do have tools matching,
             // we return `Tool`.
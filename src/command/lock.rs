@@ -0,0 +1,28 @@
+use log::info;
+use structopt::StructOpt;
+
+use volta_core::lockfile::lock_project;
+use volta_core::session::{ActivityKind, Session};
+use volta_core::style::success_prefix;
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Lock {}
+
+impl Command for Lock {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Lock);
+
+        let file = lock_project(session)?;
+        info!(
+            "{} locked the project toolchain in {}",
+            success_prefix(),
+            file.display()
+        );
+
+        session.add_event_end(ActivityKind::Lock, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
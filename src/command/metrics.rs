@@ -0,0 +1,104 @@
+use log::{info, warn};
+use structopt::StructOpt;
+
+use volta_core::error::ErrorDetails;
+use volta_core::metrics::{self, Metrics as UsageMetrics};
+use volta_core::session::{ActivityKind, Session};
+use volta_core::settings::settings;
+use volta_core::style::success_prefix;
+use volta_fail::{throw, ExitCode, Fallible};
+
+use crate::command::doctor::Format;
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Metrics {
+    /// The format of the counts, `json` to export them
+    #[structopt(
+        long = "format",
+        default_value = "human",
+        raw(possible_values = r#"&["human", "json"]"#)
+    )]
+    format: Format,
+
+    /// Posts the recorded counts to the `metricsEndpoint` setting, then removes them
+    #[structopt(long = "send", conflicts_with = "clear")]
+    send: bool,
+
+    /// Removes the recorded counts
+    #[structopt(long = "clear")]
+    clear: bool,
+}
+
+impl Command for Metrics {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Metrics);
+
+        if !metrics::AVAILABLE {
+            throw!(ErrorDetails::MetricsNotAvailable);
+        }
+
+        if self.send {
+            UsageMetrics::send()?;
+            info!("{} sent the usage metrics", success_prefix());
+        } else if self.clear {
+            UsageMetrics::clear()?;
+            info!("{} removed the usage metrics", success_prefix());
+        } else {
+            if !settings()?.metrics {
+                warn!("Usage metrics are not recorded unless the 'metrics' setting is enabled");
+            }
+
+            let usage = UsageMetrics::read()?;
+            match self.format {
+                Format::Human => println!("{}", format_human(&usage)),
+                Format::JSON => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&usage).expect("metrics can always be serialized")
+                ),
+            }
+        }
+
+        session.add_event_end(ActivityKind::Metrics, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+fn format_human(usage: &UsageMetrics) -> String {
+    if usage.is_empty() {
+        return "No usage has been recorded.".to_string();
+    }
+
+    let mut lines = vec!["Commands:".to_string()];
+    lines.extend(
+        usage
+            .commands
+            .iter()
+            .map(|(command, count)| format!("    {} {}", command, count)),
+    );
+    lines.push("Tools:".to_string());
+    lines.extend(
+        usage
+            .tools
+            .iter()
+            .map(|(tool, count)| format!("    {} {}", tool, count)),
+    );
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_counts() {
+        let mut usage = UsageMetrics::default();
+        usage.commands.insert("install".to_string(), 2);
+        usage.tools.insert("node@12.13.0".to_string(), 5);
+
+        assert_eq!(
+            format_human(&usage),
+            "Commands:\n    install 2\nTools:\n    node@12.13.0 5"
+        );
+    }
+}
@@ -1,22 +1,70 @@
+pub(crate) mod audit_toolchain;
+pub(crate) mod check;
+pub(crate) mod ci_setup;
+pub(crate) mod complete;
 pub(crate) mod completions;
+pub(crate) mod doctor;
+pub(crate) mod env;
+pub(crate) mod export;
+pub(crate) mod export_env;
 pub(crate) mod fetch;
+pub(crate) mod generate;
+pub(crate) mod history;
+pub(crate) mod import;
+pub(crate) mod import_tools;
 pub(crate) mod install;
 pub(crate) mod list;
+pub(crate) mod lock;
+pub(crate) mod metrics;
+pub(crate) mod outdated;
 pub(crate) mod pin;
+pub(crate) mod prompt;
+pub(crate) mod relocate;
+pub(crate) mod run;
+pub(crate) mod self_update;
 pub(crate) mod setup;
+pub(crate) mod sign;
+pub(crate) mod stats;
+pub(crate) mod status;
 pub(crate) mod uninstall;
+pub(crate) mod upgrade;
 pub(crate) mod r#use;
+pub(crate) mod verify;
 pub(crate) mod which;
 
 pub(crate) use self::which::Which;
+pub(crate) use audit_toolchain::AuditToolchain;
+pub(crate) use check::Check;
+pub(crate) use ci_setup::CiSetup;
+pub(crate) use complete::Complete;
 pub(crate) use completions::Completions;
+pub(crate) use doctor::Doctor;
+pub(crate) use env::Env;
+pub(crate) use export::Export;
+pub(crate) use export_env::ExportEnv;
 pub(crate) use fetch::Fetch;
+pub(crate) use generate::Generate;
+pub(crate) use history::History;
+pub(crate) use import::Import;
+pub(crate) use import_tools::ImportTools;
 pub(crate) use install::Install;
 pub(crate) use list::List;
+pub(crate) use lock::Lock;
+pub(crate) use metrics::Metrics;
+pub(crate) use outdated::Outdated;
 pub(crate) use pin::Pin;
+pub(crate) use prompt::Prompt;
 pub(crate) use r#use::Use;
+pub(crate) use relocate::Relocate;
+pub(crate) use run::Run;
+pub(crate) use self_update::SelfCommand;
 pub(crate) use setup::Setup;
+pub(crate) use sign::Sign;
+pub(crate) use stats::Stats;
+pub(crate) use status::Status;
 pub(crate) use uninstall::Uninstall;
+pub(crate) use upgrade::Upgrade;
+pub(crate) use verify::Verify;
 
 use volta_core::session::Session;
 use volta_fail::{ExitCode, Fallible};
@@ -0,0 +1,211 @@
+use semver::Version;
+use serde::Serialize;
+use structopt::StructOpt;
+
+use volta_core::error::ErrorDetails;
+use volta_core::manifest::Manifest;
+use volta_core::session::{ActivityKind, Session};
+use volta_core::tool::Spec;
+use volta_core::version::{Bump, VersionSpec};
+use volta_fail::{throw, ExitCode, Fallible};
+
+use crate::command::doctor::Format;
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Outdated {
+    /// The format of the report
+    #[structopt(
+        long = "format",
+        default_value = "human",
+        raw(possible_values = r#"&["human", "json"]"#)
+    )]
+    format: Format,
+}
+
+/// The newest releases of a pinned tool, as reported by `volta outdated`
+#[derive(Serialize)]
+struct Entry {
+    tool: String,
+    pinned: Version,
+    #[serde(rename = "latestPatch")]
+    latest_patch: Version,
+    #[serde(rename = "latestMinor")]
+    latest_minor: Version,
+    latest: Version,
+}
+
+impl Entry {
+    fn is_outdated(&self) -> bool {
+        self.latest_patch > self.pinned
+            || self.latest_minor > self.pinned
+            || self.latest > self.pinned
+    }
+}
+
+impl Command for Outdated {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Outdated);
+
+        let pins = match session.project()? {
+            Some(project) => pinned_versions(project.manifest()),
+            None => throw!(ErrorDetails::NotInPackage),
+        };
+
+        let entries = pins
+            .into_iter()
+            .map(|(tool, pinned)| newest_releases(tool, pinned, session))
+            .collect::<Fallible<Vec<_>>>()?;
+
+        match self.format {
+            Format::Human => println!("{}", format_human(&entries)),
+            Format::JSON => println!(
+                "{}",
+                serde_json::to_string_pretty(&entries)
+                    .expect("the outdated versions can always be serialized")
+            ),
+        }
+
+        session.add_event_end(ActivityKind::Outdated, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+/// Returns the versions pinned in the project's own manifest, by tool name
+fn pinned_versions(manifest: &Manifest) -> Vec<(String, Version)> {
+    let mut pins = Vec::new();
+    if let Some(platform) = manifest.platform() {
+        pins.push(("node".to_string(), platform.node_runtime.clone()));
+        pins.extend(
+            platform
+                .npm
+                .clone()
+                .map(|version| ("npm".to_string(), version)),
+        );
+        pins.extend(
+            platform
+                .yarn
+                .clone()
+                .map(|version| ("yarn".to_string(), version)),
+        );
+        pins.extend(
+            platform
+                .pnpm
+                .clone()
+                .map(|version| ("pnpm".to_string(), version)),
+        );
+    }
+    pins.extend(
+        manifest
+            .tools
+            .iter()
+            .map(|(name, version)| (name.clone(), version.clone())),
+    );
+    pins
+}
+
+fn newest_releases(tool: String, pinned: Version, session: &mut Session) -> Fallible<Entry> {
+    let mut newest = |version: VersionSpec| -> Fallible<Version> {
+        Ok(Spec::from_str_and_version(&tool, version)
+            .resolve(session)?
+            .into())
+    };
+
+    let latest_patch = newest(VersionSpec::Semver(Bump::Patch.line(&pinned)))?;
+    let latest_minor = newest(VersionSpec::Semver(Bump::Minor.line(&pinned)))?;
+    let latest = newest(VersionSpec::default())?;
+
+    Ok(Entry {
+        tool,
+        pinned,
+        latest_patch,
+        latest_minor,
+        latest,
+    })
+}
+
+fn format_human(entries: &[Entry]) -> String {
+    if entries.is_empty() {
+        return "No tools are pinned in this project.".to_string();
+    }
+
+    let mut rows = vec![[
+        "Tool".to_string(),
+        "Pinned".to_string(),
+        "Patch".to_string(),
+        "Minor".to_string(),
+        "Latest".to_string(),
+    ]];
+    rows.extend(entries.iter().map(|entry| {
+        [
+            entry.tool.clone(),
+            entry.pinned.to_string(),
+            entry.latest_patch.to_string(),
+            entry.latest_minor.to_string(),
+            entry.latest.to_string(),
+        ]
+    }));
+
+    let widths: Vec<usize> = (0..5)
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect();
+    let mut lines: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+
+    let outdated = entries.iter().filter(|entry| entry.is_outdated()).count();
+    lines.push(String::new());
+    lines.push(match outdated {
+        0 => "All pinned versions are up to date.".to_string(),
+        1 => "1 pinned version has newer releases.".to_string(),
+        count => format!("{} pinned versions have newer releases.", count),
+    });
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    #[test]
+    fn formats_outdated_versions_as_a_table() {
+        let entries = vec![
+            Entry {
+                tool: "node".to_string(),
+                pinned: version("14.15.0"),
+                latest_patch: version("14.15.5"),
+                latest_minor: version("14.17.0"),
+                latest: version("16.13.0"),
+            },
+            Entry {
+                tool: "yarn".to_string(),
+                pinned: version("1.22.10"),
+                latest_patch: version("1.22.10"),
+                latest_minor: version("1.22.10"),
+                latest: version("1.22.10"),
+            },
+        ];
+
+        assert_eq!(
+            format_human(&entries),
+            "Tool  Pinned   Patch    Minor    Latest
+node  14.15.0  14.15.5  14.17.0  16.13.0
+yarn  1.22.10  1.22.10  1.22.10  1.22.10
+
+1 pinned version has newer releases."
+        );
+    }
+}
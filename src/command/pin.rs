@@ -1,8 +1,17 @@
+use std::env;
+
+use log::info;
 use structopt::StructOpt;
 
+use semver::Version;
+use volta_core::error::ErrorDetails;
+use volta_core::manifest::Manifest;
 use volta_core::session::{ActivityKind, Session};
+use volta_core::style::success_prefix;
 use volta_core::tool::Spec;
-use volta_fail::{ExitCode, Fallible};
+use volta_core::version::Bump;
+use volta_core::workspace;
+use volta_fail::{throw, ExitCode, Fallible, ResultExt};
 
 use crate::command::Command;
 
@@ -11,17 +20,99 @@ pub(crate) struct Pin {
     /// Tools to pin, like `node@lts` or `yarn@^1.14`.
     #[structopt(name = "tool[@version]", required = true, min_values = 1)]
     tools: Vec<String>,
+
+    /// Pin the tools in the workspace root and in every package of the workspace
+    #[structopt(long = "all")]
+    all: bool,
+
+    /// With `--all`, make the workspace packages extend the root instead of copying its pins
+    #[structopt(long = "extends", requires = "all")]
+    extends: bool,
+
+    /// Pin the newest patch release in the line of each version (or of the pinned version, for
+    /// tools given without one)
+    #[structopt(long = "latest-patch", conflicts_with = "latest_minor")]
+    latest_patch: bool,
+
+    /// Pin the newest minor release in the line of each version (or of the pinned version, for
+    /// tools given without one)
+    #[structopt(long = "latest-minor")]
+    latest_minor: bool,
 }
 
 impl Command for Pin {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Pin);
 
-        for tool in Spec::from_strings(&self.tools, "pin")? {
-            tool.resolve(session)?.pin(session)?;
+        let workspace_root = if self.all {
+            let current_dir = env::current_dir().with_context(|_| ErrorDetails::CurrentDirError)?;
+            let root = workspace::find_root(&current_dir).ok_or(ErrorDetails::NotInWorkspace)?;
+            session.use_project(&root)?;
+            Some(root)
+        } else {
+            None
+        };
+
+        if session.project()?.is_none() {
+            throw!(ErrorDetails::NotInPackage);
+        }
+
+        // Resolve every requested tool before pinning any of them, so that a bad version for
+        // one tool doesn't leave `package.json` partially updated.
+        let bump = if self.latest_patch {
+            Some(Bump::Patch)
+        } else if self.latest_minor {
+            Some(Bump::Minor)
+        } else {
+            None
+        };
+        let resolved = Spec::from_strings(&self.tools, "pin")?
+            .into_iter()
+            .map(|tool| match bump {
+                Some(bump) => {
+                    // Note: We know this will succeed, since we checked above
+                    let manifest = session.project()?.unwrap().manifest();
+                    let current = pinned_version(&tool, manifest);
+                    tool.resolve_bumped(bump, current.as_ref(), session)
+                }
+                None => tool.resolve(session),
+            })
+            .collect::<Fallible<Vec<_>>>()?;
+
+        for tool in resolved {
+            tool.pin(session)?;
+        }
+
+        if let Some(root) = workspace_root {
+            let packages = workspace::packages(&root);
+            let changed = workspace::pin_packages(&root, &packages, self.extends)?;
+
+            info!(
+                "{} updated {} of {} workspace packages",
+                success_prefix(),
+                changed.len(),
+                packages.len()
+            );
+            for file in changed {
+                let relative = file.strip_prefix(&root).unwrap_or(&file);
+                info!("  {}", relative.display());
+            }
         }
 
         session.add_event_end(ActivityKind::Pin, ExitCode::Success);
         Ok(ExitCode::Success)
     }
 }
+
+/// Returns the version of a tool that is currently pinned in a manifest, if any
+fn pinned_version(tool: &Spec, manifest: &Manifest) -> Option<Version> {
+    match tool {
+        Spec::Node(_) => manifest.node(),
+        Spec::Npm(_) => manifest
+            .platform()
+            .and_then(|platform| platform.npm.clone()),
+        Spec::Yarn(_) => manifest.yarn(),
+        Spec::Pnpm(_) => manifest.pnpm(),
+        Spec::Package(name, _) => manifest.custom_tool(name).cloned(),
+    }
+}
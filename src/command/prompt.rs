@@ -0,0 +1,81 @@
+use log::debug;
+use semver::Version;
+use structopt::StructOpt;
+
+use volta_core::platform::Source;
+use volta_core::session::{active_node, ActivityKind, Session};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Prompt {
+    /// The template to print, where `{version}` is replaced by the version of Node and
+    /// `{source}` by where it comes from, `project` or `default`
+    #[structopt(long = "format", default_value = "⬡ {version}")]
+    format: String,
+
+    /// Prints nothing unless the current project pins Node
+    #[structopt(long = "project-only")]
+    project_only: bool,
+}
+
+impl Prompt {
+    /// Prints the prompt, returning the exit code of any error instead of reporting it
+    ///
+    /// `volta prompt` runs this without the layout check, the session and its events that
+    /// other commands have, since a prompt is printed after every shell command.
+    pub(crate) fn print(&self) -> ExitCode {
+        // Errors are never reported: the prompt is left empty instead, with the exit code of
+        // the error
+        match active_node() {
+            Ok(Some((version, source))) => {
+                if !self.project_only || source == Source::Project {
+                    println!("{}", format_prompt(&self.format, &version, source));
+                }
+                ExitCode::Success
+            }
+            Ok(None) => ExitCode::Success,
+            Err(error) => {
+                debug!(
+                    "Could not resolve the Node version for the prompt: {}",
+                    error
+                );
+                error.exit_code()
+            }
+        }
+    }
+}
+
+impl Command for Prompt {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Prompt);
+        let exit_code = self.print();
+        session.add_event_end(ActivityKind::Prompt, exit_code);
+        Ok(exit_code)
+    }
+}
+
+fn format_prompt(template: &str, version: &Version, source: Source) -> String {
+    template
+        .replace("{version}", &version.to_string())
+        .replace("{source}", &source.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_prompt_template() {
+        let version = Version::parse("18.19.0").unwrap();
+        assert_eq!(
+            format_prompt("⬡ {version}", &version, Source::Project),
+            "⬡ 18.19.0"
+        );
+        assert_eq!(
+            format_prompt("node {version} ({source})", &version, Source::Default),
+            "node 18.19.0 (default)"
+        );
+    }
+}
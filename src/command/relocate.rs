@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use log::info;
+use structopt::StructOpt;
+use volta_core::layout::volta_home;
+use volta_core::relocate::relocate;
+use volta_core::session::{ActivityKind, Session};
+use volta_core::style::{note_prefix, success_prefix};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::setup::setup_environment;
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Relocate {
+    /// The directory to move the Volta home to, which must not exist or be empty
+    #[structopt(parse(from_os_str))]
+    path: PathBuf,
+
+    /// Don't modify shell profiles or the user PATH; print the required changes instead
+    #[structopt(long = "no-modify-profile")]
+    no_modify_profile: bool,
+}
+
+impl Command for Relocate {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Relocate);
+
+        let previous = volta_home()?;
+        let home = relocate(&self.path, session)?;
+
+        if self.no_modify_profile {
+            info!(
+                "{} Moved the Volta home to '{}'. To finish, set VOLTA_HOME to '{}'
+and replace '{}' with '{}' in your PATH.",
+                note_prefix(),
+                home.root().display(),
+                home.root().display(),
+                previous.shim_dir().display(),
+                home.shim_dir().display()
+            );
+        } else {
+            setup_environment(&home, Some(previous))?;
+
+            info!(
+                "{} Moved the Volta home to '{}'. Open a new terminal to start using it!",
+                success_prefix(),
+                home.root().display()
+            );
+        }
+
+        session.add_event_end(ActivityKind::Relocate, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
@@ -0,0 +1,166 @@
+use std::env;
+use std::ffi::OsString;
+
+use semver::Version;
+
+use structopt::StructOpt;
+
+use volta_core::env_vars;
+use volta_core::error::ErrorDetails;
+use volta_core::platform::PlatformSpec;
+use volta_core::run::execute_with_platform;
+use volta_core::session::{ActivityKind, Session};
+use volta_core::tool::Spec;
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+use crate::common::{Error, IntoResult};
+
+#[derive(StructOpt)]
+#[structopt(raw(setting = "structopt::clap::AppSettings::TrailingVarArg"))]
+pub(crate) struct Run {
+    /// Set the custom Node version
+    #[structopt(long = "node", value_name = "version")]
+    node: Option<String>,
+
+    /// Set the custom Yarn version
+    #[structopt(long = "yarn", value_name = "version")]
+    yarn: Option<String>,
+
+    /// Set the custom pnpm version
+    #[structopt(long = "pnpm", value_name = "version")]
+    pnpm: Option<String>,
+
+    /// Fail if the current project doesn't pin Node, instead of using the default
+    #[structopt(long = "strict")]
+    strict: bool,
+
+    /// The command to run, along with any arguments
+    #[structopt(
+        name = "command",
+        required = true,
+        min_values = 1,
+        parse(from_os_str),
+        raw(allow_hyphen_values = "true")
+    )]
+    command: Vec<OsString>,
+}
+
+impl Command for Run {
+    /// Runs the command with the requested platform.
+    ///
+    /// `volta run` exits with the status of the underlying tool, which may be any code, so a
+    /// failing tool exits the process directly rather than returning.
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Run);
+
+        let platform = self.platform(session)?;
+        let mut command = self.command.into_iter();
+        let exe = command
+            .next()
+            .expect("StructOpt requires the command to be specified");
+
+        match execute_with_platform(&exe, command, &platform, session).into_result() {
+            Ok(()) => {
+                session.add_event_end(ActivityKind::Run, ExitCode::Success);
+                Ok(ExitCode::Success)
+            }
+            Err(Error::Tool(code)) => {
+                session.add_event_tool_end(ActivityKind::Run, code);
+                session.exit_tool(code);
+            }
+            Err(Error::Volta(err)) => Err(err),
+        }
+    }
+}
+
+impl Run {
+    /// Builds the platform for the command, using the current platform for any tool that
+    /// wasn't specified on the command line.
+    fn platform(&self, session: &mut Session) -> Fallible<PlatformSpec> {
+        if self.strict {
+            // Tools run by the command, like the shims used by npm scripts, are strict as well
+            env::set_var(env_vars::VOLTA_STRICT, "1");
+            if self.node.is_none() {
+                session.check_pinned()?;
+            }
+        }
+
+        let current = session.current_platform()?;
+
+        let (node_runtime, npm) = match &self.node {
+            Some(node) => (resolve(Spec::Node(node.parse()?), session)?, None),
+            None => match &current {
+                Some(current) => (current.node().clone(), current.npm().cloned()),
+                None => return Err(ErrorDetails::NoPlatform.into()),
+            },
+        };
+
+        let yarn = match &self.yarn {
+            Some(yarn) => Some(resolve(Spec::Yarn(yarn.parse()?), session)?),
+            None => current.as_ref().and_then(|current| current.yarn().cloned()),
+        };
+
+        let pnpm = match &self.pnpm {
+            Some(pnpm) => Some(resolve(Spec::Pnpm(pnpm.parse()?), session)?),
+            None => current.as_ref().and_then(|current| current.pnpm().cloned()),
+        };
+
+        Ok(PlatformSpec {
+            node_runtime,
+            npm,
+            yarn,
+            pnpm,
+        })
+    }
+}
+
+fn resolve(spec: Spec, session: &mut Session) -> Fallible<Version> {
+    spec.resolve(session).map(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Run {
+        Run::from_iter_safe(args).unwrap()
+    }
+
+    #[test]
+    fn parses_versions_and_command() {
+        let run = parse(&[
+            "run", "--node", "10.16", "--yarn", "1.17", "--strict", "yarn", "install",
+        ]);
+
+        assert_eq!(run.node, Some("10.16".to_string()));
+        assert_eq!(run.yarn, Some("1.17".to_string()));
+        assert_eq!(run.pnpm, None);
+        assert!(run.strict);
+        assert_eq!(
+            run.command,
+            vec![OsString::from("yarn"), OsString::from("install")]
+        );
+    }
+
+    #[test]
+    fn passes_flags_after_the_command_to_it() {
+        let run = parse(&["run", "node", "--version", "--node", "12"]);
+
+        assert_eq!(run.node, None);
+        assert_eq!(
+            run.command,
+            vec![
+                OsString::from("node"),
+                OsString::from("--version"),
+                OsString::from("--node"),
+                OsString::from("12")
+            ]
+        );
+    }
+
+    #[test]
+    fn requires_a_command() {
+        assert!(Run::from_iter_safe(&["run", "--node", "10"]).is_err());
+    }
+}
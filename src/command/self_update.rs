@@ -0,0 +1,62 @@
+use log::info;
+use structopt::StructOpt;
+
+use volta_core::self_update::{check_for_update, install};
+use volta_core::session::{ActivityKind, Session};
+use volta_core::style::success_prefix;
+use volta_core::version::parse_version;
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) enum SelfCommand {
+    /// Updates Volta to the latest release
+    #[structopt(name = "update", author = "", version = "")]
+    Update(Update),
+}
+
+impl Command for SelfCommand {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        match self {
+            SelfCommand::Update(update) => update.run(session),
+        }
+    }
+}
+
+#[derive(StructOpt)]
+pub(crate) struct Update {
+    /// Only report whether a newer release is available, without installing it
+    #[structopt(long = "check")]
+    check: bool,
+}
+
+impl Command for Update {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::SelfUpdate);
+
+        let current = parse_version(env!("CARGO_PKG_VERSION"))?;
+        match check_for_update(&current)? {
+            None => info!("Volta is up to date (v{})", current),
+            Some(release) => {
+                if self.check {
+                    info!(
+                        "Volta v{} is available (currently v{}). Run `volta self update` to install it.",
+                        release.version, current
+                    );
+                } else {
+                    install(&release)?;
+                    info!(
+                        "{} updated Volta from v{} to v{}",
+                        success_prefix(),
+                        current,
+                        release.version
+                    );
+                }
+            }
+        }
+
+        session.add_event_end(ActivityKind::SelfUpdate, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
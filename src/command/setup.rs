@@ -1,24 +1,49 @@
 use log::info;
 use structopt::StructOpt;
+use volta_core::fs::check_home_writable;
+use volta_core::layout::volta_home;
 use volta_core::session::{ActivityKind, Session};
-use volta_core::style::success_prefix;
+use volta_core::style::{note_prefix, success_prefix};
 use volta_fail::{ExitCode, Fallible};
+use volta_migrate::run_migration;
 
 use crate::command::Command;
 
+pub(crate) use os::setup_environment;
+
 #[derive(StructOpt)]
-pub(crate) struct Setup {}
+pub(crate) struct Setup {
+    /// Don't modify shell profiles or the user PATH; print the required changes instead
+    #[structopt(long = "no-modify-profile")]
+    no_modify_profile: bool,
+}
 
 impl Command for Setup {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Setup);
 
-        os::setup_environment()?;
+        // Creates the directory layout and default shims for a fresh install, or brings an
+        // existing one up to date
+        check_home_writable()?;
+        run_migration()?;
 
-        info!(
-            "{} Setup complete. Open a new terminal to start using Volta!",
-            success_prefix()
-        );
+        if self.no_modify_profile {
+            let home = volta_home()?;
+            info!(
+                "{} Skipped modifying your shell profile. To finish setup, set VOLTA_HOME to '{}'
+and add '{}' to the front of your PATH.",
+                note_prefix(),
+                home.root().display(),
+                home.shim_dir().display()
+            );
+        } else {
+            setup_environment(volta_home()?, None)?;
+
+            info!(
+                "{} Setup complete. Open a new terminal to start using Volta!",
+                success_prefix()
+            );
+        }
 
         session.add_event_end(ActivityKind::Setup, ExitCode::Success);
         Ok(ExitCode::Success)
@@ -34,20 +59,27 @@ mod os {
 
     use log::{debug, warn};
     use volta_core::error::ErrorDetails;
-    use volta_core::layout::volta_home;
+    use volta_core::fs::check_writable;
+    use volta_core::layout::VoltaHome;
     use volta_fail::Fallible;
 
-    const PROFILES: [&str; 5] = [
+    use crate::command::env::{fish_quote, posix_quote, powershell_quote};
+
+    const PROFILES: [&str; 6] = [
         ".profile",
         ".bash_profile",
         ".bashrc",
         ".zshrc",
         ".config/fish/config.fish",
+        ".config/powershell/Microsoft.PowerShell_profile.ps1",
     ];
 
-    pub fn setup_environment() -> Fallible<()> {
+    /// Writes the Volta entries of the shell profiles for `home`
+    ///
+    /// Any existing Volta entry is replaced, which also removes the entries for the `previous`
+    /// home when the home was moved.
+    pub fn setup_environment(home: &VoltaHome, _previous: Option<&VoltaHome>) -> Fallible<()> {
         let user_home_dir = dirs::home_dir().ok_or(ErrorDetails::NoHomeEnvironmentVar)?;
-        let home = volta_home()?;
 
         debug!("Searching for profiles to update");
         let env_profile = env::var("PROFILE");
@@ -63,9 +95,19 @@ mod os {
 
                         let write_profile = match profile.extension() {
                             Some(ext) if ext == "fish" => write_profile_fish,
+                            Some(ext) if ext == "ps1" => write_profile_powershell,
                             _ => write_profile_sh,
                         };
 
+                        if let Err(err) = check_writable(&profile) {
+                            warn!(
+                                "Found profile script, but don't have permission to modify it: {}",
+                                profile.display()
+                            );
+                            debug!("Profile permissions error: {}", err);
+                            return prev;
+                        }
+
                         match write_profile(&profile, contents, home.root()) {
                             Ok(()) => true,
                             Err(err) => {
@@ -116,9 +158,9 @@ mod os {
         let mut file = File::create(path)?;
         write!(
             file,
-            "{}\nexport VOLTA_HOME=\"{}\"\ngrep --silent \"$VOLTA_HOME/bin\" <<< $PATH || export PATH=\"$VOLTA_HOME/bin:$PATH\"\n",
+            "{}\nexport VOLTA_HOME={}\ngrep --silent \"$VOLTA_HOME/bin\" <<< $PATH || export PATH=\"$VOLTA_HOME/bin:$PATH\"\n",
             contents,
-            volta_home.display(),
+            posix_quote(&volta_home.to_string_lossy()),
         )
     }
 
@@ -126,9 +168,23 @@ mod os {
         let mut file = File::create(path)?;
         write!(
             file,
-            "{}\nset -gx VOLTA_HOME \"{}\"\nstring match -r \".volta\" \"$PATH\" > /dev/null; or set -gx PATH \"$VOLTA_HOME/bin\" $PATH\n",
+            "{}\nset -gx VOLTA_HOME {}\nstring match -r \".volta\" \"$PATH\" > /dev/null; or set -gx PATH \"$VOLTA_HOME/bin\" $PATH\n",
             contents,
-            volta_home.display(),
+            fish_quote(&volta_home.to_string_lossy()),
+        )
+    }
+
+    fn write_profile_powershell(
+        path: &Path,
+        contents: String,
+        volta_home: &Path,
+    ) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        write!(
+            file,
+            "{}\n$env:VOLTA_HOME = {}\nif (-not $env:PATH.Contains(\"$env:VOLTA_HOME/bin\")) {{ $env:PATH = \"$env:VOLTA_HOME/bin:$env:PATH\" }}\n",
+            contents,
+            powershell_quote(&volta_home.to_string_lossy()),
         )
     }
 }
@@ -139,39 +195,64 @@ mod os {
 
     use log::debug;
     use volta_core::error::ErrorDetails;
-    use volta_core::layout::volta_home;
+    use volta_core::layout::VoltaHome;
     use volta_fail::{Fallible, ResultExt};
     use winreg::enums::HKEY_CURRENT_USER;
     use winreg::RegKey;
 
-    pub fn setup_environment() -> Fallible<()> {
-        let shim_dir = volta_home()?.shim_dir().to_string_lossy().to_string();
+    /// Adds the shim directory of `home` to the user Path
+    ///
+    /// When the home was moved from `previous`, its shim directory is removed from the Path, and
+    /// VOLTA_HOME is set, since the new location isn't the default one.
+    pub fn setup_environment(home: &VoltaHome, previous: Option<&VoltaHome>) -> Fallible<()> {
+        let shim_dir = home.shim_dir().to_string_lossy().to_string();
         let hkcu = RegKey::predef(HKEY_CURRENT_USER);
         let env = hkcu
             .open_subkey("Environment")
             .with_context(|_| ErrorDetails::ReadUserPathError)?;
-        let path: String = env
+        let mut path: String = env
             .get_value("Path")
             .with_context(|_| ErrorDetails::ReadUserPathError)?;
 
+        let mut modified = false;
+        if let Some(previous) = previous {
+            let previous_dir = previous.shim_dir().to_string_lossy().to_string();
+            path = path
+                .split(';')
+                .filter(|entry| !entry.eq_ignore_ascii_case(&previous_dir))
+                .collect::<Vec<_>>()
+                .join(";");
+            modified = true;
+            set_user_variable("VOLTA_HOME", &home.root().to_string_lossy())?;
+        }
+
         if !path.contains(&shim_dir) {
-            // Use `setx` command to edit the user Path environment variable
-            let mut command = Command::new("setx");
-            command.arg("Path");
-            command.arg(format!("{};{}", shim_dir, path));
-
-            debug!("Modifying User Path with command: {:?}", command);
-            let output = command
-                .output()
-                .with_context(|_| ErrorDetails::WriteUserPathError)?;
-
-            if !output.status.success() {
-                debug!("[setx stderr]\n{}", String::from_utf8_lossy(&output.stderr));
-                debug!("[setx stdout]\n{}", String::from_utf8_lossy(&output.stdout));
-                return Err(ErrorDetails::WriteUserPathError.into());
-            }
+            path = format!("{};{}", shim_dir, path);
+            modified = true;
+        }
+        if modified {
+            set_user_variable("Path", &path)?;
         }
 
         Ok(())
     }
+
+    fn set_user_variable(name: &str, value: &str) -> Fallible<()> {
+        // Use `setx` command to edit the user environment variable
+        let mut command = Command::new("setx");
+        command.arg(name);
+        command.arg(value);
+
+        debug!("Modifying User {} with command: {:?}", name, command);
+        let output = command
+            .output()
+            .with_context(|_| ErrorDetails::WriteUserPathError)?;
+
+        if !output.status.success() {
+            debug!("[setx stderr]\n{}", String::from_utf8_lossy(&output.stderr));
+            debug!("[setx stdout]\n{}", String::from_utf8_lossy(&output.stdout));
+            return Err(ErrorDetails::WriteUserPathError.into());
+        }
+        Ok(())
+    }
 }
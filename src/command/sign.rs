@@ -0,0 +1,35 @@
+use log::info;
+use structopt::StructOpt;
+use volta_core::integrity::{disable_signing, enable_signing};
+use volta_core::session::{ActivityKind, Session};
+use volta_core::style::success_prefix;
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Sign {
+    /// Turn signing off, removing the machine secret and the signatures
+    #[structopt(long = "off")]
+    off: bool,
+}
+
+impl Command for Sign {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Sign);
+
+        if self.off {
+            disable_signing()?;
+            info!("{} Turned off signing of the Volta files", success_prefix());
+        } else {
+            enable_signing()?;
+            info!(
+                "{} Signed the default platform and settings files",
+                success_prefix()
+            );
+        }
+
+        session.add_event_end(ActivityKind::Sign, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
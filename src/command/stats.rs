@@ -0,0 +1,131 @@
+use serde::Serialize;
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_core::stats::{self, ToolStats};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::doctor::Format;
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Stats {
+    /// The format of the statistics
+    #[structopt(
+        long = "format",
+        default_value = "human",
+        raw(possible_values = r#"&["human", "json"]"#)
+    )]
+    format: Format,
+}
+
+/// The statistics of one tool, which may come from an installed package
+#[derive(Serialize)]
+struct Row {
+    tool: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package: Option<String>,
+    runs: u64,
+    /// The local time of the last run, in RFC 3339 format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_run: Option<String>,
+}
+
+impl Command for Stats {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Stats);
+
+        let mut recorded = stats::read()?;
+        let mut rows = Vec::new();
+        // The executables of installed packages are listed even when they were never run, since
+        // those are the packages that can be uninstalled
+        for package in session.inventory()?.packages.iter() {
+            for bin in &package.bins {
+                rows.push(row(
+                    bin,
+                    Some(format!("{}@{}", package.name, package.version)),
+                    recorded.remove(bin),
+                ));
+            }
+        }
+        rows.extend(
+            recorded
+                .into_iter()
+                .map(|(tool, stats)| row(&tool, None, Some(stats))),
+        );
+        rows.sort_by(|a, b| b.runs.cmp(&a.runs).then_with(|| a.tool.cmp(&b.tool)));
+
+        match self.format {
+            Format::Human => println!("{}", format_human(&rows)),
+            Format::JSON => println!(
+                "{}",
+                serde_json::to_string_pretty(&rows)
+                    .expect("the statistics can always be serialized")
+            ),
+        }
+
+        session.add_event_end(ActivityKind::Stats, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
+
+fn row(tool: &str, package: Option<String>, stats: Option<ToolStats>) -> Row {
+    Row {
+        tool: tool.to_string(),
+        package,
+        runs: stats.as_ref().map_or(0, |stats| stats.runs),
+        last_run: stats.map(|stats| stats.last_run.to_rfc3339()),
+    }
+}
+
+fn format_human(rows: &[Row]) -> String {
+    if rows.is_empty() {
+        return "No tools have been run through Volta yet.".to_string();
+    }
+
+    let mut lines = vec![format!("{:>6}  {:<16}  {}", "Runs", "Last run", "Tool")];
+    for row in rows {
+        // Only the date and time of the RFC 3339 timestamp are shown
+        let last_run = row
+            .last_run
+            .as_ref()
+            .map(|last_run| last_run.get(..16).unwrap_or(last_run).replace('T', " "))
+            .unwrap_or_else(|| "never".to_string());
+        let tool = match &row.package {
+            Some(package) => format!("{} ({})", row.tool, package),
+            None => row.tool.clone(),
+        };
+        lines.push(format!("{:>6}  {:<16}  {}", row.runs, last_run, tool));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_unused_tools_as_never_run() {
+        let rows = vec![
+            Row {
+                tool: "node".to_string(),
+                package: None,
+                runs: 12,
+                last_run: Some("2020-09-13T14:26:40+02:00".to_string()),
+            },
+            Row {
+                tool: "tsc".to_string(),
+                package: Some("typescript@3.9.7".to_string()),
+                runs: 0,
+                last_run: None,
+            },
+        ];
+
+        assert_eq!(
+            format_human(&rows),
+            "  Runs  Last run          Tool
+    12  2020-09-13 14:26  node
+     0  never             tsc (typescript@3.9.7)"
+        );
+    }
+}
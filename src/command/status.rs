@@ -0,0 +1,263 @@
+use semver::Version;
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_core::tool::load_default_npm_version;
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Status {
+    /// Prints the status in a stable format for scripts, one `<tool> <version> <source> <state>`
+    /// line per tool after a `status <state>` line
+    ///
+    /// The exit status also reports the state: 0 when ready, 20 when unpinned and 21 when not
+    /// installed. These are distinct from the statuses that Volta exits with on an error.
+    #[structopt(long = "porcelain")]
+    porcelain: bool,
+}
+
+/// The overall state of the toolchain for the current directory
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    /// Every resolved version is installed
+    Ready,
+    /// The current project doesn't pin Node, or there is no current project
+    Unpinned,
+    /// At least one of the resolved versions has not been fetched yet
+    NotInstalled,
+}
+
+impl State {
+    fn name(self) -> &'static str {
+        match self {
+            State::Ready => "ready",
+            State::Unpinned => "unpinned",
+            State::NotInstalled => "not-installed",
+        }
+    }
+
+    fn exit_code(self) -> ExitCode {
+        match self {
+            State::Ready => ExitCode::Success,
+            State::Unpinned => ExitCode::StatusUnpinned,
+            State::NotInstalled => ExitCode::StatusNotInstalled,
+        }
+    }
+}
+
+/// The resolved version of a tool
+struct Entry {
+    tool: &'static str,
+    version: Version,
+    /// Where the version comes from, `project` or `default`
+    source: &'static str,
+    installed: bool,
+}
+
+impl Command for Status {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Status);
+
+        let pinned = session.project_platform()?.is_some();
+        let entries = resolved_tools(session)?;
+        let state = if !pinned {
+            State::Unpinned
+        } else if entries.iter().any(|entry| !entry.installed) {
+            State::NotInstalled
+        } else {
+            State::Ready
+        };
+
+        if self.porcelain {
+            print!("{}", format_porcelain(state, &entries));
+        } else {
+            println!("{}", format_human(state, &entries));
+        }
+
+        let exit_code = state.exit_code();
+        session.add_event_end(ActivityKind::Status, exit_code);
+        Ok(exit_code)
+    }
+}
+
+/// Returns the versions of the tools that the current directory resolves to
+fn resolved_tools(session: &Session) -> Fallible<Vec<Entry>> {
+    let platform = match session.current_platform()? {
+        Some(platform) => platform,
+        None => return Ok(Vec::new()),
+    };
+    let project = session.project_platform()?;
+    let default = session.default_platform()?;
+
+    let inventory = session.inventory()?;
+    let mut entries = Vec::new();
+
+    let node = platform.node();
+    let node_source = source(
+        project.as_ref().map(|p| &p.node_runtime),
+        default.as_ref().map(|p| &p.node_runtime),
+        node,
+    );
+    let node_installed = inventory.node.versions.contains(node);
+    entries.push(Entry {
+        tool: "node",
+        version: node.clone(),
+        source: node_source,
+        installed: node_installed,
+    });
+
+    // The bundled npm is only known once Node has been fetched
+    let bundled_npm = load_default_npm_version(node).ok();
+    match platform.npm() {
+        Some(npm) => entries.push(Entry {
+            tool: "npm",
+            version: npm.clone(),
+            source: source(
+                project.as_ref().and_then(|p| p.npm.as_ref()),
+                default.as_ref().and_then(|p| p.npm.as_ref()),
+                npm,
+            ),
+            installed: if bundled_npm.as_ref() == Some(npm) {
+                node_installed
+            } else {
+                inventory.npm.versions.contains(npm)
+            },
+        }),
+        None => entries.extend(bundled_npm.map(|npm| Entry {
+            tool: "npm",
+            version: npm,
+            source: node_source,
+            installed: node_installed,
+        })),
+    }
+
+    if let Some(yarn) = platform.yarn() {
+        entries.push(Entry {
+            tool: "yarn",
+            version: yarn.clone(),
+            source: source(
+                project.as_ref().and_then(|p| p.yarn.as_ref()),
+                default.as_ref().and_then(|p| p.yarn.as_ref()),
+                yarn,
+            ),
+            installed: inventory.yarn.versions.contains(yarn),
+        });
+    }
+
+    if let Some(pnpm) = platform.pnpm() {
+        entries.push(Entry {
+            tool: "pnpm",
+            version: pnpm.clone(),
+            source: source(
+                project.as_ref().and_then(|p| p.pnpm.as_ref()),
+                default.as_ref().and_then(|p| p.pnpm.as_ref()),
+                pnpm,
+            ),
+            installed: inventory.pnpm.versions.contains(pnpm),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Returns where a resolved version comes from: the project if the project pins it, or the
+/// default toolchain, unless the project selected another version some other way, such as
+/// `packageManager`
+fn source(pinned: Option<&Version>, default: Option<&Version>, version: &Version) -> &'static str {
+    if pinned.is_some() || default != Some(version) {
+        "project"
+    } else {
+        "default"
+    }
+}
+
+fn installed_name(installed: bool) -> &'static str {
+    if installed {
+        "installed"
+    } else {
+        "not-installed"
+    }
+}
+
+fn format_porcelain(state: State, entries: &[Entry]) -> String {
+    let mut output = format!("status {}\n", state.name());
+    for entry in entries {
+        output.push_str(&format!(
+            "{} {} {} {}\n",
+            entry.tool,
+            entry.version,
+            entry.source,
+            installed_name(entry.installed)
+        ));
+    }
+    output
+}
+
+fn format_human(state: State, entries: &[Entry]) -> String {
+    let mut lines: Vec<String> = entries
+        .iter()
+        .map(|entry| {
+            if entry.installed {
+                format!("{} {} ({})", entry.tool, entry.version, entry.source)
+            } else {
+                format!(
+                    "{} {} ({}, not installed)",
+                    entry.tool, entry.version, entry.source
+                )
+            }
+        })
+        .collect();
+
+    let summary = match state {
+        State::Ready => "The toolchain is ready.",
+        State::Unpinned => "Node is not pinned in the current project.",
+        State::NotInstalled => {
+            "Some versions have not been fetched yet, run `volta fetch --from-project` to fetch them."
+        }
+    };
+    if !lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines.push(summary.to_string());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_porcelain_status() {
+        let entries = vec![
+            Entry {
+                tool: "node",
+                version: Version::parse("14.15.0").unwrap(),
+                source: "project",
+                installed: true,
+            },
+            Entry {
+                tool: "yarn",
+                version: Version::parse("1.22.10").unwrap(),
+                source: "default",
+                installed: false,
+            },
+        ];
+
+        assert_eq!(
+            format_porcelain(State::NotInstalled, &entries),
+            "status not-installed
+node 14.15.0 project installed
+yarn 1.22.10 default not-installed
+"
+        );
+    }
+
+    #[test]
+    fn reports_states_with_dedicated_statuses() {
+        assert_eq!(State::Ready.exit_code() as i32, 0);
+        assert_eq!(State::Unpinned.exit_code() as i32, 20);
+        assert_eq!(State::NotInstalled.exit_code() as i32, 21);
+    }
+}
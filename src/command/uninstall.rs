@@ -1,5 +1,7 @@
+use log::info;
 use structopt::StructOpt;
 
+use volta_core::prompt;
 use volta_core::session::{ActivityKind, Session};
 use volta_core::tool;
 use volta_core::version::VersionSpec;
@@ -10,19 +12,79 @@ use crate::command::Command;
 #[derive(StructOpt)]
 pub(crate) struct Uninstall {
     /// The tool to uninstall, e.g. `node`, `npm`, `yarn`, or <package>
-    tool: String,
+    #[structopt(required_unless = "all", conflicts_with = "all")]
+    tool: Option<String>,
+
+    /// Uninstall every installed package
+    #[structopt(long = "all")]
+    all: bool,
+
+    /// Uninstalls without asking for confirmation, which is required in non-interactive runs
+    #[structopt(long = "yes", short = "y")]
+    yes: bool,
 }
 
 impl Command for Uninstall {
     fn run(self, session: &mut Session) -> Fallible<ExitCode> {
         session.add_event_start(ActivityKind::Uninstall);
 
-        let version = VersionSpec::default();
-        let tool = tool::Spec::from_str_and_version(&self.tool, version);
+        let tools = match self.tool {
+            Some(tool) => vec![tool],
+            None => installed_packages(session)?,
+        };
 
-        tool.uninstall()?;
+        if !tools.is_empty() && !prompt::confirm(&action(&tools), self.yes)? {
+            info!("Nothing was uninstalled.");
+            session.add_event_end(ActivityKind::Uninstall, ExitCode::Success);
+            return Ok(ExitCode::Success);
+        }
+
+        for tool in tools {
+            let version = VersionSpec::default();
+            tool::Spec::from_str_and_version(&tool, version).uninstall()?;
+        }
 
         session.add_event_end(ActivityKind::Uninstall, ExitCode::Success);
         Ok(ExitCode::Success)
     }
 }
+
+/// Describes the uninstallation to confirm, like `uninstall cowsay and typescript`
+fn action(tools: &[String]) -> String {
+    match tools.split_last() {
+        Some((last, [])) => format!("uninstall {}", last),
+        Some((last, rest)) => format!("uninstall {} and {}", rest.join(", "), last),
+        None => "uninstall nothing".to_string(),
+    }
+}
+
+/// The names of all the packages in the user's toolchain
+pub(crate) fn installed_packages(session: &Session) -> Fallible<Vec<String>> {
+    Ok(session
+        .inventory()?
+        .packages
+        .iter()
+        .map(|config| config.name.clone())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_the_action() {
+        let tools = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(action(&tools(&["cowsay"])), "uninstall cowsay");
+        assert_eq!(
+            action(&tools(&["cowsay", "typescript", "serve"])),
+            "uninstall cowsay, typescript and serve"
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use structopt::StructOpt;
+
+use volta_core::session::{ActivityKind, Session};
+use volta_core::tool;
+use volta_core::version::VersionSpec;
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::uninstall::installed_packages;
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Upgrade {
+    /// Packages to upgrade to their latest version
+    #[structopt(
+        name = "package",
+        min_values = 1,
+        required_unless = "all",
+        conflicts_with = "all"
+    )]
+    packages: Vec<String>,
+
+    /// Upgrade every installed package
+    #[structopt(long = "all")]
+    all: bool,
+}
+
+impl Command for Upgrade {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Upgrade);
+
+        let packages = if self.all {
+            installed_packages(session)?
+        } else {
+            self.packages
+        };
+
+        for package in packages {
+            let version = VersionSpec::default();
+            tool::Spec::from_str_and_version(&package, version).upgrade(session)?;
+        }
+
+        session.add_event_end(ActivityKind::Upgrade, ExitCode::Success);
+        Ok(ExitCode::Success)
+    }
+}
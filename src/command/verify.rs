@@ -0,0 +1,80 @@
+use log::{info, warn};
+use structopt::StructOpt;
+
+use volta_core::doctor::Status;
+use volta_core::session::{ActivityKind, Session};
+use volta_core::style::success_prefix;
+use volta_core::verify::{verify, Item};
+use volta_fail::{ExitCode, Fallible};
+
+use crate::command::doctor::{format_result, Format};
+use crate::command::Command;
+
+#[derive(StructOpt)]
+pub(crate) struct Verify {
+    /// Removes the damaged versions and fetches them again
+    #[structopt(long = "repair")]
+    repair: bool,
+
+    /// The format of the results
+    #[structopt(
+        long = "format",
+        default_value = "human",
+        raw(possible_values = r#"&["human", "json"]"#)
+    )]
+    format: Format,
+}
+
+impl Command for Verify {
+    fn run(self, session: &mut Session) -> Fallible<ExitCode> {
+        session.add_event_start(ActivityKind::Verify);
+
+        let mut items = verify(session)?;
+        if self.repair && items.iter().any(Item::needs_repair) {
+            for item in items.iter().filter(|item| item.needs_repair()) {
+                // A version that can't be repaired is reported by verifying again below, so the
+                // remaining versions still get repaired
+                match item.repair(session) {
+                    Ok(()) => info!("{} repaired {}", success_prefix(), item.name),
+                    Err(error) => warn!("Could not repair {}: {}", item.name, error),
+                }
+            }
+            items = verify(session)?;
+        }
+
+        match self.format {
+            Format::Human => println!("{}", format_human(&items)),
+            Format::JSON => println!(
+                "{}",
+                serde_json::to_string_pretty(&items).expect("the results can always be serialized")
+            ),
+        }
+
+        let exit_code = match items.iter().map(|item| item.status).max() {
+            Some(Status::Fail) => ExitCode::FileSystemError,
+            _ => ExitCode::Success,
+        };
+
+        session.add_event_end(ActivityKind::Verify, exit_code);
+        Ok(exit_code)
+    }
+}
+
+fn format_human(items: &[Item]) -> String {
+    if items.is_empty() {
+        return "There are no tools to verify.".to_string();
+    }
+
+    let mut lines: Vec<String> = items
+        .iter()
+        .map(|item| format_result(item.status, &item.name, &item.problems))
+        .collect();
+    let summary = if items.iter().any(Item::needs_repair) {
+        "Some versions have problems; run `volta verify --repair` to fetch them again."
+    } else {
+        "All versions are intact."
+    };
+    lines.push(String::new());
+    lines.push(summary.to_string());
+    lines.join("\n")
+}
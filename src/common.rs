@@ -1,8 +1,10 @@
 use std::process::{Command, ExitStatus};
 
+use volta_core::env_vars;
 use volta_core::error::ErrorDetails;
 use volta_core::layout::{volta_home, volta_install};
-use volta_fail::{ResultExt, VoltaError};
+use volta_core::settings::exit_policy;
+use volta_fail::{ExitCode, ResultExt, VoltaError};
 
 pub enum Error {
     Volta(VoltaError),
@@ -15,7 +17,7 @@ pub fn ensure_layout() -> Result<(), Error> {
     if !home.layout_file().exists() {
         let install = volta_install().map_err(Error::Volta)?;
         Command::new(install.migrate_executable())
-            .env("VOLTA_LOGLEVEL", format!("{}", log::max_level()))
+            .env(env_vars::VOLTA_LOG, format!("{}", log::max_level()))
             .status()
             .with_context(|_| ErrorDetails::CouldNotStartMigration)
             .into_result()?;
@@ -35,7 +37,7 @@ impl IntoResult<()> for Result<ExitStatus, VoltaError> {
                 if status.success() {
                     Ok(())
                 } else {
-                    let code = status.code().unwrap_or(1);
+                    let code = status.code().unwrap_or_else(|| signal_status(status));
                     Err(Error::Tool(code))
                 }
             }
@@ -43,3 +45,21 @@ impl IntoResult<()> for Result<ExitStatus, VoltaError> {
         }
     }
 }
+
+/// Returns the status to exit with for a tool that was terminated by a signal, and so has no
+/// exit code of its own
+#[cfg(unix)]
+fn signal_status(status: ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+
+    let policy = exit_policy();
+    match status.signal() {
+        Some(signal) => policy.signal_status(signal),
+        None => policy.status(ExitCode::UnknownError),
+    }
+}
+
+#[cfg(windows)]
+fn signal_status(_status: ExitStatus) -> i32 {
+    exit_policy().status(ExitCode::UnknownError)
+}
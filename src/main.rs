@@ -4,9 +4,14 @@ mod cli;
 
 use structopt::StructOpt;
 
+use volta_core::deprecation;
 use volta_core::error::report_error;
 use volta_core::log::{LogContext, LogVerbosity, Logger};
 use volta_core::session::{ActivityKind, Session};
+use volta_core::settings::{enable_backtraces, exit_policy};
+use volta_core::signal::setup_signal_handler;
+use volta_core::style::set_color_choice;
+use volta_core::warn_deprecated;
 
 mod common;
 use common::{ensure_layout, Error};
@@ -14,7 +19,9 @@ use common::{ensure_layout, Error};
 /// The entry point for the `volta` CLI.
 pub fn main() {
     let volta = cli::Volta::from_args();
-    let verbosity = match (&volta.verbose, &volta.quiet) {
+    set_color_choice(volta.color);
+    let quiet = volta.quiet || volta.silent;
+    let verbosity = match (&volta.verbose, &quiet) {
         (false, false) => LogVerbosity::Default,
         (true, false) => LogVerbosity::Verbose,
         (false, true) => LogVerbosity::Quiet,
@@ -23,11 +30,25 @@ pub fn main() {
         ),
     };
     Logger::init(LogContext::Volta, verbosity).expect("Only a single logger should be initialized");
+    if volta.no_warnings {
+        deprecation::suppress();
+    }
+    if volta.silent {
+        warn_deprecated!("the `--silent` flag is deprecated and will be removed in a future version. Please use `--quiet` instead.");
+    }
+    enable_backtraces();
+    setup_signal_handler();
+
+    // A prompt is printed after every shell command, so it skips the layout check, the session,
+    // and the metrics and events that are recorded when a session exits
+    if let Some(cli::Subcommand::Prompt(prompt)) = &volta.command {
+        exit_policy().exit(prompt.print());
+    }
 
     let mut session = Session::init();
     session.add_event_start(ActivityKind::Volta);
 
-    let result = ensure_layout().and_then(|()| volta.run(&mut session).map_err(Error::Volta));
+    let result = ensure_layout().and_then(|()| volta.run(&mut session));
     match result {
         Ok(exit_code) => {
             session.add_event_end(ActivityKind::Volta, exit_code);
@@ -1,9 +1,11 @@
 use volta_core::error::report_error;
 use volta_core::log::{LogContext, LogVerbosity, Logger};
+use volta_core::style::{set_color_choice, ColorChoice};
 use volta_fail::ExitCode;
 use volta_migrate::run_migration;
 
 pub fn main() {
+    set_color_choice(ColorChoice::Auto);
     Logger::init(LogContext::Migration, LogVerbosity::Default)
         .expect("Only a single Logger should be initialized");
 
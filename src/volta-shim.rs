@@ -6,9 +6,12 @@ use volta_core::log::{LogContext, LogVerbosity, Logger};
 use volta_core::run::execute_tool;
 use volta_core::session::{ActivityKind, Session};
 use volta_core::signal::setup_signal_handler;
+use volta_core::style::{set_color_choice, ColorChoice};
+use volta_core::trace;
 use volta_fail::ExitCode;
 
 pub fn main() {
+    set_color_choice(ColorChoice::Auto);
     Logger::init(LogContext::Shim, LogVerbosity::Default)
         .expect("Only a single Logger should be initialized");
     setup_signal_handler();
@@ -16,7 +19,12 @@ pub fn main() {
     let mut session = Session::init();
     session.add_event_start(ActivityKind::Tool);
 
-    let result = ensure_layout().and_then(|()| execute_tool(&mut session).into_result());
+    let result = {
+        let _phase = trace::phase("shim");
+        ensure_layout().and_then(|()| execute_tool(&mut session).into_result())
+    };
+    trace::report();
+
     match result {
         Ok(()) => {
             session.add_event_end(ActivityKind::Tool, ExitCode::Success);
@@ -0,0 +1,71 @@
+use crate::support::sandbox::{sandbox, DistroMetadata, NodeFixture};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON: &str = r#"{
+    "name": "pinned-node",
+    "volta": {
+        "node": "10.99.1040"
+    }
+}"#;
+
+const NODE_VERSION_INFO: &str = r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]}
+]
+"#;
+
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "windows")] {
+        const NODE_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+            version: "10.99.1040",
+            compressed_size: 1096,
+            uncompressed_size: None,
+        }];
+    } else {
+        const NODE_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+            version: "10.99.1040",
+            compressed_size: 273,
+            uncompressed_size: Some(0x0028_0000),
+        }];
+    }
+}
+
+#[test]
+fn installs_missing_project_node() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_LOGLEVEL", "info")
+        .build();
+
+    assert_that!(
+        s.npm("--version"),
+        execs().with_stdout_contains("Installing node@10.99.1040, which isn't installed yet")
+    );
+
+    assert!(s.node_inventory_archive_exists("10.99.1040"));
+}
+
+#[test]
+fn reports_missing_project_node_without_autoinstall() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_AUTOINSTALL", "0")
+        .build();
+
+    assert_that!(
+        s.npm("--version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]node@10.99.1040 is not installed.")
+            .with_stderr_contains("[..]volta fetch --from-project[..]")
+    );
+
+    assert!(!s.node_inventory_archive_exists("10.99.1040"));
+}
@@ -0,0 +1,147 @@
+use crate::support::sandbox::{sandbox, DistroMetadata, Sandbox, YarnFixture};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const TOOL_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+    version: "1.4.159",
+    compressed_size: 177,
+    uncompressed_size: Some(0x0028_0000),
+}];
+
+const TOOL_INDEX: &str = r#"[
+    { "tag_name": "v1.5.0-beta.1" },
+    { "tag_name": "v1.4.159" },
+    { "tag_name": "v0.9.0" },
+    { "tag_name": "nightly" }
+]"#;
+
+const TSC_BIN_CONFIG: &str = r#"{
+  "name": "tsc",
+  "package": "typescript",
+  "version": "3.9.7",
+  "path": "./bin/tsc",
+  "platform": {
+    "node": {
+      "runtime": "12.18.3",
+      "npm": "6.14.6"
+    },
+    "yarn": null
+  }
+}"#;
+
+/// Hooks for a custom tool named `mytool`, whose archives are served as the Yarn fixtures
+fn tool_hooks(bins: &str) -> String {
+    format!(
+        r#"{{
+  "tools": {{
+    "mytool": {{
+      "distro": {{
+        "template": "{url}/v{{{{version}}}}/yarn-v{{{{version}}}}.tar.gz"
+      }},
+      "index": {{
+        "template": "{url}/mytool/releases",
+        "format": "github"
+      }},
+      "bins": {bins}
+    }}
+  }}
+}}"#,
+        url = mockito::SERVER_URL,
+        bins = bins
+    )
+}
+
+#[test]
+fn fetch_exact_version() {
+    let s = sandbox()
+        .default_hooks(&tool_hooks(r#"["mytool"]"#))
+        .distro_mocks::<YarnFixture>(&TOOL_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch mytool@1.4.159"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]fetched mytool@1.4.159")
+    );
+
+    assert!(Sandbox::custom_image_exists("mytool", "1.4.159"));
+}
+
+#[test]
+fn fetch_resolves_requirement_from_index() {
+    let s = sandbox()
+        .default_hooks(&tool_hooks(r#"["mytool"]"#))
+        .custom_tool_index("/mytool/releases", TOOL_INDEX)
+        .distro_mocks::<YarnFixture>(&TOOL_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch mytool@^1"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]fetched mytool@1.4.159")
+    );
+}
+
+#[test]
+fn fetch_reports_requirement_missing_from_index() {
+    let s = sandbox()
+        .default_hooks(&tool_hooks(r#"["mytool"]"#))
+        .custom_tool_index("/mytool/releases", TOOL_INDEX)
+        .build();
+
+    assert_that!(
+        s.volta("fetch mytool@^2"),
+        execs()
+            .with_status(ExitCode::NoVersionMatch as i32)
+            .with_stderr_contains("[..]mytool[..]")
+    );
+}
+
+#[test]
+fn rejects_bin_outside_of_image() {
+    let s = sandbox()
+        .default_hooks(&tool_hooks(r#"["../../bin/mytool"]"#))
+        .build();
+
+    assert_that!(
+        s.volta("fetch mytool@1.4.159"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Invalid name '../../bin/mytool'[..]")
+    );
+}
+
+#[test]
+fn rejects_bin_of_builtin_tool() {
+    let s = sandbox().default_hooks(&tool_hooks(r#"["node"]"#)).build();
+
+    assert_that!(
+        s.volta("fetch mytool@1.4.159"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Cannot use 'node' as an executable[..]")
+    );
+}
+
+#[test]
+fn install_rejects_bin_of_installed_package() {
+    let s = sandbox()
+        .default_hooks(&tool_hooks(r#"["mytool", "tsc"]"#))
+        .binary_config("tsc", TSC_BIN_CONFIG)
+        .distro_mocks::<YarnFixture>(&TOOL_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("install mytool@1.4.159"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Cannot use 'tsc' as an executable[..]")
+            .with_stderr_contains("[..]the package 'typescript'[..]")
+    );
+    assert!(!Sandbox::custom_image_exists("mytool", "1.4.159"));
+}
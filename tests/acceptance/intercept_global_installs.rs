@@ -1,4 +1,4 @@
-use crate::support::sandbox::sandbox;
+use crate::support::sandbox::{sandbox, Sandbox};
 use hamcrest2::assert_that;
 use hamcrest2::prelude::*;
 use test_support::matchers::execs;
@@ -13,54 +13,128 @@ const PACKAGE_JSON: &str = r#"{
     }
 }"#;
 
+const PKG_CONFIG: &str = r#"{
+  "name": "cowsay",
+  "version": "1.4.0",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  },
+  "bins": [
+    "cowsay"
+  ]
+}"#;
+
+const BIN_CONFIG: &str = r#"{
+  "name": "cowsay",
+  "package": "cowsay",
+  "version": "1.4.0",
+  "path": "./cli.js",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  }
+}"#;
+
 #[test]
-fn npm_prevents_global_install() {
-    let s = sandbox().package_json(PACKAGE_JSON).build();
+fn npm_installs_global_packages_with_volta() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
 
     assert_that!(
         s.npm("install ember-cli --global"),
         execs()
-            .with_status(ExitCode::ExecutionFailure as i32)
-            .with_stderr_contains("[..]Global package installs are not supported.")
+            .with_stderr_does_not_contain("[..]Global package installs are not supported.")
+            .with_stderr_contains("[..]installing ember-cli[..] with Volta")
     );
 
     assert_that!(
         s.npm("i ember-cli --global"),
         execs()
-            .with_status(ExitCode::ExecutionFailure as i32)
-            .with_stderr_contains("[..]Global package installs are not supported.")
+            .with_stderr_does_not_contain("[..]Global package installs are not supported.")
+            .with_stderr_contains("[..]installing ember-cli[..] with Volta")
     );
 
     assert_that!(
         s.npm("install ember-cli -g"),
         execs()
-            .with_status(ExitCode::ExecutionFailure as i32)
-            .with_stderr_contains("[..]Global package installs are not supported.")
+            .with_stderr_does_not_contain("[..]Global package installs are not supported.")
+            .with_stderr_contains("[..]installing ember-cli[..] with Volta")
     );
 
     assert_that!(
         s.npm("i -g ember-cli"),
         execs()
-            .with_status(ExitCode::ExecutionFailure as i32)
-            .with_stderr_contains("[..]Global package installs are not supported.")
+            .with_stderr_does_not_contain("[..]Global package installs are not supported.")
+            .with_stderr_contains("[..]installing ember-cli[..] with Volta")
     );
 
     assert_that!(
         s.npm("-g i ember-cli"),
         execs()
-            .with_status(ExitCode::ExecutionFailure as i32)
-            .with_stderr_contains("[..]Global package installs are not supported.")
+            .with_stderr_does_not_contain("[..]Global package installs are not supported.")
+            .with_stderr_contains("[..]installing ember-cli[..] with Volta")
     );
 
     assert_that!(
         s.npm("add ember-cli --global"),
         execs()
-            .with_status(ExitCode::ExecutionFailure as i32)
-            .with_stderr_contains("[..]Global package installs are not supported.")
+            .with_stderr_does_not_contain("[..]Global package installs are not supported.")
+            .with_stderr_contains("[..]installing ember-cli[..] with Volta")
     );
 
     assert_that!(
         s.npm("isntall --global ember-cli"),
+        execs()
+            .with_stderr_does_not_contain("[..]Global package installs are not supported.")
+            .with_stderr_contains("[..]installing ember-cli[..] with Volta")
+    );
+}
+
+#[test]
+fn npm_installs_every_global_package_with_volta() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.npm("i -g ember-cli typescript@3"),
+        execs()
+            .with_stderr_contains("[..]installing ember-cli[..] with Volta")
+            .with_stderr_contains("[..]installing typescript[..] with Volta")
+    );
+}
+
+#[test]
+fn npm_global_install_skips_flag_values() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.npm("i -g --registry https://npm.example.com/ ember-cli"),
+        execs()
+            .with_stderr_contains("[..]installing ember-cli[..] with Volta")
+            .with_stderr_does_not_contain("[..]installing https[..]")
+    );
+}
+
+#[test]
+fn npm_prevents_global_install_without_packages() {
+    let s = sandbox().package_json(PACKAGE_JSON).build();
+
+    assert_that!(
+        s.npm("install --global"),
         execs()
             .with_status(ExitCode::ExecutionFailure as i32)
             .with_stderr_contains("[..]Global package installs are not supported.")
@@ -85,28 +159,65 @@ fn npm_allows_global_install_with_env_variable() {
 }
 
 #[test]
-fn yarn_prevents_global_add() {
-    let s = sandbox().package_json(PACKAGE_JSON).build();
+fn yarn_installs_global_packages_with_volta() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
 
     assert_that!(
         s.yarn("global add ember-cli"),
         execs()
-            .with_status(ExitCode::ExecutionFailure as i32)
-            .with_stderr_contains("[..]Global package installs are not supported.")
+            .with_stderr_does_not_contain("[..]Global package installs are not supported.")
+            .with_stderr_contains("[..]installing ember-cli[..] with Volta")
     );
 
     assert_that!(
         s.yarn("--verbose global add ember-cli"),
         execs()
-            .with_status(ExitCode::ExecutionFailure as i32)
-            .with_stderr_contains("[..]Global package installs are not supported.")
+            .with_stderr_does_not_contain("[..]Global package installs are not supported.")
+            .with_stderr_contains("[..]installing ember-cli[..] with Volta")
     );
 
     assert_that!(
         s.yarn("global --verbose add ember-cli"),
         execs()
-            .with_status(ExitCode::ExecutionFailure as i32)
-            .with_stderr_contains("[..]Global package installs are not supported.")
+            .with_stderr_does_not_contain("[..]Global package installs are not supported.")
+            .with_stderr_contains("[..]installing ember-cli[..] with Volta")
+    );
+}
+
+#[test]
+fn yarn_uninstalls_global_packages_with_volta() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .package_config("cowsay", PKG_CONFIG)
+        .binary_config("cowsay", BIN_CONFIG)
+        .shim("cowsay")
+        .package_image("cowsay", "1.4.0")
+        .build();
+
+    assert_that!(s.yarn("global remove cowsay"), execs().with_status(0));
+
+    assert!(!Sandbox::package_config_exists("cowsay"));
+    assert!(!Sandbox::bin_config_exists("cowsay"));
+    assert!(!Sandbox::shim_exists("cowsay"));
+    assert!(!Sandbox::package_image_exists("cowsay", "1.4.0"));
+}
+
+#[test]
+fn yarn_lists_global_packages_from_volta() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .package_config("cowsay", PKG_CONFIG)
+        .build();
+
+    assert_that!(
+        s.yarn("global list"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("cowsay@1.4.0")
+            .with_stdout_contains("  - cowsay")
     );
 }
 
@@ -126,3 +237,44 @@ fn yarn_allows_global_add_with_env_variable() {
             .with_stderr_contains("[..]Could not download node@[..]")
     );
 }
+
+#[test]
+fn npm_links_current_package_with_volta() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.npm("link"),
+        execs().with_stderr_contains("[..]installing .[..] with Volta")
+    );
+
+    assert_that!(
+        s.npm("ln"),
+        execs().with_stderr_contains("[..]installing .[..] with Volta")
+    );
+
+    assert_that!(
+        s.npm("link cowsay"),
+        execs().with_stderr_does_not_contain("[..]with Volta")
+    );
+}
+
+#[test]
+fn yarn_links_current_package_with_volta() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.yarn("link"),
+        execs().with_stderr_contains("[..]installing .[..] with Volta")
+    );
+
+    assert_that!(
+        s.yarn("link cowsay"),
+        execs().with_stderr_does_not_contain("[..]with Volta")
+    );
+}
@@ -5,14 +5,38 @@ cfg_if! {
         mod support;
 
         // test files
+        mod auto_install;
         mod corrupted_download;
+        mod custom_tools;
         mod intercept_global_installs;
         mod merged_platform;
         mod migrations;
+        mod node_index_cache;
+        mod path_handling;
+        mod read_only_mode;
         mod run_shim_directly;
+        mod strict_mode;
         mod verbose_errors;
+        mod volta_audit_toolchain;
         mod volta_bypass;
+        mod volta_check;
+        mod volta_ci_setup;
+        mod volta_complete;
+        mod volta_doctor;
+        mod volta_fetch;
+        mod volta_history;
+        mod volta_install;
+        mod volta_lock;
+        mod volta_outdated;
         mod volta_pin;
+        mod volta_prompt;
+        mod volta_relocate;
+        mod volta_self_update;
+        mod volta_sign;
+        mod volta_status;
         mod volta_uninstall;
+        mod volta_upgrade;
+        mod volta_verify;
+        mod volta_which;
     }
 }
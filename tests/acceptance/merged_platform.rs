@@ -114,3 +114,30 @@ fn throws_default_error_outside_project() {
             .with_stderr_contains("[..]Yarn is not available.")
     );
 }
+
+#[test]
+fn delegates_to_system_yarn_with_passthrough() {
+    let s = sandbox()
+        .platform(PLATFORM_NO_YARN)
+        .package_json(PACKAGE_JSON_NO_YARN)
+        .env("VOLTA_PASSTHROUGH", "1")
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.yarn("--version"),
+        execs().with_stderr_contains("[..]Could not find Volta-managed yarn, delegating to system")
+    );
+}
+
+#[test]
+fn delegates_to_system_npm_without_platform() {
+    // npm comes with Node, so it only needs to fall through when there is no platform at all,
+    // which it does whether or not passthrough is enabled
+    let s = sandbox().env("VOLTA_LOGLEVEL", "debug").build();
+
+    assert_that!(
+        s.npm("--version"),
+        execs().with_stderr_contains("[..]Could not find Volta-managed npm, delegating to system")
+    );
+}
@@ -31,7 +31,7 @@ fn empty_volta_home_is_created() {
     assert!(Sandbox::path_exists(".volta/tools/user"));
 
     // Layout file should now exist
-    assert!(Sandbox::path_exists(".volta/layout.v1"));
+    assert!(Sandbox::path_exists(".volta/layout.v2"));
 
     // shims should all be created
     // NOTE: this doesn't work in Windows, because the default shims are stored separately
@@ -58,6 +58,7 @@ fn legacy_v0_volta_home_is_upgraded() {
 
     // Layout file is not there
     assert!(!Sandbox::path_exists(".volta/layout.v1"));
+    assert!(!Sandbox::path_exists(".volta/layout.v2"));
 
     // running volta should not create anything else
     assert_that!(s.volta("--version"), execs().with_status(0));
@@ -71,7 +72,7 @@ fn legacy_v0_volta_home_is_upgraded() {
     assert!(Sandbox::path_exists(".volta/tools/inventory/yarn"));
 
     // Layout file should now exist
-    assert!(Sandbox::path_exists(".volta/layout.v1"));
+    assert!(Sandbox::path_exists(".volta/layout.v2"));
 
     // shims should all be created
     // NOTE: this doesn't work in Windows, because the shim directory
@@ -85,13 +86,55 @@ fn legacy_v0_volta_home_is_upgraded() {
     }
 }
 
+const PKG_CONFIG_COWSAY: &str = r#"{
+  "name": "cowsay",
+  "version": "1.4.0",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  },
+  "bins": [
+    "cowsay"
+  ]
+}"#;
+
 #[test]
-fn current_v1_volta_home_is_unchanged() {
-    let s = sandbox().layout_file("v1").build();
+fn v1_volta_home_is_upgraded() {
+    let s = sandbox()
+        .layout_file("v1")
+        .package_config("cowsay", PKG_CONFIG_COWSAY)
+        .package_image("cowsay", "1.4.0")
+        .build();
+
+    assert!(Sandbox::path_exists(".volta/layout.v1"));
+    assert!(!Sandbox::package_platform_image_exists(
+        "cowsay", "1.4.0", "11.10.1"
+    ));
+
+    assert_that!(s.volta("--version"), execs().with_status(0));
+
+    // the installed image is copied to the image for its Node version
+    assert!(Sandbox::package_platform_image_exists(
+        "cowsay", "1.4.0", "11.10.1"
+    ));
+    // and the original is kept as the fetched package
+    assert!(Sandbox::package_image_exists("cowsay", "1.4.0"));
+
+    // the layout file is replaced
+    assert!(!Sandbox::path_exists(".volta/layout.v1"));
+    assert!(Sandbox::path_exists(".volta/layout.v2"));
+}
+
+#[test]
+fn current_v2_volta_home_is_unchanged() {
+    let s = sandbox().layout_file("v2").build();
 
     // directories that are already created by the test framework
     assert!(Sandbox::path_exists(".volta"));
-    assert!(Sandbox::path_exists(".volta/layout.v1"));
+    assert!(Sandbox::path_exists(".volta/layout.v2"));
     assert!(Sandbox::path_exists(".volta/cache/node"));
     assert!(Sandbox::path_exists(".volta/tmp"));
     assert!(Sandbox::path_exists(".volta/tools/inventory/node"));
@@ -103,7 +146,7 @@ fn current_v1_volta_home_is_unchanged() {
 
     // everything should be the same as before running the command
     assert!(Sandbox::path_exists(".volta"));
-    assert!(Sandbox::path_exists(".volta/layout.v1"));
+    assert!(Sandbox::path_exists(".volta/layout.v2"));
     assert!(Sandbox::path_exists(".volta/cache/node"));
     assert!(Sandbox::path_exists(".volta/tmp"));
     assert!(Sandbox::path_exists(".volta/tools/inventory/node"));
@@ -0,0 +1,69 @@
+use std::time::{Duration, SystemTime};
+
+use crate::support::fixtures::{NODE_VERSION_FIXTURES, NODE_VERSION_INFO};
+use crate::support::sandbox::{sandbox, NodeFixture};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const ETAG: &str = r#""5fa1-index""#;
+
+#[test]
+fn fetch_saves_index_validators() {
+    let s = sandbox()
+        .node_available_versions_with_etag(NODE_VERSION_INFO, ETAG)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch node 6"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    let validators = s.read_node_index_validators();
+    assert!(validators.contains(r#""etag":"\"5fa1-index\"""#));
+    assert!(validators.contains("/node-dist/index.json"));
+}
+
+#[test]
+fn expired_index_is_revalidated_with_etag() {
+    let s = sandbox()
+        .node_cache(NODE_VERSION_INFO, true)
+        .node_index_validators(ETAG)
+        .node_index_not_modified(ETAG)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.volta("fetch node 6"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]fetched node@6.19.62 (with npm@3.10.1066)")
+            .with_stderr_contains("[..]Cached Node index has not been modified")
+    );
+
+    // The index was still current, so only its expiry was renewed
+    assert!(s.node_index_expiry() > SystemTime::now());
+}
+
+#[test]
+fn node_index_max_age_overrides_server() {
+    let s = sandbox()
+        .settings(r#"{ "nodeIndexMaxAge": 60 }"#)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch node 6"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    // Without the setting, the index would be cached for the default of four hours
+    let expiry = s.node_index_expiry();
+    assert!(expiry > SystemTime::now());
+    assert!(expiry < SystemTime::now() + Duration::from_secs(120));
+}
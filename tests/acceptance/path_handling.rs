@@ -0,0 +1,180 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::support::fixtures::{NODE_VERSION_FIXTURES, NODE_VERSION_INFO, YARN_VERSION_FIXTURES};
+use crate::support::sandbox::{
+    sandbox, sandbox_in, DistroMetadata, NodeFixture, PnpmFixture, YarnFixture,
+};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+use test_support::paths;
+
+use volta_core::tool::Node;
+use volta_fail::ExitCode;
+
+const PROJECT_DIR: &str = "my projects/café über";
+
+const PACKAGE_JSON_WITH_NODE_YARN: &str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "6.19.62",
+    "yarn": "1.4.159"
+  }
+}"#;
+
+const BASIC_PACKAGE_JSON: &str = r#"{
+  "name": "test-package"
+}"#;
+
+const PNPM_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+    version: "8.15.9",
+    compressed_size: 24,
+    uncompressed_size: None,
+}];
+
+/// A Volta home outside of the sandbox, whose path contains spaces and non-ASCII characters
+fn unusual_volta_home() -> PathBuf {
+    let home = paths::root().join("volta home ünïcödé");
+    // The staging directory is normally created by the installer
+    fs::create_dir_all(home.join("tmp")).unwrap();
+    home
+}
+
+#[test]
+fn fetch_from_project_with_unusual_path() {
+    let s = sandbox_in(PROJECT_DIR)
+        .package_json(PACKAGE_JSON_WITH_NODE_YARN)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .distro_mocks::<YarnFixture>(&YARN_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch --from-project"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]fetched node@6.19.62 (with npm@3.10.1066)")
+            .with_stdout_contains("[..]fetched yarn@1.4.159")
+    );
+
+    assert!(s.node_inventory_archive_exists("6.19.62"));
+    assert!(s.yarn_inventory_archive_exists("1.4.159"));
+}
+
+#[test]
+fn pin_node_with_unusual_path() {
+    let s = sandbox_in(PROJECT_DIR)
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("pin node@6"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert_eq!(
+        s.read_package_json(),
+        r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "6.19.62"
+  }
+}"#,
+    );
+}
+
+#[test]
+fn fetch_into_unusual_volta_home() {
+    let home = unusual_volta_home();
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_HOME", &home.to_string_lossy())
+        .build();
+
+    assert_that!(
+        s.volta("fetch node@6.19.62"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]fetched node@6.19.62 (with npm@3.10.1066)")
+    );
+
+    assert!(home
+        .join("tools")
+        .join("inventory")
+        .join("node")
+        .join(Node::archive_filename("6.19.62"))
+        .exists());
+    assert!(home
+        .join("tools")
+        .join("image")
+        .join("node")
+        .join("6.19.62")
+        .exists());
+}
+
+#[test]
+fn install_shim_into_unusual_volta_home() {
+    let home = unusual_volta_home();
+    let s = sandbox()
+        .distro_mocks::<PnpmFixture>(&PNPM_VERSION_FIXTURES)
+        .env("VOLTA_HOME", &home.to_string_lossy())
+        .build();
+
+    assert_that!(
+        s.volta("install pnpm@8.15.9"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    let shim_dir = home.join("bin");
+    assert!(shim_dir
+        .join(format!("pnpm{}", env::consts::EXE_SUFFIX))
+        .exists());
+
+    // Git Bash runs the shim through a script, which has to quote the path of the shim
+    #[cfg(windows)]
+    assert_eq!(
+        fs::read_to_string(shim_dir.join("pnpm")).unwrap(),
+        r#"cmd //C "$0" "$@""#
+    );
+}
+
+// `volta setup` changes the user PATH in the registry on Windows, so it is only run on Unix
+#[cfg(unix)]
+#[test]
+fn setup_writes_profile_for_unusual_volta_home() {
+    use std::process::Command;
+
+    let home = unusual_volta_home();
+    let user_home = paths::home();
+    fs::create_dir_all(&user_home).unwrap();
+    let profile = user_home.join(".bashrc");
+    fs::write(&profile, "# existing profile\n").unwrap();
+
+    let s = sandbox()
+        .env("HOME", &user_home.to_string_lossy())
+        .env("VOLTA_HOME", &home.to_string_lossy())
+        .build();
+
+    assert_that!(
+        s.volta("setup"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    // Sourcing the profile sets the Volta home exactly, spaces and all
+    let output = Command::new("bash")
+        .arg("-c")
+        .arg(r#". "$0" && printf '%s' "$VOLTA_HOME""#)
+        .arg(&profile)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        home.to_string_lossy()
+    );
+}
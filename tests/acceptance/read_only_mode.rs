@@ -0,0 +1,63 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const BASIC_PACKAGE_JSON: &str = r#"{
+  "name": "test-package"
+}"#;
+
+const NODE_VERSION_INFO: &str = r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]}
+]
+"#;
+
+#[test]
+fn read_only_mode_refuses_install() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .env("VOLTA_READ_ONLY", "1")
+        .build();
+
+    assert_that!(
+        s.volta("install node@10"),
+        execs()
+            .with_status(ExitCode::ReadOnlyError as i32)
+            .with_stderr_contains(
+                "[..]Could not change the installed tools because read-only mode is enabled."
+            )
+    );
+}
+
+#[test]
+fn read_only_mode_refuses_pin() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .env("VOLTA_READ_ONLY", "1")
+        .build();
+
+    assert_that!(
+        s.volta("pin node@10"),
+        execs().with_status(ExitCode::ReadOnlyError as i32)
+    );
+
+    assert_eq!(s.read_package_json(), BASIC_PACKAGE_JSON);
+}
+
+#[test]
+fn project_settings_cannot_turn_off_read_only_mode() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .settings(r#"{ "readOnly": true }"#)
+        .project_file(".volta/settings.json", r#"{ "readOnly": false }"#)
+        .node_available_versions(NODE_VERSION_INFO)
+        .build();
+
+    assert_that!(
+        s.volta("install node@10"),
+        execs().with_status(ExitCode::ReadOnlyError as i32)
+    );
+}
@@ -0,0 +1,65 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON_UNPINNED: &str = r#"{
+    "name": "unpinned"
+}"#;
+
+const PLATFORM: &str = r#"{
+    "node":{
+        "runtime":"9.11.2",
+        "npm":"5.6.0"
+    },
+    "yarn": "1.22.300"
+}"#;
+
+#[test]
+fn strict_setting_rejects_unpinned_project() {
+    let s = sandbox()
+        .platform(PLATFORM)
+        .package_json(PACKAGE_JSON_UNPINNED)
+        .env("VOLTA_STRICT", "1")
+        .build();
+
+    assert_that!(
+        s.npm("--version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]No Node version found in this project.")
+    );
+}
+
+#[test]
+fn run_strict_rejects_unpinned_project() {
+    let s = sandbox()
+        .platform(PLATFORM)
+        .package_json(PACKAGE_JSON_UNPINNED)
+        .build();
+
+    assert_that!(
+        s.volta("run --strict node --version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]No Node version found in this project.")
+    );
+}
+
+#[test]
+fn strict_setting_uses_default_outside_project() {
+    let s = sandbox()
+        .platform(PLATFORM)
+        .env("VOLTA_STRICT", "1")
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.yarn("--version"),
+        execs()
+            .with_stderr_does_not_contain("[..]No Node version found in this project.")
+            .with_stderr_contains("[..]Using yarn@1.22.300 from default configuration")
+    );
+}
@@ -0,0 +1,35 @@
+//! The index and distros of the Node and Yarn versions that most tests fetch, shared so that
+//! their sizes only have to be updated in one place when the fixtures change.
+
+use crate::support::sandbox::DistroMetadata;
+
+/// A Node index that only lists Node 6.19.62
+pub const NODE_VERSION_INFO: &str = r#"[
+{"version":"v6.19.62","npm":"3.10.1066","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]}
+]
+"#;
+
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "windows")] {
+        /// The distro of Node 6.19.62
+        pub const NODE_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+            version: "6.19.62",
+            compressed_size: 1056,
+            uncompressed_size: None,
+        }];
+    } else {
+        /// The distro of Node 6.19.62
+        pub const NODE_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+            version: "6.19.62",
+            compressed_size: 273,
+            uncompressed_size: Some(0x0028_0000),
+        }];
+    }
+}
+
+/// The distro of Yarn 1.4.159
+pub const YARN_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+    version: "1.4.159",
+    compressed_size: 177,
+    uncompressed_size: Some(0x0028_0000),
+}];
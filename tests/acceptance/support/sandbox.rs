@@ -5,11 +5,12 @@ use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
+use cfg_if::cfg_if;
 use mockito::{self, mock, Matcher};
 use reqwest::hyper_011::header::HttpDate;
 use test_support::{self, ok_or_panic, paths, paths::PathExt, process::ProcessBuilder};
 use volta_core::fs::symlink_file;
-use volta_core::tool::{Node, Yarn, NODE_DISTRO_ARCH, NODE_DISTRO_EXTENSION, NODE_DISTRO_OS};
+use volta_core::tool::{Node, Pnpm, Yarn, NODE_DISTRO_ARCH, NODE_DISTRO_EXTENSION, NODE_DISTRO_OS};
 
 // version cache for node and yarn
 #[derive(PartialEq, Clone)]
@@ -135,6 +136,14 @@ pub struct YarnFixture {
     pub metadata: DistroMetadata,
 }
 
+pub struct NpmFixture {
+    pub metadata: DistroMetadata,
+}
+
+pub struct PnpmFixture {
+    pub metadata: DistroMetadata,
+}
+
 impl From<DistroMetadata> for NodeFixture {
     fn from(metadata: DistroMetadata) -> Self {
         Self { metadata }
@@ -147,6 +156,18 @@ impl From<DistroMetadata> for YarnFixture {
     }
 }
 
+impl From<DistroMetadata> for NpmFixture {
+    fn from(metadata: DistroMetadata) -> Self {
+        Self { metadata }
+    }
+}
+
+impl From<DistroMetadata> for PnpmFixture {
+    fn from(metadata: DistroMetadata) -> Self {
+        Self { metadata }
+    }
+}
+
 impl DistroFixture for NodeFixture {
     fn server_path(&self) -> String {
         let version = &self.metadata.version;
@@ -184,6 +205,39 @@ impl DistroFixture for YarnFixture {
     }
 }
 
+impl DistroFixture for NpmFixture {
+    fn server_path(&self) -> String {
+        format!("/npm/npm-{}.tgz", self.metadata.version)
+    }
+
+    fn fixture_path(&self) -> String {
+        format!("tests/fixtures/npm-{}.tgz", self.metadata.version)
+    }
+
+    fn metadata(&self) -> &DistroMetadata {
+        &self.metadata
+    }
+}
+
+impl DistroFixture for PnpmFixture {
+    fn server_path(&self) -> String {
+        format!(
+            "/pnpm/v{}/{}",
+            self.metadata.version,
+            Pnpm::distro_filename()
+        )
+    }
+
+    // The standalone executables are the same script on every platform
+    fn fixture_path(&self) -> String {
+        format!("tests/fixtures/pnpm-v{}", self.metadata.version)
+    }
+
+    fn metadata(&self) -> &DistroMetadata {
+        &self.metadata
+    }
+}
+
 impl SandboxBuilder {
     /// Root of the project, ex: `/path/to/cargo/target/integration_test/t0/foo`
     pub fn root(&self) -> PathBuf {
@@ -224,6 +278,13 @@ impl SandboxBuilder {
         self
     }
 
+    /// Add a file to the project, relative to the project root (chainable)
+    pub fn project_file(mut self, path: &str, contents: &str) -> Self {
+        let file = self.root().join(path);
+        self.files.push(FileBuilder::new(file, contents));
+        self
+    }
+
     /// Set the platform.json for the sandbox (chainable)
     pub fn platform(mut self, contents: &str) -> Self {
         self.files
@@ -231,6 +292,20 @@ impl SandboxBuilder {
         self
     }
 
+    /// Set the user settings.json for the sandbox (chainable)
+    pub fn settings(mut self, contents: &str) -> Self {
+        self.files
+            .push(FileBuilder::new(default_settings_file(), contents));
+        self
+    }
+
+    /// Set the user hooks.json for the sandbox (chainable)
+    pub fn default_hooks(mut self, contents: &str) -> Self {
+        self.files
+            .push(FileBuilder::new(default_hooks_file(), contents));
+        self
+    }
+
     /// Set a layout version file for the sandbox (chainable)
     pub fn layout_file(mut self, version: &str) -> Self {
         self.files.push(FileBuilder::new(layout_file(version), ""));
@@ -254,6 +329,43 @@ impl SandboxBuilder {
         self
     }
 
+    /// Setup mock to return the available node versions with an ETag (chainable)
+    pub fn node_available_versions_with_etag(mut self, body: &str, etag: &str) -> Self {
+        let mock = mock("GET", "/node-dist/index.json")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_header("etag", etag)
+            .with_body(body)
+            .create();
+        self.root.mocks.push(mock);
+
+        self
+    }
+
+    /// Setup mock to report that the node index with the given ETag is unchanged (chainable)
+    pub fn node_index_not_modified(mut self, etag: &str) -> Self {
+        let mock = mock("GET", "/node-dist/index.json")
+            .match_header("if-none-match", etag)
+            .with_status(304)
+            .create();
+        self.root.mocks.push(mock);
+
+        self
+    }
+
+    /// Set the HTTP validators of the Node cache for the mock index (chainable)
+    pub fn node_index_validators(mut self, etag: &str) -> Self {
+        let validators = serde_json::json!({
+            "url": format!("{}/node-dist/index.json", mockito::SERVER_URL),
+            "etag": etag,
+        });
+        self.files.push(FileBuilder::new(
+            node_index_validators_file(),
+            &validators.to_string(),
+        ));
+        self
+    }
+
     /// Setup mock to return the available yarn versions (chainable)
     pub fn yarn_available_versions(mut self, body: &str) -> Self {
         let mock = mock("GET", "/yarn-releases/index.json")
@@ -275,6 +387,17 @@ impl SandboxBuilder {
         self
     }
 
+    /// Setup mock to return the available pnpm versions (chainable)
+    pub fn pnpm_available_versions(mut self, body: &str) -> Self {
+        let mock = mock("GET", "/pnpm/index.json")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create();
+        self.root.mocks.push(mock);
+        self
+    }
+
     /// Setup mock to return the available npm versions (chainable)
     pub fn npm_available_versions(mut self, body: &str) -> Self {
         let mock = mock("GET", "/registry/npm")
@@ -287,6 +410,70 @@ impl SandboxBuilder {
         self
     }
 
+    /// Setup mock to return the index of a custom tool at `path` (chainable)
+    pub fn custom_tool_index(mut self, path: &str, body: &str) -> Self {
+        let mock = mock("GET", path)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create();
+        self.root.mocks.push(mock);
+        self
+    }
+
+    /// Setup mocks for the latest Volta release, its checksum file and its archive, which is
+    /// served from the release fixture for the current OS (chainable)
+    pub fn volta_release(mut self, version: &str, checksum: &str) -> Self {
+        let archive = volta_release_archive(version);
+        let release = format!(
+            r#"{{"tag_name":"v{version}","assets":[{{"name":"{archive}","browser_download_url":"{url}/volta-releases/{archive}"}},{{"name":"{archive}.sha1","browser_download_url":"{url}/volta-releases/{archive}.sha1"}}]}}"#,
+            version = version,
+            archive = archive,
+            url = mockito::SERVER_URL
+        );
+
+        let release_mock = mock("GET", "/volta-releases/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(&release)
+            .create();
+        let checksum_mock = mock("GET", &format!("/volta-releases/{}.sha1", archive)[..])
+            .with_status(200)
+            .with_body(&format!("{}  {}", checksum, archive))
+            .create();
+        let archive_mock = mock("GET", &format!("/volta-releases/{}", archive)[..])
+            .with_status(200)
+            .with_body_from_file(&format!("tests/fixtures/{}", archive))
+            .create();
+        self.root.mocks.push(release_mock);
+        self.root.mocks.push(checksum_mock);
+        self.root.mocks.push(archive_mock);
+        self
+    }
+
+    /// Setup mock to return the Node release schedule (chainable)
+    pub fn node_release_schedule(mut self, schedule: &str) -> Self {
+        let mock = mock("GET", "/node-release-schedule.json")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(schedule)
+            .create();
+        self.root.mocks.push(mock);
+        self
+    }
+
+    /// Setup mocks to return the Node release schedule and security advisories (chainable)
+    pub fn node_security_metadata(self, schedule: &str, vulnerabilities: &str) -> Self {
+        let mut this = self.node_release_schedule(schedule);
+        let vulnerabilities_mock = mock("GET", "/node-vulnerabilities.json")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(vulnerabilities)
+            .create();
+        this.root.mocks.push(vulnerabilities_mock);
+        this
+    }
+
     /// Setup mock to return a 404 for any GET request
     /// Note: Mocks are matched in reverse order, so any created _after_ this will work
     ///       While those created before will not
@@ -372,6 +559,29 @@ impl SandboxBuilder {
         self
     }
 
+    /// Set a package image installed with the given Node version, containing the files of its
+    /// binaries (chainable)
+    pub fn package_platform_image(
+        mut self,
+        name: &str,
+        version: &str,
+        node: &str,
+        bins: &[&str],
+    ) -> Self {
+        let package_img_dir = package_image_dir(name, &format!("{}-node-{}", version, node));
+        self.files.push(FileBuilder::new(
+            package_img_dir.join("package.json"),
+            &format!(r#"{{"name":"{}","version":"{}"}}"#, name, version),
+        ));
+        for bin in bins {
+            self.files.push(FileBuilder::new(
+                package_img_dir.join(bin),
+                "contents don't matter",
+            ));
+        }
+        self
+    }
+
     /// Set cached package tarballs for the sandbox (chainable)
     pub fn package_inventory(mut self, name: &str, version: &str) -> Self {
         let pkg_inventory_dir = package_inventory_dir();
@@ -398,6 +608,7 @@ impl SandboxBuilder {
         ok_or_panic! { fs::create_dir_all(package_inventory_dir()) };
         ok_or_panic! { fs::create_dir_all(yarn_inventory_dir()) };
         ok_or_panic! { fs::create_dir_all(volta_tmp_dir()) };
+        ok_or_panic! { fs::create_dir_all(volta_install_dir()) };
 
         // Make sure the shims to npm and yarn exist
         ok_or_panic! { symlink_file(shim_exe(), self.root.npm_exe()) };
@@ -433,6 +644,11 @@ fn home_dir() -> PathBuf {
 fn volta_home() -> PathBuf {
     home_dir().join(".volta")
 }
+/// The install directory that `volta self update` replaces the executables in, which is set
+/// with `VOLTA_INSTALL_DIR` so that the executables under test are never replaced
+pub fn volta_install_dir() -> PathBuf {
+    home_dir().join("volta-install")
+}
 fn volta_tmp_dir() -> PathBuf {
     volta_home().join("tmp")
 }
@@ -463,6 +679,9 @@ fn node_inventory_dir() -> PathBuf {
 fn yarn_inventory_dir() -> PathBuf {
     inventory_dir().join("yarn")
 }
+fn pnpm_inventory_dir() -> PathBuf {
+    inventory_dir().join("pnpm")
+}
 fn package_inventory_dir() -> PathBuf {
     inventory_dir().join("packages")
 }
@@ -476,10 +695,12 @@ fn node_cache_dir() -> PathBuf {
 fn node_index_file() -> PathBuf {
     node_cache_dir().join("index.json")
 }
-#[allow(dead_code)]
 fn node_index_expiry_file() -> PathBuf {
     node_cache_dir().join("index.json.expires")
 }
+fn node_index_validators_file() -> PathBuf {
+    node_cache_dir().join("index.json.validators")
+}
 fn package_json_file(mut root: PathBuf) -> PathBuf {
     root.push("package.json");
     root
@@ -496,6 +717,15 @@ fn shim_file(name: &str) -> PathBuf {
 fn package_image_dir(name: &str, version: &str) -> PathBuf {
     image_dir().join("packages").join(name).join(version)
 }
+fn custom_image_dir(name: &str, version: &str) -> PathBuf {
+    image_dir().join("custom").join(name).join(version)
+}
+fn default_hooks_file() -> PathBuf {
+    volta_home().join("hooks.json")
+}
+fn default_settings_file() -> PathBuf {
+    volta_home().join("settings.json")
+}
 fn default_platform_file() -> PathBuf {
     user_dir().join("platform.json")
 }
@@ -601,6 +831,29 @@ impl Sandbox {
         volta_home().rm_rf();
     }
 
+    pub fn write_platform(&self, contents: &str) {
+        fs::write(default_platform_file(), contents).expect("Could not write platform.json");
+    }
+
+    pub fn read_package_config(&self, name: &str) -> String {
+        read_file_to_string(package_config_file(name))
+    }
+
+    pub fn read_node_index_validators(&self) -> String {
+        read_file_to_string(node_index_validators_file())
+    }
+
+    pub fn node_index_expiry(&self) -> SystemTime {
+        let expiry = read_file_to_string(node_index_expiry_file());
+        let expiry: HttpDate = ok_or_panic! { expiry.parse() };
+        SystemTime::from(expiry)
+    }
+
+    pub fn remove_integrity_key(&self) {
+        fs::remove_file(volta_home().join("integrity.key"))
+            .expect("Could not remove integrity.key");
+    }
+
     // check that files in the sandbox exist
 
     pub fn node_inventory_archive_exists(&self, version: &str) -> bool {
@@ -609,12 +862,33 @@ impl Sandbox {
             .exists()
     }
 
+    pub fn node_inventory_zstd_archive_exists(&self, version: &str) -> bool {
+        node_inventory_dir()
+            .join(Node::archive_filename(version))
+            .with_extension("zst")
+            .exists()
+    }
+
+    pub fn platform_signature_exists(&self) -> bool {
+        default_platform_file().with_extension("json.sig").exists()
+    }
+
     pub fn yarn_inventory_archive_exists(&self, version: &str) -> bool {
         yarn_inventory_dir()
             .join(Yarn::archive_filename(version))
             .exists()
     }
 
+    pub fn pnpm_inventory_archive_exists(&self, version: &str) -> bool {
+        pnpm_inventory_dir()
+            .join(Pnpm::archive_filename(version))
+            .exists()
+    }
+
+    pub fn pnpm_image_exists(&self, version: &str) -> bool {
+        image_dir().join("pnpm").join(version).join("bin").is_dir()
+    }
+
     pub fn package_config_exists(name: &str) -> bool {
         package_config_file(name).exists()
     }
@@ -631,6 +905,13 @@ impl Sandbox {
         let package_img_dir = package_image_dir(name, version);
         package_img_dir.join("package.json").exists()
     }
+    pub fn package_platform_image_exists(name: &str, version: &str, node: &str) -> bool {
+        let package_img_dir = package_image_dir(name, &format!("{}-node-{}", version, node));
+        package_img_dir.join("package.json").exists()
+    }
+    pub fn custom_image_exists(name: &str, version: &str) -> bool {
+        custom_image_dir(name, version).is_dir()
+    }
     pub fn pkg_inventory_tarball_exists(name: &str, version: &str) -> bool {
         let pkg_inventory_dir = package_inventory_dir();
         pkg_inventory_dir
@@ -656,6 +937,11 @@ pub fn sandbox() -> SandboxBuilder {
     SandboxBuilder::new(paths::root().join("sandbox"))
 }
 
+// Generates a sandboxed environment with the project in the named directory
+pub fn sandbox_in(dir: &str) -> SandboxBuilder {
+    SandboxBuilder::new(paths::root().join(dir))
+}
+
 // Path to compiled executables
 pub fn cargo_dir() -> PathBuf {
     env::var_os("CARGO_BIN_PATH")
@@ -672,6 +958,21 @@ pub fn cargo_dir() -> PathBuf {
         .unwrap_or_else(|| panic!("CARGO_BIN_PATH wasn't set. Cannot continue running test"))
 }
 
+cfg_if! {
+    if #[cfg(windows)] {
+        const VOLTA_RELEASE_SUFFIX: &str = "windows.zip";
+    } else if #[cfg(target_os = "macos")] {
+        const VOLTA_RELEASE_SUFFIX: &str = "macos.tar.gz";
+    } else {
+        const VOLTA_RELEASE_SUFFIX: &str = "linux.tar.gz";
+    }
+}
+
+/// The name of the archive of a Volta release for the current OS
+pub fn volta_release_archive(version: &str) -> String {
+    format!("volta-{}-{}", version, VOLTA_RELEASE_SUFFIX)
+}
+
 fn volta_exe() -> PathBuf {
     cargo_dir().join(format!("volta{}", env::consts::EXE_SUFFIX))
 }
@@ -0,0 +1,60 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON: &str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "8.9.0"
+  }
+}"#;
+
+const NODE_VERSION_INFO: &str = r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]},
+{"version":"v8.12.1","npm":"6.4.1","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]},
+{"version":"v8.9.10","npm":"5.6.7","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]},
+{"version":"v8.9.0","npm":"5.5.1","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]}
+]
+"#;
+
+const SCHEDULE: &str = r#"{
+  "v8": { "start": "2017-05-30", "lts": "2017-10-31", "end": "2019-12-31", "codename": "Carbon" },
+  "v10": { "start": "2018-04-24", "lts": "2018-10-30", "end": "2099-04-30", "codename": "Dubnium" }
+}"#;
+
+const VULNERABILITIES: &str = r#"{
+  "80": { "cve": ["CVE-2018-12115"], "vulnerable": "<8.11.4", "patched": "^8.11.4" }
+}"#;
+
+#[test]
+fn audit_reports_vulnerable_versions() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .node_security_metadata(SCHEDULE, VULNERABILITIES)
+        .build();
+
+    assert_that!(
+        s.volta("audit-toolchain"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stdout_contains("[..]node@8.9.0 (project)")
+            .with_stdout_contains("[..]the Node 8 line reached its end of life on 2019-12-31")
+            .with_stdout_contains("[..]affected by CVE-2018-12115, fixed in 8.12.1")
+    );
+}
+
+#[test]
+fn audit_without_versions() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("audit-toolchain"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("There are no Node versions to audit.")
+    );
+}
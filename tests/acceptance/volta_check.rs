@@ -0,0 +1,138 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const NODE_VERSION_INFO: &str = r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]},
+{"version":"v6.19.62","npm":"3.10.1066","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]}
+]
+"#;
+
+fn package_json(node: &str, engines: &str) -> String {
+    format!(
+        r#"{{
+  "name": "test-package",
+  "engines": {{
+    "node": "{}"
+  }},
+  "volta": {{
+    "node": "{}"
+  }}
+}}"#,
+        engines, node
+    )
+}
+
+#[test]
+fn check_valid_project() {
+    let s = sandbox()
+        .package_json(&package_json("10.99.1040", ">= 10"))
+        .node_available_versions(NODE_VERSION_INFO)
+        .build();
+
+    assert_that!(
+        s.volta("check"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]The project toolchain is valid.")
+    );
+}
+
+#[test]
+fn check_missing_node_version() {
+    let s = sandbox()
+        .package_json(&package_json("8.1.1", ">= 8"))
+        .node_available_versions(NODE_VERSION_INFO)
+        .build();
+
+    assert_that!(
+        s.volta("check"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stdout_contains("[..][fail][..] pinned versions")
+            .with_stdout_contains(
+                "[..]node@8.1.1 has not been fetched and could not be found upstream"
+            )
+    );
+}
+
+#[test]
+fn check_end_of_life_node() {
+    let s = sandbox()
+        .package_json(&package_json("6.19.62", ">= 6"))
+        .node_available_versions(NODE_VERSION_INFO)
+        .node_release_schedule(r#"{"v6": {"start": "2016-04-26", "end": "2019-04-30"}}"#)
+        .build();
+
+    assert_that!(
+        s.volta("check"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..][warn][..] pinned versions")
+            .with_stdout_contains("[..]node@6.19.62 is in the Node 6 line, which reached its end of life on 2019-04-30[..]")
+    );
+}
+
+#[test]
+fn check_engines_mismatch() {
+    let s = sandbox()
+        .package_json(&package_json("6.19.62", ">= 10"))
+        .node_available_versions(NODE_VERSION_INFO)
+        .build();
+
+    assert_that!(
+        s.volta("check"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stdout_contains("[..][fail][..] engines")
+            .with_stdout_contains("[..]node@6.19.62 does not satisfy `engines.node` (>= 10)")
+    );
+}
+
+#[test]
+fn check_shadowing_local_executable() {
+    let s = sandbox()
+        .package_json(&package_json("10.99.1040", ">= 10"))
+        .project_file("node_modules/.bin/node", "")
+        .node_available_versions(NODE_VERSION_INFO)
+        .build();
+
+    assert_that!(
+        s.volta("check"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stdout_contains("[..][fail][..] local executables")
+    );
+}
+
+#[test]
+fn check_outside_project_fails() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("check"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Not in a node package.")
+    );
+}
+
+#[test]
+fn check_reports_github_annotations() {
+    let s = sandbox()
+        .package_json(&package_json("6.19.62", ">= 10"))
+        .node_available_versions(NODE_VERSION_INFO)
+        .build();
+
+    assert_that!(
+        s.volta("check --format github"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stdout_contains(
+                "::error file=package.json,line=3,title=volta check%3A engines::node@6.19.62 does not satisfy `engines.node` (>= 10)"
+            )
+    );
+}
@@ -0,0 +1,52 @@
+use std::fs::read_to_string;
+
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON: &str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "8.9.0",
+    "yarn": "1.12.99"
+  }
+}"#;
+
+#[test]
+fn ci_setup_writes_github_files() {
+    let builder = sandbox().package_json(PACKAGE_JSON);
+    let output_file = builder.root().join("github-output");
+    let env_file = builder.root().join("github-env");
+    let s = builder
+        .env("GITHUB_OUTPUT", output_file.to_str().unwrap())
+        .env("GITHUB_ENV", env_file.to_str().unwrap())
+        .build();
+
+    assert_that!(
+        s.volta("ci-setup --github"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    let outputs = read_to_string(&output_file).unwrap();
+    assert!(outputs.contains("node-version=8.9.0\n"));
+    assert!(outputs.contains("yarn-version=1.12.99\n"));
+    let env = read_to_string(&env_file).unwrap();
+    assert!(env.contains("VOLTA_NODE_VERSION=8.9.0\n"));
+}
+
+#[test]
+fn ci_setup_requires_pinned_node() {
+    let s = sandbox()
+        .package_json(r#"{ "name": "test-package" }"#)
+        .build();
+
+    assert_that!(
+        s.volta("ci-setup"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]No Node version found in this project.")
+    );
+}
@@ -0,0 +1,37 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const NODE_INDEX_CACHE: &str = r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]},
+{"version":"v9.27.6","npm":"5.6.17","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]}
+]
+"#;
+
+#[test]
+fn complete_remote_uses_expired_cache() {
+    let s = sandbox().node_cache(NODE_INDEX_CACHE, true).build();
+
+    assert_that!(
+        s.volta("complete --remote -- node@1"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("node@10.99.1040")
+            .with_stdout_does_not_contain("node@9.27.6")
+    );
+}
+
+#[test]
+fn complete_without_remote_ignores_cache() {
+    let s = sandbox().node_cache(NODE_INDEX_CACHE, false).build();
+
+    assert_that!(
+        s.volta("complete -- node@"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_does_not_contain("node@10.99.1040")
+    );
+}
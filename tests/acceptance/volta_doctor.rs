@@ -0,0 +1,33 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+// The other checks depend on the machine, so only the network check is asserted
+#[test]
+fn doctor_skips_network_check_offline() {
+    let s = sandbox().env("VOLTA_OFFLINE", "1").build();
+
+    assert_that!(
+        s.volta("doctor --format json"),
+        execs()
+            .with_stdout_contains(r#"[..]"name": "network",[..]"#)
+            .with_stdout_contains(r#"[..]"status": "skip",[..]"#)
+            .with_stdout_contains("[..]offline mode is enabled[..]")
+    );
+}
+
+#[test]
+fn doctor_checks_copied_shims() {
+    let s = sandbox()
+        .shim("node")
+        .env("VOLTA_SHIM_STRATEGY", "copy")
+        .build();
+
+    assert_that!(
+        s.volta("doctor --format json"),
+        execs()
+            .with_stdout_contains(r#"[..]"name": "shim integrity",[..]"#)
+            .with_stdout_contains("[..]node[..] is not a copy of [..]volta-shim[..]")
+    );
+}
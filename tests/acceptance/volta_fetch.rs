@@ -0,0 +1,235 @@
+use std::fs;
+
+use crate::support::fixtures::{NODE_VERSION_FIXTURES, NODE_VERSION_INFO, YARN_VERSION_FIXTURES};
+use crate::support::sandbox::{sandbox, DistroMetadata, NodeFixture, PnpmFixture, YarnFixture};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON_WITH_NODE_YARN: &str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "6.19.62",
+    "yarn": "1.4.159"
+  }
+}"#;
+
+#[test]
+fn fetch_from_project() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON_WITH_NODE_YARN)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .distro_mocks::<YarnFixture>(&YARN_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch --from-project"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]fetched node@6.19.62 (with npm@3.10.1066)")
+            .with_stdout_contains("[..]fetched yarn@1.4.159")
+    );
+
+    assert!(s.node_inventory_archive_exists("6.19.62"));
+    assert!(s.yarn_inventory_archive_exists("1.4.159"));
+}
+
+#[test]
+fn fetch_from_project_outside_package() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("fetch --from-project"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Not in a node package.")
+    );
+}
+
+#[test]
+fn fetch_several_tools() {
+    let s = sandbox()
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .distro_mocks::<YarnFixture>(&YARN_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch node@6.19.62 yarn@1.4.159"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]fetched node@6.19.62 (with npm@3.10.1066)")
+            .with_stdout_contains("[..]fetched yarn@1.4.159")
+    );
+
+    assert!(s.node_inventory_archive_exists("6.19.62"));
+    assert!(s.yarn_inventory_archive_exists("1.4.159"));
+}
+
+const PACKAGE_JSON_WITH_NODE_PNPM: &str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "6.19.62",
+    "pnpm": "8.15.9"
+  }
+}"#;
+
+const PNPM_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+    version: "8.15.9",
+    compressed_size: 24,
+    uncompressed_size: None,
+}];
+
+#[test]
+fn fetch_pnpm() {
+    let s = sandbox()
+        .distro_mocks::<PnpmFixture>(&PNPM_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch pnpm@8.15.9"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]fetched pnpm@8.15.9")
+    );
+
+    assert!(s.pnpm_inventory_archive_exists("8.15.9"));
+    assert!(s.pnpm_image_exists("8.15.9"));
+}
+
+#[test]
+fn fetch_pnpm_from_project() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON_WITH_NODE_PNPM)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .distro_mocks::<PnpmFixture>(&PNPM_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch --from-project"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]fetched node@6.19.62 (with npm@3.10.1066)")
+            .with_stdout_contains("[..]fetched pnpm@8.15.9")
+    );
+
+    assert!(s.pnpm_inventory_archive_exists("8.15.9"));
+    assert!(s.pnpm_image_exists("8.15.9"));
+}
+
+#[test]
+fn fetch_pnpm_twice_reuses_the_image() {
+    let s = sandbox()
+        .distro_mocks::<PnpmFixture>(&PNPM_VERSION_FIXTURES)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.volta("fetch pnpm@8.15.9"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    assert_that!(
+        s.volta("fetch pnpm@8.15.9"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stderr_contains("[..]pnpm@8.15.9 has already been fetched, skipping download")
+    );
+}
+
+const PACKAGE_JSON_WITH_YARN: &str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "6.19.62",
+    "yarn": "1.4.159"
+  }
+}"#;
+
+const YARN_LOCKFILE: &str = r#"{ "yarn": { "version": "1.4.159", "sha256": { "yarn-v1.4.159.tar.gz": "47f1c848f62e4490d7f2aa56e3f2ddce50f3b273eef6f380073059d823a9edfb" } } }"#;
+
+#[test]
+fn fetch_from_shared_cache() {
+    let builder = sandbox();
+    let shared_dir = builder.root().join("shared");
+    let s = builder
+        .package_json(PACKAGE_JSON_WITH_YARN)
+        .project_file("volta.lock", YARN_LOCKFILE)
+        .env("VOLTA_CACHE_SHARED_DIR", &shared_dir.to_string_lossy())
+        .mock_not_found()
+        .build();
+
+    // The shared cache has the layout of the inventory, and is used without any download once
+    // the archive matches the lockfile
+    fs::create_dir_all(shared_dir.join("yarn")).unwrap();
+    fs::copy(
+        "tests/fixtures/yarn-v1.4.159.tar.gz",
+        shared_dir.join("yarn").join("yarn-v1.4.159.tar.gz"),
+    )
+    .unwrap();
+
+    assert_that!(
+        s.volta("fetch yarn@1.4.159"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]fetched yarn@1.4.159")
+    );
+
+    assert!(s.yarn_inventory_archive_exists("1.4.159"));
+    assert!(shared_dir
+        .join("yarn")
+        .join("yarn-v1.4.159.tar.gz")
+        .exists());
+}
+
+#[test]
+fn fetch_ignores_unverified_shared_archive() {
+    let builder = sandbox();
+    let shared_dir = builder.root().join("shared");
+    let s = builder
+        .package_json(PACKAGE_JSON_WITH_YARN)
+        .project_file("volta.lock", YARN_LOCKFILE)
+        .env("VOLTA_CACHE_SHARED_DIR", &shared_dir.to_string_lossy())
+        .mock_not_found()
+        .build();
+
+    // An archive that doesn't match the lockfile is downloaded instead, which fails here
+    fs::create_dir_all(shared_dir.join("yarn")).unwrap();
+    fs::write(
+        shared_dir.join("yarn").join("yarn-v1.4.159.tar.gz"),
+        "not the real yarn",
+    )
+    .unwrap();
+
+    assert_that!(
+        s.volta("fetch yarn@1.4.159"),
+        execs()
+            .with_status(ExitCode::NetworkError as i32)
+            .with_stderr_contains("[..]does not match its checksum, downloading it instead")
+    );
+
+    assert!(!s.yarn_inventory_archive_exists("1.4.159"));
+}
+
+#[test]
+#[cfg(unix)]
+fn fetch_node_recompressed_with_zstd() {
+    let s = sandbox()
+        .env("VOLTA_ARCHIVE_COMPRESSION", "zstd")
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch node@6.19.62"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]fetched node@6.19.62 (with npm@3.10.1066)")
+    );
+
+    // The downloaded tarball is replaced with the recompressed one
+    assert!(!s.node_inventory_archive_exists("6.19.62"));
+    assert!(s.node_inventory_zstd_archive_exists("6.19.62"));
+}
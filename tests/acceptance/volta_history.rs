@@ -0,0 +1,78 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PKG_CONFIG: &str = r#"{
+  "name": "cowsay",
+  "version": "1.4.0",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  },
+  "bins": [
+    "cowsay"
+  ]
+}"#;
+
+const BIN_CONFIG: &str = r#"{
+  "name": "cowsay",
+  "package": "cowsay",
+  "version": "1.4.0",
+  "path": "./cli.js",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  }
+}"#;
+
+#[test]
+fn history_is_empty_without_changes() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("history"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("No changes to the toolchain have been recorded.")
+    );
+}
+
+#[test]
+fn history_records_uninstall() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG)
+        .binary_config("cowsay", BIN_CONFIG)
+        .shim("cowsay")
+        .package_image("cowsay", "1.4.0")
+        // The user that invoked `sudo` takes precedence, so it is the most reliable to set
+        .env("SUDO_USER", "alice")
+        .build();
+
+    assert_that!(s.volta("uninstall cowsay --yes"), execs().with_status(0));
+
+    assert_that!(
+        s.volta("history --format json"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains(r#"[..]"user": "alice",[..]"#)
+            .with_stdout_contains(r#"[..]"action": "uninstall",[..]"#)
+            .with_stdout_contains(r#"[..]"tool": "cowsay",[..]"#)
+            .with_stdout_contains(r#"[..]"previous": "1.4.0"[..]"#)
+    );
+
+    assert_that!(
+        s.volta("history --tool node"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("No changes to the toolchain have been recorded.")
+    );
+}
@@ -0,0 +1,47 @@
+use crate::support::fixtures::{NODE_VERSION_FIXTURES, NODE_VERSION_INFO};
+use crate::support::sandbox::{sandbox, NodeFixture};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+const PACKAGE_JSON_WITH_NODE: &str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "6.19.62"
+  }
+}"#;
+
+#[test]
+fn install_package_passes_registry_to_npm() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON_WITH_NODE)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    // The fixture Node has no working npm, so only the command that Volta runs is checked
+    assert_that!(
+        s.volta("install cowsay --registry https://npm.example.com/ --legacy-peer-deps"),
+        execs().with_stderr_contains(
+            "[..]\"view\" \"--json\" \"cowsay@latest\" \"--registry=https://npm.example.com/\"[..]"
+        )
+    );
+}
+
+#[test]
+fn install_package_without_flags_runs_plain_npm_view() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON_WITH_NODE)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.volta("install cowsay"),
+        execs()
+            .with_stderr_contains("[..]\"view\" \"--json\" \"cowsay@latest\"[..]")
+            .with_stderr_does_not_contain("[..]--registry[..]")
+    );
+}
@@ -0,0 +1,107 @@
+use crate::support::sandbox::{sandbox, DistroMetadata, NodeFixture, YarnFixture};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const NODE_VERSION_INFO: &str = r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]}
+]
+"#;
+
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "windows")] {
+        const NODE_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+            version: "10.99.1040",
+            compressed_size: 1096,
+            uncompressed_size: None,
+        }];
+    } else {
+        const NODE_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+            version: "10.99.1040",
+            compressed_size: 273,
+            uncompressed_size: Some(0x0028_0000),
+        }];
+    }
+}
+
+const YARN_VERSION_INFO: &str = r#"[
+{"tag_name":"v1.2.42","assets":[{"name":"yarn-v1.2.42.tar.gz"}]}
+]"#;
+
+const YARN_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+    version: "1.2.42",
+    compressed_size: 174,
+    uncompressed_size: Some(0x0028_0000),
+}];
+
+const PACKAGE_JSON: &str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "10.99.1040",
+    "yarn": "1.2.42"
+  }
+}"#;
+
+#[test]
+fn lock_records_pinned_versions() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .yarn_available_versions(YARN_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .distro_mocks::<YarnFixture>(&YARN_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("lock"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]locked the project toolchain in[..]volta.lock")
+    );
+
+    let lockfile = std::fs::read_to_string(s.root().join("volta.lock")).unwrap();
+    assert!(lockfile.contains(r#""version": "10.99.1040""#));
+    assert!(lockfile.contains("yarn-v1.2.42.tar.gz"));
+}
+
+#[test]
+fn fetch_rejects_archive_with_different_checksum() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .project_file(
+            "volta.lock",
+            r#"{ "yarn": { "version": "1.2.42", "sha256": { "yarn-v1.2.42.tar.gz": "0000000000000000000000000000000000000000000000000000000000000000" } } }"#,
+        )
+        .yarn_available_versions(YARN_VERSION_INFO)
+        .distro_mocks::<YarnFixture>(&YARN_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch yarn@1.2.42"),
+        execs()
+            .with_status(ExitCode::FileSystemError as i32)
+            .with_stderr_contains("[..]does not match the checksum recorded in volta.lock")
+    );
+}
+
+#[test]
+fn run_rejects_version_that_differs_from_lock() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .project_file(
+            "volta.lock",
+            r#"{ "node": { "version": "10.99.1041", "sha256": {} } }"#,
+        )
+        .build();
+
+    assert_that!(
+        s.npm("--version"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains(
+                "[..]This project pins node v10.99.1040, but its volta.lock records v10.99.1041."
+            )
+    );
+}
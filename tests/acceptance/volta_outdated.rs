@@ -0,0 +1,65 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON: &str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "8.9.0"
+  }
+}"#;
+
+const NODE_VERSION_INFO: &str = r#"[
+{"version":"v10.99.1040","npm":"6.2.26","lts": "Dubnium","files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]},
+{"version":"v8.12.1","npm":"6.4.1","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]},
+{"version":"v8.9.10","npm":"5.6.7","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]},
+{"version":"v8.9.0","npm":"5.5.1","lts": false,"files":["linux-x64","osx-x64-tar","win-x64-zip","win-x86-zip", "linux-arm64"]}
+]
+"#;
+
+#[test]
+fn outdated_reports_newer_releases() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .build();
+
+    assert_that!(
+        s.volta("outdated"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("node  8.9.0   8.9.10  8.12.1  10.99.1040")
+            .with_stdout_contains("1 pinned version has newer releases.")
+    );
+}
+
+#[test]
+fn outdated_reports_json() {
+    let s = sandbox()
+        .package_json(PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .build();
+
+    assert_that!(
+        s.volta("outdated --format json"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains(r#"    "latestPatch": "8.9.10","#)
+            .with_stdout_contains(r#"    "latestMinor": "8.12.1","#)
+    );
+}
+
+#[test]
+fn outdated_outside_project_fails() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("outdated"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Not in a node package.")
+    );
+}
@@ -1,4 +1,6 @@
-use crate::support::sandbox::{sandbox, DistroMetadata, NodeFixture, YarnFixture};
+use crate::support::sandbox::{
+    sandbox, DistroMetadata, NodeFixture, NpmFixture, PnpmFixture, YarnFixture,
+};
 use hamcrest2::assert_that;
 use hamcrest2::prelude::*;
 use test_support::matchers::execs;
@@ -197,6 +199,12 @@ const NPM_VERSION_INFO: &str = r#"
 }
 "#;
 
+const NPM_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+    version: "5.10.12",
+    compressed_size: 244,
+    uncompressed_size: Some(0x0028_0000),
+}];
+
 const VOLTA_LOGLEVEL: &str = "VOLTA_LOGLEVEL";
 
 #[test]
@@ -332,6 +340,66 @@ fn pin_yarn() {
     )
 }
 
+const PNPM_VERSION_INFO: &str = r#"{
+  "versions": { "8.15.9": {}, "9.1.0": {} },
+  "dist-tags": { "latest": "9.1.0" }
+}"#;
+
+const PNPM_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+    version: "8.15.9",
+    compressed_size: 24,
+    uncompressed_size: None,
+}];
+
+fn package_json_with_pinned_node_pnpm(node_version: &str, pnpm_version: &str) -> String {
+    format!(
+        r#"{{
+  "name": "test-package",
+  "volta": {{
+    "node": "{}",
+    "pnpm": "{}"
+  }}
+}}"#,
+        node_version, pnpm_version
+    )
+}
+
+#[test]
+fn pin_pnpm() {
+    let s = sandbox()
+        .package_json(&package_json_with_pinned_node("1.2.3"))
+        .pnpm_available_versions(PNPM_VERSION_INFO)
+        .distro_mocks::<PnpmFixture>(&PNPM_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("pin pnpm@8"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert_eq!(
+        s.read_package_json(),
+        package_json_with_pinned_node_pnpm("1.2.3", "8.15.9"),
+    );
+    assert!(s.pnpm_image_exists("8.15.9"));
+}
+
+#[test]
+fn pin_pnpm_without_node() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .pnpm_available_versions(PNPM_VERSION_INFO)
+        .distro_mocks::<PnpmFixture>(&PNPM_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("pin pnpm@8"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Cannot pin pnpm because the Node version is not pinned[..]")
+    );
+}
+
 #[test]
 fn pin_yarn_reports_info() {
     let s = sandbox()
@@ -427,12 +495,11 @@ fn pin_yarn_leaves_npm() {
 }
 
 #[test]
-#[ignore]
 fn pin_npm() {
-    // ISSUE(#292): Get this test working after pinning npm is correct
     let s = sandbox()
         .package_json(&package_json_with_pinned_node("1.2.3"))
         .npm_available_versions(NPM_VERSION_INFO)
+        .distro_mocks::<NpmFixture>(&NPM_VERSION_FIXTURES)
         .build();
 
     assert_that!(
@@ -482,3 +549,156 @@ fn pin_node_does_not_remove_bottom_empty_line() {
 
     assert!(s.read_package_json().ends_with('\n'))
 }
+
+#[test]
+fn pin_node_preserves_crlf_line_endings() {
+    let s = sandbox()
+        .package_json("{\r\n  \"name\": \"test-package\"\r\n}\r\n")
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("pin node@6"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert_eq!(
+        s.read_package_json(),
+        package_json_with_pinned_node("6.19.62").replace('\n', "\r\n") + "\r\n",
+    )
+}
+
+#[test]
+fn pin_node_and_missing_yarn_leaves_package_unchanged() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .yarn_available_versions(YARN_VERSION_INFO)
+        .build();
+
+    assert_that!(
+        s.volta("pin node@6 yarn@10"),
+        execs().with_status(ExitCode::NoVersionMatch as i32)
+    );
+
+    assert_eq!(s.read_package_json(), BASIC_PACKAGE_JSON)
+}
+
+const WORKSPACE_PACKAGE_JSON: &str = r#"{
+  "name": "test-workspace",
+  "workspaces": [
+    "packages/*"
+  ]
+}"#;
+
+const WORKSPACE_MEMBER_PACKAGE_JSON: &str = r#"{
+  "name": "member"
+}"#;
+
+#[test]
+fn pin_node_in_all_workspace_packages() {
+    let s = sandbox()
+        .package_json(WORKSPACE_PACKAGE_JSON)
+        .project_file("packages/a/package.json", WORKSPACE_MEMBER_PACKAGE_JSON)
+        .project_file("packages/b/package.json", WORKSPACE_MEMBER_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .env(VOLTA_LOGLEVEL, "info")
+        .build();
+
+    assert_that!(
+        s.volta("pin --all node@6"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]updated 2 of 2 workspace packages")
+    );
+
+    let member = std::fs::read_to_string(s.root().join("packages/a/package.json")).unwrap();
+    assert_eq!(
+        member,
+        r#"{
+  "name": "member",
+  "volta": {
+    "node": "6.19.62"
+  }
+}"#
+    );
+}
+
+#[test]
+fn pin_node_in_workspace_packages_with_extends() {
+    let s = sandbox()
+        .package_json(WORKSPACE_PACKAGE_JSON)
+        .project_file("packages/a/package.json", WORKSPACE_MEMBER_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("pin --all --extends node@6"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    let member = std::fs::read_to_string(s.root().join("packages/a/package.json")).unwrap();
+    assert_eq!(
+        member,
+        r#"{
+  "name": "member",
+  "volta": {
+    "extends": "../../package.json"
+  }
+}"#
+    );
+}
+
+#[test]
+fn pin_all_outside_workspace_fails() {
+    let s = sandbox().package_json(BASIC_PACKAGE_JSON).build();
+
+    assert_that!(
+        s.volta("pin --all node@6"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]Not in a workspace.")
+    );
+}
+
+#[test]
+fn pin_node_latest_patch_of_pinned_line() {
+    let s = sandbox()
+        .package_json(&package_json_with_pinned_node("10.99.0"))
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("pin node --latest-patch"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert_eq!(
+        s.read_package_json(),
+        package_json_with_pinned_node("10.99.1040"),
+    )
+}
+
+#[test]
+fn pin_node_latest_minor_of_requested_line() {
+    let s = sandbox()
+        .package_json(BASIC_PACKAGE_JSON)
+        .node_available_versions(NODE_VERSION_INFO)
+        .distro_mocks::<NodeFixture>(&NODE_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("pin node@10.1.0 --latest-minor"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    assert_eq!(
+        s.read_package_json(),
+        package_json_with_pinned_node("10.99.1040"),
+    )
+}
@@ -0,0 +1,62 @@
+use crate::support::sandbox::{sandbox, Sandbox};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON: &str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "8.9.0"
+  }
+}"#;
+
+const PLATFORM: &str = r#"{
+    "node":{
+        "runtime":"9.11.2",
+        "npm":"5.6.0"
+    }
+}"#;
+
+#[test]
+fn prompt_prints_project_node() {
+    let s = sandbox()
+        .platform(PLATFORM)
+        .package_json(PACKAGE_JSON)
+        .build();
+
+    assert_that!(
+        s.volta("prompt --format {version}:{source}"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("8.9.0:project")
+    );
+}
+
+#[test]
+fn prompt_project_only_ignores_default() {
+    let s = sandbox().platform(PLATFORM).build();
+
+    assert_that!(
+        s.volta("prompt --project-only"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_does_not_contain("9.11.2")
+    );
+}
+
+#[test]
+fn prompt_skips_layout_setup() {
+    let s = sandbox().build();
+    s.remove_volta_home();
+
+    // Other commands create the Volta home first, but a prompt never does
+    assert_that!(
+        s.volta("prompt"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout("")
+    );
+    assert!(!Sandbox::path_exists(".volta"));
+}
@@ -0,0 +1,99 @@
+use crate::support::sandbox::{sandbox, Sandbox};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+use test_support::paths;
+
+use volta_fail::ExitCode;
+
+const PKG_CONFIG: &str = r#"{
+  "name": "cowsay",
+  "version": "1.4.0",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  },
+  "bins": [
+    "cowsay"
+  ]
+}"#;
+
+#[test]
+fn relocate_moves_home() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG)
+        .package_image("cowsay", "1.4.0")
+        .env("VOLTA_LOGLEVEL", "info")
+        .build();
+
+    assert_that!(
+        s.volta("relocate moved-volta --no-modify-profile"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("[..]Moved the Volta home to[..]moved-volta[..]")
+    );
+
+    let moved = s.root().join("moved-volta");
+    assert!(moved
+        .join("tools")
+        .join("user")
+        .join("packages")
+        .join("cowsay.json")
+        .exists());
+    assert!(moved
+        .join("tools")
+        .join("image")
+        .join("packages")
+        .join("cowsay")
+        .join("1.4.0")
+        .join("package.json")
+        .exists());
+    assert!(!Sandbox::package_config_exists("cowsay"));
+    assert!(!Sandbox::path_exists(".volta"));
+}
+
+#[test]
+#[cfg(unix)]
+fn relocate_relinks_npx_dir() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG)
+        .env("npm_config_cache", "npm-cache")
+        .build();
+    let npm_cache = s.root().join("npm-cache");
+    std::fs::create_dir_all(&npm_cache).unwrap();
+    std::os::unix::fs::symlink(
+        paths::home().join(".volta").join("cache").join("npx"),
+        npm_cache.join("_npx"),
+    )
+    .unwrap();
+
+    assert_that!(
+        s.volta("relocate moved-volta --no-modify-profile"),
+        execs().with_status(0)
+    );
+
+    assert_eq!(
+        std::fs::read_link(npm_cache.join("_npx")).unwrap(),
+        s.root().join("moved-volta").join("cache").join("npx")
+    );
+}
+
+#[test]
+fn relocate_rejects_occupied_directory() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG)
+        .project_file("occupied/notes.txt", "not empty")
+        .build();
+
+    assert_that!(
+        s.volta("relocate occupied --no-modify-profile"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains("[..]since it already exists and isn't empty.")
+    );
+
+    assert!(Sandbox::package_config_exists("cowsay"));
+}
@@ -0,0 +1,93 @@
+use std::fs;
+
+use crate::support::sandbox::{sandbox, volta_install_dir};
+use cfg_if::cfg_if;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+cfg_if! {
+    if #[cfg(windows)] {
+        const RELEASE_CHECKSUM: &str = "5b9060e9b7f525bbe896a19999a7b40672a67a66";
+        const VOLTA_EXECUTABLE: &str = "volta.exe";
+    } else {
+        const RELEASE_CHECKSUM: &str = "f4858cf5ceae7505a63ffacda5d1b7fdce282261";
+        const VOLTA_EXECUTABLE: &str = "volta";
+    }
+}
+
+const WRONG_CHECKSUM: &str = "0000000000000000000000000000000000000000";
+
+fn install_dir() -> String {
+    volta_install_dir().to_string_lossy().to_string()
+}
+
+#[test]
+fn self_update_installs_release() {
+    let s = sandbox()
+        .env("VOLTA_INSTALL_DIR", &install_dir())
+        .volta_release("99.0.0", RELEASE_CHECKSUM)
+        .build();
+
+    assert_that!(
+        s.volta("self update"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]updated Volta from v[..] to v99.0.0")
+    );
+    assert_eq!(
+        fs::read_to_string(volta_install_dir().join(VOLTA_EXECUTABLE)).unwrap(),
+        "volta 99.0.0\n"
+    );
+}
+
+#[test]
+fn self_update_check_does_not_install() {
+    let s = sandbox()
+        .env("VOLTA_INSTALL_DIR", &install_dir())
+        .volta_release("99.0.0", RELEASE_CHECKSUM)
+        .build();
+
+    assert_that!(
+        s.volta("self update --check"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("Volta v99.0.0 is available[..]")
+    );
+    assert!(!volta_install_dir().join(VOLTA_EXECUTABLE).exists());
+}
+
+#[test]
+fn self_update_rejects_wrong_checksum() {
+    let s = sandbox()
+        .env("VOLTA_INSTALL_DIR", &install_dir())
+        .volta_release("99.0.0", WRONG_CHECKSUM)
+        .build();
+
+    assert_that!(
+        s.volta("self update"),
+        execs()
+            .with_status(ExitCode::NetworkError as i32)
+            .with_stderr_contains(
+                "[..]The download of Volta 99.0.0 does not match its published checksum."
+            )
+    );
+    assert!(!volta_install_dir().join(VOLTA_EXECUTABLE).exists());
+}
+
+#[test]
+fn self_update_reports_failed_download() {
+    let s = sandbox()
+        .env("VOLTA_INSTALL_DIR", &install_dir())
+        .mock_not_found()
+        .build();
+
+    assert_that!(
+        s.volta("self update"),
+        execs()
+            .with_status(ExitCode::NetworkError as i32)
+            .with_stderr_contains("[..]Could not determine the latest Volta release")
+    );
+}
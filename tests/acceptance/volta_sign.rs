@@ -0,0 +1,93 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PLATFORM: &str = r#"{"node":{"runtime":"10.99.1040","npm":null},"yarn":null}"#;
+const CHANGED_PLATFORM: &str = r#"{"node":{"runtime":"6.19.62","npm":null},"yarn":null}"#;
+
+#[test]
+fn sign_detects_changed_platform() {
+    let s = sandbox()
+        .platform(PLATFORM)
+        .env("VOLTA_LOGLEVEL", "info")
+        .build();
+
+    assert_that!(
+        s.volta("sign"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]Signed the default platform and settings files")
+    );
+    assert!(s.platform_signature_exists());
+    assert_that!(
+        s.volta("list"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+
+    s.write_platform(CHANGED_PLATFORM);
+    assert_that!(
+        s.volta("list"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]does not match its contents.")
+    );
+
+    assert_that!(
+        s.volta("sign"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    assert_that!(
+        s.volta("list"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+}
+
+#[test]
+fn sign_detects_removed_key() {
+    let s = sandbox().platform(PLATFORM).build();
+
+    assert_that!(
+        s.volta("sign"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    s.remove_integrity_key();
+    assert_that!(
+        s.volta("list"),
+        execs()
+            .with_status(ExitCode::ConfigurationError as i32)
+            .with_stderr_contains("[..]is missing or corrupt, but signing is turned on.")
+    );
+
+    assert_that!(
+        s.volta("sign"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    assert_that!(
+        s.volta("list"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+}
+
+#[test]
+fn sign_off_removes_signatures() {
+    let s = sandbox().platform(PLATFORM).build();
+
+    assert_that!(
+        s.volta("sign"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    assert_that!(
+        s.volta("sign --off"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    assert!(!s.platform_signature_exists());
+
+    s.write_platform(CHANGED_PLATFORM);
+    assert_that!(
+        s.volta("list"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+}
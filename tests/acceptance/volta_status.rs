@@ -0,0 +1,44 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PACKAGE_JSON: &str = r#"{
+  "name": "test-package",
+  "volta": {
+    "node": "8.9.0",
+    "npm": "5.5.1"
+  }
+}"#;
+
+const UNPINNED_PACKAGE_JSON: &str = r#"{
+  "name": "test-package"
+}"#;
+
+#[test]
+fn status_reports_pinned_versions_that_are_not_installed() {
+    let s = sandbox().package_json(PACKAGE_JSON).build();
+
+    assert_that!(
+        s.volta("status --porcelain"),
+        execs()
+            .with_status(ExitCode::StatusNotInstalled as i32)
+            .with_stdout_contains("status not-installed")
+            .with_stdout_contains("node 8.9.0 project not-installed")
+            .with_stdout_contains("npm 5.5.1 project not-installed")
+    );
+}
+
+#[test]
+fn status_reports_unpinned_project() {
+    let s = sandbox().package_json(UNPINNED_PACKAGE_JSON).build();
+
+    assert_that!(
+        s.volta("status --porcelain"),
+        execs()
+            .with_status(ExitCode::StatusUnpinned as i32)
+            .with_stdout_contains("status unpinned")
+    );
+}
@@ -3,6 +3,8 @@ use hamcrest2::assert_that;
 use hamcrest2::prelude::*;
 use test_support::matchers::execs;
 
+use volta_fail::ExitCode;
+
 const PKG_CONFIG_BASIC: &str = r#"{
   "name": "cowsay",
   "version": "1.4.0",
@@ -59,7 +61,7 @@ fn uninstall_nonexistent_pkg() {
     let s = sandbox().env(VOLTA_LOGLEVEL, "info").build();
 
     assert_that!(
-        s.volta("uninstall cowsay"),
+        s.volta("uninstall cowsay --yes"),
         execs()
             .with_status(0)
             .with_stderr_contains("[..]No package 'cowsay' found to uninstall")
@@ -82,7 +84,7 @@ fn uninstall_package_basic() {
         .build();
 
     assert_that!(
-        s.volta("uninstall cowsay"),
+        s.volta("uninstall cowsay --yes"),
         execs()
             .with_status(0)
             .with_stdout_contains("Removed executable 'cowsay' installed by 'cowsay'")
@@ -102,6 +104,32 @@ fn uninstall_package_basic() {
     assert!(Sandbox::pkg_inventory_shasum_exists("cowsay", "1.4.0"));
 }
 
+#[test]
+fn uninstall_requires_confirmation() {
+    // the tests aren't run in a terminal, so there is no one to ask and `--yes` is required
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_BASIC)
+        .binary_config("cowsay", &bin_config("cowsay"))
+        .binary_config("cowthink", &bin_config("cowthink"))
+        .shim("cowsay")
+        .shim("cowthink")
+        .package_image("cowsay", "1.4.0")
+        .env(VOLTA_LOGLEVEL, "info")
+        .build();
+
+    assert_that!(
+        s.volta("uninstall cowsay"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains("[..]Could not uninstall cowsay without confirmation[..]")
+    );
+
+    // check that nothing is deleted
+    assert!(Sandbox::package_config_exists("cowsay"));
+    assert!(Sandbox::shim_exists("cowsay"));
+    assert!(Sandbox::package_image_exists("cowsay", "1.4.0"));
+}
+
 #[test]
 fn uninstall_package_no_bins() {
     // the package doesn't contain any executables, it should uninstall without error
@@ -114,7 +142,7 @@ fn uninstall_package_no_bins() {
         .build();
 
     assert_that!(
-        s.volta("uninstall cowsay"),
+        s.volta("uninstall cowsay --yes"),
         execs()
             .with_status(0)
             .with_stdout_contains("[..]package 'cowsay' uninstalled")
@@ -147,7 +175,7 @@ fn uninstall_package_no_image() {
         .build();
 
     assert_that!(
-        s.volta("uninstall cowsay"),
+        s.volta("uninstall cowsay --yes"),
         execs()
             .with_status(0)
             .with_stdout_contains("Removed executable 'cowsay' installed by 'cowsay'")
@@ -180,7 +208,7 @@ fn uninstall_package_orphaned_bins() {
         .build();
 
     assert_that!(
-        s.volta("uninstall cowsay"),
+        s.volta("uninstall cowsay --yes"),
         execs()
             .with_status(0)
             .with_stdout_contains("Removed executable 'cowsay' installed by 'cowsay'")
@@ -194,3 +222,32 @@ fn uninstall_package_orphaned_bins() {
     assert!(!Sandbox::shim_exists("cowsay"));
     assert!(!Sandbox::shim_exists("cowthink"));
 }
+
+#[test]
+fn uninstall_all_packages() {
+    // every package in the toolchain should be removed
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG_BASIC)
+        .binary_config("cowsay", &bin_config("cowsay"))
+        .binary_config("cowthink", &bin_config("cowthink"))
+        .shim("cowsay")
+        .shim("cowthink")
+        .package_image("cowsay", "1.4.0")
+        .package_inventory("cowsay", "1.4.0")
+        .env(VOLTA_LOGLEVEL, "info")
+        .build();
+
+    assert_that!(
+        s.volta("uninstall --all --yes"),
+        execs()
+            .with_status(0)
+            .with_stdout_contains("[..]package 'cowsay' uninstalled")
+    );
+
+    assert!(!Sandbox::package_config_exists("cowsay"));
+    assert!(!Sandbox::bin_config_exists("cowsay"));
+    assert!(!Sandbox::bin_config_exists("cowthink"));
+    assert!(!Sandbox::shim_exists("cowsay"));
+    assert!(!Sandbox::shim_exists("cowthink"));
+    assert!(!Sandbox::package_image_exists("cowsay", "1.4.0"));
+}
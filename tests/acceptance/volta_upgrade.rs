@@ -0,0 +1,135 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PKG_CONFIG: &str = r#"{
+  "name": "cowsay",
+  "version": "1.4.0",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  },
+  "bins": [
+    "cowsay"
+  ]
+}"#;
+
+const BIN_CONFIG: &str = r#"{
+  "name": "cowsay",
+  "package": "cowsay",
+  "version": "1.4.0",
+  "path": "./cli.js",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  }
+}"#;
+
+/// Hooks that resolve the latest version of a package from the mock registry
+fn package_hooks() -> String {
+    format!(
+        r#"{{
+  "packages": {{
+    "latest": {{
+      "prefix": "{}/registry/"
+    }}
+  }}
+}}"#,
+        mockito::SERVER_URL
+    )
+}
+
+/// The registry document of cowsay, with `latest` as its latest version
+fn cowsay_metadata(latest: &str) -> String {
+    format!(
+        r#"{{
+  "name": "cowsay",
+  "dist-tags": {{ "latest": "{latest}" }},
+  "versions": {{
+    "1.4.0": {{
+      "version": "1.4.0",
+      "dist": {{
+        "shasum": "0000000000000000000000000000000000000000",
+        "tarball": "{url}/registry/cowsay/-/cowsay-1.4.0.tgz"
+      }}
+    }},
+    "{latest}": {{
+      "version": "{latest}",
+      "dist": {{
+        "shasum": "0000000000000000000000000000000000000000",
+        "tarball": "{url}/registry/cowsay/-/cowsay-{latest}.tgz"
+      }}
+    }}
+  }}
+}}"#,
+        latest = latest,
+        url = mockito::SERVER_URL
+    )
+}
+
+#[test]
+fn upgrade_package_not_installed() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("upgrade cowsay"),
+        execs()
+            .with_status(ExitCode::InvalidArguments as i32)
+            .with_stderr_contains("[..]Package 'cowsay' is not installed.")
+    );
+}
+
+#[test]
+fn upgrade_package_already_up_to_date() {
+    let s = sandbox()
+        .default_hooks(&package_hooks())
+        .custom_tool_index("/registry/cowsay", &cowsay_metadata("1.4.0"))
+        .package_config("cowsay", PKG_CONFIG)
+        .binary_config("cowsay", BIN_CONFIG)
+        .shim("cowsay")
+        .package_image("cowsay", "1.4.0")
+        .env("VOLTA_LOGLEVEL", "info")
+        .build();
+
+    assert_that!(
+        s.volta("upgrade cowsay"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]Package cowsay@1.4.0 is already up to date")
+    );
+    assert!(s
+        .read_package_config("cowsay")
+        .contains(r#""version": "1.4.0""#));
+}
+
+#[test]
+fn upgrade_keeps_installed_version_when_download_fails() {
+    let s = sandbox()
+        .default_hooks(&package_hooks())
+        .custom_tool_index("/registry/cowsay", &cowsay_metadata("1.5.0"))
+        .package_config("cowsay", PKG_CONFIG)
+        .binary_config("cowsay", BIN_CONFIG)
+        .shim("cowsay")
+        .package_image("cowsay", "1.4.0")
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    // The tarball of 1.5.0 can't be fetched, so the upgrade fails after resolving it, with an
+    // error that depends on the npm available on the machine
+    assert_that!(
+        s.volta("upgrade cowsay"),
+        execs().with_stderr_contains("[..]Found cowsay latest version (1.5.0)[..]")
+    );
+    assert!(s
+        .read_package_config("cowsay")
+        .contains(r#""version": "1.4.0""#));
+}
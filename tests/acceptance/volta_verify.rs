@@ -0,0 +1,100 @@
+use std::fs;
+
+use crate::support::sandbox::{sandbox, DistroMetadata, PnpmFixture};
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+use test_support::paths;
+
+use volta_fail::ExitCode;
+
+const PNPM_VERSION_FIXTURES: [DistroMetadata; 1] = [DistroMetadata {
+    version: "8.15.9",
+    compressed_size: 24,
+    uncompressed_size: None,
+}];
+
+fn pnpm_bin_dir(version: &str) -> std::path::PathBuf {
+    paths::home()
+        .join(".volta")
+        .join("tools")
+        .join("image")
+        .join("pnpm")
+        .join(version)
+        .join("bin")
+}
+
+#[test]
+fn verify_reports_damaged_image() {
+    let s = sandbox()
+        .distro_mocks::<PnpmFixture>(&PNPM_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch pnpm@8.15.9"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    assert_that!(
+        s.volta("verify"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("All versions are intact.")
+    );
+
+    fs::remove_dir_all(pnpm_bin_dir("8.15.9")).unwrap();
+    assert_that!(
+        s.volta("verify"),
+        execs()
+            .with_status(ExitCode::FileSystemError as i32)
+            .with_stdout_contains("[..]pnpm@8.15.9")
+            .with_stdout_contains("    the image has no `bin`")
+    );
+}
+
+#[test]
+fn verify_repair_fetches_damaged_version_again() {
+    let s = sandbox()
+        .distro_mocks::<PnpmFixture>(&PNPM_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch pnpm@8.15.9"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    fs::remove_dir_all(pnpm_bin_dir("8.15.9")).unwrap();
+
+    assert_that!(
+        s.volta("verify --repair"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]repaired pnpm@8.15.9")
+            .with_stdout_contains("All versions are intact.")
+    );
+    assert!(s.pnpm_image_exists("8.15.9"));
+    assert!(s.pnpm_inventory_archive_exists("8.15.9"));
+}
+
+#[test]
+fn verify_repair_keeps_version_that_cannot_be_fetched() {
+    let s = sandbox()
+        .distro_mocks::<PnpmFixture>(&PNPM_VERSION_FIXTURES)
+        .build();
+
+    assert_that!(
+        s.volta("fetch pnpm@8.15.9"),
+        execs().with_status(ExitCode::Success as i32)
+    );
+    fs::remove_dir_all(pnpm_bin_dir("8.15.9")).unwrap();
+
+    // Offline, the archive that was moved aside can't be downloaded again, so it is put back
+    let mut repair = s.volta("verify --repair");
+    repair.env("VOLTA_OFFLINE", "1");
+    assert_that!(
+        &mut repair,
+        execs()
+            .with_status(ExitCode::FileSystemError as i32)
+            .with_stderr_contains("[..]Could not repair pnpm@8.15.9[..]")
+            .with_stdout_contains("    the image has no `bin`")
+    );
+    assert!(s.pnpm_inventory_archive_exists("8.15.9"));
+}
@@ -0,0 +1,66 @@
+use crate::support::sandbox::sandbox;
+use hamcrest2::assert_that;
+use hamcrest2::prelude::*;
+use test_support::matchers::execs;
+
+use volta_fail::ExitCode;
+
+const PKG_CONFIG: &str = r#"{
+  "name": "cowsay",
+  "version": "1.4.0",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  },
+  "bins": [
+    "cowsay"
+  ]
+}"#;
+
+const BIN_CONFIG: &str = r#"{
+  "name": "cowsay",
+  "package": "cowsay",
+  "version": "1.4.0",
+  "path": "./cli.js",
+  "platform": {
+    "node": {
+      "runtime": "11.10.1",
+      "npm": "6.7.0"
+    },
+    "yarn": null
+  }
+}"#;
+
+#[test]
+fn which_finds_default_package_binary() {
+    let s = sandbox()
+        .package_config("cowsay", PKG_CONFIG)
+        .binary_config("cowsay", BIN_CONFIG)
+        .shim("cowsay")
+        .package_platform_image("cowsay", "1.4.0", "11.10.1", &["cli.js"])
+        .env("VOLTA_LOGLEVEL", "debug")
+        .build();
+
+    assert_that!(
+        s.volta("which cowsay"),
+        execs()
+            .with_status(ExitCode::Success as i32)
+            .with_stdout_contains("[..]cowsay[..]1.4.0[..]cli.js")
+            .with_stderr_contains("[..]Resolved cowsay using the default package")
+    );
+}
+
+#[test]
+fn which_fails_quietly_for_unknown_binary() {
+    let s = sandbox().build();
+
+    assert_that!(
+        s.volta("which volta-test-no-such-binary"),
+        execs()
+            .with_status(ExitCode::UnknownError as i32)
+            .with_stdout("")
+    );
+}